@@ -0,0 +1,217 @@
+/* Adamant terminal core. Generated by cbindgen from src/ffi.rs; do not edit. */
+
+#ifndef ADAMANT_H
+#define ADAMANT_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define ADAMANT_FLAG_BOLD (1 << 0)
+
+#define ADAMANT_FLAG_DIM (1 << 1)
+
+#define ADAMANT_FLAG_ITALIC (1 << 2)
+
+#define ADAMANT_FLAG_UNDERLINE (1 << 3)
+
+#define ADAMANT_FLAG_BLINK (1 << 4)
+
+#define ADAMANT_FLAG_INVERSE (1 << 5)
+
+#define ADAMANT_FLAG_HIDDEN (1 << 6)
+
+#define ADAMANT_FLAG_STRIKEOUT (1 << 7)
+
+/**
+ * The cell holds a double-width character.
+ */
+#define ADAMANT_FLAG_WIDE (1 << 8)
+
+/**
+ * The cell is the right half of a double-width character.
+ */
+#define ADAMANT_FLAG_WIDE_SPACER (1 << 9)
+
+/**
+ * Which member of [`AdamantColor`] is meaningful.
+ */
+enum AdamantColorKind
+#if defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+  : uint8_t
+#endif // defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+ {
+  /**
+   * The terminal's default foreground/background
+   */
+  ADAMANT_COLOR_KIND_DEFAULT = 0,
+  /**
+   * `index` into the 256-color palette
+   */
+  ADAMANT_COLOR_KIND_INDEXED = 1,
+  /**
+   * `r`, `g`, `b` truecolor
+   */
+  ADAMANT_COLOR_KIND_RGB = 2,
+};
+#ifndef __cplusplus
+#if __STDC_VERSION__ >= 202311L
+typedef enum AdamantColorKind AdamantColorKind;
+#else
+typedef uint8_t AdamantColorKind;
+#endif // __STDC_VERSION__ >= 202311L
+#endif // __cplusplus
+
+/**
+ * Opaque terminal handle.
+ */
+typedef struct AdamantTerminal AdamantTerminal;
+
+/**
+ * A cell color, still symbolic (palette resolution is up to the host).
+ */
+typedef struct AdamantColor {
+  AdamantColorKind kind;
+  uint8_t index;
+  uint8_t r;
+  uint8_t g;
+  uint8_t b;
+} AdamantColor;
+
+/**
+ * One grid cell.
+ */
+typedef struct AdamantCell {
+  /**
+   * Unicode scalar value (a space for empty cells)
+   */
+  uint32_t codepoint;
+  struct AdamantColor fg;
+  struct AdamantColor bg;
+  /**
+   * `ADAMANT_FLAG_*` bits
+   */
+  uint16_t flags;
+} AdamantCell;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Create a terminal of `cols` x `rows` keeping `scrollback` lines of history.
+ * Returns null if that fails.
+ *
+ * Free it with [`adamant_terminal_free`].
+ */
+struct AdamantTerminal *adamant_terminal_new(uint32_t cols, uint32_t rows, uint32_t scrollback);
+
+/**
+ * Destroy a terminal. Null is ignored.
+ *
+ * # Safety
+ *
+ * `term` must be null or a handle from [`adamant_terminal_new`] that has
+ * not been freed yet.
+ */
+void adamant_terminal_free(struct AdamantTerminal *term);
+
+/**
+ * Process `len` bytes of program output.
+ *
+ * # Safety
+ *
+ * `term` must be a live handle; `bytes` must point to `len` readable bytes
+ * (or be null when `len` is 0).
+ */
+void adamant_terminal_feed(struct AdamantTerminal *term, const uint8_t *bytes, size_t len);
+
+/**
+ * Resize both screens to `cols` x `rows`.
+ *
+ * # Safety
+ *
+ * `term` must be a live handle.
+ */
+void adamant_terminal_resize(struct AdamantTerminal *term, uint32_t cols, uint32_t rows);
+
+/**
+ * Set the width of East Asian Ambiguous characters and of text-style emoji
+ * (see `WidthOptions`). Affects text fed afterwards.
+ *
+ * # Safety
+ *
+ * `term` must be a live handle.
+ */
+void adamant_terminal_set_widths(struct AdamantTerminal *term,
+                                 bool ambiguous_wide,
+                                 bool emoji_wide);
+
+/**
+ * Write the grid size to `cols` and `rows` (either may be null).
+ *
+ * # Safety
+ *
+ * `term` must be a live handle; non-null out pointers must be writable.
+ */
+void adamant_terminal_size(const struct AdamantTerminal *term, uint32_t *cols, uint32_t *rows);
+
+/**
+ * Write the cursor position (0-based) to `row` and `col` (either may be null).
+ *
+ * # Safety
+ *
+ * `term` must be a live handle; non-null out pointers must be writable.
+ */
+void adamant_terminal_cursor(const struct AdamantTerminal *term, uint32_t *row, uint32_t *col);
+
+/**
+ * Read the cell at screen `row`, `col` of the active screen into `out`.
+ *
+ * Returns false (leaving `out` untouched) when the position is outside
+ * the grid.
+ *
+ * # Safety
+ *
+ * `term` must be a live handle; `out` must be writable.
+ */
+bool adamant_terminal_cell(const struct AdamantTerminal *term,
+                           uint32_t row,
+                           uint32_t col,
+                           struct AdamantCell *out);
+
+/**
+ * Serialize the active screen as UTF-8 text, one line per row with
+ * trailing blanks trimmed, joined by `\n`.
+ *
+ * Works like `snprintf`: writes at most `cap - 1` bytes plus a NUL
+ * terminator into `buf` and returns the full length (excluding the NUL),
+ * so a return value `>= cap` means the text was truncated. Pass a null
+ * `buf` and `cap` 0 to query the size.
+ *
+ * # Safety
+ *
+ * `term` must be a live handle; `buf` must point to `cap` writable bytes
+ * (or be null when `cap` is 0).
+ */
+size_t adamant_terminal_serialize(const struct AdamantTerminal *term, char *buf, size_t cap);
+
+/**
+ * Move up to `cap` bytes of pending replies (device status, device
+ * attributes) into `buf` and return how many were written. The host must
+ * send them to the program; call until it returns 0.
+ *
+ * # Safety
+ *
+ * `term` must be a live handle; `buf` must point to `cap` writable bytes
+ * (or be null when `cap` is 0).
+ */
+size_t adamant_terminal_read_responses(struct AdamantTerminal *term, uint8_t *buf, size_t cap);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ADAMANT_H */
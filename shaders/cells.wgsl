@@ -0,0 +1,220 @@
+// Adamant Cell Shader
+//
+// Draws the terminal grid from one packed record per cell (see
+// renderer/cells.rs). Cell positions are derived from the instance index,
+// so the CPU only writes what is *in* each cell, never where it goes.
+//
+// The same instance buffer is drawn three times:
+//   vs_background + fs_fill   cell backgrounds
+//   vs_glyph      + fs_glyph  glyphs from the atlas
+//   vs_decoration + fs_fill   underline and strikeout (12 vertices: 2 quads)
+
+// Cell flags (see renderer/cells.rs)
+const CELL_WIDE: u32 = 1u;
+const CELL_COLOR_GLYPH: u32 = 2u;
+const CELL_UNDERLINE: u32 = 4u;
+const CELL_STRIKEOUT: u32 = 8u;
+
+struct Uniforms {
+    resolution: vec2<f32>,  // Viewport size in pixels
+    cell_size: vec2<f32>,   // Cell size in pixels
+    cols: u32,              // Grid width in cells
+    linear_target: u32,     // 1 if colors must be converted to linear
+    baseline: f32,          // Baseline offset from the top of the cell
+    underline_y: f32,       // Underline offset from the top of the cell
+    strikeout_y: f32,       // Strikeout offset from the top of the cell
+    stroke: f32,            // Decoration thickness
+    atlas_size: f32,        // Atlas side length in texels
+    scroll_y: f32,          // Vertical shift of all rows while scrolling animates
+};
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+@group(0) @binding(1) var atlas_sampler: sampler;
+@group(0) @binding(2) var atlas: texture_2d<f32>;
+
+// One cell, exactly as written by the CPU
+struct CellInput {
+    @location(0) glyph_pos: u32,     // Atlas texel position (x | y << 16)
+    @location(1) glyph_size: u32,    // Glyph size in texels (w | h << 16), 0 = none
+    @location(2) glyph_offset: u32,  // Bearing (left | top << 16), signed 16-bit
+    @location(3) fg: u32,            // sRGB RGBA8
+    @location(4) bg: u32,            // sRGB RGBA8, alpha 0 = default background
+    @location(5) flags: u32,         // CELL_*
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) @interpolate(flat) color_glyph: u32,
+};
+
+// Unit quad corner for a vertex index (two triangles, y down)
+fn corner(index: u32) -> vec2<f32> {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+    );
+    return corners[index % 6u];
+}
+
+// Pixels -> clip space (-1 to 1, y up)
+fn to_clip(pixel: vec2<f32>) -> vec4<f32> {
+    return vec4<f32>(
+        pixel.x / uniforms.resolution.x * 2.0 - 1.0,
+        1.0 - pixel.y / uniforms.resolution.y * 2.0,
+        0.0,
+        1.0,
+    );
+}
+
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+    let low = c / 12.92;
+    let high = pow((c + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, c <= vec3<f32>(0.04045));
+}
+
+fn decode_color(packed: u32) -> vec4<f32> {
+    let color = unpack4x8unorm(packed);
+    if uniforms.linear_target == 1u {
+        return vec4<f32>(srgb_to_linear(color.rgb), color.a);
+    }
+    return color;
+}
+
+fn unpack_u16(packed: u32) -> vec2<f32> {
+    return vec2<f32>(f32(packed & 0xffffu), f32(packed >> 16u));
+}
+
+fn unpack_i16(packed: u32) -> vec2<f32> {
+    let x = bitcast<i32>(packed << 16u) >> 16u;
+    let y = bitcast<i32>(packed) >> 16u;
+    return vec2<f32>(f32(x), f32(y));
+}
+
+// Top-left pixel of the cell drawn by this instance
+fn cell_origin(instance: u32) -> vec2<f32> {
+    let col = instance % uniforms.cols;
+    let row = instance / uniforms.cols;
+    return vec2<f32>(f32(col), f32(row)) * uniforms.cell_size + vec2<f32>(0.0, uniforms.scroll_y);
+}
+
+fn cell_width(cell: CellInput) -> f32 {
+    if (cell.flags & CELL_WIDE) != 0u {
+        return uniforms.cell_size.x * 2.0;
+    }
+    return uniforms.cell_size.x;
+}
+
+// A vertex that produces no fragments
+fn discarded() -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(2.0, 2.0, 0.0, 1.0);
+    return out;
+}
+
+@vertex
+fn vs_background(
+    @builtin(vertex_index) vertex: u32,
+    @builtin(instance_index) instance: u32,
+    cell: CellInput,
+) -> VertexOutput {
+    let color = decode_color(cell.bg);
+    if color.a == 0.0 {
+        return discarded();
+    }
+
+    var out: VertexOutput;
+    let size = vec2<f32>(cell_width(cell), uniforms.cell_size.y);
+    out.clip_position = to_clip(cell_origin(instance) + corner(vertex) * size);
+    out.color = color;
+    return out;
+}
+
+@vertex
+fn vs_glyph(
+    @builtin(vertex_index) vertex: u32,
+    @builtin(instance_index) instance: u32,
+    cell: CellInput,
+) -> VertexOutput {
+    if cell.glyph_size == 0u {
+        return discarded();
+    }
+
+    let glyph_size = unpack_u16(cell.glyph_size);
+    let bearing = unpack_i16(cell.glyph_offset);
+    let origin = cell_origin(instance);
+
+    var pos: vec2<f32>;
+    var size: vec2<f32>;
+    var out: VertexOutput;
+    out.color = decode_color(cell.fg);
+
+    if (cell.flags & CELL_COLOR_GLYPH) != 0u {
+        // Color bitmaps (emoji) come in fixed sizes: scale down to fit the cell
+        let max_size = vec2<f32>(cell_width(cell), uniforms.cell_size.y);
+        let scale = min(min(max_size.y / glyph_size.y, max_size.x / glyph_size.x), 1.0);
+        size = glyph_size * scale;
+        pos = origin + (max_size - size) / 2.0;
+        out.color = vec4<f32>(1.0, 1.0, 1.0, out.color.a);
+        out.color_glyph = 1u;
+    } else {
+        size = glyph_size;
+        pos = origin + vec2<f32>(bearing.x, uniforms.baseline - bearing.y);
+        out.color_glyph = 0u;
+    }
+
+    let c = corner(vertex);
+    out.clip_position = to_clip(pos + c * size);
+    out.uv = (unpack_u16(cell.glyph_pos) + c * glyph_size) / uniforms.atlas_size;
+    return out;
+}
+
+@vertex
+fn vs_decoration(
+    @builtin(vertex_index) vertex: u32,
+    @builtin(instance_index) instance: u32,
+    cell: CellInput,
+) -> VertexOutput {
+    // First quad: underline, second quad: strikeout
+    var flag = CELL_UNDERLINE;
+    var y = uniforms.underline_y;
+    if vertex >= 6u {
+        flag = CELL_STRIKEOUT;
+        y = uniforms.strikeout_y;
+    }
+    if (cell.flags & flag) == 0u {
+        return discarded();
+    }
+
+    var out: VertexOutput;
+    let pos = cell_origin(instance) + vec2<f32>(0.0, y);
+    let size = vec2<f32>(cell_width(cell), uniforms.stroke);
+    out.clip_position = to_clip(pos + corner(vertex) * size);
+    out.color = decode_color(cell.fg);
+    return out;
+}
+
+// Solid fill (outputs premultiplied alpha)
+@fragment
+fn fs_fill(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.color.rgb * in.color.a, in.color.a);
+}
+
+// Atlas sample (outputs premultiplied alpha)
+@fragment
+fn fs_glyph(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = textureSample(atlas, atlas_sampler, in.uv);
+    let alpha = texel.a * in.color.a;
+
+    if in.color_glyph == 1u {
+        return vec4<f32>(texel.rgb * alpha, alpha);
+    }
+
+    // The atlas holds coverage in alpha: tint with the cell color
+    return vec4<f32>(in.color.rgb * alpha, alpha);
+}
@@ -0,0 +1,41 @@
+// Adamant Color Filter Shader
+//
+// The color vision filter (Config::color_filter), run as a post shader
+// (see post.wgsl) after every other pass. The matrix, which works on
+// linear RGB, and whether the frame already holds linear values are put
+// in front as constants:
+//
+//     const FILTER: mat3x3<f32> = mat3x3<f32>(...);  // Rows of the matrix
+//     const LINEAR_FRAME: bool = true;
+
+fn to_linear(c: vec3<f32>) -> vec3<f32> {
+    let low = c / 12.92;
+    let high = pow((c + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, c <= vec3<f32>(0.04045));
+}
+
+fn to_srgb(c: vec3<f32>) -> vec3<f32> {
+    let low = c * 12.92;
+    let high = 1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, c <= vec3<f32>(0.0031308));
+}
+
+@fragment
+fn fs_main(in: PostInput) -> @location(0) vec4<f32> {
+    let color = textureSample(frame, frame_sampler, in.uv);
+    if color.a == 0.0 {
+        return color;
+    }
+    // Filter the color itself, not its premultiplied value
+    var rgb = color.rgb / color.a;
+    if !LINEAR_FRAME {
+        rgb = to_linear(rgb);
+    }
+    // FILTER holds the rows, so the row vector times it is the matrix
+    // times the color
+    rgb = clamp(rgb * FILTER, vec3<f32>(0.0), vec3<f32>(1.0));
+    if !LINEAR_FRAME {
+        rgb = to_srgb(rgb);
+    }
+    return vec4<f32>(rgb * color.a, color.a);
+}
@@ -0,0 +1,51 @@
+// Adamant CRT Shader
+//
+// The picture of the built-in CRT effect (Config::crt), run as a post
+// shader (see post.wgsl) over the frame after phosphor.wgsl has added the
+// afterglow. The intensities are put in front as constants:
+//
+//     const SCANLINES: f32 = 0.5;
+//     const BLOOM: f32 = 0.3;
+//     const CURVATURE: f32 = 0.3;
+
+const PI: f32 = 3.14159265;
+
+// Where the glass at `uv` shows the frame. Points move out with their
+// squared distance from the center, scaled so the middles of the edges
+// stay put: the whole frame stays visible and only the corners fall off.
+fn curve(uv: vec2<f32>) -> vec2<f32> {
+    let k = CURVATURE * 0.1;
+    let centered = uv * 2.0 - 1.0;
+    let bent = centered * (1.0 + k * dot(centered, centered)) / (1.0 + k);
+    return bent * 0.5 + 0.5;
+}
+
+// Average of the frame on a ring around `uv`, `radius` pixels out
+fn glow(uv: vec2<f32>, radius: f32) -> vec4<f32> {
+    var sum = vec4<f32>(0.0);
+    for (var i = 0; i < 12; i++) {
+        let angle = f32(i) * PI / 6.0;
+        let offset = vec2<f32>(cos(angle), sin(angle)) * radius / post.resolution;
+        sum += textureSample(frame, frame_sampler, uv + offset);
+    }
+    return sum / 12.0;
+}
+
+@fragment
+fn fs_main(in: PostInput) -> @location(0) vec4<f32> {
+    let uv = curve(in.uv);
+    var color = textureSample(frame, frame_sampler, uv);
+
+    // Light only adds: dark text on a light background doesn't bleed
+    let bloom = glow(uv, post.cell.y * 0.3);
+    color = vec4<f32>(color.rgb + BLOOM * 0.6 * bloom.rgb, max(color.a, bloom.a));
+
+    // One scanline every three pixels of the frame
+    let line = uv.y * post.resolution.y * PI / 3.0;
+    let scanline = 1.0 - SCANLINES * 0.45 * (1.0 - abs(sin(line)));
+    color = vec4<f32>(color.rgb * scanline, color.a);
+
+    // Past the edges of the glass
+    let inside = all(uv >= vec2<f32>(0.0)) && all(uv <= vec2<f32>(1.0));
+    return select(vec4<f32>(0.0, 0.0, 0.0, 1.0), color, inside);
+}
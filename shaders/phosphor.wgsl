@@ -0,0 +1,33 @@
+// Adamant Phosphor Shader
+//
+// The afterglow of the CRT effect (Config::crt). The afterglow texture is
+// kept from frame to frame: first `fs_fade` dims it (the blend multiplies
+// it by a constant, see crt.rs), then `fs_main` draws the new frame over
+// it keeping the brighter of the two. Pixels that went dark fade out
+// instead of vanishing.
+
+@group(0) @binding(0) var frame: texture_2d<f32>;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // A triangle covering the window
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    return out;
+}
+
+@fragment
+fn fs_fade() -> @location(0) vec4<f32> {
+    return vec4<f32>(0.0);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Both textures are the size of the window
+    return textureLoad(frame, vec2<i32>(in.position.xy), 0);
+}
@@ -0,0 +1,40 @@
+// Adamant Post-Processing Prelude
+//
+// Put in front of the user's shader (Config::shader). The terminal is
+// first drawn into `frame`; then one triangle covering the window runs the
+// user's `fs_main` for every pixel, e.g.:
+//
+//     @fragment
+//     fn fs_main(in: PostInput) -> @location(0) vec4<f32> {
+//         return textureSample(frame, frame_sampler, in.uv);
+//     }
+//
+// Colors in `frame` are premultiplied by alpha, and fs_main returns them
+// premultiplied too.
+
+struct PostUniforms {
+    resolution: vec2<f32>,  // Window size in pixels
+    cursor: vec2<f32>,      // Top-left corner of the cursor cell, in pixels
+    cell: vec2<f32>,        // Cell size in pixels
+    time: f32,              // Seconds since the shader was loaded
+    _padding: f32,
+};
+
+@group(0) @binding(0) var<uniform> post: PostUniforms;
+@group(0) @binding(1) var frame: texture_2d<f32>;
+@group(0) @binding(2) var frame_sampler: sampler;
+
+struct PostInput {
+    @builtin(position) position: vec4<f32>,  // Pixel center, in pixels
+    @location(0) uv: vec2<f32>,              // 0..1 across the window, y down
+};
+
+@vertex
+fn vs_post(@builtin(vertex_index) index: u32) -> PostInput {
+    // (0, 0), (2, 0), (0, 2): the window fits into its corner
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: PostInput;
+    out.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
@@ -1,7 +1,15 @@
 // Adamant Quad Shader
 //
 // This shader renders quads using GPU instancing for efficient batch rendering.
-// Each instance represents a single cell/quad with its own position, size, and color.
+// Each instance is a filled rectangle (default background, cursor, overlays)
+// positioned in pixels. Grid cells are drawn by cells.wgsl.
+
+struct Uniforms {
+    resolution: vec2<f32>,  // Viewport size in pixels
+    _padding: vec2<f32>,
+};
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
 
 // Vertex input from vertex buffer
 struct VertexInput {
@@ -10,8 +18,8 @@ struct VertexInput {
 
 // Instance input from instance buffer (per-quad data)
 struct InstanceInput {
-    @location(1) pos: vec2<f32>,    // Position in clip space (-1 to 1)
-    @location(2) size: vec2<f32>,   // Size in clip space
+    @location(1) pos: vec2<f32>,    // Top-left position in pixels
+    @location(2) size: vec2<f32>,   // Size in pixels
     @location(3) color: vec4<f32>,  // RGBA color
 };
 
@@ -29,28 +37,23 @@ fn vs_main(
 ) -> VertexOutput {
     var out: VertexOutput;
 
-    // Transform local vertex position by instance size and position
-    // vertex.position is in range [0, 1], we scale and translate it
-    let world_pos = vertex.position * instance.size + instance.pos;
+    // Scale the unit quad to the instance rectangle (pixels, y down)
+    let pixel = vertex.position * instance.size + instance.pos;
 
-    out.clip_position = vec4<f32>(world_pos, 0.0, 1.0);
+    // Pixels -> clip space (-1 to 1, y up)
+    let clip = vec2<f32>(
+        pixel.x / uniforms.resolution.x * 2.0 - 1.0,
+        1.0 - pixel.y / uniforms.resolution.y * 2.0,
+    );
+
+    out.clip_position = vec4<f32>(clip, 0.0, 1.0);
     out.color = instance.color;
 
     return out;
 }
 
-// Fragment shader
+// Fragment shader (outputs premultiplied alpha)
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return in.color;
+    return vec4<f32>(in.color.rgb * in.color.a, in.color.a);
 }
-
-// =============================================================================
-// TODO: Phase 2 - Text Rendering
-//
-// To render text, add to VertexInput/InstanceInput:
-// - UV coordinates for glyph atlas sampling
-// - Texture bindings:
-//   @group(0) @binding(0) var glyph_sampler: sampler;
-//   @group(0) @binding(1) var glyph_atlas: texture_2d<f32>;
-// =============================================================================
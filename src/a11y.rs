@@ -0,0 +1,229 @@
+//! Accessibility Model
+//!
+//! What a screen reader needs from a terminal, independent of the platform
+//! API that delivers it: the visible text line by line, the cursor and the
+//! selection as an [`AccessibleScreen`], and [`Announcement`]s for what
+//! changed since the last look (new output, a new selection).
+//!
+//! [`A11yTracker`] compares consecutive screens. Scrolling is detected so
+//! that output pushing the screen up is announced once, and text appended
+//! to the cursor line (echoed typing, a progress counter) is announced as
+//! the appended part only.
+//!
+//! ```
+//! use adamant::a11y::{A11yTracker, AccessibleScreen, Announcement};
+//!
+//! let mut tracker = A11yTracker::default();
+//! let screen = |lines: &[&str]| AccessibleScreen {
+//!     lines: lines.iter().map(|line| line.to_string()).collect(),
+//!     ..AccessibleScreen::default()
+//! };
+//! tracker.update(screen(&["$ ls", ""]));
+//! let announced = tracker.update(screen(&["$ ls", "Cargo.toml  src"]));
+//! assert_eq!(announced, vec![Announcement::Output("Cargo.toml  src".into())]);
+//! ```
+//!
+//! With `gui`, [`tree_update`] turns a screen into the AccessKit tree the
+//! window hands to AT-SPI, UI Automation or NSAccessibility: a terminal
+//! node of line nodes whose text selection is the selected cells, or the
+//! cursor collapsed without a selection, and a polite live region speaking
+//! the last announcement.
+
+/// A snapshot of what is on screen.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccessibleScreen {
+    /// Visible lines, trailing blanks trimmed
+    pub lines: Vec<String>,
+    /// Cursor (row, column) on the visible screen, if shown
+    pub cursor: Option<(usize, usize)>,
+    /// The selected text
+    pub selection: Option<String>,
+    /// The first and last selected cells (inclusive) as (row, column) on
+    /// the visible screen, cut to the part of the selection shown
+    pub selected: Option<((usize, usize), (usize, usize))>,
+}
+
+/// Something worth speaking.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Announcement {
+    /// New or changed text, in screen order
+    Output(String),
+    /// The selection now covers this text
+    Selection(String),
+}
+
+/// Turns successive screens into announcements.
+#[derive(Clone, Debug, Default)]
+pub struct A11yTracker {
+    last: Option<AccessibleScreen>,
+}
+
+impl A11yTracker {
+    /// The screen as of the last [`update`](Self::update).
+    pub fn screen(&self) -> Option<&AccessibleScreen> {
+        self.last.as_ref()
+    }
+
+    /// Record the current screen and return what changed since the last
+    /// one. The first screen is not announced: it is read on demand.
+    pub fn update(&mut self, screen: AccessibleScreen) -> Vec<Announcement> {
+        let Some(last) = self.last.replace(screen) else {
+            return Vec::new();
+        };
+        let screen = self.last.as_ref().expect("just stored");
+        let mut announcements = Vec::new();
+
+        let output = changed_text(&last.lines, &screen.lines);
+        if !output.is_empty() {
+            announcements.push(Announcement::Output(output));
+        }
+        if screen.selection != last.selection
+            && let Some(selection) = &screen.selection
+            && !selection.trim().is_empty()
+        {
+            announcements.push(Announcement::Selection(selection.clone()));
+        }
+        announcements
+    }
+}
+
+/// The text of `new` that wasn't on `old`, lines joined with `\n`.
+fn changed_text(old: &[String], new: &[String]) -> String {
+    let scrolled = scroll_distance(old, new);
+    let mut changed = Vec::new();
+    for (row, line) in new.iter().enumerate() {
+        let before = old.get(row + scrolled).map_or("", String::as_str);
+        if line == before {
+            continue;
+        }
+        // Only the new part of a line that grew (blanks are trimmed from
+        // lines, so a typed word can come with the space before it)
+        let added = match line.strip_prefix(before) {
+            Some(added) => added.trim(),
+            None => line.trim_end(),
+        };
+        if !added.is_empty() {
+            changed.push(added);
+        }
+    }
+    changed.join("\n")
+}
+
+/// How many lines the screen moved up between `old` and `new`: the
+/// smallest shift after which the remaining old lines reappear at the top,
+/// or 0 when nothing lines up. Blank screens don't count as a match.
+fn scroll_distance(old: &[String], new: &[String]) -> usize {
+    let rows = old.len().min(new.len());
+    (0..rows)
+        .find(|&shift| {
+            // The last old line may still be growing
+            let Some((last, settled)) = old[shift..rows].split_last() else {
+                return false;
+            };
+            settled.iter().any(|line| !line.is_empty())
+                && settled.iter().eq(new[..settled.len()].iter())
+                && new[settled.len()].starts_with(last.as_str())
+        })
+        .unwrap_or(0)
+}
+
+/// AccessKit node of the window.
+#[cfg(all(feature = "gui", not(target_arch = "wasm32")))]
+pub const WINDOW_NODE: accesskit::NodeId = accesskit::NodeId(0);
+/// AccessKit node of the terminal, which holds the focus.
+#[cfg(all(feature = "gui", not(target_arch = "wasm32")))]
+pub const TERMINAL_NODE: accesskit::NodeId = accesskit::NodeId(1);
+/// AccessKit live region speaking announcements.
+#[cfg(all(feature = "gui", not(target_arch = "wasm32")))]
+pub const LIVE_NODE: accesskit::NodeId = accesskit::NodeId(2);
+
+/// AccessKit node of visible line `row`.
+#[cfg(all(feature = "gui", not(target_arch = "wasm32")))]
+pub fn line_node(row: usize) -> accesskit::NodeId {
+    accesskit::NodeId(3 + row as u64)
+}
+
+/// The whole AccessKit tree for `screen`, with `spoken` in the live
+/// region. `widths` are the terminal's, to find the character under the
+/// cursor. Every update carries the full tree, so it never depends on
+/// what the screen reader saw before.
+#[cfg(all(feature = "gui", not(target_arch = "wasm32")))]
+pub fn tree_update(
+    screen: &AccessibleScreen,
+    spoken: &str,
+    widths: crate::term::WidthOptions,
+) -> accesskit::TreeUpdate {
+    use accesskit::{Live, Node, Role, TextPosition, TextSelection, TreeId, TreeInfo, TreeUpdate};
+
+    let mut nodes = Vec::with_capacity(screen.lines.len() + 3);
+    let mut window = Node::new(Role::Window);
+    window.set_label("Adamant");
+    window.set_children(vec![TERMINAL_NODE, LIVE_NODE]);
+    nodes.push((WINDOW_NODE, window));
+
+    let mut terminal = Node::new(Role::Terminal);
+    terminal.set_children((0..screen.lines.len()).map(line_node).collect::<Vec<_>>());
+    let position = |(row, col): (usize, usize)| {
+        let line = screen.lines.get(row)?;
+        Some(TextPosition {
+            node: line_node(row),
+            character_index: char_index(line, col, widths),
+        })
+    };
+    // The selection, from its first cell to the end of its last one
+    let selection = match screen.selected {
+        Some((first, (row, col))) => position(first).zip(position((row, col + 1))),
+        None => screen
+            .cursor
+            .and_then(position)
+            .map(|cursor| (cursor, cursor)),
+    };
+    if let Some((anchor, focus)) = selection {
+        terminal.set_text_selection(TextSelection { anchor, focus });
+    }
+    nodes.push((TERMINAL_NODE, terminal));
+
+    let mut live = Node::new(Role::Status);
+    live.set_live(Live::Polite);
+    live.set_label(spoken);
+    nodes.push((LIVE_NODE, live));
+
+    let last = screen.lines.len().saturating_sub(1);
+    for (row, line) in screen.lines.iter().enumerate() {
+        // Lines but the last end in a hard break, which counts as a character
+        let mut value = line.clone();
+        if row < last {
+            value.push('\n');
+        }
+        let mut node = Node::new(Role::TextRun);
+        node.set_character_lengths(
+            value
+                .chars()
+                .map(|c| c.len_utf8() as u8)
+                .collect::<Vec<_>>(),
+        );
+        node.set_value(value);
+        nodes.push((line_node(row), node));
+    }
+
+    TreeUpdate {
+        nodes,
+        tree: Some(TreeInfo::new(WINDOW_NODE)),
+        tree_id: TreeId::ROOT,
+        focus: TERMINAL_NODE,
+    }
+}
+
+/// The character of `line` at cell column `col`, counting wide characters
+/// as the terminal does. Columns past the text land at its end.
+#[cfg(all(feature = "gui", not(target_arch = "wasm32")))]
+fn char_index(line: &str, col: usize, widths: crate::term::WidthOptions) -> usize {
+    let mut cells = 0;
+    for (index, c) in line.chars().enumerate() {
+        cells += widths.width(c);
+        if cells > col {
+            return index;
+        }
+    }
+    line.chars().count()
+}
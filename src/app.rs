@@ -2,85 +2,2631 @@
 //!
 //! This module contains the main application struct and the event loop logic.
 //! See docs/02_event_loop.md for details on how the game-loop style architecture works.
+//!
+//! The window hosts a single [`TerminalWidget`]; everything terminal-specific
+//! (shell, emulation, drawing) lives in the widget so other applications can
+//! embed it the same way.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use web_time::{Duration, Instant, SystemTime};
+use winit::{
+    application::ApplicationHandler,
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{ElementState, KeyEvent, StartCause, TouchPhase, WindowEvent},
+    event_loop::{ActiveEventLoop, AsyncRequestSerial, ControlFlow, EventLoop, EventLoopProxy},
+    keyboard::{Key, ModifiersState, NamedKey},
+    monitor::MonitorHandle,
+    window::{CursorIcon, Fullscreen, UserAttentionType, Window, WindowId, WindowLevel},
+};
+
+use crate::a11y::{A11yTracker, Announcement};
+use crate::backend::Backend;
+use crate::bindings::KeyOutcome;
+use crate::clipboard_history::ClipboardHistory;
+use crate::commands::{self, Action};
+use crate::config::{
+    self, Bell, ColorFilter, CommandAction, Config, GpuPreference, HookAction, HostProfile,
+    Monitor, UserCommand, WindowMode,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::dropdown::Toggle;
+use crate::dropdown::{Dropdown, MonitorArea};
+use crate::error::{AdamantError, Result};
+use crate::file_drop;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::geometry::{self, WindowGeometry};
+use crate::hints::HintPick;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::hotkey::{GlobalHotkey, Hotkey};
+#[cfg(unix)]
+use crate::ipc::{IpcCommand, IpcQuery, IpcReply, IpcServer};
+use crate::pinch::{self, Gesture, TouchPinch};
+use crate::recording;
+use crate::renderer::{Effects, Renderer};
+use crate::secure_input::SecureInput;
+use crate::styled_copy::StyledText;
+use crate::term::{Event as TermEvent, escape_bytes};
+use crate::ui::{Layer, LayerId};
+use crate::watch::WatchHit;
+use crate::widget::{TerminalWidget, TerminalWidgetDescriptor};
+
+/// Initial PTY size; the widget resizes it to the window right away.
+#[cfg(not(target_arch = "wasm32"))]
+const DEFAULT_COLS: u16 = 80;
+#[cfg(not(target_arch = "wasm32"))]
+const DEFAULT_ROWS: u16 = 24;
+
+/// Time between frames while the window is in the background. Output
+/// still wakes the loop immediately; this only slows down idle redraws.
+const UNFOCUSED_FRAME_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long the last screen of an exited program stays before
+/// `Config::respawn` starts it again; also keeps a failing program from
+/// restarting in a tight loop.
+const RESPAWN_DELAY: Duration = Duration::from_secs(1);
+
+/// How often the foreground programs are looked up for
+/// `Config::app_profiles`; a title change looks right away.
+const APP_PROFILE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Zoom change per Zoom In/Out, and its limits.
+const ZOOM_STEP: f32 = 0.1;
+const ZOOM_RANGE: std::ops::RangeInclusive<f32> = 0.5..=3.0;
+
+/// Buttons of the dialog asking whether to close the window.
+const CLOSE_BUTTON: usize = 1;
+const CANCEL_BUTTON: usize = 0;
+
+/// Copy button of the dialog listing the unhandled sequences, next to
+/// `CANCEL_BUTTON`.
+const COPY_REPORT_BUTTON: usize = 2;
+
+/// Unhandled sequences listed at most in their dialog, and the characters
+/// of a sample shown.
+const UNHANDLED_SHOWN: usize = 12;
+const SAMPLE_SHOWN: usize = 32;
+
+/// Events sent into the event loop from background threads and tasks.
+pub enum AppEvent {
+    /// The backend produced output (or exited); run an update.
+    Wakeup,
+    /// The renderer finished initializing in the background.
+    RendererReady(Box<Result<Renderer>>),
+    /// A command arrived on the IPC socket.
+    #[cfg(unix)]
+    Ipc(IpcCommand, IpcReply),
+    /// An item of the menu bar was chosen.
+    #[cfg(target_os = "macos")]
+    Menu(Action),
+    /// The dropdown window's global hotkey was pressed.
+    #[cfg(not(target_arch = "wasm32"))]
+    Hotkey,
+    /// A screen reader asked for the accessibility tree or an action.
+    #[cfg(not(target_arch = "wasm32"))]
+    Accessibility(accesskit_winit::WindowEvent),
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<accesskit_winit::Event> for AppEvent {
+    fn from(event: accesskit_winit::Event) -> Self {
+        AppEvent::Accessibility(event.window_event)
+    }
+}
+
+/// Main application state.
+///
+/// This struct holds all the state needed to run Adamant.
+/// It follows a game-loop style architecture rather than traditional MVC.
+pub struct App {
+    /// Compile-time configuration
+    config: Config,
+    /// The window handle (None until resumed)
+    window: Option<Arc<Window>>,
+    /// The GPU renderer (None until window is created)
+    renderer: Option<Renderer>,
+    /// The effects of the renderer a GPU switch replaced, for the new one
+    switched_effects: Option<Effects>,
+    /// The terminal pane (None until the renderer exists)
+    widget: Option<TerminalWidget>,
+    /// Handle for waking the event loop from background threads
+    proxy: EventLoopProxy<AppEvent>,
+    /// Modifier keys currently held (for the app's own shortcuts)
+    modifiers: ModifiersState,
+    /// Whether the window has keyboard focus
+    focused: bool,
+    /// Last candidate window position given to the input method
+    ime_area: Option<(PhysicalPosition<f64>, PhysicalSize<f64>)>,
+    /// The mouse cursor last shown over the window
+    cursor_icon: CursorIcon,
+    /// The error that stopped the event loop, returned from `run`
+    error: Option<AdamantError>,
+    /// Control socket for scripts
+    #[cfg(unix)]
+    ipc: Option<IpcServer>,
+    /// Screen changes for screen readers, when enabled
+    a11y: Option<A11yTracker>,
+    /// The window's connection to the platform's accessibility API, and
+    /// what its live region last said
+    #[cfg(not(target_arch = "wasm32"))]
+    a11y_adapter: Option<accesskit_winit::Adapter>,
+    #[cfg(not(target_arch = "wasm32"))]
+    spoken: String,
+    /// The dialog asking whether to close while programs are running
+    close_dialog: Option<LayerId>,
+    /// The dialog listing the sequences the terminal doesn't handle
+    unhandled_dialog: Option<LayerId>,
+    /// Program and arguments to run instead of the shell (`adamant -e`)
+    #[cfg(not(target_arch = "wasm32"))]
+    command: Option<Vec<String>>,
+    /// The program exited and the window stays open (`Config::hold_on_exit`)
+    held: bool,
+    /// When the held program starts again (`Config::respawn`)
+    respawn_at: Option<Instant>,
+    /// The detachable session the window shows, and the arguments
+    /// starting its server (`adamant --session`)
+    #[cfg(unix)]
+    session: Option<(String, Vec<String>)>,
+    /// The title set by the program, before the app's additions
+    title: String,
+    /// The host the shell reported running on, which picks the profile
+    host: Option<String>,
+    /// The app profile applied (`Config::app_profiles`), by index, and
+    /// when it was last looked for
+    app_profile: Option<usize>,
+    app_profile_checked: Option<Instant>,
+    /// The working directory the shell last reported, for `{cwd}` in the
+    /// title
+    directory: Option<String>,
+    /// Secure keyboard entry, toggled from the command palette
+    secure_input: SecureInput,
+    /// Activity or silence seen while the window wasn't focused, shown in
+    /// the title until it is
+    alert: Option<Monitor>,
+    /// Font size relative to the configured one (Zoom In/Out)
+    zoom: f32,
+    /// The unrounded zoom while pinching
+    pinch_zoom: Option<f32>,
+    /// Two-finger gestures on touchscreens
+    touch_pinch: TouchPinch,
+    /// Quake-style window state (`Config::dropdown`)
+    dropdown: Option<Dropdown>,
+    /// Position and size before the window was maximized or went
+    /// fullscreen, to go back to
+    windowed: Option<(Option<PhysicalPosition<i32>>, PhysicalSize<u32>)>,
+    /// The mode to leave fullscreen to
+    before_fullscreen: WindowMode,
+    /// Files dropped into the window in this batch of events
+    dropped: Vec<PathBuf>,
+    /// The activation token being fetched for a new window, and the
+    /// launch profile it opens with
+    activation_request: Option<(AsyncRequestSerial, Option<&'static str>)>,
+    /// The dropdown window's hotkey, registered while it lives
+    #[cfg(not(target_arch = "wasm32"))]
+    hotkey: Option<GlobalHotkey>,
+    /// The system clipboard, opened on first use
+    #[cfg(not(target_arch = "wasm32"))]
+    clipboard: Option<arboard::Clipboard>,
+    /// What was copied in the window (`Config::clipboard_history`)
+    clipboard_history: ClipboardHistory,
+    /// Canvas and bridge server (browser builds)
+    #[cfg(target_arch = "wasm32")]
+    web: Option<crate::web::WebOptions>,
+}
+
+impl App {
+    /// Create a new application instance.
+    pub fn new(config: Config, proxy: EventLoopProxy<AppEvent>) -> Self {
+        let clipboard_history = ClipboardHistory::new(&config.clipboard_history);
+        Self {
+            a11y: config.screen_reader.then(A11yTracker::default),
+            #[cfg(not(target_arch = "wasm32"))]
+            a11y_adapter: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            spoken: String::new(),
+            config,
+            window: None,
+            renderer: None,
+            switched_effects: None,
+            widget: None,
+            proxy,
+            modifiers: ModifiersState::empty(),
+            focused: true,
+            ime_area: None,
+            cursor_icon: CursorIcon::Default,
+            error: None,
+            close_dialog: None,
+            unhandled_dialog: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            command: None,
+            held: false,
+            respawn_at: None,
+            #[cfg(unix)]
+            session: None,
+            title: String::from("Adamant"),
+            host: None,
+            app_profile: None,
+            app_profile_checked: None,
+            directory: None,
+            secure_input: SecureInput::default(),
+            alert: None,
+            zoom: 1.0,
+            pinch_zoom: None,
+            touch_pinch: TouchPinch::default(),
+            dropdown: None,
+            windowed: None,
+            before_fullscreen: WindowMode::Windowed,
+            dropped: Vec::new(),
+            activation_request: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            hotkey: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            clipboard: None,
+            clipboard_history,
+            #[cfg(unix)]
+            ipc: None,
+            #[cfg(target_arch = "wasm32")]
+            web: None,
+        }
+    }
+
+    /// Render into a browser canvas and talk to a websocket bridge.
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) fn with_web(mut self, options: crate::web::WebOptions) -> Self {
+        self.web = Some(options);
+        self
+    }
+
+    /// Run the application.
+    ///
+    /// This is the main entry point that creates the event loop and runs the application.
+    /// `command` is the program and arguments to run instead of the shell.
+    /// `session` is the name of a detachable session to attach to (see
+    /// `crate::session`) and the arguments starting its server when it
+    /// isn't running.
+    ///
+    /// Returns the program's exit code when it exited before the window
+    /// closed, for `adamant -e` to exit with.
+    pub async fn run(
+        config: Config,
+        command: Option<Vec<String>>,
+        session: Option<(String, Vec<String>)>,
+    ) -> Result<Option<u32>> {
+        let mut builder = EventLoop::<AppEvent>::with_user_event();
+        // Adamant's own menu bar replaces winit's, once launched
+        #[cfg(target_os = "macos")]
+        winit::platform::macos::EventLoopBuilderExtMacOS::with_default_menu(&mut builder, false);
+        let event_loop = builder.build()?;
+
+        let mut app = App::new(config, event_loop.create_proxy());
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            app.command = command.filter(|command| !command.is_empty());
+        }
+        #[cfg(target_arch = "wasm32")]
+        if command.is_some() {
+            log::warn!("Running a program is not supported on the web");
+        }
+        #[cfg(unix)]
+        {
+            app.session = session;
+        }
+        #[cfg(not(unix))]
+        if session.is_some() {
+            log::warn!("Detachable sessions need Unix sockets");
+        }
+        #[cfg(unix)]
+        if app.config.ipc {
+            app.listen_ipc();
+        }
+        if app.config.dropdown.enabled {
+            app.dropdown = Some(Dropdown::new(&app.config.dropdown));
+            #[cfg(not(target_arch = "wasm32"))]
+            app.register_hotkey();
+            #[cfg(all(
+                unix,
+                not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+            ))]
+            if winit::platform::wayland::EventLoopExtWayland::is_wayland(&event_loop) {
+                log::warn!(
+                    "Under Wayland the dropdown hotkey only works while X11 windows have the focus"
+                );
+            }
+        }
+
+        event_loop.run_app(&mut app)?;
+
+        let exited = app.widget.as_ref().filter(|widget| widget.has_exited());
+        let code = exited.and_then(TerminalWidget::exit_code);
+        let error = app.error.take();
+        // Closing the terminals starts their grace periods, which have to
+        // run out before the process exits
+        drop(app);
+        #[cfg(feature = "pty")]
+        crate::pty::join_closers();
+        match error {
+            Some(error) => Err(error),
+            None => Ok(code),
+        }
+    }
+
+    /// Register the dropdown window's global hotkey.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn register_hotkey(&mut self) {
+        let text = self.config.dropdown.hotkey;
+        let hotkey = match Hotkey::parse(text) {
+            Ok(hotkey) => hotkey,
+            Err(e) => return log::warn!("Invalid dropdown hotkey: {}", e),
+        };
+        let proxy = self.proxy.clone();
+        let pressed = move || {
+            let _ = proxy.send_event(AppEvent::Hotkey);
+        };
+        match GlobalHotkey::register(hotkey, pressed) {
+            Ok(hotkey) => self.hotkey = Some(hotkey),
+            Err(e) => log::warn!("Can't register the dropdown hotkey {}: {}", text, e),
+        }
+    }
+
+    /// Show or hide the dropdown window, for its hotkey.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn toggle_dropdown(&mut self) {
+        let (Some(dropdown), Some(window)) = (&mut self.dropdown, &self.window) else {
+            return;
+        };
+        match dropdown.toggle(self.focused, Instant::now()) {
+            Toggle::Focus => window.focus_window(),
+            Toggle::Show => {
+                let area = dropdown_area(dropdown, window);
+                let (position, size) = dropdown.frame(area);
+                let _ = window.request_inner_size(size);
+                let start = dropdown.slide_position(area, Instant::now());
+                window.set_outer_position(start.unwrap_or(position));
+                window.set_visible(true);
+                window.focus_window();
+            }
+            Toggle::Hide => dropdown.set_monitor(window.current_monitor()),
+        }
+        window.request_redraw();
+    }
+
+    /// Move the dropdown window along its slide, and hide it when it slid
+    /// out.
+    fn slide_dropdown(&mut self) {
+        let (Some(dropdown), Some(window)) = (&mut self.dropdown, &self.window) else {
+            return;
+        };
+        if !dropdown.is_sliding() {
+            return;
+        }
+        let area = dropdown_area(dropdown, window);
+        match dropdown.slide_position(area, Instant::now()) {
+            Some(position) => window.set_outer_position(position),
+            None if dropdown.is_shown() => window.set_outer_position(dropdown.frame(area).0),
+            None => window.set_visible(false),
+        }
+    }
+
+    /// Stop with a fatal error, to be reported by [`App::run`].
+    fn fail(&mut self, event_loop: &ActiveEventLoop, error: AdamantError) {
+        log::error!("{}", error);
+        self.error = Some(error);
+        event_loop.exit();
+    }
+
+    /// Open the IPC socket. Scripts still work without it, so failing is
+    /// not fatal.
+    #[cfg(unix)]
+    fn listen_ipc(&mut self) {
+        let proxy = self.proxy.clone();
+        let server = IpcServer::bind(move |command, reply| {
+            let _ = proxy.send_event(AppEvent::Ipc(command, reply));
+        });
+        match server {
+            Ok(server) => self.ipc = Some(server),
+            Err(e) => log::warn!("IPC socket unavailable: {}", e),
+        }
+    }
+
+    /// Recreate the renderer on another GPU, keeping the terminal.
+    fn switch_gpu(&mut self, event_loop: &ActiveEventLoop, gpu: GpuPreference) {
+        // Still initializing, or already switching
+        let (Some(window), Some(_)) = (self.window.clone(), &self.renderer) else {
+            return;
+        };
+        self.config.gpu = gpu;
+        // A window can only have one surface at a time.
+        let renderer = self.renderer.take();
+        self.switched_effects = renderer.map(|renderer| renderer.effects().clone());
+        if let Err(e) = self.init_renderer(window) {
+            self.fail(event_loop, e);
+        }
+    }
+
+    /// Run a command received over IPC.
+    #[cfg(unix)]
+    fn handle_ipc(&mut self, event_loop: &ActiveEventLoop, command: IpcCommand, reply: IpcReply) {
+        match command {
+            IpcCommand::Query(query) => {
+                let Some(widget) = &self.widget else {
+                    return reply.send(Vec::new());
+                };
+                let lines = match query {
+                    IpcQuery::Text => widget.screen_lines(),
+                    IpcQuery::Cursor => widget
+                        .cursor_position()
+                        .map(|(row, col)| format!("{} {}", row, col))
+                        .into_iter()
+                        .collect(),
+                    IpcQuery::Selection => widget
+                        .selection_text()
+                        .map(|text| text.lines().map(String::from).collect())
+                        .unwrap_or_default(),
+                    IpcQuery::Unhandled => widget
+                        .terminal()
+                        .unhandled()
+                        .iter()
+                        .map(|entry| entry.line())
+                        .collect(),
+                };
+                reply.send(lines);
+            }
+            IpcCommand::Gpu(gpu) => self.switch_gpu(event_loop, gpu),
+            IpcCommand::ReadOnly(read_only) => self.set_read_only(read_only),
+            IpcCommand::Monitor(monitor) => self.set_monitor(monitor),
+            IpcCommand::SetColor(..) | IpcCommand::ResetColors | IpcCommand::Tint(_) => {
+                let Some(widget) = &mut self.widget else {
+                    return;
+                };
+                let colors = widget.terminal_mut().colors_mut();
+                match command {
+                    IpcCommand::SetColor(slot, color) => colors.set(slot, Some(color)),
+                    IpcCommand::Tint(tint) => colors.tint = tint,
+                    _ => colors.reset(),
+                }
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            IpcCommand::Exec { program, args } => {
+                let waker = self.waker();
+                let Some(widget) = &mut self.widget else {
+                    return;
+                };
+                let (cols, rows) = (widget.terminal().cols(), widget.terminal().rows());
+                match crate::backend::PtyBackend::spawn_program(
+                    &program,
+                    &args,
+                    &self.config.shell,
+                    cols as u16,
+                    rows as u16,
+                    waker,
+                ) {
+                    Ok(backend) => widget.replace_backend(Box::new(backend)),
+                    Err(e) => log::warn!("Failed to exec {}: {}", program, e),
+                }
+            }
+        }
+    }
+
+    /// Wakes the event loop when a backend has something to deliver.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn waker(&self) -> impl Fn() + Send + 'static {
+        let proxy = self.proxy.clone();
+        move || {
+            let _ = proxy.send_event(AppEvent::Wakeup);
+        }
+    }
+
+    /// Start the I/O backend: a local shell, the program given with `-e`,
+    /// the detachable session, started first if it isn't running, or the
+    /// recording given with `--replay`.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn connect(&self) -> Result<Box<dyn Backend>> {
+        use crate::backend::PtyBackend;
+
+        if let Some(replay) = self.config.replay {
+            let typescript = recording::Typescript::read(Path::new(replay.file))?;
+            if let Some((cols, rows)) = typescript.size {
+                log::info!("Replaying {}, recorded at {}x{}", replay.file, cols, rows);
+            }
+            let backend =
+                crate::backend::ReplayBackend::start(typescript, replay.speed, self.waker())?;
+            return Ok(Box::new(backend));
+        }
+
+        #[cfg(unix)]
+        if let Some((name, server_args)) = &self.session {
+            if !crate::session::is_running(name) {
+                log::info!("Starting session {:?}", name);
+                crate::session::start(name, server_args)?;
+            }
+            let backend = crate::backend::SessionBackend::attach(
+                name,
+                DEFAULT_COLS,
+                DEFAULT_ROWS,
+                self.waker(),
+            )?;
+            return Ok(Box::new(backend));
+        }
+
+        let backend = match self.command.as_deref().and_then(<[String]>::split_first) {
+            Some((program, args)) => PtyBackend::spawn_program(
+                program,
+                args,
+                &self.config.shell,
+                DEFAULT_COLS,
+                DEFAULT_ROWS,
+                self.waker(),
+            )?,
+            None => {
+                PtyBackend::spawn(&self.config.shell, DEFAULT_COLS, DEFAULT_ROWS, self.waker())?
+            }
+        };
+        Ok(Box::new(backend))
+    }
+
+    /// Start the I/O backend: the websocket bridge.
+    #[cfg(target_arch = "wasm32")]
+    fn connect(&self) -> Result<Box<dyn Backend>> {
+        let url = &self
+            .web
+            .as_ref()
+            .ok_or_else(|| AdamantError::Backend("no bridge server configured".into()))?
+            .url;
+        let proxy = self.proxy.clone();
+        let backend = crate::backend::WebSocketBackend::connect(url, move || {
+            let _ = proxy.send_event(AppEvent::Wakeup);
+        })?;
+        Ok(Box::new(backend))
+    }
+
+    /// Start creating the renderer without blocking the event loop; it
+    /// arrives as [`AppEvent::RendererReady`].
+    fn init_renderer(&self, window: Arc<Window>) -> Result<()> {
+        let proxy = self.proxy.clone();
+        let gpu = self.config.gpu;
+        let wide_gamut = self.config.wide_gamut;
+        let init = async move {
+            let result = Renderer::new(window, gpu, wide_gamut).await;
+            let _ = proxy.send_event(AppEvent::RendererReady(Box::new(result)));
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        std::thread::Builder::new()
+            .name("renderer-init".into())
+            .spawn(move || pollster::block_on(init))?;
+        // The browser has no threads to block; run it as a task instead.
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(init);
+
+        Ok(())
+    }
+
+    /// Create the terminal pane covering the whole window once the renderer exists.
+    fn attach_renderer(&mut self, mut renderer: Renderer) -> Result<()> {
+        let Some(window) = &self.window else {
+            return Ok(());
+        };
+
+        // The window may have been resized while the renderer was being created
+        let size = window.inner_size();
+        renderer.resize(size);
+        // A GPU switch keeps the effects as they are now, which may not be
+        // the configured ones any more
+        let effects = (self.switched_effects.take()).unwrap_or_else(|| self.configured_effects());
+        apply_effects(&mut renderer, &effects);
+
+        // A GPU switch: the terminal moves over to the new device
+        if let Some(widget) = &mut self.widget {
+            widget.set_device(renderer.device(), renderer.format());
+            self.renderer = Some(renderer);
+            window.request_redraw();
+            log::info!("Switched renderer to {:?} GPU", self.config.gpu);
+            return Ok(());
+        }
+
+        // Something to look at while fonts load and the shell starts
+        if let Err(e) = renderer.render_placeholder(&self.config.colors) {
+            log::debug!("Skipping placeholder frame: {:?}", e);
+        }
+
+        let backend = self.connect()?;
+        let widget = TerminalWidget::new(
+            renderer.device(),
+            &TerminalWidgetDescriptor {
+                format: renderer.format(),
+                width: size.width,
+                height: size.height,
+                scale_factor: window.scale_factor() as f32,
+                config: &self.config,
+            },
+            backend,
+        )?;
+
+        self.renderer = Some(renderer);
+        self.widget = Some(widget);
+        if self.config.read_only {
+            self.update_title();
+        }
+        if let Some(path) = self.config.record {
+            self.start_recording(Path::new(path));
+        }
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+
+        log::info!("Window and renderer initialized");
+        Ok(())
+    }
+
+    /// The effects of a new window: the post-processing shader
+    /// (`Config::shader`), the CRT effect and the color filter.
+    fn configured_effects(&self) -> Effects {
+        let config = &self.config;
+        Effects {
+            shader: self.read_shader(),
+            crt: config.crt.enabled.then(|| config.crt.clone()),
+            color_filter: config.color_filter,
+        }
+    }
+
+    /// The source of the post-processing shader (`Config::shader`).
+    fn read_shader(&self) -> Option<String> {
+        let path = self.config.shader?;
+        #[cfg(target_arch = "wasm32")]
+        {
+            log::warn!("Shader files can't be read in the browser: {}", path);
+            None
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        match std::fs::read_to_string(path) {
+            Ok(source) => {
+                log::info!("Drawing through the shader {}", path);
+                Some(source)
+            }
+            Err(e) => {
+                log::warn!("Can't read the shader {}: {}", path, e);
+                None
+            }
+        }
+    }
+
+    /// Whether `event` goes to the program in the foreground instead of
+    /// running one of the shortcuts below (`Config::passthrough`).
+    fn passes_through(&self, event: &KeyEvent) -> bool {
+        self.widget
+            .as_ref()
+            .is_some_and(|widget| widget.passes_through(self.modifiers, &event.logical_key))
+    }
+
+    /// Run the key bindings (`Config::key_bindings`). Returns `true` if the
+    /// key was taken by one, or by a sequence pending, and must not reach
+    /// the terminal.
+    fn key_binding(&mut self, event_loop: &ActiveEventLoop, event: &KeyEvent) -> bool {
+        if event.state != ElementState::Pressed {
+            return false;
+        }
+        let Some(widget) = &mut self.widget else {
+            return false;
+        };
+        if event.repeat {
+            let held = match widget.key_binding_repeat() {
+                KeyOutcome::Unbound => return false,
+                KeyOutcome::Run(held) => held,
+                _ => return true,
+            };
+            let action = match &held {
+                HookAction::Command(name) => self.command_named(name),
+                _ => None,
+            };
+            if action.is_some_and(|action| self.repeats(action)) {
+                self.run_hook(event_loop, vec![held]);
+            }
+            return true;
+        }
+        let outcome = widget.key_binding(self.modifiers, &event.logical_key);
+        if let Some(window) = &self.window
+            && outcome != KeyOutcome::Unbound
+        {
+            window.request_redraw();
+        }
+        match outcome {
+            KeyOutcome::Unbound => false,
+            KeyOutcome::Pending | KeyOutcome::Cancelled => true,
+            KeyOutcome::Run(action) => {
+                self.run_hook(event_loop, vec![action]);
+                true
+            }
+        }
+    }
+
+    /// Handle Ctrl+Shift+C, Ctrl+Shift+V and Ctrl+Shift+Y. Returns `true`
+    /// if the key was one of them and must not reach the terminal.
+    fn clipboard_shortcut(&mut self, event: &KeyEvent) -> bool {
+        if !(self.modifiers.control_key() && self.modifiers.shift_key()) {
+            return false;
+        }
+        let Key::Character(key) = &event.logical_key else {
+            return false;
+        };
+        let action = match key.to_lowercase().as_str() {
+            "c" => Action::Copy,
+            "v" => Action::Paste,
+            "y" => Action::PasteFromHistory,
+            _ => return false,
+        };
+        if !pressed_once(event) {
+            return true;
+        }
+        match action {
+            Action::Copy => self.copy_selection(),
+            Action::Paste => self.paste_clipboard(),
+            _ => self.pick_from_history(),
+        }
+        true
+    }
+
+    /// The system clipboard. It stays open once opened: on X11 copied
+    /// text is served by this process and would go away with it.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn clipboard(&mut self) -> Option<&mut arboard::Clipboard> {
+        if self.clipboard.is_none() {
+            match arboard::Clipboard::new() {
+                Ok(clipboard) => self.clipboard = Some(clipboard),
+                Err(e) => log::warn!("Clipboard unavailable: {}", e),
+            }
+        }
+        self.clipboard.as_mut()
+    }
+
+    /// Copy the selection to the clipboard.
+    fn copy_selection(&mut self) {
+        let Some(widget) = &self.widget else {
+            return;
+        };
+        let Some(text) = widget.selection_text() else {
+            return;
+        };
+        let styled = self
+            .config
+            .copy_styled
+            .then(|| widget.styled_selection())
+            .flatten();
+        self.copy(text, styled);
+    }
+
+    /// Put `text` on the clipboard, and in the clipboard history.
+    fn copy_text(&mut self, text: String) {
+        self.copy(text, None);
+    }
+
+    /// Put `text` on the clipboard along with `styled` as HTML, and RTF
+    /// where there is a place for it (see [`crate::styled_copy`]).
+    fn copy(&mut self, text: String, styled: Option<StyledText>) {
+        self.clipboard_history.push(text.clone());
+        #[cfg(not(target_arch = "wasm32"))]
+        match self.clipboard().map(|c| match &styled {
+            Some(styled) => c.set_html(styled.html(), Some(text)),
+            None => c.set_text(text),
+        }) {
+            Some(Ok(())) => {
+                #[cfg(any(target_os = "macos", windows))]
+                if let Some(styled) = &styled {
+                    crate::styled_copy::add_rtf(&styled.rtf());
+                }
+                if let Some(widget) = &mut self.widget {
+                    widget.notify("Copied to clipboard");
+                }
+            }
+            Some(Err(e)) => log::warn!("Failed to copy: {}", e),
+            None => {}
+        }
+        // TODO: The browser clipboard API is async and needs a user gesture
+        #[cfg(target_arch = "wasm32")]
+        let _ = (text, styled);
+    }
+
+    /// The output of the last command that finished
+    /// (`Grid::last_output`), or a notice that there is none.
+    fn last_output(&mut self) -> Option<String> {
+        let widget = self.widget.as_mut()?;
+        let grid = widget.terminal().grid();
+        let options = widget.copy_options();
+        let text = grid
+            .last_output()
+            .map(|selection| grid.selection_text_with(&selection, options));
+        if text.is_none() {
+            widget.notify("No command output (needs shell integration)");
+        }
+        text
+    }
+
+    /// Open the last command's output in a new window running the program
+    /// named by the environment variable `var`, or `fallback`.
+    fn open_last_output(&mut self, var: &str, fallback: &str) {
+        let Some(text) = self.last_output() else {
+            return;
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let program = std::env::var(var)
+                .ok()
+                .filter(|program| !program.trim().is_empty())
+                .unwrap_or_else(|| fallback.to_string());
+            let exe = std::env::current_exe();
+            match (output_file(&text), exe) {
+                (Ok(path), Ok(exe)) => {
+                    let mut command = std::process::Command::new(exe);
+                    command.arg("-e");
+                    // Like git, let the shell split the program's arguments
+                    #[cfg(unix)]
+                    command
+                        .args(["sh", "-c", &format!("{} \"$1\"", program), "sh"])
+                        .arg(path);
+                    #[cfg(not(unix))]
+                    command.arg(program).arg(path);
+                    spawn_detached(&mut command);
+                }
+                (Err(e), _) | (_, Err(e)) => log::warn!("Can't open the output: {}", e),
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            drop((text, var, fallback));
+            log::warn!("New windows are not supported on the web");
+        }
+    }
+
+    /// Paste the clipboard's text into the terminal.
+    fn paste_clipboard(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        match self.clipboard().map(|c| c.get_text()) {
+            Some(Ok(text)) => {
+                if let Some(widget) = &mut self.widget {
+                    widget.paste(&text);
+                }
+            }
+            Some(Err(e)) => log::warn!("Failed to paste: {}", e),
+            None => {}
+        }
+    }
+
+    /// Open the picker of recent copies, which pastes the one picked.
+    fn pick_from_history(&mut self) {
+        let Some(widget) = &mut self.widget else {
+            return;
+        };
+        if self.clipboard_history.is_empty() {
+            widget.notify("Nothing copied yet");
+        } else {
+            widget.open_clipboard_picker(self.clipboard_history.entries().to_vec());
+        }
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+
+    /// Paste the files dropped into the window (`MouseConfig::drop_format`),
+    /// or change into a single folder dropped with Shift held.
+    fn paste_dropped(&mut self) {
+        if self.dropped.is_empty() {
+            return;
+        }
+        let paths = std::mem::take(&mut self.dropped);
+        let Some(widget) = &mut self.widget else {
+            return;
+        };
+        match paths.as_slice() {
+            [dir] if self.modifiers.shift_key() && dir.is_dir() => {
+                widget.write(file_drop::cd_command(dir).as_bytes())
+            }
+            _ => widget.paste(&file_drop::drop_text(&paths, self.config.mouse.drop_format)),
+        }
+        if let Some(window) = &self.window {
+            window.focus_window();
+        }
+    }
+
+    /// Put text selected with the mouse into the PRIMARY selection, and
+    /// paste it on a middle click (`MouseConfig::primary_selection`).
+    fn sync_primary_selection(&mut self) {
+        let Some(widget) = &mut self.widget else {
+            return;
+        };
+        let selected = widget.take_mouse_selection();
+        let paste = widget.take_primary_paste();
+        let primary = (widget.app_profile())
+            .and_then(|profile| profile.primary_selection)
+            .unwrap_or(self.config.mouse.primary_selection);
+        if !primary {
+            return;
+        }
+
+        #[cfg(all(
+            unix,
+            not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+        ))]
+        {
+            use arboard::{GetExtLinux, LinuxClipboardKind, SetExtLinux};
+
+            if let Some(text) = selected
+                && let Some(clipboard) = self.clipboard()
+                && let Err(e) = clipboard
+                    .set()
+                    .clipboard(LinuxClipboardKind::Primary)
+                    .text(text)
+            {
+                log::warn!("Failed to set the primary selection: {}", e);
+            }
+            if paste && let Some(clipboard) = self.clipboard() {
+                match clipboard
+                    .get()
+                    .clipboard(LinuxClipboardKind::Primary)
+                    .text()
+                {
+                    Ok(text) => {
+                        if let Some(widget) = &mut self.widget {
+                            widget.paste(&text);
+                        }
+                    }
+                    Err(e) => log::debug!("Nothing to paste from the primary selection: {}", e),
+                }
+            }
+        }
+        // No PRIMARY: paste what is selected in the window
+        #[cfg(not(all(
+            unix,
+            not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+        )))]
+        {
+            let _ = selected;
+            if paste && let Some(text) = widget.selection_text() {
+                widget.paste(&text);
+            }
+        }
+    }
+
+    /// Handle Ctrl+Shift+E, which starts hints mode. Returns `true` if the
+    /// key was it and must not reach the terminal.
+    fn hints_shortcut(&mut self, event: &KeyEvent) -> bool {
+        if !(self.modifiers.control_key() && self.modifiers.shift_key()) {
+            return false;
+        }
+        if !matches!(&event.logical_key, Key::Character(key) if key.eq_ignore_ascii_case("e")) {
+            return false;
+        }
+        if pressed_once(event)
+            && let Some(widget) = &mut self.widget
+            && widget.start_hints()
+            && let Some(window) = &self.window
+        {
+            window.request_redraw();
+        }
+        true
+    }
+
+    /// Handle Ctrl+Shift+F, which opens the search prompt. Returns `true`
+    /// if the key was it and must not reach the terminal.
+    fn search_shortcut(&mut self, event: &KeyEvent) -> bool {
+        if !(self.modifiers.control_key() && self.modifiers.shift_key()) {
+            return false;
+        }
+        if !matches!(&event.logical_key, Key::Character(key) if key.eq_ignore_ascii_case("f")) {
+            return false;
+        }
+        if pressed_once(event)
+            && let Some(widget) = &mut self.widget
+        {
+            widget.start_search();
+            if let Some(window) = &self.window {
+                window.request_redraw();
+            }
+        }
+        true
+    }
+
+    /// Handle Ctrl+Shift+Space, which starts copy mode. Returns `true` if
+    /// the key was it and must not reach the terminal.
+    fn copy_mode_shortcut(&mut self, event: &KeyEvent) -> bool {
+        if !(self.modifiers.control_key() && self.modifiers.shift_key()) {
+            return false;
+        }
+        let space = match &event.logical_key {
+            Key::Named(NamedKey::Space) => true,
+            Key::Character(key) => key == " ",
+            _ => false,
+        };
+        if !space {
+            return false;
+        }
+        if pressed_once(event)
+            && let Some(widget) = &mut self.widget
+        {
+            widget.start_copy_mode();
+            if let Some(window) = &self.window {
+                window.request_redraw();
+            }
+        }
+        true
+    }
+
+    /// Handle Ctrl+Shift+P, which opens the command palette. Returns `true`
+    /// if the key was it and must not reach the terminal.
+    fn palette_shortcut(&mut self, event: &KeyEvent) -> bool {
+        if !(self.modifiers.control_key() && self.modifiers.shift_key()) {
+            return false;
+        }
+        if !matches!(&event.logical_key, Key::Character(key) if key.eq_ignore_ascii_case("p")) {
+            return false;
+        }
+        if pressed_once(event)
+            && let Some(widget) = &mut self.widget
+        {
+            let mut commands = commands::commands(&self.config);
+            commands.extend(widget.plugins().commands());
+            widget.open_command_palette(commands);
+            if let Some(window) = &self.window {
+                window.request_redraw();
+            }
+        }
+        true
+    }
+
+    /// Run the action picked in the command palette.
+    fn run_command(&mut self, event_loop: &ActiveEventLoop) {
+        if let Some(action) = self.widget.as_mut().and_then(|w| w.take_command()) {
+            self.run_action(event_loop, action);
+        }
+    }
+
+    /// Run an action from the command palette or the menu bar.
+    fn run_action(&mut self, event_loop: &ActiveEventLoop, action: Action) {
+        match action {
+            Action::Copy => self.copy_selection(),
+            Action::CopyLastOutput => {
+                if let Some(text) = self.last_output() {
+                    self.copy_text(text);
+                }
+            }
+            Action::PageLastOutput => self.open_last_output("PAGER", "less"),
+            Action::EditLastOutput => self.open_last_output("EDITOR", "vi"),
+            Action::Paste => self.paste_clipboard(),
+            Action::PasteFromHistory => self.pick_from_history(),
+            Action::NewWindow => self.open_new_window(None),
+            Action::LaunchProfile(index) => self.open_new_window(Some(index)),
+            Action::ZoomIn => self.set_zoom(self.zoom + ZOOM_STEP),
+            Action::ZoomOut => self.set_zoom(self.zoom - ZOOM_STEP),
+            Action::ZoomReset => self.set_zoom(1.0),
+            Action::Maximize => self.toggle_window_mode(WindowMode::Maximized),
+            Action::Fullscreen => self.toggle_window_mode(WindowMode::Fullscreen),
+            Action::ExclusiveFullscreen => self.toggle_window_mode(WindowMode::ExclusiveFullscreen),
+            Action::Quit => self.request_close(event_loop),
+            Action::Gpu(gpu) => self.switch_gpu(event_loop, gpu),
+            Action::User(index) => self.run_user_command(index),
+            Action::Plugin(plugin, action) => {
+                if let Some(widget) = &mut self.widget {
+                    let actions = widget.plugins_mut().run(plugin, action);
+                    self.run_hook(event_loop, actions);
+                }
+            }
+            Action::Restart => self.restart_exited(),
+            Action::SecureInput => self.toggle_secure_input(),
+            Action::ReadOnly => self.set_read_only(None),
+            Action::ShowUnhandled => self.show_unhandled(),
+            Action::Record => self.toggle_recording(),
+            Action::Rename => self.rename(),
+            Action::Monitor(monitor) => self.set_monitor(monitor),
+            Action::ColorFilter(filter) => self.set_color_filter(filter),
+            Action::SelectAll
+            | Action::ResetTerminal
+            | Action::Reselect
+            | Action::CopyMode
+            | Action::Search
+            | Action::Hints
+            | Action::ScrollLineUp
+            | Action::ScrollLineDown
+            | Action::ScrollPageUp
+            | Action::ScrollPageDown
+            | Action::ScrollLeft
+            | Action::ScrollRight
+            | Action::ScrollToTop
+            | Action::ScrollToBottom => {
+                let Some(widget) = &mut self.widget else {
+                    return;
+                };
+                let history = widget.terminal().grid().history_len() as isize;
+                let page = widget.terminal().rows() as isize;
+                let half_view = (widget.terminal().grid().view_cols() as isize / 2).max(1);
+                match action {
+                    Action::SelectAll => widget.select_all(),
+                    Action::ResetTerminal => widget.reset_terminal(),
+                    Action::Reselect => {
+                        if !widget.reselect() {
+                            widget.notify("Nothing to reselect");
+                        }
+                    }
+                    Action::CopyMode => widget.start_copy_mode(),
+                    Action::Search => widget.start_search(),
+                    Action::Hints => drop(widget.start_hints()),
+                    Action::ScrollLineUp => widget.scroll(1),
+                    Action::ScrollLineDown => widget.scroll(-1),
+                    Action::ScrollPageUp => widget.scroll(page),
+                    Action::ScrollPageDown => widget.scroll(-page),
+                    Action::ScrollLeft => drop(widget.scroll_columns(-half_view)),
+                    Action::ScrollRight => drop(widget.scroll_columns(half_view)),
+                    Action::ScrollToTop => widget.scroll(history),
+                    _ => widget.scroll(-history),
+                }
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+        }
+    }
+
+    /// Open another Adamant window, with the launch profile at `profile`
+    /// of `Config::launch_profiles` if given, and a startup-notification
+    /// token from the desktop so it gets the focus
+    /// (`DesktopConfig::startup_notify`).
+    fn open_new_window(&mut self, profile: Option<usize>) {
+        let profile = profile
+            .and_then(|index| self.config.launch_profiles.get(index))
+            .map(|profile| profile.name);
+        #[cfg(all(
+            unix,
+            not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+        ))]
+        if self.config.desktop.startup_notify
+            && let Some(window) = &self.window
+        {
+            use winit::platform::startup_notify::WindowExtStartupNotify;
+
+            // The token arrives as `WindowEvent::ActivationTokenDone`
+            match window.request_activation_token() {
+                Ok(serial) => return self.activation_request = Some((serial, profile)),
+                Err(e) => log::debug!("No activation token for the new window: {}", e),
+            }
+        }
+        new_window(None, profile);
+    }
+
+    /// Draw the text at `zoom` times the configured size, in whole
+    /// percent.
+    fn set_zoom(&mut self, zoom: f32) {
+        // Steps add up to 0.99999 instead of 1
+        self.pinch_zoom = None;
+        self.apply_zoom((zoom * 100.0).round() / 100.0);
+    }
+
+    /// Zoom with a pinch: smoothly while the fingers move, then snapped to
+    /// a whole pixel font size.
+    fn pinch(&mut self, gesture: Gesture) {
+        match gesture {
+            Gesture::Scale(scale) => {
+                let zoom = (self.pinch_zoom.unwrap_or(self.zoom) * scale)
+                    .clamp(*ZOOM_RANGE.start(), *ZOOM_RANGE.end());
+                self.pinch_zoom = Some(zoom);
+                self.apply_zoom(zoom);
+            }
+            Gesture::Ended => {
+                let (Some(zoom), Some(window)) = (self.pinch_zoom.take(), &self.window) else {
+                    return;
+                };
+                let font_px = self.config.font.size * window.scale_factor() as f32;
+                self.apply_zoom(pinch::snap_zoom(zoom, font_px));
+            }
+            Gesture::Reset => self.set_zoom(1.0),
+        }
+    }
+
+    fn apply_zoom(&mut self, zoom: f32) {
+        let zoom = zoom.clamp(*ZOOM_RANGE.start(), *ZOOM_RANGE.end());
+        let (Some(window), Some(widget)) = (&self.window, &mut self.widget) else {
+            return;
+        };
+        self.zoom = zoom;
+        widget.set_scale_factor(window.scale_factor() as f32 * zoom);
+        widget.notify(&format!("Zoom {:.0}%", zoom * 100.0));
+        window.request_redraw();
+    }
+
+    /// Switch the window to `mode`, or out of it if it is in it already:
+    /// fullscreen goes back to what the window was before, maximized back
+    /// to its previous size and position. The grid follows the `Resized`
+    /// events.
+    fn toggle_window_mode(&mut self, mode: WindowMode) {
+        let Some(window) = &self.window else {
+            return;
+        };
+        if self.dropdown.is_some() {
+            if let Some(widget) = &mut self.widget {
+                widget.notify("The dropdown window keeps its size");
+            }
+            return;
+        }
+        let current = window_mode(window);
+        let mode = match (current, mode) {
+            (WindowMode::Fullscreen | WindowMode::ExclusiveFullscreen, _) if current == mode => {
+                self.before_fullscreen
+            }
+            _ if current == mode => WindowMode::Windowed,
+            _ => mode,
+        };
+        if current == WindowMode::Windowed {
+            self.windowed = Some((window.outer_position().ok(), window.inner_size()));
+        }
+        if matches!(
+            mode,
+            WindowMode::Fullscreen | WindowMode::ExclusiveFullscreen
+        ) && matches!(current, WindowMode::Windowed | WindowMode::Maximized)
+        {
+            self.before_fullscreen = current;
+        }
+
+        match mode {
+            WindowMode::Windowed => {
+                window.set_fullscreen(None);
+                window.set_maximized(false);
+                if let Some((position, size)) = self.windowed.take() {
+                    if let Some(position) = position {
+                        window.set_outer_position(position);
+                    }
+                    let _ = window.request_inner_size(size);
+                }
+            }
+            WindowMode::Maximized => {
+                window.set_fullscreen(None);
+                window.set_maximized(true);
+            }
+            WindowMode::Fullscreen | WindowMode::ExclusiveFullscreen => {
+                window.set_fullscreen(Some(fullscreen(mode, window.current_monitor())));
+            }
+        }
+    }
+
+    /// The window's place from the last run (`Config::remember_geometry`).
+    /// The dropdown window has its own.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_geometry(&self) -> Option<WindowGeometry> {
+        if !self.config.remember_geometry || self.dropdown.is_some() {
+            return None;
+        }
+        WindowGeometry::load(&geometry::state_path(self.config.desktop.app_id)?)
+    }
+
+    /// Save the window's place for the next run: its windowed size and
+    /// position, even while maximized or fullscreen, and its mode.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_geometry(&self) {
+        let Some(window) = &self.window else {
+            return;
+        };
+        if !self.config.remember_geometry || self.dropdown.is_some() {
+            return;
+        }
+        let Some(path) = geometry::state_path(self.config.desktop.app_id) else {
+            return log::warn!("No directory to save the window geometry in");
+        };
+        let mode = window_mode(window);
+        let (position, size) = match self.windowed {
+            Some(windowed) if mode != WindowMode::Windowed => windowed,
+            _ => (window.outer_position().ok(), window.inner_size()),
+        };
+        let monitor = window.current_monitor();
+        let geometry = WindowGeometry {
+            size,
+            position: position.zip(monitor.as_ref()).map(|(position, monitor)| {
+                let origin = monitor.position();
+                PhysicalPosition::new(position.x - origin.x, position.y - origin.y)
+            }),
+            monitor: monitor.and_then(|monitor| monitor.name()),
+            mode,
+        };
+        if let Err(e) = geometry.save(&path) {
+            log::warn!(
+                "Failed to save the window geometry to {}: {}",
+                path.display(),
+                e
+            );
+        }
+    }
+
+    /// Run `Config::commands[index]`.
+    fn run_user_command(&mut self, index: usize) {
+        if let Some(&UserCommand {
+            name,
+            action: CommandAction::PipeOutput(command),
+        }) = self.config.commands.get(index)
+        {
+            return self.pipe_last_output(name, command);
+        }
+        let (Some(command), Some(widget)) = (self.config.commands.get(index), &mut self.widget)
+        else {
+            return;
+        };
+        match command.action {
+            CommandAction::Input(text) => widget.write(text.as_bytes()),
+            #[cfg(not(target_arch = "wasm32"))]
+            CommandAction::Run(command) => {
+                let Some((program, args)) = command.split_first() else {
+                    return;
+                };
+                spawn_detached(std::process::Command::new(program).args(args));
+            }
+            #[cfg(target_arch = "wasm32")]
+            CommandAction::Run(_) => {
+                log::warn!("{:?} can't run programs in the browser", command.name)
+            }
+            CommandAction::PipeOutput(_) => {}
+        }
+    }
+
+    /// Run the user command `name`, `command`, with the last command's
+    /// output on its standard input.
+    fn pipe_last_output(&mut self, name: &str, command: &'static [&'static str]) {
+        let Some(text) = self.last_output() else {
+            return;
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            use std::io::Write;
+            use std::process::{Command, Stdio};
+
+            let Some((program, args)) = command.split_first() else {
+                return;
+            };
+            let child = Command::new(program)
+                .args(args)
+                .stdin(Stdio::piped())
+                .spawn();
+            match child {
+                Ok(mut child) => {
+                    let stdin = child.stdin.take();
+                    drop(std::thread::spawn(move || {
+                        if let Some(mut stdin) = stdin {
+                            let _ = stdin.write_all(text.as_bytes());
+                        }
+                        child.wait()
+                    }));
+                    if let Some(widget) = &mut self.widget {
+                        widget.notify(&format!("Output sent to {}", name));
+                    }
+                }
+                Err(e) => log::warn!("Failed to run {:?}: {}", program, e),
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            drop((text, command));
+            log::warn!("{:?} can't run programs in the browser", name)
+        }
+    }
+
+    /// Close the window, unless programs other than the shell are still
+    /// running in it: then list them and ask first (`Config::confirm_close`).
+    fn request_close(&mut self, event_loop: &ActiveEventLoop) {
+        let running = match &self.widget {
+            Some(widget) if self.config.confirm_close => widget.foreground_processes(),
+            _ => Vec::new(),
+        };
+        let Some(widget) = self.widget.as_mut().filter(|_| !running.is_empty()) else {
+            log::info!("Close requested, exiting...");
+            return event_loop.exit();
+        };
+        if let Some(dialog) = self.close_dialog
+            && widget.ui().layer(dialog).is_some()
+        {
+            return;
+        }
+
+        let mut message = String::from("Still running:\n");
+        for name in &running {
+            message.push_str(&format!("  {}\n", name));
+        }
+        message.push_str("\nClose the window and stop them?");
+        let (rows, cols) = (
+            widget.terminal().rows(),
+            widget.terminal().grid().view_cols(),
+        );
+        let mut dialog = Layer::dialog(
+            &message,
+            &[("Close", CLOSE_BUTTON), ("Cancel", CANCEL_BUTTON)],
+            rows,
+            cols,
+        );
+        dialog.set_focus(CANCEL_BUTTON);
+        self.close_dialog = Some(widget.ui_mut().push(dialog));
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+
+    /// Act on a button pressed in one of the app's dialogs.
+    fn run_activated(&mut self, event_loop: &ActiveEventLoop) {
+        let Some((layer, id)) = self.widget.as_mut().and_then(|w| w.take_activated()) else {
+            return;
+        };
+        if Some(layer) == self.close_dialog && id == CLOSE_BUTTON {
+            log::info!("Closing with programs running");
+            event_loop.exit();
+        }
+        if Some(layer) == self.unhandled_dialog
+            && id == COPY_REPORT_BUTTON
+            && let Some(widget) = &self.widget
+        {
+            let lines: Vec<String> = widget
+                .terminal()
+                .unhandled()
+                .iter()
+                .map(|entry| entry.line())
+                .collect();
+            self.copy_text(lines.join("\n"));
+        }
+    }
+
+    /// Stop recording the output, or start in a new file in the home
+    /// directory.
+    fn toggle_recording(&mut self) {
+        let Some(widget) = &mut self.widget else {
+            return;
+        };
+        if let Some(path) = widget.stop_recording() {
+            return widget.notify(&format!("Saved the recording to {}", path.display()));
+        }
+        let home = std::env::var_os("HOME")
+            .map(PathBuf::from)
+            .unwrap_or_default();
+        self.start_recording(&recording::default_path(&home, SystemTime::now()));
+    }
+
+    fn start_recording(&mut self, path: &Path) {
+        let Some(widget) = &mut self.widget else {
+            return;
+        };
+        match widget.start_recording(path) {
+            Ok(()) => widget.notify(&format!("Recording the output to {}", path.display())),
+            Err(e) => {
+                log::warn!("Can't record to {}: {}", path.display(), e);
+                widget.notify(&format!("Can't record: {}", e));
+            }
+        }
+    }
+
+    /// List the sequences the terminal received but doesn't handle, to be
+    /// copied into a bug report.
+    fn show_unhandled(&mut self) {
+        let Some(widget) = &mut self.widget else {
+            return;
+        };
+        let unhandled = widget.terminal().unhandled();
+        if unhandled.is_empty() {
+            return widget.notify("No unhandled sequences so far");
+        }
+        let mut message = String::from("Sequences received but not handled:\n");
+        for entry in unhandled.iter().take(UNHANDLED_SHOWN) {
+            let mut sample = escape_bytes(&entry.sample);
+            if sample.len() > SAMPLE_SHOWN {
+                sample.truncate(SAMPLE_SHOWN - 1);
+                sample.push('\u{2026}');
+            }
+            message.push_str(&format!(
+                "\n  {:>5}  {:<16} {}",
+                entry.count, entry.sequence, sample
+            ));
+        }
+        if unhandled.len() > UNHANDLED_SHOWN {
+            let more = unhandled.len() - UNHANDLED_SHOWN;
+            message.push_str(&format!("\n  \u{2026} {} more", more));
+        }
+        let (rows, cols) = (
+            widget.terminal().rows(),
+            widget.terminal().grid().view_cols(),
+        );
+        let dialog = Layer::dialog(
+            &message,
+            &[("Copy", COPY_REPORT_BUTTON), ("Close", CANCEL_BUTTON)],
+            rows,
+            cols,
+        );
+        if let Some(dialog) = self.unhandled_dialog.take() {
+            widget.ui_mut().remove(dialog);
+        }
+        self.unhandled_dialog = Some(widget.ui_mut().push(dialog));
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+
+    /// Handle Ctrl+Shift+F10 and Ctrl+Shift+F11, which toggle maximized
+    /// and fullscreen. Returns `true` if the key was one of them.
+    fn window_shortcut(&mut self, event: &KeyEvent) -> bool {
+        if !(self.modifiers.control_key() && self.modifiers.shift_key()) {
+            return false;
+        }
+        let mode = match event.logical_key {
+            Key::Named(NamedKey::F10) => WindowMode::Maximized,
+            Key::Named(NamedKey::F11) => WindowMode::Fullscreen,
+            _ => return false,
+        };
+        if pressed_once(event) {
+            self.toggle_window_mode(mode);
+        }
+        true
+    }
+
+    /// Handle Ctrl+Shift+F2, which renames the window. Returns `true` if
+    /// the key was it and must not reach the terminal.
+    fn rename_shortcut(&mut self, event: &KeyEvent) -> bool {
+        if !(self.modifiers.control_key() && self.modifiers.shift_key())
+            || event.logical_key != Key::Named(NamedKey::F2)
+        {
+            return false;
+        }
+        if pressed_once(event) {
+            self.rename();
+        }
+        true
+    }
+
+    /// Open the prompt renaming the window.
+    fn rename(&mut self) {
+        if let Some(widget) = &mut self.widget {
+            widget.start_rename();
+            if let Some(window) = &self.window {
+                window.request_redraw();
+            }
+        }
+    }
+
+    /// Handle Ctrl+Shift+R, which restarts the exited program. Returns
+    /// `true` if the key was it and must not reach the terminal.
+    fn restart_shortcut(&mut self, event: &KeyEvent) -> bool {
+        if !(self.modifiers.control_key() && self.modifiers.shift_key()) {
+            return false;
+        }
+        if !matches!(&event.logical_key, Key::Character(key) if key.eq_ignore_ascii_case("r")) {
+            return false;
+        }
+        if pressed_once(event) {
+            self.restart_exited();
+        }
+        true
+    }
+
+    /// Restart the program if it exited, otherwise say why not.
+    fn restart_exited(&mut self) {
+        if self.held {
+            self.restart();
+        } else if let Some(widget) = &mut self.widget {
+            widget.notify("The program is still running");
+        }
+    }
+
+    /// Switch the color vision filter (`Config::color_filter`).
+    fn set_color_filter(&mut self, filter: Option<ColorFilter>) {
+        let (Some(renderer), Some(widget)) = (&mut self.renderer, &mut self.widget) else {
+            return;
+        };
+        if let Err(e) = renderer.set_color_filter(filter) {
+            return log::warn!("Can't use the color filter: {}", e);
+        }
+        match filter {
+            Some(filter) => widget.notify(&format!(
+                "Color filter: {}",
+                commands::color_filter_name(filter)
+            )),
+            None => widget.notify("Color filter off"),
+        }
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+
+    /// Turn secure keyboard entry on or off, and show it in the title.
+    fn toggle_secure_input(&mut self) {
+        let Some(widget) = &mut self.widget else {
+            return;
+        };
+        if !SecureInput::SUPPORTED {
+            return widget.notify("Secure keyboard entry needs macOS");
+        }
+        if self.secure_input.toggle(self.focused) {
+            widget.notify("Secure keyboard entry on");
+        } else {
+            widget.notify("Secure keyboard entry off");
+        }
+        self.update_title();
+    }
+
+    /// Lock the window against input, unlock it, or toggle it with
+    /// `None`, and show it in the title.
+    fn set_read_only(&mut self, read_only: Option<bool>) {
+        let Some(widget) = &mut self.widget else {
+            return;
+        };
+        let read_only = read_only.unwrap_or(!widget.is_read_only());
+        widget.set_read_only(read_only);
+        widget.notify(if read_only {
+            "Read-only: input is not sent"
+        } else {
+            "Read-only off"
+        });
+        self.update_title();
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
 
-use std::sync::Arc;
+    /// Watch the window for activity or silence, or stop with `None`.
+    fn set_monitor(&mut self, monitor: Option<Monitor>) {
+        let Some(widget) = &mut self.widget else {
+            return;
+        };
+        widget.set_monitor(monitor);
+        widget.notify(match monitor {
+            Some(Monitor::Activity) => "Watching for activity",
+            Some(Monitor::Silence) => "Watching for silence",
+            None => "Not watching",
+        });
+        self.alert = None;
+        self.update_title();
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
 
-use winit::{
-    application::ApplicationHandler,
-    dpi::PhysicalSize,
-    event::WindowEvent,
-    event_loop::{ActiveEventLoop, EventLoop},
-    window::{Window, WindowId},
-};
+    /// Report activity or silence seen in the window: a notification, and
+    /// while unfocused a mark in the title and a request for attention.
+    fn report_alert(&mut self, alert: Monitor) {
+        let Some(widget) = &mut self.widget else {
+            return;
+        };
+        widget.notify(match alert {
+            Monitor::Activity => "Activity",
+            Monitor::Silence => "Silence",
+        });
+        if self.focused {
+            return;
+        }
+        self.alert = Some(alert);
+        self.update_title();
+        if let Some(window) = &self.window {
+            window.request_user_attention(Some(UserAttentionType::Informational));
+        }
+    }
+
+    /// Act on what a watch rule matched in the output.
+    fn run_watch_hit(&mut self, event_loop: &ActiveEventLoop, hit: WatchHit) {
+        match hit {
+            WatchHit::Line(line) => {
+                if let Some(hook) = self.config.hooks.on_output {
+                    self.run_hook(event_loop, hook(&line));
+                }
+            }
+            WatchHit::Notify { rule, line } => {
+                if let Some(widget) = &mut self.widget {
+                    widget.notify(&format!("{}: {}", rule, line));
+                }
+                if let Some(window) = &self.window
+                    && !self.focused
+                {
+                    window.request_user_attention(Some(UserAttentionType::Informational));
+                }
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            WatchHit::Sound(file) => play_sound(file),
+            #[cfg(not(target_arch = "wasm32"))]
+            WatchHit::Run(command) => {
+                let Some((program, args)) = command.split_first() else {
+                    return;
+                };
+                spawn_detached(std::process::Command::new(program).args(args));
+            }
+            #[cfg(target_arch = "wasm32")]
+            hit => log::warn!("{:?} is not supported in the browser yet", hit),
+        }
+    }
 
-use crate::renderer::Renderer;
+    /// The action of the palette entry called `name`, the plugins'
+    /// included.
+    fn command_named(&self, name: &str) -> Option<Action> {
+        commands::command_named(&self.config, name).or_else(|| {
+            let commands = self.widget.as_ref()?.plugins().commands();
+            commands
+                .into_iter()
+                .find(|command| command.name == name)
+                .map(|command| command.action)
+        })
+    }
 
-/// Main application state.
-///
-/// This struct holds all the state needed to run Adamant.
-/// It follows a game-loop style architecture rather than traditional MVC.
-pub struct App {
-    /// The window handle (None until resumed)
-    window: Option<Arc<Window>>,
-    /// The GPU renderer (None until window is created)
-    renderer: Option<Renderer>,
+    /// Handle the shortcuts of the plugins' actions. Returns `true` if the
+    /// key was one and must not reach the terminal.
+    fn plugin_shortcut(&mut self, event_loop: &ActiveEventLoop, event: &KeyEvent) -> bool {
+        let Some(action) = (self.widget.as_ref()).and_then(|widget| {
+            widget
+                .plugins()
+                .shortcut(self.modifiers, &event.logical_key)
+        }) else {
+            return false;
+        };
+        if pressed_once(event) || event.repeat && self.repeats(action) {
+            self.run_action(event_loop, action);
+        }
+        true
+    }
+
+    /// Whether holding down a key keeps running `action`: only scrolling
+    /// does, when `Config::repeat_scroll_keys` allows it. Opening windows
+    /// or zooming dozens of times is never what a held key meant.
+    fn repeats(&self, action: Action) -> bool {
+        self.config.repeat_scroll_keys && action.is_scroll()
+    }
+
+    /// Do what a hook (`Config::hooks`) or a plugin asked for.
+    fn run_hook(&mut self, event_loop: &ActiveEventLoop, actions: Vec<HookAction>) {
+        for action in actions {
+            match action {
+                HookAction::Command(name) => match self.command_named(&name) {
+                    Some(action) => self.run_action(event_loop, action),
+                    None => log::warn!("Hook: no command named {:?}", name),
+                },
+                HookAction::Input(text) => {
+                    if let Some(widget) = &mut self.widget {
+                        widget.write(text.as_bytes());
+                    }
+                }
+                HookAction::Notify(text) => {
+                    if let Some(widget) = &mut self.widget {
+                        widget.notify(&text);
+                    }
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                HookAction::Run(command) => match command.split_first() {
+                    Some((program, args)) => {
+                        spawn_detached(std::process::Command::new(program).args(args));
+                    }
+                    None => log::warn!("Hook: Run needs a program"),
+                },
+                #[cfg(target_arch = "wasm32")]
+                action => log::warn!("{:?} is not supported in the browser yet", action),
+            }
+        }
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+
+    /// Show the terminal's title, formatted by the host profile or
+    /// `Config::title`.
+    fn update_title(&self) {
+        let Some(window) = &self.window else {
+            return;
+        };
+        let template =
+            (self.profile().and_then(|profile| profile.title)).unwrap_or(self.config.title);
+        let title = config::expand_title(
+            template,
+            &self.title,
+            self.host.as_deref().unwrap_or(""),
+            self.directory.as_deref().unwrap_or(""),
+        );
+        let mut marks = Vec::new();
+        if self.secure_input.enabled() {
+            marks.push("\u{1F512}");
+        }
+        if self
+            .widget
+            .as_ref()
+            .is_some_and(TerminalWidget::is_read_only)
+        {
+            marks.push("[read-only]");
+        }
+        match self.alert {
+            Some(Monitor::Activity) => marks.push("[activity]"),
+            Some(Monitor::Silence) => marks.push("[silence]"),
+            None => {}
+        }
+        window.set_title(&window_title(&title, &marks));
+    }
+
+    /// The profile of the host the shell runs on (`Config::profiles`).
+    fn profile(&self) -> Option<&HostProfile> {
+        self.config.profile_for(self.host.as_deref()?)
+    }
+
+    /// The shell reported running on `host`: switch to its profile, or
+    /// back to the configured settings.
+    fn set_host(&mut self, host: Option<String>) {
+        let before = self.profile().map(|profile| profile.name);
+        self.host = host;
+        let after = self.profile().map(|profile| profile.name);
+        if after != before {
+            self.apply_colors();
+            match after {
+                Some(name) => {
+                    log::info!("Using the {:?} profile", name);
+                    if let Some(widget) = &mut self.widget {
+                        widget.notify(&format!("Profile: {}", name));
+                    }
+                }
+                None => log::info!("Back to the default profile"),
+            }
+            if let Some(window) = &self.window {
+                window.request_redraw();
+            }
+        }
+        self.update_title();
+    }
+
+    /// Draw with the colors of the app profile, the host profile or
+    /// `Config::colors`, the first that has them.
+    fn apply_colors(&mut self) {
+        let app_colors = self
+            .app_profile
+            .and_then(|i| self.config.app_profiles[i].colors.as_ref());
+        let host_colors = self.profile().and_then(|profile| profile.colors.as_ref());
+        let mut colors = (app_colors.or(host_colors))
+            .unwrap_or(&self.config.colors)
+            .clone();
+        colors.background_opacity = self.config.colors.background_opacity;
+        if let Some(widget) = &mut self.widget {
+            widget.set_colors(&colors);
+            widget.terminal_mut().colors_mut().tint = colors.tint;
+        }
+    }
+
+    /// Switch to the app profile for the title and the foreground
+    /// programs, or back to the configured settings. The programs are
+    /// looked up every `APP_PROFILE_INTERVAL` unless `now` is set.
+    fn check_app_profile(&mut self, now: bool) {
+        let Some(widget) = &mut self.widget else {
+            return;
+        };
+        if self.config.app_profiles.is_empty()
+            || !now
+                && self
+                    .app_profile_checked
+                    .is_some_and(|at| at.elapsed() < APP_PROFILE_INTERVAL)
+        {
+            return;
+        }
+        self.app_profile_checked = Some(Instant::now());
+        let programs = widget.foreground_job();
+        let index = (self.config.app_profiles.iter())
+            .position(|profile| profile.matches(&self.title, &programs));
+        if index == self.app_profile {
+            return;
+        }
+        self.app_profile = index;
+        let profile = index.map(|i| &self.config.app_profiles[i]);
+        widget.set_app_profile(profile);
+        match profile {
+            Some(profile) => log::info!("Using the {:?} app profile", profile.name),
+            None => log::info!("Leaving the app profile"),
+        }
+        self.apply_colors();
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+
+    /// Ring the bell the way the profile or `Config::bell` says.
+    fn bell(&self) {
+        let bell = self
+            .profile()
+            .and_then(|profile| profile.bell)
+            .unwrap_or(self.config.bell);
+        match (bell, &self.window) {
+            (Bell::Attention, Some(window)) if !self.focused => {
+                window.request_user_attention(Some(UserAttentionType::Informational));
+            }
+            _ => log::debug!("Bell"),
+        }
+    }
+
+    /// Handle Enter and R while the window is held after the program
+    /// exited. Returns `true` if the key was one of them.
+    fn held_key(&mut self, event_loop: &ActiveEventLoop, event: &KeyEvent) -> bool {
+        if !self.held || !(self.modifiers.is_empty() || self.modifiers == ModifiersState::SHIFT) {
+            return false;
+        }
+        let restart = match &event.logical_key {
+            Key::Named(NamedKey::Enter) => false,
+            Key::Character(key) if key.eq_ignore_ascii_case("r") => true,
+            _ => return false,
+        };
+        if !pressed_once(event) {
+            return true;
+        }
+        if restart {
+            self.restart();
+        } else {
+            log::info!("Closing the held window");
+            event_loop.exit();
+        }
+        true
+    }
+
+    /// Start the program again in the same pane, keeping the screen and
+    /// scrollback.
+    fn restart(&mut self) {
+        let backend = match self.connect() {
+            Ok(backend) => backend,
+            Err(e) => return log::warn!("Failed to restart: {}", e),
+        };
+        let Some(widget) = &mut self.widget else {
+            return;
+        };
+        // A replay starts from the blank screen it was recorded on
+        if self.config.replay.is_some() {
+            widget.reset_terminal();
+        } else {
+            widget.feed(b"\r\n");
+        }
+        widget.replace_backend(backend);
+        self.held = false;
+        self.respawn_at = None;
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+
+    /// Act on a hint picked in hints mode.
+    fn run_picked_hint(&mut self) {
+        let Some(widget) = &mut self.widget else {
+            return;
+        };
+        let Some(pick) = widget.take_picked_hint() else {
+            return;
+        };
+        match pick {
+            HintPick::Paste(text) => widget.paste(&text),
+            #[cfg(not(target_arch = "wasm32"))]
+            HintPick::Copy(text) => self.copy_text(text),
+            #[cfg(not(target_arch = "wasm32"))]
+            HintPick::Open(target) => open_external(&target),
+            #[cfg(not(target_arch = "wasm32"))]
+            HintPick::Edit { path, line, column } => {
+                edit_file(&path, line, column, widget.terminal().directory())
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            HintPick::Run(command) => {
+                let Some((program, args)) = command.split_first() else {
+                    return;
+                };
+                spawn_detached(std::process::Command::new(program).args(args));
+            }
+            // TODO: Copy with the async clipboard API, open with window.open()
+            #[cfg(target_arch = "wasm32")]
+            pick => log::warn!("{:?} is not supported in the browser yet", pick),
+        }
+    }
+
+    /// Show the mouse cursor the widget asks for: a pointer over links.
+    fn update_cursor_icon(&mut self) {
+        let (Some(window), Some(widget)) = (&self.window, &self.widget) else {
+            return;
+        };
+        let icon = widget.cursor_icon();
+        if self.cursor_icon != icon {
+            window.set_cursor(icon);
+            self.cursor_icon = icon;
+        }
+    }
+
+    /// Keep the input method's candidate window next to the cursor.
+    fn update_ime_area(&mut self) {
+        let (Some(window), Some(widget)) = (&self.window, &self.widget) else {
+            return;
+        };
+        let area = widget.ime_cursor_area();
+        if self.ime_area != Some(area) {
+            window.set_ime_cursor_area(area.0, area.1);
+            self.ime_area = Some(area);
+        }
+    }
+
+    /// Pull backend output into the terminal and react to its notifications.
+    fn update(&mut self, event_loop: &ActiveEventLoop) {
+        if self.respawn_at.is_some_and(|at| Instant::now() >= at) {
+            self.restart();
+        }
+        let Some(widget) = &mut self.widget else {
+            return;
+        };
+
+        let changed = widget.update();
+
+        for event in widget.take_events() {
+            match event {
+                TermEvent::Title(title) if title.is_empty() => {
+                    self.title = String::from("Adamant");
+                    self.update_title();
+                    self.check_app_profile(true);
+                }
+                TermEvent::Title(title) => {
+                    self.title = title;
+                    self.update_title();
+                    self.check_app_profile(true);
+                    if let Some(hook) = self.config.hooks.on_title_change {
+                        self.run_hook(event_loop, hook(&self.title));
+                    }
+                }
+                TermEvent::Bell => self.bell(),
+                TermEvent::Host(host) => self.set_host(host),
+            }
+        }
+        self.check_app_profile(false);
+        if let Some(alert) = self.widget.as_mut().and_then(TerminalWidget::take_alert) {
+            self.report_alert(alert);
+        }
+        if let Some(widget) = &self.widget
+            && widget.terminal().directory() != self.directory.as_deref()
+        {
+            self.directory = widget.terminal().directory().map(str::to_string);
+            self.update_title();
+        }
+        let hits = (self.widget.as_mut()).map_or_else(Vec::new, TerminalWidget::take_watch_hits);
+        for hit in hits {
+            self.run_watch_hit(event_loop, hit);
+        }
+        let Some(widget) = &mut self.widget else {
+            return;
+        };
+
+        // A program started over IPC ends holding
+        self.held &= widget.has_exited();
+        if widget.has_exited() && !self.held {
+            let code = widget.exit_code();
+            let respawn = self.config.respawn;
+            if !respawn && !self.config.hold_on_exit.holds(code) {
+                log::info!("Shell exited, closing...");
+                return event_loop.exit();
+            }
+            log::info!("Shell exited with {:?}, holding the window", code);
+            self.held = true;
+            self.respawn_at = respawn.then(|| Instant::now() + RESPAWN_DELAY);
+            let status = match code {
+                Some(code) => format!("[process exited with code {}]", code),
+                None => "[process exited]".to_string(),
+            };
+            let newline = if widget.terminal().grid().cursor.col > 0 {
+                "\r\n"
+            } else {
+                ""
+            };
+            let keys = if respawn {
+                "restarting... Enter: close"
+            } else {
+                "Enter: close, R: restart"
+            };
+            let banner = format!("\x1b[0m{}\x1b[7m{}\x1b[0m {}", newline, status, keys);
+            // The replayed screen stays as it was recorded
+            if self.config.replay.is_some() {
+                widget.notify("Replay finished (R: play again, Enter: close)");
+            } else {
+                widget.feed(banner.as_bytes());
+            }
+        }
+        if changed {
+            self.track_accessibility();
+        }
+    }
+
+    /// Compare the screen with the last one and announce what changed.
+    fn track_accessibility(&mut self) {
+        let (Some(tracker), Some(widget)) = (&mut self.a11y, &self.widget) else {
+            return;
+        };
+        let screen = widget.accessible_screen();
+        #[cfg(not(target_arch = "wasm32"))]
+        let moved = tracker.screen() != Some(&screen);
+        let mut spoken = Vec::new();
+        for announcement in tracker.update(screen) {
+            log::debug!("Announce: {:?}", announcement);
+            match announcement {
+                Announcement::Output(text) | Announcement::Selection(text) => spoken.push(text),
+            }
+        }
+        // Spoken through the live region of the tree
+        #[cfg(not(target_arch = "wasm32"))]
+        if moved {
+            if !spoken.is_empty() {
+                self.spoken = spoken.join("\n");
+            }
+            self.update_accessibility_tree();
+        }
+    }
+
+    /// Hand the screen to a screen reader, if one is listening.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn update_accessibility_tree(&mut self) {
+        let (Some(adapter), Some(tracker), Some(widget)) =
+            (&mut self.a11y_adapter, &self.a11y, &self.widget)
+        else {
+            return;
+        };
+        let Some(screen) = tracker.screen() else {
+            return;
+        };
+        let widths = widget.terminal().width_options();
+        adapter.update_if_active(|| crate::a11y::tree_update(screen, &self.spoken, widths));
+    }
+
+    /// A request from the screen reader.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn accessibility_event(&mut self, event: accesskit_winit::WindowEvent) {
+        match event {
+            accesskit_winit::WindowEvent::InitialTreeRequested => {
+                // The tree starts from the screen as it is now
+                if let (Some(tracker), Some(widget)) = (&mut self.a11y, &self.widget) {
+                    tracker.update(widget.accessible_screen());
+                }
+                self.update_accessibility_tree();
+            }
+            accesskit_winit::WindowEvent::ActionRequested(request) => {
+                if request.action == accesskit::Action::Focus
+                    && let Some(window) = &self.window
+                {
+                    window.focus_window();
+                }
+            }
+            accesskit_winit::WindowEvent::AccessibilityDeactivated => {}
+        }
+    }
 }
 
-impl App {
-    /// Create a new application instance.
-    pub fn new() -> Self {
-        Self {
-            window: None,
-            renderer: None,
+/// Whether `event` is a key going down, not held down and repeating.
+fn pressed_once(event: &KeyEvent) -> bool {
+    event.state == ElementState::Pressed && !event.repeat
+}
+
+/// The window title: the program's, after the marks for secure keyboard
+/// entry, read-only and monitor alerts.
+fn window_title(title: &str, marks: &[&str]) -> String {
+    marks
+        .iter()
+        .fold(String::new(), |marked, mark| marked + mark + " ")
+        + title
+}
+
+/// The mode `window` is in.
+fn window_mode(window: &Window) -> WindowMode {
+    match window.fullscreen() {
+        Some(Fullscreen::Exclusive(_)) => WindowMode::ExclusiveFullscreen,
+        Some(Fullscreen::Borderless(_)) => WindowMode::Fullscreen,
+        None if window.is_maximized() => WindowMode::Maximized,
+        None => WindowMode::Windowed,
+    }
+}
+
+/// Fullscreen on `monitor` for one of the fullscreen modes. Exclusive
+/// fullscreen keeps the monitor's resolution and picks its highest refresh
+/// rate; without video modes to pick from it is borderless.
+fn fullscreen(mode: WindowMode, monitor: Option<MonitorHandle>) -> Fullscreen {
+    let video_mode = monitor
+        .as_ref()
+        .filter(|_| mode == WindowMode::ExclusiveFullscreen)
+        .and_then(|monitor| {
+            let size = monitor.size();
+            monitor.video_modes().max_by_key(|video_mode| {
+                (
+                    video_mode.size() == size,
+                    video_mode.refresh_rate_millihertz(),
+                    video_mode.bit_depth(),
+                )
+            })
+        });
+    match video_mode {
+        Some(video_mode) => Fullscreen::Exclusive(video_mode),
+        None => {
+            if mode == WindowMode::ExclusiveFullscreen {
+                log::warn!("No video modes for exclusive fullscreen, going borderless");
+            }
+            Fullscreen::Borderless(monitor)
         }
     }
+}
 
-    /// Run the application.
-    ///
-    /// This is the main entry point that creates the event loop and runs the application.
-    pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
-        let event_loop = EventLoop::new()?;
+/// The monitor the dropdown window goes on: the one it was hidden on, or
+/// the one it is on.
+fn dropdown_area(dropdown: &Dropdown, window: &Window) -> MonitorArea {
+    match dropdown
+        .monitor()
+        .cloned()
+        .or_else(|| window.current_monitor())
+    {
+        Some(monitor) => (monitor.position(), monitor.size()),
+        None => (PhysicalPosition::default(), window.outer_size()),
+    }
+}
 
-        let mut app = App::new();
+/// Start another instance of Adamant, in its own window, handing it the
+/// startup-notification `token` and the launch `profile`.
+fn new_window(token: Option<String>, profile: Option<&str>) {
+    #[cfg(not(target_arch = "wasm32"))]
+    match std::env::current_exe() {
+        Ok(exe) => {
+            let mut command = std::process::Command::new(exe);
+            if let Some(profile) = profile {
+                command.args(["--profile", profile]);
+            }
+            if let Some(token) = &token {
+                for name in crate::pty::STARTUP_NOTIFY_VARS {
+                    command.env(name, token);
+                }
+            }
+            spawn_detached(&mut command);
+        }
+        Err(e) => log::warn!("Can't find the executable for a new window: {}", e),
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        drop((token, profile));
+        log::warn!("New windows are not supported on the web");
+    }
+}
 
-        event_loop.run_app(&mut app)?;
+/// Open a URL or file with the desktop's default handler.
+#[cfg(not(target_arch = "wasm32"))]
+fn open_external(target: &str) {
+    let target = crate::hints::open_target(target);
+    #[cfg(windows)]
+    shell_open(&target);
+    #[cfg(not(windows))]
+    spawn_detached(&mut crate::hints::open_command(&target));
+}
 
-        Ok(())
+/// Open `target` with `ShellExecuteW`, which gets it as one string: no
+/// command line for cmd.exe to split at `&` or `|`.
+#[cfg(windows)]
+fn shell_open(target: &std::ffi::OsStr) {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::UI::Shell::ShellExecuteW;
+    use windows_sys::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+
+    let wide = |text: &std::ffi::OsStr| -> Vec<u16> { text.encode_wide().chain([0]).collect() };
+    let (verb, file) = (wide("open".as_ref()), wide(target));
+    // SAFETY: both strings are NUL-terminated and outlive the call
+    let result = unsafe {
+        ShellExecuteW(
+            std::ptr::null_mut(),
+            verb.as_ptr(),
+            file.as_ptr(),
+            std::ptr::null(),
+            std::ptr::null(),
+            SW_SHOWNORMAL,
+        )
+    };
+    // Values up to 32 are errors
+    if result as usize <= 32 {
+        log::warn!("Failed to open {:?} (error {})", target, result as usize);
+    }
+}
+
+/// Open `path` in `$EDITOR` (or `vi`) at `line` and `column`, in a new
+/// window. A relative `path` starts from `directory`, where the shell is.
+#[cfg(not(target_arch = "wasm32"))]
+fn edit_file(path: &str, line: Option<usize>, column: Option<usize>, directory: Option<&str>) {
+    use std::path::Path;
+
+    let path = match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => Path::new(&home).join(rest),
+        // Joining an absolute path keeps it as it is
+        _ => Path::new(directory.unwrap_or("")).join(path),
+    };
+    let editor = std::env::var("EDITOR")
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    let program = editor.split_whitespace().next().unwrap_or("vi");
+    let args = crate::hints::editor_args(program, &path.to_string_lossy(), line, column);
+
+    match std::env::current_exe() {
+        Ok(exe) => {
+            let mut command = std::process::Command::new(exe);
+            command.arg("-e");
+            // Like git, let the shell split the editor's arguments
+            #[cfg(unix)]
+            command
+                .args(["sh", "-c", &format!("{} \"$@\"", editor), "sh"])
+                .args(args);
+            #[cfg(not(unix))]
+            command.args(editor.split_whitespace()).args(args);
+            spawn_detached(&mut command);
+        }
+        Err(e) => log::warn!("Can't find the executable for a new window: {}", e),
+    }
+}
+
+/// Play a sound file with the platform's player, in the background.
+#[cfg(not(target_arch = "wasm32"))]
+fn play_sound(file: &str) {
+    use std::process::Command;
+
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new("afplay");
+    #[cfg(windows)]
+    let mut command = {
+        let mut command = Command::new("powershell");
+        command.args([
+            "-NoProfile",
+            "-Command",
+            "(New-Object Media.SoundPlayer $args[0]).PlaySync()",
+        ]);
+        command
+    };
+    #[cfg(not(any(target_os = "macos", windows)))]
+    let mut command = Command::new("paplay");
+
+    spawn_detached(command.arg(file));
+}
+
+/// Save a command's output in a new file, readable only by the user, to
+/// open it in another window. The file is left for the pager or editor.
+#[cfg(not(target_arch = "wasm32"))]
+fn output_file(text: &str) -> std::io::Result<std::path::PathBuf> {
+    use std::io::Write;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "adamant-output-{}-{}.txt",
+        std::process::id(),
+        COUNT.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(&path)?;
+    file.write_all(text.as_bytes())?;
+    file.write_all(b"\n")?;
+    Ok(path)
+}
+
+/// Draw `renderer`'s frames through `effects`. Those that fail to build
+/// are left out.
+fn apply_effects(renderer: &mut Renderer, effects: &Effects) {
+    if let Some(source) = &effects.shader
+        && let Err(e) = renderer.set_shader(Some(source))
+    {
+        log::warn!("Can't use the shader: {}", e);
+    }
+    if let Some(crt) = &effects.crt
+        && let Err(e) = renderer.set_crt(Some(crt))
+    {
+        log::warn!("Can't use the CRT effect: {}", e);
+    }
+    if let Err(e) = renderer.set_color_filter(effects.color_filter) {
+        log::warn!("Can't use the color filter: {}", e);
     }
 }
 
-impl Default for App {
-    fn default() -> Self {
-        Self::new()
+/// Start `command` in the background and reap it when it exits.
+#[cfg(not(target_arch = "wasm32"))]
+fn spawn_detached(command: &mut std::process::Command) {
+    match command.spawn() {
+        Ok(mut child) => drop(std::thread::spawn(move || child.wait())),
+        Err(e) => log::warn!("Failed to run {:?}: {}", command.get_program(), e),
     }
 }
 
-impl ApplicationHandler for App {
+impl ApplicationHandler<AppEvent> for App {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         log::info!("Application resumed, creating window...");
 
+        #[cfg(target_os = "macos")]
+        if self.window.is_none() {
+            let proxy = self.proxy.clone();
+            crate::menu::install(&self.config, move |action| {
+                let _ = proxy.send_event(AppEvent::Menu(action));
+            });
+        }
+
         // Create window attributes
-        let window_attributes = Window::default_attributes()
+        #[allow(unused_mut)]
+        let mut window_attributes = Window::default_attributes()
             .with_title("Adamant")
             .with_inner_size(PhysicalSize::new(1280, 720))
-            .with_transparent(true);
+            .with_transparent(self.config.colors.background_opacity < 1.0)
+            .with_decorations(self.config.decorations);
+        #[cfg(target_os = "macos")]
+        {
+            use crate::config::OptionAsAlt;
+            use winit::platform::macos::{OptionAsAlt as MacOptionAsAlt, WindowAttributesExtMacOS};
+
+            let option_as_alt = match self.config.option_as_alt {
+                OptionAsAlt::None => MacOptionAsAlt::None,
+                OptionAsAlt::Left => MacOptionAsAlt::OnlyLeft,
+                OptionAsAlt::Right => MacOptionAsAlt::OnlyRight,
+                OptionAsAlt::Both => MacOptionAsAlt::Both,
+            };
+            window_attributes = window_attributes.with_option_as_alt(option_as_alt);
+        }
+        let desktop = &self.config.desktop;
+        if let Some(path) = desktop.icon {
+            match crate::desktop::load_icon(std::path::Path::new(path)) {
+                Ok(icon) => {
+                    #[cfg(windows)]
+                    {
+                        use winit::platform::windows::WindowAttributesExtWindows;
+                        window_attributes = window_attributes.with_taskbar_icon(Some(icon.clone()));
+                    }
+                    window_attributes = window_attributes.with_window_icon(Some(icon));
+                }
+                Err(e) => log::warn!("Can't load the window icon {}: {}", path, e),
+            }
+        }
+        #[cfg(all(
+            unix,
+            not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+        ))]
+        {
+            use winit::platform::startup_notify::{
+                EventLoopExtStartupNotify, WindowAttributesExtStartupNotify,
+            };
+            // Sets the Wayland app-id too
+            use winit::platform::x11::WindowAttributesExtX11;
+
+            window_attributes = window_attributes.with_name(desktop.app_id, desktop.app_id);
+            if desktop.startup_notify
+                && let Some(token) = event_loop.read_token_from_env()
+            {
+                window_attributes = window_attributes.with_activation_token(token);
+            }
+        }
+        #[cfg(windows)]
+        {
+            use winit::platform::windows::WindowAttributesExtWindows;
+            window_attributes = window_attributes.with_class_name(desktop.app_id);
+        }
+
+        // The dropdown window starts shown, across the top of the primary
+        // monitor
+        if let Some(dropdown) = &self.dropdown {
+            let monitor = event_loop
+                .primary_monitor()
+                .or_else(|| event_loop.available_monitors().next());
+            if let Some(monitor) = monitor {
+                let (position, size) = dropdown.frame((monitor.position(), monitor.size()));
+                window_attributes = window_attributes
+                    .with_position(position)
+                    .with_inner_size(size);
+            }
+            window_attributes = window_attributes
+                .with_decorations(false)
+                .with_window_level(WindowLevel::AlwaysOnTop);
+        }
+        // The window opens where it was closed last time, if remembered,
+        // then in the configured mode (or the saved one), with the
+        // windowed size underneath to go back to
+        #[allow(unused_mut)]
+        let mut mode = self.config.window_mode;
+        #[allow(unused_mut)]
+        let mut monitor = event_loop
+            .primary_monitor()
+            .or_else(|| event_loop.available_monitors().next());
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(geometry) = self.load_geometry() {
+            let monitors: Vec<_> = event_loop.available_monitors().collect();
+            let areas: Vec<_> = monitors
+                .iter()
+                .map(|monitor| (monitor.name(), (monitor.position(), monitor.size())))
+                .collect();
+            let position = geometry.position_on(&areas);
+            if let Some(position) = position {
+                window_attributes = window_attributes.with_position(position);
+            }
+            if let Some(saved) = monitors
+                .into_iter()
+                .find(|monitor| monitor.name().is_some() && monitor.name() == geometry.monitor)
+            {
+                monitor = Some(saved);
+            }
+            window_attributes = window_attributes.with_inner_size(geometry.size);
+            if mode == WindowMode::Windowed {
+                mode = geometry.mode;
+            }
+            if mode != WindowMode::Windowed {
+                self.windowed = Some((position, geometry.size));
+            }
+        }
+        match mode {
+            WindowMode::Windowed => {}
+            WindowMode::Maximized => window_attributes = window_attributes.with_maximized(true),
+            mode => {
+                window_attributes =
+                    window_attributes.with_fullscreen(Some(fullscreen(mode, monitor)));
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        if let Some(web) = &self.web {
+            use winit::platform::web::WindowAttributesExtWebSys;
+            window_attributes = window_attributes.with_canvas(Some(web.canvas.clone()));
+        }
+        // AccessKit has to be attached before the window first shows
+        #[cfg(not(target_arch = "wasm32"))]
+        let accessible = self.a11y.is_some() && self.a11y_adapter.is_none();
+        #[cfg(not(target_arch = "wasm32"))]
+        if accessible {
+            window_attributes = window_attributes.with_visible(false);
+        }
 
         // Create the window
-        let window = Arc::new(
-            event_loop
-                .create_window(window_attributes)
-                .expect("Failed to create window"),
-        );
+        let window = match event_loop.create_window(window_attributes) {
+            Ok(window) => Arc::new(window),
+            Err(e) => return self.fail(event_loop, e.into()),
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        if accessible {
+            self.a11y_adapter = Some(accesskit_winit::Adapter::with_event_loop_proxy(
+                event_loop,
+                &window,
+                self.proxy.clone(),
+            ));
+            window.set_visible(true);
+        }
+
+        // Composed input (CJK and others) arrives as `WindowEvent::Ime`
+        window.set_ime_allowed(true);
+        self.window = Some(Arc::clone(&window));
 
         // Initialize the renderer
-        // TODO: This blocks - consider moving to a separate task for smoother startup
-        let renderer = pollster::block_on(Renderer::new(Arc::clone(&window)))
-            .expect("Failed to create renderer");
+        if let Err(e) = self.init_renderer(window) {
+            self.fail(event_loop, e);
+        }
+    }
 
-        self.window = Some(window);
-        self.renderer = Some(renderer);
+    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+        self.paste_dropped();
+    }
 
-        log::info!("Window and renderer initialized");
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.save_geometry();
+    }
+
+    fn new_events(&mut self, event_loop: &ActiveEventLoop, cause: StartCause) {
+        // The background frame timer fired
+        if let StartCause::ResumeTimeReached { .. } = cause {
+            event_loop.set_control_flow(ControlFlow::Wait);
+            if let Some(window) = &self.window {
+                window.request_redraw();
+            }
+        }
+    }
+
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: AppEvent) {
+        match event {
+            AppEvent::Wakeup => {
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            AppEvent::RendererReady(result) => {
+                let starting = self.widget.is_none();
+                match (*result).and_then(|renderer| self.attach_renderer(renderer)) {
+                    Err(e) => self.fail(event_loop, e),
+                    Ok(()) if starting && self.widget.is_some() => {
+                        if let Some(hook) = self.config.hooks.on_startup {
+                            self.run_hook(event_loop, hook());
+                        }
+                    }
+                    Ok(()) => {}
+                }
+            }
+            #[cfg(unix)]
+            AppEvent::Ipc(command, reply) => self.handle_ipc(event_loop, command, reply),
+            #[cfg(target_os = "macos")]
+            AppEvent::Menu(action) => self.run_action(event_loop, action),
+            #[cfg(not(target_arch = "wasm32"))]
+            AppEvent::Hotkey => self.toggle_dropdown(),
+            #[cfg(not(target_arch = "wasm32"))]
+            AppEvent::Accessibility(event) => self.accessibility_event(event),
+        }
     }
 
     fn window_event(
@@ -89,10 +2635,105 @@ impl ApplicationHandler for App {
         _window_id: WindowId,
         event: WindowEvent,
     ) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let (Some(adapter), Some(window)) = (&mut self.a11y_adapter, &self.window) {
+            adapter.process_event(window, &event);
+        }
+        if let WindowEvent::ModifiersChanged(modifiers) = &event {
+            self.modifiers = modifiers.state();
+        }
+        if let WindowEvent::KeyboardInput { event: key, .. } = &event
+            && (self.held_key(event_loop, key)
+                || !self.passes_through(key)
+                    && (self.key_binding(event_loop, key)
+                        || self.restart_shortcut(key)
+                        || self.window_shortcut(key)
+                        || self.rename_shortcut(key)
+                        || self.clipboard_shortcut(key)
+                        || self.hints_shortcut(key)
+                        || self.search_shortcut(key)
+                        || self.copy_mode_shortcut(key)
+                        || self.palette_shortcut(key)
+                        || self.plugin_shortcut(event_loop, key)))
+        {
+            return;
+        }
+
+        if let Some(widget) = &mut self.widget
+            && widget.handle_event(&event)
+            && let Some(window) = &self.window
+        {
+            window.request_redraw();
+            self.run_picked_hint();
+            self.sync_primary_selection();
+            self.run_command(event_loop);
+            self.run_activated(event_loop);
+            if let Some(action) = self
+                .widget
+                .as_mut()
+                .and_then(TerminalWidget::take_mouse_action)
+            {
+                self.run_hook(event_loop, vec![action]);
+            }
+            // Selections change through mouse events
+            self.track_accessibility();
+        }
+
         match event {
-            WindowEvent::CloseRequested => {
-                log::info!("Close requested, exiting...");
-                event_loop.exit();
+            WindowEvent::CloseRequested => self.request_close(event_loop),
+
+            // Touchpad gestures (macOS) and touchscreen fingers
+            WindowEvent::PinchGesture { delta, phase, .. } => {
+                self.pinch(Gesture::Scale(1.0 + delta as f32));
+                if matches!(phase, TouchPhase::Ended | TouchPhase::Cancelled) {
+                    self.pinch(Gesture::Ended);
+                }
+            }
+            WindowEvent::DoubleTapGesture { .. } => self.pinch(Gesture::Reset),
+            WindowEvent::Touch(touch) => {
+                if let Some(gesture) =
+                    self.touch_pinch
+                        .touch(touch.id, touch.phase, touch.location, Instant::now())
+                {
+                    self.pinch(gesture);
+                }
+            }
+
+            // One event per file; they are pasted together once the batch
+            // is handled
+            WindowEvent::DroppedFile(path) => self.dropped.push(path),
+
+            WindowEvent::ActivationTokenDone { serial, token } => {
+                if let Some((request, profile)) = self.activation_request
+                    && request == serial
+                {
+                    self.activation_request = None;
+                    new_window(Some(token.into_raw()), profile);
+                }
+            }
+
+            WindowEvent::Focused(focused) => {
+                self.focused = focused;
+                self.secure_input.set_focused(focused);
+                if focused {
+                    event_loop.set_control_flow(ControlFlow::Wait);
+                    if self.alert.take().is_some() {
+                        self.update_title();
+                    }
+                }
+                if let Some(hook) = self.config.hooks.on_focus_change {
+                    self.run_hook(event_loop, hook(focused));
+                }
+            }
+
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                log::debug!("Scale factor changed to {}", scale_factor);
+                if let Some(widget) = &mut self.widget {
+                    widget.set_scale_factor(scale_factor as f32 * self.zoom);
+                }
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
             }
 
             WindowEvent::Resized(new_size) => {
@@ -100,13 +2741,21 @@ impl ApplicationHandler for App {
                 if let Some(renderer) = &mut self.renderer {
                     renderer.resize(new_size);
                 }
+                if let Some(widget) = &mut self.widget {
+                    widget.resize(new_size.width, new_size.height);
+                }
+                if let Some(dropdown) = &mut self.dropdown {
+                    dropdown.resized(new_size.height);
+                }
             }
 
             WindowEvent::RedrawRequested => {
-                if let Some(renderer) = &mut self.renderer {
-                    // TODO: Update state here (Phase 3: read PTY output, update grid)
+                self.slide_dropdown();
+                // Update state: read PTY output into the grid
+                self.update(event_loop);
 
-                    match renderer.render() {
+                if let (Some(renderer), Some(widget)) = (&mut self.renderer, &mut self.widget) {
+                    match renderer.render(widget) {
                         Ok(_) => {}
                         Err(wgpu::SurfaceError::Lost) => {
                             log::warn!("Surface lost, reconfiguring...");
@@ -124,15 +2773,26 @@ impl ApplicationHandler for App {
                     }
                 }
 
-                // Request next frame
-                if let Some(window) = &self.window {
-                    window.request_redraw();
-                }
-            }
+                self.update_ime_area();
+                self.update_cursor_icon();
 
-            WindowEvent::KeyboardInput { event, .. } => {
-                // TODO: Phase 3 - Send key events to PTY
-                log::trace!("Key event: {:?}", event);
+                // Request next frame: right away while focused, sliding or
+                // gliding, otherwise at a lower rate
+                if self.focused
+                    || self.dropdown.as_ref().is_some_and(Dropdown::is_sliding)
+                    || self
+                        .widget
+                        .as_ref()
+                        .is_some_and(TerminalWidget::is_scrolling)
+                {
+                    if let Some(window) = &self.window {
+                        window.request_redraw();
+                    }
+                } else {
+                    event_loop.set_control_flow(ControlFlow::WaitUntil(
+                        Instant::now() + UNFOCUSED_FRAME_INTERVAL,
+                    ));
+                }
             }
 
             _ => {}
@@ -0,0 +1,86 @@
+//! Terminal I/O Backends
+//!
+//! A backend is whatever sits on the other side of the terminal: the bytes
+//! the program writes are fed to the emulator, keystrokes are sent back.
+//! The widget only talks to the [`Backend`] trait, so the same frontend can
+//! drive a local shell or a remote one.
+//!
+//! | Backend | Feature | Transport |
+//! |---------|---------|-----------|
+//! | [`PtyBackend`] | `pty` | Local pseudo-terminal, reader thread |
+//! | [`SessionBackend`] | Unix | Socket to a detachable session server |
+//! | [`ReplayBackend`] | `gui`, native | A recorded typescript, played at its pace |
+//! | [`WebSocketBackend`] | `web` (wasm32) | Websocket to a PTY bridge server |
+//!
+//! Backends deliver output asynchronously: they buffer it internally and
+//! call the waker passed to their constructor, and the owner drains it with
+//! [`Backend::poll`] from its event loop.
+
+#[cfg(feature = "pty")]
+mod pty;
+#[cfg(all(feature = "gui", not(target_arch = "wasm32")))]
+mod replay;
+#[cfg(unix)]
+mod session;
+#[cfg(all(feature = "web", target_arch = "wasm32"))]
+mod websocket;
+
+#[cfg(feature = "pty")]
+pub use self::pty::PtyBackend;
+#[cfg(all(feature = "gui", not(target_arch = "wasm32")))]
+pub use self::replay::ReplayBackend;
+#[cfg(unix)]
+pub use self::session::SessionBackend;
+#[cfg(all(feature = "web", target_arch = "wasm32"))]
+pub use self::websocket::WebSocketBackend;
+
+/// Something that happened on the backend side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackendEvent {
+    /// Bytes for the terminal to interpret.
+    Output(Vec<u8>),
+    /// The program exited or the connection closed, with the program's
+    /// exit code when known.
+    Exited(Option<u32>),
+}
+
+/// The program end of a terminal.
+pub trait Backend {
+    /// Take the next pending event without blocking.
+    fn poll(&mut self) -> Option<BackendEvent>;
+
+    /// Send input (keystrokes, paste, query responses) to the program.
+    /// It must not block: backends queue what the program isn't ready for.
+    fn write(&mut self, bytes: &[u8]);
+
+    /// Bytes written that the program hasn't been given yet, so callers
+    /// can hold back more. Backends that don't queue return 0.
+    fn pending_input(&self) -> usize {
+        0
+    }
+
+    /// Tell the program the terminal now has `cols` x `rows` cells.
+    fn resize(&mut self, cols: u16, rows: u16);
+
+    /// Programs running in the foreground besides the shell, which closing
+    /// the terminal would kill. Backends that can't tell return nothing.
+    fn foreground_processes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Programs in the foreground, the shell included when it is at its
+    /// prompt. Backends that can't tell return nothing.
+    fn foreground_job(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// Whether the process named `name` (see [`Backend::foreground_job`]) runs
+/// `program`. Programs that rename themselves keep their name before a
+/// colon (`tmux: client`, `sshd: user@pts/0`).
+pub fn is_program(name: &str, program: &str) -> bool {
+    match name.strip_prefix(program) {
+        Some(rest) => rest.is_empty() || rest.starts_with(':'),
+        None => false,
+    }
+}
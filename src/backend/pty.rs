@@ -0,0 +1,131 @@
+//! Local shell backend.
+
+use std::io::Read;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::{Backend, BackendEvent};
+use crate::config::ShellConfig;
+use crate::error::Result;
+use crate::pty::Pty;
+
+/// How long an exited child's output may keep coming before the exit is
+/// reported anyway.
+pub const EXIT_GRACE: Duration = Duration::from_millis(200);
+
+/// Runs the user's shell on a local PTY.
+///
+/// A "pty-reader" thread blocks on the PTY and forwards output through a
+/// channel, calling the waker after every chunk. Input goes through the
+/// PTY's own writer thread. A "pty-exit" thread reports the child's exit
+/// once the reader has seen the end of its output, or after
+/// [`EXIT_GRACE`] if a background job holds the PTY open.
+pub struct PtyBackend {
+    pty: Pty,
+    output: Receiver<BackendEvent>,
+}
+
+impl PtyBackend {
+    /// Spawn the configured shell on a PTY of `cols` x `rows`.
+    pub fn spawn(
+        shell: &ShellConfig,
+        cols: u16,
+        rows: u16,
+        waker: impl Fn() + Send + 'static,
+    ) -> Result<Self> {
+        Self::start(Pty::spawn(shell, cols, rows)?, waker)
+    }
+
+    /// Run `program` with `args` instead of the shell, in the environment
+    /// configured in `shell`.
+    pub fn spawn_program(
+        program: &str,
+        args: &[String],
+        shell: &ShellConfig,
+        cols: u16,
+        rows: u16,
+        waker: impl Fn() + Send + 'static,
+    ) -> Result<Self> {
+        Self::start(Pty::spawn_program(program, args, shell, cols, rows)?, waker)
+    }
+
+    fn start(pty: Pty, waker: impl Fn() + Send + 'static) -> Result<Self> {
+        let mut reader = pty.reader()?;
+        let (sender, output) = mpsc::channel();
+        // Disconnected when the reader is done
+        let (eof, read_all) = mpsc::channel::<()>();
+        // Both threads wake the owner; the waker only has to be `Send`
+        let waker = Arc::new(Mutex::new(waker));
+
+        let exit = pty.exit_watch();
+        let exited = sender.clone();
+        let exit_waker = Arc::clone(&waker);
+        std::thread::Builder::new()
+            .name("pty-exit".into())
+            .spawn(move || {
+                let code = exit.wait();
+                let _ = read_all.recv_timeout(EXIT_GRACE);
+                if exited.send(BackendEvent::Exited(Some(code))).is_ok() {
+                    wake(&exit_waker);
+                }
+            })?;
+
+        std::thread::Builder::new()
+            .name("pty-reader".into())
+            .spawn(move || {
+                let _eof = eof;
+                let mut buf = vec![0u8; 64 * 1024];
+                loop {
+                    match reader.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => {
+                            if sender
+                                .send(BackendEvent::Output(buf[..n].to_vec()))
+                                .is_err()
+                            {
+                                return;
+                            }
+                            wake(&waker);
+                        }
+                    }
+                }
+            })?;
+
+        Ok(Self { pty, output })
+    }
+}
+
+fn wake(waker: &Mutex<impl Fn()>) {
+    (waker.lock().unwrap_or_else(|e| e.into_inner()))();
+}
+
+impl Backend for PtyBackend {
+    fn poll(&mut self) -> Option<BackendEvent> {
+        self.output.try_recv().ok()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        if let Err(e) = self.pty.write(bytes) {
+            log::warn!("Failed to write to PTY: {}", e);
+        }
+    }
+
+    fn pending_input(&self) -> usize {
+        self.pty.pending_input()
+    }
+
+    fn resize(&mut self, cols: u16, rows: u16) {
+        if let Err(e) = self.pty.resize(cols, rows) {
+            log::warn!("Failed to resize PTY: {}", e);
+        }
+    }
+
+    fn foreground_processes(&self) -> Vec<String> {
+        self.pty.foreground_processes()
+    }
+
+    fn foreground_job(&self) -> Vec<String> {
+        self.pty.foreground_job()
+    }
+}
@@ -0,0 +1,54 @@
+//! Replay backend.
+
+use std::sync::mpsc::{self, Receiver};
+
+use super::{Backend, BackendEvent};
+use crate::error::Result;
+use crate::recording::Typescript;
+
+/// Plays a recording (see `crate::recording`) as if a program wrote it.
+///
+/// A "replay" thread sends each chunk after its delay divided by `speed`
+/// (all at once with a speed of 0) and calls the waker, then reports an
+/// exit. Input goes nowhere.
+pub struct ReplayBackend {
+    output: Receiver<BackendEvent>,
+}
+
+impl ReplayBackend {
+    /// Start playing `typescript` at `speed` times the recorded pace.
+    pub fn start(
+        typescript: Typescript,
+        speed: f32,
+        waker: impl Fn() + Send + 'static,
+    ) -> Result<Self> {
+        let (sender, output) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("replay".into())
+            .spawn(move || {
+                for (delay, chunk) in typescript.chunks {
+                    if speed > 0.0 {
+                        std::thread::sleep(delay.div_f32(speed));
+                    }
+                    // The window closed
+                    if sender.send(BackendEvent::Output(chunk)).is_err() {
+                        return;
+                    }
+                    waker();
+                }
+                let _ = sender.send(BackendEvent::Exited(Some(0)));
+                waker();
+            })?;
+        Ok(Self { output })
+    }
+}
+
+impl Backend for ReplayBackend {
+    fn poll(&mut self) -> Option<BackendEvent> {
+        self.output.try_recv().ok()
+    }
+
+    fn write(&mut self, _bytes: &[u8]) {}
+
+    fn resize(&mut self, _cols: u16, _rows: u16) {}
+}
@@ -0,0 +1,78 @@
+//! Detachable session backend.
+
+use std::os::unix::net::UnixStream;
+use std::sync::mpsc::{self, Receiver};
+
+use super::{Backend, BackendEvent};
+use crate::error::{AdamantError, Result};
+use crate::session::{self, Frame};
+
+/// Attaches to a session server (see `crate::session`).
+///
+/// A "session-reader" thread turns the server's frames into events and
+/// calls the waker after each. Dropping the backend detaches; the
+/// session's programs keep running.
+pub struct SessionBackend {
+    stream: UnixStream,
+    output: Receiver<BackendEvent>,
+}
+
+impl SessionBackend {
+    /// Attach to the running session `name` with a grid of `cols` x `rows`.
+    pub fn attach(
+        name: &str,
+        cols: u16,
+        rows: u16,
+        waker: impl Fn() + Send + 'static,
+    ) -> Result<Self> {
+        let mut stream = session::connect(name).map_err(|e| {
+            AdamantError::Backend(format!("can't attach to session {:?}: {}", name, e))
+        })?;
+        Frame::Resize { cols, rows }.write(&mut stream)?;
+        let mut reader = stream.try_clone()?;
+        let (sender, output) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("session-reader".into())
+            .spawn(move || {
+                let event = loop {
+                    match Frame::read(&mut reader) {
+                        Ok(Some(Frame::Output(bytes))) => {
+                            if sender.send(BackendEvent::Output(bytes)).is_err() {
+                                return;
+                            }
+                            waker();
+                        }
+                        Ok(Some(Frame::Exited(code))) => break BackendEvent::Exited(code),
+                        Ok(Some(frame)) => {
+                            log::debug!("Unexpected frame from the session: {:?}", frame)
+                        }
+                        // The server went away, or another window took over
+                        Ok(None) | Err(_) => break BackendEvent::Exited(None),
+                    }
+                };
+                let _ = sender.send(event);
+                waker();
+            })?;
+        Ok(Self { stream, output })
+    }
+
+    fn send(&mut self, frame: Frame) {
+        if let Err(e) = frame.write(&mut self.stream) {
+            log::warn!("Failed to write to the session: {}", e);
+        }
+    }
+}
+
+impl Backend for SessionBackend {
+    fn poll(&mut self) -> Option<BackendEvent> {
+        self.output.try_recv().ok()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.send(Frame::Input(bytes.to_vec()));
+    }
+
+    fn resize(&mut self, cols: u16, rows: u16) {
+        self.send(Frame::Resize { cols, rows });
+    }
+}
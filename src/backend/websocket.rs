@@ -0,0 +1,159 @@
+//! Remote shell backend for the browser.
+//!
+//! Connects to a websocket bridge that runs the shell on a PTY server-side.
+//! The protocol is deliberately minimal so a bridge is a few lines in any
+//! language:
+//!
+//! - Binary frames carry terminal data in both directions.
+//! - Text frames from the server are treated as UTF-8 terminal data.
+//! - Text frames from the client are control messages; the only one is
+//!   `{"type":"resize","cols":80,"rows":24}`.
+//!
+//! Closing the socket counts as the program exiting.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use wasm_bindgen::JsCast;
+use wasm_bindgen::closure::Closure;
+use web_sys::{BinaryType, CloseEvent, Event, MessageEvent, WebSocket};
+
+use super::{Backend, BackendEvent};
+use crate::error::{AdamantError, Result};
+
+/// State shared between the backend and the socket callbacks.
+#[derive(Default)]
+struct Shared {
+    events: VecDeque<BackendEvent>,
+    open: bool,
+    /// Input typed before the connection was established
+    pending_input: Vec<u8>,
+    /// Latest size requested before the connection was established
+    pending_size: Option<(u16, u16)>,
+}
+
+/// Talks to a PTY bridge over a websocket.
+pub struct WebSocketBackend {
+    socket: WebSocket,
+    shared: Rc<RefCell<Shared>>,
+    // Kept alive for as long as the socket may call them
+    _on_open: Closure<dyn FnMut(Event)>,
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+    _on_close: Closure<dyn FnMut(CloseEvent)>,
+}
+
+impl WebSocketBackend {
+    /// Connect to the bridge at `url` (`ws://` or `wss://`).
+    ///
+    /// `waker` is called whenever output arrives or the connection closes.
+    pub fn connect(url: &str, waker: impl Fn() + 'static) -> Result<Self> {
+        let socket = WebSocket::new(url)
+            .map_err(|e| AdamantError::Backend(format!("cannot open {}: {:?}", url, e)))?;
+        socket.set_binary_type(BinaryType::Arraybuffer);
+
+        let shared = Rc::new(RefCell::new(Shared::default()));
+        let waker = Rc::new(waker);
+
+        let on_open = {
+            let shared = Rc::clone(&shared);
+            let socket = socket.clone();
+            Closure::<dyn FnMut(Event)>::new(move |_| {
+                let mut shared = shared.borrow_mut();
+                shared.open = true;
+                let input = std::mem::take(&mut shared.pending_input);
+                if !input.is_empty() {
+                    let _ = socket.send_with_u8_array(&input);
+                }
+                if let Some((cols, rows)) = shared.pending_size.take() {
+                    let _ = socket.send_with_str(&resize_message(cols, rows));
+                }
+            })
+        };
+
+        let on_message = {
+            let shared = Rc::clone(&shared);
+            let waker = Rc::clone(&waker);
+            Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+                let data = event.data();
+                let bytes = if let Some(buffer) = data.dyn_ref::<js_sys::ArrayBuffer>() {
+                    js_sys::Uint8Array::new(buffer).to_vec()
+                } else if let Some(text) = data.as_string() {
+                    text.into_bytes()
+                } else {
+                    return;
+                };
+                shared
+                    .borrow_mut()
+                    .events
+                    .push_back(BackendEvent::Output(bytes));
+                waker();
+            })
+        };
+
+        let on_close = {
+            let shared = Rc::clone(&shared);
+            Closure::<dyn FnMut(CloseEvent)>::new(move |_| {
+                let mut shared = shared.borrow_mut();
+                shared.open = false;
+                shared.events.push_back(BackendEvent::Exited(None));
+                drop(shared);
+                waker();
+            })
+        };
+
+        socket.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+        socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+
+        Ok(Self {
+            socket,
+            shared,
+            _on_open: on_open,
+            _on_message: on_message,
+            _on_close: on_close,
+        })
+    }
+}
+
+impl Backend for WebSocketBackend {
+    fn poll(&mut self) -> Option<BackendEvent> {
+        self.shared.borrow_mut().events.pop_front()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        let mut shared = self.shared.borrow_mut();
+        if !shared.open {
+            shared.pending_input.extend_from_slice(bytes);
+            return;
+        }
+        if let Err(e) = self.socket.send_with_u8_array(bytes) {
+            log::warn!("Failed to send to websocket: {:?}", e);
+        }
+    }
+
+    fn resize(&mut self, cols: u16, rows: u16) {
+        let mut shared = self.shared.borrow_mut();
+        if !shared.open {
+            shared.pending_size = Some((cols, rows));
+            return;
+        }
+        if let Err(e) = self.socket.send_with_str(&resize_message(cols, rows)) {
+            log::warn!("Failed to send resize to websocket: {:?}", e);
+        }
+    }
+}
+
+impl Drop for WebSocketBackend {
+    fn drop(&mut self) {
+        // The closures die with us; make sure the socket can't call them.
+        self.socket.set_onopen(None);
+        self.socket.set_onmessage(None);
+        self.socket.set_onclose(None);
+        let _ = self.socket.close();
+    }
+}
+
+fn resize_message(cols: u16, rows: u16) -> String {
+    format!(r#"{{"type":"resize","cols":{},"rows":{}}}"#, cols, rows)
+}
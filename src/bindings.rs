@@ -0,0 +1,377 @@
+//! Key Bindings
+//!
+//! `Config::key_bindings` maps keys to what hooks can ask for (a palette
+//! command, text to type, a program to run). A binding can be a sequence
+//! of keys, tmux-style: a leader such as Ctrl+A, then `c` or `|`. While a
+//! sequence is pending its keys don't reach the program, and the host
+//! shows which keys can come next ([`KeyBindings::next_keys`]). A key
+//! that continues no binding drops the sequence, and so does waiting
+//! longer than `Config::key_sequence_timeout_ms`.
+//!
+//! [`MouseBindings`] do the same for `MouseConfig::bindings`: buttons and
+//! the wheel with modifiers, such as Ctrl+Right to paste or Shift+Wheel to
+//! scroll sideways.
+//!
+//! ```
+//! use adamant::bindings::{KeyBindings, KeyOutcome};
+//! use adamant::config::{HookAction, KeyBinding};
+//! use web_time::{Duration, Instant};
+//! use winit::keyboard::{Key, ModifiersState};
+//!
+//! let zoom = HookAction::Command("Zoom in".into());
+//! let mut bindings = KeyBindings::new(
+//!     &[KeyBinding { keys: "Ctrl+A +", action: zoom.clone() }],
+//!     Duration::from_secs(1),
+//! )
+//! .unwrap();
+//! let now = Instant::now();
+//! let a = Key::Character("a".into());
+//! assert_eq!(bindings.key(ModifiersState::CONTROL, &a, now), KeyOutcome::Pending);
+//! assert_eq!(bindings.next_keys(), [("+", "Zoom in".to_string())]);
+//! // `+` is typed with Shift on most layouts
+//! let plus = Key::Character("+".into());
+//! assert_eq!(bindings.key(ModifiersState::SHIFT, &plus, now), KeyOutcome::Run(zoom));
+//! assert_eq!(bindings.key(ModifiersState::SHIFT, &plus, now), KeyOutcome::Unbound);
+//! ```
+
+use web_time::{Duration, Instant};
+use winit::event::MouseButton;
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+
+use crate::config::{HookAction, KeyBinding, MouseBinding, MouseCommand};
+use crate::error::{AdamantError, Result};
+use crate::hotkey::{self, Hotkey};
+
+/// One key of a binding.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Chord {
+    /// Modifiers and a key, written like `Ctrl+A` (see [`Hotkey::parse`])
+    Hotkey(Hotkey),
+    /// A character other than a letter or digit, typed with whatever
+    /// Shift or AltGr it takes on the layout: `|`, `%`, `"`
+    Char(char),
+}
+
+impl Chord {
+    /// Parse one key of a binding.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if !c.is_alphanumeric() => Ok(Self::Char(c)),
+            _ => Hotkey::parse(text).map(Self::Hotkey),
+        }
+    }
+
+    /// Whether `key` pressed with `modifiers` is this chord.
+    pub fn matches(&self, modifiers: ModifiersState, key: &Key) -> bool {
+        match self {
+            Self::Hotkey(hotkey) => hotkey.matches(modifiers, key),
+            Self::Char(c) => {
+                let typed = modifiers - ModifiersState::SHIFT;
+                // AltGr comes as Ctrl+Alt on Windows
+                (typed.is_empty() || typed == ModifiersState::CONTROL | ModifiersState::ALT)
+                    && matches!(key, Key::Character(text) if text.chars().eq([*c]))
+            }
+        }
+    }
+}
+
+/// What a key press meant to the bindings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeyOutcome {
+    /// No binding starts with it: it goes on to the shortcuts and the
+    /// program
+    Unbound,
+    /// It started or continued a sequence, which waits for the next key
+    Pending,
+    /// It finished a binding
+    Run(HookAction),
+    /// It continues no binding of the pending sequence, which is dropped
+    /// with the key
+    Cancelled,
+}
+
+/// The configured bindings and the sequence being typed.
+#[derive(Clone, Debug, Default)]
+pub struct KeyBindings {
+    /// Each binding's keys, their text and its action
+    bindings: Vec<(Vec<Chord>, Vec<&'static str>, HookAction)>,
+    timeout: Duration,
+    /// Keys of the pending sequence pressed so far
+    pressed: usize,
+    /// Bindings starting with those keys
+    candidates: Vec<usize>,
+    /// When the pending sequence is dropped
+    deadline: Option<Instant>,
+    /// What the last key pressed ran, for it held down and repeating
+    held: Option<HookAction>,
+}
+
+impl KeyBindings {
+    /// Parse the bindings. A key that doesn't parse, or a binding that is
+    /// the start of another (which would never wait for the rest), is a
+    /// configuration error.
+    pub fn new(bindings: &[KeyBinding], timeout: Duration) -> Result<Self> {
+        let error =
+            |keys: &str, e: String| AdamantError::Config(format!("key binding {:?}: {}", keys, e));
+        let mut parsed = Vec::new();
+        for binding in bindings {
+            let texts: Vec<&'static str> = binding.keys.split_whitespace().collect();
+            if texts.is_empty() {
+                return Err(error(binding.keys, "no keys".into()));
+            }
+            let chords = (texts.iter())
+                .map(|text| Chord::parse(text))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| error(binding.keys, e))?;
+            parsed.push((chords, texts, binding.action.clone()));
+        }
+        for (i, (chords, ..)) in parsed.iter().enumerate() {
+            if let Some(j) = (0..parsed.len()).find(|&j| j != i && parsed[j].0.starts_with(chords))
+            {
+                return Err(error(
+                    bindings[i].keys,
+                    format!("it is the start of {:?}", bindings[j].keys),
+                ));
+            }
+        }
+        Ok(Self {
+            bindings: parsed,
+            timeout,
+            ..Self::default()
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bindings.is_empty()
+    }
+
+    /// Take a key pressed at `now`.
+    pub fn key(&mut self, modifiers: ModifiersState, key: &Key, now: Instant) -> KeyOutcome {
+        self.expire(now);
+        // Shift and the like are pressed on the way to the next key
+        if is_modifier(key) {
+            return if self.is_pending() {
+                KeyOutcome::Pending
+            } else {
+                KeyOutcome::Unbound
+            };
+        }
+        self.held = None;
+        let pending = self.is_pending();
+        let candidates: Vec<usize> = if pending {
+            self.candidates.clone()
+        } else {
+            (0..self.bindings.len()).collect()
+        };
+        let matching: Vec<usize> = candidates
+            .into_iter()
+            .filter(|&i| {
+                let chord = self.bindings[i].0[self.pressed];
+                chord.matches(modifiers, key)
+            })
+            .collect();
+        let Some(&first) = matching.first() else {
+            self.reset();
+            return if pending {
+                KeyOutcome::Cancelled
+            } else {
+                KeyOutcome::Unbound
+            };
+        };
+        self.pressed += 1;
+        // No binding is the start of another, so a finished one is alone
+        if self.bindings[first].0.len() == self.pressed {
+            let action = self.bindings[first].2.clone();
+            self.reset();
+            self.held = Some(action.clone());
+            return KeyOutcome::Run(action);
+        }
+        self.candidates = matching;
+        self.deadline = Some(now + self.timeout);
+        KeyOutcome::Pending
+    }
+
+    /// Take the last key pressed, held down and repeating at `now`. It
+    /// continues no sequence: a pending one keeps waiting
+    /// ([`KeyOutcome::Pending`]), and a key that ran a binding gives its
+    /// action again, which the caller may run or not.
+    pub fn repeat(&mut self, now: Instant) -> KeyOutcome {
+        self.expire(now);
+        if self.is_pending() {
+            return KeyOutcome::Pending;
+        }
+        match &self.held {
+            Some(action) => KeyOutcome::Run(action.clone()),
+            None => KeyOutcome::Unbound,
+        }
+    }
+
+    /// Whether a sequence waits for its next key.
+    pub fn is_pending(&self) -> bool {
+        self.deadline.is_some()
+    }
+
+    /// When the pending sequence is dropped if no key comes.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Drop the pending sequence if its time is up by `now`. Returns `true`
+    /// if it was.
+    pub fn expire(&mut self, now: Instant) -> bool {
+        if self.deadline.is_some_and(|deadline| now >= deadline) {
+            self.reset();
+            return true;
+        }
+        false
+    }
+
+    /// Drop the pending sequence.
+    pub fn reset(&mut self) {
+        self.pressed = 0;
+        self.candidates.clear();
+        self.deadline = None;
+    }
+
+    /// The keys that can continue the pending sequence, each with what
+    /// pressing it does: the binding's action, or `…` for a longer one.
+    pub fn next_keys(&self) -> Vec<(&'static str, String)> {
+        let mut keys: Vec<(&'static str, String)> = Vec::new();
+        for &i in &self.candidates {
+            let (chords, texts, action) = &self.bindings[i];
+            let text = texts[self.pressed];
+            let what = if chords.len() == self.pressed + 1 {
+                describe(action)
+            } else {
+                "…".to_string()
+            };
+            if !keys.iter().any(|(key, _)| *key == text) {
+                keys.push((text, what));
+            }
+        }
+        keys
+    }
+}
+
+/// What a mouse binding is for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MouseTrigger {
+    Button(MouseButton),
+    WheelUp,
+    WheelDown,
+    /// Either way of the wheel
+    Wheel,
+}
+
+/// A mouse trigger with the modifiers held.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MouseChord {
+    pub modifiers: ModifiersState,
+    pub trigger: MouseTrigger,
+}
+
+impl MouseChord {
+    /// Parse a chord written like `Ctrl+Right` or `Shift+Wheel`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let Some(trigger) = parts.pop().filter(|trigger| !trigger.is_empty()) else {
+            return Err(format!("{:?} has no button", text));
+        };
+        let mut modifiers = ModifiersState::empty();
+        for part in parts {
+            modifiers |= hotkey::parse_modifier(part, text)?;
+        }
+        let lower = trigger.to_ascii_lowercase();
+        let trigger = match lower.strip_suffix("click").unwrap_or(&lower) {
+            "left" => MouseTrigger::Button(MouseButton::Left),
+            "middle" => MouseTrigger::Button(MouseButton::Middle),
+            "right" => MouseTrigger::Button(MouseButton::Right),
+            "back" => MouseTrigger::Button(MouseButton::Back),
+            "forward" => MouseTrigger::Button(MouseButton::Forward),
+            "wheelup" => MouseTrigger::WheelUp,
+            "wheeldown" => MouseTrigger::WheelDown,
+            "wheel" => MouseTrigger::Wheel,
+            _ => return Err(format!("unknown button {:?} in {:?}", trigger, text)),
+        };
+        Ok(Self { modifiers, trigger })
+    }
+
+    /// Whether `trigger` is this chord's, a wheel turn matching `Wheel`.
+    fn covers(&self, trigger: MouseTrigger) -> bool {
+        self.trigger == trigger
+            || self.trigger == MouseTrigger::Wheel
+                && matches!(trigger, MouseTrigger::WheelUp | MouseTrigger::WheelDown)
+    }
+}
+
+/// The configured mouse bindings, parsed.
+#[derive(Clone, Debug, Default)]
+pub struct MouseBindings {
+    bindings: Vec<(MouseChord, MouseCommand)>,
+}
+
+impl MouseBindings {
+    /// Parse the bindings. A chord that doesn't parse is a configuration
+    /// error, and so is a plain left click, which selects text.
+    pub fn new(bindings: &[MouseBinding]) -> Result<Self> {
+        let bindings = bindings
+            .iter()
+            .map(|binding| {
+                let error = |e| AdamantError::Config(format!("mouse binding: {}", e));
+                let chord = MouseChord::parse(binding.mouse).map_err(error)?;
+                if chord.modifiers.is_empty()
+                    && chord.trigger == MouseTrigger::Button(MouseButton::Left)
+                {
+                    return Err(error("a plain left click selects text".into()));
+                }
+                Ok((chord, binding.command.clone()))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { bindings })
+    }
+
+    /// The command `trigger` runs with `modifiers` held: the one bound with
+    /// exactly those modifiers, otherwise the one bound without any. Of
+    /// the same chord bound twice, the first counts.
+    pub fn command(
+        &self,
+        modifiers: ModifiersState,
+        trigger: MouseTrigger,
+    ) -> Option<&MouseCommand> {
+        let bound = |modifiers: ModifiersState| {
+            (self.bindings.iter())
+                .find(|(chord, _)| chord.modifiers == modifiers && chord.covers(trigger))
+                .map(|(_, command)| command)
+        };
+        bound(modifiers).or_else(|| bound(ModifiersState::empty()))
+    }
+}
+
+/// How the key hints show `action`.
+pub fn describe(action: &HookAction) -> String {
+    match action {
+        HookAction::Command(name) => name.clone(),
+        HookAction::Input(text) => format!("Type {:?}", text),
+        HookAction::Notify(text) => format!("Show {:?}", text),
+        HookAction::Run(command) => format!("Run {}", command.join(" ")),
+    }
+}
+
+/// Whether `key` is a modifier on its own.
+fn is_modifier(key: &Key) -> bool {
+    matches!(
+        key,
+        Key::Named(
+            NamedKey::Shift
+                | NamedKey::Control
+                | NamedKey::Alt
+                | NamedKey::AltGraph
+                | NamedKey::Super
+                | NamedKey::Meta
+                | NamedKey::Hyper
+                | NamedKey::CapsLock
+                | NamedKey::NumLock
+        )
+    )
+}
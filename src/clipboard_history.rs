@@ -0,0 +1,198 @@
+//! Clipboard History
+//!
+//! The text copied in a window, most recent first ([`ClipboardHistory`]),
+//! and the picker (Ctrl+Shift+Y) that pastes an entry again. The picker
+//! filters like the command palette: typed characters have to appear in
+//! order, and between equal matches the more recent copy comes first.
+//!
+//! ```
+//! use adamant::clipboard_history::{ClipboardHistory, ClipboardPicker};
+//! use adamant::config::ClipboardHistoryConfig;
+//!
+//! let mut history = ClipboardHistory::new(&ClipboardHistoryConfig::default());
+//! history.push("cargo test".into());
+//! history.push("git status".into());
+//! history.push("cargo test".into());
+//! assert_eq!(history.entries(), ["cargo test", "git status"]);
+//!
+//! let mut picker = ClipboardPicker::new(history.entries().to_vec());
+//! picker.input('g');
+//! picker.input('s');
+//! assert_eq!(picker.selected(), Some("git status"));
+//! ```
+
+use crate::commands::fuzzy_score;
+use crate::config::ClipboardHistoryConfig;
+use crate::ui::{Element, Layer, LayerKind, Rect, Style};
+
+/// Recent copies, bounded in number and size.
+#[derive(Clone, Debug)]
+pub struct ClipboardHistory {
+    entries: Vec<String>,
+    enabled: bool,
+    max_entries: usize,
+    max_entry_bytes: usize,
+}
+
+impl ClipboardHistory {
+    pub fn new(config: &ClipboardHistoryConfig) -> Self {
+        Self {
+            entries: Vec::new(),
+            enabled: config.enabled,
+            max_entries: config.max_entries,
+            max_entry_bytes: config.max_entry_bytes,
+        }
+    }
+
+    /// Remember a copy. Copying an entry again moves it to the front;
+    /// blank text and text over the size limit aren't kept.
+    pub fn push(&mut self, text: String) {
+        if !self.enabled || text.trim().is_empty() || text.len() > self.max_entry_bytes {
+            return;
+        }
+        self.entries.retain(|entry| *entry != text);
+        self.entries.insert(0, text);
+        self.entries.truncate(self.max_entries);
+    }
+
+    /// The copies, most recent first.
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// The picker's query and the entries matching it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClipboardPicker {
+    entries: Vec<String>,
+    /// The start of each entry on one line, what is shown and matched
+    previews: Vec<String>,
+    query: String,
+    /// Indices into `entries` of the matches, best first
+    matches: Vec<usize>,
+    /// Index into `matches`
+    selected: usize,
+}
+
+impl ClipboardPicker {
+    /// Rows of matches shown at most.
+    const ROWS: usize = 12;
+    /// Characters of an entry shown and matched at most.
+    const PREVIEW_CHARS: usize = 256;
+
+    /// Pick from `entries`, most recent first.
+    pub fn new(entries: Vec<String>) -> Self {
+        let mut picker = Self {
+            previews: entries.iter().map(|entry| preview(entry)).collect(),
+            entries,
+            ..Self::default()
+        };
+        picker.filter();
+        picker
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// The highlighted entry.
+    pub fn selected(&self) -> Option<&str> {
+        let index = *self.matches.get(self.selected)?;
+        Some(&self.entries[index])
+    }
+
+    /// Type a character of the query.
+    pub fn input(&mut self, c: char) {
+        if !c.is_control() {
+            self.query.push(c);
+            self.filter();
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        if self.query.pop().is_some() {
+            self.filter();
+        }
+    }
+
+    /// Highlight the next match, wrapping around.
+    pub fn select_next(&mut self) {
+        if !self.matches.is_empty() {
+            self.selected = (self.selected + 1) % self.matches.len();
+        }
+    }
+
+    /// Highlight the previous match, wrapping around.
+    pub fn select_prev(&mut self) {
+        if !self.matches.is_empty() {
+            self.selected = (self.selected + self.matches.len() - 1) % self.matches.len();
+        }
+    }
+
+    /// Highlight the match with this entry index, as given to the ids of
+    /// [`layer`](Self::layer)'s elements.
+    pub fn select(&mut self, entry: usize) {
+        if let Some(position) = self.matches.iter().position(|&i| i == entry) {
+            self.selected = position;
+        }
+    }
+
+    /// Draw the picker at the top of a `screen_rows` x `screen_cols`
+    /// screen like the command palette, each entry on one line with its
+    /// line breaks shown as `⏎`. The matches' ids are their entry index.
+    pub fn layer(&self, screen_rows: usize, screen_cols: usize) -> Layer {
+        let cols = screen_cols.min(64);
+        let col = (screen_cols - cols) / 2;
+        let shown = Self::ROWS.min(screen_rows.saturating_sub(2));
+        let first = (self.selected + 1).saturating_sub(shown);
+
+        let mut elements = vec![Element::label(
+            Rect::new(0, col, 1, cols),
+            format!("paste> {}", self.query),
+            Style::PANEL,
+        )];
+        for (row, &index) in self.matches.iter().enumerate().skip(first).take(shown) {
+            let text = format!(" {}", self.previews[index]);
+            let rect = Rect::new(row - first + 1, col, 1, cols);
+            elements.push(Element::button(rect, text, Style::ITEM, index));
+        }
+        let mut layer = Layer::new(LayerKind::Menu, elements);
+        if let Some(&index) = self.matches.get(self.selected) {
+            layer.set_focus(index);
+        }
+        layer
+    }
+
+    /// Match the entries against the query, the more recent first between
+    /// equal scores.
+    fn filter(&mut self) {
+        let mut scored: Vec<(u32, usize)> = self
+            .previews
+            .iter()
+            .enumerate()
+            .filter_map(|(i, preview)| Some((fuzzy_score(&self.query, preview)?, i)))
+            .collect();
+        scored.sort_by_key(|&(score, i)| (std::cmp::Reverse(score), i));
+        self.matches = scored.into_iter().map(|(_, i)| i).collect();
+        self.selected = 0;
+    }
+}
+
+/// The start of `text` on one line: line breaks as `⏎`, other control
+/// characters as spaces.
+fn preview(text: &str) -> String {
+    text.trim_end_matches('\n')
+        .replace("\r\n", "\n")
+        .chars()
+        .take(ClipboardPicker::PREVIEW_CHARS)
+        .map(|c| match c {
+            '\n' => '\u{23CE}',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect()
+}
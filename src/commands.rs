@@ -0,0 +1,513 @@
+//! Command Palette
+//!
+//! Every action the application can run, in one list ([`commands`]), and
+//! the palette (Ctrl+Shift+P) that finds one by typing part of its name.
+//! Filtering is fuzzy: the typed characters have to appear in order, and
+//! matches at word starts and in runs rank higher, so `cpy` finds
+//! "Copy selection" and `sb` "Scroll to bottom".
+//!
+//! ```
+//! use adamant::commands::{Action, CommandPalette, commands};
+//! use adamant::config::Config;
+//!
+//! let mut palette = CommandPalette::new(commands(&Config::default()));
+//! for c in "paste".chars() {
+//!     palette.input(c);
+//! }
+//! assert_eq!(palette.selected(), Some(Action::Paste));
+//! ```
+
+use std::sync::Mutex;
+
+use crate::config::{ColorBlindness, ColorFilter, Config, GpuPreference, HookAction, Monitor};
+use crate::secure_input::SecureInput;
+use crate::ui::{Element, Layer, LayerKind, Rect, Style};
+
+/// Something the palette can run.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Copy the selection to the clipboard
+    Copy,
+    /// Copy the output of the last command that finished
+    CopyLastOutput,
+    /// Open the last command's output in `$PAGER` in a new window
+    PageLastOutput,
+    /// Open the last command's output in `$EDITOR` in a new window
+    EditLastOutput,
+    /// Paste from the clipboard
+    Paste,
+    /// Pick a recent copy to paste
+    PasteFromHistory,
+    /// Select the scrollback and the screen
+    SelectAll,
+    /// Select the selection before the current one again
+    Reselect,
+    /// Move the selection with the keyboard
+    CopyMode,
+    /// Start hints mode
+    Hints,
+    /// Scroll back a line
+    ScrollLineUp,
+    /// Scroll forward a line
+    ScrollLineDown,
+    /// Scroll back a screen
+    ScrollPageUp,
+    /// Scroll forward a screen
+    ScrollPageDown,
+    /// Move the view of a wide grid left (`Config::wide_columns`)
+    ScrollLeft,
+    /// Move the view of a wide grid right
+    ScrollRight,
+    /// Show the oldest line of the scrollback
+    ScrollToTop,
+    /// Back to the latest output
+    ScrollToBottom,
+    /// Start the exited program again
+    Restart,
+    /// Open another Adamant window
+    NewWindow,
+    /// Open another window with the launch profile at this index of
+    /// `Config::launch_profiles`
+    LaunchProfile(usize),
+    /// Make the text bigger
+    ZoomIn,
+    /// Make the text smaller
+    ZoomOut,
+    /// Back to the configured font size
+    ZoomReset,
+    /// Maximize the window, or restore it
+    Maximize,
+    /// Fill the monitor without a frame, or go back
+    Fullscreen,
+    /// Take over the monitor's video mode, or go back
+    ExclusiveFullscreen,
+    /// Recreate the renderer on another GPU
+    Gpu(GpuPreference),
+    /// Turn secure keyboard entry on or off (macOS)
+    SecureInput,
+    /// Stop sending input to the program, or start again
+    ReadOnly,
+    /// Reset the terminal as RIS does, e.g. after binary output
+    ResetTerminal,
+    /// List the escape sequences received that aren't implemented
+    ShowUnhandled,
+    /// Start recording the program's output to a file, or stop
+    Record,
+    /// Give the window a name of its own, or go back to the program's
+    /// title
+    Rename,
+    /// Find text in the scrollback
+    Search,
+    /// Watch the output for activity or silence, or stop watching
+    Monitor(Option<Monitor>),
+    /// Switch the color vision filter, or turn it off
+    ColorFilter(Option<ColorFilter>),
+    /// Close the window, asking first if programs are running
+    Quit,
+    /// The user command at this index of `Config::commands`
+    User(usize),
+    /// Action (second index) of the plugin at the first index of
+    /// `Config::plugins`
+    Plugin(usize, usize),
+}
+
+impl Action {
+    /// Whether it scrolls the view, which a key held down may repeat.
+    pub fn is_scroll(self) -> bool {
+        matches!(
+            self,
+            Self::ScrollLineUp
+                | Self::ScrollLineDown
+                | Self::ScrollPageUp
+                | Self::ScrollPageDown
+                | Self::ScrollLeft
+                | Self::ScrollRight
+                | Self::ScrollToTop
+                | Self::ScrollToBottom
+        )
+    }
+}
+
+/// How the palette and notifications name `filter`.
+pub fn color_filter_name(filter: ColorFilter) -> &'static str {
+    match filter {
+        ColorFilter::Simulate(ColorBlindness::Protanopia) => "Simulate protanopia (red-blind)",
+        ColorFilter::Simulate(ColorBlindness::Deuteranopia) => {
+            "Simulate deuteranopia (green-blind)"
+        }
+        ColorFilter::Simulate(ColorBlindness::Tritanopia) => "Simulate tritanopia (blue-blind)",
+        ColorFilter::Correct(ColorBlindness::Protanopia) => "Correct colors for protanopia",
+        ColorFilter::Correct(ColorBlindness::Deuteranopia) => "Correct colors for deuteranopia",
+        ColorFilter::Correct(ColorBlindness::Tritanopia) => "Correct colors for tritanopia",
+    }
+}
+
+/// How the palette and the menu name the launch profile `name`: `New
+/// window: <name>`. Entries take static names, so each is made once.
+pub fn launch_profile_label(name: &'static str) -> &'static str {
+    static LABELS: Mutex<Vec<(&str, &str)>> = Mutex::new(Vec::new());
+    let mut labels = LABELS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(&(_, label)) = labels.iter().find(|(other, _)| *other == name) {
+        return label;
+    }
+    let label = format!("New window: {}", name).leak();
+    labels.push((name, label));
+    label
+}
+
+/// An entry of the palette.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Command {
+    pub action: Action,
+    pub name: &'static str,
+    /// The key that runs it directly
+    pub shortcut: Option<&'static str>,
+}
+
+/// The built-in actions followed by the user's commands.
+pub fn commands(config: &Config) -> Vec<Command> {
+    let builtin = |action, name, shortcut| Command {
+        action,
+        name,
+        shortcut,
+    };
+    let mut commands = vec![
+        builtin(Action::Copy, "Copy selection", Some("Ctrl+Shift+C")),
+        builtin(Action::Paste, "Paste", Some("Ctrl+Shift+V")),
+        builtin(Action::SelectAll, "Select all", None),
+        builtin(
+            Action::Hints,
+            "Pick text on screen (hints)",
+            Some("Ctrl+Shift+E"),
+        ),
+        builtin(Action::Restart, "Restart the program", Some("Ctrl+Shift+R")),
+        builtin(Action::NewWindow, "New window", None),
+    ];
+    commands.extend(
+        config
+            .launch_profiles
+            .iter()
+            .enumerate()
+            .map(|(i, profile)| {
+                builtin(
+                    Action::LaunchProfile(i),
+                    launch_profile_label(profile.name),
+                    None,
+                )
+            }),
+    );
+    commands.extend([
+        builtin(Action::ScrollToTop, "Scroll to top", None),
+        builtin(Action::ScrollToBottom, "Scroll to bottom", None),
+        builtin(Action::ScrollLineUp, "Scroll up a line", None),
+        builtin(Action::ScrollLineDown, "Scroll down a line", None),
+        builtin(Action::ScrollPageUp, "Scroll up a page", None),
+        builtin(Action::ScrollPageDown, "Scroll down a page", None),
+        builtin(Action::ScrollLeft, "Scroll left", None),
+        builtin(Action::ScrollRight, "Scroll right", None),
+        builtin(
+            Action::Gpu(GpuPreference::Integrated),
+            "Render with the integrated GPU",
+            None,
+        ),
+        builtin(
+            Action::Gpu(GpuPreference::Discrete),
+            "Render with the discrete GPU",
+            None,
+        ),
+        builtin(
+            Action::Gpu(GpuPreference::Auto),
+            "Pick the GPU by power source",
+            None,
+        ),
+        builtin(Action::ZoomIn, "Zoom in", None),
+        builtin(Action::ZoomOut, "Zoom out", None),
+        builtin(Action::ZoomReset, "Reset zoom", None),
+        builtin(Action::Maximize, "Toggle maximized", Some("Ctrl+Shift+F10")),
+        builtin(
+            Action::Fullscreen,
+            "Toggle fullscreen",
+            Some("Ctrl+Shift+F11"),
+        ),
+        builtin(
+            Action::ExclusiveFullscreen,
+            "Toggle exclusive fullscreen",
+            None,
+        ),
+        builtin(
+            Action::CopyLastOutput,
+            "Copy the last command's output",
+            None,
+        ),
+        builtin(
+            Action::PageLastOutput,
+            "Open the last command's output in the pager",
+            None,
+        ),
+        builtin(
+            Action::EditLastOutput,
+            "Open the last command's output in the editor",
+            None,
+        ),
+        builtin(
+            Action::PasteFromHistory,
+            "Paste a recent copy",
+            Some("Ctrl+Shift+Y"),
+        ),
+        builtin(
+            Action::CopyMode,
+            "Copy mode: select with the keyboard",
+            Some("Ctrl+Shift+Space"),
+        ),
+        builtin(Action::Reselect, "Reselect the last selection", None),
+        builtin(Action::ReadOnly, "Toggle read-only", None),
+        builtin(Action::ResetTerminal, "Reset the terminal", None),
+        builtin(Action::ShowUnhandled, "Show unhandled sequences", None),
+        builtin(Action::Record, "Record output to a file", None),
+        builtin(
+            Action::Search,
+            "Search the scrollback",
+            Some("Ctrl+Shift+F"),
+        ),
+        builtin(Action::Rename, "Rename the window", Some("Ctrl+Shift+F2")),
+        builtin(
+            Action::Monitor(Some(Monitor::Activity)),
+            "Watch for activity",
+            None,
+        ),
+        builtin(
+            Action::Monitor(Some(Monitor::Silence)),
+            "Watch for silence",
+            None,
+        ),
+        builtin(Action::Monitor(None), "Stop watching", None),
+        builtin(Action::Quit, "Quit", None),
+    ]);
+    commands.extend(
+        [
+            ColorBlindness::Protanopia,
+            ColorBlindness::Deuteranopia,
+            ColorBlindness::Tritanopia,
+        ]
+        .into_iter()
+        .flat_map(|blindness| {
+            [
+                ColorFilter::Simulate(blindness),
+                ColorFilter::Correct(blindness),
+            ]
+        })
+        .map(|filter| {
+            builtin(
+                Action::ColorFilter(Some(filter)),
+                color_filter_name(filter),
+                None,
+            )
+        }),
+    );
+    commands.push(builtin(
+        Action::ColorFilter(None),
+        "Turn off the color filter",
+        None,
+    ));
+    if SecureInput::SUPPORTED {
+        commands.push(builtin(
+            Action::SecureInput,
+            "Toggle secure keyboard entry",
+            None,
+        ));
+    }
+    commands.extend(
+        config
+            .commands
+            .iter()
+            .enumerate()
+            .map(|(i, command)| Command {
+                action: Action::User(i),
+                name: command.name,
+                shortcut: None,
+            }),
+    );
+    // Key bindings running a command show as its shortcut
+    for binding in &config.key_bindings {
+        if let HookAction::Command(name) = &binding.action
+            && let Some(command) = (commands.iter_mut())
+                .find(|command| command.name == name && command.shortcut.is_none())
+        {
+            command.shortcut = Some(binding.keys);
+        }
+    }
+    commands
+}
+
+/// The action of the palette entry called `name` (for `Hooks`).
+pub fn command_named(config: &Config, name: &str) -> Option<Action> {
+    commands(config)
+        .into_iter()
+        .find(|command| command.name == name)
+        .map(|command| command.action)
+}
+
+/// How well `query` matches `text`, or `None` if its characters don't all
+/// appear in `text` in order. Case is ignored; an empty query matches
+/// everything equally. Each matched character scores, more at the start
+/// of a word and right after the previous match; the best placement of
+/// the query counts.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let text: Vec<char> = text.chars().collect();
+    let word_start = |j: usize| {
+        j == 0
+            || !text[j - 1].is_alphanumeric()
+            || (text[j].is_uppercase() && text[j - 1].is_lowercase())
+    };
+
+    // Best score of the query so far with its last character at each
+    // position of `text`
+    let mut scores: Option<Vec<Option<u32>>> = None;
+    for wanted in query.chars().flat_map(char::to_lowercase) {
+        let mut row = vec![None; text.len()];
+        // Best of the previous row before the position left of `j`
+        let mut before = None;
+        for j in 0..text.len() {
+            if text[j].to_lowercase().eq(std::iter::once(wanted)) {
+                let bonus = if word_start(j) { 9 } else { 1 };
+                row[j] = match &scores {
+                    None => Some(bonus),
+                    Some(previous) => {
+                        let run = j.checked_sub(1).and_then(|k| previous[k]).map(|s| s + 4);
+                        before.max(run).map(|s| s + bonus)
+                    }
+                };
+            }
+            if let Some(previous) = &scores
+                && j > 0
+            {
+                before = before.max(previous[j - 1]);
+            }
+        }
+        scores = Some(row);
+    }
+    match scores {
+        Some(scores) => scores.into_iter().flatten().max(),
+        None => Some(0),
+    }
+}
+
+/// The palette's query and the commands matching it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommandPalette {
+    commands: Vec<Command>,
+    query: String,
+    /// Indices into `commands` of the matches, best first
+    matches: Vec<usize>,
+    /// Index into `matches`
+    selected: usize,
+}
+
+impl CommandPalette {
+    /// Rows of matches shown at most.
+    const ROWS: usize = 12;
+
+    pub fn new(commands: Vec<Command>) -> Self {
+        let mut palette = Self {
+            commands,
+            ..Self::default()
+        };
+        palette.filter();
+        palette
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// The commands matching the query, best first.
+    pub fn matches(&self) -> impl Iterator<Item = &Command> {
+        self.matches.iter().map(|&i| &self.commands[i])
+    }
+
+    /// The action of the highlighted match.
+    pub fn selected(&self) -> Option<Action> {
+        let index = *self.matches.get(self.selected)?;
+        Some(self.commands[index].action)
+    }
+
+    /// Type a character of the query.
+    pub fn input(&mut self, c: char) {
+        if !c.is_control() {
+            self.query.push(c);
+            self.filter();
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        if self.query.pop().is_some() {
+            self.filter();
+        }
+    }
+
+    /// Highlight the next match, wrapping around.
+    pub fn select_next(&mut self) {
+        if !self.matches.is_empty() {
+            self.selected = (self.selected + 1) % self.matches.len();
+        }
+    }
+
+    /// Highlight the previous match, wrapping around.
+    pub fn select_prev(&mut self) {
+        if !self.matches.is_empty() {
+            self.selected = (self.selected + self.matches.len() - 1) % self.matches.len();
+        }
+    }
+
+    /// Highlight the match with this command index, as given to the ids of
+    /// [`layer`](Self::layer)'s elements.
+    pub fn select(&mut self, command: usize) {
+        if let Some(position) = self.matches.iter().position(|&i| i == command) {
+            self.selected = position;
+        }
+    }
+
+    /// Draw the palette at the top of a `screen_rows` x `screen_cols`
+    /// screen: the query, then the matches around the highlighted one. The
+    /// matches' ids are their command index.
+    pub fn layer(&self, screen_rows: usize, screen_cols: usize) -> Layer {
+        let cols = screen_cols.min(64);
+        let col = (screen_cols - cols) / 2;
+        let shown = Self::ROWS.min(screen_rows.saturating_sub(2));
+        // Scroll the list so the highlighted match stays in view
+        let first = (self.selected + 1).saturating_sub(shown);
+
+        let mut elements = vec![Element::label(
+            Rect::new(0, col, 1, cols),
+            format!("> {}", self.query),
+            Style::PANEL,
+        )];
+        for (row, &index) in self.matches.iter().enumerate().skip(first).take(shown) {
+            let command = &self.commands[index];
+            let shortcut = command.shortcut.unwrap_or("");
+            let name_cols = cols.saturating_sub(shortcut.len() + 3);
+            let text = format!(" {:<name_cols$} {} ", command.name, shortcut);
+            let rect = Rect::new(row - first + 1, col, 1, cols);
+            elements.push(Element::button(rect, text, Style::ITEM, index));
+        }
+        let mut layer = Layer::new(LayerKind::Menu, elements);
+        if let Some(&index) = self.matches.get(self.selected) {
+            layer.set_focus(index);
+        }
+        layer
+    }
+
+    /// Match the commands against the query, keeping the registry's order
+    /// between equal scores.
+    fn filter(&mut self) {
+        let mut scored: Vec<(u32, usize)> = self
+            .commands
+            .iter()
+            .enumerate()
+            .filter_map(|(i, command)| Some((fuzzy_score(&self.query, command.name)?, i)))
+            .collect();
+        scored.sort_by_key(|&(score, i)| (std::cmp::Reverse(score), i));
+        self.matches = scored.into_iter().map(|(_, i)| i).collect();
+        self.selected = 0;
+    }
+}
@@ -0,0 +1,1935 @@
+//! Compile-Time Configuration
+//!
+//! "Don't configure it. Compile it." Adamant has no config file: settings
+//! are plain Rust structs, and [`Config::default`] *is* the configuration.
+//! Edit the defaults below and rebuild to customize the terminal.
+
+use crate::error::AdamantError;
+
+/// A 24-bit sRGB color.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+/// Top-level configuration.
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub font: FontConfig,
+    pub colors: Colors,
+    /// Lines of history kept for the primary screen
+    pub scrollback_lines: usize,
+    /// Whether `clear` (ED 3) also erases the scrollback
+    pub erase_saved_lines: bool,
+    /// Keep what scrolls off the alternate screen (pagers, editors) in the
+    /// primary scrollback
+    pub save_alt_screen_lines: bool,
+    /// Columns of a grid wider than the window, 0 for none: lines up to
+    /// this long don't wrap, and the view scrolls sideways (Shift+Wheel,
+    /// `Scroll left` / `Scroll right`). Programs see the terminal this wide
+    pub wide_columns: u16,
+    pub mouse: MouseConfig,
+    /// Recent copies, pasted again with Ctrl+Shift+Y
+    pub clipboard_history: ClipboardHistoryConfig,
+    /// Asking before pastes that would run at a shell prompt
+    pub paste: PasteConfig,
+    /// Copies also carry HTML (and RTF on macOS and Windows) with the
+    /// text's colors, bold and italics, for pasting into documents and chats
+    pub copy_styled: bool,
+    /// Copies leave out the blanks at the end of each line
+    pub copy_trim_trailing_whitespace: bool,
+    /// Copies keep lines the terminal wrapped as one line, so long
+    /// commands paste cleanly
+    pub copy_join_wrapped_lines: bool,
+    /// The program the window runs and the environment it gets
+    pub shell: ShellConfig,
+    /// Keyboard-driven selection of URLs, paths and hashes on screen
+    pub hints: HintsConfig,
+    /// Your own entries in the command palette (Ctrl+Shift+P)
+    pub commands: Vec<UserCommand>,
+    /// Keys, and tmux-style sequences after a leader key, that run palette
+    /// commands, type text or run programs (see [`KeyBinding`])
+    pub key_bindings: Vec<KeyBinding>,
+    /// How long a started key sequence waits for its next key
+    pub key_sequence_timeout_ms: u64,
+    /// A key held down repeats into the program, but shortcuts and key
+    /// bindings run once per press. `true` lets the ones that scroll
+    /// (`Scroll up a line` and the like) repeat
+    pub repeat_scroll_keys: bool,
+    /// Shortcuts left to the programs that use them themselves, such as
+    /// tmux and vim (see [`KeyPassthrough`])
+    pub passthrough: Vec<KeyPassthrough>,
+    /// Listen on a control socket for scripts (see `crate::ipc`)
+    pub ipc: bool,
+    /// Which GPU to draw with on machines that have two
+    pub gpu: GpuPreference,
+    /// Show Arabic and Hebrew text right to left (see `crate::grid::BidiRow`).
+    /// Off by default: programs that lay out RTL text themselves would get
+    /// it reversed twice.
+    pub bidi: bool,
+    /// East Asian Ambiguous characters (Greek, Cyrillic, `①`, box drawing)
+    /// take two columns. Match the `wcwidth()` of the programs you run:
+    /// usually on for CJK locales, off otherwise
+    pub ambiguous_wide: bool,
+    /// Emoji that default to text presentation (`☺`, `❤`, `✔`) take two
+    /// columns
+    pub emoji_wide: bool,
+    /// Expose the screen to screen readers through AccessKit (NVDA,
+    /// VoiceOver, Orca) and speak new output (see `crate::a11y`). Native
+    /// builds only. Off by default since it snapshots the screen after
+    /// every update
+    pub screen_reader: bool,
+    /// Which Option keys act as Alt on macOS, sending ESC-prefixed Meta
+    /// combinations (`M-f` in Emacs and readline) instead of composing
+    /// characters like `ƒ`
+    pub option_as_alt: OptionAsAlt,
+    /// What the numeric keypad sends while a program asks for
+    /// application keypad mode (DECKPAM), as some full-screen programs do
+    pub keypad: Keypad,
+    /// Title bar and borders around the window. On Wayland the compositor
+    /// draws them where it supports server-side decorations (KDE,
+    /// wlroots); elsewhere (GNOME) winit draws its own client-side frame,
+    /// in its colors rather than the terminal's
+    pub decorations: bool,
+    /// The window title, with `{title}` for the terminal's (the program's,
+    /// or the name it was renamed to), `{host}` for the host the shell
+    /// reported and `{cwd}` for its working directory. A host profile's
+    /// title replaces it
+    pub title: &'static str,
+    /// Ask before closing the window while programs other than the shell
+    /// run in it (an editor, `ssh`, a build)
+    pub confirm_close: bool,
+    /// Whether the window stays open when its program exits, showing the
+    /// last screen and the exit code
+    pub hold_on_exit: HoldOnExit,
+    /// Start the program again in the same window when it exits, keeping
+    /// the scrollback: for watch-style commands, or to never lose the
+    /// shell. Wins over `hold_on_exit`.
+    pub respawn: bool,
+    /// Open the window read-only: keys, pastes and mouse reports are not
+    /// sent to the program until it is unlocked. For windows tailing logs
+    /// or attached to production consoles
+    pub read_only: bool,
+    /// Record the program's output from the start to this file, with its
+    /// timing in `<file>.timing` (`--record`, see `crate::recording`)
+    pub record: Option<&'static str>,
+    /// Play a recording instead of running a program (`--replay`), to
+    /// reproduce what a user saw
+    pub replay: Option<Replay>,
+    /// How the window opens: in a window, maximized or fullscreen
+    pub window_mode: WindowMode,
+    /// Save the window's size, position, monitor and mode when it closes
+    /// and open it that way next time (see `crate::geometry`), instead of
+    /// at the default size where the window manager puts it. A
+    /// `window_mode` other than windowed still wins over the saved mode
+    pub remember_geometry: bool,
+    /// Slide the rows over a few frames when scrolling, instead of jumping
+    /// line by line
+    pub scroll_animation: ScrollAnimationConfig,
+    /// When the scrollbar is shown over the right edge (see
+    /// `crate::scrollbar`)
+    pub scrollbar: ScrollbarMode,
+    /// Give the right edge a column with an overview of the scrollback,
+    /// one pixel row per line, that jumps to where it is clicked (see
+    /// `crate::minimap`)
+    pub minimap: bool,
+    /// A row above or below the terminal showing the directory, the Git
+    /// branch, the time and more (see [`StatusBarConfig`])
+    pub status_bar: StatusBarConfig,
+    /// A WGSL file run over every frame, for effects such as CRT curvature
+    /// or scanlines (see `crate::renderer::check_post_shader` for what it
+    /// gets). A shader that doesn't compile is reported and skipped. Native
+    /// builds only
+    pub shader: Option<&'static str>,
+    /// A built-in retro CRT look, without writing a shader. Drawn before
+    /// `shader` when both are set
+    pub crt: CrtConfig,
+    /// Draw in wide gamut where the surface supports it, so colors look
+    /// the same on Display P3 and HDR monitors instead of over-saturated
+    /// (see `crate::renderer::surface_format`). Falls back to sRGB
+    pub wide_gamut: bool,
+    /// Show the colors as with a color vision deficiency, or correct them
+    /// for one (see `crate::renderer::color_filter_matrix`). Can be changed
+    /// from the command palette
+    pub color_filter: Option<ColorFilter>,
+    /// Quake-style dropdown window, shown and hidden with a global hotkey
+    pub dropdown: DropdownConfig,
+    /// How window managers, taskbars and launchers identify the window
+    pub desktop: DesktopConfig,
+    /// What BEL (`\a`) does
+    pub bell: Bell,
+    /// Watch new windows for output after a pause, or for output stopping
+    pub monitor: Option<Monitor>,
+    /// How long without output counts as a pause, or as silence, for
+    /// [`Monitor`]
+    pub silence_ms: u64,
+    /// Regular expressions run over every line of output, highlighting it
+    /// or reporting it (see [`WatchRule`]): build failures, errors in a
+    /// tailed log
+    pub watch_rules: Vec<WatchRule>,
+    /// Your own functions called when things happen: at startup, for each
+    /// line of output, when the title or focus changes (see [`Hooks`])
+    pub hooks: Hooks,
+    /// Extensions adding palette actions and shortcuts, output filters and
+    /// overlays, made for every window (see `crate::plugin`)
+    #[cfg(feature = "gui")]
+    pub plugins: Vec<crate::plugin::PluginFactory>,
+    /// Title, colors and bell for sessions on given hosts, applied when the
+    /// shell reports the host it runs on (see [`HostProfile`])
+    pub profiles: Vec<HostProfile>,
+    /// Settings for while given programs run in the foreground, or the
+    /// title matches (see [`AppProfile`])
+    pub app_profiles: Vec<AppProfile>,
+    /// Named setups to start a window with: `adamant --profile <name>`, or
+    /// a new window from the command palette and the Shell menu (see
+    /// [`LaunchProfile`])
+    pub launch_profiles: Vec<LaunchProfile>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            font: FontConfig::default(),
+            colors: Colors::default(),
+            scrollback_lines: 10_000,
+            erase_saved_lines: true,
+            save_alt_screen_lines: false,
+            wide_columns: 0,
+            mouse: MouseConfig::default(),
+            clipboard_history: ClipboardHistoryConfig::default(),
+            paste: PasteConfig::default(),
+            copy_styled: true,
+            copy_trim_trailing_whitespace: true,
+            copy_join_wrapped_lines: true,
+            shell: ShellConfig::default(),
+            hints: HintsConfig::default(),
+            commands: Vec::new(),
+            key_bindings: Vec::new(),
+            key_sequence_timeout_ms: 1500,
+            repeat_scroll_keys: true,
+            passthrough: Vec::new(),
+            ipc: true,
+            gpu: GpuPreference::Auto,
+            bidi: false,
+            ambiguous_wide: false,
+            emoji_wide: false,
+            screen_reader: false,
+            option_as_alt: OptionAsAlt::None,
+            keypad: Keypad::NumLock,
+            decorations: true,
+            title: "{title}",
+            confirm_close: true,
+            hold_on_exit: HoldOnExit::Never,
+            respawn: false,
+            read_only: false,
+            record: None,
+            replay: None,
+            window_mode: WindowMode::Windowed,
+            remember_geometry: false,
+            scroll_animation: ScrollAnimationConfig::default(),
+            scrollbar: ScrollbarMode::Scrolled,
+            minimap: false,
+            status_bar: StatusBarConfig::default(),
+            shader: None,
+            crt: CrtConfig::default(),
+            wide_gamut: false,
+            color_filter: None,
+            dropdown: DropdownConfig::default(),
+            desktop: DesktopConfig::default(),
+            bell: Bell::Ignore,
+            monitor: None,
+            silence_ms: 10_000,
+            watch_rules: Vec::new(),
+            hooks: Hooks::default(),
+            #[cfg(feature = "gui")]
+            plugins: Vec::new(),
+            profiles: Vec::new(),
+            app_profiles: Vec::new(),
+            launch_profiles: Vec::new(),
+        }
+    }
+}
+
+/// GPU selection on dual-GPU machines (laptops with integrated and
+/// discrete graphics). Can be changed at runtime with the IPC `gpu` command.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum GpuPreference {
+    /// Integrated on battery, discrete on AC power
+    #[default]
+    Auto,
+    /// The power-saving GPU
+    Integrated,
+    /// The fast GPU
+    Discrete,
+}
+
+/// Option keys used as Alt (Meta) on macOS; the others type the
+/// characters of the keyboard layout.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum OptionAsAlt {
+    /// Both Option keys compose characters
+    #[default]
+    None,
+    /// The left one is Alt, the right one composes
+    Left,
+    /// The right one is Alt, the left one composes
+    Right,
+    /// Both are Alt
+    Both,
+}
+
+/// The numeric keypad in application keypad mode (DECKPAM, `ESC =`).
+/// Keys sending application sequences send SS3 ones: `ESC O p` for 0,
+/// `ESC O M` for Enter. In numeric mode (DECKPNM, `ESC >`) the keypad
+/// types digits, or moves the cursor with NumLock off.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Keypad {
+    /// NumLock decides, as on PC keyboards: on, the digits and the
+    /// decimal point type themselves; off, the keys move the cursor.
+    /// Enter and the operators send application sequences
+    #[default]
+    NumLock,
+    /// Every key sends its application sequence whatever NumLock says,
+    /// like a VT100's keypad
+    Application,
+}
+
+/// The window's size mode. Toggled at runtime with Ctrl+Shift+F10
+/// (maximized), Ctrl+Shift+F11 (fullscreen) or the command palette;
+/// going back restores the window's previous size and position.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum WindowMode {
+    #[default]
+    Windowed,
+    Maximized,
+    /// Borderless fullscreen: a window covering the monitor, quick to
+    /// switch in and out of
+    Fullscreen,
+    /// The monitor's video mode is taken over (highest refresh rate at
+    /// its current resolution); falls back to borderless where that isn't
+    /// supported (Wayland, the web)
+    ExclusiveFullscreen,
+}
+
+/// When to keep the window open after its program exited. A held window
+/// shows `[process exited with code N]`; Enter closes it and R starts the
+/// program again.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum HoldOnExit {
+    /// Close right away
+    #[default]
+    Never,
+    /// Hold when the exit code isn't 0 (or isn't known), to read the error
+    Failure,
+    /// Always hold
+    Always,
+}
+
+impl HoldOnExit {
+    /// Whether a program exiting with `code` holds the window.
+    ///
+    /// ```
+    /// use adamant::config::HoldOnExit;
+    ///
+    /// assert!(HoldOnExit::Failure.holds(Some(1)));
+    /// assert!(HoldOnExit::Failure.holds(None));
+    /// assert!(!HoldOnExit::Failure.holds(Some(0)));
+    /// ```
+    pub fn holds(self, code: Option<u32>) -> bool {
+        match self {
+            Self::Never => false,
+            Self::Failure => code != Some(0),
+            Self::Always => true,
+        }
+    }
+}
+
+/// Mouse behavior.
+#[derive(Clone, Debug)]
+pub struct MouseConfig {
+    /// While an application tracks the mouse (vim, tmux, htop), plain drags
+    /// are reported to it and Shift+drag selects text, like xterm. `true`
+    /// swaps the two: drags always select, and Shift sends them to the
+    /// application.
+    pub swap_selection_modifier: bool,
+    /// Selecting text with the mouse also puts it into the PRIMARY
+    /// selection, which a middle click pastes, as X11 and Wayland
+    /// programs do. Ctrl+Shift+C and Ctrl+Shift+V keep using the
+    /// clipboard. Where there is no PRIMARY, a middle click pastes the
+    /// selection in the window.
+    pub primary_selection: bool,
+    /// How files dragged into the window are pasted (see
+    /// `crate::file_drop`). A single folder dropped with Shift held is
+    /// changed into (`cd`) instead
+    pub drop_format: DropFormat,
+    /// Keep scrolling after a touchpad flick, slowing down until it
+    /// stops (see `crate::scroll`). macOS glides by itself and needs no
+    /// emulation
+    pub touchpad_momentum: bool,
+    /// Characters a double click selects as part of a word, besides
+    /// letters and digits, so paths and URLs select whole
+    pub word_chars: &'static str,
+    /// Clicks on the same cell within this many milliseconds count as a
+    /// double click (a word) or a triple click (a line)
+    pub multi_click_ms: u64,
+    /// Held over a URL or path (the hints patterns, see `HintsConfig`),
+    /// it underlines the match and shows a pointer; a click runs the
+    /// pattern's action
+    pub link_modifier: LinkModifier,
+    /// What buttons and the wheel do with modifiers held (see
+    /// [`MouseBinding`]). Applications tracking the mouse get it first,
+    /// and a plain left click always selects
+    pub bindings: Vec<MouseBinding>,
+}
+
+/// A mouse button or the wheel with modifiers, bound to a command.
+/// `mouse` is written like `Ctrl+Right` or `Shift+Wheel`: `Left`,
+/// `Middle`, `Right`, `Back` and `Forward` are buttons (`RightClick` and
+/// the like work too), `WheelUp` and `WheelDown` one way of the wheel and
+/// `Wheel` both. A binding without modifiers also covers modifiers no
+/// other binding names, so Shift+Wheel scrolls unless bound otherwise:
+///
+/// ```
+/// use adamant::config::{Config, HookAction, MouseBinding, MouseCommand};
+///
+/// let mut config = Config::default();
+/// config.mouse.bindings.extend([
+///     MouseBinding {
+///         mouse: "Ctrl+Right",
+///         command: MouseCommand::PasteSelection,
+///     },
+///     MouseBinding {
+///         mouse: "Shift+Wheel",
+///         command: MouseCommand::ScrollHorizontal,
+///     },
+///     MouseBinding {
+///         mouse: "Ctrl+WheelUp",
+///         command: MouseCommand::Run(HookAction::Command("Zoom in".into())),
+///     },
+/// ]);
+/// assert!(config.validate().is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct MouseBinding {
+    pub mouse: &'static str,
+    pub command: MouseCommand,
+}
+
+/// What a [`MouseBinding`] does.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MouseCommand {
+    /// Run the action of the URL or path under the pointer (see
+    /// `HintsConfig`)
+    OpenAtPointer,
+    /// Paste the PRIMARY selection (see `MouseConfig::primary_selection`)
+    PasteSelection,
+    /// Scroll the scrollback, or send the wheel to an application
+    /// tracking the mouse. For the wheel only
+    Scroll,
+    /// Scroll sideways: an application tracking the mouse gets the wheel
+    /// as tilted left or right, otherwise the view of a grid wider than
+    /// the window (`Config::wide_columns`) moves. Without either the
+    /// scrollback scrolls as with `Scroll`. For the wheel only
+    ScrollHorizontal,
+    /// What a hook can ask for: a palette command, typing, a program
+    Run(HookAction),
+}
+
+/// What is kept of the text copied in a window (see
+/// `crate::clipboard_history`). Copies are only kept in memory, and go
+/// with the window.
+#[derive(Clone, Debug)]
+pub struct ClipboardHistoryConfig {
+    /// `false` keeps nothing: for windows where passwords get copied
+    pub enabled: bool,
+    /// Entries kept; the oldest goes first
+    pub max_entries: usize,
+    /// Copies larger than this many bytes are left out
+    pub max_entry_bytes: usize,
+}
+
+impl Default for ClipboardHistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_entries: 50,
+            max_entry_bytes: 256 * 1024,
+        }
+    }
+}
+
+/// How pasted text is cleaned up, and when a paste shows a preview first
+/// (see `crate::paste`). Programs using bracketed paste are never asked
+/// about.
+#[derive(Clone, Debug)]
+pub struct PasteConfig {
+    /// Drop C0 and C1 control characters other than tabs and line breaks,
+    /// escape included
+    pub strip_control: bool,
+    /// Drop the sequences starting and ending a bracketed paste, so pasted
+    /// text can't leave it early
+    pub strip_brackets: bool,
+    /// Send line breaks (CRLF and LF) as CR, like Enter
+    pub normalize_line_endings: bool,
+    /// Ask before pasting text with line breaks
+    pub confirm_multi_line: bool,
+    /// Ask before pasting more than this many bytes; 0 doesn't ask
+    pub confirm_over_bytes: usize,
+}
+
+impl Default for PasteConfig {
+    fn default() -> Self {
+        Self {
+            strip_control: true,
+            strip_brackets: true,
+            normalize_line_endings: true,
+            confirm_multi_line: true,
+            confirm_over_bytes: 16 * 1024,
+        }
+    }
+}
+
+/// The text files dropped into the window become.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum DropFormat {
+    /// Paths, quoted for the shell where needed
+    #[default]
+    Path,
+    /// `file://` URLs, for programs that take those
+    Url,
+}
+
+/// The key held to click links.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LinkModifier {
+    Control,
+    Shift,
+    Alt,
+    /// Command on macOS, the Windows or Super key elsewhere
+    Super,
+}
+
+impl Default for LinkModifier {
+    fn default() -> Self {
+        if cfg!(target_os = "macos") {
+            Self::Super
+        } else {
+            Self::Control
+        }
+    }
+}
+
+impl Default for MouseConfig {
+    fn default() -> Self {
+        Self {
+            swap_selection_modifier: false,
+            primary_selection: true,
+            drop_format: DropFormat::Path,
+            touchpad_momentum: false,
+            word_chars: "-_./~:@%+=?&#",
+            multi_click_ms: 400,
+            link_modifier: LinkModifier::default(),
+            bindings: vec![
+                MouseBinding {
+                    mouse: "Right",
+                    command: MouseCommand::OpenAtPointer,
+                },
+                MouseBinding {
+                    mouse: "Middle",
+                    command: MouseCommand::PasteSelection,
+                },
+                MouseBinding {
+                    mouse: "Wheel",
+                    command: MouseCommand::Scroll,
+                },
+                MouseBinding {
+                    mouse: "Shift+Wheel",
+                    command: MouseCommand::ScrollHorizontal,
+                },
+            ],
+        }
+    }
+}
+
+/// Animated scrolling (see `crate::scroll::ScrollAnimation`). The rows
+/// slide into place with easing; jumps longer than a screen only animate
+/// their last screen. While overlays (toasts, dialogs) are shown the view
+/// jumps as before.
+#[derive(Clone, Debug)]
+pub struct ScrollAnimationConfig {
+    /// Animate scrolling through the scrollback (wheel, touchpad, Scroll
+    /// to top/bottom)
+    pub enabled: bool,
+    /// How long the rows take to catch up
+    pub duration_ms: u64,
+    /// Also animate output scrolling the screen, while following it. Fast
+    /// output keeps the rows moving
+    pub output: bool,
+}
+
+impl Default for ScrollAnimationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            duration_ms: 120,
+            output: false,
+        }
+    }
+}
+
+/// When the scrollbar is shown. It never is without scrollback, e.g. on
+/// the alternate screen.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ScrollbarMode {
+    Never,
+    /// While the view is scrolled back, the pointer is over the bar or it
+    /// is dragged
+    #[default]
+    Scrolled,
+    Always,
+}
+
+/// The status bar: a row of its own, so it never hides output, with the
+/// segments' texts side by side. Segments with nothing to show are left
+/// out.
+///
+/// ```
+/// use adamant::config::{Config, StatusBarPosition, StatusSegment};
+///
+/// let mut config = Config::default();
+/// config.status_bar.position = Some(StatusBarPosition::Bottom);
+/// config.status_bar.segments = vec![
+///     StatusSegment::Cwd,
+///     StatusSegment::GitBranch,
+///     StatusSegment::Command(&["uptime", "-p"]),
+///     StatusSegment::Clock { utc_offset_minutes: 60 },
+/// ];
+/// assert!(config.validate().is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct StatusBarConfig {
+    /// Where the bar is shown, `None` for no bar
+    pub position: Option<StatusBarPosition>,
+    pub segments: Vec<StatusSegment>,
+    /// Put between two segments
+    pub separator: &'static str,
+    /// How often the clock and the programs' output are refreshed. What
+    /// the shell reports (title, directory, host) shows right away
+    pub interval_ms: u64,
+}
+
+impl Default for StatusBarConfig {
+    fn default() -> Self {
+        Self {
+            position: None,
+            segments: vec![
+                StatusSegment::Cwd,
+                StatusSegment::GitBranch,
+                StatusSegment::Hostname,
+            ],
+            separator: " | ",
+            interval_ms: 1_000,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StatusBarPosition {
+    Top,
+    Bottom,
+}
+
+/// What a part of the status bar shows.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StatusSegment {
+    /// The working directory the shell reported (OSC 7), with the home
+    /// directory as `~`
+    Cwd,
+    /// The Git branch checked out in that directory
+    GitBranch,
+    /// The host the shell reported, or this machine's name
+    Hostname,
+    /// The window title the program set. There are no tabs, so this is
+    /// what a list of them would show
+    Title,
+    /// The time as `HH:MM`, this many minutes ahead of UTC. The offset is
+    /// fixed; for the system's time zone, use
+    /// `Command(&["date", "+%H:%M"])`
+    Clock { utc_offset_minutes: i32 },
+    /// The first line a program prints, run in the reported working
+    /// directory. Native builds only
+    Command(&'static [&'static str]),
+    /// Text of your own
+    Text(&'static str),
+}
+
+/// A recording to play (`Config::replay`, see `crate::recording`).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Replay {
+    /// The typescript; its timing is read from `<file>.timing`
+    pub file: &'static str,
+    /// Times the recorded pace; 0 plays it all at once
+    pub speed: f32,
+}
+
+/// The CRT effect (`adamant --crt`, see `crate::renderer::crt_shader`).
+/// Each intensity is between 0 (off) and 1.
+#[derive(Clone, Debug)]
+pub struct CrtConfig {
+    pub enabled: bool,
+    /// Dark gaps between the lines of the picture
+    pub scanlines: f32,
+    /// Glow spilling from bright text onto its surroundings
+    pub bloom: f32,
+    /// Bulge of the glass; the edges of the terminal stay on screen and
+    /// the corners are rounded off
+    pub curvature: f32,
+    /// How long pixels keep glowing after going dark; at 1 about a tenth
+    /// of a second
+    pub persistence: f32,
+}
+
+impl Default for CrtConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            scanlines: 0.5,
+            bloom: 0.3,
+            curvature: 0.3,
+            persistence: 0.4,
+        }
+    }
+}
+
+/// A filter over the whole frame for a color vision deficiency.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColorFilter {
+    /// Show the colors as they look with the deficiency, to check that a
+    /// color scheme or TUI still works
+    Simulate(ColorBlindness),
+    /// Move the differences that are lost with the deficiency to ones
+    /// that aren't (daltonization)
+    Correct(ColorBlindness),
+}
+
+/// The color vision deficiencies the filters know, each missing one kind
+/// of cone.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColorBlindness {
+    /// No red cones
+    Protanopia,
+    /// No green cones
+    Deuteranopia,
+    /// No blue cones
+    Tritanopia,
+}
+
+/// The dropdown window (`adamant --dropdown`): a borderless window across
+/// the top of the screen that slides down when the hotkey is pressed and
+/// back up when it is pressed again. It keeps the height it was resized
+/// to and comes back on the monitor it was hidden on.
+#[derive(Clone, Debug)]
+pub struct DropdownConfig {
+    pub enabled: bool,
+    /// The global hotkey, e.g. `F12` or `Ctrl+Alt+grave` (see
+    /// `crate::hotkey::Hotkey::parse`). Global hotkeys need X11, Windows
+    /// or macOS; Wayland has none
+    pub hotkey: &'static str,
+    /// Height as a fraction of the monitor's, until the window is resized
+    pub height: f32,
+    /// How long sliding in or out takes, in milliseconds (0: no animation)
+    pub slide_ms: u64,
+}
+
+impl Default for DropdownConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            hotkey: "F12",
+            height: 0.4,
+            slide_ms: 150,
+        }
+    }
+}
+
+/// Desktop integration (see `crate::desktop`).
+#[derive(Clone, Debug)]
+pub struct DesktopConfig {
+    /// The Wayland app-id and X11 `WM_CLASS` (instance and class; the
+    /// window class on Windows). Window manager rules match on it, and
+    /// docks find the `.desktop` file by it: name the file
+    /// `<app_id>.desktop` or set its `StartupWMClass`. `adamant --class`
+    pub app_id: &'static str,
+    /// A PNG file for the window icon, on X11 and Windows (Wayland docks
+    /// use the `.desktop` file's icon, macOS the bundle's). `adamant
+    /// --icon`
+    pub icon: Option<&'static str>,
+    /// Use the startup-notification token a launcher passes
+    /// (`DESKTOP_STARTUP_ID`, `XDG_ACTIVATION_TOKEN`), so the window gets
+    /// the focus and the launcher's busy cursor stops, and hand a new one
+    /// to windows opened with New Window (X11 and Wayland)
+    pub startup_notify: bool,
+}
+
+impl Default for DesktopConfig {
+    fn default() -> Self {
+        Self {
+            app_id: "adamant",
+            icon: None,
+            startup_notify: true,
+        }
+    }
+}
+
+/// The program started in the window and its environment.
+///
+/// The environment applies to programs given with `adamant -e` too, e.g.
+/// for a zsh that isn't the login shell, started in its own settings:
+///
+/// ```
+/// use adamant::config::ShellConfig;
+///
+/// let shell = ShellConfig {
+///     program: Some("/usr/bin/zsh"),
+///     env: &[("ZDOTDIR", "/home/me/.config/zsh")],
+///     ..ShellConfig::default()
+/// };
+/// ```
+#[derive(Clone, Debug)]
+pub struct ShellConfig {
+    /// Program to run, looked up in `PATH`. `None` is `$SHELL`, or the
+    /// account's shell when that isn't set
+    pub program: Option<&'static str>,
+    /// Arguments after the program name
+    pub args: &'static [&'static str],
+    /// Start it as a login shell, which reads `~/.profile` and friends.
+    /// `$SHELL` gets a `-` in front of its name like `login` does; a
+    /// configured program gets `-l` first, which bash, zsh and fish know
+    pub login: bool,
+    /// Value of `TERM`, which tells programs the escape sequences they may
+    /// use. `adamant` is installed on first run and falls back to
+    /// `xterm-256color` when it can't be (see `crate::terminfo`)
+    pub term: &'static str,
+    /// Value of `COLORTERM`; `truecolor` announces 24-bit colors
+    pub colorterm: &'static str,
+    /// Further variables, set after the ones above so they can override
+    /// them
+    pub env: &'static [(&'static str, &'static str)],
+    /// Where the shell starts (`~/` is the home directory). `None` is the
+    /// home directory
+    pub directory: Option<&'static str>,
+    /// Register the session in utmp and wtmp, so `who`, `w` and `last`
+    /// list it. Needs permission to write them, e.g. a binary setgid
+    /// `utmp` (glibc Linux and macOS)
+    pub utmp: bool,
+    /// What the program and its jobs are sent when the terminal closes
+    pub close_signal: CloseSignal,
+}
+
+/// The signals stopping the program when its terminal closes: the window
+/// closing, or restarting the program. They go to every process group of
+/// the terminal's session, so jobs the shell started get them too, even
+/// after the shell exited (Ctrl+D).
+///
+/// Unix only: on Windows the program is terminated.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CloseSignal {
+    /// `SIGHUP`, like a hung-up terminal
+    #[default]
+    Hangup,
+    /// `SIGTERM`, then `SIGKILL` for whatever still runs after `grace_ms`.
+    /// Closing the last window waits for that before Adamant exits
+    Terminate { grace_ms: u64 },
+}
+
+impl Default for ShellConfig {
+    fn default() -> Self {
+        Self {
+            program: None,
+            args: &[],
+            login: true,
+            term: crate::terminfo::NAME,
+            colorterm: "truecolor",
+            env: &[],
+            directory: None,
+            utmp: false,
+            close_signal: CloseSignal::Hangup,
+        }
+    }
+}
+
+/// Hints mode (Ctrl+Shift+E): every match of the patterns on screen gets a
+/// short label; typing a label runs the pattern's action on the match, or
+/// pastes it when the label is typed in upper case. Right-clicking a match
+/// runs its action too.
+///
+/// Patterns for your own kinds of text go into `patterns`, e.g. ticket IDs
+/// that open in the tracker:
+///
+/// ```
+/// use adamant::config::{HintAction, HintPattern, HintsConfig};
+///
+/// let mut hints = HintsConfig::default();
+/// hints.patterns.insert(0, HintPattern {
+///     name: "ticket",
+///     regex: r"\b(?P<project>[A-Z]{2,})-(?P<id>\d+)\b",
+///     action: HintAction::OpenUrl("https://tracker.example.com/${project}/issues/${id}"),
+/// });
+/// ```
+#[derive(Clone, Debug)]
+pub struct HintsConfig {
+    /// Characters labels are made of, easiest to reach first
+    pub alphabet: &'static str,
+    /// What to look for. Earlier patterns win where matches overlap
+    pub patterns: Vec<HintPattern>,
+}
+
+impl Default for HintsConfig {
+    fn default() -> Self {
+        Self {
+            alphabet: "asdfghjklqwertyuiopzxcvbnm",
+            patterns: vec![
+                HintPattern {
+                    name: "url",
+                    regex: r#"(?:https?|ftp|file)://[^\s<>"'`]+"#,
+                    action: HintAction::Open,
+                },
+                HintPattern {
+                    name: "uuid",
+                    regex: r"\b[0-9a-fA-F]{8}-(?:[0-9a-fA-F]{4}-){3}[0-9a-fA-F]{12}\b",
+                    action: HintAction::Copy,
+                },
+                HintPattern {
+                    name: "ip",
+                    regex: r"\b(?:\d{1,3}\.){3}\d{1,3}(?::\d+)?\b|\[[0-9a-fA-F:]*:[0-9a-fA-F:.]+\](?::\d+)?",
+                    action: HintAction::Copy,
+                },
+                // `file.rs:12:5`, as compilers and `grep -n` print them
+                HintPattern {
+                    name: "file-line",
+                    regex: r"(?P<path>(?:~|\.{1,2})?/[\w.@~+/-]+|[\w.@~+-]+(?:/[\w.@~+-]+)*\.[A-Za-z]\w*):(?P<line>\d+)(?::(?P<column>\d+))?",
+                    action: HintAction::Edit,
+                },
+                HintPattern {
+                    name: "path",
+                    regex: r"(?:~|\.{1,2})?/[\w.@~+/-]+|[\w.@~+-]+/[\w.@~+/-]+",
+                    action: HintAction::Copy,
+                },
+                HintPattern {
+                    name: "hash",
+                    regex: r"\b[0-9]*[a-f][0-9a-f]{6,63}\b",
+                    action: HintAction::Copy,
+                },
+            ],
+        }
+    }
+}
+
+/// A kind of text hints mode can pick.
+#[derive(Clone, Debug)]
+pub struct HintPattern {
+    pub name: &'static str,
+    /// A regular expression (`regex` crate syntax) matched on each line.
+    /// The hint covers the whole match, or only the group named `text` if
+    /// there is one (`key=(?P<text>\w+)` picks the value).
+    pub regex: &'static str,
+    pub action: HintAction,
+}
+
+/// What happens to a picked hint.
+///
+/// Templates can refer to the pattern's capture groups: `$0` is the whole
+/// match, `$1`, `$2`, ... are the numbered groups and `${name}` the named
+/// ones (see `regex::Captures::expand`).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HintAction {
+    /// Copy to the clipboard
+    Copy,
+    /// Open with the desktop's default handler (`xdg-open`, `open`, `ShellExecuteW`)
+    Open,
+    /// Type into the terminal, as if pasted
+    Paste,
+    /// Open the URL made from a template with the default handler. The
+    /// groups are percent-encoded, so text on screen can't add to the URL
+    /// beyond its own part: `a&b` becomes `a%26b`
+    OpenUrl(&'static str),
+    /// Open the file in `$EDITOR` (in a new window), at the line and
+    /// column in the groups named `line` and `column`; the group named
+    /// `path`, or else the match, is the file. Relative paths start from
+    /// the directory the shell reported (OSC 7)
+    Edit,
+    /// Run a program, given as templates for the program and its arguments.
+    /// It runs in the background; its output is not shown
+    Run(&'static [&'static str]),
+}
+
+/// A command of your own, listed in the command palette next to the
+/// built-in actions:
+///
+/// ```
+/// use adamant::config::{CommandAction, Config, UserCommand};
+///
+/// let mut config = Config::default();
+/// config.commands = vec![
+///     UserCommand {
+///         name: "Git status",
+///         action: CommandAction::Input("git status\r"),
+///     },
+///     UserCommand {
+///         name: "Lock screen",
+///         action: CommandAction::Run(&["loginctl", "lock-session"]),
+///     },
+///     UserCommand {
+///         name: "Send output to a paste bin",
+///         action: CommandAction::PipeOutput(&["curl", "-F", "file=@-", "https://0x0.st"]),
+///     },
+/// ];
+/// assert!(config.validate().is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct UserCommand {
+    /// What the palette shows and filters on
+    pub name: &'static str,
+    pub action: CommandAction,
+}
+
+/// What a [`UserCommand`] does.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CommandAction {
+    /// Send text to the program as if typed (`\r` is Enter)
+    Input(&'static str),
+    /// Run a program with arguments in the background; its output is not
+    /// shown
+    Run(&'static [&'static str]),
+    /// Run a program like `Run`, with the output of the last command that
+    /// finished (see `Grid::last_output`) on its standard input
+    PipeOutput(&'static [&'static str]),
+}
+
+/// Keys bound to an action, checked before the built-in shortcuts (see
+/// `crate::bindings`). `keys` holds one key, or a sequence of them
+/// separated by spaces, each written like `Ctrl+Shift+Up` (see
+/// `crate::hotkey::Hotkey::parse`) or as a single character such as `|`:
+///
+/// ```
+/// use adamant::config::{Config, HookAction, KeyBinding};
+///
+/// let mut config = Config::default();
+/// config.key_bindings = vec![
+///     KeyBinding {
+///         keys: "Ctrl+A c",
+///         action: HookAction::Command("New window".into()),
+///     },
+///     KeyBinding {
+///         keys: "Ctrl+A +",
+///         action: HookAction::Command("Zoom in".into()),
+///     },
+///     // The leader twice sends it to the program
+///     KeyBinding {
+///         keys: "Ctrl+A Ctrl+A",
+///         action: HookAction::Input("\x01".into()),
+///     },
+///     KeyBinding {
+///         keys: "Alt+Shift+L",
+///         action: HookAction::Run(vec!["loginctl".into(), "lock-session".into()]),
+///     },
+/// ];
+/// assert!(config.validate().is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct KeyBinding {
+    pub keys: &'static str,
+    pub action: HookAction,
+}
+
+/// Keys that go to the program instead of running Adamant's shortcuts
+/// while it runs in the foreground (see `crate::passthrough`).
+///
+/// ```
+/// use adamant::config::{Config, KeyPassthrough};
+///
+/// let mut config = Config::default();
+/// config.passthrough = vec![KeyPassthrough {
+///     programs: &["tmux", "vim", "nvim"],
+///     keys: &["Ctrl+Shift+Up", "Ctrl+Shift+Down", "Ctrl+Shift+Left", "Ctrl+Shift+Right"],
+/// }];
+/// ```
+#[derive(Clone, Debug)]
+pub struct KeyPassthrough {
+    /// Names of the programs as the system lists them (`tmux`, `vim`);
+    /// `tmux` also matches its client renamed `tmux: client`
+    pub programs: &'static [&'static str],
+    /// Shortcuts written like `Ctrl+Shift+Up` (see
+    /// `crate::hotkey::Hotkey::parse`)
+    pub keys: &'static [&'static str],
+}
+
+/// What BEL does.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Bell {
+    #[default]
+    Ignore,
+    /// Ask for attention (taskbar flash, dock bounce) while the window
+    /// isn't focused
+    Attention,
+}
+
+/// What a watched window reports (`Config::monitor`, the palette, IPC
+/// `monitor`).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Monitor {
+    /// Output once the window was watched, or after `Config::silence_ms`
+    /// without any: a long build printing again, a log getting a line
+    Activity,
+    /// No output for `Config::silence_ms` after some: a download or a
+    /// build finished
+    Silence,
+}
+
+/// A pattern looked for in every line of output as it is printed, and
+/// what to do when a line matches:
+///
+/// ```
+/// use adamant::config::{Config, WatchAction, WatchRule};
+///
+/// let mut config = Config::default();
+/// config.watch_rules = vec![
+///     WatchRule {
+///         name: "error",
+///         regex: r"(?i)\berror\b",
+///         action: WatchAction::Highlight,
+///     },
+///     WatchRule {
+///         name: "tests failed",
+///         regex: r"^test result: FAILED",
+///         action: WatchAction::Notify,
+///     },
+///     WatchRule {
+///         name: "deployed",
+///         regex: r"deployed (?P<version>v[\d.]+)",
+///         action: WatchAction::Run(&["notify-send", "Deployed ${version}"]),
+///     },
+/// ];
+/// ```
+///
+/// A line is matched once it is complete (the cursor moved past it), with
+/// its soft-wrapped rows joined. For more than one action on the same
+/// lines, add a rule for each. Full-screen programs on the alternate
+/// screen are not watched.
+#[derive(Clone, Debug)]
+pub struct WatchRule {
+    pub name: &'static str,
+    /// A regular expression (`regex` crate syntax)
+    pub regex: &'static str,
+    pub action: WatchAction,
+}
+
+/// What a [`WatchRule`] does with a matching line. All but `Highlight`
+/// happen at most once per rule for each batch of output, so a flood of
+/// matching lines doesn't start a flood of programs.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WatchAction {
+    /// Give the line a colored background, kept while it scrolls back
+    Highlight,
+    /// Show a notification with the rule's name and the line, and ask for
+    /// attention while the window isn't focused
+    Notify,
+    /// Play a sound file (`afplay` on macOS, `paplay` elsewhere, PowerShell
+    /// on Windows)
+    Sound(&'static str),
+    /// Run a program in the background, given as templates for the program
+    /// and its arguments that can refer to capture groups like
+    /// [`HintAction::Run`]
+    Run(&'static [&'static str]),
+}
+
+/// Functions of your own called on events, for behavior the settings
+/// don't cover. They are plain Rust, compiled in like the rest of the
+/// configuration, and answer with [`HookAction`]s: anything in the command
+/// palette, input for the program, notifications and programs to run.
+///
+/// ```
+/// use adamant::config::{Config, HookAction, Hooks};
+///
+/// let mut config = Config::default();
+/// config.hooks = Hooks {
+///     // Configuration values computed when Adamant starts
+///     on_config: Some(|config| {
+///         if std::env::var_os("SSH_CONNECTION").is_some() {
+///             config.scrollback_lines = 1_000;
+///         }
+///     }),
+///     // Lock windows running a production console
+///     on_title_change: Some(|title| {
+///         if title.contains("prod") {
+///             vec![HookAction::Command("Toggle read-only".into())]
+///         } else {
+///             Vec::new()
+///         }
+///     }),
+///     on_output: Some(|line| match line.strip_prefix("Listening on ") {
+///         Some(url) => vec![HookAction::Run(vec!["xdg-open".into(), url.into()])],
+///         None => Vec::new(),
+///     }),
+///     ..Hooks::default()
+/// };
+/// assert!(config.validate().is_ok());
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Hooks {
+    /// Change the configuration when Adamant starts, after the command
+    /// line options were applied
+    pub on_config: Option<fn(&mut Config)>,
+    /// Once the window is open and its program started
+    pub on_startup: Option<fn() -> Vec<HookAction>>,
+    /// For every line of output once it is complete, like
+    /// [`WatchRule`]s. Full-screen programs on the alternate screen are
+    /// not watched
+    pub on_output: Option<fn(&str) -> Vec<HookAction>>,
+    /// When the program sets the window title
+    pub on_title_change: Option<fn(&str) -> Vec<HookAction>>,
+    /// When the window gains (`true`) or loses focus. There are no tabs,
+    /// so this is also what switching to another terminal looks like
+    pub on_focus_change: Option<fn(bool) -> Vec<HookAction>>,
+}
+
+/// What a [`Hooks`] function asks for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HookAction {
+    /// Run the command palette entry of this name, e.g. `"Toggle
+    /// fullscreen"`, `"New window: work"` or one of `Config::commands`
+    Command(String),
+    /// Type into the terminal
+    Input(String),
+    /// Show a notification over the terminal
+    Notify(String),
+    /// Run a program with arguments in the background
+    Run(Vec<String>),
+}
+
+/// Settings for the sessions on some hosts, e.g. a red tint and a marked
+/// title while connected to production. The shell reports its host with
+/// OSC 7 (most shell integrations, `file://host/cwd`) or iTerm2's
+/// `RemoteHost` (OSC 1337), so the remote shell has to send one of them;
+/// the profile is dropped when a host without one reports:
+///
+/// ```
+/// use adamant::config::{Bell, Colors, Config, HostProfile, Rgb, Tint};
+///
+/// let mut config = Config::default();
+/// config.profiles = vec![HostProfile {
+///     name: "production",
+///     hosts: &["*.prod.example.com", "db1"],
+///     title: Some("[PROD] {host}: {title}"),
+///     colors: Some(Colors {
+///         tint: Some(Tint {
+///             color: Rgb(0xff, 0, 0),
+///             amount: 0.15,
+///         }),
+///         ..Colors::default()
+///     }),
+///     bell: Some(Bell::Attention),
+/// }];
+/// assert!(config.validate().is_ok());
+///
+/// let profile = config.profile_for("deploy@web3.prod.example.com").unwrap();
+/// assert_eq!(profile.name, "production");
+/// assert_eq!(profile.title("vim", "web3.prod.example.com"), "[PROD] web3.prod.example.com: vim");
+/// assert!(config.profile_for("web3.staging.example.com").is_none());
+/// ```
+#[derive(Clone, Debug)]
+pub struct HostProfile {
+    /// For logs and the toast shown when it applies
+    pub name: &'static str,
+    /// Host names, matched without the user name and ignoring case. `*`
+    /// stands for any text
+    pub hosts: &'static [&'static str],
+    /// The window title, with the same placeholders as `Config::title`
+    pub title: Option<&'static str>,
+    /// Replaces `Config::colors`, except the background opacity
+    pub colors: Option<Colors>,
+    /// Replaces `Config::bell`
+    pub bell: Option<Bell>,
+}
+
+impl HostProfile {
+    /// Whether the profile is for `host` (`user@host` or `host`).
+    pub fn matches(&self, host: &str) -> bool {
+        let host = host.rsplit('@').next().unwrap_or(host).to_lowercase();
+        self.hosts
+            .iter()
+            .any(|pattern| glob_match(&pattern.to_lowercase(), &host))
+    }
+
+    /// The window title for the program's `title` on `host`.
+    pub fn title(&self, title: &str, host: &str) -> String {
+        match self.title {
+            Some(template) => expand_title(template, title, host, ""),
+            None => title.to_string(),
+        }
+    }
+}
+
+/// Whether `text` matches `pattern`, where `*` matches any text.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    // Without a `*` the whole text has to match
+    let Some(last) = parts.pop() else {
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Settings for while a program runs in the foreground, or the window
+/// title matches, e.g. no PRIMARY copies while selecting in vim, or a
+/// beam cursor and another theme in an editor. The foreground programs
+/// are looked up a few times a second; the first matching profile
+/// applies until none does.
+///
+/// ```
+/// use adamant::config::{AppProfile, Config};
+/// use adamant::term::{CursorShape, CursorStyle};
+///
+/// let mut config = Config::default();
+/// config.app_profiles = vec![AppProfile {
+///     name: "vim",
+///     programs: &["vim", "nvim"],
+///     titles: &["* - VIM"],
+///     primary_selection: Some(false),
+///     cursor: Some(CursorStyle {
+///         shape: CursorShape::Beam,
+///         blinking: false,
+///     }),
+///     ..AppProfile::default()
+/// }];
+/// assert!(config.validate().is_ok());
+///
+/// let vim = config.app_profile_for("zsh", &["nvim".to_string()]).unwrap();
+/// assert_eq!(vim.name, "vim");
+/// assert!(config.app_profile_for("notes.txt - VIM", &[]).is_some());
+/// assert!(config.app_profile_for("zsh", &["zsh".to_string()]).is_none());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct AppProfile {
+    /// For logs
+    pub name: &'static str,
+    /// Foreground programs it applies to, named as with
+    /// [`KeyPassthrough::programs`]
+    pub programs: &'static [&'static str],
+    /// Titles it applies to, ignoring case. `*` stands for any text
+    pub titles: &'static [&'static str],
+    /// Replaces the colors, except the background opacity (and a host
+    /// profile's)
+    pub colors: Option<Colors>,
+    /// The cursor drawn whatever the program asks for (DECSCUSR)
+    pub cursor: Option<crate::term::CursorStyle>,
+    /// Mouse events don't go to the program even when it asks for them:
+    /// drags select and the wheel scrolls
+    pub disable_mouse_reporting: bool,
+    /// Replaces `MouseConfig::primary_selection`
+    pub primary_selection: Option<bool>,
+    /// Shortcuts that go to the program instead (see [`KeyPassthrough`])
+    pub passthrough: &'static [&'static str],
+}
+
+impl AppProfile {
+    /// Whether the profile is for a window titled `title` running
+    /// `programs` in the foreground.
+    pub fn matches(&self, title: &str, programs: &[String]) -> bool {
+        let title = title.to_lowercase();
+        self.titles
+            .iter()
+            .any(|pattern| glob_match(&pattern.to_lowercase(), &title))
+            || self.programs.iter().any(|program| {
+                programs
+                    .iter()
+                    .any(|name| crate::backend::is_program(name, program))
+            })
+    }
+}
+
+/// `template` (`Config::title`) with its placeholders filled in.
+///
+/// ```
+/// use adamant::config::expand_title;
+///
+/// let title = expand_title("{title} - {host}:{cwd}", "vim", "db1", "/srv");
+/// assert_eq!(title, "vim - db1:/srv");
+/// ```
+pub fn expand_title(template: &str, title: &str, host: &str, directory: &str) -> String {
+    template
+        .replace("{host}", host)
+        .replace("{cwd}", directory)
+        .replace("{title}", title)
+}
+
+/// A named way to start a window: its program, directory, environment,
+/// colors and font. Unset parts are the configuration's:
+///
+/// ```
+/// use adamant::config::{Config, LaunchProfile};
+///
+/// let mut config = Config::default();
+/// config.launch_profiles = vec![LaunchProfile {
+///     name: "work",
+///     command: &["ssh", "work.example.com"],
+///     directory: Some("~/work"),
+///     env: &[("AWS_PROFILE", "work")],
+///     ..LaunchProfile::default()
+/// }];
+/// assert!(config.validate().is_ok());
+///
+/// config.use_launch_profile("work").unwrap();
+/// assert_eq!(config.shell.program, Some("ssh"));
+/// assert_eq!(config.shell.args, ["work.example.com"]);
+/// assert!(config.use_launch_profile("home").is_err());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct LaunchProfile {
+    /// For `--profile`, and what the palette and menu show
+    pub name: &'static str,
+    /// The program and its arguments, run instead of the shell (and not
+    /// as a login shell). Empty runs `ShellConfig::program`
+    pub command: &'static [&'static str],
+    /// Replaces `ShellConfig::directory`
+    pub directory: Option<&'static str>,
+    /// Set after `ShellConfig::env`
+    pub env: &'static [(&'static str, &'static str)],
+    /// Replace `Config::colors` and `Config::font`
+    pub colors: Option<Colors>,
+    pub font: Option<FontConfig>,
+    /// Open read-only (`Config::read_only`)
+    pub read_only: bool,
+    /// Replaces `Config::monitor`
+    pub monitor: Option<Monitor>,
+}
+
+impl Config {
+    /// The profile for a window titled `title` running `programs` in the
+    /// foreground: the first one matching.
+    pub fn app_profile_for(&self, title: &str, programs: &[String]) -> Option<&AppProfile> {
+        (self.app_profiles.iter()).find(|profile| profile.matches(title, programs))
+    }
+
+    /// Start with the launch profile called `name` (`adamant --profile`).
+    pub fn use_launch_profile(&mut self, name: &str) -> Result<(), AdamantError> {
+        let profile = self
+            .launch_profiles
+            .iter()
+            .find(|profile| profile.name == name)
+            .cloned()
+            .ok_or_else(|| AdamantError::Config(format!("no launch profile {:?}", name)))?;
+        if let [program, args @ ..] = profile.command {
+            self.shell.program = Some(program);
+            self.shell.args = args;
+            self.shell.login = false;
+        }
+        if profile.directory.is_some() {
+            self.shell.directory = profile.directory;
+        }
+        if !profile.env.is_empty() {
+            // The configuration is static; this lives as long as the process
+            self.shell.env = [self.shell.env, profile.env].concat().leak();
+        }
+        if let Some(colors) = profile.colors {
+            self.colors = colors;
+        }
+        if let Some(font) = profile.font {
+            self.font = font;
+        }
+        self.read_only |= profile.read_only;
+        if profile.monitor.is_some() {
+            self.monitor = profile.monitor;
+        }
+        Ok(())
+    }
+
+    /// The profile for sessions on `host`: the first one matching it.
+    pub fn profile_for(&self, host: &str) -> Option<&HostProfile> {
+        self.profiles.iter().find(|profile| profile.matches(host))
+    }
+
+    /// The configuration for `adamant --safe-mode`: the defaults minus
+    /// everything that depends on the machine beyond a font and a GPU, for
+    /// when a customized build doesn't start.
+    ///
+    /// There is no config file or plugin directory to skip, so this turns
+    /// off window transparency (compositor support varies), the fallback
+    /// fonts, post-processing (the shader and CRT effect), the IPC socket,
+    /// and the hooks and plugins compiled in.
+    pub fn safe_mode() -> Self {
+        let mut config = Self::default();
+        config.colors.background_opacity = 1.0;
+        config.font.fallback.clear();
+        config.shader = None;
+        config.crt.enabled = false;
+        config.ipc = false;
+        config.hooks = Hooks::default();
+        #[cfg(feature = "gui")]
+        config.plugins.clear();
+        config
+    }
+
+    /// Check the values the types can't, so a bad edit fails at startup with
+    /// a message instead of rendering garbage.
+    pub fn validate(&self) -> Result<(), AdamantError> {
+        let size = self.font.size;
+        if !(size.is_finite() && size > 0.0) {
+            return Err(AdamantError::Config(format!(
+                "font.size must be positive, got {}",
+                size
+            )));
+        }
+        let opacity = self.colors.background_opacity;
+        if !(0.0..=1.0).contains(&opacity) {
+            return Err(AdamantError::Config(format!(
+                "colors.background_opacity must be between 0 and 1, got {}",
+                opacity
+            )));
+        }
+        if self.font.regular.is_empty() {
+            return Err(AdamantError::Font("font.regular"));
+        }
+        for (tag, _) in &self.font.features {
+            if tag.len() != 4 || !tag.is_ascii() {
+                return Err(AdamantError::Config(format!(
+                    "font features need 4-letter tags, got {}",
+                    tag
+                )));
+            }
+        }
+        for font in &self.font.overrides {
+            if let Some((first, last)) = font.ranges.iter().find(|(first, last)| first > last) {
+                return Err(AdamantError::Config(format!(
+                    "font override ranges need their first character before their last, got {:?} to {:?}",
+                    first, last
+                )));
+            }
+            if let Some(tag) = font
+                .scripts
+                .iter()
+                .find(|tag| tag.len() != 4 || !tag.is_ascii())
+            {
+                return Err(AdamantError::Config(format!(
+                    "font override scripts need 4-letter tags, got {}",
+                    tag
+                )));
+            }
+            if font.font.is_empty() {
+                return Err(AdamantError::Config(
+                    "font overrides need at least one font".into(),
+                ));
+            }
+        }
+        let axes = (self.font.variations.iter()).chain(&self.font.bold_variations);
+        for (tag, value) in axes {
+            if tag.len() != 4 || !tag.is_ascii() || !value.is_finite() {
+                return Err(AdamantError::Config(format!(
+                    "font variations need 4-letter axis tags and finite values, got {} = {}",
+                    tag, value
+                )));
+            }
+        }
+        let height = self.dropdown.height;
+        if !(height > 0.0 && height <= 1.0) {
+            return Err(AdamantError::Config(format!(
+                "dropdown.height must be above 0 and at most 1, got {}",
+                height
+            )));
+        }
+        if self.key_sequence_timeout_ms == 0 {
+            return Err(AdamantError::Config(
+                "key_sequence_timeout_ms must be positive".into(),
+            ));
+        }
+        if self.silence_ms == 0 {
+            return Err(AdamantError::Config("silence_ms must be positive".into()));
+        }
+        if self.status_bar.interval_ms == 0 {
+            return Err(AdamantError::Config(
+                "status_bar.interval_ms must be positive".into(),
+            ));
+        }
+        if self
+            .status_bar
+            .segments
+            .contains(&StatusSegment::Command(&[]))
+        {
+            return Err(AdamantError::Config(
+                "status bar Command segments need a program".into(),
+            ));
+        }
+        if let Some(tint) = self.colors.tint
+            && !(0.0..=1.0).contains(&tint.amount)
+        {
+            return Err(AdamantError::Config(format!(
+                "colors.tint.amount must be between 0 and 1, got {}",
+                tint.amount
+            )));
+        }
+        let crt = &self.crt;
+        for (name, value) in [
+            ("scanlines", crt.scanlines),
+            ("bloom", crt.bloom),
+            ("curvature", crt.curvature),
+            ("persistence", crt.persistence),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(AdamantError::Config(format!(
+                    "crt.{} must be between 0 and 1, got {}",
+                    name, value
+                )));
+            }
+        }
+        if self.dropdown.enabled && self.window_mode != WindowMode::Windowed {
+            return Err(AdamantError::Config(format!(
+                "a dropdown window can't start {:?}",
+                self.window_mode
+            )));
+        }
+        let app_id = self.desktop.app_id;
+        if app_id.is_empty() || app_id.contains(char::is_control) {
+            return Err(AdamantError::Config(format!(
+                "desktop.app_id must be a non-empty name, got {:?}",
+                app_id
+            )));
+        }
+        if self.shell.program == Some("") {
+            return Err(AdamantError::Config("shell.program is empty".into()));
+        }
+        if self.shell.directory == Some("") {
+            return Err(AdamantError::Config("shell.directory is empty".into()));
+        }
+        if self.shell.term.is_empty() {
+            return Err(AdamantError::Config("shell.term is empty".into()));
+        }
+        if let Some((name, _)) = self
+            .shell
+            .env
+            .iter()
+            .find(|(name, _)| name.is_empty() || name.contains(['=', '\0']))
+        {
+            return Err(AdamantError::Config(format!(
+                "shell.env has an invalid variable name {:?}",
+                name
+            )));
+        }
+        let mut alphabet: Vec<char> = self.hints.alphabet.chars().collect();
+        alphabet.sort_unstable();
+        alphabet.dedup();
+        if alphabet.len() < 2 || alphabet.iter().any(|c| !c.is_lowercase()) {
+            return Err(AdamantError::Config(format!(
+                "hints.alphabet needs at least two distinct lower case letters, got {:?}",
+                self.hints.alphabet
+            )));
+        }
+        for profile in &self.profiles {
+            if profile.hosts.iter().any(|host| host.is_empty()) || profile.hosts.is_empty() {
+                return Err(AdamantError::Config(format!(
+                    "profile {:?} needs non-empty host names",
+                    profile.name
+                )));
+            }
+            if let Some(tint) = profile.colors.as_ref().and_then(|colors| colors.tint)
+                && !(0.0..=1.0).contains(&tint.amount)
+            {
+                return Err(AdamantError::Config(format!(
+                    "profile {:?} has a tint amount of {}, not between 0 and 1",
+                    profile.name, tint.amount
+                )));
+            }
+        }
+        for profile in &self.app_profiles {
+            if profile.programs.is_empty() && profile.titles.is_empty()
+                || profile.programs.contains(&"")
+                || profile.titles.contains(&"")
+            {
+                return Err(AdamantError::Config(format!(
+                    "app profile {:?} needs non-empty program names or titles",
+                    profile.name
+                )));
+            }
+            if let Some(tint) = profile.colors.as_ref().and_then(|colors| colors.tint)
+                && !(0.0..=1.0).contains(&tint.amount)
+            {
+                return Err(AdamantError::Config(format!(
+                    "app profile {:?} has a tint amount of {}, not between 0 and 1",
+                    profile.name, tint.amount
+                )));
+            }
+        }
+        for (i, profile) in self.launch_profiles.iter().enumerate() {
+            let name = profile.name;
+            if name.is_empty() || name.starts_with('-') || name.contains(char::is_control) {
+                return Err(AdamantError::Config(format!(
+                    "launch profile {:?} needs a name that can follow --profile",
+                    name
+                )));
+            }
+            if self.launch_profiles[..i]
+                .iter()
+                .any(|other| other.name == name)
+            {
+                return Err(AdamantError::Config(format!(
+                    "launch profile {:?} is defined twice",
+                    name
+                )));
+            }
+            if profile.command.first() == Some(&"") || profile.directory == Some("") {
+                return Err(AdamantError::Config(format!(
+                    "launch profile {:?} has an empty program or directory",
+                    name
+                )));
+            }
+        }
+        if let Some(rule) = self
+            .passthrough
+            .iter()
+            .find(|rule| rule.programs.is_empty() || rule.programs.contains(&""))
+        {
+            return Err(AdamantError::Config(format!(
+                "passthrough for {:?} needs non-empty program names",
+                rule.keys
+            )));
+        }
+        if let Some(command) = self.commands.iter().find(|command| {
+            matches!(
+                command.action,
+                CommandAction::Run(&[]) | CommandAction::PipeOutput(&[])
+            )
+        }) {
+            return Err(AdamantError::Config(format!(
+                "command {:?} has nothing to run",
+                command.name
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Where a font comes from.
+#[derive(Copy, Clone, Debug)]
+pub enum FontSource {
+    /// A font file on disk (collections use their first face)
+    Path(&'static str),
+    /// Font data compiled into the binary, e.g.
+    /// `FontSource::Embedded(include_bytes!("../fonts/Mono.ttf"))`.
+    /// Required in the browser, which has no font files to read.
+    Embedded(&'static [u8]),
+}
+
+/// Font selection.
+///
+/// Each list holds candidate fonts; the first one that loads is used, so
+/// the same build works on Linux, macOS and Windows.
+///
+/// A variable font can be both faces, at its own axis values for each:
+///
+/// ```
+/// use adamant::config::{Config, FontSource};
+///
+/// let mut config = Config::default();
+/// config.font.regular = vec![FontSource::Path("/usr/share/fonts/Recursive.ttf")];
+/// config.font.bold = Vec::new();
+/// config.font.variations = vec![("wght", 400.0), ("slnt", -8.0)];
+/// config.font.bold_variations = vec![("wght", 750.0), ("slnt", -8.0)];
+/// assert!(config.validate().is_ok());
+///
+/// config.font.variations = vec![("weight", 400.0)];
+/// assert!(config.validate().is_err());
+/// ```
+///
+/// OpenType features choose among the shapes coding fonts offer:
+///
+/// ```
+/// use adamant::config::Config;
+///
+/// let mut config = Config::default();
+/// config.font.ligatures = true;
+/// config.font.features = vec![("ss01", true), ("zero", true), ("dlig", false)];
+/// assert!(config.validate().is_ok());
+/// ```
+///
+/// Overrides pick the font of some characters ahead of everything else,
+/// such as a CJK font for Han and kana, or a Nerd Font for the icons in
+/// the private use area:
+///
+/// ```
+/// use adamant::config::{Config, FontOverride, FontSource};
+///
+/// let mut config = Config::default();
+/// config.font.overrides = vec![
+///     FontOverride {
+///         ranges: Vec::new(),
+///         scripts: vec!["hani", "kana"],
+///         font: vec![FontSource::Path("/usr/share/fonts/SourceHanSans-Regular.otf")],
+///     },
+///     FontOverride {
+///         ranges: vec![('\u{e000}', '\u{f8ff}')],
+///         scripts: Vec::new(),
+///         font: vec![FontSource::Path("/usr/share/fonts/SymbolsNerdFontMono.ttf")],
+///     },
+/// ];
+/// assert!(config.validate().is_ok());
+///
+/// config.font.overrides[1].ranges = vec![('\u{f8ff}', '\u{e000}')];
+/// assert!(config.validate().is_err());
+/// ```
+#[derive(Clone, Debug)]
+pub struct FontConfig {
+    /// Font size in logical pixels (multiplied by the window scale factor)
+    pub size: f32,
+    pub regular: Vec<FontSource>,
+    pub bold: Vec<FontSource>,
+    /// Fonts tried in order for characters missing from the primary font
+    pub fallback: Vec<FontSource>,
+    /// Axis values of a variable font for regular text, such as
+    /// `("wght", 450.0)`, `("wdth", 90.0)` or `("slnt", -8.0)`. Axes the
+    /// font doesn't have are ignored
+    pub variations: Vec<(&'static str, f32)>,
+    /// Axis values for bold text, e.g. `("wght", 700.0)` where a variable
+    /// font is both faces. Empty uses `variations`, and bold text without
+    /// a bold face is emboldened
+    pub bold_variations: Vec<(&'static str, f32)>,
+    /// Draw the glyphs the font puts in place of some runs of characters,
+    /// such as an arrow for `->` in coding fonts (see `crate::ligatures`)
+    pub ligatures: bool,
+    /// OpenType features of the regular and bold faces turned on or off,
+    /// such as `("ss01", true)` for a stylistic set, `("zero", true)` for a
+    /// slashed zero or `("calt", false)`. They win over `ligatures`
+    pub features: Vec<(&'static str, bool)>,
+    /// Fonts for some characters, tried in order before the regular and
+    /// bold faces and the fallback fonts
+    pub overrides: Vec<FontOverride>,
+}
+
+/// A font for some characters, ahead of the generic order. Characters it
+/// doesn't have fall through to the other fonts.
+#[derive(Clone, Debug)]
+pub struct FontOverride {
+    /// Ranges of characters, first and last included, such as
+    /// `('\u{e000}', '\u{f8ff}')` for the private use area
+    pub ranges: Vec<(char, char)>,
+    /// Scripts, by OpenType script tag: `"hani"` for Han, `"kana"` for
+    /// hiragana and katakana, `"hang"` for Hangul, `"arab"` for Arabic.
+    /// Unknown tags are ignored, with a log message
+    pub scripts: Vec<&'static str>,
+    /// Candidate fonts; the first one that loads is used, drawn at its
+    /// default axis values and emboldened for bold text
+    pub font: Vec<FontSource>,
+}
+
+impl Default for FontConfig {
+    fn default() -> Self {
+        Self {
+            size: 16.0,
+            regular: vec![
+                FontSource::Path("/usr/share/fonts/truetype/dejavu/DejaVuSansMono.ttf"),
+                FontSource::Path("/usr/share/fonts/TTF/DejaVuSansMono.ttf"),
+                FontSource::Path("/System/Library/Fonts/Menlo.ttc"),
+                FontSource::Path("C:\\Windows\\Fonts\\consola.ttf"),
+            ],
+            bold: vec![
+                FontSource::Path("/usr/share/fonts/truetype/dejavu/DejaVuSansMono-Bold.ttf"),
+                FontSource::Path("/usr/share/fonts/TTF/DejaVuSansMono-Bold.ttf"),
+                FontSource::Path("C:\\Windows\\Fonts\\consolab.ttf"),
+            ],
+            fallback: vec![
+                FontSource::Path("/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc"),
+                FontSource::Path("/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc"),
+                FontSource::Path("/usr/share/fonts/truetype/noto/NotoColorEmoji.ttf"),
+                FontSource::Path("/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf"),
+                FontSource::Path("/System/Library/Fonts/Hiragino Sans GB.ttc"),
+                FontSource::Path("/System/Library/Fonts/Apple Color Emoji.ttc"),
+                FontSource::Path("C:\\Windows\\Fonts\\msgothic.ttc"),
+                FontSource::Path("C:\\Windows\\Fonts\\seguiemj.ttf"),
+            ],
+            variations: Vec::new(),
+            bold_variations: Vec::new(),
+            ligatures: false,
+            features: Vec::new(),
+            overrides: Vec::new(),
+        }
+    }
+}
+
+/// The color scheme.
+#[derive(Clone, Debug)]
+pub struct Colors {
+    pub foreground: Rgb,
+    pub background: Rgb,
+    /// Opacity of the default background (0.0 = fully transparent)
+    pub background_opacity: f32,
+    pub cursor: Rgb,
+    /// The 16 ANSI colors (normal 0-7, bright 8-15)
+    pub ansi: [Rgb; 16],
+    /// A color mixed into the default background, e.g. red for windows
+    /// connected to production machines. Can be changed per session over
+    /// IPC (see `crate::term::ColorOverrides`)
+    pub tint: Option<Tint>,
+}
+
+/// A color mixed into the default background.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tint {
+    pub color: Rgb,
+    /// How much of the tint color: 0 (none) to 1 (only the tint)
+    pub amount: f32,
+}
+
+impl Tint {
+    /// How much of the tint color when only the color is given
+    /// (`adamant --tint`, IPC `tint`)
+    pub const DEFAULT_AMOUNT: f32 = 0.2;
+
+    /// The tint mixed into `color`.
+    pub fn over(&self, color: Rgb) -> Rgb {
+        let mix = |base: u8, tint: u8| {
+            (base as f32 + (tint as f32 - base as f32) * self.amount).round() as u8
+        };
+        Rgb(
+            mix(color.0, self.color.0),
+            mix(color.1, self.color.1),
+            mix(color.2, self.color.2),
+        )
+    }
+}
+
+impl Default for Colors {
+    fn default() -> Self {
+        Self {
+            foreground: Rgb(0xd8, 0xd8, 0xd8),
+            background: Rgb(0x10, 0x10, 0x14),
+            background_opacity: 0.9,
+            cursor: Rgb(0xe0, 0xe0, 0xe0),
+            ansi: [
+                Rgb(0x1d, 0x1f, 0x21),
+                Rgb(0xcc, 0x66, 0x66),
+                Rgb(0xb5, 0xbd, 0x68),
+                Rgb(0xf0, 0xc6, 0x74),
+                Rgb(0x81, 0xa2, 0xbe),
+                Rgb(0xb2, 0x94, 0xbb),
+                Rgb(0x8a, 0xbe, 0xb7),
+                Rgb(0xc5, 0xc8, 0xc6),
+                Rgb(0x66, 0x66, 0x66),
+                Rgb(0xd5, 0x4e, 0x53),
+                Rgb(0xb9, 0xca, 0x4a),
+                Rgb(0xe7, 0xc5, 0x47),
+                Rgb(0x7a, 0xa6, 0xda),
+                Rgb(0xc3, 0x97, 0xd8),
+                Rgb(0x70, 0xc0, 0xb1),
+                Rgb(0xea, 0xea, 0xea),
+            ],
+            tint: None,
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! Desktop Integration
+//!
+//! How window managers, taskbars and launchers see the window
+//! (`Config::desktop`): the app-id their rules and `.desktop` files match
+//! on (`WM_CLASS` on X11), the window icon, and startup notification, the
+//! token a launcher hands over so the new window gets the focus and the
+//! busy cursor stops. A matching `.desktop` file:
+//!
+//! ```text
+//! [Desktop Entry]
+//! Type=Application
+//! Name=Adamant
+//! Exec=adamant
+//! Icon=adamant
+//! StartupNotify=true
+//! StartupWMClass=adamant
+//! ```
+
+use std::io::{BufRead, Seek};
+use std::path::Path;
+
+use crate::error::Result;
+
+/// An icon's pixels, row by row, 4 bytes (RGBA) each.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IconImage {
+    pub rgba: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Read the PNG file at `path` for the window icon.
+pub fn load_icon(path: &Path) -> Result<winit::window::Icon> {
+    let file = std::fs::File::open(path)?;
+    let image = decode_png(std::io::BufReader::new(file))?;
+    winit::window::Icon::from_rgba(image.rgba, image.width, image.height)
+        .map_err(|e| invalid(e.to_string()))
+}
+
+/// Decode a PNG image of any color type and bit depth into 8-bit RGBA.
+pub fn decode_png(reader: impl BufRead + Seek) -> Result<IconImage> {
+    let mut decoder = png::Decoder::new(reader);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|e| invalid(e.to_string()))?;
+    let size = reader
+        .output_buffer_size()
+        .ok_or_else(|| invalid("the image is too large".into()))?;
+    let mut pixels = vec![0; size];
+    let info = reader
+        .next_frame(&mut pixels)
+        .map_err(|e| invalid(e.to_string()))?;
+    pixels.truncate(info.buffer_size());
+
+    let rgba = match info.color_type {
+        png::ColorType::Rgba => pixels,
+        png::ColorType::Rgb => pixels
+            .chunks_exact(3)
+            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 0xff])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => pixels
+            .chunks_exact(2)
+            .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+            .collect(),
+        png::ColorType::Grayscale => pixels.iter().flat_map(|&g| [g, g, g, 0xff]).collect(),
+        // Expanded by the transformations
+        png::ColorType::Indexed => return Err(invalid("unexpected indexed colors".into())),
+    };
+    Ok(IconImage {
+        rgba,
+        width: info.width,
+        height: info.height,
+    })
+}
+
+fn invalid(message: String) -> crate::error::AdamantError {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message).into()
+}
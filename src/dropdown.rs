@@ -0,0 +1,154 @@
+//! Dropdown Window
+//!
+//! The state of a Quake-style window (`Config::dropdown`): whether it is
+//! shown, the height the user resized it to and the slide in progress.
+//! The application moves the real window to the [`Dropdown::frame`] on
+//! its monitor, and along [`Dropdown::slide_position`] every frame while
+//! it slides.
+//!
+//! ```
+//! use adamant::config::DropdownConfig;
+//! use adamant::dropdown::{Dropdown, Toggle};
+//! use web_time::{Duration, Instant};
+//! use winit::dpi::{PhysicalPosition, PhysicalSize};
+//!
+//! let mut dropdown = Dropdown::new(&DropdownConfig::default());
+//! let monitor = (PhysicalPosition::new(0, 0), PhysicalSize::new(1920, 1080));
+//! let (position, size) = dropdown.frame(monitor);
+//! assert_eq!((position.y, size.width, size.height), (0, 1920, 432));
+//!
+//! let start = Instant::now();
+//! assert_eq!(dropdown.toggle(true, start), Toggle::Hide);
+//! // Halfway up, then gone
+//! let halfway = dropdown.slide_position(monitor, start + Duration::from_millis(75));
+//! assert_eq!(halfway, Some(PhysicalPosition::new(0, -216)));
+//! assert_eq!(dropdown.slide_position(monitor, start + Duration::from_secs(1)), None);
+//! assert!(!dropdown.is_shown());
+//! ```
+
+use web_time::{Duration, Instant};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::monitor::MonitorHandle;
+
+use crate::config::DropdownConfig;
+
+/// A monitor's position and size.
+pub type MonitorArea = (PhysicalPosition<i32>, PhysicalSize<u32>);
+
+/// What the hotkey does to the window.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Toggle {
+    /// Show it and slide it in
+    Show,
+    /// It is shown behind another window: bring it to the front
+    Focus,
+    /// Slide it out, then hide it
+    Hide,
+}
+
+/// A Quake-style window's state.
+#[derive(Debug)]
+pub struct Dropdown {
+    /// Fraction of the monitor's height, until resized
+    height_fraction: f32,
+    slide: Duration,
+    shown: bool,
+    /// The height the user resized the window to
+    height: Option<u32>,
+    /// When the running slide started, and whether it slides in
+    sliding: Option<(Instant, bool)>,
+    /// The monitor it was hidden on, to show it there again
+    monitor: Option<MonitorHandle>,
+}
+
+impl Dropdown {
+    /// A shown window, as it starts.
+    pub fn new(config: &DropdownConfig) -> Self {
+        Self {
+            height_fraction: config.height,
+            slide: Duration::from_millis(config.slide_ms),
+            shown: true,
+            height: None,
+            sliding: None,
+            monitor: None,
+        }
+    }
+
+    /// Whether the window is shown (or sliding in or out).
+    pub fn is_shown(&self) -> bool {
+        self.shown
+    }
+
+    /// Whether a slide is running.
+    pub fn is_sliding(&self) -> bool {
+        self.sliding.is_some()
+    }
+
+    /// The hotkey was pressed; `focused` is whether the window has the
+    /// focus. Starts the slide.
+    pub fn toggle(&mut self, focused: bool, now: Instant) -> Toggle {
+        let toggle = match (self.shown, self.sliding) {
+            // Pressed again while sliding: turn around
+            (true, Some((_, false))) => Toggle::Show,
+            (true, Some((_, true))) => Toggle::Hide,
+            (true, None) if !focused => return Toggle::Focus,
+            (true, None) => Toggle::Hide,
+            (false, _) => Toggle::Show,
+        };
+        self.shown = true;
+        self.sliding = Some((now, toggle == Toggle::Show));
+        toggle
+    }
+
+    /// Where the window goes on `monitor` when fully shown: across the
+    /// top, at the height it was resized to.
+    pub fn frame(&self, monitor: MonitorArea) -> (PhysicalPosition<i32>, PhysicalSize<u32>) {
+        let (position, size) = monitor;
+        let height = self
+            .height
+            .unwrap_or((size.height as f32 * self.height_fraction).round() as u32)
+            .clamp(1, size.height);
+        (position, PhysicalSize::new(size.width, height))
+    }
+
+    /// Where the window is at `now` while sliding on `monitor`, or `None`
+    /// once the slide is over (then a window sliding out is hidden).
+    pub fn slide_position(
+        &mut self,
+        monitor: MonitorArea,
+        now: Instant,
+    ) -> Option<PhysicalPosition<i32>> {
+        let (start, showing) = self.sliding?;
+        let elapsed = now.saturating_duration_since(start);
+        if elapsed >= self.slide {
+            self.sliding = None;
+            self.shown = showing;
+            return None;
+        }
+        let (top, size) = self.frame(monitor);
+        let progress = elapsed.as_secs_f32() / self.slide.as_secs_f32();
+        let out = if showing { 1.0 - progress } else { progress };
+        let offset = (size.height as f32 * out).round() as i32;
+        Some(PhysicalPosition::new(top.x, top.y - offset))
+    }
+
+    /// The window was resized to `height` by the user; it comes back at
+    /// that height. Sizes set while sliding are the application's own.
+    pub fn resized(&mut self, height: u32) {
+        if self.shown && self.sliding.is_none() {
+            self.height = Some(height);
+        }
+    }
+
+    /// The monitor to show the window on again.
+    pub fn monitor(&self) -> Option<&MonitorHandle> {
+        self.monitor.as_ref()
+    }
+
+    /// Remember the monitor the window is on while hiding.
+    pub fn set_monitor(&mut self, monitor: Option<MonitorHandle>) {
+        if monitor.is_some() {
+            self.monitor = monitor;
+        }
+    }
+}
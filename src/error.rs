@@ -0,0 +1,67 @@
+//! Error Types
+//!
+//! [`AdamantError`] covers everything that can keep a terminal from starting:
+//! creating the window and GPU device, loading fonts, spawning the shell and
+//! checking the configuration. Failures at runtime (a lost surface, a failed
+//! PTY write) are logged and recovered from instead of surfacing here.
+
+/// Anything that can go wrong while starting Adamant or creating a terminal.
+#[derive(Debug, thiserror::Error)]
+pub enum AdamantError {
+    #[cfg(feature = "gui")]
+    #[error("failed to create the window: {0}")]
+    Window(#[from] winit::error::OsError),
+
+    #[cfg(feature = "gui")]
+    #[error("event loop error: {0}")]
+    EventLoop(#[from] winit::error::EventLoopError),
+
+    #[cfg(feature = "gui")]
+    #[error("failed to create the rendering surface: {0}")]
+    Surface(#[from] wgpu::CreateSurfaceError),
+
+    /// The adapter can't present to the window at all.
+    #[cfg(feature = "gui")]
+    #[error("the GPU cannot draw to this window")]
+    UnsupportedSurface,
+
+    /// The post-processing shader (`Config::shader`) doesn't compile.
+    #[cfg(feature = "gui")]
+    #[error("invalid shader: {0}")]
+    Shader(String),
+
+    #[cfg(feature = "gui")]
+    #[error("no compatible GPU found: {0}")]
+    Adapter(#[from] wgpu::RequestAdapterError),
+
+    #[cfg(feature = "gui")]
+    #[error("failed to initialize the GPU: {0}")]
+    Device(#[from] wgpu::RequestDeviceError),
+
+    /// None of the candidates in the named font list could be loaded.
+    #[error("no usable font found in {0} (see FontConfig in src/config.rs)")]
+    Font(&'static str),
+
+    #[error("failed to start the shell: {0}")]
+    Pty(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    /// The I/O backend could not connect (e.g. the websocket bridge).
+    #[error("failed to connect to the terminal backend: {0}")]
+    Backend(String),
+
+    #[error("invalid configuration: {0}")]
+    Config(String),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl AdamantError {
+    /// Wrap an error from the PTY layer.
+    pub fn pty(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self::Pty(error.into())
+    }
+}
+
+/// `Result` with [`AdamantError`] as the default error type.
+pub type Result<T, E = AdamantError> = std::result::Result<T, E>;
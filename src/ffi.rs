@@ -0,0 +1,374 @@
+//! C Bindings for the Terminal Core
+//!
+//! Exposes [`Terminal`] over a plain C ABI so applications written in other
+//! languages can reuse Adamant's VT emulation. The header is generated by
+//! cbindgen into `include/adamant.h` whenever the crate is built with the
+//! `ffi` feature. Build a shared or static library with:
+//!
+//! ```sh
+//! cargo rustc --release --no-default-features --features ffi --crate-type cdylib
+//! cargo rustc --release --no-default-features --features ffi --crate-type staticlib
+//! ```
+//!
+//! ```c
+//! AdamantTerminal *term = adamant_terminal_new(80, 24, 1000);
+//! adamant_terminal_feed(term, (const uint8_t *)"hello", 5);
+//!
+//! AdamantCell cell;
+//! if (adamant_terminal_cell(term, 0, 0, &cell)) {
+//!     // cell.codepoint == 'h'
+//! }
+//! adamant_terminal_free(term);
+//! ```
+//!
+//! The terminal is not thread-safe: all calls on one handle must come from
+//! one thread at a time. Functions never panic across the boundary; invalid
+//! arguments (null handles, out-of-range cells) are reported through return
+//! values. A panic inside (a bug) is logged and the call returns as it does
+//! for a null handle.
+
+use std::ffi::c_char;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::ptr;
+
+use crate::grid::{Cell, Color, Flags};
+use crate::term::{Terminal, WidthOptions};
+
+/// Opaque terminal handle.
+pub struct AdamantTerminal {
+    terminal: Terminal,
+    /// Query replies not yet read by the host
+    responses: Vec<u8>,
+}
+
+/// Which member of [`AdamantColor`] is meaningful.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AdamantColorKind {
+    /// The terminal's default foreground/background
+    Default = 0,
+    /// `index` into the 256-color palette
+    Indexed = 1,
+    /// `r`, `g`, `b` truecolor
+    Rgb = 2,
+}
+
+/// A cell color, still symbolic (palette resolution is up to the host).
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct AdamantColor {
+    pub kind: AdamantColorKind,
+    pub index: u8,
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+/// One grid cell.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct AdamantCell {
+    /// Unicode scalar value (a space for empty cells)
+    pub codepoint: u32,
+    pub fg: AdamantColor,
+    pub bg: AdamantColor,
+    /// `ADAMANT_FLAG_*` bits
+    pub flags: u16,
+}
+
+pub const ADAMANT_FLAG_BOLD: u16 = 1 << 0;
+pub const ADAMANT_FLAG_DIM: u16 = 1 << 1;
+pub const ADAMANT_FLAG_ITALIC: u16 = 1 << 2;
+pub const ADAMANT_FLAG_UNDERLINE: u16 = 1 << 3;
+pub const ADAMANT_FLAG_BLINK: u16 = 1 << 4;
+pub const ADAMANT_FLAG_INVERSE: u16 = 1 << 5;
+pub const ADAMANT_FLAG_HIDDEN: u16 = 1 << 6;
+pub const ADAMANT_FLAG_STRIKEOUT: u16 = 1 << 7;
+/// The cell holds a double-width character.
+pub const ADAMANT_FLAG_WIDE: u16 = 1 << 8;
+/// The cell is the right half of a double-width character.
+pub const ADAMANT_FLAG_WIDE_SPACER: u16 = 1 << 9;
+
+// The flags are passed through as-is.
+const _: () = assert!(
+    ADAMANT_FLAG_BOLD == Flags::BOLD.bits()
+        && ADAMANT_FLAG_DIM == Flags::DIM.bits()
+        && ADAMANT_FLAG_ITALIC == Flags::ITALIC.bits()
+        && ADAMANT_FLAG_UNDERLINE == Flags::UNDERLINE.bits()
+        && ADAMANT_FLAG_BLINK == Flags::BLINK.bits()
+        && ADAMANT_FLAG_INVERSE == Flags::INVERSE.bits()
+        && ADAMANT_FLAG_HIDDEN == Flags::HIDDEN.bits()
+        && ADAMANT_FLAG_STRIKEOUT == Flags::STRIKEOUT.bits()
+        && ADAMANT_FLAG_WIDE == Flags::WIDE.bits()
+        && ADAMANT_FLAG_WIDE_SPACER == Flags::WIDE_SPACER.bits()
+);
+
+impl From<Color> for AdamantColor {
+    fn from(color: Color) -> Self {
+        let (kind, index, (r, g, b)) = match color {
+            Color::Default => (AdamantColorKind::Default, 0, (0, 0, 0)),
+            Color::Indexed(index) => (AdamantColorKind::Indexed, index, (0, 0, 0)),
+            Color::Rgb(r, g, b) => (AdamantColorKind::Rgb, 0, (r, g, b)),
+        };
+        Self {
+            kind,
+            index,
+            r,
+            g,
+            b,
+        }
+    }
+}
+
+impl From<&Cell> for AdamantCell {
+    fn from(cell: &Cell) -> Self {
+        Self {
+            codepoint: cell.c as u32,
+            fg: cell.fg.into(),
+            bg: cell.bg.into(),
+            flags: cell.flags.bits(),
+        }
+    }
+}
+
+/// Run `f`, returning `fallback` if it panics: unwinding into C aborts the
+/// host.
+fn guard<T>(fallback: T, f: impl FnOnce() -> T) -> T {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| {
+        log::error!("A panic was caught at the C API boundary");
+        fallback
+    })
+}
+
+/// Create a terminal of `cols` x `rows` keeping `scrollback` lines of history.
+/// Returns null if that fails.
+///
+/// Free it with [`adamant_terminal_free`].
+#[unsafe(no_mangle)]
+pub extern "C" fn adamant_terminal_new(
+    cols: u32,
+    rows: u32,
+    scrollback: u32,
+) -> *mut AdamantTerminal {
+    guard(ptr::null_mut(), || {
+        Box::into_raw(Box::new(AdamantTerminal {
+            terminal: Terminal::new(cols as usize, rows as usize, scrollback as usize),
+            responses: Vec::new(),
+        }))
+    })
+}
+
+/// Destroy a terminal. Null is ignored.
+///
+/// # Safety
+///
+/// `term` must be null or a handle from [`adamant_terminal_new`] that has
+/// not been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn adamant_terminal_free(term: *mut AdamantTerminal) {
+    if !term.is_null() {
+        guard((), || drop(unsafe { Box::from_raw(term) }));
+    }
+}
+
+/// Process `len` bytes of program output.
+///
+/// # Safety
+///
+/// `term` must be a live handle; `bytes` must point to `len` readable bytes
+/// (or be null when `len` is 0).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn adamant_terminal_feed(
+    term: *mut AdamantTerminal,
+    bytes: *const u8,
+    len: usize,
+) {
+    let Some(term) = (unsafe { term.as_mut() }) else {
+        return;
+    };
+    if bytes.is_null() || len == 0 {
+        return;
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(bytes, len) };
+    guard((), || {
+        term.terminal.feed(bytes);
+        let responses = term.terminal.take_responses();
+        term.responses.extend_from_slice(&responses);
+    });
+}
+
+/// Resize both screens to `cols` x `rows`.
+///
+/// # Safety
+///
+/// `term` must be a live handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn adamant_terminal_resize(term: *mut AdamantTerminal, cols: u32, rows: u32) {
+    if let Some(term) = unsafe { term.as_mut() } {
+        guard((), || term.terminal.resize(cols as usize, rows as usize));
+    }
+}
+
+/// Set the width of East Asian Ambiguous characters and of text-style emoji
+/// (see `WidthOptions`). Affects text fed afterwards.
+///
+/// # Safety
+///
+/// `term` must be a live handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn adamant_terminal_set_widths(
+    term: *mut AdamantTerminal,
+    ambiguous_wide: bool,
+    emoji_wide: bool,
+) {
+    if let Some(term) = unsafe { term.as_mut() } {
+        term.terminal.set_width_options(WidthOptions {
+            ambiguous_wide,
+            emoji_wide,
+        });
+    }
+}
+
+/// Write the grid size to `cols` and `rows` (either may be null).
+///
+/// # Safety
+///
+/// `term` must be a live handle; non-null out pointers must be writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn adamant_terminal_size(
+    term: *const AdamantTerminal,
+    cols: *mut u32,
+    rows: *mut u32,
+) {
+    let Some(term) = (unsafe { term.as_ref() }) else {
+        return;
+    };
+    let grid = term.terminal.grid();
+    unsafe {
+        if let Some(cols) = cols.as_mut() {
+            *cols = grid.cols() as u32;
+        }
+        if let Some(rows) = rows.as_mut() {
+            *rows = grid.rows() as u32;
+        }
+    }
+}
+
+/// Write the cursor position (0-based) to `row` and `col` (either may be null).
+///
+/// # Safety
+///
+/// `term` must be a live handle; non-null out pointers must be writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn adamant_terminal_cursor(
+    term: *const AdamantTerminal,
+    row: *mut u32,
+    col: *mut u32,
+) {
+    let Some(term) = (unsafe { term.as_ref() }) else {
+        return;
+    };
+    let cursor = &term.terminal.grid().cursor;
+    unsafe {
+        if let Some(row) = row.as_mut() {
+            *row = cursor.row as u32;
+        }
+        if let Some(col) = col.as_mut() {
+            *col = cursor.col as u32;
+        }
+    }
+}
+
+/// Read the cell at screen `row`, `col` of the active screen into `out`.
+///
+/// Returns false (leaving `out` untouched) when the position is outside
+/// the grid.
+///
+/// # Safety
+///
+/// `term` must be a live handle; `out` must be writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn adamant_terminal_cell(
+    term: *const AdamantTerminal,
+    row: u32,
+    col: u32,
+    out: *mut AdamantCell,
+) -> bool {
+    let (Some(term), Some(out)) = (unsafe { term.as_ref() }, unsafe { out.as_mut() }) else {
+        return false;
+    };
+    guard(false, || {
+        let grid = term.terminal.grid();
+        let (row, col) = (row as usize, col as usize);
+        if row >= grid.rows() || col >= grid.cols() {
+            return false;
+        }
+        *out = (&grid.row(row)[col]).into();
+        true
+    })
+}
+
+/// Serialize the active screen as UTF-8 text, one line per row with
+/// trailing blanks trimmed, joined by `\n`.
+///
+/// Works like `snprintf`: writes at most `cap - 1` bytes plus a NUL
+/// terminator into `buf` and returns the full length (excluding the NUL),
+/// so a return value `>= cap` means the text was truncated. Pass a null
+/// `buf` and `cap` 0 to query the size.
+///
+/// # Safety
+///
+/// `term` must be a live handle; `buf` must point to `cap` writable bytes
+/// (or be null when `cap` is 0).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn adamant_terminal_serialize(
+    term: *const AdamantTerminal,
+    buf: *mut c_char,
+    cap: usize,
+) -> usize {
+    let Some(term) = (unsafe { term.as_ref() }) else {
+        return 0;
+    };
+    let Some(text) = guard(None, || {
+        let grid = term.terminal.grid();
+        let lines: Vec<String> = (0..grid.rows()).map(|row| grid.row(row).text()).collect();
+        Some(lines.join("\n"))
+    }) else {
+        return 0;
+    };
+
+    if !buf.is_null() && cap > 0 {
+        let len = text.len().min(cap - 1);
+        unsafe {
+            ptr::copy_nonoverlapping(text.as_ptr(), buf.cast::<u8>(), len);
+            *buf.add(len) = 0;
+        }
+    }
+    text.len()
+}
+
+/// Move up to `cap` bytes of pending replies (device status, device
+/// attributes) into `buf` and return how many were written. The host must
+/// send them to the program; call until it returns 0.
+///
+/// # Safety
+///
+/// `term` must be a live handle; `buf` must point to `cap` writable bytes
+/// (or be null when `cap` is 0).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn adamant_terminal_read_responses(
+    term: *mut AdamantTerminal,
+    buf: *mut u8,
+    cap: usize,
+) -> usize {
+    let Some(term) = (unsafe { term.as_mut() }) else {
+        return 0;
+    };
+    if buf.is_null() {
+        return 0;
+    }
+    let len = term.responses.len().min(cap);
+    unsafe { ptr::copy_nonoverlapping(term.responses.as_ptr(), buf, len) };
+    term.responses.drain(..len);
+    len
+}
@@ -0,0 +1,89 @@
+//! File Drop
+//!
+//! Files and folders dragged into the window become text at the prompt
+//! (`MouseConfig::drop_format`): their paths quoted for the shell, or
+//! `file://` URLs, separated by spaces and pasted like clipboard text.
+//! Dropping a single folder with Shift held changes into it instead.
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use adamant::config::DropFormat;
+//! use adamant::file_drop::drop_text;
+//!
+//! # #[cfg(unix)] {
+//! let paths = [PathBuf::from("/tmp/notes.txt"), PathBuf::from("/tmp/My Photos")];
+//! assert_eq!(drop_text(&paths, DropFormat::Path), "/tmp/notes.txt '/tmp/My Photos' ");
+//! assert_eq!(
+//!     drop_text(&paths, DropFormat::Url),
+//!     "file:///tmp/notes.txt file:///tmp/My%20Photos "
+//! );
+//! # }
+//! ```
+
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+use crate::config::DropFormat;
+
+/// The text pasted for dropped `paths`, each followed by a space so the
+/// next argument can be typed right away.
+pub fn drop_text(paths: &[PathBuf], format: DropFormat) -> String {
+    let mut text = String::new();
+    for path in paths {
+        match format {
+            DropFormat::Path => text.push_str(&shell_quote(&path.to_string_lossy())),
+            DropFormat::Url => text.push_str(&file_url(path)),
+        }
+        text.push(' ');
+    }
+    text
+}
+
+/// The line that changes into `dir`, Enter included.
+pub fn cd_command(dir: &Path) -> String {
+    format!("cd {}\r", shell_quote(&dir.to_string_lossy()))
+}
+
+/// `text` as one shell word: unchanged if it has nothing the shell would
+/// interpret, otherwise in single quotes (double quotes on Windows, where
+/// paths can't contain them).
+pub fn shell_quote(text: &str) -> Cow<'_, str> {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "_-+=.,:/@%".contains(c);
+    if cfg!(windows) {
+        let plain = |c: char| plain(c) || c == '\\';
+        if !text.is_empty() && text.chars().all(plain) {
+            return Cow::Borrowed(text);
+        }
+        return Cow::Owned(format!("\"{}\"", text));
+    }
+    if !text.is_empty() && text.chars().all(plain) {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(format!("'{}'", text.replace('\'', r"'\''")))
+}
+
+/// `path` as a `file://` URL, with everything but unreserved characters
+/// and slashes percent-encoded (UTF-8 for non-ASCII names).
+pub fn file_url(path: &Path) -> String {
+    #[cfg(unix)]
+    let bytes = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str()).to_vec();
+    #[cfg(not(unix))]
+    let bytes = path.to_string_lossy().replace('\\', "/").into_bytes();
+
+    let mut url = String::from("file://");
+    // `C:/Users` becomes `file:///C:/Users`
+    if bytes.first() != Some(&b'/') {
+        url.push('/');
+    }
+    for byte in bytes {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                url.push(byte as char)
+            }
+            // The drive letter's colon stays readable
+            b':' if cfg!(windows) => url.push(':'),
+            _ => url.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    url
+}
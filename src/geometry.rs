@@ -0,0 +1,176 @@
+//! Window Geometry
+//!
+//! The window's size, position, monitor and mode, saved when Adamant exits
+//! and restored on the next start (`Config::remember_geometry`). The
+//! position is kept relative to the monitor, found again by name, so the
+//! window comes back on the same screen even if the monitors were
+//! rearranged; with the monitor gone the window manager places it. The
+//! file is a few lines of text:
+//!
+//! ```
+//! use adamant::config::WindowMode;
+//! use adamant::geometry::WindowGeometry;
+//! use winit::dpi::{PhysicalPosition, PhysicalSize};
+//!
+//! let text = "size 1600 900\nposition 40 30\nmonitor DP-1\nmode maximized\n";
+//! let geometry = WindowGeometry::parse(text).unwrap();
+//! assert_eq!(geometry.size, PhysicalSize::new(1600, 900));
+//! assert_eq!(geometry.mode, WindowMode::Maximized);
+//!
+//! // DP-1 is now right of another monitor
+//! let dp1 = (PhysicalPosition::new(1920, 0), PhysicalSize::new(2560, 1440));
+//! let monitors = [(Some("DP-1".to_string()), dp1)];
+//! assert_eq!(geometry.position_on(&monitors), Some(PhysicalPosition::new(1960, 30)));
+//! ```
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+
+use crate::config::WindowMode;
+use crate::dropdown::MonitorArea;
+use crate::error::Result;
+
+/// Where the window was and how big, when it last closed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WindowGeometry {
+    /// Inner size while windowed
+    pub size: PhysicalSize<u32>,
+    /// Top-left corner while windowed, relative to the monitor's
+    pub position: Option<PhysicalPosition<i32>>,
+    /// The monitor's name
+    pub monitor: Option<String>,
+    pub mode: WindowMode,
+}
+
+impl WindowGeometry {
+    /// Read the saved text; `None` without a usable `size` line. Unknown
+    /// lines are skipped, so older versions read newer files.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut size = None;
+        let mut geometry = Self {
+            size: PhysicalSize::new(0, 0),
+            position: None,
+            monitor: None,
+            mode: WindowMode::Windowed,
+        };
+        for line in text.lines() {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            match key {
+                "size" => {
+                    size = pair(value)
+                        .filter(|&(w, h): &(u32, u32)| w > 0 && h > 0)
+                        .map(|(w, h)| PhysicalSize::new(w, h))
+                }
+                "position" => {
+                    geometry.position = pair(value).map(|(x, y)| PhysicalPosition::new(x, y))
+                }
+                "monitor" if !value.is_empty() => geometry.monitor = Some(value.to_string()),
+                "mode" => {
+                    geometry.mode = match value {
+                        "maximized" => WindowMode::Maximized,
+                        "fullscreen" => WindowMode::Fullscreen,
+                        "exclusive-fullscreen" => WindowMode::ExclusiveFullscreen,
+                        _ => WindowMode::Windowed,
+                    }
+                }
+                _ => {}
+            }
+        }
+        geometry.size = size?;
+        Some(geometry)
+    }
+
+    /// Where the window goes among `monitors` (names and areas): at its
+    /// place on the saved monitor, if that is still there and the place
+    /// still on it.
+    pub fn position_on(
+        &self,
+        monitors: &[(Option<String>, MonitorArea)],
+    ) -> Option<PhysicalPosition<i32>> {
+        let position = self.position?;
+        let name = self.monitor.as_ref()?;
+        let (_, (origin, size)) = monitors
+            .iter()
+            .find(|(monitor, _)| monitor.as_ref() == Some(name))?;
+        let inside = |offset: i32, extent: u32| offset >= 0 && (offset as i64) < extent as i64;
+        (inside(position.x, size.width) && inside(position.y, size.height))
+            .then(|| PhysicalPosition::new(origin.x + position.x, origin.y + position.y))
+    }
+
+    /// Read the file at `path`, if there is a usable one.
+    pub fn load(path: &Path) -> Option<Self> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                log::warn!("Failed to read {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        let geometry = Self::parse(&text);
+        if geometry.is_none() {
+            log::warn!("Ignoring {}: no window size in it", path.display());
+        }
+        geometry
+    }
+
+    /// Write the file at `path`, creating its directory.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, self.to_string())?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for WindowGeometry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "size {} {}", self.size.width, self.size.height)?;
+        if let Some(position) = self.position {
+            writeln!(f, "position {} {}", position.x, position.y)?;
+        }
+        if let Some(monitor) = &self.monitor {
+            writeln!(f, "monitor {}", monitor)?;
+        }
+        let mode = match self.mode {
+            WindowMode::Windowed => "windowed",
+            WindowMode::Maximized => "maximized",
+            WindowMode::Fullscreen => "fullscreen",
+            WindowMode::ExclusiveFullscreen => "exclusive-fullscreen",
+        };
+        writeln!(f, "mode {}", mode)
+    }
+}
+
+/// Two numbers separated by a space.
+fn pair<T: std::str::FromStr>(value: &str) -> Option<(T, T)> {
+    let (a, b) = value.split_once(' ')?;
+    Some((a.parse().ok()?, b.parse().ok()?))
+}
+
+/// The file for windows with `app_id` (`DesktopConfig::app_id`), so
+/// differently named windows keep their own places: under
+/// `$XDG_STATE_HOME/adamant` (`~/.local/state`), `%LOCALAPPDATA%\Adamant`
+/// on Windows, or `~/Library/Application Support/Adamant` on macOS.
+pub fn state_path(app_id: &str) -> Option<PathBuf> {
+    let var = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+    let dir = if cfg!(windows) {
+        PathBuf::from(var("LOCALAPPDATA")?).join("Adamant")
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(var("HOME")?).join("Library/Application Support/Adamant")
+    } else {
+        var("XDG_STATE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| Some(PathBuf::from(var("HOME")?).join(".local/state")))?
+            .join("adamant")
+    };
+    // The app-id is a free-form name; keep it a single file name
+    let name: String = app_id
+        .chars()
+        .map(|c| if c == '/' || c == '\\' { '_' } else { c })
+        .collect();
+    Some(dir.join(format!("{}.geometry", name)))
+}
@@ -0,0 +1,346 @@
+//! Bidirectional text (UAX #9).
+//!
+//! The grid keeps cells in logical order, the order programs write them.
+//! [`BidiRow::new`] computes the visual order of one row so Arabic and
+//! Hebrew read right to left, while the cursor, selections and mouse
+//! positions can still be mapped back to logical columns.
+//!
+//! Each row is its own paragraph with a left-to-right base direction (what
+//! terminal programs lay out for), and the implicit rules are applied:
+//! W1-W7, N1-N2, I1-I2, L1-L2 and L4 mirroring. Explicit embeddings and
+//! isolates are ignored like other format characters. Character classes
+//! come from a compact range table covering Hebrew, Arabic, Syriac, Thaana,
+//! NKo and the presentation forms, not the full Unicode database.
+//!
+//! ```
+//! use adamant::grid::{BidiRow, Grid};
+//!
+//! let mut grid = Grid::new(8, 1, 0);
+//! for c in "ab שלום".chars() {
+//!     grid.write(c, 1, true);
+//! }
+//! let bidi = BidiRow::new(grid.row(0)).unwrap();
+//! let visual: String = (0..8).map(|col| grid.row(0)[bidi.logical(col)].c).collect();
+//! assert_eq!(visual, "ab םולש ");
+//! assert_eq!(bidi.visual(3), 6);
+//! ```
+
+use super::{Flags, Row};
+
+/// Bidi character types (UAX #9 table 4), minus the explicit formatting
+/// types, which are treated as boundary neutrals.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Class {
+    L,
+    R,
+    Al,
+    En,
+    Es,
+    Et,
+    An,
+    Cs,
+    Nsm,
+    Bn,
+    S,
+    Ws,
+    On,
+}
+
+/// The visual arrangement of one row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BidiRow {
+    /// Logical column shown at each visual column
+    visual_to_logical: Vec<usize>,
+    /// Visual column of each logical column
+    logical_to_visual: Vec<usize>,
+    /// Resolved embedding level of each logical column
+    levels: Vec<u8>,
+}
+
+impl BidiRow {
+    /// Reorder `row`. Returns `None` when it has no right-to-left text, so
+    /// the common case costs one scan.
+    pub fn new(row: &Row) -> Option<Self> {
+        let cells = row.cells();
+        if !cells.iter().any(|cell| is_rtl(cell.c)) {
+            return None;
+        }
+
+        let mut classes: Vec<Class> = cells.iter().map(|cell| classify(cell.c)).collect();
+        // The right half of a wide character goes with its left half.
+        for (i, cell) in cells.iter().enumerate() {
+            if cell.flags.contains(Flags::WIDE_SPACER) {
+                classes[i] = Class::Bn;
+            }
+        }
+        resolve_weak(&mut classes);
+        resolve_neutral(&mut classes);
+        let levels = resolve_levels(&classes, cells.iter().map(|cell| classify(cell.c)));
+
+        let mut visual_to_logical: Vec<usize> = (0..cells.len()).collect();
+        reorder(&levels, &mut visual_to_logical);
+        // Reversed wide characters would end up with the spacer first.
+        for visual in 1..visual_to_logical.len() {
+            let (left, right) = (visual_to_logical[visual - 1], visual_to_logical[visual]);
+            if cells[left].flags.contains(Flags::WIDE_SPACER) && left == right + 1 {
+                visual_to_logical.swap(visual - 1, visual);
+            }
+        }
+
+        let mut logical_to_visual = vec![0; cells.len()];
+        for (visual, &logical) in visual_to_logical.iter().enumerate() {
+            logical_to_visual[logical] = visual;
+        }
+
+        Some(Self {
+            visual_to_logical,
+            logical_to_visual,
+            levels,
+        })
+    }
+
+    /// The logical column displayed at visual column `col`.
+    pub fn logical(&self, col: usize) -> usize {
+        self.visual_to_logical.get(col).copied().unwrap_or(col)
+    }
+
+    /// The visual column where logical column `col` is displayed.
+    pub fn visual(&self, col: usize) -> usize {
+        self.logical_to_visual.get(col).copied().unwrap_or(col)
+    }
+
+    /// The character to draw for `c` at logical column `col`: brackets in
+    /// right-to-left runs are mirrored (rule L4).
+    pub fn glyph(&self, col: usize, c: char) -> char {
+        if self.levels.get(col).is_some_and(|level| level % 2 == 1) {
+            mirror(c)
+        } else {
+            c
+        }
+    }
+}
+
+/// Whether `c` can start right-to-left text (fast pre-check).
+fn is_rtl(c: char) -> bool {
+    matches!(classify(c), Class::R | Class::Al | Class::An)
+}
+
+fn classify(c: char) -> Class {
+    match c as u32 {
+        0x30..=0x39 | 0x6F0..=0x6F9 | 0xB2 | 0xB3 | 0xB9 => Class::En,
+        0x2B | 0x2D => Class::Es,
+        0x23..=0x25 | 0xA2..=0xA5 | 0xB0 | 0xB1 | 0x20AC => Class::Et,
+        0x2C | 0x2E | 0x2F | 0x3A | 0xA0 => Class::Cs,
+        0x09 | 0x1F => Class::S,
+        0x0B | 0x0C | 0x20 | 0x2000..=0x200A | 0x2028 | 0x205F | 0x3000 => Class::Ws,
+        0x200E => Class::L,
+        0x200F => Class::R,
+        0x061C => Class::Al,
+        0x00..=0x08
+        | 0x0E..=0x1B
+        | 0x7F..=0x9F
+        | 0x200B..=0x200D
+        | 0x202A..=0x202E
+        | 0x2066..=0x2069
+        | 0xFEFF => Class::Bn,
+        0x300..=0x36F
+        | 0x591..=0x5BD
+        | 0x5BF
+        | 0x5C1..=0x5C2
+        | 0x5C4..=0x5C5
+        | 0x5C7
+        | 0x610..=0x61A
+        | 0x64B..=0x65F
+        | 0x670
+        | 0x6D6..=0x6DC
+        | 0x6DF..=0x6E4
+        | 0x6E7..=0x6E8
+        | 0x6EA..=0x6ED
+        | 0x711
+        | 0x730..=0x74A
+        | 0x7A6..=0x7B0
+        | 0x7EB..=0x7F3
+        | 0x8D3..=0x8FF
+        | 0xFB1E
+        | 0xFE00..=0xFE0F
+        | 0xFE20..=0xFE2F => Class::Nsm,
+        0x660..=0x669 | 0x66B..=0x66C | 0x6DD | 0x890..=0x891 => Class::An,
+        0x590..=0x5FF | 0x7C0..=0x85F | 0xFB1D..=0xFB4F | 0x10800..=0x10FFF => Class::R,
+        0x600..=0x7BF | 0x860..=0x8FF | 0xFB50..=0xFDFF | 0xFE70..=0xFEFE => Class::Al,
+        _ if c.is_alphanumeric() => Class::L,
+        _ => Class::On,
+    }
+}
+
+/// Rules W1-W7 over the whole row (one isolating run sequence, sos = L).
+fn resolve_weak(classes: &mut [Class]) {
+    // W1: marks take the type of what they attach to
+    let mut previous = Class::L;
+    for class in classes.iter_mut() {
+        match *class {
+            Class::Nsm => *class = previous,
+            Class::Bn => {}
+            other => previous = other,
+        }
+    }
+
+    // W2: European digits after Arabic letters are Arabic digits; W3
+    let mut strong = Class::L;
+    for class in classes.iter_mut() {
+        match *class {
+            Class::L | Class::R => strong = *class,
+            Class::Al => {
+                strong = Class::Al;
+                *class = Class::R;
+            }
+            Class::En if strong == Class::Al => *class = Class::An,
+            _ => {}
+        }
+    }
+
+    // W4: one separator between two numbers of the same kind joins them
+    for i in 1..classes.len().saturating_sub(1) {
+        let (before, after) = (classes[i - 1], classes[i + 1]);
+        match classes[i] {
+            Class::Es if before == Class::En && after == Class::En => classes[i] = Class::En,
+            Class::Cs if before == after && matches!(before, Class::En | Class::An) => {
+                classes[i] = before
+            }
+            _ => {}
+        }
+    }
+
+    // W5: terminators next to European numbers belong to them
+    let mut i = 0;
+    while i < classes.len() {
+        if classes[i] != Class::Et {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < classes.len() && matches!(classes[i], Class::Et | Class::Bn) {
+            i += 1;
+        }
+        let touches_number =
+            (start > 0 && classes[start - 1] == Class::En) || classes.get(i) == Some(&Class::En);
+        if touches_number {
+            classes[start..i].fill(Class::En);
+        }
+    }
+
+    // W6: remaining separators and terminators are neutral
+    for class in classes.iter_mut() {
+        if matches!(class, Class::Es | Class::Et | Class::Cs) {
+            *class = Class::On;
+        }
+    }
+
+    // W7: European digits after left-to-right text are left-to-right
+    let mut strong = Class::L;
+    for class in classes.iter_mut() {
+        match *class {
+            Class::L | Class::R => strong = *class,
+            Class::En if strong == Class::L => *class = Class::L,
+            _ => {}
+        }
+    }
+}
+
+/// Rules N1-N2: neutrals between two runs of the same direction take it,
+/// others take the paragraph direction (L). sos and eos are L.
+fn resolve_neutral(classes: &mut [Class]) {
+    let direction = |class: Class| match class {
+        Class::L => Some(Class::L),
+        Class::R | Class::En | Class::An => Some(Class::R),
+        _ => None,
+    };
+
+    let mut i = 0;
+    while i < classes.len() {
+        if direction(classes[i]).is_some() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < classes.len() && direction(classes[i]).is_none() {
+            i += 1;
+        }
+        let before = start
+            .checked_sub(1)
+            .and_then(|j| direction(classes[j]))
+            .unwrap_or(Class::L);
+        let after = classes
+            .get(i)
+            .and_then(|&class| direction(class))
+            .unwrap_or(Class::L);
+        let resolved = if before == after { before } else { Class::L };
+        classes[start..i].fill(resolved);
+    }
+}
+
+/// Rules I1-I2 for a level 0 paragraph, then L1: trailing whitespace and
+/// segment separators go back to the paragraph level. `original` are the
+/// classes before resolution.
+fn resolve_levels(classes: &[Class], original: impl Iterator<Item = Class>) -> Vec<u8> {
+    let mut levels: Vec<u8> = classes
+        .iter()
+        .map(|class| match class {
+            Class::R => 1,
+            Class::En | Class::An => 2,
+            _ => 0,
+        })
+        .collect();
+
+    let original: Vec<Class> = original.collect();
+    let mut trailing = true;
+    for i in (0..levels.len()).rev() {
+        match original[i] {
+            Class::S => {
+                levels[i] = 0;
+                trailing = true;
+            }
+            Class::Ws | Class::Bn if trailing => levels[i] = 0,
+            _ => trailing = false,
+        }
+    }
+    levels
+}
+
+/// Rule L2: from the highest level down to 1, reverse every run at that
+/// level or above.
+fn reorder(levels: &[u8], order: &mut [usize]) {
+    let highest = levels.iter().copied().max().unwrap_or(0);
+    for level in (1..=highest).rev() {
+        let mut i = 0;
+        while i < order.len() {
+            if levels[order[i]] < level {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < order.len() && levels[order[i]] >= level {
+                i += 1;
+            }
+            order[start..i].reverse();
+        }
+    }
+}
+
+/// Rule L4 for the common paired characters.
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        '‹' => '›',
+        '›' => '‹',
+        c => c,
+    }
+}
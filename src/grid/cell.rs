@@ -0,0 +1,77 @@
+//! Cell representation.
+//!
+//! A cell is one character position on the screen together with the
+//! attributes (colors, bold, underline, ...) it was written with.
+
+use bitflags::bitflags;
+
+/// A color as stored in the grid.
+///
+/// Colors are kept symbolic so the renderer can resolve them against the
+/// active palette at draw time (theme changes apply to existing output).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Color {
+    /// The terminal's default foreground/background.
+    #[default]
+    Default,
+    /// An index into the 256-color palette (0-15 are the ANSI colors).
+    Indexed(u8),
+    /// A 24-bit truecolor value.
+    Rgb(u8, u8, u8),
+}
+
+bitflags! {
+    /// Rendition flags of a cell (SGR attributes plus layout markers).
+    #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+    pub struct Flags: u16 {
+        const BOLD          = 1 << 0;
+        const DIM           = 1 << 1;
+        const ITALIC        = 1 << 2;
+        const UNDERLINE     = 1 << 3;
+        const BLINK         = 1 << 4;
+        const INVERSE       = 1 << 5;
+        const HIDDEN        = 1 << 6;
+        const STRIKEOUT     = 1 << 7;
+        /// The cell holds a double-width character.
+        const WIDE          = 1 << 8;
+        /// The cell is the right half of a double-width character.
+        const WIDE_SPACER   = 1 << 9;
+    }
+}
+
+/// A single character cell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cell {
+    pub c: char,
+    pub fg: Color,
+    pub bg: Color,
+    pub flags: Flags,
+    /// The OSC 8 hyperlink the cell is part of, 0 for none (see
+    /// `Terminal::hyperlink`).
+    pub link: u16,
+}
+
+impl Cell {
+    /// A blank cell carrying the colors of `template` (background color erase).
+    pub fn blank(template: &Cell) -> Self {
+        Self {
+            c: ' ',
+            fg: template.fg,
+            bg: template.bg,
+            flags: Flags::empty(),
+            link: 0,
+        }
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            c: ' ',
+            fg: Color::Default,
+            bg: Color::Default,
+            flags: Flags::empty(),
+            link: 0,
+        }
+    }
+}
@@ -0,0 +1,1037 @@
+//! Terminal State Grid
+//!
+//! The grid stores the characters on screen plus the scrollback history.
+//! It knows nothing about escape sequences: the terminal (`crate::term`)
+//! translates parsed sequences into the primitive operations defined here.
+//!
+//! # Layout
+//!
+//! All lines live in one `VecDeque<Row>`. The first `history_len()` rows are
+//! scrollback, the last `rows()` rows are the active screen:
+//!
+//! ```text
+//! lines[0]                  <- oldest scrollback line
+//! ...
+//! lines[history_len()]      <- screen row 0
+//! ...
+//! lines[len - 1]            <- screen row rows()-1
+//! ```
+//!
+//! Scrolling the whole screen up therefore only appends a row; the previous
+//! top row becomes history without being copied.
+//!
+//! # Wide grids
+//!
+//! A grid can be wider than its view: lines up to [`Grid::cols`] long
+//! don't wrap, and [`Grid::view_cols`] of them are shown from
+//! [`Grid::column_offset`], which scrolls sideways like
+//! [`Grid::display_offset`] scrolls through history.
+
+mod bidi;
+mod cell;
+mod search;
+mod selection;
+
+use std::collections::VecDeque;
+use std::ops::{Index, IndexMut};
+
+pub use self::bidi::BidiRow;
+pub use self::cell::{Cell, Color, Flags};
+pub use self::search::SearchMatch;
+pub use self::selection::{CopyOptions, Point, Selection, SelectionMotion, SelectionUnit};
+
+/// Default distance between tab stops.
+const TAB_WIDTH: usize = 8;
+
+/// Characters a double click selects a run of.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum CharClass {
+    Blank,
+    /// Letters, digits and the configured word characters
+    Word,
+    Other(char),
+}
+
+/// One line of cells.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Row {
+    cells: Vec<Cell>,
+    /// The line continues on the next row (it was soft-wrapped by autowrap).
+    pub wrapped: bool,
+    /// A shell command was typed on this line, and how it ended (shell
+    /// integration, OSC 133).
+    pub command: Option<CommandStatus>,
+    /// A shell prompt starts on this line (OSC 133 `A`).
+    pub prompt: bool,
+    /// The watch rules ran over this line (`Config::watch_rules`).
+    pub watched: bool,
+    /// A watch rule highlights this line.
+    pub highlighted: bool,
+}
+
+/// How the command typed on a line ended.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CommandStatus {
+    /// Typed, or still running
+    Running,
+    /// Exited with status 0
+    Succeeded,
+    /// Exited with another status
+    Failed,
+}
+
+impl Row {
+    /// Create a blank row of `cols` cells.
+    pub fn new(cols: usize, template: &Cell) -> Self {
+        Self {
+            cells: vec![Cell::blank(template); cols],
+            wrapped: false,
+            command: None,
+            prompt: false,
+            watched: false,
+            highlighted: false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    /// Blank every cell and clear the wrap flag, shell marks and watch
+    /// rule state.
+    pub fn reset(&mut self, template: &Cell) {
+        self.cells.fill(Cell::blank(template));
+        self.wrapped = false;
+        self.command = None;
+        self.prompt = false;
+        self.watched = false;
+        self.highlighted = false;
+    }
+
+    /// Truncate or pad the row to `cols` cells.
+    fn resize(&mut self, cols: usize, template: &Cell) {
+        self.cells.resize(cols, Cell::blank(template));
+        // A wide character whose right half was cut off can't be displayed.
+        if let Some(last) = self.cells.last_mut()
+            && last.flags.contains(Flags::WIDE)
+        {
+            *last = Cell::blank(template);
+        }
+    }
+
+    /// The text of the row, with wide-character spacers skipped and trailing
+    /// blanks trimmed.
+    pub fn text(&self) -> String {
+        let text: String = self
+            .cells
+            .iter()
+            .filter(|cell| !cell.flags.contains(Flags::WIDE_SPACER))
+            .map(|cell| cell.c)
+            .collect();
+        text.trim_end().to_string()
+    }
+}
+
+impl Index<usize> for Row {
+    type Output = Cell;
+
+    fn index(&self, col: usize) -> &Cell {
+        &self.cells[col]
+    }
+}
+
+impl IndexMut<usize> for Row {
+    fn index_mut(&mut self, col: usize) -> &mut Cell {
+        &mut self.cells[col]
+    }
+}
+
+/// Cursor position and the attributes new characters are written with.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Cursor {
+    /// Screen row (0 = top of the active screen)
+    pub row: usize,
+    /// Column
+    pub col: usize,
+    /// Colors and flags applied to printed characters
+    pub template: Cell,
+    /// The last column was written; the next character wraps first.
+    pub pending_wrap: bool,
+}
+
+/// The character grid of one screen (primary or alternate).
+#[derive(Clone, Debug)]
+pub struct Grid {
+    cols: usize,
+    rows: usize,
+    /// Scrollback followed by the active screen (see module docs)
+    lines: VecDeque<Row>,
+    max_scrollback: usize,
+    pub cursor: Cursor,
+    /// Scroll region as a half-open range of screen rows
+    scroll_top: usize,
+    scroll_bottom: usize,
+    /// How many lines the view is scrolled back into history
+    display_offset: usize,
+    /// Lines that went into history while the view followed the output,
+    /// since [`take_scrolled`](Self::take_scrolled)
+    scrolled: usize,
+    /// Columns shown at once, fewer than `cols` in a wide grid
+    view_cols: usize,
+    /// The first column shown
+    column_offset: usize,
+    tabs: Vec<bool>,
+}
+
+impl Grid {
+    /// Create an empty grid.
+    ///
+    /// `max_scrollback` is the number of lines kept once they scroll off the
+    /// top; use 0 for screens without history (the alternate screen).
+    pub fn new(cols: usize, rows: usize, max_scrollback: usize) -> Self {
+        let cols = cols.max(1);
+        let rows = rows.max(1);
+        let template = Cell::default();
+
+        Self {
+            cols,
+            rows,
+            lines: (0..rows).map(|_| Row::new(cols, &template)).collect(),
+            max_scrollback,
+            cursor: Cursor::default(),
+            scroll_top: 0,
+            scroll_bottom: rows,
+            display_offset: 0,
+            scrolled: 0,
+            view_cols: cols,
+            column_offset: 0,
+            tabs: default_tabs(cols),
+        }
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Number of lines in the scrollback.
+    pub fn history_len(&self) -> usize {
+        self.lines.len() - self.rows
+    }
+
+    /// Total number of lines (scrollback + screen).
+    pub fn total_lines(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// A line by absolute index (0 = oldest scrollback line).
+    pub fn line(&self, index: usize) -> &Row {
+        &self.lines[index]
+    }
+
+    /// A mutable line by absolute index.
+    pub fn line_mut(&mut self, index: usize) -> &mut Row {
+        &mut self.lines[index]
+    }
+
+    /// A row of the active screen.
+    pub fn row(&self, row: usize) -> &Row {
+        &self.lines[self.history_len() + row]
+    }
+
+    /// A mutable row of the active screen.
+    pub fn row_mut(&mut self, row: usize) -> &mut Row {
+        let index = self.history_len() + row;
+        &mut self.lines[index]
+    }
+
+    /// A row as currently displayed, taking the scrollback view into account.
+    pub fn visible_row(&self, row: usize) -> &Row {
+        &self.lines[self.visible_line_index(row)]
+    }
+
+    /// The absolute line index of a displayed row.
+    pub fn visible_line_index(&self, row: usize) -> usize {
+        self.history_len() - self.display_offset + row
+    }
+
+    /// Where `query` appears, oldest line first. Each line is searched on
+    /// its own, so text wrapped onto the next row isn't found across the
+    /// break. An empty query matches nothing.
+    pub fn search(&self, query: &str) -> Vec<SearchMatch> {
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let (query, ignore_case) = search::prepare(query);
+        (0..self.total_lines())
+            .flat_map(|line| search::find(self.line(line), line, &query, ignore_case))
+            .collect()
+    }
+
+    /// The selected text. Lines are joined with `\n` except where autowrap
+    /// continued them; trailing blanks are trimmed.
+    pub fn selection_text(&self, selection: &Selection) -> String {
+        self.selection_text_with(selection, CopyOptions::default())
+    }
+
+    /// The selected text, with `options` deciding about trailing blanks and
+    /// wrapped rows. A block has a line for each row, wrapped or not.
+    pub fn selection_text_with(&self, selection: &Selection, options: CopyOptions) -> String {
+        let (start, end) = selection.range();
+        let last = self.total_lines().saturating_sub(1);
+        let mut text = String::new();
+
+        for line in start.line.min(last)..=end.line.min(last) {
+            let row = &self.lines[line];
+            let (from, to) = selection.cols_on(line, self.cols);
+            let chunk: String = row.cells[from.min(to)..to]
+                .iter()
+                .filter(|cell| !cell.flags.contains(Flags::WIDE_SPACER))
+                .map(|cell| cell.c)
+                .collect();
+
+            if row.wrapped
+                && options.join_wrapped_lines
+                && !selection.is_block()
+                && line != end.line
+            {
+                text.push_str(&chunk);
+            } else {
+                if options.trim_trailing_whitespace {
+                    text.push_str(chunk.trim_end());
+                } else {
+                    text.push_str(&chunk);
+                }
+                if line != end.line {
+                    text.push('\n');
+                }
+            }
+        }
+        text
+    }
+
+    /// The first and last cell of the `unit` at `point`: the cell itself,
+    /// the word around it, or its line with the rows autowrap continued.
+    /// A word is a run of letters, digits and `word_chars`, of blanks, or
+    /// of another character repeated.
+    pub fn unit_at(&self, point: Point, unit: SelectionUnit, word_chars: &str) -> (Point, Point) {
+        let point = Point::new(
+            point.line.min(self.total_lines().saturating_sub(1)),
+            point.col.min(self.cols.saturating_sub(1)),
+        );
+        match unit {
+            SelectionUnit::Cell => (point, point),
+            SelectionUnit::Word => {
+                let class = self.char_class(point, word_chars);
+                let mut start = point;
+                while let Some(prev) = self.prev_cell(start)
+                    && self.char_class(prev, word_chars) == class
+                {
+                    start = prev;
+                }
+                let mut end = point;
+                while let Some(next) = self.next_cell(end)
+                    && self.char_class(next, word_chars) == class
+                {
+                    end = next;
+                }
+                (start, end)
+            }
+            SelectionUnit::Line => {
+                let mut first = point.line;
+                while first > 0 && self.lines[first - 1].wrapped {
+                    first -= 1;
+                }
+                let mut last = point.line;
+                while last + 1 < self.total_lines() && self.lines[last].wrapped {
+                    last += 1;
+                }
+                (Point::new(first, 0), Point::new(last, self.cols - 1))
+            }
+        }
+    }
+
+    /// Where `motion` takes `point` (see [`SelectionMotion`]), staying in
+    /// the grid.
+    pub fn move_point(&self, point: Point, motion: SelectionMotion, word_chars: &str) -> Point {
+        let last = self.total_lines().saturating_sub(1);
+        let point = Point::new(point.line.min(last), point.col.min(self.cols - 1));
+        let word = |point| self.unit_at(point, SelectionUnit::Word, word_chars);
+        let blank = |point| self.char_class(point, word_chars) == CharClass::Blank;
+        match motion {
+            SelectionMotion::Left if point.col > 0 => Point::new(point.line, point.col - 1),
+            SelectionMotion::Left if point.line > 0 => Point::new(point.line - 1, self.cols - 1),
+            SelectionMotion::Right if point.col + 1 < self.cols => {
+                Point::new(point.line, point.col + 1)
+            }
+            SelectionMotion::Right if point.line < last => Point::new(point.line + 1, 0),
+            SelectionMotion::Left | SelectionMotion::Right => point,
+            SelectionMotion::Up => Point::new(point.line.saturating_sub(1), point.col),
+            SelectionMotion::Down => Point::new((point.line + 1).min(last), point.col),
+            SelectionMotion::WordLeft => {
+                let mut point = self.move_point(point, SelectionMotion::Left, word_chars);
+                while blank(point) && point > Point::default() {
+                    point = self.move_point(point, SelectionMotion::Left, word_chars);
+                }
+                word(point).0
+            }
+            SelectionMotion::WordRight => {
+                let end = Point::new(last, self.cols - 1);
+                let mut point = self.move_point(point, SelectionMotion::Right, word_chars);
+                while blank(point) && point < end {
+                    point = self.move_point(point, SelectionMotion::Right, word_chars);
+                }
+                word(point).1
+            }
+            SelectionMotion::LineUp => Point::new(point.line.saturating_sub(1), 0),
+            SelectionMotion::LineDown => Point::new((point.line + 1).min(last), self.cols - 1),
+            SelectionMotion::PageUp => Point::new(point.line.saturating_sub(self.rows), point.col),
+            SelectionMotion::PageDown => Point::new((point.line + self.rows).min(last), point.col),
+        }
+    }
+
+    /// What kind of character is at `point`, for double clicks. The right
+    /// half of a wide character goes with its left half.
+    fn char_class(&self, point: Point, word_chars: &str) -> CharClass {
+        let cells = &self.lines[point.line].cells;
+        let mut cell = &cells[point.col];
+        if cell.flags.contains(Flags::WIDE_SPACER) && point.col > 0 {
+            cell = &cells[point.col - 1];
+        }
+        match cell.c {
+            c if c.is_whitespace() => CharClass::Blank,
+            c if c.is_alphanumeric() || word_chars.contains(c) => CharClass::Word,
+            c => CharClass::Other(c),
+        }
+    }
+
+    /// The cell after `point` in the text, on the next row if autowrap
+    /// continued there.
+    fn next_cell(&self, point: Point) -> Option<Point> {
+        if point.col + 1 < self.cols {
+            Some(Point::new(point.line, point.col + 1))
+        } else if self.lines[point.line].wrapped && point.line + 1 < self.total_lines() {
+            Some(Point::new(point.line + 1, 0))
+        } else {
+            None
+        }
+    }
+
+    /// The cell before `point` in the text, on the previous row if it
+    /// wrapped.
+    fn prev_cell(&self, point: Point) -> Option<Point> {
+        if point.col > 0 {
+            Some(Point::new(point.line, point.col - 1))
+        } else if point.line > 0 && self.lines[point.line - 1].wrapped {
+            Some(Point::new(point.line - 1, self.cols - 1))
+        } else {
+            None
+        }
+    }
+
+    /// The output of the last command that finished, as shell integration
+    /// (OSC 133) marks it: the lines after the command's line up to the
+    /// next prompt or command, without blank lines at the end. `None`
+    /// without such a command, or if it printed nothing.
+    pub fn last_output(&self) -> Option<Selection> {
+        let typed = (0..self.total_lines()).rev().find(|&line| {
+            matches!(
+                self.lines[line].command,
+                Some(CommandStatus::Succeeded | CommandStatus::Failed)
+            )
+        })?;
+        // The command may continue on the following rows
+        let start = (typed..self.total_lines())
+            .find(|&line| !self.lines[line].wrapped)
+            .map_or(self.total_lines(), |line| line + 1);
+        let end = (start..self.total_lines())
+            .find(|&line| self.lines[line].prompt || self.lines[line].command.is_some())
+            .unwrap_or(self.total_lines());
+        let last = (start..end)
+            .rev()
+            .find(|&line| !self.lines[line].text().is_empty())?;
+        let mut selection = Selection::new(Point::new(start, 0));
+        selection.update(Point::new(last, self.cols - 1));
+        Some(selection)
+    }
+
+    /// How many lines the view is scrolled back (0 = following output).
+    pub fn display_offset(&self) -> usize {
+        self.display_offset
+    }
+
+    /// Scroll the view through history. Positive values move towards older lines.
+    pub fn scroll_display(&mut self, delta: isize) {
+        let offset = self.display_offset as isize + delta;
+        self.display_offset = offset.clamp(0, self.history_len() as isize) as usize;
+    }
+
+    /// How many lines the output scrolled the screen up into history since
+    /// the last call, while the view followed it. For animating the scroll.
+    pub fn take_scrolled(&mut self) -> usize {
+        std::mem::take(&mut self.scrolled)
+    }
+
+    /// Jump back to the bottom of the output.
+    pub fn reset_display(&mut self) {
+        self.display_offset = 0;
+    }
+
+    /// Columns shown at once: fewer than [`cols`](Self::cols) when the
+    /// grid is wider than its view.
+    pub fn view_cols(&self) -> usize {
+        self.view_cols
+    }
+
+    /// Show `cols` columns at once, at most all of them.
+    pub fn set_view_cols(&mut self, cols: usize) {
+        self.view_cols = cols.clamp(1, self.cols);
+        self.scroll_columns(0);
+    }
+
+    /// The first column shown (0 unless the grid is wider than its view).
+    pub fn column_offset(&self) -> usize {
+        self.column_offset
+    }
+
+    /// Scroll the view sideways. Positive values move towards the right.
+    /// Returns whether it moved.
+    pub fn scroll_columns(&mut self, delta: isize) -> bool {
+        let before = self.column_offset;
+        let offset = self.column_offset as isize + delta;
+        let max = self.cols - self.view_cols;
+        self.column_offset = offset.clamp(0, max as isize) as usize;
+        self.column_offset != before
+    }
+
+    /// Scroll sideways just enough to show column `col`.
+    pub fn show_column(&mut self, col: usize) {
+        let col = col.min(self.cols - 1);
+        if col < self.column_offset {
+            self.column_offset = col;
+        } else if col >= self.column_offset + self.view_cols {
+            self.column_offset = col + 1 - self.view_cols;
+        }
+    }
+
+    /// The scroll region as a half-open range of screen rows.
+    pub fn scroll_region(&self) -> (usize, usize) {
+        (self.scroll_top, self.scroll_bottom)
+    }
+
+    /// Set the scroll region (half-open). Invalid regions reset to the full screen.
+    pub fn set_scroll_region(&mut self, top: usize, bottom: usize) {
+        if top + 1 < bottom && bottom <= self.rows {
+            self.scroll_top = top;
+            self.scroll_bottom = bottom;
+        } else {
+            self.scroll_top = 0;
+            self.scroll_bottom = self.rows;
+        }
+    }
+
+    /// Drop all scrollback lines.
+    pub fn clear_history(&mut self) {
+        let history = self.history_len();
+        self.lines.drain(..history);
+        self.display_offset = 0;
+    }
+
+    /// Change how many lines of history are kept, dropping the oldest ones
+    /// if there are now too many.
+    pub fn set_max_scrollback(&mut self, max_scrollback: usize) {
+        self.max_scrollback = max_scrollback;
+        self.trim_history();
+    }
+
+    /// Remove and return all scrollback lines, oldest first.
+    pub fn take_history(&mut self) -> Vec<Row> {
+        let history = self.history_len();
+        self.display_offset = 0;
+        self.lines.drain(..history).collect()
+    }
+
+    /// Append lines to the end of the history, as if they had just scrolled
+    /// off the top of the screen.
+    pub fn push_history(&mut self, rows: impl IntoIterator<Item = Row>) {
+        for mut row in rows {
+            row.resize(self.cols, &Cell::default());
+            self.lines.insert(self.history_len(), row);
+            if self.display_offset > 0 {
+                self.display_offset += 1;
+            }
+        }
+        self.trim_history();
+    }
+
+    // -------------------------------------------------------------------------
+    // Printing
+    // -------------------------------------------------------------------------
+
+    /// Write a character of display `width` at the cursor and advance it.
+    pub fn write(&mut self, c: char, width: usize, autowrap: bool) {
+        if width == 0 {
+            return;
+        }
+
+        if self.cursor.pending_wrap && autowrap {
+            self.wrap_line();
+        }
+        self.cursor.pending_wrap = false;
+
+        if width == 2 && self.cursor.col + 1 >= self.cols {
+            if !autowrap || self.cols < 2 {
+                return;
+            }
+            // Not enough room for both halves: pad and continue on the next line.
+            let template = self.cursor.template.clone();
+            let col = self.cursor.col;
+            self.clear_wide_fragments(col);
+            self.row_mut(self.cursor.row)[col] = Cell::blank(&template);
+            self.wrap_line();
+        }
+
+        let col = self.cursor.col;
+        let row = self.cursor.row;
+        self.clear_wide_fragments(col);
+        if width == 2 {
+            self.clear_wide_fragments(col + 1);
+        }
+
+        let mut cell = self.cursor.template.clone();
+        cell.c = c;
+        if width == 2 {
+            cell.flags.insert(Flags::WIDE);
+            let mut spacer = Cell::blank(&self.cursor.template);
+            spacer.flags.insert(Flags::WIDE_SPACER);
+            spacer.link = cell.link;
+            self.row_mut(row)[col + 1] = spacer;
+        }
+        self.row_mut(row)[col] = cell;
+
+        if col + width >= self.cols {
+            self.cursor.col = self.cols - 1;
+            self.cursor.pending_wrap = true;
+        } else {
+            self.cursor.col = col + width;
+        }
+    }
+
+    /// Mark the current row as wrapped and move to the start of the next one.
+    fn wrap_line(&mut self) {
+        let row = self.cursor.row;
+        self.row_mut(row).wrapped = true;
+        self.cursor.col = 0;
+        self.linefeed();
+    }
+
+    /// Before overwriting or shifting `col`, blank both halves of any wide
+    /// character that occupies it so no orphaned halves remain.
+    fn clear_wide_fragments(&mut self, col: usize) {
+        if col >= self.cols {
+            return;
+        }
+        let template = self.cursor.template.clone();
+        let cols = self.cols;
+        let row = self.row_mut(self.cursor.row);
+        if row[col].flags.contains(Flags::WIDE_SPACER) {
+            if col > 0 {
+                row[col - 1] = Cell::blank(&template);
+            }
+            row[col] = Cell::blank(&template);
+        } else if row[col].flags.contains(Flags::WIDE) {
+            if col + 1 < cols {
+                row[col + 1] = Cell::blank(&template);
+            }
+            row[col] = Cell::blank(&template);
+        }
+    }
+
+    // -------------------------------------------------------------------------
+    // Cursor movement
+    // -------------------------------------------------------------------------
+
+    /// Move the cursor to an absolute screen position (clamped).
+    pub fn goto(&mut self, row: usize, col: usize) {
+        self.cursor.row = row.min(self.rows - 1);
+        self.cursor.col = col.min(self.cols - 1);
+        self.cursor.pending_wrap = false;
+    }
+
+    /// Move up, stopping at the top margin if the cursor is inside the region.
+    pub fn move_up(&mut self, n: usize) {
+        let min = if self.cursor.row >= self.scroll_top {
+            self.scroll_top
+        } else {
+            0
+        };
+        self.cursor.row = self.cursor.row.saturating_sub(n).max(min);
+        self.cursor.pending_wrap = false;
+    }
+
+    /// Move down, stopping at the bottom margin if the cursor is inside the region.
+    pub fn move_down(&mut self, n: usize) {
+        let max = if self.cursor.row < self.scroll_bottom {
+            self.scroll_bottom - 1
+        } else {
+            self.rows - 1
+        };
+        self.cursor.row = (self.cursor.row + n).min(max);
+        self.cursor.pending_wrap = false;
+    }
+
+    pub fn move_forward(&mut self, n: usize) {
+        self.cursor.col = (self.cursor.col + n).min(self.cols - 1);
+        self.cursor.pending_wrap = false;
+    }
+
+    pub fn move_back(&mut self, n: usize) {
+        self.cursor.col = self.cursor.col.saturating_sub(n);
+        self.cursor.pending_wrap = false;
+    }
+
+    pub fn carriage_return(&mut self) {
+        self.cursor.col = 0;
+        self.cursor.pending_wrap = false;
+    }
+
+    pub fn backspace(&mut self) {
+        self.move_back(1);
+    }
+
+    /// Move down one line, scrolling the region when at its bottom margin.
+    pub fn linefeed(&mut self) {
+        if self.cursor.row + 1 == self.scroll_bottom {
+            self.scroll_up(1);
+        } else if self.cursor.row + 1 < self.rows {
+            self.cursor.row += 1;
+        }
+        self.cursor.pending_wrap = false;
+    }
+
+    /// Move up one line, scrolling the region down when at its top margin.
+    pub fn reverse_index(&mut self) {
+        if self.cursor.row == self.scroll_top {
+            self.scroll_down(1);
+        } else if self.cursor.row > 0 {
+            self.cursor.row -= 1;
+        }
+        self.cursor.pending_wrap = false;
+    }
+
+    // -------------------------------------------------------------------------
+    // Tab stops
+    // -------------------------------------------------------------------------
+
+    /// Advance to the `n`th next tab stop (or the last column).
+    pub fn tab_forward(&mut self, n: usize) {
+        for _ in 0..n {
+            let next = (self.cursor.col + 1..self.cols).find(|&col| self.tabs[col]);
+            self.cursor.col = next.unwrap_or(self.cols - 1);
+        }
+        self.cursor.pending_wrap = false;
+    }
+
+    /// Go back to the `n`th previous tab stop (or the first column).
+    pub fn tab_backward(&mut self, n: usize) {
+        for _ in 0..n {
+            let prev = (0..self.cursor.col).rev().find(|&col| self.tabs[col]);
+            self.cursor.col = prev.unwrap_or(0);
+        }
+        self.cursor.pending_wrap = false;
+    }
+
+    /// Set a tab stop at the cursor column (HTS).
+    pub fn set_tab_stop(&mut self) {
+        self.tabs[self.cursor.col] = true;
+    }
+
+    /// Clear the tab stop at the cursor column, or all of them (TBC).
+    pub fn clear_tab_stops(&mut self, all: bool) {
+        if all {
+            self.tabs.fill(false);
+        } else {
+            self.tabs[self.cursor.col] = false;
+        }
+    }
+
+    /// Restore the default tab stop every eight columns.
+    pub fn reset_tab_stops(&mut self) {
+        self.tabs = default_tabs(self.cols);
+    }
+
+    // -------------------------------------------------------------------------
+    // Scrolling
+    // -------------------------------------------------------------------------
+
+    /// Scroll the scroll region up by `n` lines.
+    ///
+    /// Lines leaving a region that spans the whole screen go into history.
+    pub fn scroll_up(&mut self, n: usize) {
+        let (top, bottom) = (self.scroll_top, self.scroll_bottom);
+        let n = n.min(bottom - top);
+        let template = self.cursor.template.clone();
+
+        if top == 0 && bottom == self.rows && self.max_scrollback > 0 {
+            for _ in 0..n {
+                self.lines.push_back(Row::new(self.cols, &template));
+                if self.display_offset > 0 {
+                    self.display_offset += 1;
+                } else {
+                    self.scrolled += 1;
+                }
+            }
+            self.trim_history();
+            return;
+        }
+
+        let history = self.history_len();
+        for _ in 0..n {
+            self.lines.remove(history + top);
+            self.lines
+                .insert(history + bottom - 1, Row::new(self.cols, &template));
+        }
+    }
+
+    /// Scroll the scroll region down by `n` lines.
+    pub fn scroll_down(&mut self, n: usize) {
+        let (top, bottom) = (self.scroll_top, self.scroll_bottom);
+        let n = n.min(bottom - top);
+        let template = self.cursor.template.clone();
+        let history = self.history_len();
+
+        for _ in 0..n {
+            self.lines.remove(history + bottom - 1);
+            self.lines
+                .insert(history + top, Row::new(self.cols, &template));
+        }
+    }
+
+    /// Insert `n` blank lines at the cursor row (IL).
+    pub fn insert_lines(&mut self, n: usize) {
+        if self.cursor.row < self.scroll_top || self.cursor.row >= self.scroll_bottom {
+            return;
+        }
+        let top = self.scroll_top;
+        self.scroll_top = self.cursor.row;
+        self.scroll_down(n);
+        self.scroll_top = top;
+        self.carriage_return();
+    }
+
+    /// Delete `n` lines at the cursor row (DL).
+    pub fn delete_lines(&mut self, n: usize) {
+        if self.cursor.row < self.scroll_top || self.cursor.row >= self.scroll_bottom {
+            return;
+        }
+        let top = self.scroll_top;
+        self.scroll_top = self.cursor.row;
+        // Deleted lines are discarded, never pushed into history.
+        let max_scrollback = std::mem::replace(&mut self.max_scrollback, 0);
+        self.scroll_up(n);
+        self.max_scrollback = max_scrollback;
+        self.scroll_top = top;
+        self.carriage_return();
+    }
+
+    fn trim_history(&mut self) {
+        let excess = self.history_len().saturating_sub(self.max_scrollback);
+        if excess > 0 {
+            self.lines.drain(..excess);
+        }
+        self.display_offset = self.display_offset.min(self.history_len());
+    }
+
+    // -------------------------------------------------------------------------
+    // Erasing and editing
+    // -------------------------------------------------------------------------
+
+    /// Erase in display (ED). `mode`: 0 = below, 1 = above, 2 = all, 3 = scrollback.
+    pub fn erase_display(&mut self, mode: u16) {
+        let template = self.cursor.template.clone();
+        let row = self.cursor.row;
+        match mode {
+            0 => {
+                self.erase_line(0);
+                for r in row + 1..self.rows {
+                    self.row_mut(r).reset(&template);
+                }
+            }
+            1 => {
+                for r in 0..row {
+                    self.row_mut(r).reset(&template);
+                }
+                self.erase_line(1);
+            }
+            2 => {
+                for r in 0..self.rows {
+                    self.row_mut(r).reset(&template);
+                }
+            }
+            3 => self.clear_history(),
+            _ => {}
+        }
+    }
+
+    /// Erase in line (EL). `mode`: 0 = right of cursor, 1 = left, 2 = whole line.
+    pub fn erase_line(&mut self, mode: u16) {
+        let template = self.cursor.template.clone();
+        let col = self.cursor.col;
+        let cols = self.cols;
+        let range = match mode {
+            0 => col..cols,
+            1 => 0..col + 1,
+            2 => 0..cols,
+            _ => return,
+        };
+        if mode != 1 {
+            self.row_mut(self.cursor.row).wrapped = false;
+        }
+        // The line is printed anew, and watched anew
+        if range.start == 0 && range.end == cols {
+            let row = self.row_mut(self.cursor.row);
+            row.watched = false;
+            row.highlighted = false;
+        }
+        self.clear_wide_fragments(range.start);
+        self.clear_wide_fragments(range.end - 1);
+        let row = self.row_mut(self.cursor.row);
+        for c in range {
+            row[c] = Cell::blank(&template);
+        }
+    }
+
+    /// Erase `n` characters starting at the cursor (ECH).
+    pub fn erase_chars(&mut self, n: usize) {
+        let template = self.cursor.template.clone();
+        let start = self.cursor.col;
+        let end = (start + n.max(1)).min(self.cols);
+        self.clear_wide_fragments(start);
+        self.clear_wide_fragments(end - 1);
+        let row = self.row_mut(self.cursor.row);
+        for c in start..end {
+            row[c] = Cell::blank(&template);
+        }
+    }
+
+    /// Insert `n` blank characters at the cursor, shifting the rest right (ICH).
+    pub fn insert_blank(&mut self, n: usize) {
+        let template = self.cursor.template.clone();
+        let col = self.cursor.col;
+        let n = n.min(self.cols - col);
+        self.clear_wide_fragments(col);
+        let cols = self.cols;
+        let row = self.row_mut(self.cursor.row);
+        row.cells[col..].rotate_right(n);
+        for c in col..col + n {
+            row[c] = Cell::blank(&template);
+        }
+        row.resize(cols, &template);
+        self.cursor.pending_wrap = false;
+    }
+
+    /// Delete `n` characters at the cursor, shifting the rest left (DCH).
+    pub fn delete_chars(&mut self, n: usize) {
+        let template = self.cursor.template.clone();
+        let col = self.cursor.col;
+        let n = n.clamp(1, self.cols - col);
+        self.clear_wide_fragments(col);
+        self.clear_wide_fragments(col + n - 1);
+        let cols = self.cols;
+        let row = self.row_mut(self.cursor.row);
+        row.cells[col..].rotate_left(n);
+        for c in cols - n..cols {
+            row[c] = Cell::blank(&template);
+        }
+        self.cursor.pending_wrap = false;
+    }
+
+    /// Fill the whole screen with `c` (DECALN).
+    pub fn fill(&mut self, c: char) {
+        let cell = Cell {
+            c,
+            ..Cell::default()
+        };
+        for r in 0..self.rows {
+            let row = self.row_mut(r);
+            row.cells.fill(cell.clone());
+            row.wrapped = false;
+        }
+    }
+
+    // -------------------------------------------------------------------------
+    // Resizing
+    // -------------------------------------------------------------------------
+
+    /// Resize the grid, keeping the cursor line on screen.
+    ///
+    /// Shrinking first drops blank lines below the cursor, then moves lines
+    /// from the top of the screen into history. Growing pulls lines back out
+    /// of history before adding blank lines at the bottom.
+    pub fn resize(&mut self, cols: usize, rows: usize) {
+        let cols = cols.max(1);
+        let rows = rows.max(1);
+        let template = self.cursor.template.clone();
+
+        if rows < self.rows {
+            let mut excess = self.rows - rows;
+            // Drop lines below the cursor first.
+            while excess > 0 && self.cursor.row + 1 < self.rows {
+                self.lines.pop_back();
+                self.rows -= 1;
+                excess -= 1;
+            }
+            // The rest scroll off the top into history.
+            self.rows -= excess;
+            self.cursor.row -= excess;
+        } else if rows > self.rows {
+            let extra = rows - self.rows;
+            let pulled = extra.min(self.history_len());
+            self.rows += pulled;
+            self.cursor.row += pulled;
+            for _ in pulled..extra {
+                self.lines.push_back(Row::new(self.cols, &template));
+            }
+            self.rows = rows;
+        }
+
+        if cols != self.cols {
+            for row in &mut self.lines {
+                row.resize(cols, &template);
+            }
+            // A view of the whole grid stays one
+            self.view_cols = if self.view_cols >= self.cols {
+                cols
+            } else {
+                self.view_cols.min(cols)
+            };
+            self.column_offset = self.column_offset.min(cols - self.view_cols);
+            self.cols = cols;
+            self.tabs = default_tabs(cols);
+        }
+
+        self.trim_history();
+        self.scroll_top = 0;
+        self.scroll_bottom = rows;
+        self.cursor.row = self.cursor.row.min(rows - 1);
+        self.cursor.col = self.cursor.col.min(cols - 1);
+        self.cursor.pending_wrap = false;
+    }
+}
+
+fn default_tabs(cols: usize) -> Vec<bool> {
+    (0..cols)
+        .map(|col| col > 0 && col % TAB_WIDTH == 0)
+        .collect()
+}
@@ -0,0 +1,66 @@
+//! Searching the scrollback.
+//!
+//! Matches are found line by line, in absolute line indices like
+//! selections, so they stay on the same text while the view scrolls. A
+//! query without capitals ignores case ("smart case").
+
+use super::{Cell, Flags, Row};
+
+/// Text matching a search: a line and the columns it covers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SearchMatch {
+    /// Absolute line index (0 = oldest scrollback line)
+    pub line: usize,
+    /// The first column
+    pub start: usize,
+    /// The column past the last, after the right half of a wide character
+    pub end: usize,
+}
+
+/// The query as the characters to compare, and whether case is ignored.
+pub(super) fn prepare(query: &str) -> (Vec<char>, bool) {
+    let ignore_case = !query.chars().any(char::is_uppercase);
+    let chars = query.chars().map(|c| fold(c, ignore_case)).collect();
+    (chars, ignore_case)
+}
+
+/// The non-overlapping matches of `query` on `row`, line `line`.
+pub(super) fn find(row: &Row, line: usize, query: &[char], ignore_case: bool) -> Vec<SearchMatch> {
+    let chars: Vec<(usize, &Cell)> = (row.cells().iter().enumerate())
+        .filter(|(_, cell)| !cell.flags.contains(Flags::WIDE_SPACER))
+        .collect();
+    let mut matches = Vec::new();
+    let mut from = 0;
+    while from + query.len() <= chars.len() {
+        let found = chars[from..from + query.len()]
+            .iter()
+            .zip(query)
+            .all(|((_, cell), &c)| fold(cell.c, ignore_case) == c);
+        if !found {
+            from += 1;
+            continue;
+        }
+        let (last, cell) = chars[from + query.len() - 1];
+        let width = if cell.flags.contains(Flags::WIDE) {
+            2
+        } else {
+            1
+        };
+        matches.push(SearchMatch {
+            line,
+            start: chars[from].0,
+            end: last + width,
+        });
+        from += query.len();
+    }
+    matches
+}
+
+/// `c` as compared: lowercase when case is ignored.
+fn fold(c: char, ignore_case: bool) -> char {
+    if ignore_case {
+        c.to_lowercase().next().unwrap_or(c)
+    } else {
+        c
+    }
+}
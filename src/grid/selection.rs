@@ -0,0 +1,167 @@
+//! Text selection.
+//!
+//! Selections are stored in absolute line indices (see the grid layout), so
+//! they stay on the same text while the view scrolls through history.
+
+/// A cell position: absolute line index (0 = oldest scrollback line) and column.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point {
+    pub line: usize,
+    pub col: usize,
+}
+
+impl Point {
+    pub fn new(line: usize, col: usize) -> Self {
+        Self { line, col }
+    }
+}
+
+/// How selected text is turned into copied text.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CopyOptions {
+    /// Drop the blanks at the end of each line.
+    pub trim_trailing_whitespace: bool,
+    /// Rows that autowrap continued are one line, so a long command pastes
+    /// as it was typed.
+    pub join_wrapped_lines: bool,
+}
+
+impl Default for CopyOptions {
+    fn default() -> Self {
+        Self {
+            trim_trailing_whitespace: true,
+            join_wrapped_lines: true,
+        }
+    }
+}
+
+/// What a drag selects at a time: cells, or whole words after a double
+/// click and whole lines after a triple click.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SelectionUnit {
+    #[default]
+    Cell,
+    Word,
+    Line,
+}
+
+/// How far a key moves the free end of a selection in copy mode.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SelectionMotion {
+    /// A cell back or forward, going on to the neighboring line
+    Left,
+    Right,
+    /// The same column a line up or down
+    Up,
+    Down,
+    /// To the start of the word before, or the end of the word after
+    WordLeft,
+    WordRight,
+    /// To the start of the line above, or the end of the line below
+    LineUp,
+    LineDown,
+    /// A screen up or down
+    PageUp,
+    PageDown,
+}
+
+/// A run of cells in reading order, from where the drag started to where
+/// it currently is; or a block, the rectangle with those two corners.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Selection {
+    anchor: Point,
+    head: Point,
+    block: bool,
+}
+
+impl Selection {
+    /// Start a selection covering the single cell at `anchor`.
+    pub fn new(anchor: Point) -> Self {
+        Self {
+            anchor,
+            head: anchor,
+            block: false,
+        }
+    }
+
+    /// Start a rectangular selection (Alt+drag) at `anchor`: the same
+    /// columns on every line, for columns of tabular output.
+    pub fn block(anchor: Point) -> Self {
+        Self {
+            block: true,
+            ..Self::new(anchor)
+        }
+    }
+
+    pub fn is_block(&self) -> bool {
+        self.block
+    }
+
+    /// The first and last columns between the two ends (inclusive), the
+    /// columns of a block.
+    pub fn columns(&self) -> (usize, usize) {
+        let (a, b) = (self.anchor.col, self.head.col);
+        (a.min(b), a.max(b))
+    }
+
+    /// Where the free end of the selection is.
+    pub fn head(&self) -> Point {
+        self.head
+    }
+
+    /// Move the free end of the selection.
+    pub fn update(&mut self, head: Point) {
+        self.head = head;
+    }
+
+    /// Cover the unit the drag started on and the one under the pointer,
+    /// each given as its first and last cell.
+    pub fn extend(&mut self, anchor: (Point, Point), head: (Point, Point)) {
+        if head.0 < anchor.0 {
+            self.anchor = anchor.1;
+            self.head = head.0;
+        } else {
+            self.anchor = anchor.0;
+            self.head = head.1;
+        }
+    }
+
+    /// The first and last selected cells (inclusive), in reading order.
+    pub fn range(&self) -> (Point, Point) {
+        if self.anchor <= self.head {
+            (self.anchor, self.head)
+        } else {
+            (self.head, self.anchor)
+        }
+    }
+
+    /// The columns selected on `line` of a grid `cols` wide, as a range
+    /// (start inclusive, end exclusive).
+    pub fn cols_on(&self, line: usize, cols: usize) -> (usize, usize) {
+        let (start, end) = self.range();
+        let (from, to) = if self.block {
+            self.columns()
+        } else {
+            (
+                if line == start.line { start.col } else { 0 },
+                if line == end.line { end.col } else { cols },
+            )
+        };
+        (from.min(cols), (to + 1).min(cols))
+    }
+
+    /// Whether the selection covers only the cell it started on (a click).
+    pub fn is_empty(&self) -> bool {
+        self.anchor == self.head
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        let (start, end) = self.range();
+        if self.block {
+            let (left, right) = self.columns();
+            (start.line..=end.line).contains(&point.line) && (left..=right).contains(&point.col)
+        } else {
+            start <= point && point <= end
+        }
+    }
+}
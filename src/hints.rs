@@ -0,0 +1,453 @@
+//! Hints Mode
+//!
+//! Keyboard-driven picking of text on screen. [`HintMatcher`] runs the
+//! configured patterns (URLs, paths, hashes, ...) over the visible lines,
+//! soft-wrapped rows joined, and labels every match; [`Hints`] narrows the
+//! labels down as they are typed and yields the match once a label is
+//! complete. Matches are taken from the screen when hints mode starts.
+//!
+//! Labels all have the same length, so none is the prefix of another, and
+//! the same text gets the same label wherever it appears. Each hint knows
+//! what picking it does ([`HintPick`]), with the pattern's templates
+//! already filled in from the capture groups; [`Hints::hint_at`] finds the
+//! hint under the pointer for right-clicks. Positions in files, like
+//! `src/main.rs:12:5` in compiler output, open in `$EDITOR` at that line
+//! and column ([`editor_args`]).
+//!
+//! ```
+//! use adamant::config::HintsConfig;
+//! use adamant::hints::{HintMatcher, HintPick};
+//! use adamant::term::Terminal;
+//!
+//! let mut term = Terminal::new(40, 2, 0);
+//! term.feed(b"see https://example.com/docs.");
+//! let matcher = HintMatcher::new(&HintsConfig::default()).unwrap();
+//! let mut hints = matcher.find(term.grid());
+//!
+//! let hint = &hints.hints()[0];
+//! assert_eq!((hint.text.as_str(), hint.col), ("https://example.com/docs", 4));
+//! let label = hint.label.clone();
+//! assert_eq!(
+//!     hints.input(label.chars().next().unwrap()),
+//!     Some(HintPick::Open("https://example.com/docs".to_string()))
+//! );
+//! ```
+
+use std::ffi::OsString;
+
+use regex::{Captures, Regex};
+
+use crate::config::{HintAction, HintsConfig};
+use crate::error::{AdamantError, Result};
+use crate::grid::{Flags, Grid, Row};
+
+/// The compiled patterns of [`HintsConfig`].
+#[derive(Clone, Debug)]
+pub struct HintMatcher {
+    patterns: Vec<(&'static str, Regex, HintAction)>,
+    alphabet: Vec<char>,
+}
+
+/// One labeled match.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hint {
+    /// Row on the visible screen
+    pub row: usize,
+    /// First column of the match
+    pub col: usize,
+    /// Cells the match covers, continuing at the start of the next row
+    /// when it wraps
+    pub width: usize,
+    pub text: String,
+    /// Name of the pattern that matched
+    pub pattern: &'static str,
+    /// What picking the hint does
+    pub pick: HintPick,
+    pub label: String,
+}
+
+/// A picked hint's action, ready to run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HintPick {
+    /// Copy the text to the clipboard
+    Copy(String),
+    /// Open a URL or path with the default handler
+    Open(String),
+    /// Open a file in `$EDITOR`, at a line and column when known
+    Edit {
+        path: String,
+        line: Option<usize>,
+        column: Option<usize>,
+    },
+    /// Type the text into the terminal
+    Paste(String),
+    /// Run a program with arguments
+    Run(Vec<String>),
+}
+
+/// The hints on screen and the part of a label typed so far.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Hints {
+    hints: Vec<Hint>,
+    typed: String,
+    /// Width of the screen the hints were found on
+    cols: usize,
+}
+
+impl HintMatcher {
+    /// Compile the patterns. A pattern that doesn't compile, or a `Run`
+    /// action without a program, is a configuration error.
+    pub fn new(config: &HintsConfig) -> Result<Self> {
+        let patterns = config
+            .patterns
+            .iter()
+            .map(|pattern| {
+                let invalid = |message: String| {
+                    AdamantError::Config(format!("hint pattern {:?}: {}", pattern.name, message))
+                };
+                let regex = Regex::new(pattern.regex).map_err(|e| invalid(e.to_string()))?;
+                if pattern.action == HintAction::Run(&[]) {
+                    return Err(invalid("Run needs a program".into()));
+                }
+                Ok((pattern.name, regex, pattern.action))
+            })
+            .collect::<Result<_>>()?;
+        let mut alphabet = Vec::new();
+        for c in config.alphabet.chars() {
+            if !alphabet.contains(&c) {
+                alphabet.push(c);
+            }
+        }
+        Ok(Self { patterns, alphabet })
+    }
+
+    /// Label every match on the visible screen of `grid`.
+    pub fn find(&self, grid: &Grid) -> Hints {
+        let mut hints = Vec::new();
+        let mut row = 0;
+        while row < grid.rows() {
+            let mut line = vec![grid.visible_row(row)];
+            while line[line.len() - 1].wrapped && row + line.len() < grid.rows() {
+                line.push(grid.visible_row(row + line.len()));
+            }
+            self.find_in_line(&line, row, grid.cols(), &mut hints);
+            row += line.len();
+        }
+        self.label(&mut hints);
+        Hints {
+            hints,
+            typed: String::new(),
+            cols: grid.cols(),
+        }
+    }
+
+    /// Match in the rows of one line, starting at screen row `first`.
+    fn find_in_line(&self, line: &[&Row], first: usize, cols: usize, hints: &mut Vec<Hint>) {
+        let (text, cells) = line_text(line, cols);
+        // Cell ranges already claimed by an earlier pattern
+        let mut taken: Vec<(usize, usize)> = Vec::new();
+        let count = hints.len();
+
+        for (name, regex, action) in &self.patterns {
+            for captures in regex.captures_iter(&text) {
+                let Some(found) = captures.name("text").or_else(|| captures.get(0)) else {
+                    continue;
+                };
+                let matched = trim_match(found.as_str());
+                if matched.is_empty() {
+                    continue;
+                }
+                let start = cells[found.start()];
+                let end = cells[found.start() + matched.len()];
+                if taken.iter().any(|&(s, e)| start < e && s < end) {
+                    continue;
+                }
+                taken.push((start, end));
+                hints.push(Hint {
+                    row: first + start / cols,
+                    col: start % cols,
+                    width: end - start,
+                    text: matched.to_string(),
+                    pattern: name,
+                    pick: resolve(*action, matched, &captures),
+                    label: String::new(),
+                });
+            }
+        }
+        hints[count..].sort_by_key(|hint| (hint.row, hint.col));
+    }
+
+    /// Give each distinct text a label, starting from the bottom of the
+    /// screen where the latest output is.
+    fn label(&self, hints: &mut [Hint]) {
+        let mut texts: Vec<&str> = Vec::new();
+        let mut index = vec![0; hints.len()];
+        for (i, hint) in hints.iter().enumerate().rev() {
+            index[i] = match texts.iter().position(|text| *text == hint.text) {
+                Some(position) => position,
+                None => {
+                    texts.push(&hint.text);
+                    texts.len() - 1
+                }
+            };
+        }
+
+        let base = self.alphabet.len();
+        let mut length = 1;
+        while base.pow(length) < texts.len() {
+            length += 1;
+        }
+        for (hint, index) in hints.iter_mut().zip(index) {
+            hint.label = (0..length)
+                .rev()
+                .map(|digit| self.alphabet[index / base.pow(digit) % base])
+                .collect();
+        }
+    }
+}
+
+impl Hints {
+    pub fn is_empty(&self) -> bool {
+        self.hints.is_empty()
+    }
+
+    /// All hints, in screen order.
+    pub fn hints(&self) -> &[Hint] {
+        &self.hints
+    }
+
+    /// The label prefix typed so far.
+    pub fn typed(&self) -> &str {
+        &self.typed
+    }
+
+    /// The hints whose label starts with what was typed.
+    pub fn candidates(&self) -> impl Iterator<Item = &Hint> {
+        self.hints
+            .iter()
+            .filter(|hint| hint.label.starts_with(&self.typed))
+    }
+
+    /// The hint covering the cell at `col` on visible row `row`.
+    pub fn hint_at(&self, row: usize, col: usize) -> Option<&Hint> {
+        let index = row * self.cols + col;
+        self.hints.iter().find(|hint| {
+            let start = hint.row * self.cols + hint.col;
+            (start..start + hint.width).contains(&index)
+        })
+    }
+
+    /// Type one label character. Returns what to do once a label is
+    /// complete; an upper case character pastes the text instead of running
+    /// the pattern's action. Characters that don't continue any label are
+    /// ignored.
+    pub fn input(&mut self, c: char) -> Option<HintPick> {
+        let lower = c.to_lowercase().next()?;
+        self.typed.push(lower);
+        let Some(hint) = self.candidates().next() else {
+            self.typed.pop();
+            return None;
+        };
+        if hint.label != self.typed {
+            return None;
+        }
+        if c.is_uppercase() {
+            Some(HintPick::Paste(hint.text.clone()))
+        } else {
+            Some(hint.pick.clone())
+        }
+    }
+
+    /// Take back the last typed character.
+    pub fn backspace(&mut self) {
+        self.typed.pop();
+    }
+}
+
+/// What `action` does with `text`, matched with `captures`.
+fn resolve(action: HintAction, text: &str, captures: &Captures) -> HintPick {
+    let expand = |template: &str| {
+        let mut expanded = String::new();
+        captures.expand(template, &mut expanded);
+        expanded
+    };
+    match action {
+        HintAction::Copy => HintPick::Copy(text.to_string()),
+        HintAction::Open => HintPick::Open(text.to_string()),
+        HintAction::Paste => HintPick::Paste(text.to_string()),
+        HintAction::OpenUrl(template) => HintPick::Open(expand_url(template, captures)),
+        HintAction::Edit => {
+            let number = |name| captures.name(name)?.as_str().parse().ok();
+            HintPick::Edit {
+                path: captures
+                    .name("path")
+                    .map_or(text, |path| path.as_str())
+                    .to_string(),
+                line: number("line"),
+                column: number("column"),
+            }
+        }
+        HintAction::Run(command) => HintPick::Run(command.iter().map(|arg| expand(arg)).collect()),
+    }
+}
+
+/// `template` with the capture groups it refers to (`$1`, `$name`,
+/// `${name}`, and `$$` for a `$`) percent-encoded as parts of a URL, as
+/// `Captures::expand` would fill them in otherwise.
+fn expand_url(template: &str, captures: &Captures) -> String {
+    let mut url = String::new();
+    let mut rest = template;
+    while let Some(at) = rest.find('$') {
+        url.push_str(&rest[..at]);
+        rest = &rest[at + 1..];
+        if let Some(after) = rest.strip_prefix('$') {
+            url.push('$');
+            rest = after;
+            continue;
+        }
+        let (name, after) = match rest.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], &braced[end + 1..]),
+                None => ("", rest),
+            },
+            None => {
+                let end = (rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')))
+                    .unwrap_or(rest.len());
+                rest.split_at(end)
+            }
+        };
+        if name.is_empty() {
+            url.push('$');
+            continue;
+        }
+        let group = match name.parse() {
+            Ok(index) => captures.get(index),
+            Err(_) => captures.name(name),
+        };
+        for byte in group.map_or("", |group| group.as_str()).bytes() {
+            if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+                url.push(byte as char);
+            } else {
+                url.push_str(&format!("%{:02X}", byte));
+            }
+        }
+        rest = after;
+    }
+    url.push_str(rest);
+    url
+}
+
+/// What a URL or path is handed to the desktop's default handler as:
+/// itself, with a leading `~/` expanded, which handlers don't do. Nothing
+/// else changes, so a query string's `&` reaches the handler.
+pub fn open_target(target: &str) -> OsString {
+    match (target.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => std::path::Path::new(&home).join(rest).into_os_string(),
+        _ => target.into(),
+    }
+}
+
+/// The command opening `target` with the desktop's default handler: `open`
+/// on macOS, `xdg-open` elsewhere, with the target as its one argument.
+/// Windows has no such command that leaves the target alone (`start` is
+/// a cmd.exe builtin, and cmd.exe would parse `&` and `|` in it), so
+/// targets are handed to `ShellExecuteW` there.
+#[cfg(not(any(windows, target_arch = "wasm32")))]
+pub fn open_command(target: &std::ffi::OsStr) -> std::process::Command {
+    #[cfg(target_os = "macos")]
+    let mut command = std::process::Command::new("open");
+    #[cfg(not(target_os = "macos"))]
+    let mut command = std::process::Command::new("xdg-open");
+    command.arg(target);
+    command
+}
+
+/// The arguments opening `path` at `line` and `column` in `editor`, a
+/// program like `$EDITOR`'s first word. Editors disagree on how to say
+/// where to start:
+///
+/// ```
+/// use adamant::hints::editor_args;
+///
+/// assert_eq!(editor_args("vim", "a.rs", Some(12), None), ["+12", "a.rs"]);
+/// assert_eq!(
+///     editor_args("/usr/bin/code", "a.rs", Some(12), Some(5)),
+///     ["--goto", "a.rs:12:5"]
+/// );
+/// ```
+pub fn editor_args(
+    editor: &str,
+    path: &str,
+    line: Option<usize>,
+    column: Option<usize>,
+) -> Vec<String> {
+    let Some(line) = line else {
+        return vec![path.to_string()];
+    };
+    let name = editor.rsplit(['/', '\\']).next().unwrap_or(editor);
+    let name = name.strip_suffix(".exe").unwrap_or(name);
+    let at = |separator: &str| match column {
+        Some(column) => format!("{}{}{}", line, separator, column),
+        None => line.to_string(),
+    };
+    match name {
+        "code" | "code-insiders" | "codium" | "cursor" => {
+            vec!["--goto".into(), format!("{}:{}", path, at(":"))]
+        }
+        "subl" | "hx" | "helix" | "micro" | "zed" => vec![format!("{}:{}", path, at(":"))],
+        "vi" | "vim" | "nvim" | "gvim" | "mvim" if column.is_some() => {
+            vec![format!("+call cursor({})", at(",")), path.into()]
+        }
+        "nano" => vec![format!("+{}", at(",")), path.into()],
+        "emacs" | "emacsclient" | "kak" => vec![format!("+{}", at(":")), path.into()],
+        // The common denominator: a line, no column
+        _ => vec![format!("+{}", line), path.into()],
+    }
+}
+
+/// The characters of the rows of a line (wide-character spacers skipped)
+/// and, for every byte offset of a character boundary, the cell it starts
+/// at, counted from the start of the line.
+fn line_text(line: &[&Row], cols: usize) -> (String, Vec<usize>) {
+    let mut text = String::new();
+    let mut cells = Vec::new();
+    for (i, row) in line.iter().enumerate() {
+        for (col, cell) in row.cells().iter().enumerate() {
+            if cell.flags.contains(Flags::WIDE_SPACER) {
+                continue;
+            }
+            text.push(cell.c);
+            cells.resize(text.len(), i * cols + col);
+        }
+    }
+    cells.push(line.len() * cols);
+    (text, cells)
+}
+
+/// Drop punctuation that ends the sentence around a match, and closing
+/// brackets without an opening one inside it, like `(see https://a.b/c).`
+fn trim_match(text: &str) -> &str {
+    let mut text = text;
+    loop {
+        let trimmed = text.trim_end_matches(['.', ',', ':', ';', '!', '?', '\'', '"']);
+        let trimmed = match trimmed.chars().last() {
+            Some(close @ (')' | ']' | '}')) => {
+                let open = match close {
+                    ')' => '(',
+                    ']' => '[',
+                    _ => '{',
+                };
+                if trimmed.matches(close).count() > trimmed.matches(open).count() {
+                    &trimmed[..trimmed.len() - 1]
+                } else {
+                    trimmed
+                }
+            }
+            _ => trimmed,
+        };
+        if trimmed.len() == text.len() {
+            return text;
+        }
+        text = trimmed;
+    }
+}
@@ -0,0 +1,545 @@
+//! Global Hotkeys
+//!
+//! A key combination that reaches Adamant while another application has
+//! the focus, for the dropdown window (`Config::dropdown`). [`Hotkey`]
+//! parses the configured text; [`GlobalHotkey`] registers it with the
+//! system and calls back on every press:
+//!
+//! - X11: a passive grab on the root window (also with Caps Lock and Num
+//!   Lock on, which X treats as modifiers)
+//! - Windows: `RegisterHotKey` on a thread of its own
+//! - macOS: Carbon's `RegisterEventHotKey`, which needs no accessibility
+//!   permission
+//!
+//! Wayland has no way for a client to grab keys, so registering fails
+//! there. Inside the window, [`Hotkey::matches`] checks key presses
+//! against plugin shortcuts.
+//!
+//! ```
+//! use adamant::hotkey::{Hotkey, HotkeyKey};
+//! use winit::keyboard::ModifiersState;
+//!
+//! let hotkey = Hotkey::parse("Ctrl+Alt+`").unwrap();
+//! assert_eq!(hotkey.modifiers, ModifiersState::CONTROL | ModifiersState::ALT);
+//! assert_eq!(hotkey.key, HotkeyKey::Grave);
+//! assert_eq!(Hotkey::parse("F12").unwrap().key, HotkeyKey::F(12));
+//! assert!(Hotkey::parse("Ctrl+Hyper+x").is_err());
+//! ```
+
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+
+use crate::error::Result;
+
+/// The key of a [`Hotkey`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HotkeyKey {
+    /// A letter (lower case) or a digit
+    Char(char),
+    /// F1 to F12
+    F(u8),
+    /// The key left of 1 on US layouts
+    Grave,
+    Space,
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Modifiers and a key, written like `Ctrl+Shift+space` or `F12`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Hotkey {
+    pub modifiers: ModifiersState,
+    pub key: HotkeyKey,
+}
+
+impl Hotkey {
+    /// Parse `+`-separated modifiers (`Ctrl`, `Shift`, `Alt`/`Option`,
+    /// `Super`/`Cmd`/`Win`, in any case) followed by a key: a letter, a
+    /// digit, `F1`-`F12`, `` ` ``/`grave`, `space` or an arrow (`up`,
+    /// `down`, `left`, `right`).
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let key = parts.pop().filter(|key| !key.is_empty());
+        let Some(key) = key else {
+            return Err(format!("{:?} has no key", text));
+        };
+        let mut modifiers = ModifiersState::empty();
+        for part in parts {
+            modifiers |= parse_modifier(part, text)?;
+        }
+        let lower = key.to_ascii_lowercase();
+        let key = match lower.as_str() {
+            "`" | "grave" => HotkeyKey::Grave,
+            "space" => HotkeyKey::Space,
+            "up" => HotkeyKey::Up,
+            "down" => HotkeyKey::Down,
+            "left" => HotkeyKey::Left,
+            "right" => HotkeyKey::Right,
+            _ => match (lower.strip_prefix('f'), lower.chars().next()) {
+                (Some(number), _) if !number.is_empty() => match number.parse() {
+                    Ok(n @ 1..=12) => HotkeyKey::F(n),
+                    _ => return Err(format!("unknown key {:?} in {:?}", key, text)),
+                },
+                (_, Some(c)) if lower.len() == 1 && c.is_ascii_alphanumeric() => HotkeyKey::Char(c),
+                _ => return Err(format!("unknown key {:?} in {:?}", key, text)),
+            },
+        };
+        Ok(Self { modifiers, key })
+    }
+
+    /// Whether `key`, pressed with exactly `modifiers`, is this hotkey.
+    /// Letters match in either case, as Shift changes them.
+    pub fn matches(&self, modifiers: ModifiersState, key: &Key) -> bool {
+        if modifiers != self.modifiers {
+            return false;
+        }
+        match (self.key, key) {
+            (HotkeyKey::Char(c), Key::Character(text)) => {
+                text.eq_ignore_ascii_case(c.encode_utf8(&mut [0; 4]))
+            }
+            (HotkeyKey::Grave, Key::Character(text)) => text == "`",
+            (HotkeyKey::Space, Key::Named(NamedKey::Space)) => true,
+            (HotkeyKey::Up, Key::Named(NamedKey::ArrowUp))
+            | (HotkeyKey::Down, Key::Named(NamedKey::ArrowDown))
+            | (HotkeyKey::Left, Key::Named(NamedKey::ArrowLeft))
+            | (HotkeyKey::Right, Key::Named(NamedKey::ArrowRight)) => true,
+            (HotkeyKey::F(n), Key::Named(named)) => F_KEYS.get(usize::from(n) - 1) == Some(named),
+            _ => false,
+        }
+    }
+}
+
+/// Parse a modifier of `text`: `Ctrl`, `Shift`, `Alt` or `Super` and their
+/// other names (`Cmd`, `Option`, `Win`), in any case.
+pub fn parse_modifier(part: &str, text: &str) -> Result<ModifiersState, String> {
+    Ok(match part.to_ascii_lowercase().as_str() {
+        "ctrl" | "control" => ModifiersState::CONTROL,
+        "shift" => ModifiersState::SHIFT,
+        "alt" | "option" => ModifiersState::ALT,
+        "super" | "cmd" | "command" | "win" => ModifiersState::SUPER,
+        _ => return Err(format!("unknown modifier {:?} in {:?}", part, text)),
+    })
+}
+
+/// The function keys, F1 first.
+const F_KEYS: [NamedKey; 12] = [
+    NamedKey::F1,
+    NamedKey::F2,
+    NamedKey::F3,
+    NamedKey::F4,
+    NamedKey::F5,
+    NamedKey::F6,
+    NamedKey::F7,
+    NamedKey::F8,
+    NamedKey::F9,
+    NamedKey::F10,
+    NamedKey::F11,
+    NamedKey::F12,
+];
+
+/// A hotkey registered with the system, until dropped.
+pub struct GlobalHotkey {
+    _registration: platform::Registration,
+}
+
+impl GlobalHotkey {
+    /// Register `hotkey`; `pressed` runs on every press, on a background
+    /// thread (X11, Windows) or the main thread (macOS). Fails when
+    /// another application holds the same combination, or on platforms
+    /// without global hotkeys.
+    pub fn register(hotkey: Hotkey, pressed: impl Fn() + Send + 'static) -> Result<Self> {
+        Ok(Self {
+            _registration: platform::register(hotkey, Box::new(pressed))?,
+        })
+    }
+}
+
+type Callback = Box<dyn Fn() + Send>;
+
+#[cfg(all(
+    unix,
+    not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+))]
+mod platform {
+    use std::sync::Arc;
+
+    use winit::keyboard::ModifiersState;
+    use x11rb::connection::Connection;
+    use x11rb::protocol::Event;
+    use x11rb::protocol::xproto::{ConnectionExt, GrabMode, ModMask};
+    use x11rb::rust_connection::RustConnection;
+
+    use super::{Callback, Hotkey, HotkeyKey};
+    use crate::error::{AdamantError, Result};
+
+    pub struct Registration {
+        conn: Arc<RustConnection>,
+        root: u32,
+        keycode: u8,
+        masks: Vec<ModMask>,
+    }
+
+    pub fn register(hotkey: Hotkey, pressed: Callback) -> Result<Registration> {
+        // TODO: The global shortcuts portal on Wayland
+        if std::env::var_os("DISPLAY").is_none() {
+            return Err(unsupported("global hotkeys need X11 (Wayland has none)"));
+        }
+        let (conn, screen) = x11rb::connect(None).map_err(std::io::Error::other)?;
+        let root = conn.setup().roots[screen].root;
+        let keycode = keycode(&conn, keysym(hotkey.key))?
+            .ok_or_else(|| unsupported("the hotkey's key is not on the keyboard"))?;
+
+        // X counts Caps Lock and Num Lock as modifiers: grab the key with
+        // each of them on too
+        let base = mod_mask(hotkey.modifiers);
+        let masks: Vec<ModMask> = [
+            0,
+            u16::from(ModMask::LOCK),
+            u16::from(ModMask::M2),
+            u16::from(ModMask::LOCK | ModMask::M2),
+        ]
+        .into_iter()
+        .map(|extra| ModMask::from(u16::from(base) | extra))
+        .collect();
+        for &mask in &masks {
+            conn.grab_key(true, root, mask, keycode, GrabMode::ASYNC, GrabMode::ASYNC)
+                .map_err(std::io::Error::other)?
+                .check()
+                .map_err(|_| std::io::Error::other("another application holds the hotkey"))?;
+        }
+        conn.flush().map_err(std::io::Error::other)?;
+
+        let conn = Arc::new(conn);
+        let events = Arc::clone(&conn);
+        // Blocks on the connection until the process ends
+        std::thread::spawn(move || {
+            while let Ok(event) = events.wait_for_event() {
+                if let Event::KeyPress(_) = event {
+                    pressed();
+                }
+            }
+        });
+        Ok(Registration {
+            conn,
+            root,
+            keycode,
+            masks,
+        })
+    }
+
+    impl Drop for Registration {
+        fn drop(&mut self) {
+            for &mask in &self.masks {
+                let _ = self.conn.ungrab_key(self.keycode, self.root, mask);
+            }
+            let _ = self.conn.flush();
+        }
+    }
+
+    fn unsupported(message: &str) -> AdamantError {
+        std::io::Error::new(std::io::ErrorKind::Unsupported, message).into()
+    }
+
+    /// The X keysym of the key; Latin-1 keysyms are the characters' codes.
+    fn keysym(key: HotkeyKey) -> u32 {
+        match key {
+            HotkeyKey::Char(c) => c as u32,
+            HotkeyKey::F(n) => 0xffbe + u32::from(n) - 1,
+            HotkeyKey::Grave => 0x60,
+            HotkeyKey::Space => 0x20,
+            HotkeyKey::Up => 0xff52,
+            HotkeyKey::Down => 0xff54,
+            HotkeyKey::Left => 0xff51,
+            HotkeyKey::Right => 0xff53,
+        }
+    }
+
+    /// The first keycode that types `keysym` in the current layout.
+    fn keycode(conn: &RustConnection, keysym: u32) -> Result<Option<u8>> {
+        let setup = conn.setup();
+        let (min, max) = (setup.min_keycode, setup.max_keycode);
+        let mapping = conn
+            .get_keyboard_mapping(min, max - min + 1)
+            .map_err(std::io::Error::other)?
+            .reply()
+            .map_err(std::io::Error::other)?;
+        let per_keycode = usize::from(mapping.keysyms_per_keycode).max(1);
+        Ok(mapping
+            .keysyms
+            .chunks(per_keycode)
+            .position(|keysyms| keysyms.contains(&keysym))
+            .map(|i| min + i as u8))
+    }
+
+    fn mod_mask(modifiers: ModifiersState) -> ModMask {
+        let mut mask = 0;
+        for (modifier, bits) in [
+            (ModifiersState::SHIFT, ModMask::SHIFT),
+            (ModifiersState::CONTROL, ModMask::CONTROL),
+            (ModifiersState::ALT, ModMask::M1),
+            (ModifiersState::SUPER, ModMask::M4),
+        ] {
+            if modifiers.contains(modifier) {
+                mask |= u16::from(bits);
+            }
+        }
+        ModMask::from(mask)
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::sync::mpsc;
+
+    use windows_sys::Win32::System::Threading::GetCurrentThreadId;
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+        MOD_ALT, MOD_CONTROL, MOD_NOREPEAT, MOD_SHIFT, MOD_WIN, RegisterHotKey, UnregisterHotKey,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        GetMessageW, MSG, PostThreadMessageW, WM_HOTKEY, WM_QUIT,
+    };
+    use winit::keyboard::ModifiersState;
+
+    use super::{Callback, Hotkey, HotkeyKey};
+    use crate::error::Result;
+
+    /// The thread the hotkey's messages are posted to.
+    pub struct Registration {
+        thread: u32,
+    }
+
+    pub fn register(hotkey: Hotkey, pressed: Callback) -> Result<Registration> {
+        let mut modifiers = MOD_NOREPEAT;
+        for (modifier, flag) in [
+            (ModifiersState::SHIFT, MOD_SHIFT),
+            (ModifiersState::CONTROL, MOD_CONTROL),
+            (ModifiersState::ALT, MOD_ALT),
+            (ModifiersState::SUPER, MOD_WIN),
+        ] {
+            if hotkey.modifiers.contains(modifier) {
+                modifiers |= flag;
+            }
+        }
+        let vk = match hotkey.key {
+            HotkeyKey::Char(c) => c.to_ascii_uppercase() as u32,
+            HotkeyKey::F(n) => 0x70 + u32::from(n) - 1,
+            // VK_OEM_3
+            HotkeyKey::Grave => 0xc0,
+            HotkeyKey::Space => 0x20,
+            HotkeyKey::Up => 0x26,
+            HotkeyKey::Down => 0x28,
+            HotkeyKey::Left => 0x25,
+            HotkeyKey::Right => 0x27,
+        };
+
+        let (registered, result) = mpsc::channel();
+        std::thread::spawn(move || {
+            // SAFETY: A hotkey for this thread's message queue (no window),
+            // unregistered before the thread ends
+            unsafe {
+                if RegisterHotKey(std::ptr::null_mut(), 1, modifiers, vk) == 0 {
+                    let _ = registered.send(Err(std::io::Error::last_os_error()));
+                    return;
+                }
+                let _ = registered.send(Ok(GetCurrentThreadId()));
+                let mut msg: MSG = std::mem::zeroed();
+                while GetMessageW(&mut msg, std::ptr::null_mut(), 0, 0) > 0 {
+                    if msg.message == WM_HOTKEY {
+                        pressed();
+                    }
+                }
+                UnregisterHotKey(std::ptr::null_mut(), 1);
+            }
+        });
+        let thread = result
+            .recv()
+            .map_err(|_| std::io::Error::other("the hotkey thread ended"))??;
+        Ok(Registration { thread })
+    }
+
+    impl Drop for Registration {
+        fn drop(&mut self) {
+            // SAFETY: Ends the thread's message loop
+            unsafe { PostThreadMessageW(self.thread, WM_QUIT, 0, 0) };
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::c_void;
+
+    use winit::keyboard::ModifiersState;
+
+    use super::{Callback, Hotkey, HotkeyKey};
+    use crate::error::Result;
+
+    #[repr(C)]
+    struct EventHotKeyID {
+        signature: u32,
+        id: u32,
+    }
+
+    #[repr(C)]
+    struct EventTypeSpec {
+        event_class: u32,
+        event_kind: u32,
+    }
+
+    type Handler = extern "C" fn(*mut c_void, *mut c_void, *mut c_void) -> i32;
+
+    #[link(name = "Carbon", kind = "framework")]
+    unsafe extern "C" {
+        fn GetApplicationEventTarget() -> *mut c_void;
+        fn InstallEventHandler(
+            target: *mut c_void,
+            handler: Handler,
+            num_types: usize,
+            list: *const EventTypeSpec,
+            user_data: *mut c_void,
+            out_ref: *mut *mut c_void,
+        ) -> i32;
+        fn RemoveEventHandler(handler: *mut c_void) -> i32;
+        fn RegisterEventHotKey(
+            key_code: u32,
+            modifiers: u32,
+            id: EventHotKeyID,
+            target: *mut c_void,
+            options: u32,
+            out_ref: *mut *mut c_void,
+        ) -> i32;
+        fn UnregisterEventHotKey(hotkey: *mut c_void) -> i32;
+    }
+
+    /// `'keyb'` and `kEventHotKeyPressed`
+    const KEYBOARD_CLASS: u32 = u32::from_be_bytes(*b"keyb");
+    const HOTKEY_PRESSED: u32 = 5;
+
+    pub struct Registration {
+        hotkey: *mut c_void,
+        handler: *mut c_void,
+        callback: *mut Callback,
+    }
+
+    extern "C" fn handle(_call: *mut c_void, _event: *mut c_void, data: *mut c_void) -> i32 {
+        // SAFETY: `data` is the registration's callback, alive until the
+        // handler is removed
+        let pressed = unsafe { &*(data as *const Callback) };
+        pressed();
+        0
+    }
+
+    pub fn register(hotkey: Hotkey, pressed: Callback) -> Result<Registration> {
+        let mut modifiers = 0;
+        for (modifier, flag) in [
+            (ModifiersState::SUPER, 1 << 8),
+            (ModifiersState::SHIFT, 1 << 9),
+            (ModifiersState::ALT, 1 << 11),
+            (ModifiersState::CONTROL, 1 << 12),
+        ] {
+            if hotkey.modifiers.contains(modifier) {
+                modifiers |= flag;
+            }
+        }
+        let callback = Box::into_raw(Box::new(pressed));
+        let mut registration = Registration {
+            hotkey: std::ptr::null_mut(),
+            handler: std::ptr::null_mut(),
+            callback,
+        };
+        let spec = EventTypeSpec {
+            event_class: KEYBOARD_CLASS,
+            event_kind: HOTKEY_PRESSED,
+        };
+        let id = EventHotKeyID {
+            signature: u32::from_be_bytes(*b"adam"),
+            id: 1,
+        };
+        // SAFETY: The handler gets the callback, which the registration
+        // frees only after removing the handler; must run on the main thread
+        let status = unsafe {
+            let target = GetApplicationEventTarget();
+            match InstallEventHandler(
+                target,
+                handle,
+                1,
+                &spec,
+                callback.cast(),
+                &mut registration.handler,
+            ) {
+                0 => RegisterEventHotKey(
+                    key_code(hotkey.key),
+                    modifiers,
+                    id,
+                    target,
+                    0,
+                    &mut registration.hotkey,
+                ),
+                status => status,
+            }
+        };
+        if status != 0 {
+            return Err(std::io::Error::other(format!(
+                "registering the hotkey failed with status {}",
+                status
+            ))
+            .into());
+        }
+        Ok(registration)
+    }
+
+    impl Drop for Registration {
+        fn drop(&mut self) {
+            // SAFETY: Only what `register` created, each released once
+            unsafe {
+                if !self.hotkey.is_null() {
+                    UnregisterEventHotKey(self.hotkey);
+                }
+                if !self.handler.is_null() {
+                    RemoveEventHandler(self.handler);
+                }
+                drop(Box::from_raw(self.callback));
+            }
+        }
+    }
+
+    /// The virtual key code (`kVK_*`, positions on the ANSI keyboard).
+    fn key_code(key: HotkeyKey) -> u32 {
+        const LETTERS: [u32; 26] = [
+            0x00, 0x0b, 0x08, 0x02, 0x0e, 0x03, 0x05, 0x04, 0x22, 0x26, 0x28, 0x25, 0x2e, 0x2d,
+            0x1f, 0x23, 0x0c, 0x0f, 0x01, 0x11, 0x20, 0x09, 0x0d, 0x07, 0x10, 0x06,
+        ];
+        const DIGITS: [u32; 10] = [0x1d, 0x12, 0x13, 0x14, 0x15, 0x17, 0x16, 0x1a, 0x1c, 0x19];
+        const F_KEYS: [u32; 12] = [
+            0x7a, 0x78, 0x63, 0x76, 0x60, 0x61, 0x62, 0x64, 0x65, 0x6d, 0x67, 0x6f,
+        ];
+        match key {
+            HotkeyKey::Char(c @ 'a'..='z') => LETTERS[c as usize - 'a' as usize],
+            HotkeyKey::Char(c) => DIGITS[c.to_digit(10).unwrap_or(0) as usize],
+            HotkeyKey::F(n) => F_KEYS[usize::from(n) - 1],
+            HotkeyKey::Grave => 0x32,
+            HotkeyKey::Space => 0x31,
+            HotkeyKey::Up => 0x7e,
+            HotkeyKey::Down => 0x7d,
+            HotkeyKey::Left => 0x7b,
+            HotkeyKey::Right => 0x7c,
+        }
+    }
+}
+
+#[cfg(any(not(any(unix, windows)), target_os = "ios", target_os = "android"))]
+mod platform {
+    use super::{Callback, Hotkey};
+    use crate::error::Result;
+
+    pub enum Registration {}
+
+    pub fn register(_hotkey: Hotkey, _pressed: Callback) -> Result<Registration> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "global hotkeys are not supported here",
+        )
+        .into())
+    }
+}
@@ -0,0 +1,323 @@
+//! Keyboard and mouse input translation.
+//!
+//! Converts winit key events and mouse actions into the byte sequences a
+//! terminal application expects on its input (xterm conventions).
+//!
+//! Dead keys and compose sequences are resolved by the platform: the dead
+//! key itself (`Key::Dead`) and the keys in the middle of a compose
+//! sequence carry no text and send nothing, and the key that completes the
+//! sequence carries the composed character as its text. Characters typed
+//! with AltGr (Ctrl+Alt on Windows) or Option (macOS) are sent as they are,
+//! not as Ctrl or Meta combinations. Option keys set up as Alt with
+//! `Config::option_as_alt` arrive as Alt with the plain key's text, and
+//! send Meta combinations.
+//!
+//! Keys on the numeric keypad send SS3 sequences in application keypad
+//! mode (DECKPAM), the way `Config::keypad` says.
+
+use winit::{
+    event::{KeyEvent, MouseButton},
+    keyboard::{Key, KeyLocation, ModifiersState, NamedKey},
+};
+
+use crate::config::Keypad;
+use crate::term::Mode;
+
+/// The parts of a key press that decide what it sends.
+///
+/// winit's `KeyEvent` can't be constructed outside winit, so the
+/// translation works on this instead; [`key_to_bytes`] fills it in from an
+/// event.
+#[derive(Clone, Debug)]
+pub struct KeyInput<'a> {
+    /// The key after the keyboard layout and modifiers
+    pub key: &'a Key,
+    /// Text the press produces, including dead key and compose results
+    pub text: Option<&'a str>,
+    /// The key with no modifiers applied, if the platform reports it
+    pub unmodified: Option<&'a Key>,
+    /// The key is on the numeric keypad
+    pub numpad: bool,
+}
+
+/// Translate a key press into bytes for the PTY, if it produces any.
+pub fn key_to_bytes(
+    event: &KeyEvent,
+    mods: ModifiersState,
+    mode: Mode,
+    keypad: Keypad,
+) -> Option<Vec<u8>> {
+    // Browsers don't report the unmodified key.
+    #[cfg(not(target_arch = "wasm32"))]
+    let unmodified = {
+        use winit::platform::modifier_supplement::KeyEventExtModifierSupplement;
+        Some(event.key_without_modifiers())
+    };
+    #[cfg(target_arch = "wasm32")]
+    let unmodified = None;
+
+    let input = KeyInput {
+        key: &event.logical_key,
+        text: event.text.as_deref(),
+        unmodified: unmodified.as_ref(),
+        numpad: event.location == KeyLocation::Numpad,
+    };
+    key_input_to_bytes(&input, mods, mode, keypad)
+}
+
+/// [`key_to_bytes`] for a key press described by hand.
+pub fn key_input_to_bytes(
+    input: &KeyInput<'_>,
+    mods: ModifiersState,
+    mode: Mode,
+    keypad: Keypad,
+) -> Option<Vec<u8>> {
+    if input.numpad
+        && mode.contains(Mode::APP_KEYPAD)
+        && let Some(bytes) = application_keypad(input.key, mods, keypad)
+    {
+        return Some(bytes);
+    }
+
+    if let Key::Named(named) = input.key
+        && let Some(bytes) = named_key(*named, mods, mode)
+    {
+        return Some(bytes);
+    }
+
+    if typed_with_modifiers(input, mods) {
+        return input.text.map(|text| text.as_bytes().to_vec());
+    }
+
+    // Ctrl+letter produces the matching C0 control code.
+    if mods.control_key()
+        && let Key::Character(text) = input.key
+        && let Some(c) = text.chars().next()
+    {
+        let control = match c.to_ascii_lowercase() {
+            c @ 'a'..='z' => Some(c as u8 - b'a' + 1),
+            '@' | ' ' | '2' => Some(0x00),
+            '[' | '3' => Some(0x1B),
+            '\\' | '4' => Some(0x1C),
+            ']' | '5' => Some(0x1D),
+            '^' | '6' => Some(0x1E),
+            '_' | '7' | '-' => Some(0x1F),
+            _ => None,
+        };
+        if let Some(byte) = control {
+            return Some(with_alt(vec![byte], mods));
+        }
+    }
+
+    let text = input.text?;
+    if text.is_empty() {
+        return None;
+    }
+    Some(with_alt(text.as_bytes().to_vec(), mods))
+}
+
+/// Whether Ctrl/Alt were part of typing the character rather than a
+/// combination on top of it: the layout turned the key into a different
+/// character with AltGr (reported as Ctrl+Alt on Windows) or produced a
+/// non-ASCII one with Option on macOS.
+fn typed_with_modifiers(input: &KeyInput<'_>, mods: ModifiersState) -> bool {
+    let (Key::Character(text), Some(Key::Character(base))) = (input.key, input.unmodified) else {
+        return false;
+    };
+    if text.to_lowercase() == base.to_lowercase() {
+        return false;
+    }
+    (mods.control_key() && mods.alt_key()) || (mods.alt_key() && !text.is_ascii())
+}
+
+/// Alt prefixes the sequence with ESC (meta sends escape).
+fn with_alt(bytes: Vec<u8>, mods: ModifiersState) -> Vec<u8> {
+    if mods.alt_key() {
+        let mut prefixed = vec![0x1B];
+        prefixed.extend(bytes);
+        prefixed
+    } else {
+        bytes
+    }
+}
+
+/// The SS3 sequence a keypad key sends in application keypad mode, if it
+/// sends one: not with Ctrl or Alt, and for the digits and the cursor keys
+/// they share with [`Keypad::NumLock`] only the operators and Enter.
+fn application_keypad(key: &Key, mods: ModifiersState, keypad: Keypad) -> Option<Vec<u8>> {
+    if mods.control_key() || mods.alt_key() {
+        return None;
+    }
+    let numlock = keypad == Keypad::NumLock;
+    let final_byte = match key {
+        Key::Named(NamedKey::Enter) => b'M',
+        Key::Character(text) => match text.as_str() {
+            "*" => b'j',
+            "+" => b'k',
+            "," => b'l',
+            "-" => b'm',
+            "/" => b'o',
+            "=" => b'X',
+            // NumLock on
+            _ if numlock => return None,
+            "." => b'n',
+            digit => match digit.as_bytes() {
+                [c @ b'0'..=b'9'] => b'p' + (c - b'0'),
+                _ => return None,
+            },
+        },
+        // NumLock off: the digits' keys move the cursor
+        _ if numlock => return None,
+        Key::Named(named) => match named {
+            NamedKey::Insert => b'p',
+            NamedKey::End => b'q',
+            NamedKey::ArrowDown => b'r',
+            NamedKey::PageDown => b's',
+            NamedKey::ArrowLeft => b't',
+            NamedKey::Clear => b'u',
+            NamedKey::ArrowRight => b'v',
+            NamedKey::Home => b'w',
+            NamedKey::ArrowUp => b'x',
+            NamedKey::PageUp => b'y',
+            NamedKey::Delete => b'n',
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(vec![0x1b, b'O', final_byte])
+}
+
+fn named_key(key: NamedKey, mods: ModifiersState, mode: Mode) -> Option<Vec<u8>> {
+    // xterm modifier parameter: 1 + (shift | alt << 1 | ctrl << 2)
+    let modifier = 1
+        + mods.shift_key() as u8
+        + ((mods.alt_key() as u8) << 1)
+        + ((mods.control_key() as u8) << 2);
+
+    let cursor = |c: char| {
+        if modifier > 1 {
+            format!("\x1b[1;{}{}", modifier, c).into_bytes()
+        } else if mode.contains(Mode::APP_CURSOR) {
+            format!("\x1bO{}", c).into_bytes()
+        } else {
+            format!("\x1b[{}", c).into_bytes()
+        }
+    };
+    let tilde = |n: u8| {
+        if modifier > 1 {
+            format!("\x1b[{};{}~", n, modifier).into_bytes()
+        } else {
+            format!("\x1b[{}~", n).into_bytes()
+        }
+    };
+    let function = |c: char| {
+        if modifier > 1 {
+            format!("\x1b[1;{}{}", modifier, c).into_bytes()
+        } else {
+            format!("\x1bO{}", c).into_bytes()
+        }
+    };
+
+    let bytes = match key {
+        NamedKey::Enter => with_alt(b"\r".to_vec(), mods),
+        NamedKey::Backspace => with_alt(
+            if mods.control_key() { b"\x08" } else { b"\x7f" }.to_vec(),
+            mods,
+        ),
+        NamedKey::Tab if mods.shift_key() => b"\x1b[Z".to_vec(),
+        NamedKey::Tab => with_alt(b"\t".to_vec(), mods),
+        NamedKey::Escape => with_alt(b"\x1b".to_vec(), mods),
+        NamedKey::Space if mods.control_key() => vec![0x00],
+        NamedKey::ArrowUp => cursor('A'),
+        NamedKey::ArrowDown => cursor('B'),
+        NamedKey::ArrowRight => cursor('C'),
+        NamedKey::ArrowLeft => cursor('D'),
+        NamedKey::Home => cursor('H'),
+        NamedKey::End => cursor('F'),
+        NamedKey::Insert => tilde(2),
+        NamedKey::Delete => tilde(3),
+        NamedKey::PageUp => tilde(5),
+        NamedKey::PageDown => tilde(6),
+        NamedKey::F1 => function('P'),
+        NamedKey::F2 => function('Q'),
+        NamedKey::F3 => function('R'),
+        NamedKey::F4 => function('S'),
+        NamedKey::F5 => tilde(15),
+        NamedKey::F6 => tilde(17),
+        NamedKey::F7 => tilde(18),
+        NamedKey::F8 => tilde(19),
+        NamedKey::F9 => tilde(20),
+        NamedKey::F10 => tilde(21),
+        NamedKey::F11 => tilde(23),
+        NamedKey::F12 => tilde(24),
+        _ => return None,
+    };
+    Some(bytes)
+}
+
+/// A mouse action to report to an application that tracks the mouse.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MouseAction {
+    Press(MouseButton),
+    Release(MouseButton),
+    /// The pointer moved to another cell, with this button held if any.
+    Motion(Option<MouseButton>),
+    WheelUp,
+    WheelDown,
+    /// The wheel tilted, or turned for sideways scrolling
+    WheelLeft,
+    WheelRight,
+}
+
+/// Encode a mouse action at the 0-based cell `col`, `row`, if the active
+/// tracking mode asks for it.
+pub fn mouse_report(
+    action: MouseAction,
+    col: usize,
+    row: usize,
+    mods: ModifiersState,
+    mode: Mode,
+) -> Option<Vec<u8>> {
+    let button = |button: MouseButton| match button {
+        MouseButton::Left => Some(0),
+        MouseButton::Middle => Some(1),
+        MouseButton::Right => Some(2),
+        _ => None,
+    };
+
+    let wanted = match action {
+        MouseAction::Motion(None) => mode.contains(Mode::MOUSE_MOTION),
+        MouseAction::Motion(Some(_)) => mode.intersects(Mode::MOUSE_DRAG | Mode::MOUSE_MOTION),
+        _ => mode.intersects(Mode::MOUSE_MODE),
+    };
+    if !wanted {
+        return None;
+    }
+
+    let (mut code, release) = match action {
+        MouseAction::Press(b) => (button(b)?, false),
+        MouseAction::Release(b) => (button(b)?, true),
+        MouseAction::Motion(b) => (b.map_or(Some(3), button)? + 32, false),
+        MouseAction::WheelUp => (64, false),
+        MouseAction::WheelDown => (65, false),
+        MouseAction::WheelLeft => (66, false),
+        MouseAction::WheelRight => (67, false),
+    };
+    code += (mods.shift_key() as u8) << 2;
+    code += (mods.alt_key() as u8) << 3;
+    code += (mods.control_key() as u8) << 4;
+
+    if mode.contains(Mode::SGR_MOUSE) {
+        let suffix = if release { 'm' } else { 'M' };
+        return Some(format!("\x1b[<{};{};{}{}", code, col + 1, row + 1, suffix).into_bytes());
+    }
+
+    // Legacy encoding: one byte per value offset by 32, releases don't say
+    // which button, and positions beyond 223 can't be expressed.
+    if release {
+        code = (code & !0b11) | 3;
+    }
+    let col = u8::try_from(col + 1 + 32).ok()?;
+    let row = u8::try_from(row + 1 + 32).ok()?;
+    Some(vec![0x1b, b'[', b'M', code + 32, col, row])
+}
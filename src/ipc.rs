@@ -0,0 +1,323 @@
+//! IPC Socket
+//!
+//! Each Adamant window listens on a Unix socket so scripts and other
+//! programs can drive it. The protocol is one command per line with
+//! shell-style quoting; every line is answered with `ok` once the command
+//! was accepted, or `error: <message>`. Queries answer `ok <n>` followed by
+//! `n` lines of data:
+//!
+//! ```text
+//! $ echo 'exec htop -d 10' | nc -U "$XDG_RUNTIME_DIR/adamant-1234.sock"
+//! ok
+//! $ echo 'get-cursor' | nc -U "$XDG_RUNTIME_DIR/adamant-1234.sock"
+//! ok 1
+//! 3 14
+//! ```
+//!
+//! | Command | Effect |
+//! |---------|--------|
+//! | `exec <program> [args...]` | Replace the pane's program, keeping its screen and scrollback |
+//! | `gpu <integrated\|discrete\|auto>` | Redraw on another GPU |
+//! | `get-text` | The visible screen, one line per row (trailing blanks trimmed) |
+//! | `get-cursor` | `<row> <col>` (0-based, on the visible screen), or nothing while hidden |
+//! | `get-selection` | The selected text, or nothing |
+//! | `get-unhandled` | The escape sequences received but not implemented: `<count>\t<sequence>\t<sample>` per line |
+//! | `set-color <foreground\|background\|cursor\|0-255> <color>` | Override a color of the session (`#rrggbb` or `rgb:rr/gg/bb`) |
+//! | `reset-colors` | Back to the configured colors, keeping the tint |
+//! | `tint <color> [amount]` / `tint off` | Mix a color into the background (amount 0-1, default 0.2) |
+//! | `read-only <on\|off\|toggle>` | Stop sending the window's input to the program, or start again |
+//! | `monitor <activity\|silence\|off>` | Watch for output after a pause, or for output stopping |
+//!
+//! The queries let automation tools, password managers and assistive
+//! technology read the window without going through the program running
+//! in it.
+//!
+//! Commands are handed to a callback on the connection's thread; the
+//! application forwards them into its event loop. Programs started in the
+//! terminal find the socket in `$ADAMANT_SOCKET`.
+
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::config::{GpuPreference, Monitor, Rgb, Tint};
+use crate::error::Result;
+use crate::term::{ColorSlot, parse_color};
+
+/// A request received over the socket.
+#[derive(Clone, Debug, PartialEq)]
+pub enum IpcCommand {
+    /// Replace the running program with `program args...`.
+    Exec { program: String, args: Vec<String> },
+    /// Switch the GPU the window is drawn with.
+    Gpu(GpuPreference),
+    /// Read something from the window; answered through the [`IpcReply`].
+    Query(IpcQuery),
+    /// Override a color of the session.
+    SetColor(ColorSlot, Rgb),
+    /// Forget the session's colors, except for the tint.
+    ResetColors,
+    /// Tint the background, or stop with `None`.
+    Tint(Option<Tint>),
+    /// Make the window read-only or not, or toggle it with `None`.
+    ReadOnly(Option<bool>),
+    /// Watch the window for activity or silence, or stop with `None`.
+    Monitor(Option<Monitor>),
+}
+
+/// What a query asks for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum IpcQuery {
+    /// The visible screen text
+    Text,
+    /// The cursor position
+    Cursor,
+    /// The selected text
+    Selection,
+    /// The escape sequences the terminal doesn't implement
+    Unhandled,
+}
+
+/// How long a query waits for the application to answer.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The way back to the connection a command came from. Only queries need
+/// to use it; dropping it answers with an error.
+pub struct IpcReply(Sender<Vec<String>>);
+
+impl IpcReply {
+    /// Answer a query with `lines` of data (which must not contain newlines).
+    pub fn send(self, lines: Vec<String>) {
+        let _ = self.0.send(lines);
+    }
+}
+
+impl IpcCommand {
+    /// Parse one protocol line.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut words = split_words(line)?.into_iter();
+        match words.next().as_deref() {
+            Some("exec") => {
+                let program = words.next().ok_or("exec: missing program")?;
+                Ok(Self::Exec {
+                    program,
+                    args: words.collect(),
+                })
+            }
+            Some("gpu") => match words.next().as_deref() {
+                Some("integrated") => Ok(Self::Gpu(GpuPreference::Integrated)),
+                Some("discrete") => Ok(Self::Gpu(GpuPreference::Discrete)),
+                Some("auto") => Ok(Self::Gpu(GpuPreference::Auto)),
+                _ => Err("gpu: expected integrated, discrete or auto".into()),
+            },
+            Some("get-text") => Ok(Self::Query(IpcQuery::Text)),
+            Some("get-cursor") => Ok(Self::Query(IpcQuery::Cursor)),
+            Some("get-selection") => Ok(Self::Query(IpcQuery::Selection)),
+            Some("get-unhandled") => Ok(Self::Query(IpcQuery::Unhandled)),
+            Some("set-color") => {
+                let usage =
+                    "set-color: expected foreground, background, cursor or 0-255, and a color";
+                let slot = match words.next().as_deref() {
+                    Some("foreground") => ColorSlot::Foreground,
+                    Some("background") => ColorSlot::Background,
+                    Some("cursor") => ColorSlot::Cursor,
+                    Some(index) => ColorSlot::Indexed(index.parse().map_err(|_| usage)?),
+                    None => return Err(usage.into()),
+                };
+                let color = words.next().as_deref().and_then(parse_color).ok_or(usage)?;
+                Ok(Self::SetColor(slot, color))
+            }
+            Some("reset-colors") => Ok(Self::ResetColors),
+            Some("tint") => {
+                let usage = "tint: expected a color and an amount between 0 and 1, or off";
+                match words.next().as_deref() {
+                    Some("off") => Ok(Self::Tint(None)),
+                    Some(color) => {
+                        let color = parse_color(color).ok_or(usage)?;
+                        let amount = match words.next() {
+                            Some(amount) => amount.parse().map_err(|_| usage)?,
+                            None => Tint::DEFAULT_AMOUNT,
+                        };
+                        if !(0.0..=1.0).contains(&amount) {
+                            return Err(usage.into());
+                        }
+                        Ok(Self::Tint(Some(Tint { color, amount })))
+                    }
+                    None => Err(usage.into()),
+                }
+            }
+            Some("read-only") => match words.next().as_deref() {
+                Some("on") => Ok(Self::ReadOnly(Some(true))),
+                Some("off") => Ok(Self::ReadOnly(Some(false))),
+                Some("toggle") => Ok(Self::ReadOnly(None)),
+                _ => Err("read-only: expected on, off or toggle".into()),
+            },
+            Some("monitor") => match words.next().as_deref() {
+                Some("activity") => Ok(Self::Monitor(Some(Monitor::Activity))),
+                Some("silence") => Ok(Self::Monitor(Some(Monitor::Silence))),
+                Some("off") => Ok(Self::Monitor(None)),
+                _ => Err("monitor: expected activity, silence or off".into()),
+            },
+            Some(verb) => Err(format!("unknown command: {}", verb)),
+            None => Err("empty command".into()),
+        }
+    }
+}
+
+/// Path of the socket this process is listening on, for child environments.
+static ACTIVE: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// The socket this process currently listens on, if any.
+pub fn active_socket() -> Option<PathBuf> {
+    ACTIVE.lock().ok()?.clone()
+}
+
+/// The listening socket; removed from the filesystem when dropped.
+pub struct IpcServer {
+    path: PathBuf,
+}
+
+impl IpcServer {
+    /// Listen on the default socket path for this process and pass every
+    /// valid command to `handler`.
+    pub fn bind(handler: impl Fn(IpcCommand, IpcReply) + Send + Sync + 'static) -> Result<Self> {
+        let path = default_path();
+        // A stale socket from a crashed process with a recycled pid
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path)?;
+        log::info!("IPC socket listening on {}", path.display());
+
+        let handler = Arc::new(handler);
+        std::thread::Builder::new()
+            .name("ipc-listener".into())
+            .spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let handler = Arc::clone(&handler);
+                    let _ = std::thread::Builder::new()
+                        .name("ipc-client".into())
+                        .spawn(move || serve(stream, &*handler));
+                }
+            })?;
+
+        if let Ok(mut active) = ACTIVE.lock() {
+            *active = Some(path.clone());
+        }
+        Ok(Self { path })
+    }
+
+    /// Where the socket lives.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        if let Ok(mut active) = ACTIVE.lock() {
+            *active = None;
+        }
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Answer the commands of one connection until it closes.
+fn serve(stream: UnixStream, handler: &dyn Fn(IpcCommand, IpcReply)) {
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match IpcCommand::parse(&line) {
+            Ok(command) => {
+                log::debug!("IPC: {:?}", command);
+                let query = matches!(command, IpcCommand::Query(_));
+                let (sender, answer) = mpsc::channel();
+                handler(command, IpcReply(sender));
+                if query {
+                    match answer.recv_timeout(QUERY_TIMEOUT) {
+                        Ok(lines) => {
+                            let mut reply = format!("ok {}", lines.len());
+                            for line in lines {
+                                reply.push('\n');
+                                reply.push_str(&line);
+                            }
+                            reply
+                        }
+                        Err(_) => "error: no answer".to_string(),
+                    }
+                } else {
+                    "ok".to_string()
+                }
+            }
+            Err(e) => format!("error: {}", e),
+        };
+        if writeln!(writer, "{}", reply).is_err() {
+            return;
+        }
+    }
+}
+
+/// `$XDG_RUNTIME_DIR/adamant-<pid>.sock`, or the temp dir without one.
+fn default_path() -> PathBuf {
+    let dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir);
+    dir.join(format!("adamant-{}.sock", std::process::id()))
+}
+
+/// Split a line into words like a POSIX shell: whitespace separates words,
+/// `'...'` is literal, `"..."` and bare words honor backslash escapes.
+fn split_words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated single quote".into()),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err("unterminated double quote".into()),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated double quote".into()),
+                    }
+                }
+            }
+            '\\' => {
+                let c = chars.next().ok_or("trailing backslash")?;
+                word.get_or_insert_with(String::new).push(c);
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
@@ -4,16 +4,148 @@
 //!
 //! # Module Structure
 //!
+//! The terminal core has no windowing or GPU dependencies and can be
+//! embedded by other Rust projects with `default-features = false`:
+//!
+//! - `a11y`: Screen content and change announcements for screen readers
+//! - `backend`: Terminal I/O backends (local PTY, websocket bridge)
+//! - `config`: Compile-time configuration
+//! - `error`: The `AdamantError` type
+//! - `grid`: Terminal state grid (cells, scrollback)
+//! - `parser`: ANSI/VT escape sequence parser
+//! - `term`: VT emulation tying the parser to the grid
+//! - `terminfo`: Adamant's terminfo entry and installing it
+//! - `pty`: Pseudo-terminal handling (feature `pty`)
+//! - `ffi`: C bindings for the terminal core (feature `ffi`)
+//! - `ipc`: Control socket for scripts (Unix)
+//!
+//! The windowed application is one consumer of that core (feature `gui`):
+//!
+//! - `bindings`: Keys and leader-key sequences bound to actions
+//! - `clipboard_history`: Recent copies and the picker pasting them again
+//! - `commands`: Actions of the command palette and fuzzy filtering
+//! - `hotkey`: Global hotkeys registered with the system (dropdown window)
+//! - `desktop`: Window icon, app-id and startup notification for desktops
+//! - `dropdown`: Quake-style window state: slide and remembered height
+//! - `file_drop`: Files dragged into the window as shell words or URLs
+//! - `geometry`: The window's size and place, saved between runs
+//! - `hints`: Labeled matches on screen, picked by keyboard (hints mode)
+//! - `input`: Keyboard and mouse events to terminal input bytes
+//! - `ligatures`: Which cells a ligature glyph covers, and the slice each draws
+//! - `menu`: The macOS menu bar, wired to the command actions
+//! - `minimap`: An overview of the scrollback in a column along the right edge
+//! - `monitor`: Watching a window for output after a pause, or silence
+//! - `passthrough`: Shortcuts left to foreground programs such as tmux
+//! - `paste`: Confirming pastes that would run at a shell prompt
+//! - `pinch`: Pinch and two-finger double tap gestures for the font size
+//! - `plugin`: Compiled-in extensions: palette actions, output filters, overlays
 //! - `renderer`: GPU rendering pipeline using wgpu
-//! - `grid`: Terminal state grid (Phase 3)
-//! - `pty`: Pseudo-terminal handling (Phase 3)
+//! - `scroll`: Touchpad scrolling in fractions of a line, with momentum
+//! - `scrollbar`: Where the view is in the scrollback, with failed commands
+//! - `secure_input`: Secure keyboard entry for passwords (macOS)
+//! - `status_bar`: A row of segments: directory, Git branch, host, clock
+//! - `styled_copy`: Selections as HTML and RTF, with their colors and style
+//! - `ui`: Overlays drawn over the terminal (toasts, dialogs, menus)
+//! - `watch`: Rules matching lines of output: highlights and notifications
+//! - `widget`: Embeddable terminal pane for hosts with their own wgpu device
+//! - `web`: Browser entry point (feature `web`, wasm32 only)
+//!
+//! # Features
+//!
+//! - `gui` (default): the winit/wgpu frontend and the `adamant` binary
+//! - `pty` (default): spawning shells on a pseudo-terminal
+//! - `web`: the browser frontend for `wasm32-unknown-unknown`, using WebGPU
+//!   and a websocket bridge instead of a local PTY
+//! - `ffi`: `extern "C"` API over the terminal core, with a generated
+//!   `include/adamant.h`
 
-pub mod renderer;
+pub mod a11y;
+pub mod backend;
+pub mod config;
+pub mod error;
+pub mod grid;
+pub mod parser;
+pub mod term;
+pub mod terminfo;
 
-// TODO: Phase 3 - Uncomment when implementing terminal logic
-// pub mod grid;
-// pub mod pty;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(unix)]
+pub mod ipc;
+#[cfg(feature = "pty")]
+pub mod pty;
+#[cfg(unix)]
+pub mod session;
+
+#[cfg(feature = "gui")]
+pub mod bindings;
+#[cfg(feature = "gui")]
+pub mod clipboard_history;
+#[cfg(feature = "gui")]
+pub mod commands;
+#[cfg(feature = "gui")]
+pub mod desktop;
+#[cfg(feature = "gui")]
+pub mod dropdown;
+#[cfg(feature = "gui")]
+pub mod file_drop;
+#[cfg(feature = "gui")]
+pub mod geometry;
+#[cfg(feature = "gui")]
+pub mod hints;
+#[cfg(feature = "gui")]
+pub mod hotkey;
+#[cfg(feature = "gui")]
+pub mod input;
+#[cfg(feature = "gui")]
+pub mod ligatures;
+#[cfg(feature = "gui")]
+pub mod menu;
+#[cfg(feature = "gui")]
+pub mod minimap;
+#[cfg(feature = "gui")]
+pub mod monitor;
+#[cfg(feature = "gui")]
+pub mod passthrough;
+#[cfg(feature = "gui")]
+pub mod paste;
+#[cfg(feature = "gui")]
+pub mod pinch;
+#[cfg(feature = "gui")]
+pub mod plugin;
+#[cfg(feature = "gui")]
+pub mod recording;
+#[cfg(feature = "gui")]
+pub mod renderer;
+#[cfg(feature = "gui")]
+pub mod scroll;
+#[cfg(feature = "gui")]
+pub mod scrollbar;
+#[cfg(feature = "gui")]
+pub mod secure_input;
+#[cfg(feature = "gui")]
+pub mod status_bar;
+#[cfg(feature = "gui")]
+pub mod styled_copy;
+#[cfg(feature = "gui")]
+pub mod ui;
+#[cfg(feature = "gui")]
+pub mod watch;
+#[cfg(all(feature = "web", target_arch = "wasm32"))]
+pub mod web;
+#[cfg(feature = "gui")]
+pub mod widget;
 
+// The application needs a backend: a local PTY, or the websocket bridge in browsers
+#[cfg(all(
+    feature = "gui",
+    any(feature = "pty", all(feature = "web", target_arch = "wasm32"))
+))]
 mod app;
 
+#[cfg(all(
+    feature = "gui",
+    any(feature = "pty", all(feature = "web", target_arch = "wasm32"))
+))]
 pub use app::App;
+pub use error::{AdamantError, Result};
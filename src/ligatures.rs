@@ -0,0 +1,85 @@
+//! Ligatures
+//!
+//! With `FontConfig::ligatures`, runs of text are shaped, and where the font
+//! replaces characters with glyphs of its own (`->` as an arrow, `!=` as
+//! `≠`) those glyphs are drawn together as one [`Ligature`] over the cells
+//! of the characters. A character given another glyph of its own by an
+//! OpenType feature (`FontConfig::features`), such as a slashed zero, is a
+//! ligature of one cell.
+//!
+//! A ligature is still drawn cell by cell: each cell draws the slice of the
+//! ligature's image above it, in its own colors. So the block cursor
+//! inverts just the character it is on, and a selection ending in the
+//! middle of `=>` splits the arrow, without turning ligatures off around
+//! the cursor or the selection.
+//!
+//! ```
+//! use adamant::ligatures::{Ligature, LigatureMap};
+//!
+//! // `a -> b`: the arrow takes columns 2 and 3
+//! let map = LigatureMap::new(vec![Ligature { col: 2, len: 2 }]);
+//! assert_eq!(map.at(3), Some((Ligature { col: 2, len: 2 }, 1)));
+//! assert_eq!(map.at(4), None);
+//! // With 10 pixel cells, the second cell draws the image's right half
+//! let arrow = Ligature { col: 2, len: 2 };
+//! assert_eq!(arrow.slice(1, 10, 1, 18), 9..18);
+//! ```
+
+use std::ops::Range;
+
+/// Glyphs drawn together over `len` cells from column `col`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Ligature {
+    pub col: usize,
+    pub len: usize,
+}
+
+impl Ligature {
+    /// The pixel columns of the ligature's image that its cell `index`
+    /// draws, for an image `width` pixels wide starting `left` pixels right
+    /// of the first cell's left edge. Each cell draws what is above it; the
+    /// first and last also draw what sticks out past the ligature's ends.
+    pub fn slice(&self, index: usize, cell_width: u32, left: i32, width: u32) -> Range<u32> {
+        let cell = |index: usize| (index as u32 * cell_width) as i32;
+        let start = if index == 0 {
+            left
+        } else {
+            cell(index).max(left)
+        };
+        let right = left + width as i32;
+        let end = if index + 1 == self.len {
+            right
+        } else {
+            cell(index + 1).min(right)
+        };
+        if start >= end {
+            return 0..0;
+        }
+        (start - left) as u32..(end - left) as u32
+    }
+}
+
+/// The ligatures of a row, by column.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LigatureMap {
+    /// In column order, not overlapping
+    ligatures: Vec<Ligature>,
+}
+
+impl LigatureMap {
+    /// The map of `ligatures`, in column order.
+    pub fn new(ligatures: Vec<Ligature>) -> Self {
+        Self { ligatures }
+    }
+
+    /// The ligature drawn over column `col`, and which of its cells that
+    /// is.
+    pub fn at(&self, col: usize) -> Option<(Ligature, usize)> {
+        let after = self
+            .ligatures
+            .partition_point(|ligature| ligature.col <= col);
+        let ligature = *self.ligatures[..after].last()?;
+        let index = col - ligature.col;
+        (index < ligature.len).then_some((ligature, index))
+    }
+}
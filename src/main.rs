@@ -2,16 +2,282 @@
 //!
 //! Entry point for the terminal emulator.
 //! See docs/01_architecture.md for the overall design.
+//!
+//! ```text
+//! adamant [--safe-mode] [--hold] [--read-only] [--maximized | --fullscreen]
+//!         [--dropdown] [--class name] [--icon file.png] [--shader file.wgsl]
+//!         [--crt] [--tint color] [--profile name] [--session name]
+//!         [--record file] [--replay file [--replay-speed n]]
+//!         [-e program [args...]]
+//! adamant --server name [--profile name] [-e program [args...]]
+//! adamant --list-sessions
+//! adamant --install-terminfo
+//!
+//!   --safe-mode   Start with conservative settings (opaque window, no
+//!                 fallback fonts, no IPC socket), see Config::safe_mode
+//!   --hold        Keep the window open when the program exits
+//!   --read-only   Open the window read-only: input is not sent until it
+//!                 is unlocked from the palette or menu (Config::read_only)
+//!   --maximized   Open the window maximized (Config::window_mode)
+//!   --fullscreen  Open the window in borderless fullscreen
+//!   --dropdown    Run as a dropdown window, shown and hidden with the
+//!                 global hotkey (Config::dropdown, F12 by default)
+//!   --class       The Wayland app-id and X11 WM_CLASS, for window manager
+//!                 rules and .desktop files (DesktopConfig::app_id)
+//!   --icon        A PNG file for the window icon (X11, Windows)
+//!   --shader      A WGSL post-processing shader run over every frame
+//!                 (Config::shader)
+//!   --crt         Draw with the built-in retro CRT effect (Config::crt)
+//!   --tint        Mix a color (#rrggbb) into the background, e.g. for
+//!                 windows on production machines (Colors::tint)
+//!   --profile     Start with the launch profile of that name
+//!                 (Config::launch_profiles); other options override it
+//!   --session     Run the shell in the detachable session of that name,
+//!                 starting it if needed: closing the window leaves it
+//!                 running, and the next --session attaches to it again
+//!                 (Unix, see adamant::session)
+//!   --record      Write everything the program outputs to the file, with
+//!                 its timing in file.timing, for scriptreplay and bug
+//!                 reports (Config::record)
+//!   --replay      Play a recording made with --record (or script
+//!                 --timing) instead of running a program, at the recorded
+//!                 pace times --replay-speed (0: all at once)
+//!   -e            Run the program instead of the shell; the rest of the
+//!                 command line is its arguments. Adamant exits with the
+//!                 program's exit code
+//!   --server      Run a session's server without a window (what
+//!                 --session starts in the background)
+//!   --list-sessions
+//!                 Print the names of the running sessions and exit
+//!   --install-terminfo
+//!                 Compile the `adamant` terminfo entry into ~/.terminfo
+//!                 (or $TERMINFO) and exit; normally done on first run
+//! ```
 
 use adamant::App;
+use adamant::config::{Config, HoldOnExit, Replay, Tint, WindowMode};
+use adamant::term::parse_color;
+use adamant::terminfo;
 
 fn main() {
     // Initialize logging (set RUST_LOG=debug for verbose output)
     env_logger::init();
 
+    let mut config = Config::default();
+    let mut command = None;
+    let mut hold = false;
+    let mut read_only = false;
+    let mut window_mode = None;
+    let mut dropdown = false;
+    let mut class = None;
+    let mut icon = None;
+    let mut shader = None;
+    let mut record = None;
+    let mut replay = None;
+    let mut replay_speed = 1.0;
+    let mut crt = false;
+    let mut tint = None;
+    let mut profile = None;
+    let mut session = None;
+    let mut server = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--safe-mode" => {
+                log::info!("Safe mode: using conservative settings");
+                config = Config::safe_mode();
+            }
+            "--hold" => hold = true,
+            "--read-only" => read_only = true,
+            "--maximized" => window_mode = Some(WindowMode::Maximized),
+            "--fullscreen" => window_mode = Some(WindowMode::Fullscreen),
+            "--dropdown" => dropdown = true,
+            "--class" => class = Some(args.next().unwrap_or_else(|| usage("--class needs a name"))),
+            "--icon" => icon = Some(args.next().unwrap_or_else(|| usage("--icon needs a file"))),
+            "--shader" => {
+                shader = Some(
+                    args.next()
+                        .unwrap_or_else(|| usage("--shader needs a file")),
+                )
+            }
+            "--crt" => crt = true,
+            "--replay" => {
+                replay = Some(
+                    args.next()
+                        .unwrap_or_else(|| usage("--replay needs a file")),
+                )
+            }
+            "--replay-speed" => {
+                let speed = args
+                    .next()
+                    .unwrap_or_else(|| usage("--replay-speed needs a number"));
+                replay_speed = speed
+                    .parse()
+                    .ok()
+                    .filter(|speed: &f32| *speed >= 0.0)
+                    .unwrap_or_else(|| usage(&format!("--replay-speed: not a speed: {}", speed)));
+            }
+            "--record" => {
+                record = Some(
+                    args.next()
+                        .unwrap_or_else(|| usage("--record needs a file")),
+                )
+            }
+            "--tint" => {
+                let color = args.next().unwrap_or_else(|| usage("--tint needs a color"));
+                let color = parse_color(&color)
+                    .unwrap_or_else(|| usage(&format!("--tint: not a color: {}", color)));
+                tint = Some(Tint {
+                    color,
+                    amount: Tint::DEFAULT_AMOUNT,
+                });
+            }
+            "--profile" => {
+                profile = Some(
+                    args.next()
+                        .unwrap_or_else(|| usage("--profile needs a name")),
+                )
+            }
+            "--session" => {
+                session = Some(
+                    args.next()
+                        .unwrap_or_else(|| usage("--session needs a name")),
+                )
+            }
+            "--server" => {
+                server = Some(
+                    args.next()
+                        .unwrap_or_else(|| usage("--server needs a name")),
+                )
+            }
+            #[cfg(unix)]
+            "--list-sessions" => {
+                for name in adamant::session::list() {
+                    println!("{}", name);
+                }
+                return;
+            }
+            "--install-terminfo" => match terminfo::install() {
+                Ok(dir) => {
+                    println!("installed {} into {}", terminfo::NAME, dir.display());
+                    return;
+                }
+                Err(e) => {
+                    eprintln!("adamant: {}", e);
+                    std::process::exit(1);
+                }
+            },
+            "-e" => {
+                command = Some(args.by_ref().collect::<Vec<_>>());
+                if command.as_ref().is_some_and(Vec::is_empty) {
+                    usage("-e needs a program");
+                }
+            }
+            _ => usage(&format!("unknown option {}", arg)),
+        }
+    }
+    if let Some(profile) = &profile
+        && let Err(e) = config.use_launch_profile(profile)
+    {
+        usage(&format!("--profile: {}", e));
+    }
+    if hold {
+        config.hold_on_exit = HoldOnExit::Always;
+    }
+    if read_only {
+        config.read_only = true;
+    }
+    if let Some(window_mode) = window_mode {
+        config.window_mode = window_mode;
+    }
+    if dropdown {
+        config.dropdown.enabled = true;
+    }
+    if crt {
+        config.crt.enabled = true;
+    }
+    if tint.is_some() {
+        config.colors.tint = tint;
+    }
+    // The configuration is static; these live as long as the process
+    if let Some(class) = class {
+        config.desktop.app_id = class.leak();
+    }
+    if let Some(icon) = icon {
+        config.desktop.icon = Some(icon.leak());
+    }
+    if let Some(shader) = shader {
+        config.shader = Some(shader.leak());
+    }
+    if let Some(record) = record {
+        config.record = Some(record.leak());
+    }
+    if let Some(file) = replay {
+        config.replay = Some(Replay {
+            file: file.leak(),
+            speed: replay_speed,
+        });
+        // The last screen is what there is to see
+        config.hold_on_exit = HoldOnExit::Always;
+    }
+    // The user's own hook sees the command line options
+    if let Some(on_config) = config.hooks.on_config {
+        on_config(&mut config);
+    }
+
+    #[cfg(unix)]
+    if let Some(name) = server {
+        if let Err(e) = adamant::session::serve(&name, &config, command.as_deref()) {
+            eprintln!("adamant: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    let runs_program = command.is_some();
+    // The server runs the program; the window only attaches
+    #[cfg(unix)]
+    let session = session.map(|name| {
+        if let Err(e) = adamant::session::check_name(&name) {
+            usage(&format!("--session: {}", e));
+        }
+        let mut server_args = vec!["--server".to_string(), name.clone()];
+        if let Some(profile) = profile {
+            server_args.extend(["--profile".to_string(), profile]);
+        }
+        if let Some(command) = command.take() {
+            server_args.push("-e".to_string());
+            server_args.extend(command);
+        }
+        (name, server_args)
+    });
+    #[cfg(not(unix))]
+    let session = match (session, server) {
+        (None, None) => None,
+        _ => usage("sessions need Unix sockets"),
+    };
+
     log::info!("Starting Adamant...");
 
-    // Run the application
-    // TODO: Handle errors gracefully instead of unwrap
-    pollster::block_on(App::run()).unwrap();
+    // Run the application; `-e` exits with the program's exit code
+    match pollster::block_on(App::run(config, command, session)) {
+        Ok(Some(code)) if runs_program => std::process::exit(code as i32),
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("adamant: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn usage(error: &str) -> ! {
+    eprintln!("adamant: {}", error);
+    eprintln!("usage: adamant [--safe-mode] [--hold] [--read-only] [--maximized | --fullscreen]");
+    eprintln!("               [--dropdown] [--class name] [--icon file.png] [--shader file.wgsl]");
+    eprintln!("               [--crt] [--tint color] [--profile name] [--session name]");
+    eprintln!("               [--record file] [--replay file [--replay-speed n]]");
+    eprintln!("               [-e program [args...]]");
+    eprintln!("       adamant --server name [--profile name] [-e program [args...]]");
+    eprintln!("       adamant --list-sessions");
+    eprintln!("       adamant --install-terminfo");
+    std::process::exit(2);
 }
@@ -0,0 +1,383 @@
+//! Menu Bar
+//!
+//! The application menus of macOS, described once in [`menu_bar`]: items
+//! either run one of the app's [`Action`]s, the same ones the command
+//! palette offers, or send a standard AppKit message (`performZoom:`,
+//! `hide:`) that the system handles itself. Standard key equivalents
+//! (Cmd+C, Cmd+W, Cmd+M) come with the items, and the Window menu is
+//! registered as such, so the system adds its window list and tiling
+//! items to it.
+//!
+//! ```
+//! use adamant::commands::Action;
+//! use adamant::config::Config;
+//! use adamant::menu::{MenuItem, menu_bar};
+//!
+//! let edit = menu_bar(&Config::default()).into_iter().find(|menu| menu.title == "Edit").unwrap();
+//! assert!(edit.items.iter().any(|item| matches!(
+//!     item,
+//!     MenuItem::Action { action: Action::Copy, key: Some(key), .. } if key.key == "c"
+//! )));
+//! ```
+//!
+//! [`install`] puts the menus into the menu bar on macOS; other platforms
+//! have no global menu bar and use the command palette instead.
+
+use winit::keyboard::ModifiersState;
+
+use crate::commands::{self, Action};
+use crate::config::{Config, Monitor};
+use crate::secure_input::SecureInput;
+
+/// A key that runs a menu item.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct KeyEquivalent {
+    /// The character on the key, lowercase
+    pub key: &'static str,
+    /// Command is part of every key equivalent
+    pub modifiers: ModifiersState,
+}
+
+impl KeyEquivalent {
+    /// Command and `key`.
+    pub const fn command(key: &'static str) -> Self {
+        Self {
+            key,
+            modifiers: ModifiersState::SUPER,
+        }
+    }
+
+    /// Command, the extra `modifiers` and `key`.
+    pub const fn with(key: &'static str, modifiers: ModifiersState) -> Self {
+        Self {
+            key,
+            modifiers: ModifiersState::SUPER.union(modifiers),
+        }
+    }
+}
+
+/// An entry of a menu.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MenuItem {
+    /// Runs an action of the app
+    Action {
+        title: &'static str,
+        action: Action,
+        key: Option<KeyEquivalent>,
+    },
+    /// Sends a standard message along the responder chain
+    System {
+        title: &'static str,
+        selector: &'static str,
+        key: Option<KeyEquivalent>,
+    },
+    /// The Services submenu, filled by the system
+    Services,
+    Separator,
+}
+
+/// A menu of the menu bar.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Menu {
+    pub title: &'static str,
+    pub items: Vec<MenuItem>,
+    /// The system lists the windows and adds its window items here
+    pub windows: bool,
+}
+
+/// The menus, from the application menu to the Window menu. The Shell
+/// menu has a new window item for each of `Config::launch_profiles`.
+pub fn menu_bar(config: &Config) -> Vec<Menu> {
+    use MenuItem::{Separator, Services};
+
+    let action = |title, action, key| MenuItem::Action { title, action, key };
+    let system = |title, selector, key| MenuItem::System {
+        title,
+        selector,
+        key,
+    };
+    let command = |key| Some(KeyEquivalent::command(key));
+    let menu = |title, items| Menu {
+        title,
+        items,
+        windows: false,
+    };
+
+    let mut application = vec![
+        system("About Adamant", "orderFrontStandardAboutPanel:", None),
+        Separator,
+    ];
+    if SecureInput::SUPPORTED {
+        application.push(action("Secure Keyboard Entry", Action::SecureInput, None));
+        application.push(Separator);
+    }
+    application.extend([
+        Services,
+        Separator,
+        system("Hide Adamant", "hide:", command("h")),
+        system(
+            "Hide Others",
+            "hideOtherApplications:",
+            Some(KeyEquivalent::with("h", ModifiersState::ALT)),
+        ),
+        system("Show All", "unhideAllApplications:", None),
+        Separator,
+        action("Quit Adamant", Action::Quit, command("q")),
+    ]);
+
+    let mut shell = vec![action("New Window", Action::NewWindow, command("n"))];
+    shell.extend(
+        config
+            .launch_profiles
+            .iter()
+            .enumerate()
+            .map(|(i, profile)| {
+                action(
+                    commands::launch_profile_label(profile.name),
+                    Action::LaunchProfile(i),
+                    None,
+                )
+            }),
+    );
+    shell.extend([
+        Separator,
+        action("Restart", Action::Restart, None),
+        action("Read Only", Action::ReadOnly, None),
+        action("Reset", Action::ResetTerminal, None),
+        action("Record Output", Action::Record, None),
+        action("Rename\u{2026}", Action::Rename, None),
+        action(
+            "Watch for Activity",
+            Action::Monitor(Some(Monitor::Activity)),
+            None,
+        ),
+        action(
+            "Watch for Silence",
+            Action::Monitor(Some(Monitor::Silence)),
+            None,
+        ),
+        action("Stop Watching", Action::Monitor(None), None),
+        system("Close Window", "performClose:", command("w")),
+    ]);
+
+    vec![
+        menu("Adamant", application),
+        menu("Shell", shell),
+        menu(
+            "Edit",
+            vec![
+                action("Copy", Action::Copy, command("c")),
+                action("Copy Last Output", Action::CopyLastOutput, None),
+                action("Paste", Action::Paste, command("v")),
+                action("Paste from History\u{2026}", Action::PasteFromHistory, None),
+                action("Select All", Action::SelectAll, command("a")),
+                action("Reselect", Action::Reselect, None),
+                action("Copy Mode", Action::CopyMode, None),
+                Separator,
+                action("Find\u{2026}", Action::Search, command("f")),
+            ],
+        ),
+        menu(
+            "View",
+            vec![
+                action("Zoom In", Action::ZoomIn, command("=")),
+                action("Zoom Out", Action::ZoomOut, command("-")),
+                action("Actual Size", Action::ZoomReset, command("0")),
+                Separator,
+                action("Scroll to Top", Action::ScrollToTop, None),
+                action("Scroll to Bottom", Action::ScrollToBottom, None),
+                Separator,
+                system(
+                    "Enter Full Screen",
+                    "toggleFullScreen:",
+                    Some(KeyEquivalent::with("f", ModifiersState::CONTROL)),
+                ),
+            ],
+        ),
+        Menu {
+            windows: true,
+            ..menu(
+                "Window",
+                vec![
+                    system("Minimize", "performMiniaturize:", command("m")),
+                    system("Zoom", "performZoom:", None),
+                    Separator,
+                    system("Bring All to Front", "arrangeInFront:", None),
+                ],
+            )
+        },
+    ]
+}
+
+/// Put [`menu_bar`] into the application's menu bar, replacing winit's
+/// default menu. `run` is called on the main thread with the action of
+/// the chosen item. Call it once the application has launched.
+#[cfg(target_os = "macos")]
+pub fn install(config: &Config, run: impl Fn(Action) + 'static) {
+    macos::install(menu_bar(config), Box::new(run));
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyObject, NSObject, Sel};
+    use objc2::{ClassType, DeclaredClass, declare_class, msg_send_id, mutability, sel};
+    use objc2_app_kit::{NSApplication, NSEventModifierFlags, NSMenu, NSMenuItem};
+    use objc2_foundation::{MainThreadMarker, NSString};
+    use winit::keyboard::ModifiersState;
+
+    use super::{KeyEquivalent, Menu, MenuItem};
+    use crate::commands::Action;
+
+    /// The actions of the items, by tag, and what runs them.
+    struct Ivars {
+        actions: Vec<Action>,
+        run: Box<dyn Fn(Action)>,
+    }
+
+    declare_class!(
+        /// Target of the items that run actions.
+        struct MenuTarget;
+
+        unsafe impl ClassType for MenuTarget {
+            type Super = NSObject;
+            type Mutability = mutability::MainThreadOnly;
+            const NAME: &'static str = "AdamantMenuTarget";
+        }
+
+        impl DeclaredClass for MenuTarget {
+            type Ivars = Ivars;
+        }
+
+        unsafe impl MenuTarget {
+            #[method(runAction:)]
+            fn run_action(&self, sender: &NSMenuItem) {
+                // SAFETY: Reading the tag has no preconditions
+                let tag = unsafe { sender.tag() };
+                let ivars = self.ivars();
+                if let Some(&action) = usize::try_from(tag).ok().and_then(|i| ivars.actions.get(i)) {
+                    (ivars.run)(action);
+                }
+            }
+        }
+    );
+
+    pub fn install(menus: Vec<Menu>, run: Box<dyn Fn(Action)>) {
+        let Some(mtm) = MainThreadMarker::new() else {
+            return log::warn!("The menu bar can only be set up on the main thread");
+        };
+        let app = NSApplication::sharedApplication(mtm);
+
+        let actions = menus
+            .iter()
+            .flat_map(|menu| &menu.items)
+            .filter_map(|item| match item {
+                MenuItem::Action { action, .. } => Some(*action),
+                _ => None,
+            })
+            .collect();
+        let target = mtm.alloc::<MenuTarget>().set_ivars(Ivars { actions, run });
+        // SAFETY: `NSObject`'s `init` on a freshly allocated object
+        let target: Retained<MenuTarget> = unsafe { msg_send_id![super(target), init] };
+
+        let bar = NSMenu::new(mtm);
+        let mut tag = 0;
+        for menu in &menus {
+            // SAFETY: A freshly allocated menu and a valid title
+            let submenu =
+                unsafe { NSMenu::initWithTitle(mtm.alloc(), &NSString::from_str(menu.title)) };
+            for item in &menu.items {
+                let entry = match *item {
+                    MenuItem::Action { title, key, .. } => {
+                        let entry = menu_item(mtm, title, Some(sel!(runAction:)), key);
+                        let target: &AnyObject = &target;
+                        // SAFETY: The target outlives the menu (it is never released)
+                        unsafe {
+                            entry.setTarget(Some(target));
+                            entry.setTag(tag);
+                        }
+                        tag += 1;
+                        entry
+                    }
+                    MenuItem::System {
+                        title,
+                        selector,
+                        key,
+                    } => menu_item(mtm, title, Some(Sel::register(selector)), key),
+                    MenuItem::Services => {
+                        let services = NSMenu::new(mtm);
+                        let entry = menu_item(mtm, "Services", None, None);
+                        entry.setSubmenu(Some(&services));
+                        // SAFETY: Setting a menu the system fills
+                        unsafe { app.setServicesMenu(Some(&services)) };
+                        entry
+                    }
+                    MenuItem::Separator => NSMenuItem::separatorItem(mtm),
+                };
+                submenu.addItem(&entry);
+            }
+            if menu.windows {
+                // SAFETY: As above
+                unsafe { app.setWindowsMenu(Some(&submenu)) };
+            }
+            let entry = menu_item(mtm, menu.title, None, None);
+            entry.setSubmenu(Some(&submenu));
+            bar.addItem(&entry);
+        }
+        app.setMainMenu(Some(&bar));
+
+        // Menu items only hold on to their target weakly; the menus stay
+        // for the life of the process
+        std::mem::forget(target);
+    }
+
+    fn menu_item(
+        mtm: MainThreadMarker,
+        title: &str,
+        action: Option<Sel>,
+        key: Option<KeyEquivalent>,
+    ) -> Retained<NSMenuItem> {
+        let key_string = NSString::from_str(key.map_or("", |key| key.key));
+        // SAFETY: A freshly allocated item; the selector is understood by
+        // the target or an object in the responder chain
+        let item = unsafe {
+            NSMenuItem::initWithTitle_action_keyEquivalent(
+                mtm.alloc(),
+                &NSString::from_str(title),
+                action,
+                &key_string,
+            )
+        };
+        if let Some(key) = key {
+            item.setKeyEquivalentModifierMask(modifier_mask(key.modifiers));
+        }
+        item
+    }
+
+    fn modifier_mask(modifiers: ModifiersState) -> NSEventModifierFlags {
+        let mut mask = NSEventModifierFlags::empty();
+        for (modifier, flag) in [
+            (
+                ModifiersState::SUPER,
+                NSEventModifierFlags::NSEventModifierFlagCommand,
+            ),
+            (
+                ModifiersState::SHIFT,
+                NSEventModifierFlags::NSEventModifierFlagShift,
+            ),
+            (
+                ModifiersState::ALT,
+                NSEventModifierFlags::NSEventModifierFlagOption,
+            ),
+            (
+                ModifiersState::CONTROL,
+                NSEventModifierFlags::NSEventModifierFlagControl,
+            ),
+        ] {
+            if modifiers.contains(modifier) {
+                mask |= flag;
+            }
+        }
+        mask
+    }
+}
@@ -0,0 +1,167 @@
+//! Minimap
+//!
+//! An overview of the scrollback in a column along the right edge
+//! (`Config::minimap`), as in Sublime Text: each line is one pixel row,
+//! as long as its text and in the color most of its cells are drawn in.
+//! The lines in view are shaded, and clicking a line centers the view on
+//! it. Lines with matches of the current search are marked at its edge.
+//!
+//! When there are more lines than pixel rows, the minimap shows a window
+//! of them that moves with the view, so the view's band is at the top
+//! with the oldest lines and at the bottom with the newest:
+//!
+//! ```
+//! use adamant::grid::Grid;
+//! use adamant::minimap::Minimap;
+//!
+//! let mut grid = Grid::new(10, 25, 1000);
+//! for _ in 0..3 {
+//!     grid.scroll_up(25);
+//! }
+//! // 100 lines on 60 pixel rows: the last 60, the view in the last 25
+//! let minimap = Minimap::new(&grid, 60);
+//! assert_eq!(minimap.first(), 40);
+//! assert_eq!(minimap.view(), (35, 60));
+//! // Clicking the middle of the minimap centers line 70 in the view
+//! assert_eq!(minimap.offset_at(30), 17);
+//! ```
+
+use crate::grid::{Cell, Color, Flags, Grid, Row, SearchMatch};
+
+/// One line of the minimap.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MinimapLine {
+    /// Up to the line's last character, as a fraction of the columns
+    pub length: f32,
+    /// The color most of the line's cells show: their own background if
+    /// they have one, else their text's. The default color is the
+    /// foreground
+    pub color: Color,
+}
+
+/// The lines of the scrollback that fit the minimap, and the view's place
+/// among them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Minimap {
+    /// Lines in all: scrollback and screen
+    total: usize,
+    rows: usize,
+    /// The first line in view
+    top: usize,
+    /// The line on the first pixel row
+    first: usize,
+    /// The lines shown, from `first`; `None` for blank ones
+    lines: Vec<Option<MinimapLine>>,
+    /// Pixel rows of lines with search matches
+    matches: Vec<usize>,
+}
+
+impl Minimap {
+    /// The minimap of `grid` on `height` pixel rows.
+    pub fn new(grid: &Grid, height: usize) -> Self {
+        let total = grid.total_lines();
+        let rows = grid.rows();
+        let top = grid.visible_line_index(0);
+        let shown = total.min(height);
+        let history = total - rows;
+        let first = if history == 0 {
+            0
+        } else {
+            let fraction = top as f32 / history as f32;
+            (fraction * (total - shown) as f32).round() as usize
+        };
+        let lines = (first..first + shown)
+            .map(|line| summarize(grid.line(line)))
+            .collect();
+        Self {
+            total,
+            rows,
+            top,
+            first,
+            lines,
+            matches: Vec::new(),
+        }
+    }
+
+    /// Mark the lines of `matches` that are shown (see [`Grid::search`]).
+    pub fn with_matches(mut self, matches: &[SearchMatch]) -> Self {
+        let shown = self.first..self.first + self.lines.len();
+        self.matches = (matches.iter())
+            .filter(|found| shown.contains(&found.line))
+            .map(|found| found.line - self.first)
+            .collect();
+        self.matches.dedup();
+        self
+    }
+
+    /// The pixel rows of lines with search matches.
+    pub fn matches(&self) -> &[usize] {
+        &self.matches
+    }
+
+    /// The line on the first pixel row.
+    pub fn first(&self) -> usize {
+        self.first
+    }
+
+    /// Each pixel row, from the top, with the line drawn on it (`None` for
+    /// blank lines).
+    pub fn lines(&self) -> impl Iterator<Item = (usize, MinimapLine)> + '_ {
+        (self.lines.iter().enumerate()).filter_map(|(y, line)| Some((y, (*line)?)))
+    }
+
+    /// The pixel rows of the lines in view: the first, and the one past
+    /// the last.
+    pub fn view(&self) -> (usize, usize) {
+        let start = self.top - self.first;
+        (start, start + self.rows)
+    }
+
+    /// The display offset (lines scrolled back) that centers the line on
+    /// pixel row `y` in the view, or comes as close as the scrollback goes.
+    pub fn offset_at(&self, y: usize) -> usize {
+        let history = self.total - self.rows;
+        let line = self.first + y.min(self.lines.len().saturating_sub(1));
+        let top = line.saturating_sub(self.rows / 2).min(history);
+        history - top
+    }
+}
+
+/// Width of the minimap in pixels, for cells `cell_width` pixels wide:
+/// six cells.
+pub fn width(cell_width: f32) -> f32 {
+    (cell_width * 6.0).round()
+}
+
+/// A line's length and most common color; `None` if it is blank.
+fn summarize(row: &Row) -> Option<MinimapLine> {
+    let cells = row.cells();
+    let end = cells.iter().rposition(|cell| ink(cell).is_some())? + 1;
+    // Lines have few colors: counting in a list beats hashing
+    let mut counts: Vec<(Color, usize)> = Vec::new();
+    for ink in cells[..end].iter().filter_map(ink) {
+        match counts.iter_mut().find(|(seen, _)| *seen == ink) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((ink, 1)),
+        }
+    }
+    let (color, _) = counts.into_iter().max_by_key(|&(_, count)| count)?;
+    Some(MinimapLine {
+        length: end as f32 / cells.len() as f32,
+        color,
+    })
+}
+
+/// The color a cell shows: its background if it has one of its own (an
+/// inverse cell's is its text color), else its text's. Blank cells show
+/// nothing.
+fn ink(cell: &Cell) -> Option<Color> {
+    if cell.flags.contains(Flags::INVERSE) {
+        return Some(cell.fg);
+    }
+    if cell.bg != Color::Default {
+        return Some(cell.bg);
+    }
+    let blank = cell.c == ' ' || cell.flags.intersects(Flags::HIDDEN | Flags::WIDE_SPACER);
+    (!blank).then_some(cell.fg)
+}
@@ -0,0 +1,91 @@
+//! Activity and Silence Monitoring
+//!
+//! A window left running a build or tailing a log can be watched
+//! ([`Monitor`]): for output coming after a pause, or for output stopping.
+//! [`ActivityMonitor`] is told when output arrives and checked every frame;
+//! it reports each activity or silence once, so the host can mark the
+//! window and ask for attention.
+//!
+//! ```
+//! use adamant::config::Monitor;
+//! use adamant::monitor::ActivityMonitor;
+//! use web_time::{Duration, Instant};
+//!
+//! let start = Instant::now();
+//! let mut monitor = ActivityMonitor::new(Duration::from_secs(10));
+//! monitor.watch(Some(Monitor::Silence));
+//!
+//! // Nothing is reported before the first output
+//! assert_eq!(monitor.check(start + Duration::from_secs(60)), None);
+//! monitor.output(start + Duration::from_secs(60));
+//! assert_eq!(monitor.check(start + Duration::from_secs(65)), None);
+//! assert_eq!(monitor.check(start + Duration::from_secs(70)), Some(Monitor::Silence));
+//! assert_eq!(monitor.check(start + Duration::from_secs(80)), None);
+//! ```
+
+use web_time::{Duration, Instant};
+
+use crate::config::Monitor;
+
+/// Watches a window's output for activity or silence.
+#[derive(Clone, Debug)]
+pub struct ActivityMonitor {
+    watching: Option<Monitor>,
+    silence: Duration,
+    /// The latest output since the watch started
+    last_output: Option<Instant>,
+    /// The silence since `last_output` was reported
+    reported: bool,
+}
+
+impl ActivityMonitor {
+    /// A monitor watching nothing, counting `silence` without output as
+    /// a pause (`Config::silence_ms`).
+    pub fn new(silence: Duration) -> Self {
+        Self {
+            watching: None,
+            silence,
+            last_output: None,
+            reported: false,
+        }
+    }
+
+    /// What is watched, if anything.
+    pub fn watching(&self) -> Option<Monitor> {
+        self.watching
+    }
+
+    /// Start watching for `monitor`, or stop with `None`. Output from
+    /// before doesn't count.
+    pub fn watch(&mut self, monitor: Option<Monitor>) {
+        self.watching = monitor;
+        self.last_output = None;
+        self.reported = false;
+    }
+
+    /// Output arrived at `now`. Returns [`Monitor::Activity`] if that is
+    /// watched and it is the first output of the watch, or the first after
+    /// a pause.
+    pub fn output(&mut self, now: Instant) -> Option<Monitor> {
+        let paused = self
+            .last_output
+            .is_none_or(|last| now.saturating_duration_since(last) >= self.silence);
+        self.last_output = Some(now);
+        self.reported = false;
+        (self.watching == Some(Monitor::Activity) && paused).then_some(Monitor::Activity)
+    }
+
+    /// Returns [`Monitor::Silence`] once if that is watched and there was
+    /// output, but none in the last `silence`.
+    pub fn check(&mut self, now: Instant) -> Option<Monitor> {
+        let last = self.last_output?;
+        if self.watching != Some(Monitor::Silence)
+            || self.reported
+            || now.saturating_duration_since(last) < self.silence
+        {
+            return None;
+        }
+        self.reported = true;
+        Some(Monitor::Silence)
+    }
+}
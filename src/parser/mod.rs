@@ -0,0 +1,486 @@
+//! ANSI/VT Escape Sequence Parser
+//!
+//! A byte-oriented state machine after Paul Williams' DEC-compatible parser
+//! (<https://vt100.net/emu/dec_ansi_parser>). It splits the PTY output
+//! stream into printable characters, control codes and escape sequences and
+//! hands them to a [`Perform`] implementation. The parser itself keeps no
+//! terminal state, so it can drive anything from the real terminal to a
+//! test recorder.
+//!
+//! # States
+//!
+//! ```text
+//! Ground ──ESC──> Escape ──[──> CsiEntry ──> CsiParam ──> dispatch
+//!                    │──]──> OscString ──BEL/ST──> dispatch
+//!                    └──P──> DcsEntry ──> DcsPassthrough ──ST──> unhook
+//! ```
+//!
+//! tmux wraps sequences meant for the outer terminal as `DCS tmux; … ST`
+//! with each ESC inside doubled. In a DCS whose final byte is `t`, a
+//! doubled ESC is payload, a single one ends the string as usual.
+//!
+//! # C1 controls
+//!
+//! The stream is UTF-8, so the 8-bit controls come as the code points
+//! U+0080 to U+009F (`C2 80` to `C2 9F`); a bare byte in that range is
+//! broken UTF-8. CSI, OSC, DCS, SOS/PM/APC and ST switch states from
+//! anywhere like their 7-bit forms after ESC; the others, such as NEL, IND
+//! and SS2, reach [`Perform::esc_dispatch`] as those forms.
+//!
+//! ```
+//! use adamant::parser::{Parser, Perform};
+//!
+//! #[derive(Default)]
+//! struct Record(Vec<String>);
+//!
+//! impl Perform for Record {
+//!     fn print(&mut self, c: char) {
+//!         self.0.push(c.to_string());
+//!     }
+//!     fn esc_dispatch(&mut self, _: &[u8], _: bool, byte: u8) {
+//!         self.0.push(format!("ESC {}", byte as char));
+//!     }
+//!     fn osc_dispatch(&mut self, params: &[&[u8]], _: bool) {
+//!         self.0.push(format!("OSC {}", String::from_utf8_lossy(&params.join(&b';'))));
+//!     }
+//! }
+//!
+//! let mut record = Record::default();
+//! // NEL, then an OSC ended by ST, all in their 8-bit forms
+//! Parser::new().advance(&mut record, "a\u{85}\u{9d}0;é\u{9c}b".as_bytes());
+//! assert_eq!(record.0, ["a", "ESC E", "OSC 0;é", "b"]);
+//! ```
+
+mod params;
+
+pub use self::params::Params;
+
+/// Maximum number of intermediate bytes kept for one sequence.
+const MAX_INTERMEDIATES: usize = 2;
+
+/// Maximum size of an OSC string; longer payloads are truncated.
+const MAX_OSC_LEN: usize = 64 * 1024;
+
+/// Receiver of parsed input.
+///
+/// All methods have empty default implementations so implementors only
+/// handle what they need.
+pub trait Perform {
+    /// A printable character.
+    fn print(&mut self, _c: char) {}
+
+    /// A C0 control code (BEL, BS, LF, ...).
+    fn execute(&mut self, _byte: u8) {}
+
+    /// A complete CSI sequence. `ignore` is set when too many parameters or
+    /// intermediates were seen and the sequence should be treated with care.
+    fn csi_dispatch(
+        &mut self,
+        _params: &Params,
+        _intermediates: &[u8],
+        _ignore: bool,
+        _action: char,
+    ) {
+    }
+
+    /// A complete escape sequence (ESC followed by a final byte), or a C1
+    /// control as its 7-bit form.
+    fn esc_dispatch(&mut self, _intermediates: &[u8], _ignore: bool, _byte: u8) {}
+
+    /// An OSC string, split at `;` into its parameters.
+    fn osc_dispatch(&mut self, _params: &[&[u8]], _bell_terminated: bool) {}
+
+    /// Start of a DCS sequence; the payload follows through [`Perform::put`].
+    fn hook(&mut self, _params: &Params, _intermediates: &[u8], _ignore: bool, _action: char) {}
+
+    /// One byte of DCS payload.
+    fn put(&mut self, _byte: u8) {}
+
+    /// End of the current DCS sequence.
+    fn unhook(&mut self) {}
+
+    /// The current DCS sequence was cancelled by CAN or SUB. Ends it like
+    /// [`Perform::unhook`] unless implemented.
+    fn cancel(&mut self) {
+        self.unhook();
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+enum State {
+    #[default]
+    Ground,
+    Escape,
+    EscapeIntermediate,
+    CsiEntry,
+    CsiParam,
+    CsiIntermediate,
+    CsiIgnore,
+    DcsEntry,
+    DcsParam,
+    DcsIntermediate,
+    DcsPassthrough,
+    DcsIgnore,
+    OscString,
+    SosPmApcString,
+}
+
+/// The escape sequence parser.
+#[derive(Default)]
+pub struct Parser {
+    state: State,
+    intermediates: [u8; MAX_INTERMEDIATES],
+    intermediate_len: usize,
+    /// Too many intermediates/params were seen for the current sequence
+    ignoring: bool,
+    params: Params,
+    osc: Vec<u8>,
+    utf8: Utf8Decoder,
+    /// A `C2` byte inside a sequence, which may start a C1 control
+    c1_lead: bool,
+    /// The DCS is tmux's passthrough, where ESC is doubled
+    tmux: bool,
+    /// An ESC in tmux's passthrough, waiting for the byte after it
+    tmux_escape: bool,
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a chunk of bytes, calling back into `performer` as sequences complete.
+    pub fn advance<P: Perform>(&mut self, performer: &mut P, bytes: &[u8]) {
+        for &byte in bytes {
+            self.advance_byte(performer, byte);
+        }
+    }
+
+    fn advance_byte<P: Perform>(&mut self, performer: &mut P, byte: u8) {
+        // In the ground state the UTF-8 decoder finds C1 controls; inside a
+        // sequence the lead byte waits for the next one
+        if self.c1_lead {
+            self.c1_lead = false;
+            if let 0x80..=0x9F = byte {
+                self.c1(performer, byte);
+                return;
+            }
+            self.advance_state(performer, 0xC2);
+        }
+        if byte == 0xC2 && self.state != State::Ground {
+            self.c1_lead = true;
+            return;
+        }
+
+        if self.state == State::DcsPassthrough && self.tmux {
+            if self.tmux_escape {
+                self.tmux_escape = false;
+                if byte == 0x1B {
+                    performer.put(byte);
+                    return;
+                }
+                performer.unhook();
+                self.enter_escape();
+            } else if byte == 0x1B {
+                self.tmux_escape = true;
+                return;
+            }
+        }
+
+        // Transitions valid from any state
+        match byte {
+            0x18 | 0x1A => {
+                // CAN/SUB abort the sequence in progress without dispatching it.
+                if self.state == State::DcsPassthrough {
+                    performer.cancel();
+                }
+                self.utf8.reset_with(performer);
+                performer.execute(byte);
+                self.state = State::Ground;
+                return;
+            }
+            0x1B => {
+                self.leave_string(performer, false);
+                self.enter_escape();
+                return;
+            }
+            _ => {}
+        }
+        self.advance_state(performer, byte);
+    }
+
+    fn advance_state<P: Perform>(&mut self, performer: &mut P, byte: u8) {
+        match self.state {
+            State::Ground => self.ground(performer, byte),
+            State::Escape => match byte {
+                0x00..=0x1F => performer.execute(byte),
+                0x20..=0x2F => {
+                    self.collect(byte);
+                    self.state = State::EscapeIntermediate;
+                }
+                b'[' => self.enter(State::CsiEntry),
+                b']' => {
+                    self.osc.clear();
+                    self.state = State::OscString;
+                }
+                b'P' => self.enter(State::DcsEntry),
+                b'X' | b'^' | b'_' => self.state = State::SosPmApcString,
+                0x30..=0x7E => {
+                    performer.esc_dispatch(self.intermediates(), self.ignoring, byte);
+                    self.state = State::Ground;
+                }
+                _ => {}
+            },
+            State::EscapeIntermediate => match byte {
+                0x00..=0x1F => performer.execute(byte),
+                0x20..=0x2F => self.collect(byte),
+                0x30..=0x7E => {
+                    performer.esc_dispatch(self.intermediates(), self.ignoring, byte);
+                    self.state = State::Ground;
+                }
+                _ => {}
+            },
+            State::CsiEntry => match byte {
+                0x00..=0x1F => performer.execute(byte),
+                0x20..=0x2F => {
+                    self.collect(byte);
+                    self.state = State::CsiIntermediate;
+                }
+                b'0'..=b'9' | b':' | b';' => {
+                    self.params.push_byte(byte);
+                    self.state = State::CsiParam;
+                }
+                0x3C..=0x3F => {
+                    self.collect(byte);
+                    self.state = State::CsiParam;
+                }
+                0x40..=0x7E => self.csi_dispatch(performer, byte),
+                _ => {}
+            },
+            State::CsiParam => match byte {
+                0x00..=0x1F => performer.execute(byte),
+                b'0'..=b'9' | b':' | b';' => self.params.push_byte(byte),
+                0x3C..=0x3F => self.state = State::CsiIgnore,
+                0x20..=0x2F => {
+                    self.collect(byte);
+                    self.state = State::CsiIntermediate;
+                }
+                0x40..=0x7E => self.csi_dispatch(performer, byte),
+                _ => {}
+            },
+            State::CsiIntermediate => match byte {
+                0x00..=0x1F => performer.execute(byte),
+                0x20..=0x2F => self.collect(byte),
+                0x30..=0x3F => self.state = State::CsiIgnore,
+                0x40..=0x7E => self.csi_dispatch(performer, byte),
+                _ => {}
+            },
+            State::CsiIgnore => match byte {
+                0x00..=0x1F => performer.execute(byte),
+                0x40..=0x7E => self.state = State::Ground,
+                _ => {}
+            },
+            State::DcsEntry => match byte {
+                0x20..=0x2F => {
+                    self.collect(byte);
+                    self.state = State::DcsIntermediate;
+                }
+                b'0'..=b'9' | b':' | b';' => {
+                    self.params.push_byte(byte);
+                    self.state = State::DcsParam;
+                }
+                0x3C..=0x3F => {
+                    self.collect(byte);
+                    self.state = State::DcsParam;
+                }
+                0x40..=0x7E => self.hook(performer, byte),
+                _ => {}
+            },
+            State::DcsParam => match byte {
+                b'0'..=b'9' | b':' | b';' => self.params.push_byte(byte),
+                0x3C..=0x3F => self.state = State::DcsIgnore,
+                0x20..=0x2F => {
+                    self.collect(byte);
+                    self.state = State::DcsIntermediate;
+                }
+                0x40..=0x7E => self.hook(performer, byte),
+                _ => {}
+            },
+            State::DcsIntermediate => match byte {
+                0x20..=0x2F => self.collect(byte),
+                0x30..=0x3F => self.state = State::DcsIgnore,
+                0x40..=0x7E => self.hook(performer, byte),
+                _ => {}
+            },
+            State::DcsPassthrough => match byte {
+                0x7F => {}
+                _ => performer.put(byte),
+            },
+            State::DcsIgnore | State::SosPmApcString => {}
+            State::OscString => match byte {
+                0x07 => {
+                    self.osc_dispatch(performer, true);
+                    self.state = State::Ground;
+                }
+                0x00..=0x1F => {}
+                _ => {
+                    if self.osc.len() < MAX_OSC_LEN {
+                        self.osc.push(byte);
+                    }
+                }
+            },
+        }
+    }
+
+    fn ground<P: Perform>(&mut self, performer: &mut P, byte: u8) {
+        match byte {
+            0x00..=0x1F => {
+                self.utf8.reset_with(performer);
+                performer.execute(byte);
+            }
+            0x20..=0x7E => {
+                self.utf8.reset_with(performer);
+                performer.print(byte as char);
+            }
+            0x7F => {}
+            _ => match self.utf8.push(performer, byte) {
+                Some(c @ '\u{80}'..='\u{9f}') => self.c1(performer, c as u8),
+                Some(c) => performer.print(c),
+                None => {}
+            },
+        }
+    }
+
+    /// A C1 control, in any state.
+    fn c1<P: Perform>(&mut self, performer: &mut P, byte: u8) {
+        self.leave_string(performer, false);
+        match byte {
+            0x90 => self.enter(State::DcsEntry),
+            0x9B => self.enter(State::CsiEntry),
+            0x9D => {
+                self.osc.clear();
+                self.state = State::OscString;
+            }
+            0x98 | 0x9E | 0x9F => self.state = State::SosPmApcString,
+            0x9C => self.state = State::Ground,
+            _ => {
+                performer.esc_dispatch(&[], false, byte - 0x40);
+                self.state = State::Ground;
+            }
+        }
+    }
+
+    /// Leaving OSC/DCS by a state-changing byte terminates the string.
+    fn leave_string<P: Perform>(&mut self, performer: &mut P, bell_terminated: bool) {
+        match self.state {
+            State::OscString => self.osc_dispatch(performer, bell_terminated),
+            State::DcsPassthrough => performer.unhook(),
+            State::Ground => self.utf8.reset_with(performer),
+            _ => {}
+        }
+    }
+
+    fn enter_escape(&mut self) {
+        self.intermediate_len = 0;
+        self.ignoring = false;
+        self.state = State::Escape;
+    }
+
+    fn enter(&mut self, state: State) {
+        self.intermediate_len = 0;
+        self.ignoring = false;
+        self.params.clear();
+        self.state = state;
+    }
+
+    fn collect(&mut self, byte: u8) {
+        if self.intermediate_len == MAX_INTERMEDIATES {
+            self.ignoring = true;
+        } else {
+            self.intermediates[self.intermediate_len] = byte;
+            self.intermediate_len += 1;
+        }
+    }
+
+    fn intermediates(&self) -> &[u8] {
+        &self.intermediates[..self.intermediate_len]
+    }
+
+    fn csi_dispatch<P: Perform>(&mut self, performer: &mut P, byte: u8) {
+        self.params.finish();
+        let ignore = self.ignoring || self.params.is_full();
+        performer.csi_dispatch(&self.params, self.intermediates(), ignore, byte as char);
+        self.state = State::Ground;
+    }
+
+    fn hook<P: Perform>(&mut self, performer: &mut P, byte: u8) {
+        self.params.finish();
+        let ignore = self.ignoring || self.params.is_full();
+        performer.hook(&self.params, self.intermediates(), ignore, byte as char);
+        self.tmux = byte == b't' && self.intermediate_len == 0 && self.params.is_empty();
+        self.tmux_escape = false;
+        self.state = State::DcsPassthrough;
+    }
+
+    fn osc_dispatch<P: Perform>(&mut self, performer: &mut P, bell_terminated: bool) {
+        let params: Vec<&[u8]> = self.osc.split(|&b| b == b';').collect();
+        performer.osc_dispatch(&params, bell_terminated);
+        self.osc.clear();
+    }
+}
+
+/// Incremental UTF-8 decoder for printable text in the ground state.
+///
+/// Malformed input is replaced by U+FFFD instead of being dropped, so broken
+/// output stays visible.
+#[derive(Default)]
+struct Utf8Decoder {
+    buf: [u8; 4],
+    len: usize,
+    needed: usize,
+}
+
+impl Utf8Decoder {
+    /// Add `byte`, returning the character it completes. A sequence cut
+    /// short is printed as a replacement character right away.
+    fn push<P: Perform>(&mut self, performer: &mut P, byte: u8) -> Option<char> {
+        if self.len == 0 {
+            self.needed = match byte {
+                0xC2..=0xDF => 2,
+                0xE0..=0xEF => 3,
+                0xF0..=0xF4 => 4,
+                _ => return Some(char::REPLACEMENT_CHARACTER),
+            };
+            self.buf[0] = byte;
+            self.len = 1;
+            return None;
+        }
+
+        if byte & 0xC0 != 0x80 {
+            // Sequence cut short; restart with this byte.
+            self.len = 0;
+            performer.print(char::REPLACEMENT_CHARACTER);
+            return self.push(performer, byte);
+        }
+
+        self.buf[self.len] = byte;
+        self.len += 1;
+        if self.len == self.needed {
+            self.len = 0;
+            let c = std::str::from_utf8(&self.buf[..self.needed])
+                .ok()
+                .and_then(|s| s.chars().next())
+                .unwrap_or(char::REPLACEMENT_CHARACTER);
+            return Some(c);
+        }
+        None
+    }
+
+    /// Abandon a partial sequence (an ASCII byte interrupted it).
+    fn reset_with<P: Perform>(&mut self, performer: &mut P) {
+        if self.len > 0 {
+            self.len = 0;
+            performer.print(char::REPLACEMENT_CHARACTER);
+        }
+    }
+}
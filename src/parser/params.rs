@@ -0,0 +1,134 @@
+//! CSI/DCS parameter storage.
+
+/// Maximum number of parameter values (including subparameters) per sequence.
+const MAX_PARAMS: usize = 32;
+
+/// Numeric parameters of a CSI or DCS sequence.
+///
+/// Parameters are separated by `;`; each may carry colon-separated
+/// subparameters (`38:2::255:0:0`). Iteration yields one slice per
+/// `;`-separated parameter with its subparameters. Omitted values are 0.
+#[derive(Clone, Debug, Default)]
+pub struct Params {
+    values: Vec<u16>,
+    /// Number of values in each `;`-separated group
+    group_lens: Vec<usize>,
+    /// Value being accumulated from digits
+    current: u16,
+    /// Values already pushed for the group in progress
+    current_group_len: usize,
+    /// Any digit or separator was seen since the last clear
+    started: bool,
+    full: bool,
+}
+
+impl Params {
+    /// Number of `;`-separated parameters.
+    pub fn len(&self) -> usize {
+        self.group_lens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.group_lens.is_empty()
+    }
+
+    /// Iterate over parameters, each with its subparameters.
+    pub fn iter(&self) -> ParamsIter<'_> {
+        ParamsIter {
+            params: self,
+            group: 0,
+            offset: 0,
+        }
+    }
+
+    /// The first value of parameter `index`, if present.
+    pub fn get(&self, index: usize) -> Option<u16> {
+        self.iter().nth(index).map(|group| group[0])
+    }
+
+    /// Parameter `index`, with 0 or a missing value replaced by `default`.
+    pub fn get_or(&self, index: usize, default: u16) -> u16 {
+        match self.get(index) {
+            Some(0) | None => default,
+            Some(value) => value,
+        }
+    }
+
+    /// More parameters were received than can be stored.
+    pub(crate) fn is_full(&self) -> bool {
+        self.full
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.values.clear();
+        self.group_lens.clear();
+        self.current = 0;
+        self.current_group_len = 0;
+        self.started = false;
+        self.full = false;
+    }
+
+    /// Accumulate a digit or separator byte.
+    pub(crate) fn push_byte(&mut self, byte: u8) {
+        self.started = true;
+        match byte {
+            b'0'..=b'9' => {
+                self.current = self
+                    .current
+                    .saturating_mul(10)
+                    .saturating_add((byte - b'0') as u16);
+            }
+            b':' => self.push_value(),
+            b';' => {
+                self.push_value();
+                self.end_group();
+            }
+            _ => {}
+        }
+    }
+
+    /// Complete the parameter in progress before dispatch.
+    pub(crate) fn finish(&mut self) {
+        if self.started {
+            self.push_value();
+            self.end_group();
+            self.started = false;
+        }
+    }
+
+    fn push_value(&mut self) {
+        if self.values.len() == MAX_PARAMS {
+            self.full = true;
+        } else {
+            self.values.push(self.current);
+            self.current_group_len += 1;
+        }
+        self.current = 0;
+    }
+
+    fn end_group(&mut self) {
+        if self.current_group_len > 0 {
+            self.group_lens.push(self.current_group_len);
+        }
+        self.current_group_len = 0;
+    }
+}
+
+/// Iterator over the parameters of a sequence.
+pub struct ParamsIter<'a> {
+    params: &'a Params,
+    group: usize,
+    offset: usize,
+}
+
+impl<'a> Iterator for ParamsIter<'a> {
+    type Item = &'a [u16];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = *self.params.group_lens.get(self.group)?;
+        let group = &self.params.values[self.offset..self.offset + len];
+        self.group += 1;
+        self.offset += len;
+        Some(group)
+    }
+}
@@ -0,0 +1,83 @@
+//! Shortcut Pass-Through
+//!
+//! Programs such as tmux and vim have their own uses for keys Adamant
+//! takes as shortcuts: Ctrl+Shift+arrows to resize panes, Ctrl+Shift+C in
+//! a multiplexer. A [`KeyPassthrough`] rule names the keys and the
+//! programs; while one of those programs is in the terminal's foreground
+//! job (`Backend::foreground_job`), the keys go to it as if Adamant had no
+//! shortcut for them.
+//!
+//! The foreground job is only looked up for keys some rule names, so
+//! typing costs nothing.
+//!
+//! ```
+//! use adamant::config::KeyPassthrough;
+//! use adamant::passthrough::Passthrough;
+//! use winit::keyboard::{Key, ModifiersState, NamedKey};
+//!
+//! let passthrough = Passthrough::new(&[KeyPassthrough {
+//!     programs: &["tmux"],
+//!     keys: &["Ctrl+Shift+Up"],
+//! }])
+//! .unwrap();
+//! let ctrl_shift = ModifiersState::CONTROL | ModifiersState::SHIFT;
+//! let up = Key::Named(NamedKey::ArrowUp);
+//! assert!(passthrough.passes(ctrl_shift, &up, || vec!["tmux: client".into()]));
+//! assert!(!passthrough.passes(ctrl_shift, &up, || vec!["bash".into()]));
+//! ```
+
+use winit::keyboard::{Key, ModifiersState};
+
+use crate::backend::is_program;
+use crate::config::KeyPassthrough;
+use crate::error::{AdamantError, Result};
+use crate::hotkey::Hotkey;
+
+/// The configured rules, with their keys parsed.
+#[derive(Clone, Debug, Default)]
+pub struct Passthrough {
+    rules: Vec<(Vec<Hotkey>, &'static [&'static str])>,
+}
+
+impl Passthrough {
+    /// Parse the keys of every rule. A key that doesn't parse is a
+    /// configuration error.
+    pub fn new(rules: &[KeyPassthrough]) -> Result<Self> {
+        let rules = rules
+            .iter()
+            .map(|rule| Ok((parse_keys(rule.keys)?, rule.programs)))
+            .collect::<Result<_>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Whether `key` with `modifiers` goes to the program instead of
+    /// running a shortcut. `foreground` names the programs in the
+    /// foreground job; it is only called when a rule has the key.
+    pub fn passes(
+        &self,
+        modifiers: ModifiersState,
+        key: &Key,
+        foreground: impl FnOnce() -> Vec<String>,
+    ) -> bool {
+        let mut programs = self
+            .rules
+            .iter()
+            .filter(|(keys, _)| keys.iter().any(|hotkey| hotkey.matches(modifiers, key)))
+            .flat_map(|(_, programs)| programs.iter())
+            .peekable();
+        if programs.peek().is_none() {
+            return false;
+        }
+        let job = foreground();
+        programs.any(|program| job.iter().any(|name| is_program(name, program)))
+    }
+}
+
+/// Parse shortcuts written like `Ctrl+Shift+Up`. One that doesn't parse is
+/// a configuration error.
+pub fn parse_keys(keys: &[&str]) -> Result<Vec<Hotkey>> {
+    keys.iter()
+        .map(|key| Hotkey::parse(key))
+        .collect::<Result<_, String>>()
+        .map_err(|e| AdamantError::Config(format!("passthrough: {}", e)))
+}
@@ -0,0 +1,122 @@
+//! Paste
+//!
+//! Pasted text is cleaned up first ([`sanitize`]): control characters
+//! copied along from a web page can't move the cursor or start escape
+//! sequences, and the end of a bracketed paste can't be pasted to leave
+//! it early.
+//!
+//! Text pasted at a shell prompt runs line by line: a stray line break is
+//! an Enter. Unless the program asked for bracketed paste, and so can tell
+//! pasted text from typed text, a paste with line breaks or over the size
+//! limit (`Config::paste`) first shows a preview asking to go ahead.
+//!
+//! ```
+//! use adamant::config::PasteConfig;
+//! use adamant::paste::{needs_confirmation, preview, sanitize};
+//!
+//! let config = PasteConfig::default();
+//! assert_eq!(sanitize(&config, "ls\x07 -l\u{9b}\x1b[2J"), "ls -l[2J");
+//!
+//! assert!(!needs_confirmation(&config, "ls -l", false));
+//! assert!(needs_confirmation(&config, "rm -rf build\nmake\n", false));
+//! // Bracketed paste: the shell waits for Enter
+//! assert!(!needs_confirmation(&config, "rm -rf build\nmake\n", true));
+//!
+//! assert_eq!(
+//!     preview("rm -rf build\nmake\n", 40),
+//!     "Paste 2 lines (18 bytes) at the prompt?\n\n  rm -rf build\n  make"
+//! );
+//! ```
+
+use std::borrow::Cow;
+
+use crate::config::PasteConfig;
+
+/// Where a bracketed paste starts and ends.
+const BRACKETS: [&str; 2] = ["\x1b[200~", "\x1b[201~"];
+
+/// Lines of the pasted text shown at most.
+const PREVIEW_LINES: usize = 8;
+
+/// `text` as `config` wants it pasted: without control characters other
+/// than tabs and line breaks, and without bracketed paste's brackets.
+pub fn sanitize<'a>(config: &PasteConfig, text: &'a str) -> Cow<'a, str> {
+    let mut text = Cow::Borrowed(text);
+    if config.strip_control && text.chars().any(is_stripped) {
+        text = Cow::Owned(text.chars().filter(|&c| !is_stripped(c)).collect());
+    }
+    // Removing one can join the text around it into another
+    while config.strip_brackets && BRACKETS.iter().any(|bracket| text.contains(bracket)) {
+        text = Cow::Owned(
+            BRACKETS
+                .iter()
+                .fold(text.into_owned(), |text, bracket| text.replace(bracket, "")),
+        );
+    }
+    text
+}
+
+/// `text`'s line breaks as Enter sends them, when `config` asks for it.
+pub fn line_endings<'a>(config: &PasteConfig, text: &'a str) -> Cow<'a, str> {
+    if config.normalize_line_endings && text.contains('\n') {
+        Cow::Owned(text.replace("\r\n", "\r").replace('\n', "\r"))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// C0 and C1 controls and DEL, but not tabs and line breaks.
+fn is_stripped(c: char) -> bool {
+    c.is_control() && !matches!(c, '\t' | '\n' | '\r')
+}
+
+/// Whether pasting `text` has to be confirmed first. `bracketed` is
+/// whether the program asked for bracketed paste.
+pub fn needs_confirmation(config: &PasteConfig, text: &str, bracketed: bool) -> bool {
+    if bracketed {
+        return false;
+    }
+    let multi_line = config.confirm_multi_line && text.contains(['\n', '\r']);
+    let large = config.confirm_over_bytes > 0 && text.len() > config.confirm_over_bytes;
+    multi_line || large
+}
+
+/// The question asked before pasting `text`, with its first lines cut to
+/// `cols` characters.
+pub fn preview(text: &str, cols: usize) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut message = format!(
+        "Paste {} line{} ({} bytes) at the prompt?\n",
+        lines.len(),
+        if lines.len() == 1 { "" } else { "s" },
+        text.len()
+    );
+    for line in lines.iter().take(PREVIEW_LINES) {
+        message.push_str("\n  ");
+        message.push_str(&shorten(line, cols.saturating_sub(2)));
+    }
+    if lines.len() > PREVIEW_LINES {
+        let more = lines.len() - PREVIEW_LINES;
+        message.push_str(&format!("\n  \u{2026} {} more", more));
+    }
+    message
+}
+
+/// `line` in at most `cols` characters, control characters as spaces.
+fn shorten(line: &str, cols: usize) -> String {
+    let count = line.chars().count();
+    let keep = if count > cols {
+        cols.saturating_sub(1)
+    } else {
+        count
+    };
+    let mut shown: String = line
+        .chars()
+        .take(keep)
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    if keep < count {
+        shown.push('\u{2026}');
+    }
+    shown
+}
@@ -0,0 +1,170 @@
+//! Pinch to Zoom
+//!
+//! Font size gestures. macOS reports touchpad pinches itself
+//! (`WindowEvent::PinchGesture`, `DoubleTapGesture`); on touchscreens
+//! [`TouchPinch`] turns the raw touches into the same gestures: two
+//! fingers moving apart or together scale the text, and tapping twice
+//! with two fingers goes back to the configured size. When a pinch ends
+//! the zoom snaps to a whole pixel font size ([`snap_zoom`]) so glyphs are
+//! drawn sharp.
+//!
+//! ```
+//! use adamant::pinch::{Gesture, TouchPinch};
+//! use web_time::Instant;
+//! use winit::dpi::PhysicalPosition;
+//! use winit::event::TouchPhase;
+//!
+//! let mut pinch = TouchPinch::default();
+//! let now = Instant::now();
+//! pinch.touch(0, TouchPhase::Started, PhysicalPosition::new(100.0, 100.0), now);
+//! pinch.touch(1, TouchPhase::Started, PhysicalPosition::new(200.0, 100.0), now);
+//! // The fingers spread to twice the distance
+//! let gesture = pinch.touch(1, TouchPhase::Moved, PhysicalPosition::new(300.0, 100.0), now);
+//! assert_eq!(gesture, Some(Gesture::Scale(2.0)));
+//! ```
+
+use web_time::{Duration, Instant};
+use winit::dpi::PhysicalPosition;
+use winit::event::TouchPhase;
+
+/// Fingers moving less than this (pixels) are tapping, not pinching.
+const TAP_SLOP: f64 = 24.0;
+
+/// A two-finger tap is over within this time.
+const TAP_TIME: Duration = Duration::from_millis(250);
+
+/// The second two-finger tap comes within this time of the first.
+const DOUBLE_TAP_TIME: Duration = Duration::from_millis(400);
+
+/// What the fingers did to the font size.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Gesture {
+    /// Scale the text by this factor, relative to the last step
+    Scale(f32),
+    /// The pinch ended: snap the size
+    Ended,
+    /// Back to the configured size
+    Reset,
+}
+
+/// The zoom nearest to `zoom` at which a `font_px` pixel font (the
+/// configured size at the window's scale factor) is a whole number of
+/// pixels.
+pub fn snap_zoom(zoom: f32, font_px: f32) -> f32 {
+    if font_px <= 0.0 {
+        return zoom;
+    }
+    (font_px * zoom).round().max(1.0) / font_px
+}
+
+/// Two-finger gestures on a touchscreen.
+#[derive(Clone, Debug, Default)]
+pub struct TouchPinch {
+    /// The fingers down, by touch id, and where they are
+    touches: Vec<(u64, PhysicalPosition<f64>)>,
+    /// The fingers' distance when the second one came down
+    start_distance: Option<f64>,
+    /// Their distance at the last scale step, once pinching
+    distance: Option<f64>,
+    /// When the second finger came down, while it still may be a tap
+    tap_started: Option<Instant>,
+    /// When the last two-finger tap ended
+    last_tap: Option<Instant>,
+}
+
+impl TouchPinch {
+    /// Touch `id` did `phase` at `location`. Returns the gesture it
+    /// completed, if any.
+    pub fn touch(
+        &mut self,
+        id: u64,
+        phase: TouchPhase,
+        location: PhysicalPosition<f64>,
+        now: Instant,
+    ) -> Option<Gesture> {
+        match phase {
+            TouchPhase::Started => {
+                self.touches.retain(|(touch, _)| *touch != id);
+                self.touches.push((id, location));
+                if self.touches.len() == 2 {
+                    self.start_distance = self.finger_distance();
+                    self.tap_started = Some(now);
+                } else {
+                    // A third finger is something else
+                    self.start_distance = None;
+                    self.distance = None;
+                    self.tap_started = None;
+                }
+                None
+            }
+            TouchPhase::Moved => {
+                if let Some((_, position)) = self.touches.iter_mut().find(|(touch, _)| *touch == id)
+                {
+                    *position = location;
+                }
+                self.pinch_step()
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                let before = self.touches.len();
+                self.touches.retain(|(touch, _)| *touch != id);
+                if before != 2 || self.touches.len() != 1 {
+                    return None;
+                }
+                // The first of the two fingers lifted
+                let pinched = self.distance.take().is_some();
+                self.start_distance = None;
+                let tapped = phase == TouchPhase::Ended
+                    && self
+                        .tap_started
+                        .take()
+                        .is_some_and(|start| now.saturating_duration_since(start) <= TAP_TIME);
+                if pinched {
+                    Some(Gesture::Ended)
+                } else if tapped {
+                    self.tap(now)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// The scale step of two fingers that moved, once they moved apart
+    /// or together by more than a tap would.
+    fn pinch_step(&mut self) -> Option<Gesture> {
+        let distance = self.finger_distance()?;
+        let start = self.start_distance?;
+        let last = match self.distance {
+            Some(last) => last,
+            None if (distance - start).abs() > TAP_SLOP => {
+                self.tap_started = None;
+                start
+            }
+            None => return None,
+        };
+        self.distance = Some(distance);
+        (last > 0.0 && distance != last).then(|| Gesture::Scale((distance / last) as f32))
+    }
+
+    /// A two-finger tap ended at `now`: the second one of a double tap
+    /// resets the size.
+    fn tap(&mut self, now: Instant) -> Option<Gesture> {
+        let double = self
+            .last_tap
+            .is_some_and(|last| now.saturating_duration_since(last) <= DOUBLE_TAP_TIME);
+        if double {
+            self.last_tap = None;
+            return Some(Gesture::Reset);
+        }
+        self.last_tap = Some(now);
+        None
+    }
+
+    /// The distance between the two fingers down, if exactly two are.
+    fn finger_distance(&self) -> Option<f64> {
+        match self.touches.as_slice() {
+            [(_, a), (_, b)] => Some((a.x - b.x).hypot(a.y - b.y)),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! Plugins
+//!
+//! Extensions compiled in next to the configuration: a [`Plugin`] adds
+//! actions to the command palette (with shortcuts of their own), rewrites
+//! the output before the terminal sees it, and draws elements over the
+//! terminal, such as a status bar. `Config::plugins` lists a constructor
+//! for each; every window makes its own instances, held by [`Plugins`].
+//!
+//! Actions answer with [`HookAction`]s, the same as `Config::hooks`, so a
+//! plugin can run anything in the palette, type into the terminal, show a
+//! notification or start a program. Overlays are asked for again whenever
+//! the terminal is drawn; clicks go through them.
+//!
+//! ```
+//! use adamant::config::HookAction;
+//! use adamant::plugin::{Plugin, PluginAction, Plugins};
+//! use adamant::ui::{Element, Rect, Style};
+//!
+//! struct Status;
+//!
+//! impl Plugin for Status {
+//!     fn name(&self) -> &'static str {
+//!         "status"
+//!     }
+//!
+//!     fn actions(&self) -> Vec<PluginAction> {
+//!         vec![PluginAction {
+//!             name: "Say hello",
+//!             shortcut: Some("Ctrl+Shift+H"),
+//!         }]
+//!     }
+//!
+//!     fn run(&mut self, _action: usize) -> Vec<HookAction> {
+//!         vec![HookAction::Notify("Hello".into())]
+//!     }
+//!
+//!     fn overlay(&mut self, rows: usize, cols: usize) -> Vec<Element> {
+//!         let bar = Rect::new(rows - 1, 0, 1, cols);
+//!         vec![Element::label(bar, "status", Style::PANEL)]
+//!     }
+//! }
+//!
+//! let mut plugins = Plugins::new(&[|| Box::new(Status)]).unwrap();
+//! let command = &plugins.commands()[0];
+//! assert_eq!((command.name, command.shortcut), ("Say hello", Some("Ctrl+Shift+H")));
+//! assert_eq!(plugins.run(0, 0), [HookAction::Notify("Hello".into())]);
+//! assert_eq!(plugins.overlays(24, 80)[0].rect.row, 23);
+//! ```
+
+use winit::keyboard::{Key, ModifiersState};
+
+use crate::commands::{Action, Command};
+use crate::config::HookAction;
+use crate::error::{AdamantError, Result};
+use crate::hotkey::Hotkey;
+use crate::ui::Element;
+
+/// Makes a plugin for a new window (`Config::plugins`).
+pub type PluginFactory = fn() -> Box<dyn Plugin>;
+
+/// An extension of the terminal. Everything but the name is optional.
+pub trait Plugin {
+    /// Shown in log messages and configuration errors
+    fn name(&self) -> &'static str;
+
+    /// Entries for the command palette, after the built-in ones and
+    /// `Config::commands`.
+    fn actions(&self) -> Vec<PluginAction> {
+        Vec::new()
+    }
+
+    /// Run the entry at `action` of [`actions`](Self::actions).
+    fn run(&mut self, action: usize) -> Vec<HookAction> {
+        let _ = action;
+        Vec::new()
+    }
+
+    /// Change output from the program before the terminal interprets it.
+    /// Escape sequences may be split between two calls.
+    fn filter_output(&mut self, bytes: &mut Vec<u8>) {
+        let _ = bytes;
+    }
+
+    /// Elements to draw over a `rows` x `cols` screen, under dialogs and
+    /// menus.
+    fn overlay(&mut self, rows: usize, cols: usize) -> Vec<Element> {
+        let _ = (rows, cols);
+        Vec::new()
+    }
+}
+
+/// A palette entry of a plugin.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PluginAction {
+    pub name: &'static str,
+    /// Keys that run it directly, written like `Ctrl+Shift+H` (see
+    /// [`Hotkey::parse`])
+    pub shortcut: Option<&'static str>,
+}
+
+/// The plugins of a window, with their actions.
+pub struct Plugins {
+    plugins: Vec<Box<dyn Plugin>>,
+    /// Every plugin's actions, by plugin and action index
+    actions: Vec<(usize, usize, PluginAction, Option<Hotkey>)>,
+}
+
+impl Plugins {
+    /// Make one of each plugin. A shortcut that doesn't parse is a
+    /// configuration error.
+    pub fn new(factories: &[PluginFactory]) -> Result<Self> {
+        let plugins: Vec<Box<dyn Plugin>> = factories.iter().map(|factory| factory()).collect();
+        let mut actions = Vec::new();
+        for (i, plugin) in plugins.iter().enumerate() {
+            for (j, action) in plugin.actions().into_iter().enumerate() {
+                let hotkey = action
+                    .shortcut
+                    .map(Hotkey::parse)
+                    .transpose()
+                    .map_err(|e| {
+                        AdamantError::Config(format!("plugin {:?}: {}", plugin.name(), e))
+                    })?;
+                actions.push((i, j, action, hotkey));
+            }
+        }
+        Ok(Self { plugins, actions })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// The plugins' palette entries, as [`Action::Plugin`]s.
+    pub fn commands(&self) -> Vec<Command> {
+        self.actions
+            .iter()
+            .map(|&(plugin, action, entry, _)| Command {
+                action: Action::Plugin(plugin, action),
+                name: entry.name,
+                shortcut: entry.shortcut,
+            })
+            .collect()
+    }
+
+    /// The action whose shortcut `key` with `modifiers` is.
+    pub fn shortcut(&self, modifiers: ModifiersState, key: &Key) -> Option<Action> {
+        self.actions
+            .iter()
+            .find(|(.., hotkey)| hotkey.is_some_and(|hotkey| hotkey.matches(modifiers, key)))
+            .map(|&(plugin, action, ..)| Action::Plugin(plugin, action))
+    }
+
+    /// Run action `action` of plugin `plugin`.
+    pub fn run(&mut self, plugin: usize, action: usize) -> Vec<HookAction> {
+        match self.plugins.get_mut(plugin) {
+            Some(plugin) => plugin.run(action),
+            None => Vec::new(),
+        }
+    }
+
+    /// Pass output through every plugin's filter, in order.
+    pub fn filter_output(&mut self, bytes: &mut Vec<u8>) {
+        for plugin in &mut self.plugins {
+            plugin.filter_output(bytes);
+        }
+    }
+
+    /// What all plugins draw over a `rows` x `cols` screen.
+    pub fn overlays(&mut self, rows: usize, cols: usize) -> Vec<Element> {
+        self.plugins
+            .iter_mut()
+            .flat_map(|plugin| plugin.overlay(rows, cols))
+            .collect()
+    }
+}
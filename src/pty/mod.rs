@@ -0,0 +1,540 @@
+//! Pseudo-Terminal Handling
+//!
+//! Spawns the user's shell on a PTY using `portable-pty`, which covers
+//! Unix PTYs and Windows ConPTY behind one interface. Which program runs
+//! and its environment come from [`ShellConfig`].
+//!
+//! Besides `TERM`, `COLORTERM` and [`ShellConfig::env`], children see
+//! variables that let scripts detect Adamant without parsing `TERM`:
+//!
+//! | Variable | Value |
+//! |----------|-------|
+//! | `ADAMANT_VERSION` | Crate version, e.g. `0.1.0` |
+//! | `ADAMANT_SOCKET` | Path of the IPC socket (unset when IPC is unavailable) |
+//! | `ADAMANT_CAPABILITIES` | Comma-separated features, see [`CAPABILITIES`](crate::term::CAPABILITIES); `ipc` when the socket is set |
+//!
+//! [`Pty::foreground_processes`] tells whether something other than the
+//! shell is running in the foreground (an editor, `ssh`, a build), so
+//! closing the window can ask first. [`Pty::foreground_job`] names whatever
+//! is in the foreground, for shortcuts left to programs such as `tmux`
+//! (`Config::passthrough`).
+//!
+//! Reading is left to callers: they obtain a blocking reader with
+//! [`Pty::reader`] and drive it from wherever suits them
+//! (`backend::PtyBackend` uses a background thread that forwards chunks into
+//! the event loop). Writing never blocks: [`Pty::write`] queues the bytes
+//! for a "pty-writer" thread, so a large paste waiting on a child that
+//! doesn't read can't stall the event loop. [`Pty::pending_input`] tells
+//! how much is still queued, for callers holding back more.
+//!
+//! A "pty-waiter" thread waits for the child (a process handle on
+//! Windows), reaping it as soon as it exits rather than when the PTY
+//! closes, which a background job keeping the PTY open could put off
+//! forever. [`Pty::exit_watch`] lets other threads wait for it too.
+//!
+//! Dropping a [`Pty`] closes the terminal: the process groups of its
+//! session get [`ShellConfig::close_signal`], SIGHUP unless configured
+//! otherwise (Unix; ConPTY's child is terminated).
+//!
+//! With [`ShellConfig::utmp`] the session is registered in utmp and wtmp
+//! for `who` and `last`, and marked ended when the child exits (see
+//! `utmp`, Unix).
+
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+#[cfg(unix)]
+mod utmp;
+
+use portable_pty::{CommandBuilder, MasterPty, PtySize, native_pty_system};
+
+use crate::config::{CloseSignal, ShellConfig};
+use crate::error::{AdamantError, Result};
+use crate::term::CAPABILITIES;
+
+/// Variables a launcher passes its startup-notification token in (X11,
+/// Wayland). The token is for the window; children don't get it.
+pub const STARTUP_NOTIFY_VARS: [&str; 2] = ["DESKTOP_STARTUP_ID", "XDG_ACTIVATION_TOKEN"];
+
+/// A running child process attached to a pseudo-terminal.
+pub struct Pty {
+    master: Box<dyn MasterPty + Send>,
+    /// Input for the "pty-writer" thread
+    input: Sender<Vec<u8>>,
+    /// Bytes queued that the thread hasn't written yet
+    pending: Arc<AtomicUsize>,
+    /// Kills the child, which the waiter thread owns
+    #[cfg(not(unix))]
+    killer: Box<dyn portable_pty::ChildKiller + Send + Sync>,
+    pid: Option<u32>,
+    #[cfg(unix)]
+    close_signal: CloseSignal,
+    exit: ExitWatch,
+    /// The utmp record, ended by whichever of the waiter and `drop` comes
+    /// first
+    #[cfg(unix)]
+    login: Arc<Mutex<Option<utmp::Login>>>,
+}
+
+/// The exit code of a [`Pty`]'s child, once the "pty-waiter" thread reaped
+/// it. Clones watch the same child.
+#[derive(Clone, Default)]
+pub struct ExitWatch(Arc<(Mutex<Option<u32>>, Condvar)>);
+
+impl ExitWatch {
+    /// The exit code, if the child exited.
+    pub fn code(&self) -> Option<u32> {
+        *self.0.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Block until the child exits, or at most `timeout`. Returns its exit
+    /// code, if it did.
+    pub fn wait_timeout(&self, timeout: Duration) -> Option<u32> {
+        let (code, exited) = &*self.0;
+        let code = code.lock().unwrap_or_else(|e| e.into_inner());
+        let (code, _) = exited
+            .wait_timeout_while(code, timeout, |code| code.is_none())
+            .unwrap_or_else(|e| e.into_inner());
+        *code
+    }
+
+    /// Block until the child exits. Returns its exit code.
+    pub fn wait(&self) -> u32 {
+        let (code, exited) = &*self.0;
+        let code = code.lock().unwrap_or_else(|e| e.into_inner());
+        let code = exited
+            .wait_while(code, |code| code.is_none())
+            .unwrap_or_else(|e| e.into_inner());
+        code.unwrap_or_default()
+    }
+
+    fn set(&self, code: u32) {
+        let (lock, exited) = &*self.0;
+        *lock.lock().unwrap_or_else(|e| e.into_inner()) = Some(code);
+        exited.notify_all();
+    }
+}
+
+impl Pty {
+    /// Spawn the configured shell on a new PTY of `cols` x `rows`, in its
+    /// directory or the home directory.
+    pub fn spawn(shell: &ShellConfig, cols: u16, rows: u16) -> Result<Self> {
+        let mut cmd = match shell.program {
+            // portable-pty runs `$SHELL` as a login shell by itself
+            None if shell.login && shell.args.is_empty() => CommandBuilder::new_default_prog(),
+            program => {
+                let program = program.map_or_else(
+                    || CommandBuilder::new_default_prog().get_shell(),
+                    String::from,
+                );
+                let mut cmd = CommandBuilder::new(program);
+                if shell.login {
+                    cmd.arg("-l");
+                }
+                cmd.args(shell.args);
+                cmd
+            }
+        };
+        if let Some(directory) = shell.directory {
+            match (directory.strip_prefix("~/"), std::env::var_os("HOME")) {
+                (Some(rest), Some(home)) => cmd.cwd(std::path::Path::new(&home).join(rest)),
+                _ => cmd.cwd(directory),
+            }
+        }
+        Self::spawn_command(cmd, shell, cols, rows)
+    }
+
+    /// Spawn `program` with `args` (looked up in `PATH`) on a new PTY, in
+    /// the current directory. Only the environment is taken from `shell`.
+    pub fn spawn_program(
+        program: &str,
+        args: &[String],
+        shell: &ShellConfig,
+        cols: u16,
+        rows: u16,
+    ) -> Result<Self> {
+        let mut cmd = CommandBuilder::new(program);
+        cmd.args(args);
+        if let Ok(cwd) = std::env::current_dir() {
+            cmd.cwd(cwd);
+        }
+        Self::spawn_command(cmd, shell, cols, rows)
+    }
+
+    fn spawn_command(
+        mut cmd: CommandBuilder,
+        shell: &ShellConfig,
+        cols: u16,
+        rows: u16,
+    ) -> Result<Self> {
+        let pair = native_pty_system()
+            .openpty(PtySize {
+                rows,
+                cols,
+                pixel_width: 0,
+                pixel_height: 0,
+            })
+            .map_err(AdamantError::pty)?;
+
+        cmd.env("TERM", crate::terminfo::term(shell.term));
+        cmd.env("COLORTERM", shell.colorterm);
+        cmd.env("ADAMANT_VERSION", env!("CARGO_PKG_VERSION"));
+
+        let mut capabilities = CAPABILITIES.to_vec();
+        // Don't pass on the socket of an Adamant we were started from
+        cmd.env_remove("ADAMANT_SOCKET");
+        for name in STARTUP_NOTIFY_VARS {
+            cmd.env_remove(name);
+        }
+        #[cfg(unix)]
+        if let Some(socket) = crate::ipc::active_socket() {
+            cmd.env("ADAMANT_SOCKET", socket);
+            capabilities.push("ipc");
+        }
+        cmd.env("ADAMANT_CAPABILITIES", capabilities.join(","));
+        for (name, value) in shell.env {
+            cmd.env(name, value);
+        }
+
+        let child = pair.slave.spawn_command(cmd).map_err(AdamantError::pty)?;
+        // The child holds its own handle to the slave side; dropping ours
+        // lets reads return EOF once the child exits.
+        drop(pair.slave);
+
+        let writer = pair.master.take_writer().map_err(AdamantError::pty)?;
+        let pending = Arc::new(AtomicUsize::new(0));
+        let input = spawn_writer(writer, Arc::clone(&pending))?;
+
+        #[cfg(not(unix))]
+        let killer = child.clone_killer();
+        let pid = child.process_id();
+        let exit = ExitWatch::default();
+        let watch = exit.clone();
+        #[cfg(unix)]
+        let login = Arc::new(Mutex::new(
+            match (shell.utmp, pair.master.tty_name(), pid) {
+                (true, Some(tty), Some(pid)) => utmp::Login::register(&tty, pid),
+                _ => None,
+            },
+        ));
+        #[cfg(unix)]
+        let logout = Arc::clone(&login);
+        std::thread::Builder::new()
+            .name("pty-waiter".into())
+            .spawn(move || {
+                let mut child = child;
+                // Waiting reaps it
+                let code = match child.wait() {
+                    Ok(status) => status.exit_code(),
+                    Err(e) => {
+                        log::warn!("Failed to wait for the child: {}", e);
+                        1
+                    }
+                };
+                #[cfg(unix)]
+                log_out(&logout);
+                watch.set(code);
+            })?;
+
+        Ok(Self {
+            master: pair.master,
+            input,
+            pending,
+            #[cfg(not(unix))]
+            killer,
+            pid,
+            #[cfg(unix)]
+            close_signal: shell.close_signal,
+            exit,
+            #[cfg(unix)]
+            login,
+        })
+    }
+
+    /// A blocking reader for the child's output.
+    pub fn reader(&self) -> Result<Box<dyn Read + Send>> {
+        self.master.try_clone_reader().map_err(AdamantError::pty)
+    }
+
+    /// Queue input for the child. Fails once writing to the PTY failed,
+    /// typically because the child exited.
+    pub fn write(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        self.pending.fetch_add(bytes.len(), Ordering::Relaxed);
+        self.input.send(bytes.to_vec()).map_err(|_| {
+            self.pending.fetch_sub(bytes.len(), Ordering::Relaxed);
+            std::io::Error::from(std::io::ErrorKind::BrokenPipe)
+        })
+    }
+
+    /// Bytes written with [`write`](Self::write) that the child hasn't
+    /// been given yet.
+    pub fn pending_input(&self) -> usize {
+        self.pending.load(Ordering::Relaxed)
+    }
+
+    /// Tell the child about a new window size (delivers SIGWINCH on Unix).
+    pub fn resize(&self, cols: u16, rows: u16) -> Result<()> {
+        self.master
+            .resize(PtySize {
+                rows,
+                cols,
+                pixel_width: 0,
+                pixel_height: 0,
+            })
+            .map_err(AdamantError::pty)
+    }
+
+    /// Whether the child is still running.
+    pub fn is_alive(&self) -> bool {
+        self.exit.code().is_none()
+    }
+
+    /// The child's exit code once it has exited. A child that just closed
+    /// the terminal gets a moment to finish exiting.
+    pub fn exit_code(&self) -> Option<u32> {
+        self.exit.wait_timeout(Duration::from_millis(100))
+    }
+
+    /// A handle to wait for the child's exit from another thread.
+    pub fn exit_watch(&self) -> ExitWatch {
+        self.exit.clone()
+    }
+
+    /// Names of the processes in the foreground job, unless that is the
+    /// child itself (a shell waiting at its prompt). A pipeline lists each
+    /// program once.
+    pub fn foreground_processes(&self) -> Vec<String> {
+        #[cfg(unix)]
+        if self.master.process_group_leader().map(|pid| pid as u32) == self.pid {
+            return Vec::new();
+        }
+        self.foreground_job()
+    }
+
+    /// Names of the processes in the terminal's foreground process group
+    /// (`tcgetpgrp`), the child included, each once and in the order they
+    /// started: `vim` while it runs, `sh` at the prompt.
+    ///
+    /// Unix only. Without `/proc` to list its members only the group
+    /// leader's pid is known.
+    #[cfg(unix)]
+    pub fn foreground_job(&self) -> Vec<String> {
+        let Some(leader) = self.master.process_group_leader() else {
+            return Vec::new();
+        };
+        let Ok(entries) = std::fs::read_dir("/proc") else {
+            return vec![format!("pid {}", leader)];
+        };
+        let mut members: Vec<(u32, String)> = entries
+            .filter_map(|entry| {
+                let pid: u32 = entry.ok()?.file_name().to_str()?.parse().ok()?;
+                let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+                let (name, pgrp, _) = parse_stat(&stat)?;
+                (pgrp == leader).then_some((pid, name))
+            })
+            .collect();
+        members.sort_unstable();
+        let mut names: Vec<String> = Vec::new();
+        for (_, name) in members {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// Send the close signal to the process groups of the terminal's
+    /// session: the child's, the foreground job's and background jobs'.
+    /// With [`CloseSignal::Terminate`] a "pty-closer" thread kills what
+    /// still runs after the grace period (see [`join_closers`]).
+    #[cfg(unix)]
+    fn hang_up(&self) {
+        // The child leads its own session and process group, which keep
+        // its pid after it exited
+        let mut groups = match self.pid {
+            Some(pid) => session_groups(pid as i32),
+            None => Vec::new(),
+        };
+        // Once the waiter reaped the child its pid may belong to another
+        // process, whose group isn't ours to signal
+        let exited = self.exit.code().is_some();
+        let leaders = self.pid.filter(|_| !exited).map(|pid| pid as i32);
+        for leader in leaders
+            .into_iter()
+            .chain(self.master.process_group_leader())
+        {
+            if !groups.contains(&leader) {
+                groups.push(leader);
+            }
+        }
+        match self.close_signal {
+            CloseSignal::Hangup => signal_groups(&groups, libc::SIGHUP),
+            CloseSignal::Terminate { grace_ms } => {
+                signal_groups(&groups, libc::SIGTERM);
+                let Some(leader) = self.pid else {
+                    return;
+                };
+                let exit = self.exit.clone();
+                let deadline = std::time::Instant::now() + Duration::from_millis(grace_ms);
+                let (running, finished) = &CLOSERS;
+                *running.lock().unwrap_or_else(|e| e.into_inner()) += 1;
+                let closer =
+                    std::thread::Builder::new()
+                        .name("pty-closer".into())
+                        .spawn(move || {
+                            // Until the waiter reaps the child, its pid (and the
+                            // groups') can't go to another process. After that
+                            // only groups with members in its session are
+                            // surely still the terminal's
+                            let live = || match exit.code() {
+                                None => groups.clone(),
+                                Some(_) => session_groups(leader as i32),
+                            };
+                            // SAFETY: signal 0 only checks; it fails once the
+                            // group is empty
+                            let runs = |group: &i32| unsafe { libc::kill(-group, 0) } == 0;
+                            while live().iter().any(runs) && std::time::Instant::now() < deadline {
+                                std::thread::sleep(Duration::from_millis(20));
+                            }
+                            signal_groups(&live(), libc::SIGKILL);
+                            *running.lock().unwrap_or_else(|e| e.into_inner()) -= 1;
+                            finished.notify_all();
+                        });
+                if let Err(e) = closer {
+                    *running.lock().unwrap_or_else(|e| e.into_inner()) -= 1;
+                    log::warn!("Failed to start the pty-closer thread: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Foreground jobs aren't known on this platform: ConPTY has no
+    /// process groups to ask about.
+    #[cfg(not(unix))]
+    pub fn foreground_job(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// How many "pty-closer" threads are still waiting out a grace period,
+/// and the signal that one finished.
+#[cfg(unix)]
+static CLOSERS: (Mutex<usize>, Condvar) = (Mutex::new(0), Condvar::new());
+
+/// Wait until the terminals closed with [`CloseSignal::Terminate`] have
+/// had their grace period, and what still ran after it was killed. The
+/// process exiting first would end the "pty-closer" threads doing that,
+/// leaving programs that ignore SIGTERM running.
+pub fn join_closers() {
+    #[cfg(unix)]
+    {
+        let (running, finished) = &CLOSERS;
+        let running = running.lock().unwrap_or_else(|e| e.into_inner());
+        drop(finished.wait_while(running, |running| *running > 0));
+    }
+}
+
+/// Start the "pty-writer" thread, which writes what is sent to it in
+/// order, blocking on the PTY instead of the caller. It stops at the
+/// first error and when the sender goes.
+fn spawn_writer(
+    mut writer: Box<dyn Write + Send>,
+    pending: Arc<AtomicUsize>,
+) -> Result<Sender<Vec<u8>>> {
+    let (sender, input) = mpsc::channel::<Vec<u8>>();
+    std::thread::Builder::new()
+        .name("pty-writer".into())
+        .spawn(move || {
+            for bytes in input {
+                let written = writer.write_all(&bytes).and_then(|()| writer.flush());
+                pending.fetch_sub(bytes.len(), Ordering::Relaxed);
+                if let Err(e) = written {
+                    log::warn!("Failed to write to PTY: {}", e);
+                    // What is still queued goes with the thread
+                    pending.store(0, Ordering::Relaxed);
+                    return;
+                }
+            }
+        })?;
+    Ok(sender)
+}
+
+/// The command name, process group and session of a `/proc/<pid>/stat`
+/// line: `pid (comm) state ppid pgrp session ...`, where `comm` may contain
+/// anything.
+#[cfg(unix)]
+fn parse_stat(stat: &str) -> Option<(String, i32, i32)> {
+    let open = stat.find('(')?;
+    let close = stat.rfind(')')?;
+    let name = stat.get(open + 1..close)?.to_string();
+    let mut fields = stat.get(close + 1..)?.split_whitespace().skip(2);
+    let pgrp = fields.next()?.parse().ok()?;
+    let session = fields.next()?.parse().ok()?;
+    Some((name, pgrp, session))
+}
+
+/// The process groups of the session `session`, from `/proc`; empty
+/// without it.
+#[cfg(unix)]
+fn session_groups(session: i32) -> Vec<i32> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    let mut groups: Vec<i32> = entries
+        .filter_map(|entry| {
+            let pid = entry.ok()?.file_name();
+            let pid = pid
+                .to_str()
+                .filter(|pid| pid.bytes().all(|b| b.is_ascii_digit()))?;
+            let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+            let (_, pgrp, sid) = parse_stat(&stat)?;
+            (sid == session).then_some(pgrp)
+        })
+        .collect();
+    groups.sort_unstable();
+    groups.dedup();
+    groups
+}
+
+impl Drop for Pty {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        self.hang_up();
+        #[cfg(not(unix))]
+        if self.exit.code().is_none() {
+            let _ = self.killer.kill();
+        }
+        // The waiter may not get to it before the process exits
+        #[cfg(unix)]
+        log_out(&self.login);
+    }
+}
+
+/// Send `signal` to every process group in `groups`, and continue stopped
+/// jobs so they get it. Groups that are gone are skipped.
+#[cfg(unix)]
+fn signal_groups(groups: &[i32], signal: libc::c_int) {
+    for group in groups {
+        // SAFETY: kill only takes plain values; a negative pid is a group
+        unsafe {
+            if libc::kill(-group, signal) == 0 && signal != libc::SIGKILL {
+                libc::kill(-group, libc::SIGCONT);
+            }
+        }
+    }
+}
+
+/// End the utmp record, unless that was done already.
+#[cfg(unix)]
+fn log_out(login: &Mutex<Option<utmp::Login>>) {
+    let login = login.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let Some(login) = login {
+        login.logout();
+    }
+}
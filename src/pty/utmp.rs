@@ -0,0 +1,129 @@
+//! Login records in utmp and wtmp (`ShellConfig::utmp`)
+//!
+//! A [`Login`] is written when the child starts on the PTY and ended when
+//! it exits, so `who`, `w` and `last` list Adamant sessions like those of
+//! other terminals. The user field is the login name and the host field
+//! the X11 or Wayland display, as xterm does.
+//!
+//! Writing the files needs permission (root or the `utmp` group, e.g. a
+//! setgid binary); without it the records are skipped with a log message.
+//! Only glibc Linux and macOS are supported. macOS appends every record to
+//! its wtmp log itself; on Linux both files are written.
+
+#[cfg(not(any(all(target_os = "linux", target_env = "gnu"), target_os = "macos")))]
+pub use self::unsupported::Login;
+#[cfg(any(all(target_os = "linux", target_env = "gnu"), target_os = "macos"))]
+pub use self::utmpx::Login;
+
+#[cfg(any(all(target_os = "linux", target_env = "gnu"), target_os = "macos"))]
+mod utmpx {
+    use std::path::Path;
+    use std::time::SystemTime;
+
+    /// A session registered in utmp, ended on [`logout`](Login::logout).
+    pub struct Login {
+        record: libc::utmpx,
+    }
+
+    // `updwtmpx` is in glibc but not in the `libc` crate
+    #[cfg(target_os = "linux")]
+    unsafe extern "C" {
+        fn updwtmpx(file: *const libc::c_char, ut: *const libc::utmpx);
+    }
+
+    impl Login {
+        /// Register the session of the process `pid` on the terminal `tty`
+        /// (`/dev/pts/3`).
+        pub fn register(tty: &Path, pid: u32) -> Option<Self> {
+            let line = tty.strip_prefix("/dev").unwrap_or(tty).to_str()?;
+            // SAFETY: utmpx is plain data; zeroed is an empty record
+            let mut record: libc::utmpx = unsafe { std::mem::zeroed() };
+            record.ut_pid = pid as libc::pid_t;
+            copy(&mut record.ut_line, line);
+            // The last characters of the line, as login and sshd do
+            copy(&mut record.ut_id, &line[line.len().saturating_sub(4)..]);
+            copy(&mut record.ut_user, &user()?);
+            let display = std::env::var("DISPLAY").or_else(|_| std::env::var("WAYLAND_DISPLAY"));
+            copy(&mut record.ut_host, &display.unwrap_or_default());
+            let login = Self { record };
+            login.write(libc::USER_PROCESS);
+            Some(login)
+        }
+
+        /// Mark the session ended.
+        pub fn logout(mut self) {
+            self.record.ut_user.fill(0);
+            self.record.ut_host.fill(0);
+            self.write(libc::DEAD_PROCESS);
+        }
+
+        fn write(&self, kind: libc::c_short) {
+            let mut record = self.record;
+            record.ut_type = kind;
+            let now = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default();
+            record.ut_tv.tv_sec = now.as_secs() as _;
+            record.ut_tv.tv_usec = now.subsec_micros() as _;
+            // SAFETY: the utmp functions keep no pointer to the record
+            let written = unsafe {
+                libc::setutxent();
+                let written = !libc::pututxline(&record).is_null();
+                libc::endutxent();
+                written
+            };
+            if !written {
+                let error = std::io::Error::last_os_error();
+                return log::info!("Can't write the utmp record: {}", error);
+            }
+            // SAFETY: as above, with a nul-terminated path
+            #[cfg(target_os = "linux")]
+            unsafe {
+                updwtmpx(c"/var/log/wtmp".as_ptr(), &record);
+            }
+        }
+    }
+
+    /// The login name: `$USER`, else the account of the real user ID.
+    fn user() -> Option<String> {
+        if let Ok(user) = std::env::var("USER") {
+            return Some(user);
+        }
+        // SAFETY: getpwuid returns a static record or null; the name is
+        // copied out before anything could overwrite it
+        unsafe {
+            let account = libc::getpwuid(libc::getuid());
+            if account.is_null() {
+                return None;
+            }
+            let name = std::ffi::CStr::from_ptr((*account).pw_name);
+            Some(name.to_string_lossy().into_owned())
+        }
+    }
+
+    /// Copy `text` into a fixed-size field, truncated: full fields need no
+    /// terminating nul.
+    fn copy(field: &mut [libc::c_char], text: &str) {
+        field.fill(0);
+        for (slot, byte) in field.iter_mut().zip(text.bytes()) {
+            *slot = byte as libc::c_char;
+        }
+    }
+}
+
+#[cfg(not(any(all(target_os = "linux", target_env = "gnu"), target_os = "macos")))]
+mod unsupported {
+    use std::path::Path;
+
+    /// Nothing is registered on this platform.
+    pub struct Login;
+
+    impl Login {
+        pub fn register(_tty: &Path, _pid: u32) -> Option<Self> {
+            log::warn!("utmp records are only written on glibc Linux and macOS");
+            None
+        }
+
+        pub fn logout(self) {}
+    }
+}
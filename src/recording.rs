@@ -0,0 +1,262 @@
+//! Output Recording
+//!
+//! The program's output byte for byte, as `script --timing` writes it: a
+//! typescript that starts with a `Script started on …` line, and a timing
+//! file next to it (`<file>.timing`) with a `<seconds since the last
+//! chunk> <bytes>` line per chunk, so `scriptreplay` plays it back at the
+//! original pace. Started with `--record <file>` or "Record output to a
+//! file" in the palette, for reproducing drawing bugs.
+//!
+//! [`Typescript`] reads a recording back. `--replay <file>` plays it in a
+//! window (see `crate::backend::ReplayBackend`); tests feed it to a
+//! [`Terminal`] of the recorded size and check the screen.
+//!
+//! ```
+//! use adamant::recording::Recorder;
+//! use web_time::{Duration, Instant};
+//!
+//! let path = std::env::temp_dir().join(format!("adamant-doc-{}.typescript", std::process::id()));
+//! let start = Instant::now();
+//! let mut recorder = Recorder::create(&path, 80, 24, start).unwrap();
+//! recorder.record(b"\x1b[1mhello", start + Duration::from_millis(250)).unwrap();
+//! recorder.record(b"\r\n", start + Duration::from_millis(1250)).unwrap();
+//! let timing = Recorder::timing_path(&path);
+//! recorder.finish().unwrap();
+//!
+//! let typescript = std::fs::read(&path).unwrap();
+//! assert!(typescript.starts_with(b"Script started on "));
+//! assert!(typescript.windows(11).any(|w| w == b"\x1b[1mhello\r\n"));
+//! assert_eq!(std::fs::read_to_string(&timing).unwrap(), "0.250000 9\n1.000000 2\n");
+//! # std::fs::remove_file(&path).unwrap();
+//! # std::fs::remove_file(&timing).unwrap();
+//! ```
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use web_time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::term::Terminal;
+
+/// Start of the header line of a typescript.
+const HEADER: &[u8] = b"Script started on ";
+
+/// Start of the footer `script` adds after the output.
+const FOOTER: &[u8] = b"\nScript done on ";
+
+/// The size of a terminal whose recording doesn't say.
+const DEFAULT_SIZE: (usize, usize) = (80, 24);
+
+/// A recording in progress.
+pub struct Recorder {
+    path: PathBuf,
+    typescript: BufWriter<File>,
+    timing: BufWriter<File>,
+    /// When the last chunk came
+    last: Instant,
+}
+
+impl Recorder {
+    /// Start recording to `path` and its timing file, replacing them, for
+    /// a `cols` x `rows` terminal. The first chunk's delay counts from
+    /// `now`.
+    pub fn create(path: &Path, cols: usize, rows: usize, now: Instant) -> io::Result<Self> {
+        let mut typescript = BufWriter::new(File::create(path)?);
+        let timing = BufWriter::new(File::create(Self::timing_path(path))?);
+        // The header line `scriptreplay` skips
+        writeln!(
+            typescript,
+            "Script started on {} [TERM=\"xterm-256color\" COLUMNS=\"{}\" LINES=\"{}\"]",
+            timestamp(SystemTime::now()),
+            cols,
+            rows
+        )?;
+        typescript.flush()?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            typescript,
+            timing,
+            last: now,
+        })
+    }
+
+    /// Where the timing of the typescript at `path` goes.
+    pub fn timing_path(path: &Path) -> PathBuf {
+        let mut timing = path.as_os_str().to_owned();
+        timing.push(".timing");
+        PathBuf::from(timing)
+    }
+
+    /// The typescript being written.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Add output that arrived at `now`. Written through right away, so a
+    /// crash keeps what led up to it.
+    pub fn record(&mut self, bytes: &[u8], now: Instant) -> io::Result<()> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        let delay = now.saturating_duration_since(self.last);
+        self.last = now;
+        self.typescript.write_all(bytes)?;
+        writeln!(self.timing, "{:.6} {}", delay.as_secs_f64(), bytes.len())?;
+        self.typescript.flush()?;
+        self.timing.flush()
+    }
+
+    /// End the typescript with the footer `script` writes. Returns its
+    /// path.
+    pub fn finish(mut self) -> io::Result<PathBuf> {
+        write!(
+            self.typescript,
+            "\nScript done on {}\n",
+            timestamp(SystemTime::now())
+        )?;
+        self.typescript.flush()?;
+        self.timing.flush()?;
+        Ok(self.path)
+    }
+}
+
+/// A recording read back.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Typescript {
+    /// Columns and rows, from the header
+    pub size: Option<(usize, usize)>,
+    /// The output, each chunk with the delay before it
+    pub chunks: Vec<(Duration, Vec<u8>)>,
+}
+
+impl Typescript {
+    /// Read the typescript at `path`, with the timing next to it if there
+    /// is one.
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let typescript = std::fs::read(path)?;
+        let timing = match std::fs::read_to_string(Recorder::timing_path(path)) {
+            Ok(timing) => Some(timing),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+        Self::parse(&typescript, timing.as_deref())
+    }
+
+    /// A typescript from its bytes and its timing file. Without timing the
+    /// output is one chunk; without a header, any bytes are output.
+    pub fn parse(typescript: &[u8], timing: Option<&str>) -> io::Result<Self> {
+        let (size, mut output) = match typescript.strip_prefix(HEADER) {
+            Some(rest) => {
+                let end = rest
+                    .iter()
+                    .position(|&b| b == b'\n')
+                    .map_or(rest.len(), |i| i + 1);
+                let header = String::from_utf8_lossy(&rest[..end]);
+                let size = header_value(&header, "COLUMNS").zip(header_value(&header, "LINES"));
+                (size, &rest[end..])
+            }
+            None => (None, typescript),
+        };
+
+        let Some(timing) = timing else {
+            if let Some(footer) = output.windows(FOOTER.len()).rposition(|w| w == FOOTER) {
+                output = &output[..footer];
+            }
+            let chunks = vec![(Duration::ZERO, output.to_vec())];
+            return Ok(Self { size, chunks });
+        };
+        let mut chunks = Vec::new();
+        for (i, line) in timing.lines().enumerate() {
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("timing line {}: expected `<seconds> <bytes>`", i + 1),
+                )
+            };
+            let (delay, len) = line.trim().split_once(' ').ok_or_else(invalid)?;
+            let delay = delay.parse::<f64>().ok().filter(|delay| *delay >= 0.0);
+            let delay =
+                Duration::try_from_secs_f64(delay.ok_or_else(invalid)?).map_err(|_| invalid())?;
+            let len: usize = len.parse().map_err(|_| invalid())?;
+            let (chunk, rest) = output.split_at(len.min(output.len()));
+            chunks.push((delay, chunk.to_vec()));
+            output = rest;
+        }
+        Ok(Self { size, chunks })
+    }
+
+    /// All of the output, without the delays.
+    pub fn output(&self) -> Vec<u8> {
+        self.chunks
+            .iter()
+            .flat_map(|(_, chunk)| chunk)
+            .copied()
+            .collect()
+    }
+
+    /// A terminal of the recorded size (80 x 24 if unknown) that was fed
+    /// the whole output, for checking the screen it leaves.
+    pub fn terminal(&self, scrollback: usize) -> Terminal {
+        let (cols, rows) = self.size.unwrap_or(DEFAULT_SIZE);
+        let mut terminal = Terminal::new(cols, rows, scrollback);
+        for (_, chunk) in &self.chunks {
+            terminal.feed(chunk);
+        }
+        terminal
+    }
+}
+
+/// The number in `NAME="n"` in a header.
+fn header_value(header: &str, name: &str) -> Option<usize> {
+    let start = header.find(&format!("{}=\"", name))? + name.len() + 2;
+    let len = header[start..].find('"')?;
+    header[start..start + len].parse().ok()
+}
+
+/// A new recording's path in `dir`, named after the time `now` (UTC):
+/// `adamant-20261015-093000.typescript`.
+pub fn default_path(dir: &Path, now: SystemTime) -> PathBuf {
+    let (date, time) = civil(now);
+    let name = format!(
+        "adamant-{:04}{:02}{:02}-{:02}{:02}{:02}.typescript",
+        date.0, date.1, date.2, time.0, time.1, time.2
+    );
+    dir.join(name)
+}
+
+/// `2026-10-15 09:30:00+00:00`, in UTC.
+fn timestamp(now: SystemTime) -> String {
+    let ((year, month, day), (hour, minute, second)) = civil(now);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}+00:00",
+        year, month, day, hour, minute, second
+    )
+}
+
+/// The UTC date and time of `now`.
+fn civil(now: SystemTime) -> ((i64, u32, u32), (u32, u32, u32)) {
+    let seconds = now
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() as i64);
+    let (days, secs) = (
+        seconds.div_euclid(86_400),
+        seconds.rem_euclid(86_400) as u32,
+    );
+    // Howard Hinnant's days-to-civil, with eras of 400 years from 0000-03-01
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    ((year, month, day), (secs / 3600, secs / 60 % 60, secs % 60))
+}
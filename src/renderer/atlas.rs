@@ -0,0 +1,200 @@
+//! Glyph Atlas
+//!
+//! Rasterized glyphs are packed into one RGBA texture so the whole grid can
+//! be drawn with a single instanced draw call. Packing uses simple shelves:
+//! glyphs are placed left to right, starting a new row (shelf) when the
+//! current one is full.
+//!
+//! When the texture is full the atlas is cleared and refilled on demand.
+//! TODO: Evict least recently used glyphs instead of clearing everything
+
+use std::collections::HashMap;
+
+use super::font::{FontSet, GlyphKey, LigatureKey, RasterizedGlyph};
+
+/// Side length of the atlas texture in pixels.
+pub const ATLAS_SIZE: u32 = 2048;
+
+/// Gap between glyphs to avoid sampling bleed.
+const PADDING: u32 = 1;
+
+/// A glyph's location in the atlas and how to place it in a cell.
+///
+/// Stored as small integers so it can be packed into the per-cell GPU
+/// format as-is.
+#[derive(Copy, Clone, Debug)]
+pub struct AtlasGlyph {
+    /// Top-left corner in the atlas, in texels
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+    /// Offset from the pen position to the left edge, in pixels
+    pub left: i16,
+    /// Offset from the baseline up to the top edge, in pixels
+    pub top: i16,
+    /// Color glyph (not tinted)
+    pub color: bool,
+}
+
+/// The atlas is out of space; clear it and rebuild the frame.
+#[derive(Debug)]
+pub struct AtlasFull;
+
+/// GPU texture holding rasterized glyphs, plus the lookup cache.
+pub struct Atlas {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    /// Next free position on the current shelf
+    cursor_x: u32,
+    cursor_y: u32,
+    /// Height of the tallest glyph on the current shelf
+    shelf_height: u32,
+    /// Cached glyphs; `None` marks glyphs with no pixels (e.g. space)
+    glyphs: HashMap<GlyphKey, Option<AtlasGlyph>>,
+    /// Cached ligature images, likewise
+    ligatures: HashMap<LigatureKey, Option<AtlasGlyph>>,
+}
+
+impl Atlas {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Glyph Atlas"),
+            size: wgpu::Extent3d {
+                width: ATLAS_SIZE,
+                height: ATLAS_SIZE,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self {
+            texture,
+            view,
+            cursor_x: 0,
+            cursor_y: 0,
+            shelf_height: 0,
+            glyphs: HashMap::new(),
+            ligatures: HashMap::new(),
+        }
+    }
+
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+
+    /// Forget all glyphs; they are re-rasterized on next use.
+    pub fn clear(&mut self) {
+        self.glyphs.clear();
+        self.ligatures.clear();
+        self.cursor_x = 0;
+        self.cursor_y = 0;
+        self.shelf_height = 0;
+    }
+
+    /// Look up a glyph, rasterizing and uploading it on first use.
+    pub fn get(
+        &mut self,
+        queue: &wgpu::Queue,
+        fonts: &mut FontSet,
+        key: GlyphKey,
+    ) -> Result<Option<AtlasGlyph>, AtlasFull> {
+        if let Some(glyph) = self.glyphs.get(&key) {
+            return Ok(*glyph);
+        }
+
+        let glyph = match fonts.rasterize(key) {
+            Some(raster) => Some(self.upload(queue, &raster)?),
+            None => None,
+        };
+        self.glyphs.insert(key, glyph);
+        Ok(glyph)
+    }
+
+    /// Look up the image of a ligature, rasterizing and uploading it on
+    /// first use.
+    pub fn get_ligature(
+        &mut self,
+        queue: &wgpu::Queue,
+        fonts: &mut FontSet,
+        key: &LigatureKey,
+    ) -> Result<Option<AtlasGlyph>, AtlasFull> {
+        if let Some(glyph) = self.ligatures.get(key) {
+            return Ok(*glyph);
+        }
+
+        let glyph = match fonts.rasterize_ligature(key) {
+            Some(raster) => Some(self.upload(queue, &raster)?),
+            None => None,
+        };
+        self.ligatures.insert(key.clone(), glyph);
+        Ok(glyph)
+    }
+
+    /// Copy a rasterized glyph into free space.
+    fn upload(
+        &mut self,
+        queue: &wgpu::Queue,
+        raster: &RasterizedGlyph,
+    ) -> Result<AtlasGlyph, AtlasFull> {
+        let (x, y) = self.allocate(raster.width, raster.height)?;
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            &raster.data,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(raster.width * 4),
+                rows_per_image: Some(raster.height),
+            },
+            wgpu::Extent3d {
+                width: raster.width,
+                height: raster.height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        // Everything fits in 16 bits: the atlas is smaller than 65536 texels
+        Ok(AtlasGlyph {
+            x: x as u16,
+            y: y as u16,
+            width: raster.width as u16,
+            height: raster.height as u16,
+            left: raster.left.clamp(i16::MIN as i32, i16::MAX as i32) as i16,
+            top: raster.top.clamp(i16::MIN as i32, i16::MAX as i32) as i16,
+            color: raster.color,
+        })
+    }
+
+    /// Reserve a `width` x `height` region on the shelves.
+    fn allocate(&mut self, width: u32, height: u32) -> Result<(u32, u32), AtlasFull> {
+        if width + PADDING > ATLAS_SIZE || height + PADDING > ATLAS_SIZE {
+            return Err(AtlasFull);
+        }
+
+        if self.cursor_x + width + PADDING > ATLAS_SIZE {
+            // Start a new shelf
+            self.cursor_x = 0;
+            self.cursor_y += self.shelf_height;
+            self.shelf_height = 0;
+        }
+        if self.cursor_y + height + PADDING > ATLAS_SIZE {
+            return Err(AtlasFull);
+        }
+
+        let position = (self.cursor_x, self.cursor_y);
+        self.cursor_x += width + PADDING;
+        self.shelf_height = self.shelf_height.max(height + PADDING);
+        Ok(position)
+    }
+}
@@ -0,0 +1,378 @@
+//! Cell Pipeline
+//!
+//! Draws the grid from one fixed-size [`GpuCell`] per visible cell, in
+//! row-major order. The record only describes what is *in* the cell (glyph,
+//! colors, flags); the shader derives the cell's position from the instance
+//! index, so frames are encoded without building any intermediate geometry.
+//!
+//! Cells are written straight into wgpu's mapped staging memory via
+//! [`CellPipeline::write_cells`]: one pass over the visible grid, one
+//! 24-byte store per cell, no `Vec` in between.
+//!
+//! See shaders/cells.wgsl for the matching shader.
+
+use std::num::NonZeroU64;
+
+use wgpu::{RenderPass, util::DeviceExt};
+
+use super::atlas::ATLAS_SIZE;
+use super::color_space::is_linear;
+use super::font::CellMetrics;
+use crate::config::Rgb;
+
+/// The cell spans two columns.
+pub const CELL_WIDE: u32 = 1 << 0;
+/// The glyph is a color bitmap (emoji) and is not tinted.
+pub const CELL_COLOR_GLYPH: u32 = 1 << 1;
+pub const CELL_UNDERLINE: u32 = 1 << 2;
+pub const CELL_STRIKEOUT: u32 = 1 << 3;
+
+/// GPU representation of one cell.
+///
+/// An all-zero record is an empty cell on the default background.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GpuCell {
+    /// Atlas position of the glyph in texels (`x | y << 16`)
+    pub glyph_pos: u32,
+    /// Glyph size in texels (`width | height << 16`); 0 = no glyph
+    pub glyph_size: u32,
+    /// Glyph bearing in pixels (`left | top << 16`, signed 16-bit each)
+    pub glyph_offset: u32,
+    /// Foreground color, see [`pack_color`]
+    pub fg: u32,
+    /// Background color; alpha 0 = default background (not drawn)
+    pub bg: u32,
+    /// `CELL_*` flags
+    pub flags: u32,
+}
+
+impl GpuCell {
+    const ATTRIBS: [wgpu::VertexAttribute; 6] = wgpu::vertex_attr_array![
+        0 => Uint32,  // glyph_pos
+        1 => Uint32,  // glyph_size
+        2 => Uint32,  // glyph_offset
+        3 => Uint32,  // fg
+        4 => Uint32,  // bg
+        5 => Uint32,  // flags
+    ];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<GpuCell>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBS,
+        }
+    }
+}
+
+/// Pack an sRGB color as RGBA8 (red in the lowest byte, as `unpack4x8unorm`
+/// expects). The shader converts to linear for sRGB targets.
+pub fn pack_color(color: Rgb, alpha: u8) -> u32 {
+    u32::from_le_bytes([color.0, color.1, color.2, alpha])
+}
+
+/// Pack two unsigned 16-bit values.
+pub fn pack_u16(low: u16, high: u16) -> u32 {
+    low as u32 | (high as u32) << 16
+}
+
+/// Pack two signed 16-bit values.
+pub fn pack_i16(low: i16, high: i16) -> u32 {
+    pack_u16(low as u16, high as u16)
+}
+
+/// Shader uniforms (must match `Uniforms` in cells.wgsl).
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Uniforms {
+    resolution: [f32; 2],
+    cell_size: [f32; 2],
+    cols: u32,
+    linear_target: u32,
+    baseline: f32,
+    underline_y: f32,
+    strikeout_y: f32,
+    stroke: f32,
+    atlas_size: f32,
+    scroll_y: f32,
+}
+
+/// Initial cell buffer capacity (grows on demand).
+const INITIAL_CAPACITY: usize = 200 * 60;
+
+/// Pipelines and buffers for drawing the grid.
+pub struct CellPipeline {
+    background: wgpu::RenderPipeline,
+    glyph: wgpu::RenderPipeline,
+    decoration: wgpu::RenderPipeline,
+    cell_buffer: wgpu::Buffer,
+    capacity: usize,
+    cell_count: u32,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    linear_target: bool,
+}
+
+impl CellPipeline {
+    /// Create the cell pipelines drawing into `format` targets.
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        atlas: &wgpu::TextureView,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Cell Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../../shaders/cells.wgsl").into()),
+        });
+
+        // Bind group: uniforms + glyph atlas
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Cell Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Cell Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            immediate_size: 0,
+        });
+
+        let create = |label, vertex, fragment| {
+            create_pipeline(
+                device,
+                &pipeline_layout,
+                &shader,
+                format,
+                label,
+                vertex,
+                fragment,
+            )
+        };
+        let background = create("Cell Background Pipeline", "vs_background", "fs_fill");
+        let glyph = create("Cell Glyph Pipeline", "vs_glyph", "fs_glyph");
+        let decoration = create("Cell Decoration Pipeline", "vs_decoration", "fs_fill");
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Cell Uniform Buffer"),
+            contents: bytemuck::bytes_of(&Uniforms::default()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        // Glyphs are placed on whole pixels, so nearest sampling keeps them crisp.
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Atlas Sampler"),
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Cell Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(atlas),
+                },
+            ],
+        });
+
+        Self {
+            background,
+            glyph,
+            decoration,
+            cell_buffer: create_cell_buffer(device, INITIAL_CAPACITY),
+            capacity: INITIAL_CAPACITY,
+            cell_count: 0,
+            uniform_buffer,
+            bind_group,
+            linear_target: is_linear(format),
+        }
+    }
+
+    /// Update the viewport and cell geometry. All rows are drawn
+    /// `scroll_y` pixels lower (higher if negative).
+    pub fn set_uniforms(
+        &self,
+        queue: &wgpu::Queue,
+        resolution: [f32; 2],
+        metrics: &CellMetrics,
+        cols: usize,
+        scroll_y: f32,
+    ) {
+        let uniforms = Uniforms {
+            resolution,
+            cell_size: [metrics.width, metrics.height],
+            cols: cols as u32,
+            linear_target: self.linear_target as u32,
+            baseline: metrics.baseline,
+            underline_y: metrics.baseline + metrics.underline_offset,
+            strikeout_y: metrics.baseline - metrics.strikeout_offset,
+            stroke: metrics.stroke_size,
+            atlas_size: ATLAS_SIZE as f32,
+            scroll_y,
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+    }
+
+    /// Write `count` cells, produced by `encode(index)` in row-major order,
+    /// directly into the queue's staging memory for the cell buffer.
+    ///
+    /// If `encode` fails the upload is abandoned half-written; call again
+    /// to rewrite the whole frame.
+    pub fn write_cells<E>(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        count: usize,
+        mut encode: impl FnMut(usize) -> Result<GpuCell, E>,
+    ) -> Result<(), E> {
+        self.cell_count = 0;
+        let Some(size) = NonZeroU64::new((count * std::mem::size_of::<GpuCell>()) as u64) else {
+            return Ok(());
+        };
+
+        if count > self.capacity {
+            self.capacity = count.next_power_of_two();
+            self.cell_buffer = create_cell_buffer(device, self.capacity);
+        }
+
+        let Some(mut view) = queue.write_buffer_with(&self.cell_buffer, 0, size) else {
+            return Ok(());
+        };
+
+        // Staging memory is normally aligned for u32; fall back to per-cell
+        // copies if an implementation hands out something odd.
+        match bytemuck::try_cast_slice_mut::<u8, GpuCell>(&mut view) {
+            Ok(cells) => {
+                for (index, slot) in cells.iter_mut().enumerate() {
+                    *slot = encode(index)?;
+                }
+            }
+            Err(_) => {
+                let stride = std::mem::size_of::<GpuCell>();
+                for (index, slot) in view.chunks_exact_mut(stride).enumerate() {
+                    slot.copy_from_slice(bytemuck::bytes_of(&encode(index)?));
+                }
+            }
+        }
+
+        self.cell_count = count as u32;
+        Ok(())
+    }
+
+    /// Draw the non-default cell backgrounds.
+    pub fn draw_backgrounds<'a>(&'a self, render_pass: &mut RenderPass<'a>) {
+        self.draw_with(render_pass, &self.background, 6);
+    }
+
+    /// Draw the glyphs.
+    pub fn draw_glyphs<'a>(&'a self, render_pass: &mut RenderPass<'a>) {
+        self.draw_with(render_pass, &self.glyph, 6);
+    }
+
+    /// Draw underlines and strikeouts (two quads per cell).
+    pub fn draw_decorations<'a>(&'a self, render_pass: &mut RenderPass<'a>) {
+        self.draw_with(render_pass, &self.decoration, 12);
+    }
+
+    fn draw_with<'a>(
+        &'a self,
+        render_pass: &mut RenderPass<'a>,
+        pipeline: &'a wgpu::RenderPipeline,
+        vertices: u32,
+    ) {
+        if self.cell_count == 0 {
+            return;
+        }
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.cell_buffer.slice(..));
+        render_pass.draw(0..vertices, 0..self.cell_count);
+    }
+}
+
+fn create_cell_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Cell Buffer"),
+        size: (capacity * std::mem::size_of::<GpuCell>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn create_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    label: &str,
+    vertex: &str,
+    fragment: &str,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some(vertex),
+            buffers: &[GpuCell::desc()],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some(fragment),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            cull_mode: None,
+            ..Default::default()
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview_mask: None,
+        cache: None,
+    })
+}
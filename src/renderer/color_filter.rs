@@ -0,0 +1,91 @@
+//! Color Vision Filters
+//!
+//! `Config::color_filter`: one 3x3 matrix over linear RGB, applied to the
+//! finished frame by a post shader (`shaders/color_filter.wgsl`) after
+//! every other pass, so it shows what is really on screen.
+//!
+//! Simulation uses the matrices of Machado, Oliveira and Fernandes (2009)
+//! at full severity. Correction is daltonization: what the simulation
+//! loses is moved into the channels the viewer still tells apart, and as
+//! both steps are linear the result is one matrix too. Grays are left
+//! alone either way:
+//!
+//! ```
+//! use adamant::config::{ColorBlindness, ColorFilter};
+//! use adamant::renderer::color_filter_matrix;
+//!
+//! let m = color_filter_matrix(ColorFilter::Correct(ColorBlindness::Deuteranopia));
+//! for row in m {
+//!     assert!((row.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+//! }
+//! ```
+
+use crate::config::{ColorBlindness, ColorFilter};
+
+type Matrix = [[f32; 3]; 3];
+
+const IDENTITY: Matrix = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+/// How `blindness` sees linear RGB.
+fn simulation(blindness: ColorBlindness) -> Matrix {
+    match blindness {
+        ColorBlindness::Protanopia => [
+            [0.152286, 1.052583, -0.204868],
+            [0.114503, 0.786281, 0.099216],
+            [-0.003882, -0.048116, 1.051998],
+        ],
+        ColorBlindness::Deuteranopia => [
+            [0.367322, 0.860646, -0.227968],
+            [0.280085, 0.672501, 0.047413],
+            [-0.011820, 0.042940, 0.968881],
+        ],
+        ColorBlindness::Tritanopia => [
+            [1.255528, -0.076749, -0.178779],
+            [-0.078411, 0.930809, 0.147602],
+            [0.004733, 0.691367, 0.303900],
+        ],
+    }
+}
+
+/// Where the lost differences go: red-green into green and blue, and
+/// blue-yellow into red and green.
+fn shift(blindness: ColorBlindness) -> Matrix {
+    match blindness {
+        ColorBlindness::Protanopia | ColorBlindness::Deuteranopia => {
+            [[0.0, 0.0, 0.0], [0.7, 1.0, 0.0], [0.7, 0.0, 1.0]]
+        }
+        ColorBlindness::Tritanopia => [[1.0, 0.0, 0.7], [0.0, 1.0, 0.7], [0.0, 0.0, 0.0]],
+    }
+}
+
+/// The matrix of `filter`, by rows, over linear RGB.
+pub fn color_filter_matrix(filter: ColorFilter) -> [[f32; 3]; 3] {
+    match filter {
+        ColorFilter::Simulate(blindness) => simulation(blindness),
+        ColorFilter::Correct(blindness) => {
+            let simulated = simulation(blindness);
+            let shift = shift(blindness);
+            // color + shift * (color - simulated color)
+            std::array::from_fn(|i| {
+                std::array::from_fn(|j| {
+                    let lost = |k: usize| IDENTITY[k][j] - simulated[k][j];
+                    IDENTITY[i][j] + (0..3).map(|k| shift[i][k] * lost(k)).sum::<f32>()
+                })
+            })
+        }
+    }
+}
+
+/// The filter as a post shader, for a frame holding linear values or not
+/// (see `is_linear`).
+pub fn color_filter_shader(filter: ColorFilter, linear_frame: bool) -> String {
+    let rows = color_filter_matrix(filter).map(|[r, g, b]| format!("{:?}, {:?}, {:?}", r, g, b));
+    format!(
+        "const FILTER: mat3x3<f32> = mat3x3<f32>(\n    {},\n    {},\n    {},\n);\nconst LINEAR_FRAME: bool = {};\n\n{}",
+        rows[0],
+        rows[1],
+        rows[2],
+        linear_frame,
+        include_str!("../../shaders/color_filter.wgsl")
+    )
+}
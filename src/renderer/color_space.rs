@@ -0,0 +1,51 @@
+//! Color Management
+//!
+//! Picks the surface format, and tells the pipelines whether their target
+//! stores linear light. Colors are configured in sRGB. An sRGB surface is
+//! sent to the display as-is, which over-saturates them on wide-gamut
+//! (Display P3) monitors unless the system color-manages the window.
+//! With `Config::wide_gamut` the renderer draws into an extended-range
+//! float surface instead, linear light in sRGB primaries (scRGB on
+//! Windows and Vulkan, EDR on macOS), and the compositor maps it to
+//! whatever the monitor can show, HDR included. sRGB white stays the
+//! standard white: nothing is brightened.
+//!
+//! ```
+//! use adamant::renderer::{is_linear, surface_format};
+//! use wgpu::TextureFormat;
+//!
+//! let formats = [
+//!     TextureFormat::Bgra8Unorm,
+//!     TextureFormat::Bgra8UnormSrgb,
+//!     TextureFormat::Rgba16Float,
+//! ];
+//! assert_eq!(surface_format(&formats, false), Some(TextureFormat::Bgra8UnormSrgb));
+//! assert_eq!(surface_format(&formats, true), Some(TextureFormat::Rgba16Float));
+//! // Both take linear colors from the shaders
+//! assert!(is_linear(TextureFormat::Bgra8UnormSrgb) && is_linear(TextureFormat::Rgba16Float));
+//! ```
+
+/// The extended-range format of wide-gamut and HDR surfaces.
+pub const WIDE_GAMUT_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// The format to configure a surface offering `formats` with: the wide
+/// gamut one if `wide_gamut` and there is one, else sRGB, else the
+/// surface's preferred format. `None` if it offers none.
+pub fn surface_format(
+    formats: &[wgpu::TextureFormat],
+    wide_gamut: bool,
+) -> Option<wgpu::TextureFormat> {
+    let wide = formats.iter().find(|&&f| f == WIDE_GAMUT_FORMAT);
+    let srgb = formats.iter().find(|f| f.is_srgb());
+    wide.filter(|_| wide_gamut)
+        .or(srgb)
+        .or(formats.first())
+        .copied()
+}
+
+/// Whether a target of `format` takes linear colors: sRGB formats encode
+/// them on write, and the wide gamut format is linear. Other formats get
+/// the sRGB values.
+pub fn is_linear(format: wgpu::TextureFormat) -> bool {
+    format.is_srgb() || format == WIDE_GAMUT_FORMAT
+}
@@ -0,0 +1,260 @@
+//! CRT Effect
+//!
+//! The built-in retro look (`Config::crt`): scanlines, a slight bloom,
+//! barrel distortion and phosphor persistence, tuned with intensities
+//! instead of a shader. It runs in two stages after the terminal is drawn:
+//! the phosphor stage keeps an afterglow texture from frame to frame
+//! (`shaders/phosphor.wgsl`), and the picture stage bends and lights it
+//! (`shaders/crt.wgsl`), a post shader with the intensities put in front:
+//!
+//! ```
+//! use adamant::config::CrtConfig;
+//! use adamant::renderer::{check_post_shader, crt_shader};
+//!
+//! let crt = CrtConfig {
+//!     curvature: 1.0,
+//!     ..CrtConfig::default()
+//! };
+//! let source = crt_shader(&crt);
+//! assert!(source.contains("const CURVATURE: f32 = 1.0;"));
+//! assert!(check_post_shader(&source).is_ok());
+//! ```
+
+use web_time::Instant;
+
+use super::post::{PostPass, PostUniforms, frame_texture};
+use crate::config::CrtConfig;
+use crate::error::Result;
+
+/// The picture stage as a post shader, for the intensities of `crt`.
+pub fn crt_shader(crt: &CrtConfig) -> String {
+    format!(
+        "const SCANLINES: f32 = {:?};\nconst BLOOM: f32 = {:?};\nconst CURVATURE: f32 = {:?};\n\n{}",
+        crt.scanlines,
+        crt.bloom,
+        crt.curvature,
+        include_str!("../../shaders/crt.wgsl")
+    )
+}
+
+/// Both stages, and the textures between them.
+pub struct CrtPass {
+    picture: PostPass,
+    /// `None` without persistence: the terminal is drawn straight into the
+    /// picture's frame
+    phosphor: Option<Phosphor>,
+}
+
+impl CrtPass {
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        crt: &CrtConfig,
+    ) -> Result<Self> {
+        let picture = PostPass::new(device, format, &crt_shader(crt))?;
+        let phosphor =
+            (crt.persistence > 0.0).then(|| Phosphor::new(device, format, crt.persistence));
+        Ok(Self { picture, phosphor })
+    }
+
+    /// The texture to draw the frame into, `width` x `height` pixels.
+    pub fn frame(&mut self, device: &wgpu::Device, width: u32, height: u32) -> wgpu::TextureView {
+        let afterglow = self.picture.frame(device, width, height);
+        match &mut self.phosphor {
+            Some(phosphor) => phosphor.frame(device, width, height, afterglow),
+            None => afterglow,
+        }
+    }
+
+    /// Add the frame to the afterglow and draw the picture into `target`.
+    pub fn draw(
+        &mut self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        uniforms: PostUniforms,
+    ) {
+        if let Some(phosphor) = &mut self.phosphor {
+            phosphor.draw(encoder);
+        }
+        self.picture.draw(queue, encoder, target, uniforms);
+    }
+}
+
+/// The phosphor stage: dims the afterglow, then draws the frame over it
+/// keeping the brighter of the two.
+struct Phosphor {
+    fade: wgpu::RenderPipeline,
+    glow: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    format: wgpu::TextureFormat,
+    /// Seconds for the afterglow to fall to 1/e
+    time_constant: f32,
+    /// The frame texture, its size, the bind group reading it, and the
+    /// afterglow texture drawn into
+    frame: Option<(
+        wgpu::TextureView,
+        (u32, u32),
+        wgpu::BindGroup,
+        wgpu::TextureView,
+    )>,
+    last_draw: Option<Instant>,
+}
+
+impl Phosphor {
+    fn new(device: &wgpu::Device, format: wgpu::TextureFormat, persistence: f32) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Phosphor Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../../shaders/phosphor.wgsl").into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Phosphor Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Phosphor Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            immediate_size: 0,
+        });
+
+        let pipeline = |label, entry_point, blend| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    buffers: &[],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some(entry_point),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: Some(wgpu::BlendState {
+                            color: blend,
+                            alpha: blend,
+                        }),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview_mask: None,
+                cache: None,
+            })
+        };
+        // The afterglow times the blend constant
+        let fade = pipeline(
+            "Phosphor Fade Pipeline",
+            "fs_fade",
+            wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::Zero,
+                dst_factor: wgpu::BlendFactor::Constant,
+                operation: wgpu::BlendOperation::Add,
+            },
+        );
+        // The brighter of the frame and the afterglow
+        let glow = pipeline(
+            "Phosphor Pipeline",
+            "fs_main",
+            wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Max,
+            },
+        );
+
+        Self {
+            fade,
+            glow,
+            bind_group_layout,
+            format,
+            time_constant: persistence * 0.1,
+            frame: None,
+            last_draw: None,
+        }
+    }
+
+    /// The texture to draw the frame into, with `afterglow` the texture
+    /// the phosphor draws into.
+    fn frame(
+        &mut self,
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        afterglow: wgpu::TextureView,
+    ) -> wgpu::TextureView {
+        if let Some((view, size, _, _)) = &self.frame
+            && *size == (width, height)
+        {
+            return view.clone();
+        }
+        let view = frame_texture(device, self.format, width, height);
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Phosphor Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            }],
+        });
+        self.frame = Some((view.clone(), (width, height), bind_group, afterglow));
+        view
+    }
+
+    fn draw(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let Some((_, _, bind_group, afterglow)) = &self.frame else {
+            return;
+        };
+        // What is left of the afterglow since the last frame; frames aren't
+        // drawn at a steady rate
+        let now = Instant::now();
+        let left = self.last_draw.map_or(0.0, |last| {
+            (-now.duration_since(last).as_secs_f32() / self.time_constant).exp()
+        }) as f64;
+        self.last_draw = Some(now);
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Phosphor Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: afterglow,
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+            multiview_mask: None,
+        });
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.set_pipeline(&self.fade);
+        render_pass.set_blend_constant(wgpu::Color {
+            r: left,
+            g: left,
+            b: left,
+            a: left,
+        });
+        render_pass.draw(0..3, 0..1);
+        render_pass.set_pipeline(&self.glow);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,535 @@
+//! Font Loading & Rasterization
+//!
+//! Fonts are loaded from the candidate sources in [`FontConfig`] and
+//! rasterized with `swash`. A [`FontSet`] answers two questions for the
+//! renderer: how big is a cell, and what does a character look like.
+//! Variable fonts are drawn at the axis values in the config, set apart
+//! for bold text. Overrides (`FontConfig::overrides`) draw some ranges of
+//! characters or scripts with fonts of their own, ahead of the faces and
+//! the fallback fonts. With `FontConfig::ligatures` or OpenType features turned
+//! on or off (`FontConfig::features`) it also shapes runs of text, for the
+//! glyphs the font puts in place of characters (see `crate::ligatures`).
+
+use std::ops::Range;
+
+use swash::{
+    CacheKey, FontRef, NormalizedCoord, Tag,
+    scale::{Render, ScaleContext, Scaler, Source, StrikeWith, image::Content, image::Image},
+    shape::ShapeContext,
+    tag_from_str_lossy,
+    text::{Codepoint, Script},
+    zeno::Format,
+};
+
+use crate::config::{FontConfig, FontOverride, FontSource};
+use crate::error::{AdamantError, Result};
+
+/// An owned font file.
+pub struct Font {
+    data: Vec<u8>,
+    offset: u32,
+    key: CacheKey,
+}
+
+impl Font {
+    /// Load the first font of a source (collections use index 0).
+    pub fn load(source: &FontSource) -> Option<Self> {
+        let data = match source {
+            FontSource::Path(path) => std::fs::read(path).ok()?,
+            FontSource::Embedded(data) => data.to_vec(),
+        };
+        let font = FontRef::from_index(&data, 0)?;
+        let (offset, key) = (font.offset, font.key);
+        Some(Self { data, offset, key })
+    }
+
+    /// Load the first candidate that exists and parses.
+    pub fn load_first(sources: &[FontSource]) -> Option<Self> {
+        sources.iter().find_map(|source| {
+            let font = Self::load(source)?;
+            match source {
+                FontSource::Path(path) => log::info!("Loaded font: {}", path),
+                FontSource::Embedded(data) => {
+                    log::info!("Loaded embedded font ({} bytes)", data.len())
+                }
+            }
+            Some(font)
+        })
+    }
+
+    pub fn as_ref(&self) -> FontRef<'_> {
+        FontRef {
+            data: &self.data,
+            offset: self.offset,
+            key: self.key,
+        }
+    }
+
+    /// The glyph for `c`, or 0 if the font doesn't cover it.
+    pub fn glyph_id(&self, c: char) -> u16 {
+        self.as_ref().charmap().map(c)
+    }
+}
+
+/// Pixel dimensions of one terminal cell.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CellMetrics {
+    pub width: f32,
+    pub height: f32,
+    /// Distance from the top of the cell to the baseline
+    pub baseline: f32,
+    /// Distance from the baseline down to the underline
+    pub underline_offset: f32,
+    /// Distance from the baseline up to the strikeout line
+    pub strikeout_offset: f32,
+    /// Thickness of underline/strikeout strokes
+    pub stroke_size: f32,
+}
+
+/// Identifies a rasterized glyph in the atlas cache.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GlyphKey {
+    pub c: char,
+    pub bold: bool,
+}
+
+/// Glyphs shaped in place of characters, drawn as one image over their
+/// cells (see `crate::ligatures`).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LigatureKey {
+    /// Each glyph, and where it goes in pixels: right of the first cell's
+    /// left edge, and up from the baseline
+    pub glyphs: Vec<(u16, i32, i32)>,
+    pub bold: bool,
+}
+
+/// Characters shaped into glyphs other than their own.
+pub struct ShapedCluster {
+    /// The characters' indices in the text shaped
+    pub chars: Range<usize>,
+    /// Each glyph, and where it goes in pixels: right of the first
+    /// character's pen position, and up from the baseline
+    pub glyphs: Vec<(u16, i32, i32)>,
+}
+
+/// A rasterized glyph as RGBA pixels.
+pub struct RasterizedGlyph {
+    /// Offset from the pen position to the left edge
+    pub left: i32,
+    /// Offset from the baseline up to the top edge
+    pub top: i32,
+    pub width: u32,
+    pub height: u32,
+    /// Color glyph (emoji) drawn as-is instead of tinted with the cell color
+    pub color: bool,
+    pub data: Vec<u8>,
+}
+
+/// A loaded `FontOverride`.
+struct Override {
+    ranges: Vec<(char, char)>,
+    /// OpenType script tags
+    scripts: Vec<Tag>,
+    font: Font,
+}
+
+impl Override {
+    /// Load the first of the override's fonts that loads, if any does.
+    fn load(config: &FontOverride) -> Option<Self> {
+        let Some(font) = Font::load_first(&config.font) else {
+            log::warn!("No font of the override {:?} loaded", config.font);
+            return None;
+        };
+        let scripts = (config.scripts.iter())
+            .filter_map(|name| {
+                let tag = tag_from_str_lossy(name);
+                let known = known_script(tag);
+                if !known {
+                    log::warn!("Unknown script in a font override: {}", name);
+                }
+                known.then_some(tag)
+            })
+            .collect();
+        Some(Self {
+            ranges: config.ranges.clone(),
+            scripts,
+            font,
+        })
+    }
+
+    /// Whether the override is for `c`.
+    fn matches(&self, c: char) -> bool {
+        (self.ranges.iter()).any(|&(first, last)| (first..=last).contains(&c))
+            || (!self.scripts.is_empty() && self.scripts.contains(&c.script().to_opentype()))
+    }
+}
+
+/// Whether `tag` is the OpenType tag of a script. `Script::from_opentype`
+/// misses some (its table is out of order, e.g. for `grek`), so look for a
+/// character of the script then.
+fn known_script(tag: Tag) -> bool {
+    Script::from_opentype(tag).is_some()
+        || (0..=char::MAX as u32)
+            .filter_map(char::from_u32)
+            .any(|c| c.script().to_opentype() == tag)
+}
+
+/// The fonts text is drawn with, and the axis values of variable ones.
+struct Faces {
+    /// Tried first, in order
+    overrides: Vec<Override>,
+    regular: Font,
+    bold: Option<Font>,
+    fallback: Vec<Font>,
+    /// Normalized axis values of regular text (`FontConfig::variations`)
+    regular_coords: Vec<NormalizedCoord>,
+    /// Those of bold text, in its face (`FontConfig::bold_variations`)
+    bold_coords: Vec<NormalizedCoord>,
+    /// Bold text is emboldened regular text: there is neither a bold face
+    /// nor axis values for it
+    synthetic_bold: bool,
+}
+
+impl Faces {
+    /// The face of regular or `bold` text, its axis values, and whether it
+    /// is emboldened.
+    fn face(&self, bold: bool) -> (&Font, &[NormalizedCoord], bool) {
+        match (&self.bold, bold) {
+            (_, false) => (&self.regular, &self.regular_coords, false),
+            (Some(face), true) => (face, &self.bold_coords, false),
+            (None, true) => (&self.regular, &self.bold_coords, self.synthetic_bold),
+        }
+    }
+
+    /// The glyph for `c` in the first override for it that has one.
+    fn overridden(&self, c: char) -> Option<(&Font, u16)> {
+        (self.overrides.iter())
+            .filter(|font| font.matches(c))
+            .find_map(|font| {
+                let id = font.font.glyph_id(c);
+                (id != 0).then_some((&font.font, id))
+            })
+    }
+
+    /// Pick the font that covers `key.c`: an override for it, else the
+    /// faces, falling back in configured order. Returns it with its axis
+    /// values and whether it is emboldened. Overrides and fallback fonts
+    /// are drawn at their default axis values.
+    fn select(&self, key: GlyphKey) -> (&Font, u16, &[NormalizedCoord], bool) {
+        if let Some((font, id)) = self.overridden(key.c) {
+            return (font, id, &[], key.bold);
+        }
+
+        let (face, coords, embolden) = self.face(key.bold);
+        let id = face.glyph_id(key.c);
+        if id != 0 {
+            return (face, id, coords, embolden);
+        }
+
+        // Missing from the bold face: regular, emboldened
+        let id = self.regular.glyph_id(key.c);
+        if id != 0 {
+            return (&self.regular, id, &self.regular_coords, key.bold);
+        }
+
+        for font in &self.fallback {
+            let id = font.glyph_id(key.c);
+            if id != 0 {
+                return (font, id, &[], key.bold);
+            }
+        }
+
+        // Nothing covers it: draw the primary font's .notdef box.
+        (&self.regular, 0, &self.regular_coords, false)
+    }
+}
+
+/// The fonts used to draw the grid.
+pub struct FontSet {
+    faces: Faces,
+    /// Configured size in logical pixels per em
+    base_size: f32,
+    /// Size in physical pixels per em
+    size: f32,
+    metrics: CellMetrics,
+    context: ScaleContext,
+    /// Shape runs of text (`FontConfig::ligatures` or `features`)
+    shaping: bool,
+    /// The OpenType features shaping turns on or off
+    features: Vec<(&'static str, u16)>,
+    shaper: ShapeContext,
+}
+
+impl FontSet {
+    /// Load the configured fonts at `config.size * scale_factor` pixels.
+    pub fn load(config: &FontConfig, scale_factor: f32) -> Result<Self> {
+        let regular =
+            Font::load_first(&config.regular).ok_or(AdamantError::Font("font.regular"))?;
+        let bold = Font::load_first(&config.bold);
+        let fallback = config.fallback.iter().filter_map(Font::load).collect();
+        let overrides = config.overrides.iter().filter_map(Override::load).collect();
+        let regular_coords = normalize(&regular, &config.variations);
+        let bold_variations = if config.bold_variations.is_empty() {
+            &config.variations
+        } else {
+            &config.bold_variations
+        };
+        let bold_coords = normalize(bold.as_ref().unwrap_or(&regular), bold_variations);
+        let synthetic_bold = bold.is_none() && config.bold_variations.is_empty();
+
+        let size = config.size * scale_factor;
+        let metrics = compute_metrics(&regular, &regular_coords, size);
+
+        Ok(Self {
+            faces: Faces {
+                overrides,
+                regular,
+                bold,
+                fallback,
+                regular_coords,
+                bold_coords,
+                synthetic_bold,
+            },
+            base_size: config.size,
+            size,
+            metrics,
+            context: ScaleContext::new(),
+            shaping: config.ligatures || !config.features.is_empty(),
+            features: features(config),
+            shaper: ShapeContext::new(),
+        })
+    }
+
+    pub fn metrics(&self) -> CellMetrics {
+        self.metrics
+    }
+
+    /// The normalized axis values regular or `bold` text is drawn at, one
+    /// for each axis of its face.
+    pub fn variations(&self, bold: bool) -> &[NormalizedCoord] {
+        self.faces.face(bold).1
+    }
+
+    /// Draw at `scale_factor` from now on, e.g. on a monitor with another
+    /// scale (including fractional ones like 1.25). Glyphs rasterized
+    /// before are at the old size.
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        self.size = self.base_size * scale_factor;
+        let faces = &self.faces;
+        self.metrics = compute_metrics(&faces.regular, &faces.regular_coords, self.size);
+    }
+
+    /// Rasterize a glyph. Returns `None` for glyphs with no visible pixels.
+    pub fn rasterize(&mut self, key: GlyphKey) -> Option<RasterizedGlyph> {
+        let (font, glyph_id, coords, embolden) = self.faces.select(key);
+        let mut scaler = self
+            .context
+            .builder(font.as_ref())
+            .size(self.size)
+            .normalized_coords(coords)
+            .hint(true)
+            .build();
+        let image = render(&mut scaler, glyph_id, embolden)?;
+        Some(RasterizedGlyph {
+            left: image.placement.left,
+            top: image.placement.top,
+            width: image.placement.width,
+            height: image.placement.height,
+            color: image.content == Content::Color,
+            data: rgba(&image),
+        })
+    }
+
+    /// Whether runs of text are shaped, for ligatures or OpenType
+    /// features.
+    pub fn shaping(&self) -> bool {
+        self.shaping
+    }
+
+    /// Whether `c` is drawn with the face of regular or `bold` text,
+    /// rather than an override or a fallback font. Only those runs are
+    /// shaped.
+    pub fn covers(&self, c: char, bold: bool) -> bool {
+        self.faces.face(bold).0.glyph_id(c) != 0 && self.faces.overridden(c).is_none()
+    }
+
+    /// Shape `text` in the face of regular or `bold` text. Returns the
+    /// clusters the font put glyphs of its own in place of the characters
+    /// for.
+    pub fn shape(&mut self, text: &str, bold: bool) -> Vec<ShapedCluster> {
+        let (font, coords, _) = self.faces.face(bold);
+        let mut shaper = (self.shaper.builder(font.as_ref()))
+            .size(self.size)
+            .normalized_coords(coords)
+            .features(&self.features)
+            .build();
+        shaper.add_str(text);
+        let starts: Vec<usize> = text.char_indices().map(|(i, _)| i).collect();
+        let index = |byte: u32| starts.partition_point(|&start| start < byte as usize);
+        let mut clusters = Vec::new();
+        shaper.shape_with(|cluster| {
+            let chars = index(cluster.source.start)..index(cluster.source.end);
+            let own = match (cluster.glyphs, &text[cluster.source.to_range()]) {
+                ([glyph], source) => {
+                    let mut source = source.chars();
+                    let c = source.next();
+                    source.next().is_none() && c.is_some_and(|c| glyph.id == font.glyph_id(c))
+                }
+                _ => false,
+            };
+            if own || cluster.is_empty() {
+                return;
+            }
+            let mut pen = 0.0;
+            let glyphs = cluster
+                .glyphs
+                .iter()
+                .map(|glyph| {
+                    let x = (pen + glyph.x).round() as i32;
+                    pen += glyph.advance;
+                    (glyph.id, x, glyph.y.round() as i32)
+                })
+                .collect();
+            clusters.push(ShapedCluster { chars, glyphs });
+        });
+        clusters
+    }
+
+    /// Rasterize a ligature's glyphs into one image. Returns `None` if none
+    /// has visible pixels.
+    pub fn rasterize_ligature(&mut self, key: &LigatureKey) -> Option<RasterizedGlyph> {
+        let (font, coords, embolden) = self.faces.face(key.bold);
+        let mut scaler = self
+            .context
+            .builder(font.as_ref())
+            .size(self.size)
+            .normalized_coords(coords)
+            .hint(true)
+            .build();
+        let images: Vec<(Image, i32, i32)> = key
+            .glyphs
+            .iter()
+            .filter_map(|&(id, x, y)| {
+                let image = render(&mut scaler, id, embolden)?;
+                let (left, top) = (x + image.placement.left, y + image.placement.top);
+                Some((image, left, top))
+            })
+            .collect();
+
+        // Everything drawn, from the pen position and the baseline
+        let left = images.iter().map(|(_, left, _)| *left).min()?;
+        let top = images.iter().map(|(_, _, top)| *top).max()?;
+        let right = (images.iter())
+            .map(|(image, left, _)| left + image.placement.width as i32)
+            .max()?;
+        let bottom = (images.iter())
+            .map(|(image, _, top)| top - image.placement.height as i32)
+            .min()?;
+        let (width, height) = ((right - left) as u32, (top - bottom) as u32);
+        let mut data = vec![0; (width * height * 4) as usize];
+        for (image, x, y) in &images {
+            let (x, y) = ((x - left) as u32, (top - y) as u32);
+            let pixels = rgba(image);
+            let stride = image.placement.width as usize * 4;
+            for (row, line) in pixels.chunks_exact(stride).enumerate() {
+                let start = (((y as usize + row) * width as usize) + x as usize) * 4;
+                for (dst, src) in data[start..start + stride]
+                    .chunks_exact_mut(4)
+                    .zip(line.chunks_exact(4))
+                {
+                    // Glyphs touching each other overlap at their edges
+                    if src[3] > dst[3] {
+                        dst.copy_from_slice(src);
+                    }
+                }
+            }
+        }
+        Some(RasterizedGlyph {
+            left,
+            top,
+            width,
+            height,
+            color: images
+                .iter()
+                .any(|(image, ..)| image.content == Content::Color),
+            data,
+        })
+    }
+}
+
+/// The OpenType features to shape with: the configured ones, after turning
+/// ligatures off unless they are wanted.
+fn features(config: &FontConfig) -> Vec<(&'static str, u16)> {
+    let ligatures = [("liga", config.ligatures), ("calt", config.ligatures)];
+    (ligatures.iter().chain(&config.features))
+        .map(|&(tag, on)| (tag, on as u16))
+        .collect()
+}
+
+/// The normalized coordinates of `font` at the axis values `variations`.
+/// Axes the font doesn't have are left out, with a log message.
+fn normalize(font: &Font, variations: &[(&str, f32)]) -> Vec<NormalizedCoord> {
+    let font = font.as_ref();
+    for (tag, _) in variations {
+        let mut axes = font.variations();
+        if !axes.any(|axis| axis.tag().to_be_bytes() == tag.as_bytes()) {
+            log::info!("The font has no {} axis", tag);
+        }
+    }
+    (font.variations())
+        .normalized_coords(variations.iter().copied())
+        .collect()
+}
+
+/// Render glyph `id`, emboldened if asked. Returns `None` for glyphs with
+/// no visible pixels.
+fn render(scaler: &mut Scaler, id: u16, embolden: bool) -> Option<Image> {
+    let mut render = Render::new(&[
+        Source::ColorOutline(0),
+        Source::ColorBitmap(StrikeWith::BestFit),
+        Source::Outline,
+    ]);
+    render.format(Format::Alpha);
+    if embolden {
+        render.embolden(1.0);
+    }
+    let image = render.render(scaler, id)?;
+    (image.placement.width != 0 && image.placement.height != 0).then_some(image)
+}
+
+/// The pixels of `image` as RGBA, masks as white with their coverage as
+/// alpha.
+fn rgba(image: &Image) -> Vec<u8> {
+    match image.content {
+        Content::Mask => image
+            .data
+            .iter()
+            .flat_map(|&a| [255, 255, 255, a])
+            .collect(),
+        Content::Color => image.data.clone(),
+        Content::SubpixelMask => image
+            .data
+            .chunks_exact(4)
+            .flat_map(|px| [255, 255, 255, px[1]])
+            .collect(),
+    }
+}
+
+fn compute_metrics(font: &Font, coords: &[NormalizedCoord], size: f32) -> CellMetrics {
+    let font = font.as_ref();
+    let metrics = font.metrics(coords).scale(size);
+    let glyph_metrics = font.glyph_metrics(coords).scale(size);
+    let advance = glyph_metrics.advance_width(font.charmap().map('M'));
+
+    let ascent = metrics.ascent.round();
+    let height = (metrics.ascent + metrics.descent + metrics.leading).ceil();
+
+    CellMetrics {
+        width: advance.round().max(1.0),
+        height: height.max(1.0),
+        baseline: ascent,
+        underline_offset: (-metrics.underline_offset).round().max(1.0),
+        strikeout_offset: metrics.strikeout_offset.round(),
+        stroke_size: metrics.stroke_size.round().max(1.0),
+    }
+}
@@ -0,0 +1,97 @@
+//! GPU Selection
+//!
+//! Maps [`GpuPreference`] to the adapter wgpu should pick. wgpu treats
+//! `LowPower` as "integrated" and `HighPerformance` as "discrete"; machines
+//! with a single GPU get that one either way.
+//!
+//! `Auto` asks the system whether it runs on battery: `/sys/class/power_supply`
+//! on Linux, `pmset` on macOS and `GetSystemPowerStatus` on Windows. Other
+//! systems, and ones that don't say, count as on AC power.
+//!
+//! ```
+//! use adamant::config::GpuPreference;
+//! use adamant::renderer::{pmset_on_battery, power_preference};
+//!
+//! let pmset = "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=1)\t81%; discharging";
+//! assert!(pmset_on_battery(pmset));
+//! assert_eq!(
+//!     power_preference(GpuPreference::Auto, true),
+//!     wgpu::PowerPreference::LowPower
+//! );
+//! ```
+
+use std::path::Path;
+
+use crate::config::GpuPreference;
+
+/// The adapter power preference for `preference`, on battery or not.
+pub fn power_preference(preference: GpuPreference, on_battery: bool) -> wgpu::PowerPreference {
+    match preference {
+        GpuPreference::Integrated => wgpu::PowerPreference::LowPower,
+        GpuPreference::Discrete => wgpu::PowerPreference::HighPerformance,
+        GpuPreference::Auto if on_battery => wgpu::PowerPreference::LowPower,
+        GpuPreference::Auto => wgpu::PowerPreference::HighPerformance,
+    }
+}
+
+/// Whether the machine is running on battery right now. Unknown counts as
+/// AC power.
+pub fn on_battery() -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        sysfs_on_battery(Path::new("/sys/class/power_supply"))
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let output = std::process::Command::new("pmset")
+            .args(["-g", "batt"])
+            .output();
+        output.is_ok_and(|output| pmset_on_battery(&String::from_utf8_lossy(&output.stdout)))
+    }
+    #[cfg(windows)]
+    {
+        use windows_sys::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+        // SAFETY: a struct of plain integers; the call only fills it in
+        let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+        let known = unsafe { GetSystemPowerStatus(&mut status) } != 0;
+        // 0 is offline, 1 online and 255 unknown
+        known && status.ACLineStatus == 0
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+    false
+}
+
+/// Whether the power supplies in `dir`, laid out as Linux's
+/// `/sys/class/power_supply`, say the machine runs on battery: a battery
+/// is discharging and no mains or USB supply is online.
+pub fn sysfs_on_battery(dir: &Path) -> bool {
+    let Ok(supplies) = std::fs::read_dir(dir) else {
+        return false;
+    };
+    let read = |supply: &Path, name: &str| {
+        std::fs::read_to_string(supply.join(name))
+            .map(|value| value.trim().to_string())
+            .unwrap_or_default()
+    };
+
+    let mut discharging = false;
+    for supply in supplies.flatten() {
+        let supply = supply.path();
+        match read(&supply, "type").as_str() {
+            "Mains" | "USB" if read(&supply, "online") == "1" => return false,
+            "Battery" => discharging |= read(&supply, "status") == "Discharging",
+            _ => {}
+        }
+    }
+    discharging
+}
+
+/// Whether the output of macOS's `pmset -g batt` says the machine draws
+/// from its battery.
+pub fn pmset_on_battery(output: &str) -> bool {
+    output
+        .lines()
+        .next()
+        .is_some_and(|line| line.contains("'Battery Power'"))
+}
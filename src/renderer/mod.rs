@@ -15,33 +15,83 @@
 //! - **Device**: The GPU abstraction
 //! - **Queue**: Command submission queue
 //! - **Pipeline**: The shader program + render state
+//! - **Atlas**: Texture cache of rasterized glyphs
+//!
+//! `Renderer` owns the window surface; drawing the terminal itself is done
+//! by [`TerminalRenderer`], which works with any device and target and is
+//! shared with the embeddable widget.
 
+mod atlas;
+mod cells;
+mod color_filter;
+mod color_space;
+mod crt;
+mod font;
+mod gpu;
+mod palette;
 mod pipeline;
+mod post;
+mod terminal;
 
 use std::sync::Arc;
 
 use winit::{dpi::PhysicalSize, window::Window};
 
-use self::pipeline::Pipeline;
+use crate::config::{ColorFilter, Colors, CrtConfig, GpuPreference};
+use crate::error::{AdamantError, Result};
+use crate::widget::TerminalWidget;
+
+use self::crt::CrtPass;
+use self::palette::Palette;
+use self::post::{PostPass, PostUniforms};
+
+pub use self::color_filter::{color_filter_matrix, color_filter_shader};
+pub use self::color_space::{is_linear, surface_format};
+pub use self::crt::crt_shader;
+pub use self::font::{CellMetrics, FontSet, GlyphKey};
+pub use self::gpu::{pmset_on_battery, power_preference, sysfs_on_battery};
+pub use self::post::check_post_shader;
+pub use self::terminal::{CursorDisplay, Decorations, Preedit, TerminalRenderer};
+
+/// The passes frames are drawn through, as they were last set: what a
+/// new renderer starts with when switching GPUs.
+#[derive(Clone, Debug, Default)]
+pub struct Effects {
+    /// Source of the post-processing shader
+    pub shader: Option<String>,
+    pub crt: Option<CrtConfig>,
+    pub color_filter: Option<ColorFilter>,
+}
 
 /// The main renderer struct.
 ///
-/// Holds all wgpu resources and handles rendering.
+/// Holds the window surface and the wgpu device used to draw into it.
 pub struct Renderer {
     surface: wgpu::Surface<'static>,
     device: wgpu::Device,
     queue: wgpu::Queue,
     config: wgpu::SurfaceConfiguration,
     size: PhysicalSize<u32>,
-    pipeline: Pipeline,
+    /// The user's shader over the frame (`Config::shader`); boxed, as
+    /// most renderers don't have one
+    post: Option<Box<PostPass>>,
+    /// The built-in CRT effect (`Config::crt`), drawn before `post`
+    crt: Option<Box<CrtPass>>,
+    /// The color vision filter (`Config::color_filter`), drawn last
+    filter: Option<Box<PostPass>>,
+    effects: Effects,
 }
 
 impl Renderer {
-    /// Create a new renderer for the given window.
+    /// Create a new renderer for the given window on the GPU `gpu` asks for,
+    /// drawing in wide gamut if asked and the surface supports it (see
+    /// `color_space.rs`).
     ///
     /// This initializes all wgpu resources.
-    pub async fn new(window: Arc<Window>) -> Result<Self, Box<dyn std::error::Error>> {
+    pub async fn new(window: Arc<Window>, gpu: GpuPreference, wide_gamut: bool) -> Result<Self> {
+        // A canvas may not be laid out yet; surfaces can't be zero-sized.
         let size = window.inner_size();
+        let size = PhysicalSize::new(size.width.max(1), size.height.max(1));
 
         // Create wgpu instance
         // Backends::all() => Vulkan + Metal + DX12 + Browser WebGPU
@@ -56,7 +106,7 @@ impl Renderer {
         // Request adapter (GPU selection)
         let adapter = instance
             .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
+                power_preference: gpu::power_preference(gpu, gpu::on_battery()),
                 compatible_surface: Some(&surface),
                 force_fallback_adapter: false,
             })
@@ -65,12 +115,19 @@ impl Renderer {
         log::info!("Using adapter: {:?}", adapter.get_info());
         log::info!("wgpu::Limits: {:?}", wgpu::Limits::default());
 
+        // Browsers may expose less than the native defaults
+        #[cfg(not(target_arch = "wasm32"))]
+        let required_limits = wgpu::Limits::default();
+        #[cfg(target_arch = "wasm32")]
+        let required_limits =
+            wgpu::Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits());
+
         // Request device and queue
         let (device, queue) = adapter
             .request_device(&wgpu::DeviceDescriptor {
                 label: Some("Adamant Device"),
                 required_features: wgpu::Features::empty(),
-                required_limits: wgpu::Limits::default(),
+                required_limits,
                 experimental_features: Default::default(),
                 memory_hints: wgpu::MemoryHints::Performance,
                 trace: Default::default(),
@@ -79,14 +136,17 @@ impl Renderer {
 
         // Configure surface
         let surface_caps = surface.get_capabilities(&adapter);
-
-        // Prefer sRGB format for correct color display
-        let surface_format = surface_caps
+        let surface_format = color_space::surface_format(&surface_caps.formats, wide_gamut)
+            .ok_or(AdamantError::UnsupportedSurface)?;
+        let can_widen = surface_caps
             .formats
-            .iter()
-            .find(|f| f.is_srgb())
-            .copied()
-            .unwrap_or(surface_caps.formats[0]);
+            .contains(&color_space::WIDE_GAMUT_FORMAT);
+        match (wide_gamut, can_widen) {
+            (true, true) => log::info!("Drawing in wide gamut ({:?})", surface_format),
+            (true, false) => log::warn!("The surface has no wide gamut format; drawing in sRGB"),
+            (false, true) => log::info!("The surface supports wide gamut (Config::wide_gamut)"),
+            (false, false) => {}
+        }
 
         // Prefer Mailbox for low latency, fallback to Fifo
         let present_mode = if surface_caps
@@ -125,19 +185,81 @@ impl Renderer {
         };
         surface.configure(&device, &config);
 
-        // Create render pipeline
-        let pipeline = Pipeline::new(&device, &config);
-
         Ok(Self {
             surface,
             device,
             queue,
             config,
             size,
-            pipeline,
+            post: None,
+            crt: None,
+            filter: None,
+            effects: Effects::default(),
         })
     }
 
+    pub fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    pub fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+
+    /// The surface texture format.
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.config.format
+    }
+
+    /// Draw frames through a post-processing shader (see `post.rs`), or
+    /// directly with `None`. A shader that doesn't compile is an error and
+    /// leaves the previous one in place.
+    pub fn set_shader(&mut self, source: Option<&str>) -> Result<()> {
+        self.post = match source {
+            Some(source) => Some(Box::new(PostPass::new(
+                &self.device,
+                self.config.format,
+                source,
+            )?)),
+            None => None,
+        };
+        Ok(())
+    }
+
+    /// Draw frames through the CRT effect, or without it with `None`.
+    pub fn set_crt(&mut self, crt: Option<&CrtConfig>) -> Result<()> {
+        self.crt = match crt {
+            Some(crt) => Some(Box::new(CrtPass::new(
+                &self.device,
+                self.config.format,
+                crt,
+            )?)),
+            None => None,
+        };
+        self.effects.crt = crt.cloned();
+        Ok(())
+    }
+
+    /// Draw frames through a color vision filter, or without one with
+    /// `None`.
+    pub fn set_color_filter(&mut self, filter: Option<ColorFilter>) -> Result<()> {
+        let format = self.config.format;
+        self.filter = match filter {
+            Some(filter) => {
+                let source = color_filter_shader(filter, is_linear(format));
+                Some(Box::new(PostPass::new(&self.device, format, &source)?))
+            }
+            None => None,
+        };
+        self.effects.color_filter = filter;
+        Ok(())
+    }
+
+    /// The passes frames are drawn through.
+    pub fn effects(&self) -> &Effects {
+        &self.effects
+    }
+
     /// Handle window resize.
     pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
@@ -145,18 +267,72 @@ impl Renderer {
             self.config.width = new_size.width;
             self.config.height = new_size.height;
             self.surface.configure(&self.device, &self.config);
-
-            // TODO: Phase 2 - Update projection matrix for text rendering
         }
     }
 
     /// Render a frame.
-    pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+    pub fn render(&mut self, widget: &mut TerminalWidget) -> Result<(), wgpu::SurfaceError> {
+        let (cursor, cell) = widget.ime_cursor_area();
+        let uniforms = PostUniforms {
+            cursor: [cursor.x as f32, cursor.y as f32],
+            cell: [cell.width as f32, cell.height as f32],
+            ..PostUniforms::default()
+        };
+        // Clear the frame; the widget draws the (translucent) background
+        self.frame(
+            wgpu::Color::TRANSPARENT,
+            uniforms,
+            |device, queue, encoder, view| {
+                widget.render(device, queue, encoder, view);
+            },
+        )
+    }
+
+    /// Present a frame showing only the default background, while the
+    /// terminal itself is still being set up.
+    pub fn render_placeholder(&mut self, colors: &Colors) -> Result<(), wgpu::SurfaceError> {
+        let palette = Palette::new(colors, self.config.format);
+        let [r, g, b, a] = palette.to_rgba(colors.background, colors.background_opacity);
+        // The surface expects premultiplied alpha
+        let (r, g, b, a) = (r * a, g * a, b * a, a);
+        let color = wgpu::Color {
+            r: r as f64,
+            g: g as f64,
+            b: b as f64,
+            a: a as f64,
+        };
+        self.frame(color, PostUniforms::default(), |_, _, _, _| {})
+    }
+
+    /// Clear the next surface texture to `clear`, let `draw` record into it
+    /// and present it. With a post shader, the CRT effect or a color
+    /// filter the frame is drawn offscreen first, then through them with
+    /// `uniforms` (time and resolution filled in here).
+    fn frame(
+        &mut self,
+        clear: wgpu::Color,
+        mut uniforms: PostUniforms,
+        draw: impl FnOnce(&wgpu::Device, &wgpu::Queue, &mut wgpu::CommandEncoder, &wgpu::TextureView),
+    ) -> Result<(), wgpu::SurfaceError> {
         // Get the next frame to render to
         let output = self.surface.get_current_texture()?;
         let view = output
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
+        // Each pass draws into the frame of the next one
+        let (width, height) = (self.size.width, self.size.height);
+        let filter_target = match &mut self.filter {
+            Some(filter) => filter.frame(&self.device, width, height),
+            None => view.clone(),
+        };
+        let post_target = match &mut self.post {
+            Some(post) => post.frame(&self.device, width, height),
+            None => filter_target.clone(),
+        };
+        let target = match &mut self.crt {
+            Some(crt) => crt.frame(&self.device, width, height),
+            None => post_target.clone(),
+        };
 
         // Create command encoder
         let mut encoder = self
@@ -165,22 +341,15 @@ impl Renderer {
                 label: Some("Render Encoder"),
             });
 
-        // Begin render pass
         {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            let _render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
+                    view: &target,
                     depth_slice: None,
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        // Clear to dark background color
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.0,
-                            g: 0.0,
-                            b: 0.0,
-                            a: 0.7,
-                        }),
+                        load: wgpu::LoadOp::Clear(clear),
                         store: wgpu::StoreOp::Store,
                     },
                 })],
@@ -189,9 +358,19 @@ impl Renderer {
                 timestamp_writes: None,
                 multiview_mask: None,
             });
+        }
+
+        draw(&self.device, &self.queue, &mut encoder, &target);
 
-            // Draw using the pipeline
-            self.pipeline.draw(&mut render_pass);
+        uniforms.resolution = [width as f32, height as f32];
+        if let Some(crt) = &mut self.crt {
+            crt.draw(&self.queue, &mut encoder, &post_target, uniforms);
+        }
+        if let Some(post) = &self.post {
+            post.draw(&self.queue, &mut encoder, &filter_target, uniforms);
+        }
+        if let Some(filter) = &self.filter {
+            filter.draw(&self.queue, &mut encoder, &view, uniforms);
         }
 
         // Submit commands
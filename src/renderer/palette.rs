@@ -0,0 +1,96 @@
+//! Color Palette
+//!
+//! Resolves the symbolic colors stored in the grid into GPU-ready RGBA.
+
+use super::color_space::is_linear;
+use crate::config::{Colors, Rgb};
+use crate::grid::Color;
+use crate::term::{ColorOverrides, ColorSlot};
+
+/// The full 256-color palette plus default colors, in sRGB.
+#[derive(Clone, Debug)]
+pub struct Palette {
+    pub foreground: Rgb,
+    pub background: Rgb,
+    pub background_opacity: f32,
+    pub cursor: Rgb,
+    colors: [Rgb; 256],
+    /// Output linear values (for sRGB and wide gamut render targets)
+    linear: bool,
+}
+
+impl Palette {
+    /// Build the palette from the configured scheme.
+    ///
+    /// Indices 16-231 are the xterm 6x6x6 color cube and 232-255 the
+    /// grayscale ramp.
+    pub fn new(config: &Colors, format: wgpu::TextureFormat) -> Self {
+        let mut palette = Self {
+            foreground: config.foreground,
+            background: config.background,
+            background_opacity: config.background_opacity,
+            cursor: config.cursor,
+            colors: [Rgb(0, 0, 0); 256],
+            linear: is_linear(format),
+        };
+        let mut overrides = ColorOverrides::default();
+        overrides.tint = config.tint;
+        palette.set_overrides(config, &overrides);
+        palette
+    }
+
+    /// Rebuild from the configured scheme with a session's `overrides`.
+    pub fn set_overrides(&mut self, config: &Colors, overrides: &ColorOverrides) {
+        self.colors[..16].copy_from_slice(&config.ansi);
+        let level = |n: usize| if n == 0 { 0 } else { (55 + n * 40) as u8 };
+        for i in 0..216 {
+            self.colors[16 + i] = Rgb(level(i / 36), level((i / 6) % 6), level(i % 6));
+        }
+        for i in 0..24 {
+            let gray = (8 + i * 10) as u8;
+            self.colors[232 + i] = Rgb(gray, gray, gray);
+        }
+        for (index, color) in overrides.indexed() {
+            self.colors[index as usize] = color;
+        }
+
+        let color = |slot, configured| overrides.get(slot).unwrap_or(configured);
+        self.foreground = color(ColorSlot::Foreground, config.foreground);
+        self.background = color(ColorSlot::Background, config.background);
+        self.cursor = color(ColorSlot::Cursor, config.cursor);
+        if let Some(tint) = overrides.tint {
+            self.background = tint.over(self.background);
+        }
+    }
+
+    /// Adapt the output to a new target format.
+    pub fn set_format(&mut self, format: wgpu::TextureFormat) {
+        self.linear = is_linear(format);
+    }
+
+    /// Resolve a grid color; `Color::Default` maps to `default`.
+    pub fn resolve(&self, color: Color, default: Rgb) -> Rgb {
+        match color {
+            Color::Default => default,
+            Color::Indexed(index) => self.colors[index as usize],
+            Color::Rgb(r, g, b) => Rgb(r, g, b),
+        }
+    }
+
+    /// Convert to the RGBA the shader expects for the current target format.
+    pub fn to_rgba(&self, color: Rgb, alpha: f32) -> [f32; 4] {
+        let channel = |c: u8| {
+            let c = c as f32 / 255.0;
+            if self.linear { srgb_to_linear(c) } else { c }
+        };
+        [channel(color.0), channel(color.1), channel(color.2), alpha]
+    }
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
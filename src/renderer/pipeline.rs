@@ -3,9 +3,15 @@
 //! This module handles the wgpu render pipeline setup.
 //! A pipeline defines how vertices are processed and pixels are colored.
 //!
+//! This pipeline draws free-standing filled rectangles in pixel
+//! coordinates: the default background, the cursor and other overlays.
+//! The grid itself is drawn by the cell pipeline (`cells.rs`).
+//!
 //! See docs/04_shaders.md for shader development guide.
 
-use wgpu::{util::DeviceExt, RenderPass};
+use std::ops::Range;
+
+use wgpu::{RenderPass, util::DeviceExt};
 
 /// Vertex data for a unit quad.
 /// Position in [0, 1] range, will be scaled by instance size.
@@ -28,25 +34,37 @@ impl Vertex {
 }
 
 /// Unit quad vertices (two triangles).
-/// Coordinates in [0, 1] range for easy scaling.
+/// Coordinates in [0, 1] range for easy scaling; y grows downwards.
 const QUAD_VERTICES: &[Vertex] = &[
     // Triangle 1
-    Vertex { position: [0.0, 0.0] }, // bottom-left
-    Vertex { position: [1.0, 0.0] }, // bottom-right
-    Vertex { position: [0.0, 1.0] }, // top-left
+    Vertex {
+        position: [0.0, 0.0],
+    }, // top-left
+    Vertex {
+        position: [1.0, 0.0],
+    }, // top-right
+    Vertex {
+        position: [0.0, 1.0],
+    }, // bottom-left
     // Triangle 2
-    Vertex { position: [1.0, 0.0] }, // bottom-right
-    Vertex { position: [1.0, 1.0] }, // top-right
-    Vertex { position: [0.0, 1.0] }, // top-left
+    Vertex {
+        position: [1.0, 0.0],
+    }, // top-right
+    Vertex {
+        position: [1.0, 1.0],
+    }, // bottom-right
+    Vertex {
+        position: [0.0, 1.0],
+    }, // bottom-left
 ];
 
 /// Per-instance data for each quad.
 #[repr(C)]
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Instance {
-    pub pos: [f32; 2],   // Position in clip space
-    pub size: [f32; 2],  // Size in clip space
-    pub color: [f32; 4], // RGBA color
+    pub pos: [f32; 2],   // Top-left position in pixels
+    pub size: [f32; 2],  // Size in pixels
+    pub color: [f32; 4], // RGBA color (straight alpha)
 }
 
 impl Instance {
@@ -63,30 +81,64 @@ impl Instance {
             attributes: &Self::ATTRIBS,
         }
     }
+
+    /// A filled rectangle.
+    pub fn rect(pos: [f32; 2], size: [f32; 2], color: [f32; 4]) -> Self {
+        Self { pos, size, color }
+    }
 }
 
+/// Shader uniforms.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct Uniforms {
+    /// Viewport size in pixels
+    resolution: [f32; 2],
+    _padding: [f32; 2],
+}
+
+/// Initial instance buffer capacity (grows on demand).
+const INITIAL_CAPACITY: usize = 4096;
+
 /// The render pipeline wrapper.
 pub struct Pipeline {
     render_pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
     instance_buffer: wgpu::Buffer,
+    instance_capacity: usize,
     instance_count: u32,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
 }
 
 impl Pipeline {
-    /// Create a new render pipeline.
-    pub fn new(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> Self {
+    /// Create a new render pipeline drawing into `format` targets.
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
         // Load shader
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Quad Shader"),
             source: wgpu::ShaderSource::Wgsl(include_str!("../../shaders/quad.wgsl").into()),
         });
 
+        // Bind group: uniforms
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Quad Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
         // Create pipeline layout
-        // TODO: Phase 2 - Add bind groups for textures (glyph atlas)
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Render Pipeline Layout"),
-            bind_group_layouts: &[],
+            bind_group_layouts: &[&bind_group_layout],
             immediate_size: 0,
         });
 
@@ -104,8 +156,10 @@ impl Pipeline {
                 module: &shader,
                 entry_point: Some("fs_main"),
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    format,
+                    // The shader outputs premultiplied alpha so translucent
+                    // backgrounds composite correctly with the desktop.
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
                 compilation_options: wgpu::PipelineCompilationOptions::default(),
@@ -136,62 +190,78 @@ impl Pipeline {
             usage: wgpu::BufferUsages::VERTEX,
         });
 
-        // Create test instances (grid of colored quads)
-        let instances = Self::create_test_instances();
-        let instance_count = instances.len() as u32;
+        let instance_buffer = Self::create_instance_buffer(device, INITIAL_CAPACITY);
 
-        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Instance Buffer"),
-            contents: bytemuck::cast_slice(&instances),
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniform Buffer"),
+            contents: bytemuck::bytes_of(&Uniforms {
+                resolution: [1.0, 1.0],
+                _padding: [0.0; 2],
+            }),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Quad Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
         });
 
         Self {
             render_pipeline,
             vertex_buffer,
             instance_buffer,
-            instance_count,
+            instance_capacity: INITIAL_CAPACITY,
+            instance_count: 0,
+            uniform_buffer,
+            bind_group,
         }
     }
 
-    /// Create test instances for visual debugging.
-    fn create_test_instances() -> Vec<Instance> {
-        let cols = 16;
-        let rows = 8;
-        let quad_size = 0.1;
-        let spacing = 0.12;
-
-        let mut instances = Vec::with_capacity(cols * rows);
-
-        for row in 0..rows {
-            for col in 0..cols {
-                // Calculate position (centered grid)
-                let x = (col as f32 - cols as f32 / 2.0) * spacing;
-                let y = (row as f32 - rows as f32 / 2.0) * spacing;
-
-                // Rainbow color based on position
-                let r = col as f32 / cols as f32;
-                let g = row as f32 / rows as f32;
-                let b = 1.0 - (r + g) / 2.0;
-
-                instances.push(Instance {
-                    pos: [x, y],
-                    size: [quad_size, quad_size],
-                    color: [r, g, b, 1.0],
-                });
-            }
-        }
+    fn create_instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Instance Buffer"),
+            size: (capacity * std::mem::size_of::<Instance>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Update the viewport size used to map pixels to clip space.
+    pub fn set_resolution(&self, queue: &wgpu::Queue, width: f32, height: f32) {
+        let uniforms = Uniforms {
+            resolution: [width, height],
+            _padding: [0.0; 2],
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+    }
 
-        instances
+    /// Upload this frame's instances, growing the buffer if needed.
+    pub fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, instances: &[Instance]) {
+        if instances.len() > self.instance_capacity {
+            self.instance_capacity = instances.len().next_power_of_two();
+            self.instance_buffer = Self::create_instance_buffer(device, self.instance_capacity);
+        }
+        queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(instances));
+        self.instance_count = instances.len() as u32;
     }
 
-    /// Draw all instances.
-    pub fn draw<'a>(&'a self, render_pass: &mut RenderPass<'a>) {
+    /// Draw a subset of the uploaded instances, so other pipelines can be
+    /// interleaved between them.
+    pub fn draw_range<'a>(&'a self, render_pass: &mut RenderPass<'a>, instances: Range<u32>) {
+        let instances = instances.start..instances.end.min(self.instance_count);
+        if instances.is_empty() {
+            return;
+        }
         render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
         render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
         render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
 
         // Draw 6 vertices (quad) for each instance
-        render_pass.draw(0..6, 0..self.instance_count);
+        render_pass.draw(0..6, instances);
     }
 }
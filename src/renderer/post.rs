@@ -0,0 +1,314 @@
+//! Post-Processing Pass
+//!
+//! A user shader run over the finished frame (`Config::shader`), for CRT
+//! curvature, scanlines, glow or background effects. The terminal is drawn
+//! into an offscreen texture the size of the window; then one full-screen
+//! triangle runs the user's fragment shader, which samples that texture,
+//! into the surface.
+//!
+//! The user's WGSL gets the prelude in `shaders/post.wgsl` put in front of
+//! it: the `frame` texture and its sampler, and the `post` uniforms (time,
+//! resolution, cursor position, cell size). It defines
+//! `fs_main(in: PostInput) -> @location(0) vec4<f32>`. Shaders are checked
+//! with naga before wgpu sees them, so a mistake is reported with its line
+//! instead of aborting:
+//!
+//! ```
+//! use adamant::renderer::check_post_shader;
+//!
+//! let invert = "@fragment
+//! fn fs_main(in: PostInput) -> @location(0) vec4<f32> {
+//!     let color = textureSample(frame, frame_sampler, in.uv);
+//!     return vec4<f32>(color.a - color.rgb, color.a);
+//! }";
+//! assert!(check_post_shader(invert).is_ok());
+//!
+//! let typo = "@fragment
+//! fn fs_main(in: PostInput) -> @location(0) vec4<f32> {
+//!     return colour;
+//! }";
+//! let error = check_post_shader(typo).unwrap_err().to_string();
+//! assert!(error.starts_with("invalid shader: line 3:"));
+//! ```
+
+use web_time::Instant;
+
+use crate::error::{AdamantError, Result};
+
+const PRELUDE: &str = include_str!("../../shaders/post.wgsl");
+
+/// The per-frame inputs of the shader; matches `PostUniforms` in
+/// `shaders/post.wgsl`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PostUniforms {
+    /// Window size in pixels
+    pub resolution: [f32; 2],
+    /// Top-left corner of the cursor cell, in pixels
+    pub cursor: [f32; 2],
+    /// Cell size in pixels
+    pub cell: [f32; 2],
+    /// Seconds since the shader was loaded
+    pub time: f32,
+    pub _padding: f32,
+}
+
+/// Check a post-processing shader: it must compile after the prelude and
+/// define the `fs_main` fragment entry point. Errors name the line of
+/// `source` they are on.
+pub fn check_post_shader(source: &str) -> Result<()> {
+    let full = full_source(source);
+    // On the line of the first place the error points to, if any
+    let error = |message: String, span: Option<naga::Span>| {
+        let prelude_lines = PRELUDE.lines().count() as u32;
+        let message = match span.map(|span| span.location(&full).line_number) {
+            Some(line) if line > prelude_lines => {
+                format!("line {}: {}", line - prelude_lines, message)
+            }
+            _ => message,
+        };
+        AdamantError::Shader(message)
+    };
+
+    let module = naga::front::wgsl::parse_str(&full).map_err(|e| {
+        let span = e
+            .labels()
+            .map(|(span, _)| span)
+            .find(naga::Span::is_defined);
+        error(e.message().to_string(), span)
+    })?;
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::default(),
+    )
+    .validate(&module)
+    .map_err(|e| {
+        let span = e
+            .spans()
+            .map(|&(span, _)| span)
+            .find(naga::Span::is_defined);
+        error(e.as_inner().to_string(), span)
+    })?;
+
+    let entry_point = module.entry_points.iter().any(|entry_point| {
+        entry_point.name == "fs_main" && entry_point.stage == naga::ShaderStage::Fragment
+    });
+    if !entry_point {
+        return Err(AdamantError::Shader(
+            "no `@fragment fn fs_main(in: PostInput) -> @location(0) vec4<f32>`".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// The prelude followed by the user's shader.
+fn full_source(source: &str) -> String {
+    format!("{}\n{}", PRELUDE.trim_end(), source)
+}
+
+/// An offscreen frame: drawn into, then sampled by the next pass.
+pub(super) fn frame_texture(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+) -> wgpu::TextureView {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Post Frame"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+/// The offscreen frame and the pass drawing it with the user's shader.
+pub struct PostPass {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    uniform_buffer: wgpu::Buffer,
+    sampler: wgpu::Sampler,
+    format: wgpu::TextureFormat,
+    /// The texture the frame is drawn into, its size, and the bind group
+    /// reading it
+    frame: Option<(wgpu::TextureView, (u32, u32), wgpu::BindGroup)>,
+    /// `PostUniforms::time` 0
+    start: Instant,
+}
+
+impl PostPass {
+    /// Compile `source` (see [`check_post_shader`]) for targets of
+    /// `format`.
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, source: &str) -> Result<Self> {
+        check_post_shader(source)?;
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Post Shader"),
+            source: wgpu::ShaderSource::Wgsl(full_source(source).into()),
+        });
+
+        // Bind group: uniforms + the frame and its sampler
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Post Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Post Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            immediate_size: 0,
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Post Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_post"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    // The frame is replaced, not blended over
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview_mask: None,
+            cache: None,
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Post Uniform Buffer"),
+            size: std::mem::size_of::<PostUniforms>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Effects bending the frame sample between pixels
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Post Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Ok(Self {
+            pipeline,
+            bind_group_layout,
+            uniform_buffer,
+            sampler,
+            format,
+            frame: None,
+            start: Instant::now(),
+        })
+    }
+
+    /// The texture to draw the frame into, `width` x `height` pixels.
+    pub fn frame(&mut self, device: &wgpu::Device, width: u32, height: u32) -> wgpu::TextureView {
+        if let Some((view, size, _)) = &self.frame
+            && *size == (width, height)
+        {
+            return view.clone();
+        }
+        let view = frame_texture(device, self.format, width, height);
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Post Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        });
+        self.frame = Some((view.clone(), (width, height), bind_group));
+        view
+    }
+
+    /// Draw the frame into `target` through the shader; `uniforms.time`
+    /// is filled in here.
+    pub fn draw(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        mut uniforms: PostUniforms,
+    ) {
+        let Some((_, _, bind_group)) = &self.frame else {
+            return;
+        };
+        uniforms.time = self.start.elapsed().as_secs_f32();
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Post Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+            multiview_mask: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,1013 @@
+//! Terminal Grid Rendering
+//!
+//! Draws the visible part of a [`Terminal`] in six layers:
+//!
+//! 1. Default background (one quad for the whole viewport), and lines
+//!    highlighted by watch rules
+//! 2. Cell backgrounds that differ from the default (and the selection)
+//! 3. The cursor
+//! 4. Glyphs from the atlas
+//! 5. Underline / strikeout decorations
+//! 6. The minimap and the scrollbar
+//!
+//! The order matters: instances are blended in submission order. Layers 2,
+//! 4 and 5 come from the per-cell buffer (see `cells.rs`), which is only
+//! re-encoded when [`Terminal::generation`], the selection, the inverted
+//! block cursor cell, the IME pre-edit, the hint labels or the overlays
+//! changed since the last upload.
+//!
+//! When text is shaped (`FontConfig::ligatures` and `features`) each cell
+//! of a ligature draws the slice of its image above it (see
+//! `crate::ligatures`).
+//!
+//! Text being composed with an input method ([`Preedit`]) is laid over the
+//! cursor row as underlined cells, so it looks like it was typed in place.
+//! Hints mode underlines its matches and draws their labels over them, and
+//! the [`Ui`] layers are drawn over everything, hiding the cursor under them.
+//! A status bar takes a row of its own above or below the grid's rows.
+//! Layers 1, 3 and 6 are quads rebuilt every frame: a couple, and a
+//! minimap's one per line.
+
+use std::collections::HashMap;
+
+use crate::config::{Colors, Config, Rgb, StatusBarPosition};
+use crate::error::Result;
+use crate::grid::{BidiRow, Cell, Color, CommandStatus, Flags, Grid, Point, Row, Selection};
+use crate::hints::{Hint, Hints};
+use crate::ligatures::{Ligature, LigatureMap};
+use crate::minimap::{self, Minimap};
+use crate::scrollbar::{self, Scrollbar};
+use crate::term::{ColorOverrides, CursorShape, Mode, Terminal, WidthOptions};
+use crate::ui::Ui;
+
+use super::atlas::{Atlas, AtlasFull};
+use super::cells::{
+    CELL_COLOR_GLYPH, CELL_STRIKEOUT, CELL_UNDERLINE, CELL_WIDE, CellPipeline, GpuCell, pack_color,
+    pack_i16, pack_u16,
+};
+use super::font::{CellMetrics, FontSet, GlyphKey, LigatureKey, ShapedCluster};
+use super::palette::Palette;
+use super::pipeline::{Instance, Pipeline};
+
+/// How the cursor is drawn in a frame.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CursorDisplay {
+    /// In the shape the application asked for
+    #[default]
+    Normal,
+    /// An outline of the cell, while the terminal doesn't have focus
+    Hollow,
+    /// Not drawn (the off phase of blinking)
+    Hidden,
+}
+
+/// Text being composed with an input method, drawn inline at the cursor.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Preedit {
+    pub text: String,
+    /// Byte offset of the input method's caret in `text`, if it shows one
+    pub cursor: Option<usize>,
+}
+
+/// What is drawn over the grid contents in a frame.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Decorations<'a> {
+    pub selection: Option<&'a Selection>,
+    pub cursor: CursorDisplay,
+    /// The cursor's shape: the application's (`Terminal::cursor_style`)
+    /// unless the host overrides it
+    pub cursor_shape: CursorShape,
+    /// Text being composed with an input method
+    pub preedit: Option<&'a Preedit>,
+    /// Labels of hints mode
+    pub hints: Option<&'a Hints>,
+    /// The link under the pointer while the link modifier is held,
+    /// underlined
+    pub link: Option<&'a Hint>,
+    /// Toasts, dialogs and menus
+    pub ui: Option<&'a Ui>,
+    /// Lines the drawn rows lag behind the view while scrolling animates:
+    /// positive shows older lines than the view's (see
+    /// `crate::scroll::ScrollAnimation`). The cursor is hidden meanwhile
+    pub scroll: f32,
+    pub scrollbar: Option<&'a Scrollbar>,
+    /// The minimap, in a column of its own right of the grid's. The
+    /// scrollbar moves left of it
+    pub minimap: Option<&'a Minimap>,
+    /// The cells of a status bar, drawn on a row added above or below the
+    /// grid. Scrolling isn't animated with one
+    pub status_bar: Option<(StatusBarPosition, &'a [Cell])>,
+    /// The scrollbar is hovered or dragged: its thumb is drawn stronger
+    pub scrollbar_active: bool,
+}
+
+/// Everything the cell buffer depends on besides the grid contents.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct CellState {
+    generation: u64,
+    selection: Option<Selection>,
+    block_cursor: Option<(usize, usize)>,
+    preedit: Option<Preedit>,
+    hints: Option<Hints>,
+    /// The hovered link's first cell (row, column) and width
+    link: Option<(usize, usize, usize)>,
+    ui: Option<Ui>,
+    status_bar: Option<(StatusBarPosition, Vec<Cell>)>,
+    /// The absolute line index of the first row drawn (negative above the
+    /// oldest line) and the number of rows drawn
+    lines: (isize, usize),
+    /// The first column drawn and the number of columns, of a wide grid
+    columns: (usize, usize),
+}
+
+/// Draws a terminal grid.
+pub struct TerminalRenderer {
+    pipeline: Pipeline,
+    cells: CellPipeline,
+    atlas: Atlas,
+    fonts: FontSet,
+    palette: Palette,
+    /// The configured colors, and the session's changes the palette was
+    /// last built with
+    colors: Colors,
+    overrides: Option<ColorOverrides>,
+    /// Default background, highlighted lines, cursor and scrollbar quads
+    instances: Vec<Instance>,
+    /// Index of the first quad drawn over the cell backgrounds
+    backgrounds_end: u32,
+    /// Index of the first minimap or scrollbar quad in `instances`
+    scrollbar_start: u32,
+    /// What the cell buffer was last encoded from
+    uploaded: Option<CellState>,
+    /// Show right-to-left text in visual order (`Config::bidi`)
+    bidi: bool,
+    width: u32,
+    height: u32,
+}
+
+impl TerminalRenderer {
+    /// Create the renderer for targets of the given `format`.
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        config: &Config,
+        scale_factor: f32,
+    ) -> Result<Self> {
+        let fonts = FontSet::load(&config.font, scale_factor)?;
+        let atlas = Atlas::new(device);
+        let pipeline = Pipeline::new(device, format);
+        let cells = CellPipeline::new(device, format, atlas.view());
+
+        Ok(Self {
+            pipeline,
+            cells,
+            atlas,
+            fonts,
+            palette: Palette::new(&config.colors, format),
+            colors: config.colors.clone(),
+            overrides: None,
+            instances: Vec::new(),
+            backgrounds_end: 1,
+            scrollbar_start: 0,
+            uploaded: None,
+            bidi: config.bidi,
+            width: 1,
+            height: 1,
+        })
+    }
+
+    /// Move to another device (after a GPU switch). The fonts are kept;
+    /// pipelines and the glyph atlas are created anew on `device`.
+    pub fn set_device(&mut self, device: &wgpu::Device, format: wgpu::TextureFormat) {
+        self.atlas = Atlas::new(device);
+        self.pipeline = Pipeline::new(device, format);
+        self.cells = CellPipeline::new(device, format, self.atlas.view());
+        self.palette.set_format(format);
+        self.uploaded = None;
+    }
+
+    /// Rasterize glyphs for another scale factor. The cell size changes
+    /// with it; callers recompute the grid size.
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        self.fonts.set_scale_factor(scale_factor);
+        self.atlas.clear();
+        self.uploaded = None;
+    }
+
+    /// Draw with another color scheme (a host profile's). The session's own
+    /// changes stay on top of it.
+    pub fn set_colors(&mut self, colors: &Colors) {
+        self.colors = colors.clone();
+        self.overrides = None;
+    }
+
+    /// The foreground and background `cell` is drawn with.
+    pub fn cell_colors(&self, cell: &Cell) -> (Rgb, Rgb) {
+        let (fg, bg, _) = cell_colors(&self.palette, cell);
+        (fg, bg)
+    }
+
+    /// The default foreground and background.
+    pub fn default_colors(&self) -> (Rgb, Rgb) {
+        (self.palette.foreground, self.palette.background)
+    }
+
+    /// Whether rows with right-to-left text are drawn in visual order.
+    pub fn bidi(&self) -> bool {
+        self.bidi
+    }
+
+    pub fn cell_metrics(&self) -> CellMetrics {
+        self.fonts.metrics()
+    }
+
+    /// How many columns and rows fit into `width` x `height` pixels.
+    pub fn grid_size(&self, width: u32, height: u32) -> (usize, usize) {
+        let metrics = self.fonts.metrics();
+        let cols = (width as f32 / metrics.width).floor() as usize;
+        let rows = (height as f32 / metrics.height).floor() as usize;
+        (cols.max(1), rows.max(1))
+    }
+
+    /// Set the size of the area the terminal is drawn into.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width.max(1);
+        self.height = height.max(1);
+    }
+
+    /// Upload this frame's data.
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        terminal: &Terminal,
+        decorations: &Decorations,
+    ) {
+        if self.overrides.as_ref() != Some(terminal.colors()) {
+            self.palette.set_overrides(&self.colors, terminal.colors());
+            self.overrides = Some(terminal.colors().clone());
+            self.uploaded = None;
+        }
+        let metrics = self.fonts.metrics();
+        let resolution = [self.width as f32, self.height as f32];
+        let first_line = terminal.grid().visible_line_index(0);
+
+        // The pre-edit text takes the cursor's place.
+        let preedit = decorations
+            .preedit
+            .filter(|preedit| !preedit.text.is_empty());
+        let grid = terminal.grid();
+        let covered = view_cursor_col(grid, self.bidi).is_some_and(|col| {
+            decorations
+                .ui
+                .is_some_and(|ui| ui.covers(grid.cursor.row, col))
+        });
+        let status_bar = decorations.status_bar;
+        let scroll = if status_bar.is_some() {
+            0.0
+        } else {
+            decorations.scroll
+        };
+        let cursor = if preedit.is_some() || covered || scroll != 0.0 {
+            CursorDisplay::Hidden
+        } else {
+            decorations.cursor
+        };
+
+        let mut state = CellState {
+            generation: terminal.generation(),
+            selection: decorations.selection.copied(),
+            block_cursor: block_cursor(terminal, cursor, decorations.cursor_shape),
+            preedit: preedit.cloned(),
+            hints: decorations.hints.cloned(),
+            link: (decorations.link).map(|link| (link.row, link.col, link.width)),
+            ui: decorations.ui.filter(|ui| !ui.is_empty()).cloned(),
+            status_bar: status_bar.map(|(position, cells)| (position, cells.to_vec())),
+            lines: (first_line as isize, grid.rows()),
+            columns: (grid.column_offset(), grid.view_cols()),
+        };
+        // While animating, one row more is drawn, shifted up by the part of
+        // a line the view is between two rows
+        let mut scroll_y = 0.0;
+        if scroll != 0.0 {
+            let top = first_line as f32 + scroll;
+            scroll_y = -top.rem_euclid(1.0) * metrics.height;
+            state.lines = (top.floor() as isize, grid.rows() + 1);
+        }
+        // The bar's row is one more; above the grid, it moves the rest down
+        match status_bar {
+            Some((StatusBarPosition::Top, _)) => {
+                state.lines = (first_line as isize - 1, grid.rows() + 1)
+            }
+            Some((StatusBarPosition::Bottom, _)) => state.lines.1 += 1,
+            None => {}
+        }
+        let state_lines = state.lines;
+        let status_row = bar_row(status_bar.map(|(position, _)| position), state_lines.1);
+        if self.uploaded.as_ref() != Some(&state) {
+            if self.encode_cells(device, queue, terminal, &state).is_err() {
+                log::debug!("Glyph atlas full, clearing");
+                self.atlas.clear();
+                if self.encode_cells(device, queue, terminal, &state).is_err() {
+                    log::warn!("Glyph atlas too small for one frame");
+                }
+            }
+            self.uploaded = Some(state);
+        }
+        self.cells.set_uniforms(
+            queue,
+            resolution,
+            &metrics,
+            terminal.grid().view_cols(),
+            scroll_y,
+        );
+
+        self.build_overlays(
+            terminal,
+            (cursor, decorations.cursor_shape),
+            preedit,
+            state_lines,
+            status_row,
+            scroll_y,
+        );
+        self.scrollbar_start = self.instances.len() as u32;
+        let mut right = self.width as f32;
+        if let Some(minimap) = decorations.minimap {
+            right -= self.build_minimap(minimap);
+        }
+        if let Some(scrollbar) = decorations.scrollbar {
+            self.build_scrollbar(scrollbar, decorations.scrollbar_active, right);
+        }
+        self.pipeline
+            .set_resolution(queue, resolution[0], resolution[1]);
+        self.pipeline.upload(device, queue, &self.instances);
+    }
+
+    /// Record the draw into `render_pass`.
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        // The first overlay instances are the default background and the
+        // highlighted lines, the rest (the cursor) goes between cell
+        // backgrounds and glyphs, and the minimap and scrollbar over
+        // everything.
+        self.pipeline
+            .draw_range(render_pass, 0..self.backgrounds_end);
+        self.cells.draw_backgrounds(render_pass);
+        self.pipeline
+            .draw_range(render_pass, self.backgrounds_end..self.scrollbar_start);
+        self.cells.draw_glyphs(render_pass);
+        self.cells.draw_decorations(render_pass);
+        self.pipeline
+            .draw_range(render_pass, self.scrollbar_start..u32::MAX);
+    }
+
+    /// Encode the visible grid straight into the cell buffer.
+    fn encode_cells(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        terminal: &Terminal,
+        state: &CellState,
+    ) -> Result<(), AtlasFull> {
+        let grid = terminal.grid();
+        // Cells of the view, of which `offset` is the first column
+        let (offset, cols) = state.columns;
+        let cursor = &grid.cursor;
+        let (first_line, rows) = state.lines;
+        // The rows drawn, by the line shown in them; none past either end
+        let line = |row: usize| {
+            usize::try_from(first_line + row as isize)
+                .ok()
+                .filter(|&line| line < grid.total_lines())
+        };
+        // Rows of the view (what overlays are positioned by) shift with it
+        let top = grid.visible_line_index(0) as isize;
+        let screen_row = |row: usize| {
+            usize::try_from(first_line + row as isize - top)
+                .ok()
+                .filter(|&row| row < grid.rows())
+        };
+        let blank = Cell::default();
+        let bidi: Vec<Option<BidiRow>> = if self.bidi {
+            (0..rows)
+                .map(|row| line(row).and_then(|line| BidiRow::new(grid.line(line))))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let cursor_col = view_cursor_col(grid, self.bidi).unwrap_or(0);
+        let preedit = match &state.preedit {
+            Some(preedit) => layout_preedit(preedit, cursor_col, cols, terminal.width_options()),
+            None => PreeditLayout::default(),
+        };
+        let hints = state
+            .hints
+            .as_ref()
+            .map(|hints| hint_cells(grid, hints))
+            .unwrap_or_default();
+        let overlay = state
+            .ui
+            .as_ref()
+            .map(|ui| ui.cells(grid.rows(), cols))
+            .unwrap_or_default();
+
+        let Self {
+            cells,
+            atlas,
+            fonts,
+            palette,
+            ..
+        } = self;
+
+        let bar = state
+            .status_bar
+            .as_ref()
+            .map(|(position, cells)| (bar_row(Some(*position), rows), cells));
+        // Rows in visual order aren't shaped
+        let ligatures: Vec<Option<RowLigatures>> = if fonts.shaping() {
+            (0..rows)
+                .map(|row| {
+                    let line = line(row).filter(|_| bidi.get(row).is_none_or(Option::is_none))?;
+                    Some(shape_row(fonts, grid.line(line)))
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        cells.write_cells(device, queue, cols * rows, |index| {
+            let (row, col) = (index / cols, index % cols);
+            if let Some((bar_row, bar_cells)) = bar
+                && bar_row == Some(row)
+            {
+                let cell = bar_cells.get(col).unwrap_or(&blank);
+                return encode_cell(palette, atlas, fonts, queue, cell, false, false);
+            }
+            let Some(line) = line(row) else {
+                return encode_cell(palette, atlas, fonts, queue, &blank, false, false);
+            };
+            let screen_row = screen_row(row);
+            if let Some(cell) = screen_row.and_then(|row| overlay.get(&(row, col))) {
+                return encode_cell(palette, atlas, fonts, queue, cell, false, false);
+            }
+            if screen_row == Some(cursor.row)
+                && let Some(cell) = col
+                    .checked_sub(preedit.start)
+                    .and_then(|i| preedit.cells.get(i))
+            {
+                return encode_cell(palette, atlas, fonts, queue, cell, false, false);
+            }
+            let reordered = bidi.get(row).and_then(Option::as_ref);
+            // Everything but the position on screen is logical
+            let col = col + offset;
+            let col = reordered.map_or(col, |bidi| bidi.logical(col));
+            if let Some(cell) = screen_row.and_then(|row| hints.get(&(row, col))) {
+                return encode_cell(palette, atlas, fonts, queue, cell, false, false);
+            }
+            let mut cell = &grid.line(line)[col];
+            let mirrored;
+            if let Some(bidi) = reordered
+                && bidi.glyph(col, cell.c) != cell.c
+            {
+                mirrored = Cell {
+                    c: bidi.glyph(col, cell.c),
+                    ..cell.clone()
+                };
+                cell = &mirrored;
+            }
+            // Links continue on the next row when they wrap, like hints
+            let in_link =
+                screen_row
+                    .zip(state.link)
+                    .is_some_and(|(row, (link_row, link_col, width))| {
+                        let cols = grid.cols();
+                        let start = link_row * cols + link_col;
+                        (start..start + width).contains(&(row * cols + col))
+                    });
+            let linked;
+            if in_link {
+                linked = Cell {
+                    flags: cell.flags | Flags::UNDERLINE,
+                    ..cell.clone()
+                };
+                cell = &linked;
+            }
+            let point = Point::new(line, col);
+            let selected = state
+                .selection
+                .is_some_and(|selection| selection.contains(point));
+            let under_cursor = screen_row.is_some_and(|row| state.block_cursor == Some((row, col)));
+            // A cell of a ligature draws its slice of it, in its own colors
+            if let Some(shaped) = ligatures.get(row).and_then(Option::as_ref)
+                && let Some((ligature, index)) = shaped.map.at(col)
+            {
+                let without = Cell {
+                    c: ' ',
+                    ..cell.clone()
+                };
+                let mut gpu = encode_cell(
+                    palette,
+                    atlas,
+                    fonts,
+                    queue,
+                    &without,
+                    selected,
+                    under_cursor,
+                )?;
+                let key = &shaped.keys[&ligature.col];
+                draw_slice(&mut gpu, atlas, fonts, queue, key, ligature, index)?;
+                return Ok(gpu);
+            }
+            encode_cell(palette, atlas, fonts, queue, cell, selected, under_cursor)
+        })
+    }
+
+    /// The default background, lines highlighted by watch rules and the
+    /// cursor. `lines`, `bar_row` and `scroll_y` place the rows like the
+    /// cells.
+    fn build_overlays(
+        &mut self,
+        terminal: &Terminal,
+        (display, shape): (CursorDisplay, CursorShape),
+        preedit: Option<&Preedit>,
+        lines: (isize, usize),
+        bar_row: Option<usize>,
+        scroll_y: f32,
+    ) {
+        let metrics = self.fonts.metrics();
+        let palette = &self.palette;
+        let instances = &mut self.instances;
+        instances.clear();
+
+        instances.push(Instance::rect(
+            [0.0, 0.0],
+            [self.width as f32, self.height as f32],
+            palette.to_rgba(palette.background, palette.background_opacity),
+        ));
+
+        let grid = terminal.grid();
+        let (cw, ch) = (metrics.width, metrics.height);
+
+        let highlight =
+            palette.to_rgba(palette.resolve(Color::Indexed(3), palette.foreground), 0.25);
+        let (first_line, rows) = lines;
+        for row in (0..rows).filter(|&row| Some(row) != bar_row) {
+            if usize::try_from(first_line + row as isize)
+                .ok()
+                .filter(|&line| line < grid.total_lines())
+                .is_some_and(|line| grid.line(line).highlighted)
+            {
+                instances.push(Instance::rect(
+                    [0.0, row as f32 * ch + scroll_y],
+                    [self.width as f32, ch],
+                    highlight,
+                ));
+            }
+        }
+        self.backgrounds_end = instances.len() as u32;
+        // The cursor row is one lower under a bar at the top
+        let top = if bar_row == Some(0) { ch } else { 0.0 };
+
+        // A thin caret inside the pre-edit text, where the input method has it
+        if let Some(preedit) = preedit
+            && let Some(offset) = preedit.cursor
+        {
+            let cursor_col = view_cursor_col(grid, self.bidi).unwrap_or(0);
+            let widths = terminal.width_options();
+            let layout = layout_preedit(preedit, cursor_col, grid.view_cols(), widths);
+            let col = layout.start + layout.caret(preedit, offset, widths);
+            instances.push(Instance::rect(
+                [col as f32 * cw, grid.cursor.row as f32 * ch + top],
+                [metrics.stroke_size * 2.0, ch],
+                palette.to_rgba(palette.cursor, 1.0),
+            ));
+        }
+
+        if !cursor_visible(terminal) || display == CursorDisplay::Hidden {
+            return;
+        }
+
+        let cursor = &grid.cursor;
+        let cell = &grid.row(cursor.row)[cursor.col];
+        // Scrolled out of a wide grid's view
+        let Some(col) = view_cursor_col(grid, self.bidi) else {
+            return;
+        };
+        let (x, y) = (col as f32 * cw, cursor.row as f32 * ch + top);
+        let width = if cell.flags.contains(Flags::WIDE) {
+            cw * 2.0
+        } else {
+            cw
+        };
+        let color = palette.to_rgba(palette.cursor, 1.0);
+
+        if display == CursorDisplay::Hollow {
+            let t = metrics.stroke_size;
+            instances.extend([
+                Instance::rect([x, y], [width, t], color),
+                Instance::rect([x, y + ch - t], [width, t], color),
+                Instance::rect([x, y], [t, ch], color),
+                Instance::rect([x + width - t, y], [t, ch], color),
+            ]);
+            return;
+        }
+
+        let (pos, size) = match shape {
+            CursorShape::Block => ([x, y], [width, ch]),
+            CursorShape::Underline => {
+                let thickness = metrics.stroke_size * 2.0;
+                ([x, y + ch - thickness], [width, thickness])
+            }
+            CursorShape::Beam => ([x, y], [metrics.stroke_size * 2.0, ch]),
+        };
+        instances.push(Instance::rect(pos, size, color));
+    }
+
+    /// The minimap's column along the right edge: a pixel row per line,
+    /// over a shade where the view is, and a yellow tick at the left of
+    /// lines with search matches. Returns the column's width.
+    fn build_minimap(&mut self, minimap: &Minimap) -> f32 {
+        let palette = &self.palette;
+        let column = minimap::width(self.fonts.metrics().width);
+        let x = self.width as f32 - column;
+        // A pixel of margin on either side of the lines
+        let length = column - 2.0;
+
+        let (start, end) = minimap.view();
+        self.instances.push(Instance::rect(
+            [x, start as f32],
+            [column, (end - start) as f32],
+            palette.to_rgba(palette.foreground, 0.12),
+        ));
+        for (y, line) in minimap.lines() {
+            let color = palette.resolve(line.color, palette.foreground);
+            self.instances.push(Instance::rect(
+                [x + 1.0, y as f32],
+                [(line.length * length).max(1.0), 1.0],
+                palette.to_rgba(color, 0.7),
+            ));
+        }
+        let yellow = palette.to_rgba(palette.resolve(Color::Indexed(3), palette.foreground), 1.0);
+        for &y in minimap.matches() {
+            self.instances
+                .push(Instance::rect([x, y as f32], [2.0, 1.0], yellow));
+        }
+        column
+    }
+
+    /// The thumb along the right edge, at `right`, a yellow line across it
+    /// at each line with search matches, and one at each command: red
+    /// where it failed.
+    fn build_scrollbar(&mut self, scrollbar: &Scrollbar, active: bool, right: f32) {
+        let metrics = self.fonts.metrics();
+        let palette = &self.palette;
+        let height = self.height as f32;
+        let bar = scrollbar::width(metrics.width);
+        let x = right - bar;
+
+        let (start, end) = scrollbar.thumb();
+        let alpha = if active { 0.6 } else { 0.35 };
+        self.instances.push(Instance::rect(
+            [x, start * height],
+            [bar, (end - start) * height],
+            palette.to_rgba(palette.foreground, alpha),
+        ));
+
+        let mark = (metrics.stroke_size * 2.0).max(2.0);
+        let red = palette.to_rgba(palette.resolve(Color::Indexed(1), palette.foreground), 1.0);
+        let plain = palette.to_rgba(palette.foreground, 0.7);
+        let yellow = palette.to_rgba(palette.resolve(Color::Indexed(3), palette.foreground), 1.0);
+        for position in scrollbar.matches() {
+            let y = (position * height - mark / 2.0).clamp(0.0, height - mark);
+            self.instances
+                .push(Instance::rect([x, y], [bar, mark], yellow));
+        }
+        for (position, status) in scrollbar.commands() {
+            let y = (position * height - mark / 2.0).clamp(0.0, height - mark);
+            let color = if status == CommandStatus::Failed {
+                red
+            } else {
+                plain
+            };
+            self.instances
+                .push(Instance::rect([x, y], [bar, mark], color));
+        }
+    }
+}
+
+/// The row of `rows` drawn the status bar is on.
+fn bar_row(position: Option<StatusBarPosition>, rows: usize) -> Option<usize> {
+    match position? {
+        StatusBarPosition::Top => Some(0),
+        StatusBarPosition::Bottom => Some(rows - 1),
+    }
+}
+
+fn cursor_visible(terminal: &Terminal) -> bool {
+    terminal.mode().contains(Mode::SHOW_CURSOR) && terminal.grid().display_offset() == 0
+}
+
+/// The cells hints mode replaces: matches still to be picked are
+/// underlined, with the rest of their label over their first cells.
+fn hint_cells(grid: &Grid, hints: &Hints) -> HashMap<(usize, usize), Cell> {
+    let mut cells = HashMap::new();
+    let typed = hints.typed().len();
+    let (cols, rows) = (grid.cols(), grid.rows());
+    for hint in hints.candidates() {
+        // Matches continue on the next row when they wrap
+        let start = hint.row * cols + hint.col;
+        let end = (start + hint.width).min(rows * cols);
+        let mut label = hint.label[typed..].chars();
+        for index in start..end {
+            let (row, col) = (index / cols, index % cols);
+            let cell = match label.next() {
+                Some(c) => Cell {
+                    c,
+                    fg: Color::Indexed(0),
+                    bg: Color::Indexed(11),
+                    flags: Flags::BOLD,
+                    link: 0,
+                },
+                None => {
+                    let mut cell = grid.visible_row(row)[col].clone();
+                    cell.flags |= Flags::UNDERLINE;
+                    cell
+                }
+            };
+            cells.insert((row, col), cell);
+        }
+    }
+    cells
+}
+
+/// The column the cursor is drawn at, after reordering its row.
+fn visual_cursor_col(grid: &Grid, bidi: bool) -> usize {
+    let cursor = &grid.cursor;
+    if !bidi {
+        return cursor.col;
+    }
+    BidiRow::new(grid.row(cursor.row)).map_or(cursor.col, |row| row.visual(cursor.col))
+}
+
+/// The column of the view the cursor is drawn at, unless the view of a
+/// wide grid is scrolled away from it.
+fn view_cursor_col(grid: &Grid, bidi: bool) -> Option<usize> {
+    let col = visual_cursor_col(grid, bidi).checked_sub(grid.column_offset())?;
+    (col < grid.view_cols()).then_some(col)
+}
+
+/// The cell under a solid block cursor, whose glyph is drawn inverted.
+fn block_cursor(
+    terminal: &Terminal,
+    display: CursorDisplay,
+    shape: CursorShape,
+) -> Option<(usize, usize)> {
+    let cursor = &terminal.grid().cursor;
+    (cursor_visible(terminal) && display == CursorDisplay::Normal && shape == CursorShape::Block)
+        .then_some((cursor.row, cursor.col))
+}
+
+/// Pre-edit text as cells on the cursor row.
+#[derive(Default)]
+struct PreeditLayout {
+    /// Column of the first cell
+    start: usize,
+    cells: Vec<Cell>,
+}
+
+impl PreeditLayout {
+    /// Columns covered by the text before byte `offset`.
+    fn caret(&self, preedit: &Preedit, offset: usize, widths: WidthOptions) -> usize {
+        let before = preedit.text.get(..offset).unwrap_or(&preedit.text);
+        widths.str_width(before).min(self.cells.len())
+    }
+}
+
+/// Lay out `preedit` from the cursor column, moved left when it would run
+/// past the right edge and cut off when it's wider than the whole row.
+/// Widths follow the terminal's policy so the text lines up once committed.
+fn layout_preedit(
+    preedit: &Preedit,
+    cursor_col: usize,
+    cols: usize,
+    widths: WidthOptions,
+) -> PreeditLayout {
+    let mut cells = Vec::new();
+    for c in preedit.text.chars() {
+        let width = widths.width(c);
+        if width == 0 {
+            continue;
+        }
+        let mut cell = Cell {
+            c,
+            flags: Flags::UNDERLINE,
+            ..Cell::default()
+        };
+        if width == 2 {
+            cell.flags |= Flags::WIDE;
+            cells.push(cell);
+            cells.push(Cell {
+                c: ' ',
+                flags: Flags::WIDE_SPACER,
+                ..Cell::default()
+            });
+        } else {
+            cells.push(cell);
+        }
+    }
+
+    if cells.len() > cols {
+        cells.truncate(cols);
+        // Don't leave half of a wide character at the edge
+        if let Some(last) = cells.last_mut()
+            && last.flags.contains(Flags::WIDE)
+        {
+            *last = Cell {
+                flags: Flags::UNDERLINE,
+                ..Cell::default()
+            };
+        }
+    }
+    PreeditLayout {
+        start: cursor_col.min(cols - cells.len()),
+        cells,
+    }
+}
+
+/// Translate one grid cell into its GPU record, rasterizing its glyph into
+/// the atlas on first use.
+fn encode_cell(
+    palette: &Palette,
+    atlas: &mut Atlas,
+    fonts: &mut FontSet,
+    queue: &wgpu::Queue,
+    cell: &Cell,
+    selected: bool,
+    under_cursor: bool,
+) -> Result<GpuCell, AtlasFull> {
+    // The wide character to the left covers this cell.
+    if cell.flags.contains(Flags::WIDE_SPACER) {
+        return Ok(GpuCell::default());
+    }
+
+    let (mut fg, mut bg, mut default_bg) = cell_colors(palette, cell);
+    // Selected cells are shown inverted.
+    if selected {
+        std::mem::swap(&mut fg, &mut bg);
+        default_bg = false;
+    }
+    if under_cursor {
+        fg = bg;
+    }
+    let alpha = if cell.flags.contains(Flags::DIM) {
+        168
+    } else {
+        255
+    };
+
+    let mut gpu = GpuCell {
+        fg: pack_color(fg, alpha),
+        bg: if default_bg { 0 } else { pack_color(bg, 255) },
+        ..Default::default()
+    };
+    if cell.flags.contains(Flags::WIDE) {
+        gpu.flags |= CELL_WIDE;
+    }
+    if cell.flags.contains(Flags::HIDDEN) {
+        return Ok(gpu);
+    }
+    if cell.flags.contains(Flags::UNDERLINE) {
+        gpu.flags |= CELL_UNDERLINE;
+    }
+    if cell.flags.contains(Flags::STRIKEOUT) {
+        gpu.flags |= CELL_STRIKEOUT;
+    }
+
+    if cell.c != ' ' {
+        let key = GlyphKey {
+            c: cell.c,
+            bold: cell.flags.contains(Flags::BOLD),
+        };
+        if let Some(glyph) = atlas.get(queue, fonts, key)? {
+            gpu.glyph_pos = pack_u16(glyph.x, glyph.y);
+            gpu.glyph_size = pack_u16(glyph.width, glyph.height);
+            gpu.glyph_offset = pack_i16(glyph.left, glyph.top);
+            if glyph.color {
+                gpu.flags |= CELL_COLOR_GLYPH;
+            }
+        }
+    }
+
+    Ok(gpu)
+}
+
+/// Draw the slice of a ligature's image above its cell `index` in `gpu`.
+fn draw_slice(
+    gpu: &mut GpuCell,
+    atlas: &mut Atlas,
+    fonts: &mut FontSet,
+    queue: &wgpu::Queue,
+    key: &LigatureKey,
+    ligature: Ligature,
+    index: usize,
+) -> Result<(), AtlasFull> {
+    let Some(glyph) = atlas.get_ligature(queue, fonts, key)? else {
+        return Ok(());
+    };
+    let cell_width = fonts.metrics().width as u32;
+    let slice = ligature.slice(index, cell_width, glyph.left as i32, glyph.width as u32);
+    if slice.is_empty() {
+        return Ok(());
+    }
+    // The slice's left edge, from the cell's
+    let left = glyph.left as i32 + slice.start as i32 - (index as u32 * cell_width) as i32;
+    gpu.glyph_pos = pack_u16(glyph.x + slice.start as u16, glyph.y);
+    gpu.glyph_size = pack_u16(slice.len() as u16, glyph.height);
+    gpu.glyph_offset = pack_i16(left as i16, glyph.top);
+    if glyph.color {
+        gpu.flags |= CELL_COLOR_GLYPH;
+    }
+    Ok(())
+}
+
+/// The ligatures of a row, and the glyphs of each by its first column.
+struct RowLigatures {
+    map: LigatureMap,
+    keys: HashMap<usize, LigatureKey>,
+}
+
+/// Shape the runs of `row` drawn with the faces of regular and bold text.
+/// Glyphs of clusters next to each other are drawn together.
+fn shape_row(fonts: &mut FontSet, row: &Row) -> RowLigatures {
+    let cells = row.cells();
+    let cell_width = fonts.metrics().width as i32;
+    let mut shaped: Vec<(Ligature, LigatureKey)> = Vec::new();
+    let mut col = 0;
+    while col < cells.len() {
+        let bold = cells[col].flags.contains(Flags::BOLD);
+        let run = cells[col..]
+            .iter()
+            .take_while(|cell| {
+                let skipped = Flags::WIDE | Flags::WIDE_SPACER | Flags::HIDDEN;
+                cell.c != ' '
+                    && !cell.flags.intersects(skipped)
+                    && cell.flags.contains(Flags::BOLD) == bold
+                    && fonts.covers(cell.c, bold)
+            })
+            .count();
+        if run == 0 {
+            col += 1;
+            continue;
+        }
+        let text: String = cells[col..col + run].iter().map(|cell| cell.c).collect();
+        // Where the run's first ligature is in `shaped`
+        let first = shaped.len();
+        for ShapedCluster { chars, glyphs } in fonts.shape(&text, bold) {
+            let start = col + chars.start;
+            let adjacent = shaped[first..]
+                .last()
+                .is_some_and(|(last, _)| last.col + last.len == start);
+            if !adjacent {
+                let key = LigatureKey {
+                    glyphs: Vec::new(),
+                    bold,
+                };
+                shaped.push((Ligature { col: start, len: 0 }, key));
+            }
+            if let Some((ligature, key)) = shaped.last_mut() {
+                let shift = (start - ligature.col) as i32 * cell_width;
+                ligature.len += chars.len();
+                key.glyphs
+                    .extend(glyphs.into_iter().map(|(id, x, y)| (id, x + shift, y)));
+            }
+        }
+        col += run;
+    }
+    let (ligatures, keys) = shaped
+        .into_iter()
+        .map(|(ligature, key)| (ligature, (ligature.col, key)))
+        .unzip();
+    RowLigatures {
+        map: LigatureMap::new(ligatures),
+        keys,
+    }
+}
+
+/// Foreground and background of a cell after bold-brightening and inverse,
+/// plus whether the background is the default one (and needs no quad).
+fn cell_colors(palette: &Palette, cell: &Cell) -> (Rgb, Rgb, bool) {
+    let fg_color = match cell.fg {
+        // Bold text uses the bright variant of the 8 basic colors
+        Color::Indexed(index @ 0..=7) if cell.flags.contains(Flags::BOLD) => {
+            Color::Indexed(index + 8)
+        }
+        color => color,
+    };
+    let fg = palette.resolve(fg_color, palette.foreground);
+    let bg = palette.resolve(cell.bg, palette.background);
+
+    if cell.flags.contains(Flags::INVERSE) {
+        (bg, fg, false)
+    } else {
+        (fg, bg, cell.bg == Color::Default)
+    }
+}
@@ -0,0 +1,296 @@
+//! Smooth Scrolling
+//!
+//! Touchpads report scrolling in pixels, a few at a time. [`SmoothScroll`]
+//! adds them up in fractions of a line and hands out whole lines as they
+//! fill up, so slow movements scroll instead of rounding to nothing. With
+//! momentum (`MouseConfig::touchpad_momentum`) the scrollback keeps gliding
+//! after the fingers lift, slowing down like a flicked list; macOS sends
+//! that glide itself and needs no emulation.
+//!
+//! ```
+//! use adamant::scroll::SmoothScroll;
+//! use web_time::Instant;
+//! use winit::event::TouchPhase;
+//!
+//! let mut scroll = SmoothScroll::new(false);
+//! let now = Instant::now();
+//! assert_eq!(scroll.pixels(0.4, TouchPhase::Moved, now), 0);
+//! assert_eq!(scroll.pixels(0.4, TouchPhase::Moved, now), 0);
+//! assert_eq!(scroll.pixels(0.4, TouchPhase::Moved, now), 1);
+//! ```
+//!
+//! However the view moves, [`ScrollAnimation`] can draw it moving over a
+//! few frames (`Config::scroll_animation`): the rows slide by fractions of
+//! a line until they catch up with the view.
+//!
+//! Dragging a selection past the top or bottom of the terminal scrolls on
+//! its own ([`AutoScroll`]), faster the further the pointer is out.
+
+use web_time::{Duration, Instant};
+use winit::event::TouchPhase;
+
+/// How quickly a glide slows down: its speed falls to 1/e in this time.
+const FRICTION: Duration = Duration::from_millis(325);
+
+/// Glides slower than this, in lines per second, stop.
+const MIN_VELOCITY: f32 = 4.0;
+
+/// Events closer together than this (seconds) count as this far apart
+/// for the speed.
+const MIN_INTERVAL: f32 = 0.004;
+
+/// Fingers resting this long before lifting stopped the movement: no glide.
+const MAX_REST: Duration = Duration::from_millis(50);
+
+/// Lines per second a drag scrolls for each line it is past the edge.
+const AUTO_SCROLL_RATE: f32 = 10.0;
+
+/// The fastest a drag scrolls, in lines per second.
+const MAX_AUTO_SCROLL: f32 = 300.0;
+
+/// Touchpad scrolling in fractions of a line, with optional momentum.
+#[derive(Clone, Debug)]
+pub struct SmoothScroll {
+    momentum: bool,
+    /// Lines moved but not scrolled yet, between -1 and 1
+    remainder: f32,
+    /// Lines per second of the recent movement, or of the glide
+    velocity: f32,
+    /// The last touchpad movement
+    last_moved: Option<Instant>,
+    /// The last step of the running glide
+    gliding: Option<Instant>,
+}
+
+impl SmoothScroll {
+    pub fn new(momentum: bool) -> Self {
+        Self {
+            momentum,
+            remainder: 0.0,
+            velocity: 0.0,
+            last_moved: None,
+            gliding: None,
+        }
+    }
+
+    /// A mouse wheel turned by `lines` (fractions from high-resolution
+    /// wheels add up too). It stops any glide. Returns the whole lines to
+    /// scroll.
+    pub fn lines(&mut self, lines: f32) -> isize {
+        self.stop();
+        self.take(lines)
+    }
+
+    /// The touchpad moved by `lines` (fractional, positive up) at `now`.
+    /// Returns the whole lines to scroll.
+    pub fn pixels(&mut self, lines: f32, phase: TouchPhase, now: Instant) -> isize {
+        match phase {
+            TouchPhase::Started => {
+                self.stop();
+                self.remainder = 0.0;
+            }
+            TouchPhase::Moved => {
+                self.gliding = None;
+                let elapsed = self
+                    .last_moved
+                    .map(|last| now.saturating_duration_since(last));
+                self.velocity = match elapsed {
+                    // Smoothed, since touchpads report unevenly
+                    Some(elapsed) if elapsed <= MAX_REST => {
+                        let seconds = elapsed.as_secs_f32().max(MIN_INTERVAL);
+                        0.6 * (lines / seconds) + 0.4 * self.velocity
+                    }
+                    _ => 0.0,
+                };
+                self.last_moved = Some(now);
+            }
+            TouchPhase::Ended => {
+                let resting = self
+                    .last_moved
+                    .is_none_or(|last| now.saturating_duration_since(last) > MAX_REST);
+                if self.momentum && !resting && self.velocity.abs() >= MIN_VELOCITY {
+                    self.gliding = Some(now);
+                }
+                self.last_moved = None;
+            }
+            TouchPhase::Cancelled => self.stop(),
+        }
+        self.take(lines)
+    }
+
+    /// Whether a glide is running; call [`glide`](Self::glide) every
+    /// frame until it ends.
+    pub fn is_gliding(&self) -> bool {
+        self.gliding.is_some()
+    }
+
+    /// Move the glide on to `now`. Returns the whole lines to scroll.
+    pub fn glide(&mut self, now: Instant) -> isize {
+        let Some(last) = self.gliding else {
+            return 0;
+        };
+        let elapsed = now.saturating_duration_since(last).as_secs_f32();
+        let decay = (-elapsed / FRICTION.as_secs_f32()).exp();
+        // The distance covered while the speed decays
+        let lines = self.velocity * FRICTION.as_secs_f32() * (1.0 - decay);
+        self.velocity *= decay;
+        self.gliding = Some(now);
+        if self.velocity.abs() < MIN_VELOCITY {
+            self.stop();
+        }
+        self.take(lines)
+    }
+
+    /// Stop gliding, e.g. when the scrollback is left.
+    pub fn stop(&mut self) {
+        self.gliding = None;
+        self.velocity = 0.0;
+        self.last_moved = None;
+    }
+
+    /// Add `lines` and take out the whole ones.
+    fn take(&mut self, lines: f32) -> isize {
+        self.remainder += lines;
+        let whole = self.remainder.trunc();
+        self.remainder -= whole;
+        whole as isize
+    }
+}
+
+/// Scrolling drawn over a few frames instead of in whole-line jumps
+/// (`Config::scroll_animation`). Each jump of the view adds to how far
+/// the drawn rows lag behind it, and the lag eases out to nothing over
+/// the duration:
+///
+/// ```
+/// use adamant::scroll::ScrollAnimation;
+/// use web_time::{Duration, Instant};
+///
+/// let mut animation = ScrollAnimation::new(Duration::from_millis(100));
+/// let start = Instant::now();
+/// // Three lines back into the scrollback
+/// animation.jump(3.0, 50.0, start);
+/// assert_eq!(animation.lag(start), 3.0);
+/// assert!(animation.lag(start + Duration::from_millis(50)) < 1.0);
+/// assert_eq!(animation.lag(start + Duration::from_millis(100)), 0.0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ScrollAnimation {
+    duration: Duration,
+    /// The lag when the last jump happened, and when that was
+    from: f32,
+    start: Option<Instant>,
+}
+
+impl ScrollAnimation {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            from: 0.0,
+            start: None,
+        }
+    }
+
+    /// The view moved by `lines` at `now` (positive: towards older
+    /// lines). The drawn rows start from where they are and catch up; they
+    /// lag at most `limit` lines, so long jumps only animate the end.
+    pub fn jump(&mut self, lines: f32, limit: f32, now: Instant) {
+        let lag = (self.lag(now) + lines).clamp(-limit, limit);
+        if self.duration.is_zero() || lag == 0.0 {
+            return self.stop();
+        }
+        self.from = lag;
+        self.start = Some(now);
+    }
+
+    /// How many lines the drawn rows lag behind the view at `now`:
+    /// positive while they show older lines than the view does.
+    pub fn lag(&self, now: Instant) -> f32 {
+        let Some(start) = self.start else {
+            return 0.0;
+        };
+        let t = now.saturating_duration_since(start).as_secs_f32() / self.duration.as_secs_f32();
+        if t >= 1.0 {
+            return 0.0;
+        }
+        // Ease out: fast first, settling gently
+        self.from * (1.0 - t).powi(3)
+    }
+
+    /// Whether the rows are still catching up at `now`.
+    pub fn is_running(&self, now: Instant) -> bool {
+        self.start
+            .is_some_and(|start| now.saturating_duration_since(start) < self.duration)
+    }
+
+    /// Show the view as it is, right away.
+    pub fn stop(&mut self) {
+        self.from = 0.0;
+        self.start = None;
+    }
+}
+
+/// Scrolling while a drag is past the edge of the terminal, at a speed
+/// proportional to how far past it is:
+///
+/// ```
+/// use adamant::scroll::AutoScroll;
+/// use web_time::{Duration, Instant};
+///
+/// let mut scroll = AutoScroll::default();
+/// let start = Instant::now();
+/// // A line above the top: 20 lines per second into the scrollback
+/// scroll.drag(1.0, start);
+/// assert_eq!(scroll.step(start + Duration::from_millis(500)), 10);
+/// // Back inside
+/// scroll.drag(0.0, start);
+/// assert!(!scroll.is_running());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct AutoScroll {
+    /// Lines per second, positive towards older lines
+    speed: f32,
+    /// Lines moved but not scrolled yet, between -1 and 1
+    remainder: f32,
+    /// The last step
+    last: Option<Instant>,
+}
+
+impl AutoScroll {
+    /// The drag is `overshoot` lines above the top (negative: below the
+    /// bottom) at `now`; 0 inside the terminal stops scrolling.
+    pub fn drag(&mut self, overshoot: f32, now: Instant) {
+        if overshoot == 0.0 {
+            return self.stop();
+        }
+        let speed = ((overshoot.abs() + 1.0) * AUTO_SCROLL_RATE).min(MAX_AUTO_SCROLL);
+        self.speed = speed.copysign(overshoot);
+        self.last.get_or_insert(now);
+    }
+
+    /// Whether the drag is past the edge; call [`step`](Self::step) every
+    /// frame while it is.
+    pub fn is_running(&self) -> bool {
+        self.last.is_some()
+    }
+
+    /// Move on to `now`. Returns the whole lines to scroll.
+    pub fn step(&mut self, now: Instant) -> isize {
+        let Some(last) = self.last else {
+            return 0;
+        };
+        let elapsed = now.saturating_duration_since(last).as_secs_f32();
+        self.last = Some(now);
+        self.remainder += self.speed * elapsed;
+        let whole = self.remainder.trunc();
+        self.remainder -= whole;
+        whole as isize
+    }
+
+    /// Stop scrolling, e.g. when the button is released.
+    pub fn stop(&mut self) {
+        self.speed = 0.0;
+        self.remainder = 0.0;
+        self.last = None;
+    }
+}
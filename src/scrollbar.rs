@@ -0,0 +1,142 @@
+//! Scrollbar
+//!
+//! A thin bar over the right edge of the terminal showing where the view
+//! is in the scrollback (`Config::scrollbar`). The thumb can be dragged
+//! with the mouse, and clicking the track jumps there. The lines commands
+//! were typed on, as reported by shell integration (OSC 133), are marked
+//! along the bar, failed ones in another color; clicking a mark scrolls
+//! its command to the top of the view. The lines of the current search's
+//! matches are marked too.
+//!
+//! Positions along the bar are fractions of its height, 0 at the top:
+//!
+//! ```
+//! use adamant::grid::Grid;
+//! use adamant::scrollbar::Scrollbar;
+//!
+//! let mut grid = Grid::new(10, 25, 1000);
+//! for _ in 0..3 {
+//!     grid.scroll_up(25);
+//! }
+//! // 100 lines, the last 25 in view
+//! let scrollbar = Scrollbar::new(&grid).unwrap();
+//! assert_eq!(scrollbar.thumb(), (0.75, 1.0));
+//! // Dragging the thumb to the top shows the oldest lines
+//! assert_eq!(scrollbar.offset_at(0.0), 75);
+//! ```
+
+use crate::grid::{CommandStatus, Grid, SearchMatch};
+
+/// The thumb is at least this much of the bar, however long the
+/// scrollback.
+const MIN_THUMB: f32 = 0.04;
+
+/// The view's place in the scrollback, and the lines to mark.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scrollbar {
+    /// Lines in all: scrollback and screen
+    total: usize,
+    rows: usize,
+    /// The first line in view
+    top: usize,
+    /// Lines commands were typed on, and how they ended
+    commands: Vec<(usize, CommandStatus)>,
+    /// Lines with search matches
+    matches: Vec<usize>,
+}
+
+impl Scrollbar {
+    /// The scrollbar of `grid`; `None` without scrollback (the alternate
+    /// screen, or right after starting).
+    pub fn new(grid: &Grid) -> Option<Self> {
+        if grid.history_len() == 0 {
+            return None;
+        }
+        let commands = (0..grid.total_lines())
+            .filter_map(|line| Some((line, grid.line(line).command?)))
+            .collect();
+        Some(Self {
+            total: grid.total_lines(),
+            rows: grid.rows(),
+            top: grid.visible_line_index(0),
+            commands,
+            matches: Vec::new(),
+        })
+    }
+
+    /// Mark the lines of `matches` (see [`Grid::search`]).
+    pub fn with_matches(mut self, matches: &[SearchMatch]) -> Self {
+        self.matches = matches.iter().map(|found| found.line).collect();
+        self.matches.dedup();
+        self
+    }
+
+    /// Where the thumb starts and ends.
+    pub fn thumb(&self) -> (f32, f32) {
+        let length = self.thumb_length();
+        let history = (self.total - self.rows) as f32;
+        let start = self.top as f32 / history * (1.0 - length);
+        (start, start + length)
+    }
+
+    /// The display offset (lines scrolled back) that puts the thumb's top
+    /// at `start`.
+    pub fn offset_at(&self, start: f32) -> usize {
+        let history = self.total - self.rows;
+        let fraction = (start / (1.0 - self.thumb_length())).clamp(0.0, 1.0);
+        let top = (fraction * history as f32).round() as usize;
+        history - top.min(history)
+    }
+
+    /// Where `line` (an absolute line index) is along the bar.
+    pub fn position(&self, line: usize) -> f32 {
+        (line as f32 + 0.5) / self.total as f32
+    }
+
+    /// Where the failed commands are along the bar.
+    pub fn failed(&self) -> impl Iterator<Item = f32> + '_ {
+        self.commands()
+            .filter(|&(_, status)| status == CommandStatus::Failed)
+            .map(|(position, _)| position)
+    }
+
+    /// Where each command is along the bar, and how it ended.
+    pub fn commands(&self) -> impl Iterator<Item = (f32, CommandStatus)> + '_ {
+        self.commands
+            .iter()
+            .map(|&(line, status)| (self.position(line), status))
+    }
+
+    /// Where the lines with search matches are along the bar.
+    pub fn matches(&self) -> impl Iterator<Item = f32> + '_ {
+        self.matches.iter().map(|&line| self.position(line))
+    }
+
+    /// The line of the command marked closest to `y`, if one is at most
+    /// `reach` away.
+    pub fn command_near(&self, y: f32, reach: f32) -> Option<usize> {
+        self.commands
+            .iter()
+            .map(|&(line, _)| (line, (self.position(line) - y).abs()))
+            .filter(|&(_, distance)| distance <= reach)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(line, _)| line)
+    }
+
+    /// The display offset that shows `line` at the top, or as close to it
+    /// as the scrollback goes.
+    pub fn offset_showing(&self, line: usize) -> usize {
+        let history = self.total - self.rows;
+        history - line.min(history)
+    }
+
+    fn thumb_length(&self) -> f32 {
+        (self.rows as f32 / self.total as f32).clamp(MIN_THUMB, 1.0)
+    }
+}
+
+/// Width of the bar in pixels, for cells `cell_width` pixels wide: half a
+/// cell.
+pub fn width(cell_width: f32) -> f32 {
+    (cell_width * 0.5).round().max(2.0)
+}
@@ -0,0 +1,98 @@
+//! Secure Keyboard Entry
+//!
+//! On macOS, other processes can read keystrokes through event taps.
+//! Secure keyboard entry (`EnableSecureEventInput`) shuts them out while a
+//! password is typed. The system only allows it for the active
+//! application, and leaving it on would block other applications' hotkeys
+//! and input methods, so [`SecureInput`] keeps it on only while the
+//! window has focus, and turns it back on when the focus returns.
+//!
+//! ```
+//! use adamant::secure_input::SecureInput;
+//!
+//! let mut secure = SecureInput::default();
+//! secure.toggle(true);
+//! assert!(secure.enabled() && secure.active());
+//! secure.set_focused(false);
+//! assert!(secure.enabled() && !secure.active());
+//! ```
+//!
+//! Elsewhere the state is tracked the same way but nothing is protected;
+//! check [`SecureInput::SUPPORTED`] before offering it.
+
+/// The secure keyboard entry toggle of a window.
+#[derive(Debug, Default)]
+pub struct SecureInput {
+    /// Turned on by the user
+    enabled: bool,
+    /// Requested from the system (enabled and focused)
+    active: bool,
+}
+
+impl SecureInput {
+    /// Whether the platform has secure keyboard entry.
+    pub const SUPPORTED: bool = cfg!(target_os = "macos");
+
+    /// Whether the user turned it on.
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Whether keystrokes are protected right now.
+    pub fn active(&self) -> bool {
+        self.active
+    }
+
+    /// Turn it on or off; `focused` is whether the window has focus.
+    /// Returns whether it is on now.
+    pub fn toggle(&mut self, focused: bool) -> bool {
+        self.enabled = !self.enabled;
+        self.set_focused(focused);
+        self.enabled
+    }
+
+    /// Follow the window's focus.
+    pub fn set_focused(&mut self, focused: bool) {
+        let active = self.enabled && focused;
+        if active != self.active {
+            self.active = active;
+            platform::set_active(active);
+        }
+    }
+}
+
+impl Drop for SecureInput {
+    fn drop(&mut self) {
+        // The system counts the calls; leave it balanced
+        self.set_focused(false);
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    #[link(name = "Carbon", kind = "framework")]
+    unsafe extern "C" {
+        fn EnableSecureEventInput() -> i32;
+        fn DisableSecureEventInput() -> i32;
+    }
+
+    pub fn set_active(active: bool) {
+        // SAFETY: Both take no arguments and only change a per-process
+        // counter in the window server.
+        let status = unsafe {
+            if active {
+                EnableSecureEventInput()
+            } else {
+                DisableSecureEventInput()
+            }
+        };
+        if status != 0 {
+            log::warn!("Secure keyboard entry failed with status {}", status);
+        }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    pub fn set_active(_active: bool) {}
+}
@@ -0,0 +1,416 @@
+//! Detachable Sessions
+//!
+//! `adamant --session <name>` keeps the shell in a background process, the
+//! session server (`adamant --server <name>`), instead of the window. The
+//! server owns the PTY and a [`Terminal`] fed with everything the programs
+//! write; windows attach to it over a Unix socket
+//! (`$XDG_RUNTIME_DIR/adamant-session-<name>.sock`, or in a directory of
+//! the user's own in the temp dir) through a
+//! [`SessionBackend`](crate::backend::SessionBackend). Closing the window
+//! only detaches: the shell keeps running, and the next window attaching
+//! gets the screen and scrollback as a [`Terminal::snapshot`]. The server
+//! exits with its shell.
+//!
+//! One window is attached at a time; a new one takes the session over,
+//! like `tmux attach -d`. The connection carries [`Frame`]s: a kind byte,
+//! a little-endian `u32` length and the payload. A window starts with a
+//! `Resize` frame, so the snapshot fits its grid:
+//!
+//! ```
+//! use adamant::session::Frame;
+//!
+//! let frame = Frame::Resize { cols: 80, rows: 24 };
+//! let bytes = frame.encode();
+//! assert_eq!(bytes, [2, 4, 0, 0, 0, 80, 0, 24, 0]);
+//! assert_eq!(Frame::read(&mut &bytes[..]).unwrap(), Some(frame));
+//! ```
+
+use std::io::{self, Read, Write};
+use std::os::unix::fs::MetadataExt;
+#[cfg(feature = "pty")]
+use std::os::unix::net::UnixListener;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "pty")]
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[cfg(feature = "pty")]
+use crate::config::Config;
+use crate::error::{AdamantError, Result};
+#[cfg(feature = "pty")]
+use crate::pty::Pty;
+#[cfg(feature = "pty")]
+use crate::term::Terminal;
+
+/// Largest frame payload accepted, against a confused peer.
+const MAX_FRAME_LEN: usize = 16 << 20;
+
+/// How often and how long [`start`] looks for the new server's socket.
+const START_POLLS: u32 = 250;
+const START_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// A message between a session server and the window attached to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Frame {
+    /// Server to window: bytes for the terminal
+    Output(Vec<u8>),
+    /// Window to server: keystrokes and replies for the program
+    Input(Vec<u8>),
+    /// Window to server: the grid size, first on every connection
+    Resize { cols: u16, rows: u16 },
+    /// Server to window: the shell exited, with its exit code if known
+    Exited(Option<u32>),
+}
+
+impl Frame {
+    /// The frame as sent over the socket.
+    pub fn encode(&self) -> Vec<u8> {
+        let (kind, payload) = match self {
+            Frame::Output(bytes) => (0, bytes.clone()),
+            Frame::Input(bytes) => (1, bytes.clone()),
+            Frame::Resize { cols, rows } => (2, [cols.to_le_bytes(), rows.to_le_bytes()].concat()),
+            Frame::Exited(code) => (
+                3,
+                code.map_or(Vec::new(), |code| code.to_le_bytes().to_vec()),
+            ),
+        };
+        let mut bytes = Vec::with_capacity(5 + payload.len());
+        bytes.push(kind);
+        bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        bytes.extend(payload);
+        bytes
+    }
+
+    /// Read the next frame, or `None` at the end of the stream.
+    pub fn read(reader: &mut impl Read) -> io::Result<Option<Self>> {
+        let mut header = [0u8; 5];
+        match reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        let len = u32::from_le_bytes([header[1], header[2], header[3], header[4]]) as usize;
+        if len > MAX_FRAME_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "session frame too long",
+            ));
+        }
+        let mut payload = vec![0u8; len];
+        reader.read_exact(&mut payload)?;
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid session frame");
+        Ok(Some(match (header[0], &payload[..]) {
+            (0, _) => Frame::Output(payload),
+            (1, _) => Frame::Input(payload),
+            (2, &[c0, c1, r0, r1]) => Frame::Resize {
+                cols: u16::from_le_bytes([c0, c1]),
+                rows: u16::from_le_bytes([r0, r1]),
+            },
+            (3, &[]) => Frame::Exited(None),
+            (3, &[a, b, c, d]) => Frame::Exited(Some(u32::from_le_bytes([a, b, c, d]))),
+            _ => return Err(invalid()),
+        }))
+    }
+
+    /// Send the frame.
+    pub fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(&self.encode())?;
+        writer.flush()
+    }
+}
+
+/// Where the session `name` listens.
+pub fn socket_path(name: &str) -> PathBuf {
+    runtime_dir().join(format!("adamant-session-{}.sock", name))
+}
+
+/// Connect to the server of the session `name`. The socket and the
+/// directory holding it must be this user's: a socket someone else put
+/// there under the session's name would get every keystroke.
+pub fn connect(name: &str) -> io::Result<UnixStream> {
+    let path = socket_path(name);
+    check_owner(&runtime_dir())?;
+    check_owner(&path)?;
+    UnixStream::connect(path)
+}
+
+/// Whether a server for the session `name` is running.
+pub fn is_running(name: &str) -> bool {
+    connect(name).is_ok()
+}
+
+/// The names of the running sessions, sorted.
+pub fn list() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(runtime_dir()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let file = entry.file_name().into_string().ok()?;
+            let name = file
+                .strip_prefix("adamant-session-")?
+                .strip_suffix(".sock")?;
+            is_running(name).then(|| name.to_string())
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+/// Check that `name` can be part of a socket file name.
+pub fn check_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && !name.starts_with('.');
+    if valid {
+        Ok(())
+    } else {
+        Err(AdamantError::Config(format!(
+            "session names are letters, digits, '-', '_' and '.', got {:?}",
+            name
+        )))
+    }
+}
+
+/// Start the server of the session `name` in the background, running
+/// this executable with `args` (which include `--server <name>`), and
+/// wait until it listens. The server gets its own process group, so it
+/// outlives the window and Ctrl+C in the shell that started it.
+pub fn start(name: &str, args: &[String]) -> Result<()> {
+    use std::os::unix::process::CommandExt;
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new(std::env::current_exe()?)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()?;
+    for _ in 0..START_POLLS {
+        if is_running(name) {
+            // Reap it when it exits
+            std::thread::spawn(move || child.wait());
+            return Ok(());
+        }
+        if let Ok(Some(status)) = child.try_wait() {
+            return Err(AdamantError::Backend(format!(
+                "the server of session {:?} exited with {}",
+                name, status
+            )));
+        }
+        std::thread::sleep(START_POLL_INTERVAL);
+    }
+    Err(AdamantError::Backend(format!(
+        "the server of session {:?} didn't start listening",
+        name
+    )))
+}
+
+/// `$XDG_RUNTIME_DIR`, or `adamant-<uid>` in the temp dir without one.
+fn runtime_dir() -> PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| std::env::temp_dir().join(format!("adamant-{}", uid())))
+}
+
+fn uid() -> u32 {
+    // SAFETY: getuid has no preconditions and can't fail
+    unsafe { libc::getuid() }
+}
+
+/// Fail unless `path` (not following links) belongs to this user, and a
+/// directory can't be written to by others, who could swap its sockets.
+fn check_owner(path: &Path) -> io::Result<()> {
+    let metadata = std::fs::symlink_metadata(path)?;
+    let shared = metadata.is_dir() && metadata.mode() & 0o022 != 0;
+    if metadata.uid() != uid() || shared {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} isn't private to this user", path.display()),
+        ));
+    }
+    Ok(())
+}
+
+/// What the server's threads share.
+#[cfg(feature = "pty")]
+struct Server {
+    pty: Pty,
+    terminal: Terminal,
+    /// The attached window, and its number to tell it from later ones
+    client: Option<(u64, UnixStream)>,
+    clients: u64,
+}
+
+#[cfg(feature = "pty")]
+impl Server {
+    /// Send `frame` to the attached window; a window that can't take it is
+    /// detached.
+    fn send(&mut self, frame: &Frame) {
+        if let Some((_, stream)) = &mut self.client
+            && frame.write(stream).is_err()
+        {
+            self.client = None;
+        }
+    }
+
+    fn resize(&mut self, cols: u16, rows: u16) {
+        let (cols, rows) = (cols.max(1), rows.max(1));
+        if let Err(e) = self.pty.resize(cols, rows) {
+            log::warn!("Failed to resize PTY: {}", e);
+        }
+        self.terminal.resize(cols as usize, rows as usize);
+    }
+}
+
+/// Run the session `name`: start the shell, or `command` instead, and
+/// serve windows until it exits. Blocks; this is all an `adamant --server`
+/// process does.
+#[cfg(feature = "pty")]
+pub fn serve(name: &str, config: &Config, command: Option<&[String]>) -> Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+
+    check_name(name)?;
+    let path = socket_path(name);
+    if is_running(name) {
+        return Err(AdamantError::Config(format!(
+            "session {:?} is already running",
+            name
+        )));
+    }
+    let dir = runtime_dir();
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&dir)?;
+    check_owner(&dir)?;
+    // Left behind by a server that didn't get to clean up
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)?;
+    let socket = SocketFile(path);
+
+    let (cols, rows) = (80, 24);
+    let pty = match command {
+        Some([program, args @ ..]) => Pty::spawn_program(program, args, &config.shell, cols, rows)?,
+        _ => Pty::spawn(&config.shell, cols, rows)?,
+    };
+    let mut reader = pty.reader()?;
+    let mut terminal = Terminal::new(cols as usize, rows as usize, config.scrollback_lines);
+    terminal.set_scrollback_options(crate::term::ScrollbackOptions {
+        erase_saved_lines: config.erase_saved_lines,
+        save_alt_screen_lines: config.save_alt_screen_lines,
+    });
+    let server = Arc::new(Mutex::new(Server {
+        pty,
+        terminal,
+        client: None,
+        clients: 0,
+    }));
+    log::info!("Session {:?} listening on {}", name, socket.0.display());
+
+    let accepting = Arc::clone(&server);
+    std::thread::Builder::new()
+        .name("session-listener".into())
+        .spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let server = Arc::clone(&accepting);
+                let _ = std::thread::Builder::new()
+                    .name("session-client".into())
+                    .spawn(move || attach(&server, stream));
+            }
+        })?;
+
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                let mut server = lock(&server);
+                server.terminal.feed(&buf[..n]);
+                // The attached window answers queries itself
+                server.terminal.take_responses();
+                server.terminal.take_events();
+                server.send(&Frame::Output(buf[..n].to_vec()));
+            }
+        }
+    }
+    let mut server = lock(&server);
+    let code = server.pty.exit_code();
+    log::info!("Session {:?} ended with {:?}", name, code);
+    server.send(&Frame::Exited(code));
+    Ok(())
+}
+
+/// Serve one window: hand it the session, then pass its input on until it
+/// disconnects or another window takes over.
+#[cfg(feature = "pty")]
+fn attach(server: &Mutex<Server>, mut stream: UnixStream) {
+    let Ok(Some(Frame::Resize { cols, rows })) = Frame::read(&mut stream) else {
+        return log::debug!("A session client didn't start with its size");
+    };
+    let Ok(writer) = stream.try_clone() else {
+        return;
+    };
+    let id = {
+        let mut server = lock(server);
+        if let Some((_, previous)) = server.client.take() {
+            let _ = previous.shutdown(std::net::Shutdown::Both);
+        }
+        server.resize(cols, rows);
+        server.clients += 1;
+        let id = server.clients;
+        server.client = Some((id, writer));
+        let snapshot = Frame::Output(server.terminal.snapshot());
+        server.send(&snapshot);
+        id
+    };
+    log::info!("Window attached ({}x{})", cols, rows);
+
+    loop {
+        let frame = Frame::read(&mut stream);
+        let mut server = lock(server);
+        if server
+            .client
+            .as_ref()
+            .is_none_or(|(current, _)| *current != id)
+        {
+            return;
+        }
+        match frame {
+            Ok(Some(Frame::Input(bytes))) => {
+                if let Err(e) = server.pty.write(&bytes) {
+                    log::warn!("Failed to write to PTY: {}", e);
+                }
+            }
+            Ok(Some(Frame::Resize { cols, rows })) => server.resize(cols, rows),
+            Ok(Some(frame)) => log::debug!("Unexpected frame from a window: {:?}", frame),
+            Ok(None) | Err(_) => {
+                log::info!("Window detached");
+                server.client = None;
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "pty")]
+fn lock(server: &Mutex<Server>) -> std::sync::MutexGuard<'_, Server> {
+    server.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The server's socket; removed from the filesystem when dropped.
+#[cfg(feature = "pty")]
+struct SocketFile(PathBuf);
+
+#[cfg(feature = "pty")]
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
@@ -0,0 +1,268 @@
+//! Status Bar
+//!
+//! The row `Config::status_bar` adds above or below the terminal. The
+//! terminal gets one row less, so the bar never hides output; it is drawn
+//! like the other overlays, as a [`LayerKind::Status`] layer.
+//!
+//! [`StatusBar::refresh`] is called on every update. What the shell reports
+//! (title, directory, host) shows right away; the clock and programs are
+//! refreshed every `interval_ms`. Programs run in the background and show
+//! their output once they finish, so a slow one never holds up a frame.
+//!
+//! ```
+//! use adamant::config::{StatusBarConfig, StatusBarPosition, StatusSegment};
+//! use adamant::status_bar::StatusBar;
+//! use adamant::term::Terminal;
+//! use web_time::Instant;
+//!
+//! let config = StatusBarConfig {
+//!     position: Some(StatusBarPosition::Bottom),
+//!     segments: vec![StatusSegment::Text("adamant"), StatusSegment::Title],
+//!     ..StatusBarConfig::default()
+//! };
+//! let mut bar = StatusBar::new(&config).unwrap().unwrap();
+//! let mut term = Terminal::new(80, 24, 100);
+//! term.feed(b"\x1b]2;vim\x07");
+//! assert!(bar.refresh(&term, Instant::now()));
+//! assert_eq!(bar.text(), "adamant | vim");
+//! ```
+
+use std::sync::mpsc::Receiver;
+
+use web_time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::config::{StatusBarConfig, StatusBarPosition, StatusSegment};
+use crate::error::Result;
+use crate::grid::Cell;
+use crate::term::Terminal;
+use crate::ui::{Element, Layer, LayerKind, Rect, Style, Ui};
+
+/// Asks Git for the branch checked out in the working directory.
+const GIT_BRANCH: &[&str] = &["git", "rev-parse", "--abbrev-ref", "HEAD"];
+
+/// The segments' texts, and the programs running for them.
+pub struct StatusBar {
+    position: StatusBarPosition,
+    segments: Vec<StatusSegment>,
+    separator: &'static str,
+    interval: Duration,
+    /// What each segment shows
+    texts: Vec<String>,
+    /// Programs started for segments, by segment
+    running: Vec<Option<Receiver<String>>>,
+    /// When the clock and programs are refreshed next
+    next_refresh: Option<Instant>,
+    /// Title, directory and host the texts were made from
+    reported: [Option<String>; 3],
+    /// This machine's name, for `Hostname` until the shell reports one
+    local_host: Option<String>,
+}
+
+impl StatusBar {
+    /// The bar `config` asks for, `None` without a position.
+    pub fn new(config: &StatusBarConfig) -> Result<Option<Self>> {
+        let Some(position) = config.position else {
+            return Ok(None);
+        };
+        #[cfg(target_arch = "wasm32")]
+        for segment in &config.segments {
+            if matches!(
+                segment,
+                StatusSegment::GitBranch | StatusSegment::Command(_)
+            ) {
+                log::warn!("{:?} is not supported in the browser yet", segment);
+            }
+        }
+        let count = config.segments.len();
+        Ok(Some(Self {
+            position,
+            segments: config.segments.clone(),
+            separator: config.separator,
+            interval: Duration::from_millis(config.interval_ms),
+            texts: vec![String::new(); count],
+            running: (0..count).map(|_| None).collect(),
+            next_refresh: None,
+            reported: [None, None, None],
+            local_host: local_host(),
+        }))
+    }
+
+    pub fn position(&self) -> StatusBarPosition {
+        self.position
+    }
+
+    /// Bring the segments up to date with `terminal`. Returns `true` if
+    /// the text changed.
+    pub fn refresh(&mut self, terminal: &Terminal, now: Instant) -> bool {
+        let before = self.text();
+
+        for (text, running) in self.texts.iter_mut().zip(&mut self.running) {
+            if let Some(output) = running.as_ref().and_then(|output| output.try_recv().ok()) {
+                *text = output;
+                *running = None;
+            }
+        }
+
+        let reported = [terminal.title(), terminal.directory(), terminal.host()]
+            .map(|value| value.map(str::to_string));
+        let moved = reported[1] != self.reported[1];
+        let tick = self.next_refresh.is_none_or(|next| now >= next);
+        if tick {
+            self.next_refresh = Some(now + self.interval);
+        }
+        if tick || reported != self.reported {
+            self.reported = reported;
+            for i in 0..self.segments.len() {
+                self.refresh_segment(i, tick || moved);
+            }
+        }
+
+        self.text() != before
+    }
+
+    /// Update segment `i`; programs are only started again on `rerun`.
+    fn refresh_segment(&mut self, i: usize, rerun: bool) {
+        let [title, directory, host] = &self.reported;
+        let text = match self.segments[i] {
+            StatusSegment::Cwd => directory.as_deref().map(shorten_home),
+            StatusSegment::Hostname => host.clone().or_else(|| self.local_host.clone()),
+            StatusSegment::Title => title.clone(),
+            StatusSegment::Clock { utc_offset_minutes } => Some(clock(utc_offset_minutes)),
+            StatusSegment::Text(text) => Some(text.to_string()),
+            StatusSegment::GitBranch | StatusSegment::Command(_) if !rerun => return,
+            StatusSegment::GitBranch => {
+                // Only where the directory is on this machine
+                match directory.as_deref().filter(|dir| is_local_dir(dir)) {
+                    Some(dir) => {
+                        self.start(i, GIT_BRANCH, Some(dir.to_string()));
+                        return;
+                    }
+                    None => None,
+                }
+            }
+            StatusSegment::Command(command) => {
+                let dir = directory.clone().filter(|dir| is_local_dir(dir));
+                self.start(i, command, dir);
+                return;
+            }
+        };
+        self.texts[i] = text.unwrap_or_default();
+    }
+
+    /// Run `command` for segment `i`, unless it still runs from last time.
+    fn start(&mut self, i: usize, command: &'static [&'static str], dir: Option<String>) {
+        if self.running[i].is_none() {
+            self.running[i] = run(command, dir);
+        }
+    }
+
+    /// The segments with something to show, separated.
+    pub fn text(&self) -> String {
+        self.texts
+            .iter()
+            .filter(|text| !text.is_empty())
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(self.separator)
+    }
+
+    /// The bar as a row of `cols` cells.
+    pub fn cells(&self, cols: usize) -> Vec<Cell> {
+        let mut ui = Ui::default();
+        let bar = Rect::new(0, 0, 1, cols);
+        let text = format!(" {}", self.text());
+        let element = Element::label(bar, text, Style::PANEL);
+        ui.push(Layer::new(LayerKind::Status, vec![element]));
+        let mut cells = ui.cells(1, cols);
+        (0..cols)
+            .map(|col| cells.remove(&(0, col)).unwrap_or_default())
+            .collect()
+    }
+}
+
+/// `dir` with the home directory replaced by `~`.
+fn shorten_home(dir: &str) -> String {
+    let home = std::env::var("HOME").unwrap_or_default();
+    match dir.strip_prefix(home.as_str()) {
+        Some(rest) if !home.is_empty() && (rest.is_empty() || rest.starts_with('/')) => {
+            format!("~{}", rest)
+        }
+        _ => dir.to_string(),
+    }
+}
+
+/// `HH:MM`, `utc_offset_minutes` ahead of UTC.
+fn clock(utc_offset_minutes: i32) -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() as i64);
+    let minutes = (seconds / 60 + utc_offset_minutes as i64).rem_euclid(24 * 60);
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn is_local_dir(dir: &str) -> bool {
+    std::path::Path::new(dir).is_dir()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn is_local_dir(_dir: &str) -> bool {
+    false
+}
+
+/// This machine's name, from the environment or `/etc/hostname`.
+fn local_host() -> Option<String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let name = std::env::var("HOSTNAME")
+            .or_else(|_| std::env::var("COMPUTERNAME"))
+            .or_else(|_| std::fs::read_to_string("/etc/hostname"))
+            .ok()?;
+        let name = name.trim();
+        (!name.is_empty()).then(|| name.to_string())
+    }
+    #[cfg(target_arch = "wasm32")]
+    None
+}
+
+/// Run `command` in `dir` on a thread of its own; its first line of
+/// output arrives on the returned channel. Empty if it fails, nothing
+/// for an empty command.
+#[cfg(not(target_arch = "wasm32"))]
+fn run(command: &'static [&'static str], dir: Option<String>) -> Option<Receiver<String>> {
+    use std::process::{Command, Stdio};
+    use std::sync::mpsc;
+
+    let (program, args) = command.split_first()?;
+    let (sender, receiver) = mpsc::channel();
+    let mut program = Command::new(program);
+    program
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null());
+    if let Some(dir) = dir {
+        program.current_dir(dir);
+    }
+    drop(std::thread::spawn(move || {
+        let text = match program.output() {
+            Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .next()
+                .unwrap_or("")
+                .trim()
+                .to_string(),
+            Ok(_) => String::new(),
+            Err(e) => {
+                log::debug!("Failed to run {:?}: {}", program.get_program(), e);
+                String::new()
+            }
+        };
+        let _ = sender.send(text);
+    }));
+    Some(receiver)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn run(_command: &'static [&'static str], _dir: Option<String>) -> Option<Receiver<String>> {
+    None
+}
@@ -0,0 +1,325 @@
+//! Styled Copy
+//!
+//! Copies carry the selected text's colors, bold, italics, underline and
+//! strikeout along with the plain text (`Config::copy_styled`), so pasting
+//! into a document, a chat or an email keeps the terminal's look. Every
+//! platform gets HTML; macOS and Windows also get RTF, which word
+//! processors prefer ([`add_rtf`]).
+//!
+//! ```
+//! use adamant::config::Rgb;
+//! use adamant::grid::{CopyOptions, Point, Selection};
+//! use adamant::styled_copy::StyledText;
+//! use adamant::term::Terminal;
+//!
+//! let mut term = Terminal::new(20, 2, 0);
+//! term.feed(b"\x1b[1;31merror\x1b[m: <oops>");
+//! let mut selection = Selection::new(Point::new(0, 0));
+//! selection.update(Point::new(0, 19));
+//!
+//! let (white, black, red) = (Rgb(255, 255, 255), Rgb(0, 0, 0), Rgb(255, 0, 0));
+//! let colors = |cell: &adamant::grid::Cell| {
+//!     let fg = if cell.fg == adamant::grid::Color::Default { white } else { red };
+//!     (fg, black)
+//! };
+//! let options = CopyOptions::default();
+//! let styled =
+//!     StyledText::from_selection(term.grid(), &selection, options, (white, black), colors);
+//! assert_eq!(
+//!     styled.html(),
+//!     "<pre style=\"color:#ffffff;background-color:#000000;font-family:monospace\">\
+//!      <span style=\"color:#ff0000;font-weight:bold\">error</span>: &lt;oops&gt;</pre>"
+//! );
+//! ```
+
+use crate::config::Rgb;
+use crate::grid::{Cell, CopyOptions, Flags, Grid, Selection};
+
+/// The attributes kept in a copy.
+const STYLE_FLAGS: Flags = Flags::BOLD
+    .union(Flags::ITALIC)
+    .union(Flags::UNDERLINE)
+    .union(Flags::STRIKEOUT);
+
+/// A run of text in one style.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub fg: Rgb,
+    pub bg: Rgb,
+    /// Bold, italic, underline and strikeout
+    pub flags: Flags,
+}
+
+/// Selected text as styled spans, line by line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StyledText {
+    pub lines: Vec<Vec<Span>>,
+    pub foreground: Rgb,
+    pub background: Rgb,
+}
+
+impl StyledText {
+    /// The cells `selection` covers in `grid`, split into lines like
+    /// [`Grid::selection_text_with`] does with `options`. `colors` gives a cell's foreground and
+    /// background as drawn, next to the terminal's default pair.
+    pub fn from_selection(
+        grid: &Grid,
+        selection: &Selection,
+        options: CopyOptions,
+        (foreground, background): (Rgb, Rgb),
+        colors: impl Fn(&Cell) -> (Rgb, Rgb),
+    ) -> Self {
+        let (start, end) = selection.range();
+        let last = grid.total_lines().saturating_sub(1);
+        let mut lines = Vec::new();
+        let mut cells: Vec<&Cell> = Vec::new();
+
+        for line in start.line.min(last)..=end.line.min(last) {
+            let row = grid.line(line);
+            let (from, to) = selection.cols_on(line, grid.cols());
+            cells.extend(
+                row.cells()[from.min(to)..to]
+                    .iter()
+                    .filter(|cell| !cell.flags.contains(Flags::WIDE_SPACER)),
+            );
+            if row.wrapped
+                && options.join_wrapped_lines
+                && !selection.is_block()
+                && line != end.line
+            {
+                continue;
+            }
+            while options.trim_trailing_whitespace
+                && cells.last().is_some_and(|cell| cell.c.is_whitespace())
+            {
+                cells.pop();
+            }
+            lines.push(spans(&cells, &colors));
+            cells.clear();
+        }
+        Self {
+            lines,
+            foreground,
+            background,
+        }
+    }
+
+    /// An HTML `<pre>` block with a `<span>` for each styled run.
+    pub fn html(&self) -> String {
+        let mut html = format!(
+            "<pre style=\"color:{};background-color:{};font-family:monospace\">",
+            hex(self.foreground),
+            hex(self.background)
+        );
+        for (i, line) in self.lines.iter().enumerate() {
+            if i > 0 {
+                html.push('\n');
+            }
+            for span in line {
+                let style = self.css(span);
+                if !style.is_empty() {
+                    html.push_str(&format!("<span style=\"{}\">", style));
+                }
+                for c in span.text.chars() {
+                    match c {
+                        '&' => html.push_str("&amp;"),
+                        '<' => html.push_str("&lt;"),
+                        '>' => html.push_str("&gt;"),
+                        '"' => html.push_str("&quot;"),
+                        c => html.push(c),
+                    }
+                }
+                if !style.is_empty() {
+                    html.push_str("</span>");
+                }
+            }
+        }
+        html.push_str("</pre>");
+        html
+    }
+
+    /// The CSS of `span` where it differs from the block's.
+    fn css(&self, span: &Span) -> String {
+        let mut style = Vec::new();
+        if span.fg != self.foreground {
+            style.push(format!("color:{}", hex(span.fg)));
+        }
+        if span.bg != self.background {
+            style.push(format!("background-color:{}", hex(span.bg)));
+        }
+        if span.flags.contains(Flags::BOLD) {
+            style.push("font-weight:bold".into());
+        }
+        if span.flags.contains(Flags::ITALIC) {
+            style.push("font-style:italic".into());
+        }
+        let lines: Vec<&str> = [
+            (Flags::UNDERLINE, "underline"),
+            (Flags::STRIKEOUT, "line-through"),
+        ]
+        .into_iter()
+        .filter(|(flag, _)| span.flags.contains(*flag))
+        .map(|(_, line)| line)
+        .collect();
+        if !lines.is_empty() {
+            style.push(format!("text-decoration:{}", lines.join(" ")));
+        }
+        style.join(";")
+    }
+
+    /// An RTF document in a monospaced font, each run in a group of its
+    /// own.
+    pub fn rtf(&self) -> String {
+        let mut table = vec![self.foreground, self.background];
+        for span in self.lines.iter().flatten() {
+            for color in [span.fg, span.bg] {
+                if !table.contains(&color) {
+                    table.push(color);
+                }
+            }
+        }
+        // Color 0 is the reader's default, so the table starts at 1
+        let index = |color: Rgb| table.iter().position(|&c| c == color).unwrap_or(0) + 1;
+
+        let mut rtf = String::from("{\\rtf1\\ansi\\deff0{\\fonttbl{\\f0\\fmodern Courier New;}}");
+        rtf.push_str("{\\colortbl;");
+        for Rgb(r, g, b) in &table {
+            rtf.push_str(&format!("\\red{}\\green{}\\blue{};", r, g, b));
+        }
+        rtf.push_str("}\\f0\\fs20\n");
+        for (i, line) in self.lines.iter().enumerate() {
+            if i > 0 {
+                rtf.push_str("\\line\n");
+            }
+            for span in line {
+                let bg = index(span.bg);
+                rtf.push_str(&format!(
+                    "{{\\cf{}\\cb{}\\highlight{}",
+                    index(span.fg),
+                    bg,
+                    bg
+                ));
+                for (flag, word) in [
+                    (Flags::BOLD, "\\b"),
+                    (Flags::ITALIC, "\\i"),
+                    (Flags::UNDERLINE, "\\ul"),
+                    (Flags::STRIKEOUT, "\\strike"),
+                ] {
+                    if span.flags.contains(flag) {
+                        rtf.push_str(word);
+                    }
+                }
+                rtf.push(' ');
+                for c in span.text.chars() {
+                    match c {
+                        '\\' | '{' | '}' => {
+                            rtf.push('\\');
+                            rtf.push(c);
+                        }
+                        '\t' => rtf.push_str("\\tab "),
+                        ' '..='~' => rtf.push(c),
+                        // Signed UTF-16 units, with `?` for readers
+                        // without Unicode
+                        c => {
+                            for unit in c.encode_utf16(&mut [0; 2]) {
+                                rtf.push_str(&format!("\\u{}?", *unit as i16));
+                            }
+                        }
+                    }
+                }
+                rtf.push('}');
+            }
+        }
+        rtf.push('}');
+        rtf
+    }
+}
+
+/// `cells` in runs of the same colors and style.
+fn spans(cells: &[&Cell], colors: &impl Fn(&Cell) -> (Rgb, Rgb)) -> Vec<Span> {
+    let mut spans: Vec<Span> = Vec::new();
+    for cell in cells {
+        let (fg, bg) = colors(cell);
+        let flags = cell.flags & STYLE_FLAGS;
+        match spans.last_mut() {
+            Some(span) if (span.fg, span.bg, span.flags) == (fg, bg, flags) => {
+                span.text.push(cell.c)
+            }
+            _ => spans.push(Span {
+                text: cell.c.to_string(),
+                fg,
+                bg,
+                flags,
+            }),
+        }
+    }
+    spans
+}
+
+fn hex(Rgb(r, g, b): Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Add `rtf` to the clipboard next to what was just put there.
+#[cfg(target_os = "macos")]
+pub fn add_rtf(rtf: &str) {
+    use objc2_app_kit::{NSPasteboard, NSPasteboardTypeRTF};
+    use objc2_foundation::{NSArray, NSData};
+
+    // SAFETY: AppKit is only used from the main thread, where copies are
+    // made
+    unsafe {
+        let pasteboard = NSPasteboard::generalPasteboard();
+        pasteboard.addTypes_owner(&NSArray::from_slice(&[NSPasteboardTypeRTF]), None);
+        let data = NSData::with_bytes(rtf.as_bytes());
+        if !pasteboard.setData_forType(Some(&data), NSPasteboardTypeRTF) {
+            log::warn!("Failed to copy as RTF");
+        }
+    }
+}
+
+/// Add `rtf` to the clipboard next to what was just put there.
+#[cfg(windows)]
+pub fn add_rtf(rtf: &str) {
+    use windows_sys::Win32::Foundation::GlobalFree;
+    use windows_sys::Win32::System::DataExchange::{
+        CloseClipboard, OpenClipboard, RegisterClipboardFormatW, SetClipboardData,
+    };
+    use windows_sys::Win32::System::Memory::{
+        GMEM_MOVEABLE, GlobalAlloc, GlobalLock, GlobalUnlock,
+    };
+
+    let name: Vec<u16> = "Rich Text Format\0".encode_utf16().collect();
+    let bytes = rtf.as_bytes();
+    // SAFETY: the memory holds the text and its terminating NUL; once set,
+    // it belongs to the clipboard
+    unsafe {
+        let format = RegisterClipboardFormatW(name.as_ptr());
+        if format == 0 || OpenClipboard(std::ptr::null_mut()) == 0 {
+            log::warn!("Failed to copy as RTF: the clipboard is busy");
+            return;
+        }
+        let memory = GlobalAlloc(GMEM_MOVEABLE, bytes.len() + 1);
+        let target = if memory.is_null() {
+            std::ptr::null_mut()
+        } else {
+            GlobalLock(memory).cast::<u8>()
+        };
+        if target.is_null() {
+            log::warn!("Failed to copy as RTF: out of memory");
+            if !memory.is_null() {
+                GlobalFree(memory);
+            }
+        } else {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), target, bytes.len());
+            *target.add(bytes.len()) = 0;
+            GlobalUnlock(memory);
+            if SetClipboardData(format, memory).is_null() {
+                log::warn!("Failed to copy as RTF");
+                GlobalFree(memory);
+            }
+        }
+        CloseClipboard();
+    }
+}
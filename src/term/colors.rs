@@ -0,0 +1,111 @@
+//! Color Overrides
+//!
+//! Colors a session sets for itself on top of `Config::colors`: palette
+//! entries and the default colors, changed with OSC 4/10/11/12 (reset with
+//! OSC 104/110/111/112) or over IPC, and a background tint. Each terminal
+//! keeps its own, so a window running an SSH session to production can
+//! look different from the local shells.
+//!
+//! ```
+//! use adamant::config::Rgb;
+//! use adamant::term::{ColorSlot, Terminal};
+//!
+//! let mut term = Terminal::new(80, 24, 1000);
+//! term.feed(b"\x1b]11;#200000\x07\x1b]4;1;rgb:ff/80/00\x07");
+//! let colors = term.colors();
+//! assert_eq!(colors.get(ColorSlot::Background), Some(Rgb(0x20, 0, 0)));
+//! assert_eq!(colors.get(ColorSlot::Indexed(1)), Some(Rgb(0xff, 0x80, 0)));
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::config::{Rgb, Tint};
+
+/// A color that can be overridden.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColorSlot {
+    Foreground,
+    Background,
+    Cursor,
+    /// An entry of the 256-color palette
+    Indexed(u8),
+}
+
+/// The colors a session changed; `None` and missing entries are the
+/// configured ones.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ColorOverrides {
+    indexed: BTreeMap<u8, Rgb>,
+    foreground: Option<Rgb>,
+    background: Option<Rgb>,
+    cursor: Option<Rgb>,
+    /// Mixed into the default background. Starts as `Colors::tint`; a
+    /// reset of the terminal keeps it
+    pub tint: Option<Tint>,
+}
+
+impl ColorOverrides {
+    pub fn get(&self, slot: ColorSlot) -> Option<Rgb> {
+        match slot {
+            ColorSlot::Foreground => self.foreground,
+            ColorSlot::Background => self.background,
+            ColorSlot::Cursor => self.cursor,
+            ColorSlot::Indexed(index) => self.indexed.get(&index).copied(),
+        }
+    }
+
+    /// Override `slot`, or go back to the configured color with `None`.
+    pub fn set(&mut self, slot: ColorSlot, color: Option<Rgb>) {
+        match slot {
+            ColorSlot::Foreground => self.foreground = color,
+            ColorSlot::Background => self.background = color,
+            ColorSlot::Cursor => self.cursor = color,
+            ColorSlot::Indexed(index) => match color {
+                Some(color) => drop(self.indexed.insert(index, color)),
+                None => drop(self.indexed.remove(&index)),
+            },
+        }
+    }
+
+    /// The overridden palette entries.
+    pub fn indexed(&self) -> impl Iterator<Item = (u8, Rgb)> + '_ {
+        self.indexed.iter().map(|(&index, &color)| (index, color))
+    }
+
+    /// Back to the configured colors, except for the tint.
+    pub fn reset(&mut self) {
+        *self = Self {
+            tint: self.tint,
+            ..Self::default()
+        };
+    }
+}
+
+/// Parse a color the way X11 and OSC color sequences spell them:
+/// `rgb:r/g/b` with 1 to 4 hex digits per channel, or `#rgb`, `#rrggbb`,
+/// `#rrrgggbbb` and `#rrrrggggbbbb`.
+pub fn parse_color(spec: &str) -> Option<Rgb> {
+    // A channel of `digits` hex digits, scaled to 8 bits
+    let channel = |digits: &str| {
+        if digits.is_empty() || digits.len() > 4 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let value = u32::from_str_radix(digits, 16).ok()?;
+        let max = (1u32 << (4 * digits.len())) - 1;
+        Some(((value * 255 + max / 2) / max) as u8)
+    };
+    let channels: Vec<&str> = if let Some(rgb) = spec.strip_prefix("rgb:") {
+        rgb.split('/').collect()
+    } else {
+        let hex = spec.strip_prefix('#')?;
+        if !hex.is_ascii() || hex.is_empty() || hex.len() % 3 != 0 {
+            return None;
+        }
+        let n = hex.len() / 3;
+        vec![&hex[..n], &hex[n..2 * n], &hex[2 * n..]]
+    };
+    match channels[..] {
+        [r, g, b] => Some(Rgb(channel(r)?, channel(g)?, channel(b)?)),
+        _ => None,
+    }
+}
@@ -0,0 +1,1274 @@
+//! Terminal Emulation
+//!
+//! [`Terminal`] is the VT emulator: it runs PTY output through the
+//! [`Parser`](crate::parser::Parser) and applies the resulting sequences to
+//! the primary or alternate [`Grid`]. It has no I/O of its own; the caller
+//! feeds it bytes and forwards [`Terminal::take_responses`] back to the
+//! child process.
+//!
+//! ```
+//! use adamant::term::Terminal;
+//!
+//! let mut term = Terminal::new(80, 24, 1000);
+//! term.feed(b"hello\r\n\x1b[1mworld");
+//! assert_eq!(term.grid().row(0).text(), "hello");
+//! ```
+
+mod charset;
+mod colors;
+mod mode;
+mod snapshot;
+mod unhandled;
+mod width;
+
+use crate::grid::{Cell, Color, CommandStatus, Flags, Grid};
+use crate::parser::{Params, Parser, Perform};
+use crate::terminfo::{self, Capability};
+
+pub use self::charset::Charset;
+pub use self::colors::{ColorOverrides, ColorSlot, parse_color};
+pub use self::mode::Mode;
+pub use self::unhandled::{Unhandled, escape_bytes};
+pub use self::width::WidthOptions;
+
+/// Optional features programs can rely on, advertised to them in
+/// `ADAMANT_CAPABILITIES` (comma-separated).
+pub const CAPABILITIES: &[&str] = &[
+    "truecolor",
+    "sgr-mouse",
+    "bracketed-paste",
+    "focus-events",
+    "alt-screen",
+    "xtgettcap",
+];
+
+/// Longest DCS payload collected; longer ones are cut off.
+const MAX_DCS_LEN: usize = 4096;
+
+/// Largest tmux passthrough run; a longer one is dropped whole.
+const MAX_PASSTHROUGH_LEN: usize = 64 * 1024;
+
+/// Attributes XTPUSHSGR keeps at most, as in xterm; pushing more drops
+/// the oldest.
+const MAX_SGR_STACK: usize = 10;
+
+/// Shape of the text cursor (DECSCUSR).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CursorShape {
+    #[default]
+    Block,
+    Underline,
+    Beam,
+}
+
+/// Cursor shape plus blinking, as requested by the application.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CursorStyle {
+    pub shape: CursorShape,
+    pub blinking: bool,
+}
+
+/// How the scrollback interacts with clearing and the alternate screen.
+///
+/// Users disagree on both, so they follow xterm's resources of the same
+/// spirit instead of hard-coding one behavior:
+///
+/// ```
+/// use adamant::term::{ScrollbackOptions, Terminal};
+///
+/// let mut term = Terminal::new(10, 2, 100);
+/// term.set_scrollback_options(ScrollbackOptions {
+///     erase_saved_lines: false,
+///     ..ScrollbackOptions::default()
+/// });
+/// term.feed(b"1\r\n2\r\n3\x1b[3J");
+/// assert_eq!(term.grid().history_len(), 1);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ScrollbackOptions {
+    /// ED 3 (what `clear` sends after clearing the screen) erases the
+    /// scrollback (xterm's `eraseSavedLines`).
+    pub erase_saved_lines: bool,
+    /// Lines scrolled off the top of the alternate screen (a pager, an
+    /// editor) are kept and moved into the primary scrollback when the
+    /// application exits, instead of being discarded.
+    pub save_alt_screen_lines: bool,
+}
+
+impl Default for ScrollbackOptions {
+    fn default() -> Self {
+        Self {
+            erase_saved_lines: true,
+            save_alt_screen_lines: false,
+        }
+    }
+}
+
+/// Notifications for the embedding application.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// The title changed: the application set one (OSC 0/2), or the user
+    /// renamed the terminal (see [`Terminal::rename`]).
+    Title(String),
+    /// BEL was received.
+    Bell,
+    /// The shell reported another host it runs on (OSC 7 or iTerm2's
+    /// `RemoteHost`), or `None` when the program was detached.
+    Host(Option<String>),
+}
+
+/// State saved by DECSC and restored by DECRC.
+#[derive(Clone, Debug, Default)]
+struct SavedCursor {
+    row: usize,
+    col: usize,
+    template: Cell,
+    pending_wrap: bool,
+    origin: bool,
+    charsets: [Charset; 4],
+    active_charset: usize,
+}
+
+/// Attributes saved by XTPUSHSGR: the ones selected are put back by
+/// XTPOPSGR, the rest stay as they are then.
+#[derive(Clone, Debug)]
+struct SavedSgr {
+    template: Cell,
+    flags: Flags,
+    fg: bool,
+    bg: bool,
+}
+
+/// A DCS being received that is answered or unwrapped.
+enum Dcs {
+    /// XTGETTCAP (`DCS + q`), the capability names asked for
+    Termcap(Vec<u8>),
+    /// tmux's passthrough (`DCS tmux;`), its doubled ESCs undone
+    Tmux(Vec<u8>),
+}
+
+/// The terminal emulator state.
+pub struct Terminal {
+    parser: Parser,
+    primary: Grid,
+    alternate: Grid,
+    mode: Mode,
+    scrollback: usize,
+    scrollback_options: ScrollbackOptions,
+    widths: WidthOptions,
+    title: Option<String>,
+    /// The name the user gave the terminal, shown instead of `title`
+    user_title: Option<String>,
+    cursor_style: CursorStyle,
+    saved_cursor: SavedCursor,
+    alt_saved_cursor: SavedCursor,
+    /// Attributes pushed by XTPUSHSGR, the latest last
+    sgr_stack: Vec<SavedSgr>,
+    charsets: [Charset; 4],
+    active_charset: usize,
+    /// G2 or G3 for the next printed character only (SS2/SS3)
+    single_shift: Option<usize>,
+    /// Last printed character, repeated by REP
+    last_char: Option<char>,
+    events: Vec<Event>,
+    responses: Vec<u8>,
+    /// Payload of the DCS being received, when it is one we answer
+    dcs: Option<Dcs>,
+    /// Colors the session changed
+    colors: ColorOverrides,
+    /// Host and working directory the shell last reported
+    host: Option<String>,
+    directory: Option<String>,
+    /// Targets of OSC 8 hyperlinks, with their `id` parameter; a cell's
+    /// `link` is one more than the index
+    hyperlinks: Vec<(String, String)>,
+    /// Sequences received that aren't implemented
+    unhandled: Vec<Unhandled>,
+    /// Bumped on every change that may affect what is displayed
+    generation: u64,
+}
+
+impl Terminal {
+    /// Create a terminal of `cols` x `rows` keeping `scrollback` lines of history.
+    pub fn new(cols: usize, rows: usize, scrollback: usize) -> Self {
+        Self {
+            parser: Parser::new(),
+            primary: Grid::new(cols, rows, scrollback),
+            alternate: Grid::new(cols, rows, 0),
+            mode: Mode::default(),
+            scrollback,
+            scrollback_options: ScrollbackOptions::default(),
+            widths: WidthOptions::default(),
+            title: None,
+            user_title: None,
+            cursor_style: CursorStyle::default(),
+            saved_cursor: SavedCursor::default(),
+            alt_saved_cursor: SavedCursor::default(),
+            sgr_stack: Vec::new(),
+            charsets: [Charset::Ascii; 4],
+            active_charset: 0,
+            single_shift: None,
+            last_char: None,
+            events: Vec::new(),
+            responses: Vec::new(),
+            dcs: None,
+            colors: ColorOverrides::default(),
+            host: None,
+            directory: None,
+            hyperlinks: Vec::new(),
+            unhandled: Vec::new(),
+            generation: 0,
+        }
+    }
+
+    /// Process output from the child process.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.generation = self.generation.wrapping_add(1);
+        let mut parser = std::mem::take(&mut self.parser);
+        parser.advance(self, bytes);
+        self.parser = parser;
+    }
+
+    /// The active grid (alternate screen when enabled).
+    pub fn grid(&self) -> &Grid {
+        if self.mode.contains(Mode::ALT_SCREEN) {
+            &self.alternate
+        } else {
+            &self.primary
+        }
+    }
+
+    /// The active grid, mutably (for scrolling the view, etc.).
+    pub fn grid_mut(&mut self) -> &mut Grid {
+        self.generation = self.generation.wrapping_add(1);
+        if self.mode.contains(Mode::ALT_SCREEN) {
+            &mut self.alternate
+        } else {
+            &mut self.primary
+        }
+    }
+
+    pub fn cols(&self) -> usize {
+        self.primary.cols()
+    }
+
+    pub fn rows(&self) -> usize {
+        self.primary.rows()
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// The title shown: the user's name for the terminal if it has one,
+    /// otherwise the last title set by the application.
+    pub fn title(&self) -> Option<&str> {
+        self.user_title.as_deref().or(self.title.as_deref())
+    }
+
+    /// The last title set by the application, renamed or not.
+    pub fn program_title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// Name the terminal `title` whatever the application sets, or go back
+    /// to the application's title with `None`. Reported as
+    /// [`Event::Title`], empty if there is no title left.
+    pub fn rename(&mut self, title: Option<String>) {
+        if title == self.user_title {
+            return;
+        }
+        self.user_title = title;
+        let title = self.title().unwrap_or_default().to_string();
+        self.events.push(Event::Title(title));
+    }
+
+    pub fn cursor_style(&self) -> CursorStyle {
+        self.cursor_style
+    }
+
+    pub fn scrollback_options(&self) -> ScrollbackOptions {
+        self.scrollback_options
+    }
+
+    pub fn set_scrollback_options(&mut self, options: ScrollbackOptions) {
+        self.scrollback_options = options;
+        self.alternate.set_max_scrollback(self.alt_scrollback());
+    }
+
+    /// Choose the width of ambiguous characters and text-style emoji. Only
+    /// affects text printed afterwards.
+    pub fn set_width_options(&mut self, options: WidthOptions) {
+        self.widths = options;
+    }
+
+    /// The width policy text is printed with.
+    pub fn width_options(&self) -> WidthOptions {
+        self.widths
+    }
+
+    /// The colors the session changed from the configured ones.
+    pub fn colors(&self) -> &ColorOverrides {
+        &self.colors
+    }
+
+    /// The host the shell last reported running on, as `host` or
+    /// `user@host`.
+    pub fn host(&self) -> Option<&str> {
+        self.host.as_deref()
+    }
+
+    /// The working directory the shell last reported.
+    pub fn directory(&self) -> Option<&str> {
+        self.directory.as_deref()
+    }
+
+    /// The target of the OSC 8 hyperlink a cell is part of (`Cell::link`).
+    pub fn hyperlink(&self, link: u16) -> Option<&str> {
+        let index = usize::from(link).checked_sub(1)?;
+        self.hyperlinks.get(index).map(|(_, uri)| uri.as_str())
+    }
+
+    /// Change the session's colors (for IPC and the configured tint).
+    pub fn colors_mut(&mut self) -> &mut ColorOverrides {
+        self.generation = self.generation.wrapping_add(1);
+        &mut self.colors
+    }
+
+    /// A counter that changes whenever the displayed state may have changed.
+    ///
+    /// Renderers compare it with the value of their last upload to skip
+    /// re-encoding frames in which nothing happened.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Resize both screens.
+    pub fn resize(&mut self, cols: usize, rows: usize) {
+        self.generation = self.generation.wrapping_add(1);
+        self.primary.resize(cols, rows);
+        self.alternate.resize(cols, rows);
+    }
+
+    /// Show `cols` columns of both screens at once, for a grid wider than
+    /// the window (see `Grid::view_cols`).
+    pub fn set_view_cols(&mut self, cols: usize) {
+        self.generation = self.generation.wrapping_add(1);
+        self.primary.set_view_cols(cols);
+        self.alternate.set_view_cols(cols);
+    }
+
+    /// The sequences received so far that aren't implemented, in the
+    /// order they first came. A reset keeps them.
+    pub fn unhandled(&self) -> &[Unhandled] {
+        &self.unhandled
+    }
+
+    fn report_unhandled(&mut self, sequence: String, sample: impl AsRef<[u8]>) {
+        unhandled::record(&mut self.unhandled, sequence, sample.as_ref());
+    }
+
+    /// Drain pending notifications.
+    pub fn take_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Drain bytes that must be written back to the child (query replies).
+    pub fn take_responses(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.responses)
+    }
+
+    /// Full reset (RIS): both screens, the scrollback, tab stops, modes,
+    /// charsets, attributes, colors and hyperlinks go back to how a new
+    /// terminal starts.
+    pub fn reset(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+        let (cols, rows) = (self.cols(), self.rows());
+        let view_cols = self.primary.view_cols();
+        self.primary = Grid::new(cols, rows, self.scrollback);
+        self.alternate = Grid::new(cols, rows, self.alt_scrollback());
+        // The window is as wide as before
+        self.set_view_cols(view_cols);
+        self.mode = Mode::default();
+        self.cursor_style = CursorStyle::default();
+        self.saved_cursor = SavedCursor::default();
+        self.alt_saved_cursor = SavedCursor::default();
+        self.sgr_stack.clear();
+        self.charsets = [Charset::Ascii; 4];
+        self.active_charset = 0;
+        self.single_shift = None;
+        self.last_char = None;
+        self.dcs = None;
+        self.hyperlinks.clear();
+        self.colors.reset();
+        // Drops a sequence cut off halfway when called directly; while
+        // feeding, the parser is put back afterwards
+        self.parser = Parser::new();
+    }
+
+    /// Soft terminal reset (DECSTR): modes, rendition, scroll region and
+    /// saved cursor go back to their defaults, the screen is kept.
+    pub fn soft_reset(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+        self.mode
+            .remove(Mode::INSERT | Mode::ORIGIN | Mode::APP_CURSOR | Mode::APP_KEYPAD);
+        self.mode.insert(Mode::AUTOWRAP | Mode::SHOW_CURSOR);
+        self.charsets = [Charset::Ascii; 4];
+        self.active_charset = 0;
+        self.single_shift = None;
+        self.saved_cursor = SavedCursor::default();
+        self.alt_saved_cursor = SavedCursor::default();
+        self.sgr_stack.clear();
+        let grid = self.grid_mut();
+        let rows = grid.rows();
+        grid.set_scroll_region(0, rows);
+        grid.cursor.template = Cell::default();
+        grid.cursor.pending_wrap = false;
+    }
+
+    /// Undo what the program on the other end left behind before another
+    /// one takes over the same screen: soft reset, back to the primary
+    /// screen, no mouse, focus reporting or bracketed paste. Output and
+    /// scrollback stay.
+    pub fn detach_program(&mut self) {
+        self.exit_alt_screen(false);
+        self.soft_reset();
+        self.mode.remove(
+            Mode::MOUSE_MODE | Mode::SGR_MOUSE | Mode::FOCUS_EVENTS | Mode::BRACKETED_PASTE,
+        );
+        self.cursor_style = CursorStyle::default();
+        self.parser = Parser::new();
+        self.dcs = None;
+        self.directory = None;
+        if self.host.take().is_some() {
+            self.events.push(Event::Host(None));
+        }
+    }
+
+    // -------------------------------------------------------------------------
+    // Helpers
+    // -------------------------------------------------------------------------
+
+    /// History kept by the alternate screen until it is handed over to the
+    /// primary one.
+    fn alt_scrollback(&self) -> usize {
+        if self.scrollback_options.save_alt_screen_lines {
+            self.scrollback
+        } else {
+            0
+        }
+    }
+
+    fn print_char(&mut self, c: char) {
+        let slot = self.single_shift.take().unwrap_or(self.active_charset);
+        let c = self.charsets[slot].map(c);
+        let width = self.widths.width(c);
+        // TODO: Combining characters (width 0) should attach to the previous cell
+        if width == 0 {
+            return;
+        }
+
+        let autowrap = self.mode.contains(Mode::AUTOWRAP);
+        let insert = self.mode.contains(Mode::INSERT);
+        let grid = self.grid_mut();
+        if insert {
+            grid.insert_blank(width);
+        }
+        grid.write(c, width, autowrap);
+        self.last_char = Some(c);
+    }
+
+    /// Move to a 1-based row/column pair, honoring origin mode.
+    fn goto_origin(&mut self, row: usize, col: usize) {
+        let origin = self.mode.contains(Mode::ORIGIN);
+        let grid = self.grid_mut();
+        if origin {
+            let (top, bottom) = grid.scroll_region();
+            grid.goto((top + row).min(bottom - 1), col);
+        } else {
+            grid.goto(row, col);
+        }
+    }
+
+    fn save_cursor(&mut self) {
+        let saved = SavedCursor {
+            row: self.grid().cursor.row,
+            col: self.grid().cursor.col,
+            template: self.grid().cursor.template.clone(),
+            pending_wrap: self.grid().cursor.pending_wrap,
+            origin: self.mode.contains(Mode::ORIGIN),
+            charsets: self.charsets,
+            active_charset: self.active_charset,
+        };
+        if self.mode.contains(Mode::ALT_SCREEN) {
+            self.alt_saved_cursor = saved;
+        } else {
+            self.saved_cursor = saved;
+        }
+    }
+
+    fn restore_cursor(&mut self) {
+        let saved = if self.mode.contains(Mode::ALT_SCREEN) {
+            self.alt_saved_cursor.clone()
+        } else {
+            self.saved_cursor.clone()
+        };
+        self.mode.set(Mode::ORIGIN, saved.origin);
+        self.charsets = saved.charsets;
+        self.active_charset = saved.active_charset;
+        let grid = self.grid_mut();
+        grid.goto(saved.row, saved.col);
+        grid.cursor.template = saved.template;
+        grid.cursor.pending_wrap = saved.pending_wrap;
+    }
+
+    fn enter_alt_screen(&mut self, save_cursor: bool, clear: bool) {
+        if self.mode.contains(Mode::ALT_SCREEN) {
+            return;
+        }
+        if save_cursor {
+            self.save_cursor();
+        }
+        // The alternate screen starts at the primary cursor position.
+        let cursor = self.primary.cursor.clone();
+        self.mode.insert(Mode::ALT_SCREEN);
+        self.alternate.cursor = cursor;
+        if clear {
+            self.alternate.erase_display(2);
+        }
+    }
+
+    fn exit_alt_screen(&mut self, restore_cursor: bool) {
+        if !self.mode.contains(Mode::ALT_SCREEN) {
+            return;
+        }
+        self.mode.remove(Mode::ALT_SCREEN);
+        let saved = self.alternate.take_history();
+        self.primary.push_history(saved);
+        if restore_cursor {
+            self.restore_cursor();
+        }
+    }
+
+    fn set_private_mode(&mut self, mode: u16, enable: bool) {
+        match mode {
+            1 => self.mode.set(Mode::APP_CURSOR, enable),
+            6 => {
+                self.mode.set(Mode::ORIGIN, enable);
+                self.goto_origin(0, 0);
+            }
+            7 => self.mode.set(Mode::AUTOWRAP, enable),
+            12 => self.cursor_style.blinking = enable,
+            25 => self.mode.set(Mode::SHOW_CURSOR, enable),
+            47 | 1047 => {
+                if enable {
+                    self.enter_alt_screen(false, mode == 1047);
+                } else {
+                    if mode == 1047 {
+                        self.alternate.erase_display(2);
+                    }
+                    self.exit_alt_screen(false);
+                }
+            }
+            1048 => {
+                if enable {
+                    self.save_cursor();
+                } else {
+                    self.restore_cursor();
+                }
+            }
+            1049 => {
+                if enable {
+                    self.enter_alt_screen(true, true);
+                } else {
+                    self.exit_alt_screen(true);
+                }
+            }
+            1000 => self.set_mouse_mode(Mode::MOUSE_REPORT_CLICK, enable),
+            1002 => self.set_mouse_mode(Mode::MOUSE_DRAG, enable),
+            1003 => self.set_mouse_mode(Mode::MOUSE_MOTION, enable),
+            1004 => self.mode.set(Mode::FOCUS_EVENTS, enable),
+            1006 => self.mode.set(Mode::SGR_MOUSE, enable),
+            2004 => self.mode.set(Mode::BRACKETED_PASTE, enable),
+            _ => self.report_unhandled(
+                format!("DEC mode {}", mode),
+                format!("\x1b[?{}{}", mode, if enable { 'h' } else { 'l' }),
+            ),
+        }
+    }
+
+    /// Mouse tracking modes are mutually exclusive.
+    fn set_mouse_mode(&mut self, mode: Mode, enable: bool) {
+        self.mode.remove(Mode::MOUSE_MODE);
+        self.mode.set(mode, enable);
+    }
+
+    fn set_mode(&mut self, mode: u16, enable: bool) {
+        match mode {
+            4 => self.mode.set(Mode::INSERT, enable),
+            20 => self.mode.set(Mode::LINE_FEED_NEW_LINE, enable),
+            _ => self.report_unhandled(
+                format!("ANSI mode {}", mode),
+                format!("\x1b[{}{}", mode, if enable { 'h' } else { 'l' }),
+            ),
+        }
+    }
+
+    /// Select Graphic Rendition (SGR).
+    fn sgr(&mut self, params: &Params) {
+        let template = &mut self.grid_mut().cursor.template;
+        // Resetting the attributes leaves the hyperlink
+        let reset = Cell {
+            link: template.link,
+            ..Cell::default()
+        };
+        if params.is_empty() {
+            *template = reset;
+            return;
+        }
+
+        let groups: Vec<&[u16]> = params.iter().collect();
+        let mut unhandled = Vec::new();
+        let mut i = 0;
+        while i < groups.len() {
+            let group = groups[i];
+            i += 1;
+            match group[0] {
+                0 => *template = reset.clone(),
+                1 => template.flags.insert(Flags::BOLD),
+                2 => template.flags.insert(Flags::DIM),
+                3 => template.flags.insert(Flags::ITALIC),
+                4 => match group.get(1) {
+                    Some(0) => template.flags.remove(Flags::UNDERLINE),
+                    _ => template.flags.insert(Flags::UNDERLINE),
+                },
+                5 | 6 => template.flags.insert(Flags::BLINK),
+                7 => template.flags.insert(Flags::INVERSE),
+                8 => template.flags.insert(Flags::HIDDEN),
+                9 => template.flags.insert(Flags::STRIKEOUT),
+                21 => template.flags.insert(Flags::UNDERLINE),
+                22 => template.flags.remove(Flags::BOLD | Flags::DIM),
+                23 => template.flags.remove(Flags::ITALIC),
+                24 => template.flags.remove(Flags::UNDERLINE),
+                25 => template.flags.remove(Flags::BLINK),
+                27 => template.flags.remove(Flags::INVERSE),
+                28 => template.flags.remove(Flags::HIDDEN),
+                29 => template.flags.remove(Flags::STRIKEOUT),
+                n @ 30..=37 => template.fg = Color::Indexed((n - 30) as u8),
+                38 => {
+                    if let Some(color) = parse_extended_color(group, &groups, &mut i) {
+                        template.fg = color;
+                    }
+                }
+                39 => template.fg = Color::Default,
+                n @ 40..=47 => template.bg = Color::Indexed((n - 40) as u8),
+                48 => {
+                    if let Some(color) = parse_extended_color(group, &groups, &mut i) {
+                        template.bg = color;
+                    }
+                }
+                49 => template.bg = Color::Default,
+                n @ 90..=97 => template.fg = Color::Indexed((n - 90 + 8) as u8),
+                n @ 100..=107 => template.bg = Color::Indexed((n - 100 + 8) as u8),
+                n => unhandled.push(n),
+            }
+        }
+        for n in unhandled {
+            self.report_unhandled(format!("SGR {}", n), format!("\x1b[{}m", n));
+        }
+    }
+
+    /// XTPUSHSGR: save the attributes in `params` (SGR numbers; 30 and 31
+    /// for the foreground and background colors), or all of them.
+    fn push_sgr(&mut self, params: &Params) {
+        let mut saved = SavedSgr {
+            template: self.grid().cursor.template.clone(),
+            flags: Flags::empty(),
+            fg: false,
+            bg: false,
+        };
+        if params.is_empty() {
+            saved.flags = Flags::all();
+            saved.fg = true;
+            saved.bg = true;
+        }
+        for param in params.iter().map(|group| group[0]) {
+            match param {
+                1 => saved.flags |= Flags::BOLD,
+                2 => saved.flags |= Flags::DIM,
+                3 => saved.flags |= Flags::ITALIC,
+                4 | 21 => saved.flags |= Flags::UNDERLINE,
+                5 => saved.flags |= Flags::BLINK,
+                7 => saved.flags |= Flags::INVERSE,
+                8 => saved.flags |= Flags::HIDDEN,
+                9 => saved.flags |= Flags::STRIKEOUT,
+                30 => saved.fg = true,
+                31 => saved.bg = true,
+                n => log::debug!("Unhandled XTPUSHSGR attribute: {}", n),
+            }
+        }
+        // Only rendition, not layout
+        saved.flags &= !(Flags::WIDE | Flags::WIDE_SPACER);
+        if self.sgr_stack.len() == MAX_SGR_STACK {
+            self.sgr_stack.remove(0);
+        }
+        self.sgr_stack.push(saved);
+    }
+
+    /// XTPOPSGR: put back the attributes the last XTPUSHSGR saved.
+    fn pop_sgr(&mut self) {
+        let Some(saved) = self.sgr_stack.pop() else {
+            return;
+        };
+        let template = &mut self.grid_mut().cursor.template;
+        template.flags = (template.flags - saved.flags) | (saved.template.flags & saved.flags);
+        if saved.fg {
+            template.fg = saved.template.fg;
+        }
+        if saved.bg {
+            template.bg = saved.template.bg;
+        }
+    }
+
+    /// Answer XTGETTCAP (`DCS + q <names> ST`): for each hex-encoded,
+    /// `;`-separated capability name, `DCS 1 + r <name>=<value> ST` with the
+    /// value hex-encoded too (no `=<value>` for booleans), or
+    /// `DCS 0 + r <name> ST` when the terminal doesn't have it.
+    fn request_termcap(&mut self, payload: &[u8]) {
+        for hex_name in payload
+            .split(|&b| b == b';')
+            .filter(|name| !name.is_empty())
+        {
+            let capability = hex_decode(hex_name)
+                .and_then(|name| String::from_utf8(name).ok())
+                .and_then(|name| terminfo::capability(&name));
+            let hex_name = String::from_utf8_lossy(hex_name);
+            let reply = match capability {
+                None => format!("\x1bP0+r{}\x1b\\", hex_name),
+                Some(Capability::Flag) => format!("\x1bP1+r{}\x1b\\", hex_name),
+                Some(Capability::Number(n)) => {
+                    let value = hex_encode(n.to_string().as_bytes());
+                    format!("\x1bP1+r{}={}\x1b\\", hex_name, value)
+                }
+                Some(Capability::String(value)) => {
+                    format!("\x1bP1+r{}={}\x1b\\", hex_name, hex_encode(&value))
+                }
+            };
+            self.responses.extend_from_slice(reply.as_bytes());
+        }
+    }
+
+    /// OSC 4/10/11/12: override `slot` with the color `spec`. Queries
+    /// (`?`) go unanswered; the configured colors aren't known here.
+    fn set_color(&mut self, slot: ColorSlot, spec: &[u8]) {
+        match std::str::from_utf8(spec).ok().and_then(parse_color) {
+            Some(color) => self.colors.set(slot, Some(color)),
+            None => log::debug!("Unhandled color {:?} for {:?}", spec, slot),
+        }
+    }
+
+    /// The shell runs on `host` now (OSC 7, OSC 1337 `RemoteHost`).
+    fn set_host(&mut self, host: &str) {
+        if host.is_empty() || self.host.as_deref() == Some(host) {
+            return;
+        }
+        self.host = Some(host.to_string());
+        self.events.push(Event::Host(self.host.clone()));
+    }
+
+    /// OSC 7: the working directory as a `file://host/path` URL. Without a
+    /// host the directory is local, and the host stays as it was.
+    fn set_location(&mut self, url: &[u8]) {
+        let url = String::from_utf8_lossy(url);
+        let Some(rest) = url
+            .strip_prefix("file://")
+            .or_else(|| url.strip_prefix("kitty-shell-cwd://"))
+        else {
+            return log::debug!("Unhandled OSC 7 URL {:?}", url);
+        };
+        let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        self.directory = Some(percent_decode(path));
+        self.set_host(host);
+    }
+
+    /// OSC 8: the text printed from now on links to `uri`, until a link
+    /// without one. Links with the same `id` parameter and target are the
+    /// same link, so a link broken over several lines hovers as one.
+    fn set_hyperlink(&mut self, params: &[u8], uri: &[u8]) {
+        let link = if uri.is_empty() {
+            0
+        } else {
+            let id = String::from_utf8_lossy(params)
+                .split(':')
+                .find_map(|param| param.strip_prefix("id="))
+                .unwrap_or_default()
+                .to_string();
+            let target = (id, String::from_utf8_lossy(uri).into_owned());
+            match self.hyperlinks.iter().rposition(|known| *known == target) {
+                Some(index) => index + 1,
+                None if self.hyperlinks.len() < usize::from(u16::MAX) => {
+                    self.hyperlinks.push(target);
+                    self.hyperlinks.len()
+                }
+                None => {
+                    log::debug!("Too many hyperlinks, ignoring {:?}", target.1);
+                    0
+                }
+            }
+        };
+        self.grid_mut().cursor.template.link = link as u16;
+    }
+
+    /// Shell integration (OSC 133): `A` starts a prompt on the cursor's
+    /// line; `B` ends the prompt, so the command is typed on the cursor's
+    /// line; `D;<status>` reports how it exited, and marks the line of the
+    /// last command typed.
+    fn shell_mark(&mut self, params: &[&[u8]]) {
+        let grid = self.grid_mut();
+        let line = grid.history_len() + grid.cursor.row;
+        match params {
+            [b"A", ..] => grid.line_mut(line).prompt = true,
+            [b"B", ..] => grid.line_mut(line).command = Some(CommandStatus::Running),
+            [b"D", status @ ..] => {
+                let failed = status
+                    .first()
+                    .and_then(|status| std::str::from_utf8(status).ok())
+                    .and_then(|status| status.parse::<i32>().ok())
+                    .is_some_and(|status| status != 0);
+                // Lines of commands that already ended stop the search
+                let typed = (0..=line)
+                    .rev()
+                    .find(|&line| grid.line(line).command.is_some())
+                    .filter(|&line| grid.line(line).command == Some(CommandStatus::Running));
+                if let Some(typed) = typed {
+                    grid.line_mut(typed).command = Some(if failed {
+                        CommandStatus::Failed
+                    } else {
+                        CommandStatus::Succeeded
+                    });
+                }
+            }
+            _ => {} // Output starts
+        }
+    }
+
+    fn device_status(&mut self, kind: u16) {
+        match kind {
+            5 => self.responses.extend_from_slice(b"\x1b[0n"),
+            6 => {
+                let cursor = &self.grid().cursor;
+                let mut row = cursor.row;
+                if self.mode.contains(Mode::ORIGIN) {
+                    // DECRC can leave the cursor above the region
+                    row = row.saturating_sub(self.grid().scroll_region().0);
+                }
+                let reply = format!("\x1b[{};{}R", row + 1, cursor.col + 1);
+                self.responses.extend_from_slice(reply.as_bytes());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// `%XX` escapes of a URL path decoded, leaving malformed ones as they are.
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(hex_decode);
+        match escaped {
+            Some(byte) => {
+                decoded.extend(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Upper case hex digits of `bytes`, as XTGETTCAP replies use.
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+/// The intermediate bytes and final character of a sequence, space-separated:
+/// `? u`, `$ q`.
+fn spaced(intermediates: &[u8], action: char) -> String {
+    let chars = intermediates
+        .iter()
+        .map(|&byte| byte as char)
+        .chain([action]);
+    chars.map(String::from).collect::<Vec<_>>().join(" ")
+}
+
+/// The bytes between the introducer and the final character of a CSI or
+/// DCS: private markers, then the parameters, then the other intermediates.
+fn params_text(params: &Params, intermediates: &[u8]) -> String {
+    let (markers, rest): (Vec<u8>, Vec<u8>) = intermediates
+        .iter()
+        .partition(|&&byte| (0x3C..=0x3F).contains(&byte));
+    let values: Vec<String> = params
+        .iter()
+        .map(|group| {
+            group
+                .iter()
+                .map(u16::to_string)
+                .collect::<Vec<_>>()
+                .join(":")
+        })
+        .collect();
+    format!(
+        "{}{}{}",
+        String::from_utf8_lossy(&markers),
+        values.join(";"),
+        String::from_utf8_lossy(&rest)
+    )
+}
+
+/// The bytes spelled by pairs of hex digits, either case.
+fn hex_decode(hex: &[u8]) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    hex.chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+/// Parse the color following SGR 38/48, in either the colon form
+/// (`38:2::r:g:b`, `38:5:n`) or the legacy semicolon form (`38;2;r;g;b`).
+fn parse_extended_color(group: &[u16], groups: &[&[u16]], i: &mut usize) -> Option<Color> {
+    if group.len() > 1 {
+        return match group[1] {
+            5 => group.get(2).map(|&n| Color::Indexed(n as u8)),
+            2 => {
+                // 38:2:<colorspace>:r:g:b, with the colorspace often omitted
+                let rgb = if group.len() >= 6 {
+                    &group[3..6]
+                } else {
+                    group.get(2..5)?
+                };
+                Some(Color::Rgb(rgb[0] as u8, rgb[1] as u8, rgb[2] as u8))
+            }
+            _ => None,
+        };
+    }
+
+    let kind = groups.get(*i)?[0];
+    *i += 1;
+    match kind {
+        5 => {
+            let n = groups.get(*i)?[0];
+            *i += 1;
+            Some(Color::Indexed(n as u8))
+        }
+        2 => {
+            let rgb = groups.get(*i..*i + 3)?;
+            *i += 3;
+            Some(Color::Rgb(
+                rgb[0][0] as u8,
+                rgb[1][0] as u8,
+                rgb[2][0] as u8,
+            ))
+        }
+        _ => None,
+    }
+}
+
+impl Perform for Terminal {
+    fn print(&mut self, c: char) {
+        self.print_char(c);
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            0x07 => self.events.push(Event::Bell),
+            0x08 => self.grid_mut().backspace(),
+            0x09 => self.grid_mut().tab_forward(1),
+            0x0A..=0x0C => {
+                let newline = self.mode.contains(Mode::LINE_FEED_NEW_LINE);
+                let grid = self.grid_mut();
+                grid.linefeed();
+                if newline {
+                    grid.carriage_return();
+                }
+            }
+            0x0D => self.grid_mut().carriage_return(),
+            0x0E => self.active_charset = 1,
+            0x0F => self.active_charset = 0,
+            _ => {}
+        }
+    }
+
+    fn csi_dispatch(&mut self, params: &Params, intermediates: &[u8], ignore: bool, action: char) {
+        if ignore {
+            return;
+        }
+
+        let arg = |i: usize| params.get_or(i, 1) as usize;
+        let private = intermediates.first() == Some(&b'?');
+
+        match (action, intermediates) {
+            ('@', []) => self.grid_mut().insert_blank(arg(0)),
+            ('A', []) => self.grid_mut().move_up(arg(0)),
+            ('B', []) | ('e', []) => self.grid_mut().move_down(arg(0)),
+            ('C', []) | ('a', []) => self.grid_mut().move_forward(arg(0)),
+            ('D', []) => self.grid_mut().move_back(arg(0)),
+            ('E', []) => {
+                let grid = self.grid_mut();
+                grid.move_down(arg(0));
+                grid.carriage_return();
+            }
+            ('F', []) => {
+                let grid = self.grid_mut();
+                grid.move_up(arg(0));
+                grid.carriage_return();
+            }
+            ('G', []) | ('`', []) => {
+                let row = self.grid().cursor.row;
+                self.grid_mut().goto(row, arg(0) - 1);
+            }
+            ('H', []) | ('f', []) => self.goto_origin(arg(0) - 1, arg(1) - 1),
+            ('I', []) => self.grid_mut().tab_forward(arg(0)),
+            ('J', _) => match params.get(0).unwrap_or(0) {
+                3 if !self.scrollback_options.erase_saved_lines => {}
+                mode => self.grid_mut().erase_display(mode),
+            },
+            ('K', _) => self.grid_mut().erase_line(params.get(0).unwrap_or(0)),
+            ('L', []) => self.grid_mut().insert_lines(arg(0)),
+            ('M', []) => self.grid_mut().delete_lines(arg(0)),
+            ('P', []) => self.grid_mut().delete_chars(arg(0)),
+            ('S', []) => self.grid_mut().scroll_up(arg(0)),
+            ('T', []) => self.grid_mut().scroll_down(arg(0)),
+            ('X', []) => self.grid_mut().erase_chars(arg(0)),
+            ('Z', []) => self.grid_mut().tab_backward(arg(0)),
+            ('b', []) => {
+                if let Some(c) = self.last_char {
+                    for _ in 0..arg(0) {
+                        self.print_char(c);
+                    }
+                }
+            }
+            ('c', []) => {
+                // Primary DA: VT220 with ANSI color
+                self.responses.extend_from_slice(b"\x1b[?62;22c");
+            }
+            ('c', [b'>']) => {
+                // Secondary DA
+                self.responses.extend_from_slice(b"\x1b[>1;10;0c");
+            }
+            ('d', []) => {
+                let col = self.grid().cursor.col;
+                self.goto_origin(arg(0) - 1, col);
+            }
+            ('g', []) => match params.get(0).unwrap_or(0) {
+                0 => self.grid_mut().clear_tab_stops(false),
+                3 => self.grid_mut().clear_tab_stops(true),
+                _ => {}
+            },
+            ('h', _) | ('l', _) => {
+                let enable = action == 'h';
+                for group in params.iter() {
+                    if private {
+                        self.set_private_mode(group[0], enable);
+                    } else {
+                        self.set_mode(group[0], enable);
+                    }
+                }
+            }
+            ('m', []) => self.sgr(params),
+            ('n', []) => self.device_status(params.get(0).unwrap_or(0)),
+            ('q', [b' ']) => {
+                let (shape, blinking) = match params.get(0).unwrap_or(0) {
+                    0 | 1 => (CursorShape::Block, true),
+                    2 => (CursorShape::Block, false),
+                    3 => (CursorShape::Underline, true),
+                    4 => (CursorShape::Underline, false),
+                    5 => (CursorShape::Beam, true),
+                    6 => (CursorShape::Beam, false),
+                    _ => return,
+                };
+                self.cursor_style = CursorStyle { shape, blinking };
+            }
+            ('r', []) => {
+                let rows = self.rows();
+                let top = arg(0) - 1;
+                let bottom = params.get_or(1, rows as u16) as usize;
+                self.grid_mut().set_scroll_region(top, bottom);
+                self.goto_origin(0, 0);
+            }
+            ('p', [b'!']) => self.soft_reset(),
+            ('s', []) => self.save_cursor(),
+            ('u', []) => self.restore_cursor(),
+            ('{' | 'p', [b'#']) => self.push_sgr(params),
+            ('}' | 'q', [b'#']) => self.pop_sgr(),
+            _ => self.report_unhandled(
+                format!("CSI {}", spaced(intermediates, action)),
+                format!("\x1b[{}{}", params_text(params, intermediates), action),
+            ),
+        }
+    }
+
+    fn esc_dispatch(&mut self, intermediates: &[u8], ignore: bool, byte: u8) {
+        if ignore {
+            return;
+        }
+
+        match (byte, intermediates) {
+            (b'7', []) => self.save_cursor(),
+            (b'8', []) => self.restore_cursor(),
+            (b'8', [b'#']) => {
+                let grid = self.grid_mut();
+                let rows = grid.rows();
+                grid.fill('E');
+                grid.set_scroll_region(0, rows);
+                grid.goto(0, 0);
+            }
+            (b'D', []) => self.grid_mut().linefeed(),
+            (b'E', []) => {
+                let grid = self.grid_mut();
+                grid.linefeed();
+                grid.carriage_return();
+            }
+            (b'H', []) => self.grid_mut().set_tab_stop(),
+            (b'M', []) => self.grid_mut().reverse_index(),
+            (b'N', []) => self.single_shift = Some(2),
+            (b'O', []) => self.single_shift = Some(3),
+            (b'c', []) => self.reset(),
+            (b'=', []) => self.mode.insert(Mode::APP_KEYPAD),
+            (b'>', []) => self.mode.remove(Mode::APP_KEYPAD),
+            (b'0' | b'B', [slot @ (b'(' | b')' | b'*' | b'+')]) => {
+                let charset = if byte == b'0' {
+                    Charset::DecSpecialGraphics
+                } else {
+                    Charset::Ascii
+                };
+                self.charsets[(*slot - b'(') as usize] = charset;
+            }
+            (b'\\', []) => {} // ST, already handled by the parser
+            _ => self.report_unhandled(
+                format!("ESC {}", spaced(intermediates, byte as char)),
+                [&[0x1b], intermediates, &[byte]].concat(),
+            ),
+        }
+    }
+
+    fn osc_dispatch(&mut self, params: &[&[u8]], bell_terminated: bool) {
+        match params {
+            [b"0" | b"2", title @ ..] => {
+                let title = String::from_utf8_lossy(&title.join(&b';')).into_owned();
+                self.title = Some(title.clone());
+                if self.user_title.is_none() {
+                    self.events.push(Event::Title(title));
+                }
+            }
+            [b"1", ..] => {} // Icon name
+            [b"133", mark @ ..] => self.shell_mark(mark),
+            [b"7", url @ ..] => self.set_location(&url.join(&b';')),
+            [b"8", params, uri @ ..] => self.set_hyperlink(params, &uri.join(&b';')),
+            [b"1337", setting] => {
+                if let Some(host) = setting.strip_prefix(b"RemoteHost=") {
+                    self.set_host(&String::from_utf8_lossy(host));
+                } else if let Some(directory) = setting.strip_prefix(b"CurrentDir=") {
+                    self.directory = Some(String::from_utf8_lossy(directory).into_owned());
+                }
+            }
+            [b"4", pairs @ ..] => {
+                for pair in pairs.chunks_exact(2) {
+                    if let Some(index) = std::str::from_utf8(pair[0])
+                        .ok()
+                        .and_then(|index| index.parse().ok())
+                    {
+                        self.set_color(ColorSlot::Indexed(index), pair[1]);
+                    }
+                }
+            }
+            // Each further color is for the next slot, as in xterm
+            [number @ (b"10" | b"11" | b"12"), specs @ ..] => {
+                let slots = [
+                    ColorSlot::Foreground,
+                    ColorSlot::Background,
+                    ColorSlot::Cursor,
+                ];
+                let first = (number[1] - b'0') as usize;
+                for (&slot, spec) in slots[first..].iter().zip(specs) {
+                    self.set_color(slot, spec);
+                }
+            }
+            [b"104"] | [b"104", b""] => {
+                for index in 0..=255 {
+                    self.colors.set(ColorSlot::Indexed(index), None);
+                }
+            }
+            [b"104", indices @ ..] => {
+                for index in indices {
+                    if let Some(index) = std::str::from_utf8(index)
+                        .ok()
+                        .and_then(|index| index.parse().ok())
+                    {
+                        self.colors.set(ColorSlot::Indexed(index), None);
+                    }
+                }
+            }
+            [b"110", ..] => self.colors.set(ColorSlot::Foreground, None),
+            [b"111", ..] => self.colors.set(ColorSlot::Background, None),
+            [b"112", ..] => self.colors.set(ColorSlot::Cursor, None),
+            _ => {
+                let first = String::from_utf8_lossy(params.first().copied().unwrap_or_default());
+                let end: &[u8] = if bell_terminated { b"\x07" } else { b"\x1b\\" };
+                let sample = [b"\x1b]", &params.join(&b';')[..], end].concat();
+                self.report_unhandled(format!("OSC {}", first), sample);
+            }
+        }
+    }
+
+    fn hook(&mut self, params: &Params, intermediates: &[u8], ignore: bool, action: char) {
+        self.dcs = match (action, intermediates) {
+            ('q', [b'+']) if !ignore => Some(Dcs::Termcap(Vec::new())),
+            ('t', []) if !ignore => Some(Dcs::Tmux(Vec::new())),
+            _ if ignore => None,
+            _ => {
+                self.report_unhandled(
+                    format!("DCS {}", spaced(intermediates, action)),
+                    format!("\x1bP{}{}", params_text(params, intermediates), action),
+                );
+                None
+            }
+        };
+    }
+
+    fn put(&mut self, byte: u8) {
+        match &mut self.dcs {
+            Some(Dcs::Termcap(payload)) if payload.len() < MAX_DCS_LEN => payload.push(byte),
+            Some(Dcs::Tmux(payload)) if payload.len() < MAX_PASSTHROUGH_LEN => payload.push(byte),
+            // Cut short, it would run half a sequence
+            Some(Dcs::Tmux(_)) => self.dcs = None,
+            _ => {}
+        }
+    }
+
+    fn unhook(&mut self) {
+        match self.dcs.take() {
+            Some(Dcs::Termcap(payload)) => self.request_termcap(&payload),
+            // We are the outer terminal: run what tmux would pass on
+            Some(Dcs::Tmux(payload)) => match payload.strip_prefix(b"mux;") {
+                Some(inner) => Parser::new().advance(self, inner),
+                None => self.report_unhandled("DCS t".into(), [b"\x1bPt", &payload[..]].concat()),
+            },
+            None => {}
+        }
+    }
+
+    fn cancel(&mut self) {
+        self.dcs = None;
+    }
+}
@@ -0,0 +1,49 @@
+//! Terminal modes set with SM/RM and DECSET/DECRST.
+
+use bitflags::bitflags;
+
+bitflags! {
+    /// Terminal mode flags.
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub struct Mode: u32 {
+        /// DECCKM (?1): cursor keys send SS3 sequences
+        const APP_CURSOR          = 1 << 0;
+        /// DECOM (?6): cursor addressing relative to the scroll region
+        const ORIGIN              = 1 << 1;
+        /// DECAWM (?7): wrap at the right margin
+        const AUTOWRAP            = 1 << 2;
+        /// DECTCEM (?25): cursor visible
+        const SHOW_CURSOR         = 1 << 3;
+        /// ?1049 / ?1047 / ?47: the alternate screen is active
+        const ALT_SCREEN          = 1 << 4;
+        /// ?1000: report button presses
+        const MOUSE_REPORT_CLICK  = 1 << 5;
+        /// ?1002: report motion while a button is held
+        const MOUSE_DRAG          = 1 << 6;
+        /// ?1003: report all motion
+        const MOUSE_MOTION        = 1 << 7;
+        /// ?1006: SGR-encoded mouse reports
+        const SGR_MOUSE           = 1 << 8;
+        /// ?2004: wrap pastes in ESC[200~ / ESC[201~
+        const BRACKETED_PASTE     = 1 << 9;
+        /// IRM (4): insert characters instead of overwriting
+        const INSERT              = 1 << 10;
+        /// LNM (20): LF also performs CR
+        const LINE_FEED_NEW_LINE  = 1 << 11;
+        /// DECKPAM: keypad sends application sequences
+        const APP_KEYPAD          = 1 << 12;
+        /// ?1004: report focus changes with ESC[I / ESC[O
+        const FOCUS_EVENTS        = 1 << 13;
+
+        /// Any mouse tracking mode
+        const MOUSE_MODE = Self::MOUSE_REPORT_CLICK.bits()
+            | Self::MOUSE_DRAG.bits()
+            | Self::MOUSE_MOTION.bits();
+    }
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Mode::AUTOWRAP | Mode::SHOW_CURSOR
+    }
+}
@@ -0,0 +1,205 @@
+//! Terminal Snapshots
+//!
+//! [`Terminal::snapshot`] writes a terminal's state out as the escape
+//! sequences that rebuild it: the scrollback and screen with their
+//! colors, the alternate screen, modes, cursor, title and session colors.
+//! A detached session (see `crate::session`) sends it to each window
+//! that attaches, so the window shows what the session's programs drew
+//! while nobody was watching.
+//!
+//! ```
+//! use adamant::term::Terminal;
+//!
+//! let mut term = Terminal::new(20, 3, 100);
+//! term.feed(b"\x1b[31mred\x1b[0m\r\nplain\x1b]2;build\x07");
+//!
+//! let mut copy = Terminal::new(20, 3, 100);
+//! copy.feed(&term.snapshot());
+//! assert_eq!(copy.grid().row(0).text(), "red");
+//! assert_eq!(copy.grid().row(0).cells()[0].fg, term.grid().row(0).cells()[0].fg);
+//! assert_eq!(copy.grid().cursor.col, 5);
+//! assert_eq!(copy.title(), Some("build"));
+//! ```
+
+use std::fmt::Write;
+
+use super::{ColorSlot, CursorShape, Mode, Terminal};
+use crate::config::Rgb;
+use crate::grid::{Cell, Color, Flags, Row};
+
+/// Private modes restored by DECSET, in the order they are set.
+const PRIVATE_MODES: [(Mode, u16); 8] = [
+    (Mode::APP_CURSOR, 1),
+    (Mode::MOUSE_REPORT_CLICK, 1000),
+    (Mode::MOUSE_DRAG, 1002),
+    (Mode::MOUSE_MOTION, 1003),
+    (Mode::FOCUS_EVENTS, 1004),
+    (Mode::SGR_MOUSE, 1006),
+    (Mode::BRACKETED_PASTE, 2004),
+    (Mode::ORIGIN, 6),
+];
+
+impl Terminal {
+    /// Escape sequences that bring a terminal of the same size to this
+    /// one's state, starting with a full reset. Not restored: the saved
+    /// cursor, character sets, tab stops and a pending wrap.
+    pub fn snapshot(&self) -> Vec<u8> {
+        let mut out = String::from("\x1bc");
+
+        // Every line from the oldest, so the older ones scroll into the
+        // scrollback
+        let mut pen = Cell::default();
+        for line in 0..self.primary.total_lines() {
+            if line > 0 && !self.primary.line(line - 1).wrapped {
+                out.push_str("\r\n");
+            }
+            write_row(&mut out, self.primary.line(line), &mut pen);
+        }
+        if self.mode.contains(Mode::ALT_SCREEN) {
+            out.push_str("\x1b[0m\x1b[?1049h");
+            pen = Cell::default();
+            for row in 0..self.alternate.rows() {
+                let _ = write!(out, "\x1b[{}H", row + 1);
+                write_row(&mut out, self.alternate.row(row), &mut pen);
+            }
+        }
+
+        let grid = self.grid();
+        let (top, bottom) = grid.scroll_region();
+        if (top, bottom) != (0, grid.rows()) {
+            let _ = write!(out, "\x1b[{};{}r", top + 1, bottom);
+        }
+        for (mode, number) in PRIVATE_MODES {
+            if self.mode.contains(mode) {
+                let _ = write!(out, "\x1b[?{}h", number);
+            }
+        }
+        for (mode, number) in [(Mode::INSERT, 4), (Mode::LINE_FEED_NEW_LINE, 20)] {
+            if self.mode.contains(mode) {
+                let _ = write!(out, "\x1b[{}h", number);
+            }
+        }
+        if self.mode.contains(Mode::APP_KEYPAD) {
+            out.push_str("\x1b=");
+        }
+        if !self.mode.contains(Mode::AUTOWRAP) {
+            out.push_str("\x1b[?7l");
+        }
+        if !self.mode.contains(Mode::SHOW_CURSOR) {
+            out.push_str("\x1b[?25l");
+        }
+        let shape = match self.cursor_style.shape {
+            CursorShape::Block => 1,
+            CursorShape::Underline => 3,
+            CursorShape::Beam => 5,
+        };
+        let _ = write!(
+            out,
+            "\x1b[{} q",
+            shape + u8::from(!self.cursor_style.blinking)
+        );
+
+        // With DECOM the row counts from the top of the scroll region
+        let origin = if self.mode.contains(Mode::ORIGIN) {
+            top
+        } else {
+            0
+        };
+        let _ = write!(
+            out,
+            "\x1b[{};{}H",
+            grid.cursor.row - origin + 1,
+            grid.cursor.col + 1
+        );
+        out.push_str(&sgr(&grid.cursor.template));
+
+        if let Some(title) = &self.title {
+            let _ = write!(out, "\x1b]2;{}\x07", title);
+        }
+        for (index, rgb) in self.colors.indexed() {
+            let _ = write!(out, "\x1b]4;{};{}\x07", index, hex(rgb));
+        }
+        for (slot, number) in [
+            (ColorSlot::Foreground, 10),
+            (ColorSlot::Background, 11),
+            (ColorSlot::Cursor, 12),
+        ] {
+            if let Some(rgb) = self.colors.get(slot) {
+                let _ = write!(out, "\x1b]{};{}\x07", number, hex(rgb));
+            }
+        }
+        if let Some(host) = &self.host {
+            let directory = self.directory.as_deref().unwrap_or("/");
+            let _ = write!(out, "\x1b]7;file://{}{}\x07", host, directory);
+        }
+        out.into_bytes()
+    }
+}
+
+/// The cells of `row`, switching the rendition where it changes. Blanks
+/// at the end are left out unless the row wraps into the next one.
+fn write_row(out: &mut String, row: &Row, pen: &mut Cell) {
+    let cells = row.cells();
+    let end = if row.wrapped {
+        cells.len()
+    } else {
+        cells
+            .iter()
+            .rposition(|cell| !is_blank(cell))
+            .map_or(0, |last| last + 1)
+    };
+    for cell in &cells[..end] {
+        if cell.flags.contains(Flags::WIDE_SPACER) {
+            continue;
+        }
+        if (cell.fg, cell.bg, rendition(cell.flags)) != (pen.fg, pen.bg, rendition(pen.flags)) {
+            out.push_str(&sgr(cell));
+            *pen = cell.clone();
+        }
+        out.push(cell.c);
+    }
+}
+
+/// A cell that erasing leaves behind.
+fn is_blank(cell: &Cell) -> bool {
+    cell.c == ' ' && cell.bg == Color::Default && rendition(cell.flags).is_empty()
+}
+
+/// The flags SGR sets, without the layout markers.
+fn rendition(flags: Flags) -> Flags {
+    flags - (Flags::WIDE | Flags::WIDE_SPACER)
+}
+
+/// SGR selecting the rendition of `cell` from scratch.
+fn sgr(cell: &Cell) -> String {
+    let mut params = vec!["0".to_string()];
+    for (flag, number) in [
+        (Flags::BOLD, 1),
+        (Flags::DIM, 2),
+        (Flags::ITALIC, 3),
+        (Flags::UNDERLINE, 4),
+        (Flags::BLINK, 5),
+        (Flags::INVERSE, 7),
+        (Flags::HIDDEN, 8),
+        (Flags::STRIKEOUT, 9),
+    ] {
+        if cell.flags.contains(flag) {
+            params.push(number.to_string());
+        }
+    }
+    for (color, base) in [(cell.fg, 30), (cell.bg, 40)] {
+        match color {
+            Color::Default => {}
+            Color::Indexed(n @ 0..=7) => params.push((base + n).to_string()),
+            Color::Indexed(n @ 8..=15) => params.push((base + 60 + n - 8).to_string()),
+            Color::Indexed(n) => params.push(format!("{};5;{}", base + 8, n)),
+            Color::Rgb(r, g, b) => params.push(format!("{};2;{};{};{}", base + 8, r, g, b)),
+        }
+    }
+    format!("\x1b[{}m", params.join(";"))
+}
+
+/// `rgb` as OSC color sequences spell it.
+fn hex(rgb: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb.0, rgb.1, rgb.2)
+}
@@ -0,0 +1,82 @@
+//! Unhandled Sequences
+//!
+//! Every escape sequence, mode and SGR attribute the terminal doesn't
+//! implement is counted with a sample of its bytes, so users can report
+//! exactly what a program needed. Read them with `get-unhandled` over IPC
+//! or "Show unhandled sequences" in the palette.
+//!
+//! ```
+//! use adamant::term::{Terminal, escape_bytes};
+//!
+//! let mut term = Terminal::new(80, 24, 0);
+//! term.feed(b"\x1b[?2026h\x1b[?2026l\x1b[6 q\x1b[5i");
+//! let unhandled = term.unhandled();
+//! assert_eq!(unhandled[0].sequence, "DEC mode 2026");
+//! assert_eq!(unhandled[0].count, 2);
+//! assert_eq!(escape_bytes(&unhandled[0].sample), "\\e[?2026h");
+//! assert_eq!(unhandled[1].sequence, "CSI i");
+//! ```
+
+/// Kinds of sequences kept at most; more are only logged.
+const MAX_KINDS: usize = 64;
+
+/// Bytes kept of a sample.
+const MAX_SAMPLE_LEN: usize = 64;
+
+/// A sequence the terminal doesn't implement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Unhandled {
+    /// What it is, without the parameters that don't change that: `CSI ? u`,
+    /// `DEC mode 2026`, `SGR 6`, `OSC 1337`
+    pub sequence: String,
+    /// How many times it was received
+    pub count: u64,
+    /// The bytes of the first one, rebuilt from what was parsed and cut
+    /// to 64 bytes
+    pub sample: Vec<u8>,
+}
+
+impl Unhandled {
+    /// One line of the report: the count, the sequence and the sample,
+    /// tab-separated.
+    pub fn line(&self) -> String {
+        format!(
+            "{}\t{}\t{}",
+            self.count,
+            self.sequence,
+            escape_bytes(&self.sample)
+        )
+    }
+}
+
+/// Count `sequence` in `unhandled`, keeping `sample` if it is the first.
+pub(super) fn record(unhandled: &mut Vec<Unhandled>, sequence: String, sample: &[u8]) {
+    log::debug!("Unhandled {}: {}", sequence, escape_bytes(sample));
+    if let Some(entry) = unhandled
+        .iter_mut()
+        .find(|entry| entry.sequence == sequence)
+    {
+        entry.count += 1;
+    } else if unhandled.len() < MAX_KINDS {
+        unhandled.push(Unhandled {
+            sequence,
+            count: 1,
+            sample: sample[..sample.len().min(MAX_SAMPLE_LEN)].to_vec(),
+        });
+    }
+}
+
+/// `bytes` in printable ASCII: ESC as `\e`, a backslash doubled, other
+/// bytes outside `' '..='~'` as `\xNN`.
+pub fn escape_bytes(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len());
+    for &byte in bytes {
+        match byte {
+            0x1b => text.push_str("\\e"),
+            b'\\' => text.push_str("\\\\"),
+            b' '..=b'~' => text.push(byte as char),
+            _ => text.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+    text
+}
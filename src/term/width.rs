@@ -0,0 +1,195 @@
+//! Character widths.
+//!
+//! How many columns a character takes is mostly fixed by Unicode (UAX #11),
+//! except for two groups where terminals and applications disagree:
+//!
+//! - East Asian Ambiguous characters (Greek and Cyrillic letters, `①`,
+//!   `○`, `※`, box drawing) are narrow in Western locales and wide in CJK
+//!   ones.
+//! - Emoji whose default presentation is text (`☺`, `❤`, `✔`, `☀`) are one
+//!   column by the standard, but many fonts draw them as two-column color
+//!   emoji.
+//!
+//! Programs size lines with `wcwidth()` from their locale, so the terminal
+//! has to agree with them or the cursor drifts from the text. The policy
+//! decides where the cursor moves when printing, so cursor position reports
+//! (`CSI 6 n`) always match what is on screen.
+//!
+//! ```
+//! use adamant::term::{Terminal, WidthOptions};
+//!
+//! let mut term = Terminal::new(10, 2, 0);
+//! term.set_width_options(WidthOptions {
+//!     ambiguous_wide: true,
+//!     ..WidthOptions::default()
+//! });
+//! term.feed("α\x1b[6n".as_bytes());
+//! assert_eq!(term.take_responses(), b"\x1b[1;3R");
+//! ```
+
+use unicode_width::UnicodeWidthChar;
+
+/// The width policy for characters Unicode leaves open.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct WidthOptions {
+    /// East Asian Ambiguous characters take two columns, as in CJK setups
+    /// (Vim's `ambiwidth=double`, a CJK `wcwidth()` table). This also
+    /// widens the box drawing characters.
+    pub ambiguous_wide: bool,
+    /// Emoji without a variation selector that default to text
+    /// presentation take two columns.
+    pub emoji_wide: bool,
+}
+
+impl WidthOptions {
+    /// Columns taken by `c`: 0 for combining and control characters.
+    pub fn width(&self, c: char) -> usize {
+        let width = if self.ambiguous_wide {
+            c.width_cjk()
+        } else {
+            c.width()
+        };
+        match width.unwrap_or(0) {
+            1 if self.ambiguous_wide && is_ambiguous_letter(c) => 2,
+            1 if self.emoji_wide && is_text_emoji(c) => 2,
+            width => width,
+        }
+    }
+
+    /// Columns taken by `text`.
+    pub fn str_width(&self, text: &str) -> usize {
+        text.chars().map(|c| self.width(c)).sum()
+    }
+}
+
+/// Greek and Cyrillic letters, which are Ambiguous but which `width_cjk`
+/// keeps narrow because it exempts letters. CJK fonts draw them full width.
+/// Accented Latin letters stay narrow, like in most CJK terminals.
+fn is_ambiguous_letter(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x391..=0x3A1 | 0x3A3..=0x3A9 | 0x3B1..=0x3C1 | 0x3C3..=0x3C9 | 0x401 | 0x410..=0x44F | 0x451
+    )
+}
+
+/// Emoji that are shown as text unless followed by U+FE0F (`Emoji=Yes`,
+/// `Emoji_Presentation=No`), without the keycap bases `#*0-9` and `©®`.
+fn is_text_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x203C
+            | 0x2049
+            | 0x2122
+            | 0x2139
+            | 0x2194..=0x2199
+            | 0x21A9..=0x21AA
+            | 0x2328
+            | 0x23CF
+            | 0x23ED..=0x23EF
+            | 0x23F1..=0x23F2
+            | 0x23F8..=0x23FA
+            | 0x24C2
+            | 0x25AA..=0x25AB
+            | 0x25B6
+            | 0x25C0
+            | 0x25FB..=0x25FC
+            | 0x2600..=0x2604
+            | 0x260E
+            | 0x2611
+            | 0x2618
+            | 0x261D
+            | 0x2620
+            | 0x2622..=0x2623
+            | 0x2626
+            | 0x262A
+            | 0x262E..=0x262F
+            | 0x2638..=0x263A
+            | 0x2640
+            | 0x2642
+            | 0x265F..=0x2660
+            | 0x2663
+            | 0x2665..=0x2666
+            | 0x2668
+            | 0x267B
+            | 0x267E
+            | 0x2692
+            | 0x2694..=0x2697
+            | 0x2699
+            | 0x269B..=0x269C
+            | 0x26A0
+            | 0x26A7
+            | 0x26B0..=0x26B1
+            | 0x26C8
+            | 0x26CF
+            | 0x26D1
+            | 0x26D3
+            | 0x26E9
+            | 0x26F0..=0x26F1
+            | 0x26F4
+            | 0x26F7..=0x26F9
+            | 0x2702
+            | 0x2708..=0x2709
+            | 0x270C..=0x270D
+            | 0x270F
+            | 0x2712
+            | 0x2714
+            | 0x2716
+            | 0x271D
+            | 0x2721
+            | 0x2733..=0x2734
+            | 0x2744
+            | 0x2747
+            | 0x2763..=0x2764
+            | 0x27A1
+            | 0x2934..=0x2935
+            | 0x2B05..=0x2B07
+            | 0x3030
+            | 0x303D
+            | 0x3297
+            | 0x3299
+            | 0x1F170..=0x1F171
+            | 0x1F17E..=0x1F17F
+            | 0x1F202
+            | 0x1F237
+            | 0x1F321
+            | 0x1F324..=0x1F32C
+            | 0x1F336
+            | 0x1F37D
+            | 0x1F396..=0x1F397
+            | 0x1F399..=0x1F39B
+            | 0x1F39E..=0x1F39F
+            | 0x1F3CB..=0x1F3CE
+            | 0x1F3D4..=0x1F3DF
+            | 0x1F3F3
+            | 0x1F3F5
+            | 0x1F3F7
+            | 0x1F43F
+            | 0x1F441
+            | 0x1F4FD
+            | 0x1F549..=0x1F54A
+            | 0x1F56F..=0x1F570
+            | 0x1F573..=0x1F579
+            | 0x1F587
+            | 0x1F58A..=0x1F58D
+            | 0x1F590
+            | 0x1F5A5
+            | 0x1F5A8
+            | 0x1F5B1..=0x1F5B2
+            | 0x1F5BC
+            | 0x1F5C2..=0x1F5C4
+            | 0x1F5D1..=0x1F5D3
+            | 0x1F5DC..=0x1F5DE
+            | 0x1F5E1
+            | 0x1F5E3
+            | 0x1F5E8
+            | 0x1F5EF
+            | 0x1F5F3
+            | 0x1F5FA
+            | 0x1F6CB
+            | 0x1F6CD..=0x1F6CF
+            | 0x1F6E0..=0x1F6E5
+            | 0x1F6E9
+            | 0x1F6F0
+            | 0x1F6F3
+    )
+}
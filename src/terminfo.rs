@@ -0,0 +1,281 @@
+//! Terminfo Entry
+//!
+//! Adamant ships its own terminfo description ([`SOURCE`], from
+//! `terminfo/adamant.terminfo`) so ncurses programs use the capabilities
+//! it really has instead of xterm's. Children get `TERM=adamant` only once
+//! the entry can be found; [`term`] compiles it into `~/.terminfo` with
+//! `tic` on first use and falls back to [`FALLBACK`] when that isn't
+//! possible (no `tic`, read-only home), so nothing breaks on a fresh
+//! machine.
+//!
+//! The terminal answers XTGETTCAP queries from the same source, through
+//! [`capability`], so programs that ask the terminal directly (tmux,
+//! neovim) see what ncurses would read from the compiled entry.
+//!
+//! Entries live in a directory per first letter of the name; macOS uses the
+//! letter's hex code instead, so both are looked up:
+//!
+//! ```
+//! use adamant::terminfo;
+//!
+//! let dir = std::env::temp_dir().join(format!("adamant-doc-{}", std::process::id()));
+//! std::fs::create_dir_all(dir.join("61")).unwrap();
+//! std::fs::write(dir.join("61/adamant"), b"").unwrap();
+//! assert_eq!(terminfo::find_in(&[dir.clone()], "adamant"), Some(dir.join("61/adamant")));
+//! assert_eq!(terminfo::find_in(&[dir.clone()], "xterm"), None);
+//! # std::fs::remove_dir_all(dir).unwrap();
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+use crate::error::Result;
+
+/// Name of Adamant's terminfo entry.
+pub const NAME: &str = "adamant";
+
+/// `TERM` for children when the entry isn't installed.
+pub const FALLBACK: &str = "xterm-256color";
+
+/// The terminfo source, for `tic`.
+pub const SOURCE: &str = include_str!("../terminfo/adamant.terminfo");
+
+/// A capability of the entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Capability {
+    /// A boolean that is set
+    Flag,
+    Number(u32),
+    /// The string with its escapes (`\E`, `^X`, octal) resolved;
+    /// parameters (`%p1%d`) are left for the program to expand
+    String(Vec<u8>),
+}
+
+/// Look up capability `name` (its terminfo name, e.g. `smcup` or `Tc`)
+/// in [`SOURCE`]. `TN` is the terminal name, and `RGB` answers
+/// `8/8/8` for direct colors, as xterm does.
+///
+/// ```
+/// use adamant::terminfo::{Capability, capability};
+///
+/// assert_eq!(capability("colors"), Some(Capability::Number(256)));
+/// assert_eq!(capability("smcup"), Some(Capability::String(b"\x1b[?1049h".to_vec())));
+/// assert_eq!(capability("bce"), Some(Capability::Flag));
+/// assert_eq!(capability("hs"), None);
+/// ```
+pub fn capability(name: &str) -> Option<Capability> {
+    match name {
+        "TN" | "name" => return Some(Capability::String(NAME.into())),
+        "RGB" => return Some(Capability::String(b"8/8/8".to_vec())),
+        _ => {}
+    }
+    let source: String = SOURCE
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect();
+    fields(&source).skip(1).find_map(|field| {
+        if let Some((key, value)) = field.split_once('=') {
+            (key == name).then(|| Capability::String(unescape(value)))
+        } else if let Some((key, value)) = field.split_once('#') {
+            let number = match value.strip_prefix("0x") {
+                Some(hex) => u32::from_str_radix(hex, 16),
+                None => value.parse(),
+            };
+            (key == name).then_some(Capability::Number(number.ok()?))
+        } else {
+            (field == name).then_some(Capability::Flag)
+        }
+    })
+}
+
+/// The comma-separated fields of an entry, trimmed. `\,` doesn't end a
+/// field.
+fn fields(entry: &str) -> impl Iterator<Item = &str> {
+    let mut rest = entry;
+    std::iter::from_fn(move || {
+        loop {
+            if rest.is_empty() {
+                return None;
+            }
+            let bytes = rest.as_bytes();
+            let mut end = 0;
+            while end < bytes.len() && bytes[end] != b',' {
+                end += if bytes[end] == b'\\' { 2 } else { 1 };
+            }
+            let end = end.min(bytes.len());
+            let field = rest[..end].trim();
+            rest = rest.get(end + 1..).unwrap_or("");
+            if !field.is_empty() {
+                return Some(field);
+            }
+        }
+    })
+}
+
+/// Resolve the escapes of a terminfo string value.
+fn unescape(value: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut chars = value.bytes().peekable();
+    while let Some(byte) = chars.next() {
+        match byte {
+            b'\\' => {
+                let Some(escaped) = chars.next() else {
+                    break;
+                };
+                bytes.push(match escaped {
+                    b'E' | b'e' => 0x1b,
+                    b'n' | b'l' => b'\n',
+                    b'r' => b'\r',
+                    b't' => b'\t',
+                    b'b' => 0x08,
+                    b'f' => 0x0c,
+                    b's' => b' ',
+                    b'0'..=b'7' => {
+                        let mut octal = u32::from(escaped - b'0');
+                        for _ in 0..2 {
+                            match chars.peek() {
+                                Some(&digit @ b'0'..=b'7') => {
+                                    octal = octal * 8 + u32::from(digit - b'0');
+                                    chars.next();
+                                }
+                                _ => break,
+                            }
+                        }
+                        // \0 stands for NUL, which can't be in the string
+                        if octal == 0 { 0x80 } else { octal as u8 }
+                    }
+                    other => other,
+                });
+            }
+            b'^' => match chars.next() {
+                Some(b'?') => bytes.push(0x7f),
+                Some(control) => bytes.push(control & 0x1f),
+                None => bytes.push(b'^'),
+            },
+            _ => bytes.push(byte),
+        }
+    }
+    bytes
+}
+
+/// System directories searched after the user's own, as ncurses does.
+const SYSTEM_DIRS: &[&str] = &[
+    "/etc/terminfo",
+    "/lib/terminfo",
+    "/usr/share/terminfo",
+    "/usr/lib/terminfo",
+    "/usr/local/share/terminfo",
+];
+
+/// The `TERM` to give children for the configured `term`: the configured
+/// name, unless it is [`NAME`] and the entry is neither installed nor
+/// installable, then [`FALLBACK`]. Other names are passed through as
+/// configured.
+pub fn term(term: &'static str) -> &'static str {
+    // Installing runs once per process, not for every spawned shell
+    static INSTALLED: OnceLock<bool> = OnceLock::new();
+
+    if term != NAME {
+        return term;
+    }
+    let installed = *INSTALLED.get_or_init(|| {
+        if find(NAME).is_some() {
+            return true;
+        }
+        match install() {
+            Ok(path) => {
+                log::info!("Installed the terminfo entry into {}", path.display());
+                true
+            }
+            Err(e) => {
+                log::warn!("Using TERM={}: {}", FALLBACK, e);
+                false
+            }
+        }
+    });
+    if installed { NAME } else { FALLBACK }
+}
+
+/// Where the compiled entry `name` is, in the directories ncurses
+/// searches: `$TERMINFO`, `~/.terminfo`, `$TERMINFO_DIRS` and the system
+/// directories.
+pub fn find(name: &str) -> Option<PathBuf> {
+    let mut dirs: Vec<PathBuf> = std::env::var_os("TERMINFO")
+        .map(PathBuf::from)
+        .into_iter()
+        .collect();
+    dirs.extend(home_dir());
+    if let Some(extra) = std::env::var_os("TERMINFO_DIRS") {
+        // An empty entry stands for the system directories
+        dirs.extend(std::env::split_paths(&extra).filter(|dir| !dir.as_os_str().is_empty()));
+    }
+    dirs.extend(SYSTEM_DIRS.iter().map(PathBuf::from));
+    find_in(&dirs, name)
+}
+
+/// Where the compiled entry `name` is in `dirs`, searched in order.
+pub fn find_in(dirs: &[PathBuf], name: &str) -> Option<PathBuf> {
+    let first = name.chars().next()?;
+    dirs.iter()
+        .flat_map(|dir| {
+            [
+                dir.join(first.to_string()).join(name),
+                dir.join(format!("{:x}", first as u32)).join(name),
+            ]
+        })
+        .find(|path| path.is_file())
+}
+
+/// Compile [`SOURCE`] with `tic` into `$TERMINFO`, or `~/.terminfo` when
+/// that isn't set. Returns the directory.
+pub fn install() -> Result<PathBuf> {
+    let dir = std::env::var_os("TERMINFO")
+        .map(PathBuf::from)
+        .or_else(home_dir)
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "no home directory for terminfo",
+            )
+        })?;
+    install_to(&dir)?;
+    Ok(dir)
+}
+
+/// Compile [`SOURCE`] with `tic` into `dir`. The source goes to `tic` on
+/// its standard input, with no file of its own for anyone to swap.
+pub fn install_to(dir: &Path) -> Result<()> {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    std::fs::create_dir_all(dir)?;
+    let tic_error = |e: std::io::Error| std::io::Error::new(e.kind(), format!("tic: {}", e));
+    // -x keeps the extended capabilities (bracketed paste, true color)
+    let mut tic = Command::new("tic")
+        .arg("-x")
+        .arg("-o")
+        .arg(dir)
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(tic_error)?;
+    if let Some(mut stdin) = tic.stdin.take() {
+        // Closing it ends the source
+        stdin.write_all(SOURCE.as_bytes()).map_err(tic_error)?;
+    }
+    let output = tic.wait_with_output().map_err(tic_error)?;
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr);
+        return Err(std::io::Error::other(format!("tic failed: {}", message.trim())).into());
+    }
+    Ok(())
+}
+
+/// `~/.terminfo`
+fn home_dir() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME").filter(|home| !home.is_empty())?;
+    Some(PathBuf::from(home).join(".terminfo"))
+}
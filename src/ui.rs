@@ -0,0 +1,571 @@
+//! Overlay UI
+//!
+//! A minimal UI drawn on top of the terminal: stacked [`Layer`]s of
+//! rectangles with text, laid out in cells so they use the terminal's font
+//! and glyph atlas. It backs the notifications, dialogs and menus of the
+//! application; the renderer draws [`Ui::cells`] over the grid.
+//!
+//! The topmost modal layer (a dialog or a menu) has the keyboard focus and
+//! moves it between its elements that have an `id`; toasts only show a
+//! message until they expire or are clicked. [`Ui::hit`] finds the element
+//! under the pointer.
+//!
+//! ```
+//! use adamant::ui::{Layer, Ui};
+//! use web_time::Instant;
+//!
+//! let mut ui = Ui::default();
+//! let dialog = ui.push(Layer::dialog("Close?", &[("Close", 1), ("Cancel", 0)], 24, 80));
+//! let layer = ui.layer_mut(dialog).unwrap();
+//! assert_eq!(layer.focused().unwrap().id, Some(1));
+//! layer.focus_next();
+//! assert_eq!(layer.focused().unwrap().id, Some(0));
+//!
+//! ui.toast("Copied to clipboard", Instant::now(), 24, 80);
+//! assert_eq!(ui.modal(), Some(dialog));
+//! ```
+
+use std::collections::HashMap;
+
+use web_time::{Duration, Instant};
+
+use crate::grid::{Cell, Color, Flags};
+use crate::term::WidthOptions;
+
+/// How long a toast stays on screen.
+pub const TOAST_DURATION: Duration = Duration::from_secs(3);
+
+/// A rectangle of cells on the visible screen.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Rect {
+    pub row: usize,
+    pub col: usize,
+    pub rows: usize,
+    pub cols: usize,
+}
+
+impl Rect {
+    pub fn new(row: usize, col: usize, rows: usize, cols: usize) -> Self {
+        Self {
+            row,
+            col,
+            rows,
+            cols,
+        }
+    }
+
+    /// A `rows` x `cols` rectangle in the middle of a screen, shrunk to fit.
+    pub fn centered(rows: usize, cols: usize, screen_rows: usize, screen_cols: usize) -> Self {
+        let (rows, cols) = (rows.min(screen_rows), cols.min(screen_cols));
+        Self::new(
+            (screen_rows - rows) / 2,
+            (screen_cols - cols) / 2,
+            rows,
+            cols,
+        )
+    }
+
+    pub fn contains(&self, row: usize, col: usize) -> bool {
+        (self.row..self.row + self.rows).contains(&row)
+            && (self.col..self.col + self.cols).contains(&col)
+    }
+}
+
+/// Colors and attributes of an element.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Style {
+    pub fg: Color,
+    pub bg: Color,
+    pub flags: Flags,
+}
+
+impl Style {
+    /// Dialogs and menus
+    pub const PANEL: Self = Self {
+        fg: Color::Indexed(15),
+        bg: Color::Indexed(8),
+        flags: Flags::empty(),
+    };
+    /// Buttons and menu items
+    pub const ITEM: Self = Self {
+        fg: Color::Indexed(15),
+        bg: Color::Indexed(8),
+        flags: Flags::BOLD,
+    };
+    /// Notifications
+    pub const TOAST: Self = Self {
+        fg: Color::Indexed(0),
+        bg: Color::Indexed(14),
+        flags: Flags::empty(),
+    };
+}
+
+/// A filled rectangle with text, one line per row, cut off at its edges.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Element {
+    pub rect: Rect,
+    pub text: String,
+    pub style: Style,
+    /// What activating the element means to whoever built the layer.
+    /// Elements with an id can take the focus and be clicked.
+    pub id: Option<usize>,
+}
+
+impl Element {
+    /// An element without an id: a background or a label.
+    pub fn label(rect: Rect, text: impl Into<String>, style: Style) -> Self {
+        Self {
+            rect,
+            text: text.into(),
+            style,
+            id: None,
+        }
+    }
+
+    /// An element that can be focused and activated as `id`.
+    pub fn button(rect: Rect, text: impl Into<String>, style: Style, id: usize) -> Self {
+        Self {
+            id: Some(id),
+            ..Self::label(rect, text, style)
+        }
+    }
+}
+
+/// How a layer takes part in input.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LayerKind {
+    /// Takes the keyboard until an element is activated or it is dismissed
+    Dialog,
+    /// Like a dialog, but clicking outside of it dismisses it
+    Menu,
+    /// Passive; goes away when it expires or is clicked
+    Toast,
+    /// Passive, and clicks go through; stays until it is replaced (plugin
+    /// overlays)
+    Status,
+}
+
+/// One overlay: elements drawn in order, later ones on top.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Layer {
+    pub kind: LayerKind,
+    pub elements: Vec<Element>,
+    /// Index of the focused element
+    focus: Option<usize>,
+    /// When the layer is removed by itself
+    pub expires: Option<Instant>,
+}
+
+/// Identifies a layer of a [`Ui`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LayerId(u64);
+
+/// The layers on screen, bottom to top.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Ui {
+    layers: Vec<(LayerId, Layer)>,
+    next_id: u64,
+}
+
+impl Layer {
+    /// A layer of `elements`, focusing the first one with an id.
+    pub fn new(kind: LayerKind, elements: Vec<Element>) -> Self {
+        let focus = match kind {
+            LayerKind::Toast | LayerKind::Status => None,
+            _ => elements.iter().position(|element| element.id.is_some()),
+        };
+        Self {
+            kind,
+            elements,
+            focus,
+            expires: None,
+        }
+    }
+
+    /// A message with a row of buttons under it, centered on a
+    /// `screen_rows` x `screen_cols` screen. The first button has the focus.
+    pub fn dialog(
+        message: &str,
+        buttons: &[(&str, usize)],
+        screen_rows: usize,
+        screen_cols: usize,
+    ) -> Self {
+        let widths = WidthOptions::default();
+        let labels: Vec<String> = buttons
+            .iter()
+            .map(|(text, _)| format!("[ {text} ]"))
+            .collect();
+        // One column between buttons
+        let buttons_width = labels
+            .iter()
+            .map(|label| widths.str_width(label) + 1)
+            .sum::<usize>()
+            .saturating_sub(1);
+        let lines = message.lines().count().max(1);
+        let message_width = message.lines().map(|line| widths.str_width(line)).max();
+        let cols = message_width.unwrap_or(0).max(buttons_width) + 4;
+        let frame = Rect::centered(lines + 4, cols, screen_rows, screen_cols);
+
+        let mut elements = vec![
+            Element::label(frame, "", Style::PANEL),
+            Element::label(
+                Rect::new(
+                    frame.row + 1,
+                    frame.col + 2,
+                    lines,
+                    frame.cols.saturating_sub(4),
+                ),
+                message,
+                Style::PANEL,
+            ),
+        ];
+        // Buttons are right-aligned on the last line
+        let mut col = (frame.col + frame.cols).saturating_sub(buttons_width + 2);
+        let row = frame.row + frame.rows.saturating_sub(2);
+        for (label, &(_, id)) in labels.into_iter().zip(buttons) {
+            let width = widths.str_width(&label);
+            let rect = Rect::new(row, col.max(frame.col), 1, width);
+            elements.push(Element::button(rect, label, Style::ITEM, id));
+            col += width + 1;
+        }
+        Self::new(LayerKind::Dialog, elements)
+    }
+
+    /// A message over a line of `text` being typed, centered on a
+    /// `screen_rows` x `screen_cols` screen. Text too long for the line
+    /// shows its end, next to the caret.
+    pub fn prompt(message: &str, text: &str, screen_rows: usize, screen_cols: usize) -> Self {
+        let widths = WidthOptions::default();
+        let cols = (widths.str_width(message).max(40) + 4).min(screen_cols);
+        let frame = Rect::centered(5, cols, screen_rows, screen_cols);
+        let line = frame.cols.saturating_sub(4);
+        let mut shown = text;
+        while widths.str_width(shown) + 1 > line
+            && let Some(c) = shown.chars().next()
+        {
+            shown = &shown[c.len_utf8()..];
+        }
+        let field = Rect::new(frame.row + 3, frame.col + 2, 1, line);
+        Self::new(
+            LayerKind::Dialog,
+            vec![
+                Element::label(frame, "", Style::PANEL),
+                Element::label(
+                    Rect::new(frame.row + 1, frame.col + 2, 1, line),
+                    message,
+                    Style::PANEL,
+                ),
+                Element::label(field, format!("{}\u{258F}", shown), Style::ITEM),
+            ],
+        )
+    }
+
+    /// A list of items opening at (`row`, `col`), moved up and left to fit
+    /// on the screen.
+    pub fn menu(
+        items: &[(&str, usize)],
+        row: usize,
+        col: usize,
+        screen_rows: usize,
+        screen_cols: usize,
+    ) -> Self {
+        let widths = WidthOptions::default();
+        let item_width = items.iter().map(|(text, _)| widths.str_width(text)).max();
+        let cols = (item_width.unwrap_or(0) + 2).min(screen_cols);
+        let rows = items.len().min(screen_rows);
+        let row = row.min(screen_rows - rows);
+        let col = col.min(screen_cols - cols);
+        let elements = items
+            .iter()
+            .take(rows)
+            .enumerate()
+            .map(|(i, &(text, id))| {
+                Element::button(
+                    Rect::new(row + i, col, 1, cols),
+                    format!(" {text}"),
+                    Style::ITEM,
+                    id,
+                )
+            })
+            .collect();
+        Self::new(LayerKind::Menu, elements)
+    }
+
+    /// A one-line message in the bottom right corner until `expires`.
+    pub fn toast(text: &str, expires: Instant, screen_rows: usize, screen_cols: usize) -> Self {
+        let cols = (WidthOptions::default().str_width(text) + 2).min(screen_cols);
+        let rect = Rect::new(
+            screen_rows.saturating_sub(2),
+            (screen_cols - cols).saturating_sub(1),
+            1,
+            cols,
+        );
+        Self {
+            expires: Some(expires),
+            ..Self::new(
+                LayerKind::Toast,
+                vec![Element::label(rect, format!(" {text}"), Style::TOAST)],
+            )
+        }
+    }
+
+    /// The keys that can come next in a key sequence, each with what it
+    /// does, in a panel over the bottom left corner until `expires`. Keys
+    /// go on to the terminal.
+    pub fn key_hints(
+        keys: &[(&str, String)],
+        expires: Instant,
+        screen_rows: usize,
+        screen_cols: usize,
+    ) -> Self {
+        let widths = WidthOptions::default();
+        let key_width = keys.iter().map(|(key, _)| widths.str_width(key)).max();
+        let lines: Vec<String> = keys
+            .iter()
+            .map(|(key, what)| {
+                let pad = key_width.unwrap_or(0) - widths.str_width(key);
+                format!("{key}{}  {what}", " ".repeat(pad))
+            })
+            .collect();
+        let line_width = lines.iter().map(|line| widths.str_width(line)).max();
+        let cols = (line_width.unwrap_or(0) + 4).min(screen_cols);
+        let rows = (lines.len() + 2).min(screen_rows);
+        let frame = Rect::new(screen_rows - rows, 0, rows, cols);
+        let text = Rect::new(
+            frame.row + 1,
+            2,
+            rows.saturating_sub(2),
+            cols.saturating_sub(4),
+        );
+        Self {
+            expires: Some(expires),
+            ..Self::new(
+                LayerKind::Status,
+                vec![
+                    Element::label(frame, "", Style::PANEL),
+                    Element::label(text, lines.join("\n"), Style::PANEL),
+                ],
+            )
+        }
+    }
+
+    /// A one-line note under (`row`, `col`), or over it on the last row,
+    /// moved left to fit. Clicks go through it.
+    pub fn tooltip(
+        text: &str,
+        row: usize,
+        col: usize,
+        screen_rows: usize,
+        screen_cols: usize,
+    ) -> Self {
+        let cols = (WidthOptions::default().str_width(text) + 2).min(screen_cols);
+        let row = if row + 1 < screen_rows {
+            row + 1
+        } else {
+            row.saturating_sub(1)
+        };
+        let rect = Rect::new(row, col.min(screen_cols - cols), 1, cols);
+        Self::new(
+            LayerKind::Status,
+            vec![Element::label(rect, format!(" {text}"), Style::PANEL)],
+        )
+    }
+
+    /// Whether the layer takes the keyboard.
+    pub fn is_modal(&self) -> bool {
+        matches!(self.kind, LayerKind::Dialog | LayerKind::Menu)
+    }
+
+    /// The focused element.
+    pub fn focused(&self) -> Option<&Element> {
+        self.elements.get(self.focus?)
+    }
+
+    /// Focus the element with `id`.
+    pub fn set_focus(&mut self, id: usize) {
+        if let Some(index) = self.elements.iter().position(|e| e.id == Some(id)) {
+            self.focus = Some(index);
+        }
+    }
+
+    /// Move the focus to the next element with an id, wrapping around.
+    pub fn focus_next(&mut self) {
+        self.move_focus(1);
+    }
+
+    /// Move the focus to the previous element with an id, wrapping around.
+    pub fn focus_prev(&mut self) {
+        self.move_focus(self.elements.len().saturating_sub(1));
+    }
+
+    fn move_focus(&mut self, step: usize) {
+        let (Some(focus), count) = (self.focus, self.elements.len()) else {
+            return;
+        };
+        self.focus = (1..=count)
+            .map(|i| (focus + i * step) % count)
+            .find(|&index| self.elements[index].id.is_some());
+    }
+
+    /// Whether any element covers the cell.
+    pub fn covers(&self, row: usize, col: usize) -> bool {
+        self.elements.iter().any(|e| e.rect.contains(row, col))
+    }
+}
+
+impl Ui {
+    /// Put `layer` on top of the others.
+    pub fn push(&mut self, layer: Layer) -> LayerId {
+        let id = LayerId(self.next_id);
+        self.next_id += 1;
+        self.layers.push((id, layer));
+        id
+    }
+
+    /// Show a notification for [`TOAST_DURATION`] from `now`, replacing the
+    /// one shown before.
+    pub fn toast(&mut self, text: &str, now: Instant, screen_rows: usize, screen_cols: usize) {
+        self.layers
+            .retain(|(_, layer)| layer.kind != LayerKind::Toast);
+        self.push(Layer::toast(
+            text,
+            now + TOAST_DURATION,
+            screen_rows,
+            screen_cols,
+        ));
+    }
+
+    pub fn remove(&mut self, id: LayerId) -> Option<Layer> {
+        let index = self.layers.iter().position(|(layer, _)| *layer == id)?;
+        Some(self.layers.remove(index).1)
+    }
+
+    pub fn layer(&self, id: LayerId) -> Option<&Layer> {
+        self.layers
+            .iter()
+            .find(|(layer, _)| *layer == id)
+            .map(|(_, layer)| layer)
+    }
+
+    pub fn layer_mut(&mut self, id: LayerId) -> Option<&mut Layer> {
+        self.layers
+            .iter_mut()
+            .find(|(layer, _)| *layer == id)
+            .map(|(_, layer)| layer)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.layers.clear();
+    }
+
+    /// The topmost layer that takes the keyboard.
+    pub fn modal(&self) -> Option<LayerId> {
+        self.layers
+            .iter()
+            .rev()
+            .find(|(_, layer)| layer.is_modal())
+            .map(|(id, _)| *id)
+    }
+
+    /// The topmost layer covering the cell, and its element there that has
+    /// an id. Status layers don't take clicks.
+    pub fn hit(&self, row: usize, col: usize) -> Option<(LayerId, Option<usize>)> {
+        let (id, layer) = self
+            .layers
+            .iter()
+            .rev()
+            .find(|(_, layer)| layer.kind != LayerKind::Status && layer.covers(row, col))?;
+        let element = layer
+            .elements
+            .iter()
+            .rev()
+            .find(|e| e.rect.contains(row, col))
+            .and_then(|e| e.id);
+        Some((*id, element))
+    }
+
+    /// Whether any layer covers the cell.
+    pub fn covers(&self, row: usize, col: usize) -> bool {
+        self.layers.iter().any(|(_, layer)| layer.covers(row, col))
+    }
+
+    /// Remove the layers that expired by `now`. Returns `true` if any did.
+    pub fn expire(&mut self, now: Instant) -> bool {
+        let count = self.layers.len();
+        self.layers
+            .retain(|(_, layer)| layer.expires.is_none_or(|expires| expires > now));
+        self.layers.len() != count
+    }
+
+    /// The cells the layers draw over a `rows` x `cols` screen, with the
+    /// focused element shown inverted.
+    pub fn cells(&self, rows: usize, cols: usize) -> HashMap<(usize, usize), Cell> {
+        let widths = WidthOptions::default();
+        let mut cells = HashMap::new();
+        for (_, layer) in &self.layers {
+            for (index, element) in layer.elements.iter().enumerate() {
+                let mut style = element.style;
+                if layer.focus == Some(index) {
+                    style.flags |= Flags::INVERSE;
+                }
+                paint(&mut cells, element, style, rows, cols, widths);
+            }
+        }
+        cells
+    }
+}
+
+/// Fill the element's rectangle and write its lines into it.
+fn paint(
+    cells: &mut HashMap<(usize, usize), Cell>,
+    element: &Element,
+    style: Style,
+    rows: usize,
+    cols: usize,
+    widths: WidthOptions,
+) {
+    let rect = element.rect;
+    let bottom = (rect.row + rect.rows).min(rows);
+    let right = (rect.col + rect.cols).min(cols);
+    let blank = Cell {
+        c: ' ',
+        fg: style.fg,
+        bg: style.bg,
+        flags: style.flags,
+        link: 0,
+    };
+    let mut lines = element.text.lines();
+    for row in rect.row..bottom {
+        let mut col = rect.col;
+        for c in lines.next().unwrap_or("").chars() {
+            let width = widths.width(c);
+            if width == 0 {
+                continue;
+            }
+            if col + width > right {
+                break;
+            }
+            let mut cell = Cell { c, ..blank.clone() };
+            if width == 2 {
+                cell.flags |= Flags::WIDE;
+                let spacer_flags = blank.flags | Flags::WIDE_SPACER;
+                cells.insert(
+                    (row, col + 1),
+                    Cell {
+                        flags: spacer_flags,
+                        ..blank.clone()
+                    },
+                );
+            }
+            cells.insert((row, col), cell);
+            col += width;
+        }
+        for col in col..right {
+            cells.insert((row, col), blank.clone());
+        }
+    }
+}
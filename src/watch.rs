@@ -0,0 +1,192 @@
+//! Watch Rules
+//!
+//! [`WatchMatcher`] runs the configured [`WatchRule`]s over output as it is
+//! printed: every line, soft-wrapped rows joined, once it is complete.
+//! Highlighting marks the line's rows in the grid, where it stays while
+//! they scroll back; everything else is handed to the host as a
+//! [`WatchHit`], at most one per rule for each scan. For
+//! `Hooks::on_output`, every line can be reported as well.
+//!
+//! Lines are marked as watched when they are scanned, so each is matched
+//! once however much output arrives between two scans, and a line the
+//! program clears and prints again is matched again.
+//!
+//! ```
+//! use adamant::config::{WatchAction, WatchRule};
+//! use adamant::term::Terminal;
+//! use adamant::watch::{WatchHit, WatchMatcher};
+//!
+//! let rules = [WatchRule {
+//!     name: "failed",
+//!     regex: r"FAILED",
+//!     action: WatchAction::Notify,
+//! }];
+//! let matcher = WatchMatcher::new(&rules).unwrap();
+//! let mut term = Terminal::new(40, 5, 100);
+//!
+//! // Not until the line is complete
+//! term.feed(b"test result: FAILED");
+//! assert!(matcher.scan(term.grid_mut()).is_empty());
+//! term.feed(b"\r\n");
+//! assert_eq!(
+//!     matcher.scan(term.grid_mut()),
+//!     [WatchHit::Notify { rule: "failed", line: "test result: FAILED".into() }]
+//! );
+//! assert!(matcher.scan(term.grid_mut()).is_empty());
+//! ```
+
+use regex::{Captures, Regex};
+
+use crate::config::{WatchAction, WatchRule};
+use crate::error::{AdamantError, Result};
+use crate::grid::{Flags, Grid};
+
+/// The compiled [`WatchRule`]s.
+#[derive(Clone, Debug)]
+pub struct WatchMatcher {
+    rules: Vec<(&'static str, Regex, WatchAction)>,
+    /// Report every line as [`WatchHit::Line`]
+    lines: bool,
+}
+
+/// A rule matched a line, and the host has to act on it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WatchHit {
+    /// Show a notification
+    Notify { rule: &'static str, line: String },
+    /// Play a sound file
+    Sound(&'static str),
+    /// Run a program with arguments
+    Run(Vec<String>),
+    /// A complete line, when lines are reported
+    Line(String),
+}
+
+impl WatchMatcher {
+    /// Compile the rules. A pattern that doesn't compile, or a `Sound` or
+    /// `Run` action without a file or program, is a configuration error.
+    pub fn new(rules: &[WatchRule]) -> Result<Self> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let invalid = |message: String| {
+                    AdamantError::Config(format!("watch rule {:?}: {}", rule.name, message))
+                };
+                let regex = Regex::new(rule.regex).map_err(|e| invalid(e.to_string()))?;
+                match rule.action {
+                    WatchAction::Sound("") => return Err(invalid("Sound needs a file".into())),
+                    WatchAction::Run(&[]) => return Err(invalid("Run needs a program".into())),
+                    _ => {}
+                }
+                Ok((rule.name, regex, rule.action))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            rules,
+            lines: false,
+        })
+    }
+
+    /// Whether there is nothing to scan for: no rules, and lines aren't
+    /// reported.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty() && !self.lines
+    }
+
+    /// Also report every complete line that isn't blank, before what the
+    /// rules ask for on it.
+    pub fn report_lines(&mut self, lines: bool) {
+        self.lines = lines;
+    }
+
+    /// Match the lines completed since the last scan: highlight them in
+    /// `grid` and return what else the rules ask for.
+    pub fn scan(&self, grid: &mut Grid) -> Vec<WatchHit> {
+        // The cursor's line, including rows it wraps from, isn't complete
+        let mut end = grid.history_len() + grid.cursor.row;
+        while end > 0 && grid.line(end - 1).wrapped {
+            end -= 1;
+        }
+        let mut start = end;
+        while start > 0 && !grid.line(start - 1).watched {
+            start -= 1;
+        }
+
+        let mut hits = Vec::new();
+        let mut fired = vec![false; self.rules.len()];
+        let mut line = start;
+        while line < end {
+            let mut rows = 1;
+            while grid.line(line + rows - 1).wrapped && line + rows < end {
+                rows += 1;
+            }
+            let text = line_text(grid, line, rows);
+            if self.lines && !text.is_empty() {
+                hits.push(WatchHit::Line(text.clone()));
+            }
+            for (i, (name, regex, action)) in self.rules.iter().enumerate() {
+                if fired[i] || text.is_empty() {
+                    continue;
+                }
+                let Some(captures) = regex.captures(&text) else {
+                    continue;
+                };
+                match resolve(name, *action, &text, &captures) {
+                    Some(hit) => {
+                        hits.push(hit);
+                        fired[i] = true;
+                    }
+                    None => {
+                        for row in line..line + rows {
+                            grid.line_mut(row).highlighted = true;
+                        }
+                    }
+                }
+            }
+            for row in line..line + rows {
+                grid.line_mut(row).watched = true;
+            }
+            line += rows;
+        }
+        hits
+    }
+}
+
+/// What `action` asks of the host for a `line` matched with `captures`,
+/// or `None` for a highlight.
+fn resolve(
+    name: &'static str,
+    action: WatchAction,
+    line: &str,
+    captures: &Captures,
+) -> Option<WatchHit> {
+    match action {
+        WatchAction::Highlight => None,
+        WatchAction::Notify => Some(WatchHit::Notify {
+            rule: name,
+            line: line.to_string(),
+        }),
+        WatchAction::Sound(file) => Some(WatchHit::Sound(file)),
+        WatchAction::Run(command) => Some(WatchHit::Run(
+            command
+                .iter()
+                .map(|arg| {
+                    let mut expanded = String::new();
+                    captures.expand(arg, &mut expanded);
+                    expanded
+                })
+                .collect(),
+        )),
+    }
+}
+
+/// The characters of `rows` rows from `line` on, wide-character spacers
+/// skipped and trailing blanks trimmed.
+fn line_text(grid: &Grid, line: usize, rows: usize) -> String {
+    let text: String = (line..line + rows)
+        .flat_map(|line| grid.line(line).cells())
+        .filter(|cell| !cell.flags.contains(Flags::WIDE_SPACER))
+        .map(|cell| cell.c)
+        .collect();
+    text.trim_end().to_string()
+}
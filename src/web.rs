@@ -0,0 +1,43 @@
+//! Browser Frontend
+//!
+//! Runs Adamant inside a web page: the renderer draws into a `<canvas>` via
+//! WebGPU, and the shell runs on a server behind a websocket bridge (see
+//! `backend::WebSocketBackend` for the protocol).
+//!
+//! Build with:
+//!
+//! ```text
+//! cargo build --release --target wasm32-unknown-unknown --no-default-features --features web
+//! wasm-bindgen --target web --out-dir pkg target/wasm32-unknown-unknown/release/adamant.wasm
+//! ```
+//!
+//! Browsers have no font files to read, so the [`Config`] passed to
+//! [`start`] must use `FontSource::Embedded` fonts.
+
+use web_sys::HtmlCanvasElement;
+use winit::event_loop::EventLoop;
+use winit::platform::web::EventLoopExtWebSys;
+
+use crate::app::{App, AppEvent};
+use crate::config::Config;
+use crate::error::Result;
+
+/// Where the browser frontend draws and connects to.
+pub struct WebOptions {
+    /// The canvas to render into
+    pub canvas: HtmlCanvasElement,
+    /// Websocket URL of the PTY bridge (`ws://` or `wss://`)
+    pub url: String,
+}
+
+/// Start the terminal in the browser.
+///
+/// Returns immediately; the event loop is driven by the browser.
+pub fn start(config: Config, options: WebOptions) -> Result<()> {
+    let event_loop = EventLoop::<AppEvent>::with_user_event().build()?;
+
+    let app = App::new(config, event_loop.create_proxy()).with_web(options);
+    event_loop.spawn_app(app);
+
+    Ok(())
+}
@@ -0,0 +1,2336 @@
+//! Embeddable Terminal Widget
+//!
+//! [`TerminalWidget`] packages an I/O backend (see `crate::backend`), the VT
+//! emulator and the grid renderer into one object that draws into any wgpu
+//! texture view. Hosts such as game engines, editors and IDEs create it with
+//! their own `wgpu::Device`, forward window events to it and call
+//! [`TerminalWidget::render`] from their render loop.
+//!
+//! Adamant's own window is just one host of this widget (see `app.rs`).
+//!
+//! ```ignore
+//! let backend = PtyBackend::spawn(&ShellConfig::default(), 80, 24, move || {
+//!     // Called from the PTY thread when new output arrives
+//!     window.request_redraw();
+//! })?;
+//! let mut widget = TerminalWidget::new(&device, &descriptor, Box::new(backend))?;
+//!
+//! // Each frame:
+//! widget.update();
+//! widget.render(&device, &queue, &mut encoder, &target_view);
+//! ```
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+use web_time::{Duration, Instant};
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{ElementState, Ime, MouseButton, MouseScrollDelta, WindowEvent},
+    keyboard::{Key, ModifiersState, NamedKey},
+    window::CursorIcon,
+};
+
+use crate::a11y::AccessibleScreen;
+use crate::backend::{Backend, BackendEvent};
+use crate::bindings::{KeyBindings, KeyOutcome, MouseBindings, MouseTrigger};
+use crate::clipboard_history::ClipboardPicker;
+use crate::commands::{Action, Command, CommandPalette};
+use crate::config::{
+    AppProfile, Colors, Config, HookAction, Keypad, LinkModifier, Monitor, MouseCommand,
+    PasteConfig, ScrollbarMode, StatusBarPosition,
+};
+use crate::error::Result;
+use crate::grid::{
+    BidiRow, CopyOptions, Point, SearchMatch, Selection, SelectionMotion, SelectionUnit,
+};
+use crate::hints::{Hint, HintMatcher, HintPick, Hints};
+use crate::hotkey::Hotkey;
+use crate::input::{self, MouseAction};
+use crate::minimap::{self, Minimap};
+use crate::monitor::ActivityMonitor;
+use crate::passthrough::{self, Passthrough};
+use crate::paste;
+use crate::plugin::Plugins;
+use crate::recording::Recorder;
+use crate::renderer::{CursorDisplay, Decorations, Preedit, TerminalRenderer};
+use crate::scroll::{AutoScroll, ScrollAnimation, SmoothScroll};
+use crate::scrollbar::{self, Scrollbar};
+use crate::status_bar::StatusBar;
+use crate::styled_copy::StyledText;
+use crate::term::{
+    CursorStyle, Event as TermEvent, Mode, ScrollbackOptions, Terminal, WidthOptions,
+};
+use crate::ui::{Layer, LayerId, LayerKind, Ui};
+use crate::watch::{WatchHit, WatchMatcher};
+
+/// Lines scrolled per mouse wheel notch.
+const SCROLL_LINES: f32 = 3.0;
+
+/// Columns a wide grid's view moves per line of the wheel.
+const WHEEL_COLUMNS: isize = 2;
+
+/// The paste preview's buttons.
+const PASTE_BUTTON: usize = 1;
+const CANCEL_PASTE_BUTTON: usize = 0;
+
+/// Input the backend may have queued before more is held back in the
+/// widget, and the most handed to it at once.
+const INPUT_BUFFER: usize = 1 << 20;
+const INPUT_CHUNK: usize = 64 << 10;
+
+/// How long a blinking cursor stays on (and off).
+const CURSOR_BLINK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Parameters for creating a [`TerminalWidget`].
+pub struct TerminalWidgetDescriptor<'a> {
+    /// Format of the texture views the widget will render into
+    pub format: wgpu::TextureFormat,
+    /// Size of the widget in physical pixels
+    pub width: u32,
+    pub height: u32,
+    /// DPI scale factor applied to the configured font size
+    pub scale_factor: f32,
+    pub config: &'a Config,
+}
+
+/// A self-contained terminal pane rendering into a host's wgpu target.
+pub struct TerminalWidget {
+    terminal: Terminal,
+    backend: Box<dyn Backend>,
+    /// Input held back while the backend has [`INPUT_BUFFER`] queued, so
+    /// a huge paste doesn't pile up there; later input waits behind it
+    held_input: VecDeque<u8>,
+    exited: bool,
+    /// Input is dropped instead of sent (`Config::read_only`)
+    read_only: bool,
+    /// Where the program's output is recorded to, if anywhere
+    recorder: Option<Recorder>,
+    /// Watches the output for activity or silence
+    monitor: ActivityMonitor,
+    /// Activity or silence seen, waiting for the host
+    alert: Option<Monitor>,
+    /// Matches `Config::watch_rules` in the output
+    watch: WatchMatcher,
+    /// What the watch rules asked for, waiting for the host
+    watch_hits: Vec<WatchHit>,
+    /// This pane's instances of `Config::plugins`
+    plugins: Plugins,
+    /// The layer of `ui` the plugins draw on
+    plugin_layer: Option<LayerId>,
+    /// Shortcuts left to foreground programs (`Config::passthrough`)
+    passthrough: Passthrough,
+    /// `Config::key_bindings` and the key sequence being typed
+    bindings: KeyBindings,
+    /// The layer of `ui` showing the keys that can come next
+    key_hints: Option<LayerId>,
+    /// The app profile the host applies, with its passthrough keys parsed
+    app_profile: Option<(AppProfile, Vec<Hotkey>)>,
+    /// The program's exit code, once it exited
+    exit_code: Option<u32>,
+    renderer: TerminalRenderer,
+    /// Top-left corner inside the render target, in pixels
+    position: [f32; 2],
+    width: u32,
+    height: u32,
+    modifiers: ModifiersState,
+    /// Where the pointer is in the render target
+    pointer: PhysicalPosition<f64>,
+    /// Cell under the pointer (column, row)
+    pointer_cell: (usize, usize),
+    /// The same cell where it is drawn, which differs in reordered rows
+    pointer_screen_cell: (usize, usize),
+    /// What the held mouse button is doing
+    drag: Option<Drag>,
+    selection: Option<Selection>,
+    /// The selection before the current one, for reselecting it
+    last_selection: Option<Selection>,
+    /// Whether keys move the selection instead of going to the program
+    copy_mode: bool,
+    /// When and where the last left click was, and how many in a row
+    last_click: Option<(Instant, Point, u8)>,
+    /// Clicks closer than this make a double or triple click
+    multi_click: Duration,
+    /// What a double click selects besides letters and digits
+    word_chars: &'static str,
+    /// How selections become copied text
+    copy_options: CopyOptions,
+    /// Shift sends mouse input to the application instead of selecting
+    swap_selection_modifier: bool,
+    /// Whether the widget has keyboard focus
+    focused: bool,
+    /// Start of the current blink cycle; typing restarts it so the cursor
+    /// stays visible while it moves
+    blink_epoch: Instant,
+    /// Text being composed with an input method
+    preedit: Option<Preedit>,
+    hint_matcher: HintMatcher,
+    /// Labeled matches while hints mode is active
+    hints: Option<Hints>,
+    /// The key held to click links
+    link_modifier: LinkModifier,
+    /// The URL or path under the pointer while the link modifier is held
+    hovered_link: Option<Hint>,
+    /// The target of the OSC 8 hyperlink under the pointer, shown on a
+    /// layer of its own
+    tooltip: Option<(LayerId, String)>,
+    /// A hint picked in hints mode, waiting for the host to act on it
+    picked_hint: Option<HintPick>,
+    /// Toasts, dialogs and menus over the terminal
+    ui: Ui,
+    /// An overlay element activated with Enter or a click, waiting for the
+    /// host
+    activated: Option<(LayerId, usize)>,
+    /// The open command palette and its layer in `ui`
+    palette: Option<(LayerId, CommandPalette)>,
+    /// The open clipboard history picker and its layer in `ui`
+    clipboard_picker: Option<(LayerId, ClipboardPicker)>,
+    /// When pastes are previewed first
+    paste: PasteConfig,
+    /// The open paste preview, its layer in `ui` and the text to paste
+    paste_prompt: Option<(LayerId, String)>,
+    /// The open rename prompt, its layer in `ui` and the name typed so far
+    rename: Option<(LayerId, String)>,
+    /// The open search prompt, its layer in `ui` and the query typed so far
+    search: Option<(LayerId, String)>,
+    /// Where the query appears, marked on the scrollbar and the minimap
+    search_matches: Vec<SearchMatch>,
+    /// An action picked in the command palette, waiting for the host
+    command: Option<Action>,
+    /// Text just selected with the mouse, waiting for the host
+    mouse_selection: Option<String>,
+    /// A middle click asked to paste the primary selection
+    primary_paste: bool,
+    /// What the keypad sends in application keypad mode
+    keypad: Keypad,
+    /// What buttons and the wheel do (`MouseConfig::bindings`)
+    mouse_bindings: MouseBindings,
+    /// What a mouse binding asked the host for
+    mouse_action: Option<HookAction>,
+    /// Touchpad scrolling in fractions of a line, and its glide
+    smooth_scroll: SmoothScroll,
+    /// Scrolling while a selection is dragged past the top or bottom
+    auto_scroll: AutoScroll,
+    /// Scrolling drawn over a few frames (`Config::scroll_animation`)
+    scroll_animation: Option<ScrollAnimation>,
+    /// Whether output scrolling the screen is animated too
+    animate_output: bool,
+    scrollbar_mode: ScrollbarMode,
+    /// A row of its own above or below the grid (`Config::status_bar`)
+    status_bar: Option<StatusBar>,
+    /// Columns of the grid when the window is narrower
+    /// (`Config::wide_columns`)
+    wide_columns: usize,
+    /// Input was sent: the view of a wide grid goes to the cursor with the
+    /// next output
+    follow_cursor: bool,
+    /// A column with an overview of the scrollback (`Config::minimap`)
+    show_minimap: bool,
+    /// The minimap last drawn, and the generation, display offset and
+    /// height it was made for
+    minimap: Option<((u64, usize, u32), Minimap)>,
+}
+
+/// A mouse drag in progress.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Drag {
+    /// Extending the selection by `SelectionUnit`s, from the first and
+    /// last cell of the one clicked
+    Select(SelectionUnit, (Point, Point)),
+    /// Reported to the application, which asked for mouse events
+    Report(MouseButton),
+    /// Moving the scrollbar's thumb, held this far (a fraction of the bar)
+    /// below its top
+    Scrollbar(f32),
+}
+
+impl TerminalWidget {
+    /// Create the widget on top of `backend`.
+    ///
+    /// The backend is resized to the grid that fits the descriptor's size
+    /// right away, so it may be created with any initial size.
+    pub fn new(
+        device: &wgpu::Device,
+        desc: &TerminalWidgetDescriptor<'_>,
+        mut backend: Box<dyn Backend>,
+    ) -> Result<Self> {
+        desc.config.validate()?;
+        let hint_matcher = HintMatcher::new(&desc.config.hints)?;
+        let mut watch = WatchMatcher::new(&desc.config.watch_rules)?;
+        watch.report_lines(desc.config.hooks.on_output.is_some());
+        let plugins = Plugins::new(&desc.config.plugins)?;
+        let passthrough = Passthrough::new(&desc.config.passthrough)?;
+        let mouse_bindings = MouseBindings::new(&desc.config.mouse.bindings)?;
+        let bindings = KeyBindings::new(
+            &desc.config.key_bindings,
+            Duration::from_millis(desc.config.key_sequence_timeout_ms),
+        )?;
+        for profile in &desc.config.app_profiles {
+            passthrough::parse_keys(profile.passthrough)?;
+        }
+        let status_bar = StatusBar::new(&desc.config.status_bar)?;
+        let mut renderer =
+            TerminalRenderer::new(device, desc.format, desc.config, desc.scale_factor)?;
+        renderer.resize(desc.width, desc.height);
+
+        let (view_cols, rows) = fit_grid(
+            &renderer,
+            status_bar.is_some(),
+            desc.config.minimap,
+            desc.width,
+            desc.height,
+        );
+        let wide_columns = desc.config.wide_columns as usize;
+        let cols = view_cols.max(wide_columns);
+        let mut terminal = Terminal::new(cols, rows, desc.config.scrollback_lines);
+        terminal.set_view_cols(view_cols);
+        terminal.set_scrollback_options(ScrollbackOptions {
+            erase_saved_lines: desc.config.erase_saved_lines,
+            save_alt_screen_lines: desc.config.save_alt_screen_lines,
+        });
+        terminal.set_width_options(WidthOptions {
+            ambiguous_wide: desc.config.ambiguous_wide,
+            emoji_wide: desc.config.emoji_wide,
+        });
+        terminal.colors_mut().tint = desc.config.colors.tint;
+        backend.resize(cols as u16, rows as u16);
+        let animation = &desc.config.scroll_animation;
+        let mut monitor = ActivityMonitor::new(Duration::from_millis(desc.config.silence_ms));
+        monitor.watch(desc.config.monitor);
+
+        Ok(Self {
+            terminal,
+            backend,
+            held_input: VecDeque::new(),
+            exited: false,
+            read_only: desc.config.read_only,
+            recorder: None,
+            monitor,
+            alert: None,
+            watch,
+            watch_hits: Vec::new(),
+            plugins,
+            plugin_layer: None,
+            passthrough,
+            bindings,
+            key_hints: None,
+            app_profile: None,
+            exit_code: None,
+            renderer,
+            position: [0.0, 0.0],
+            width: desc.width,
+            height: desc.height,
+            modifiers: ModifiersState::empty(),
+            pointer: PhysicalPosition::new(0.0, 0.0),
+            pointer_cell: (0, 0),
+            pointer_screen_cell: (0, 0),
+            drag: None,
+            selection: None,
+            last_selection: None,
+            copy_mode: false,
+            last_click: None,
+            multi_click: Duration::from_millis(desc.config.mouse.multi_click_ms),
+            word_chars: desc.config.mouse.word_chars,
+            copy_options: CopyOptions {
+                trim_trailing_whitespace: desc.config.copy_trim_trailing_whitespace,
+                join_wrapped_lines: desc.config.copy_join_wrapped_lines,
+            },
+            swap_selection_modifier: desc.config.mouse.swap_selection_modifier,
+            focused: true,
+            blink_epoch: Instant::now(),
+            preedit: None,
+            hint_matcher,
+            hints: None,
+            link_modifier: desc.config.mouse.link_modifier,
+            hovered_link: None,
+            tooltip: None,
+            picked_hint: None,
+            ui: Ui::default(),
+            activated: None,
+            palette: None,
+            clipboard_picker: None,
+            paste: desc.config.paste.clone(),
+            paste_prompt: None,
+            rename: None,
+            search: None,
+            search_matches: Vec::new(),
+            command: None,
+            mouse_selection: None,
+            primary_paste: false,
+            keypad: desc.config.keypad,
+            mouse_bindings,
+            mouse_action: None,
+            smooth_scroll: SmoothScroll::new(desc.config.mouse.touchpad_momentum),
+            auto_scroll: AutoScroll::default(),
+            scroll_animation: animation
+                .enabled
+                .then(|| ScrollAnimation::new(Duration::from_millis(animation.duration_ms))),
+            animate_output: animation.enabled && animation.output,
+            scrollbar_mode: desc.config.scrollbar,
+            status_bar,
+            wide_columns,
+            follow_cursor: false,
+            show_minimap: desc.config.minimap,
+            minimap: None,
+        })
+    }
+
+    /// The emulator state (grid, modes, title).
+    pub fn terminal(&self) -> &Terminal {
+        &self.terminal
+    }
+
+    /// The emulator state, mutably (for colors set over IPC).
+    pub fn terminal_mut(&mut self) -> &mut Terminal {
+        &mut self.terminal
+    }
+
+    /// Hand the pane over to another program, like `exec` in a shell: the
+    /// old backend is dropped (ending its program), the new one inherits
+    /// the screen and scrollback.
+    pub fn replace_backend(&mut self, mut backend: Box<dyn Backend>) {
+        backend.resize(self.terminal.cols() as u16, self.terminal.rows() as u16);
+        self.backend = backend;
+        self.held_input.clear();
+        self.exited = false;
+        self.exit_code = None;
+        self.drag = None;
+        self.terminal.detach_program();
+        self.terminal.grid_mut().reset_display();
+    }
+
+    /// Draw with another color scheme, e.g. a host profile's.
+    pub fn set_colors(&mut self, colors: &Colors) {
+        self.renderer.set_colors(colors);
+    }
+
+    /// Continue on another wgpu device, e.g. after the host switched GPUs.
+    /// Terminal state and the program are unaffected.
+    pub fn set_device(&mut self, device: &wgpu::Device, format: wgpu::TextureFormat) {
+        self.renderer.set_device(device, format);
+    }
+
+    /// Draw at another scale factor, when the window moved to a monitor
+    /// with another scale or the scale setting changed (Wayland reports
+    /// fractional scales such as 1.25 and 1.5). Glyphs are rasterized at
+    /// the new size, so they stay sharp, and the grid is refitted.
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        self.renderer.set_scale_factor(scale_factor);
+        self.resize(self.width, self.height);
+    }
+
+    /// Whether the program has exited (or the connection closed).
+    pub fn has_exited(&self) -> bool {
+        self.exited
+    }
+
+    /// The exit code of the program, if it exited and the backend knows it.
+    pub fn exit_code(&self) -> Option<u32> {
+        self.exit_code
+    }
+
+    /// Drain notifications from the terminal (title changes, bell).
+    pub fn take_events(&mut self) -> Vec<TermEvent> {
+        self.terminal.take_events()
+    }
+
+    /// The visible screen as text, one line per row with trailing blanks
+    /// trimmed.
+    pub fn screen_lines(&self) -> Vec<String> {
+        let grid = self.terminal.grid();
+        (0..grid.rows())
+            .map(|row| grid.visible_row(row).text())
+            .collect()
+    }
+
+    /// The cursor as (row, column) on the visible screen, if it is shown
+    /// and not scrolled out of view.
+    pub fn cursor_position(&self) -> Option<(usize, usize)> {
+        let grid = self.terminal.grid();
+        let row = grid.cursor.row + grid.display_offset();
+        (self.terminal.mode().contains(Mode::SHOW_CURSOR) && row < grid.rows())
+            .then_some((row, grid.cursor.col))
+    }
+
+    /// The screen as assistive technology sees it.
+    pub fn accessible_screen(&self) -> AccessibleScreen {
+        AccessibleScreen {
+            lines: self.screen_lines(),
+            cursor: self.cursor_position(),
+            selection: self.selection_text(),
+            selected: self.visible_selection(),
+        }
+    }
+
+    /// The first and last selected cells as (row, column) on the visible
+    /// screen, cut to the rows shown; `None` if none of it is shown.
+    fn visible_selection(&self) -> Option<((usize, usize), (usize, usize))> {
+        let (first, last) = self.selection.as_ref()?.range();
+        let grid = self.terminal.grid();
+        let top = grid.visible_line_index(0);
+        let bottom = top + grid.rows() - 1;
+        if last.line < top || first.line > bottom {
+            return None;
+        }
+        let first = if first.line < top {
+            (0, 0)
+        } else {
+            (first.line - top, first.col)
+        };
+        let last = if last.line > bottom {
+            (grid.rows() - 1, grid.cols() - 1)
+        } else {
+            (last.line - top, last.col)
+        };
+        Some((first, last))
+    }
+
+    /// The selected text, if anything is selected.
+    pub fn selection_text(&self) -> Option<String> {
+        let selection = self.selection.as_ref()?;
+        Some(
+            self.terminal
+                .grid()
+                .selection_text_with(selection, self.copy_options),
+        )
+    }
+
+    /// How selections become copied text (`Config::copy_trim_trailing_whitespace`,
+    /// `Config::copy_join_wrapped_lines`).
+    pub fn copy_options(&self) -> CopyOptions {
+        self.copy_options
+    }
+
+    /// The selected text with its colors and style, as drawn.
+    pub fn styled_selection(&self) -> Option<StyledText> {
+        let selection = self.selection.as_ref()?;
+        Some(StyledText::from_selection(
+            self.terminal.grid(),
+            selection,
+            self.copy_options,
+            self.renderer.default_colors(),
+            |cell| self.renderer.cell_colors(cell),
+        ))
+    }
+
+    /// Select everything: the scrollback and the screen.
+    pub fn select_all(&mut self) {
+        let grid = self.terminal.grid();
+        let mut selection = Selection::new(Point::new(0, 0));
+        selection.update(Point::new(
+            grid.total_lines() - 1,
+            grid.cols().saturating_sub(1),
+        ));
+        self.set_selection(Some(selection));
+    }
+
+    /// Select the selection before the current one again. Returns `false`
+    /// when there is none.
+    pub fn reselect(&mut self) -> bool {
+        let Some(last) = self.last_selection else {
+            return false;
+        };
+        self.set_selection(Some(last));
+        true
+    }
+
+    /// Replace the selection, keeping the one replaced for [`reselect`].
+    ///
+    /// [`reselect`]: Self::reselect
+    fn set_selection(&mut self, selection: Option<Selection>) {
+        if let Some(old) = self.selection
+            && !old.is_empty()
+        {
+            self.last_selection = Some(old);
+        }
+        self.selection = selection;
+    }
+
+    /// Let the keys move the selection's free end until Enter copies it or
+    /// Escape leaves: arrows by a cell, Shift+arrows by a word or a line,
+    /// Page Up and Page Down by a screen. Without a selection, one starts
+    /// at the cursor.
+    pub fn start_copy_mode(&mut self) {
+        if self.selection.is_none() {
+            let grid = self.terminal.grid();
+            let cursor = Point::new(grid.history_len() + grid.cursor.row, grid.cursor.col);
+            self.set_selection(Some(Selection::new(cursor)));
+        }
+        self.copy_mode = true;
+        self.reveal_selection();
+        self.notify("Copy mode: arrows select, Enter copies, Escape leaves");
+    }
+
+    /// Whether keys move the selection (see [`start_copy_mode`]).
+    ///
+    /// [`start_copy_mode`]: Self::start_copy_mode
+    pub fn copy_mode(&self) -> bool {
+        self.copy_mode
+    }
+
+    /// Reset the terminal like RIS, for when binary output left it
+    /// garbled: the screens and scrollback are cleared and modes,
+    /// charsets, attributes and colors go back to their defaults. The
+    /// program keeps running.
+    pub fn reset_terminal(&mut self) {
+        self.terminal.reset();
+        self.selection = None;
+        self.last_selection = None;
+        self.copy_mode = false;
+        self.hints = None;
+        self.hovered_link = None;
+        self.show_tooltip(None);
+        self.notify("Terminal reset");
+    }
+
+    /// Label the URLs, paths and other matches on screen and let the next
+    /// keys pick one (see [`crate::hints`]). Returns `false` when there is
+    /// nothing to pick.
+    pub fn start_hints(&mut self) -> bool {
+        let hints = self.hint_matcher.find(self.terminal.grid());
+        if hints.is_empty() {
+            return false;
+        }
+        self.hints = Some(hints);
+        true
+    }
+
+    /// Whether hints mode is waiting for a label.
+    pub fn hints_active(&self) -> bool {
+        self.hints.is_some()
+    }
+
+    /// The hint picked in hints mode or by right-clicking a match, and what
+    /// to do with it. Copying, opening and running programs are up to the
+    /// host; it can pass pasted text to [`paste`].
+    ///
+    /// [`paste`]: Self::paste
+    pub fn take_picked_hint(&mut self) -> Option<HintPick> {
+        self.picked_hint.take()
+    }
+
+    /// The mouse cursor to show over the widget: a pointer over a link
+    /// that can be clicked.
+    pub fn cursor_icon(&self) -> CursorIcon {
+        if self.hovered_link.is_some() {
+            CursorIcon::Pointer
+        } else {
+            CursorIcon::Default
+        }
+    }
+
+    /// Programs running in the foreground besides the shell (see
+    /// [`Backend::foreground_processes`]).
+    pub fn foreground_processes(&self) -> Vec<String> {
+        self.backend.foreground_processes()
+    }
+
+    /// Give `key` with `modifiers` to the key bindings
+    /// (`Config::key_bindings`), showing the keys that can come next while
+    /// a sequence is pending. Unless it is [`KeyOutcome::Unbound`] the key
+    /// must not reach the shortcuts or the program.
+    pub fn key_binding(&mut self, modifiers: ModifiersState, key: &Key) -> KeyOutcome {
+        if self.bindings.is_empty() {
+            return KeyOutcome::Unbound;
+        }
+        let outcome = self.bindings.key(modifiers, key, Instant::now());
+        if let Some(layer) = self.key_hints.take() {
+            self.ui.remove(layer);
+        }
+        if let Some(deadline) = self.bindings.deadline() {
+            let (rows, cols) = (self.terminal.rows(), self.view_cols());
+            let hints = Layer::key_hints(&self.bindings.next_keys(), deadline, rows, cols);
+            self.key_hints = Some(self.ui.push(hints));
+        }
+        outcome
+    }
+
+    /// A key held down and repeating, for the key bindings: what its press
+    /// ran, as [`KeyOutcome::Run`], whether to run it again being up to the
+    /// host (see [`KeyBindings::repeat`]).
+    pub fn key_binding_repeat(&mut self) -> KeyOutcome {
+        self.bindings.repeat(Instant::now())
+    }
+
+    /// Whether `key` with `modifiers` goes to the foreground program rather
+    /// than running the host's shortcut (`Config::passthrough`).
+    pub fn passes_through(&self, modifiers: ModifiersState, key: &Key) -> bool {
+        if let Some((_, keys)) = &self.app_profile
+            && keys.iter().any(|hotkey| hotkey.matches(modifiers, key))
+        {
+            return true;
+        }
+        self.passthrough
+            .passes(modifiers, key, || self.backend.foreground_job())
+    }
+
+    /// Programs in the foreground, the shell included (see
+    /// [`Backend::foreground_job`]).
+    pub fn foreground_job(&self) -> Vec<String> {
+        self.backend.foreground_job()
+    }
+
+    /// Apply an app profile's cursor, mouse and passthrough settings, or
+    /// go back to the configured ones. Its colors and PRIMARY selection
+    /// are up to the host.
+    pub fn set_app_profile(&mut self, profile: Option<&AppProfile>) {
+        self.app_profile = profile.map(|profile| {
+            // Checked when the widget was made
+            let keys = passthrough::parse_keys(profile.passthrough).unwrap_or_default();
+            (profile.clone(), keys)
+        });
+    }
+
+    /// The app profile applied with [`set_app_profile`](Self::set_app_profile).
+    pub fn app_profile(&self) -> Option<&AppProfile> {
+        self.app_profile.as_ref().map(|(profile, _)| profile)
+    }
+
+    /// The cursor to draw: the app profile's, or the program's.
+    fn cursor_style(&self) -> CursorStyle {
+        (self.app_profile().and_then(|profile| profile.cursor))
+            .unwrap_or_else(|| self.terminal.cursor_style())
+    }
+
+    /// The overlays drawn over the terminal.
+    pub fn ui(&self) -> &Ui {
+        &self.ui
+    }
+
+    /// Add or change overlays. Layers are laid out in cells of the
+    /// terminal's grid.
+    pub fn ui_mut(&mut self) -> &mut Ui {
+        &mut self.ui
+    }
+
+    /// Show a short notification in the corner.
+    pub fn notify(&mut self, text: &str) {
+        let (rows, cols) = (self.terminal.rows(), self.view_cols());
+        self.ui.toast(text, Instant::now(), rows, cols);
+    }
+
+    /// The overlay element activated since the last call: its layer, which
+    /// is closed by then, and the element's id.
+    pub fn take_activated(&mut self) -> Option<(LayerId, usize)> {
+        self.activated.take()
+    }
+
+    /// Open the command palette listing `commands` (see
+    /// [`crate::commands::commands`]).
+    pub fn open_command_palette(&mut self, commands: Vec<Command>) {
+        if let Some((layer, _)) = self.palette.take() {
+            self.ui.remove(layer);
+        }
+        let palette = CommandPalette::new(commands);
+        let layer = palette.layer(self.terminal.rows(), self.view_cols());
+        self.palette = Some((self.ui.push(layer), palette));
+    }
+
+    /// Open the clipboard history picker over `entries`, most recent
+    /// first. The picked entry is pasted like any other text.
+    pub fn open_clipboard_picker(&mut self, entries: Vec<String>) {
+        if let Some((layer, _)) = self.clipboard_picker.take() {
+            self.ui.remove(layer);
+        }
+        let picker = ClipboardPicker::new(entries);
+        let layer = picker.layer(self.terminal.rows(), self.view_cols());
+        self.clipboard_picker = Some((self.ui.push(layer), picker));
+    }
+
+    /// Ask for a name for the terminal, shown instead of the program's
+    /// title (see [`Terminal::rename`]). An empty name goes back to the
+    /// program's.
+    pub fn start_rename(&mut self) {
+        if let Some((layer, _)) = self.rename.take() {
+            self.ui.remove(layer);
+        }
+        let name = self.terminal.title().unwrap_or_default().to_string();
+        let layer = Self::rename_layer(&name, self.terminal.rows(), self.view_cols());
+        self.rename = Some((self.ui.push(layer), name));
+    }
+
+    /// Whether the rename prompt is open.
+    pub fn is_renaming(&self) -> bool {
+        self.rename.is_some()
+    }
+
+    fn rename_layer(name: &str, rows: usize, cols: usize) -> Layer {
+        Layer::prompt("Rename (empty for the program's title)", name, rows, cols)
+    }
+
+    /// Ask for text to find in the scrollback. Its matches are marked on
+    /// the scrollbar and the minimap while the prompt is open; Enter shows
+    /// the next older one, Shift+Enter the next newer one.
+    pub fn start_search(&mut self) {
+        if let Some((layer, _)) = self.search.take() {
+            self.ui.remove(layer);
+        }
+        let layer = Self::search_layer("", 0, self.terminal.rows(), self.view_cols());
+        self.search = Some((self.ui.push(layer), String::new()));
+        self.search_matches.clear();
+    }
+
+    fn search_layer(query: &str, matches: usize, rows: usize, cols: usize) -> Layer {
+        let title = match matches {
+            0 => "Search (Enter: older, Shift+Enter: newer)".to_string(),
+            1 => "Search: 1 match".to_string(),
+            n => format!("Search: {} matches", n),
+        };
+        Layer::prompt(&title, query, rows, cols)
+    }
+
+    /// Whether the command palette is open.
+    pub fn command_palette_open(&self) -> bool {
+        self.palette.is_some()
+    }
+
+    /// The action picked in the command palette. Running it is up to the
+    /// host, like for [`take_picked_hint`](Self::take_picked_hint).
+    pub fn take_command(&mut self) -> Option<Action> {
+        self.command.take()
+    }
+
+    /// The text of a selection the user just finished with the mouse, for
+    /// hosts that keep a primary selection (see
+    /// `MouseConfig::primary_selection`).
+    pub fn take_mouse_selection(&mut self) -> Option<String> {
+        self.mouse_selection.take()
+    }
+
+    /// Whether a middle click asked to paste the primary selection since
+    /// the last call. Reading it is up to the host, which passes the text
+    /// to [`paste`](Self::paste).
+    pub fn take_primary_paste(&mut self) -> bool {
+        std::mem::take(&mut self.primary_paste)
+    }
+
+    /// What a mouse binding (`MouseCommand::Run`) asked for since the last
+    /// call. Running it is up to the host.
+    pub fn take_mouse_action(&mut self) -> Option<HookAction> {
+        self.mouse_action.take()
+    }
+
+    /// Scroll the view by `lines` into the scrollback (negative: towards
+    /// the latest output).
+    pub fn scroll(&mut self, lines: isize) {
+        let grid = self.terminal.grid_mut();
+        let before = grid.display_offset() as isize;
+        grid.scroll_display(lines);
+        let moved = grid.display_offset() as isize - before;
+        self.animate_scroll(moved);
+        // Other text is under the pointer now
+        if moved != 0 {
+            self.refresh_hovered_link();
+        }
+    }
+
+    /// Move the view of a grid wider than the window by `columns`, right
+    /// when positive. Returns whether it moved.
+    pub fn scroll_columns(&mut self, columns: isize) -> bool {
+        if !self.terminal.grid_mut().scroll_columns(columns) {
+            return false;
+        }
+        // Other text is under the pointer now
+        self.pointer_cell = self.logical_cell(self.pointer_screen_cell);
+        self.refresh_hovered_link();
+        true
+    }
+
+    /// Draw the view moving by `lines` over the next frames, if animated.
+    fn animate_scroll(&mut self, lines: isize) {
+        let limit = self.terminal.rows() as f32;
+        if let Some(animation) = &mut self.scroll_animation
+            && lines != 0
+        {
+            animation.jump(lines as f32, limit, Instant::now());
+        }
+    }
+
+    /// Send text as if it was typed, bracketed when the application asked
+    /// for it (so shells don't run pasted commands line by line). Without
+    /// bracketing, text with line breaks or over the size limit is shown
+    /// first, to be pasted or not. Control characters are dropped before
+    /// (`Config::paste`).
+    pub fn paste(&mut self, text: &str) {
+        let text = &*paste::sanitize(&self.paste, text);
+        let bracketed = self.terminal.mode().contains(Mode::BRACKETED_PASTE);
+        if !self.read_only && paste::needs_confirmation(&self.paste, text, bracketed) {
+            if let Some((layer, _)) = self.paste_prompt.take() {
+                self.ui.remove(layer);
+            }
+            let layer = Self::paste_layer(text, self.terminal.rows(), self.view_cols());
+            self.paste_prompt = Some((self.ui.push(layer), text.to_string()));
+            return;
+        }
+        self.send_paste(text);
+    }
+
+    fn paste_layer(text: &str, rows: usize, cols: usize) -> Layer {
+        let message = paste::preview(text, cols.saturating_sub(4).min(72));
+        let buttons = [("Paste", PASTE_BUTTON), ("Cancel", CANCEL_PASTE_BUTTON)];
+        let mut layer = Layer::dialog(&message, &buttons, rows, cols);
+        layer.set_focus(CANCEL_PASTE_BUTTON);
+        layer
+    }
+
+    fn send_paste(&mut self, text: &str) {
+        let text = paste::line_endings(&self.paste, text);
+        let bracketed = self.terminal.mode().contains(Mode::BRACKETED_PASTE);
+        let mut bytes = Vec::with_capacity(text.len() + 12);
+        if bracketed {
+            bytes.extend_from_slice(b"\x1b[200~");
+        }
+        bytes.extend_from_slice(text.as_bytes());
+        if bracketed {
+            bytes.extend_from_slice(b"\x1b[201~");
+        }
+        self.terminal.grid_mut().reset_display();
+        self.follow_cursor = true;
+        self.write(&bytes);
+    }
+
+    /// Whether the widget has keyboard focus.
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Gain or lose keyboard focus. Unfocused widgets draw a hollow,
+    /// non-blinking cursor; the application is told when it asked for
+    /// focus reports (mode 1004).
+    ///
+    /// Called for `WindowEvent::Focused`; hosts that manage focus between
+    /// several panes themselves call it directly.
+    pub fn set_focused(&mut self, focused: bool) {
+        if focused == self.focused {
+            return;
+        }
+        self.focused = focused;
+        self.blink_epoch = Instant::now();
+        if self.terminal.mode().contains(Mode::FOCUS_EVENTS) {
+            self.send(if focused { b"\x1b[I" } else { b"\x1b[O" });
+        }
+    }
+
+    /// Where the input method should put its candidate window: the cursor
+    /// cell, in the render target's pixels. Hosts pass it to
+    /// `Window::set_ime_cursor_area`.
+    pub fn ime_cursor_area(&self) -> (PhysicalPosition<f64>, PhysicalSize<f64>) {
+        let metrics = self.renderer.cell_metrics();
+        let grid = self.terminal.grid();
+        let cursor = &grid.cursor;
+        let mut col = cursor.col;
+        if self.renderer.bidi()
+            && let Some(bidi) = BidiRow::new(grid.row(cursor.row))
+        {
+            col = bidi.visual(col);
+        }
+        let col = col.saturating_sub(grid.column_offset());
+        let x = self.position[0] + col as f32 * metrics.width;
+        let row = cursor.row + self.rows_above();
+        let y = self.position[1] + row as f32 * metrics.height;
+        (
+            PhysicalPosition::new(x as f64, y as f64),
+            PhysicalSize::new(metrics.width as f64, metrics.height as f64),
+        )
+    }
+
+    /// Place the widget inside the render target.
+    pub fn set_position(&mut self, x: f32, y: f32) {
+        self.position = [x, y];
+    }
+
+    /// Resize the widget, reflowing the grid and notifying the backend.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        self.width = width;
+        self.height = height;
+        self.renderer.resize(width, height);
+
+        let (view_cols, rows) = fit_grid(
+            &self.renderer,
+            self.status_bar.is_some(),
+            self.show_minimap,
+            width,
+            height,
+        );
+        let cols = view_cols.max(self.wide_columns);
+        if view_cols != self.view_cols() {
+            self.terminal.set_view_cols(view_cols);
+            self.pointer_cell = self.logical_cell(self.pointer_screen_cell);
+        }
+        if (cols, rows) != (self.terminal.cols(), self.terminal.rows()) {
+            self.selection = None;
+            self.last_selection = None;
+            self.copy_mode = false;
+            self.hints = None;
+            self.show_tooltip(None);
+            self.terminal.resize(cols, rows);
+            self.terminal.set_view_cols(view_cols);
+            self.backend.resize(cols as u16, rows as u16);
+            self.refresh_palette();
+            self.refresh_clipboard_picker();
+            self.refresh_rename();
+            self.refresh_search();
+            self.refresh_paste_prompt();
+        }
+    }
+
+    /// Apply pending backend output. Returns `true` if anything changed.
+    pub fn update(&mut self) -> bool {
+        let mut changed = false;
+        let mut output = false;
+        let now = Instant::now();
+        if !self.exited {
+            self.release_input();
+        }
+        while let Some(event) = self.backend.poll() {
+            match event {
+                BackendEvent::Output(mut bytes) => {
+                    if let Some(recorder) = &mut self.recorder
+                        && let Err(e) = recorder.record(&bytes, now)
+                    {
+                        log::warn!("Stopped recording: {}", e);
+                        self.recorder = None;
+                    }
+                    self.plugins.filter_output(&mut bytes);
+                    self.terminal.feed(&bytes);
+                    self.alert = self.monitor.output(now).or(self.alert);
+                    output = true;
+                }
+                BackendEvent::Exited(code) => {
+                    self.exited = true;
+                    self.held_input.clear();
+                    self.exit_code = code;
+                }
+            }
+            changed = true;
+        }
+
+        if output && std::mem::take(&mut self.follow_cursor) {
+            let grid = self.terminal.grid_mut();
+            let col = grid.cursor.col;
+            grid.show_column(col);
+            self.pointer_cell = self.logical_cell(self.pointer_screen_cell);
+        }
+
+        // The text under the pointer may have changed
+        if output {
+            changed |= self.refresh_hovered_link();
+        }
+
+        // Lines moved or changed under the matches
+        if output && self.search.is_some() {
+            self.refresh_search();
+        }
+
+        if output && !self.watch.is_empty() && !self.terminal.mode().contains(Mode::ALT_SCREEN) {
+            let hits = self.watch.scan(self.terminal.grid_mut());
+            self.watch_hits.extend(hits);
+        }
+
+        self.alert = self.monitor.check(now).or(self.alert);
+
+        if let Some(bar) = &mut self.status_bar {
+            changed |= bar.refresh(&self.terminal, now);
+        }
+
+        let responses = self.terminal.take_responses();
+        if !responses.is_empty() {
+            self.send(&responses);
+        }
+
+        let scrolled = self.terminal.grid_mut().take_scrolled();
+        if self.animate_output {
+            self.animate_scroll(-(scrolled as isize));
+        }
+
+        if self.smooth_scroll.is_gliding() {
+            let lines = self.smooth_scroll.glide(Instant::now());
+            changed |= self.scroll_lines(lines);
+        }
+
+        if self.auto_scroll.is_running() {
+            let lines = self.auto_scroll.step(Instant::now());
+            if lines != 0 {
+                self.scroll(lines);
+                changed |= self.drag_selection();
+            }
+        }
+
+        changed
+    }
+
+    /// Whether the scrollback is still gliding after a touchpad flick, a
+    /// selection is dragged past the edge, or the rows are sliding into
+    /// place; keep calling [`update`](Self::update) and rendering every
+    /// frame while it does.
+    pub fn is_scrolling(&self) -> bool {
+        let now = Instant::now();
+        self.smooth_scroll.is_gliding()
+            || self.auto_scroll.is_running()
+            || self
+                .scroll_animation
+                .as_ref()
+                .is_some_and(|animation| animation.is_running(now))
+    }
+
+    /// Send raw input bytes to the program. Nothing is sent once it
+    /// exited; while read-only, the input is dropped with a notice.
+    pub fn write(&mut self, bytes: &[u8]) {
+        if self.read_only {
+            self.notify("Read-only: input not sent");
+            return;
+        }
+        self.send(bytes);
+    }
+
+    /// Whether input is dropped instead of sent.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Lock the widget against input, or unlock it. Answers to the
+    /// program's queries and focus reports still go through; clicks
+    /// select text even where the program asked for the mouse.
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+        if matches!(self.drag, Some(Drag::Report(_))) {
+            self.drag = None;
+        }
+    }
+
+    /// Record the program's output to `path` from now on, as it arrives
+    /// (see [`crate::recording`]). A recording already going is finished
+    /// first.
+    pub fn start_recording(&mut self, path: &Path) -> std::io::Result<()> {
+        self.stop_recording();
+        let (cols, rows) = (self.terminal.cols(), self.terminal.rows());
+        self.recorder = Some(Recorder::create(path, cols, rows, Instant::now())?);
+        Ok(())
+    }
+
+    /// Finish the recording, returning where it went.
+    pub fn stop_recording(&mut self) -> Option<PathBuf> {
+        let recorder = self.recorder.take()?;
+        let path = recorder.path().to_path_buf();
+        if let Err(e) = recorder.finish() {
+            log::warn!("Failed to finish the recording {}: {}", path.display(), e);
+        }
+        Some(path)
+    }
+
+    /// The file the output is being recorded to.
+    pub fn recording(&self) -> Option<&Path> {
+        self.recorder.as_ref().map(Recorder::path)
+    }
+
+    /// What the output is watched for, if anything.
+    pub fn monitor(&self) -> Option<Monitor> {
+        self.monitor.watching()
+    }
+
+    /// Watch the output for activity or silence from now on, or stop
+    /// with `None`. What was seen is reported by
+    /// [`take_alert`](Self::take_alert).
+    pub fn set_monitor(&mut self, monitor: Option<Monitor>) {
+        self.monitor.watch(monitor);
+        self.alert = None;
+    }
+
+    /// The status bar, if `Config::status_bar` asks for one.
+    pub fn status_bar(&self) -> Option<&StatusBar> {
+        self.status_bar.as_ref()
+    }
+
+    /// Columns drawn: fewer than the grid's when it is wider than the
+    /// window (`Config::wide_columns`).
+    fn view_cols(&self) -> usize {
+        self.terminal.grid().view_cols()
+    }
+
+    /// Rows drawn above the grid: the status bar's at the top.
+    fn rows_above(&self) -> usize {
+        match self.status_bar.as_ref().map(StatusBar::position) {
+            Some(StatusBarPosition::Top) => 1,
+            _ => 0,
+        }
+    }
+
+    /// The plugins of this pane (`Config::plugins`).
+    pub fn plugins(&self) -> &Plugins {
+        &self.plugins
+    }
+
+    pub fn plugins_mut(&mut self) -> &mut Plugins {
+        &mut self.plugins
+    }
+
+    /// Put what the plugins draw on their layer, which keeps its place
+    /// under the layers opened since.
+    fn refresh_plugin_overlay(&mut self) {
+        if self.plugins.is_empty() {
+            return;
+        }
+        let elements = (self.plugins).overlays(self.terminal.rows(), self.view_cols());
+        if elements.is_empty() {
+            if let Some(layer) = self.plugin_layer.take() {
+                self.ui.remove(layer);
+            }
+            return;
+        }
+        let layer = Layer::new(LayerKind::Status, elements);
+        match self.plugin_layer.and_then(|id| self.ui.layer_mut(id)) {
+            Some(existing) => *existing = layer,
+            None => self.plugin_layer = Some(self.ui.push(layer)),
+        }
+    }
+
+    /// The activity or silence seen since the last call.
+    pub fn take_alert(&mut self) -> Option<Monitor> {
+        self.alert.take()
+    }
+
+    /// What the watch rules asked for since the last call: notifications,
+    /// sounds and programs to run, and the lines of output if
+    /// `Hooks::on_output` is set. Highlights are drawn by the widget.
+    pub fn take_watch_hits(&mut self) -> Vec<WatchHit> {
+        std::mem::take(&mut self.watch_hits)
+    }
+
+    /// Send bytes the widget produces on its own, read-only or not.
+    fn send(&mut self, bytes: &[u8]) {
+        if self.exited {
+            return;
+        }
+        self.held_input.extend(bytes);
+        self.release_input();
+    }
+
+    /// Hand held input to the backend while it has room for it.
+    fn release_input(&mut self) {
+        while !self.held_input.is_empty() && self.backend.pending_input() < INPUT_BUFFER {
+            let n = self.held_input.len().min(INPUT_CHUNK);
+            let chunk: Vec<u8> = self.held_input.drain(..n).collect();
+            self.backend.write(&chunk);
+        }
+    }
+
+    /// Show `bytes` as if the program had written them, for messages from
+    /// the host such as an exit status.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.terminal.feed(bytes);
+    }
+
+    /// Handle a window event aimed at this widget.
+    ///
+    /// Returns `true` if the event was consumed and a redraw is needed.
+    pub fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+                self.refresh_hovered_link()
+            }
+
+            WindowEvent::CursorLeft { .. } => {
+                let tooltip = self.show_tooltip(None);
+                self.hovered_link.take().is_some() || tooltip
+            }
+
+            WindowEvent::KeyboardInput { event, .. } if self.rename.is_some() => {
+                if event.state == ElementState::Pressed {
+                    self.rename_key(&event.logical_key);
+                }
+                true
+            }
+
+            WindowEvent::KeyboardInput { event, .. } if self.search.is_some() => {
+                if event.state == ElementState::Pressed {
+                    self.search_key(&event.logical_key);
+                }
+                true
+            }
+
+            WindowEvent::KeyboardInput { event, .. } if self.palette.is_some() => {
+                if event.state == ElementState::Pressed {
+                    self.palette_key(&event.logical_key);
+                }
+                true
+            }
+
+            WindowEvent::KeyboardInput { event, .. } if self.clipboard_picker.is_some() => {
+                if event.state == ElementState::Pressed {
+                    self.clipboard_picker_key(&event.logical_key);
+                }
+                true
+            }
+
+            WindowEvent::KeyboardInput { event, .. } if self.ui.modal().is_some() => {
+                if event.state == ElementState::Pressed {
+                    self.ui_key(&event.logical_key);
+                }
+                true
+            }
+
+            WindowEvent::KeyboardInput { event, .. } if self.hints.is_some() => {
+                if event.state == ElementState::Pressed {
+                    self.hint_key(&event.logical_key);
+                }
+                true
+            }
+
+            WindowEvent::KeyboardInput { event, .. } if self.copy_mode => {
+                if event.state == ElementState::Pressed {
+                    self.copy_mode_key(&event.logical_key);
+                }
+                true
+            }
+
+            WindowEvent::KeyboardInput { event, .. } => {
+                // Keys belong to the input method while it composes
+                if event.state != ElementState::Pressed || self.preedit.is_some() {
+                    return false;
+                }
+                let Some(bytes) =
+                    input::key_to_bytes(event, self.modifiers, self.terminal.mode(), self.keypad)
+                else {
+                    return false;
+                };
+                self.terminal.grid_mut().reset_display();
+                self.follow_cursor = true;
+                self.smooth_scroll.stop();
+                if let Some(animation) = &mut self.scroll_animation {
+                    animation.stop();
+                }
+                self.blink_epoch = Instant::now();
+                self.write(&bytes);
+                true
+            }
+
+            WindowEvent::Focused(focused) => {
+                self.set_focused(*focused);
+                true
+            }
+
+            WindowEvent::Ime(ime) => match ime {
+                Ime::Preedit(text, cursor) => {
+                    if !text.is_empty() {
+                        let grid = self.terminal.grid_mut();
+                        grid.reset_display();
+                        let col = grid.cursor.col;
+                        grid.show_column(col);
+                    }
+                    self.preedit = (!text.is_empty()).then(|| Preedit {
+                        text: text.clone(),
+                        cursor: cursor.map(|(start, _)| start),
+                    });
+                    true
+                }
+                Ime::Commit(text) if self.search.is_some() => {
+                    self.preedit = None;
+                    if let Some((_, query)) = &mut self.search {
+                        query.push_str(text);
+                    }
+                    self.refresh_search();
+                    true
+                }
+                Ime::Commit(text) if self.rename.is_some() => {
+                    self.preedit = None;
+                    if let Some((_, name)) = &mut self.rename {
+                        name.push_str(text);
+                    }
+                    self.refresh_rename();
+                    true
+                }
+                Ime::Commit(text) => {
+                    self.preedit = None;
+                    self.terminal.grid_mut().reset_display();
+                    self.follow_cursor = true;
+                    self.blink_epoch = Instant::now();
+                    self.write(text.as_bytes());
+                    true
+                }
+                Ime::Disabled => self.preedit.take().is_some(),
+                Ime::Enabled => false,
+            },
+
+            WindowEvent::CursorMoved { position, .. } => {
+                let hovered = self.over_scrollbar();
+                self.pointer = *position;
+                if let Some(Drag::Scrollbar(grab)) = self.drag {
+                    return self.drag_scrollbar(grab);
+                }
+                // The scrollbar shows up or highlights under the pointer
+                let bar_changed =
+                    self.scrollbar_mode != ScrollbarMode::Never && hovered != self.over_scrollbar();
+
+                if let Some(Drag::Select(..)) = self.drag {
+                    let overshoot = self.overshoot(*position);
+                    self.auto_scroll.drag(overshoot, Instant::now());
+                }
+
+                let screen_cell = self.screen_cell_at(*position);
+                let cell = self.logical_cell(screen_cell);
+                self.pointer_screen_cell = screen_cell;
+                if cell == self.pointer_cell {
+                    return bar_changed;
+                }
+                self.pointer_cell = cell;
+
+                match self.drag {
+                    Some(Drag::Select(..)) => self.drag_selection(),
+                    Some(Drag::Report(button)) => {
+                        self.report_mouse(MouseAction::Motion(Some(button)));
+                        bar_changed
+                    }
+                    Some(Drag::Scrollbar(_)) => false,
+                    None => {
+                        if self.mouse_reporting() {
+                            self.report_mouse(MouseAction::Motion(None));
+                        }
+                        self.refresh_hovered_link() || bar_changed
+                    }
+                }
+            }
+
+            WindowEvent::MouseInput { state, button, .. } => match state {
+                ElementState::Pressed => {
+                    if let Some(redraw) = self.ui_click() {
+                        return redraw;
+                    }
+                    // A click on the minimap centers the view on that line
+                    if *button == MouseButton::Left
+                        && let Some(y) = self.minimap_row()
+                    {
+                        let minimap = Minimap::new(self.terminal.grid(), self.height as usize);
+                        let offset = minimap.offset_at(y);
+                        let delta =
+                            offset as isize - self.terminal.grid().display_offset() as isize;
+                        self.scroll(delta);
+                        return true;
+                    }
+                    if *button == MouseButton::Left
+                        && let Some(scrollbar) = self.scrollbar()
+                        && self.over_scrollbar()
+                    {
+                        // Held where it was grabbed; a click on the track
+                        // centers the thumb there, or on a command's mark
+                        // shows that command at the top
+                        let (start, end) = scrollbar.thumb();
+                        let y = self.pointer_fraction();
+                        let reach = self.renderer.cell_metrics().height / 2.0 / self.height as f32;
+                        if !(start..end).contains(&y)
+                            && let Some(line) = scrollbar.command_near(y, reach)
+                        {
+                            let offset = scrollbar.offset_showing(line);
+                            let delta =
+                                offset as isize - self.terminal.grid().display_offset() as isize;
+                            self.scroll(delta);
+                            return true;
+                        }
+                        let grab = if (start..end).contains(&y) {
+                            y - start
+                        } else {
+                            (end - start) / 2.0
+                        };
+                        self.drag = Some(Drag::Scrollbar(grab));
+                        self.drag_scrollbar(grab);
+                        return true;
+                    }
+                    if self.mouse_reporting() {
+                        self.drag = Some(Drag::Report(*button));
+                        self.report_mouse(MouseAction::Press(*button));
+                        return false;
+                    }
+                    let trigger = MouseTrigger::Button(*button);
+                    if let Some(command) = self.mouse_bindings.command(self.modifiers, trigger) {
+                        return self.run_mouse_command(command.clone(), 0);
+                    }
+                    if *button != MouseButton::Left {
+                        return false;
+                    }
+                    if let Some(link) = &self.hovered_link {
+                        self.picked_hint = Some(link.pick.clone());
+                        return true;
+                    }
+                    let point = self.pointer_point();
+                    let unit = match self.count_click(point) {
+                        2 => SelectionUnit::Word,
+                        3 => SelectionUnit::Line,
+                        _ => SelectionUnit::Cell,
+                    };
+                    let (start, end) = self.terminal.grid().unit_at(point, unit, self.word_chars);
+                    self.drag = Some(Drag::Select(unit, (start, end)));
+                    // Alt+drag selects a block
+                    let mut selection = if unit == SelectionUnit::Cell && self.modifiers.alt_key() {
+                        Selection::block(start)
+                    } else {
+                        Selection::new(start)
+                    };
+                    selection.update(end);
+                    self.set_selection(Some(selection));
+                    true
+                }
+                ElementState::Released => match self.drag.take() {
+                    Some(Drag::Report(button)) => {
+                        self.report_mouse(MouseAction::Release(button));
+                        false
+                    }
+                    Some(Drag::Scrollbar(_)) => true,
+                    Some(Drag::Select(unit, _)) => {
+                        self.auto_scroll.stop();
+                        // A click without dragging selects nothing.
+                        if unit == SelectionUnit::Cell
+                            && self.selection.is_some_and(|selection| selection.is_empty())
+                        {
+                            self.selection = None;
+                        }
+                        self.mouse_selection = self.selection_text();
+                        true
+                    }
+                    None => false,
+                },
+            },
+
+            WindowEvent::MouseWheel { delta, phase, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => self.smooth_scroll.lines(y * SCROLL_LINES),
+                    MouseScrollDelta::PixelDelta(pos) => {
+                        let lines = pos.y as f32 / self.renderer.cell_metrics().height;
+                        self.smooth_scroll.pixels(lines, *phase, Instant::now())
+                    }
+                };
+                let trigger = match lines {
+                    0 => return false,
+                    1.. => MouseTrigger::WheelUp,
+                    _ => MouseTrigger::WheelDown,
+                };
+                match self.mouse_bindings.command(self.modifiers, trigger) {
+                    Some(command) => self.run_mouse_command(command.clone(), lines),
+                    None => false,
+                }
+            }
+
+            _ => false,
+        }
+    }
+
+    /// Do what a mouse binding says, for a click or for `lines` turned on
+    /// the wheel. Returns whether to redraw or, for `Run`, whether the
+    /// host has something to do.
+    fn run_mouse_command(&mut self, command: MouseCommand, lines: isize) -> bool {
+        match command {
+            MouseCommand::OpenAtPointer => self.pick_at_pointer(),
+            MouseCommand::PasteSelection => {
+                self.primary_paste = true;
+                true
+            }
+            MouseCommand::Scroll => self.scroll_lines(lines),
+            MouseCommand::ScrollHorizontal => self.scroll_sideways(lines),
+            MouseCommand::Run(action) => {
+                self.mouse_action = Some(action);
+                true
+            }
+        }
+    }
+
+    /// Report `lines` of the wheel as tilted left (positive) or right to an
+    /// application tracking the mouse, whatever the selection modifier.
+    /// Otherwise the view of a wide grid moves, or without one the
+    /// scrollback scrolls. Returns whether to redraw.
+    fn scroll_sideways(&mut self, lines: isize) -> bool {
+        if !self.tracks_mouse() {
+            if self.view_cols() < self.terminal.cols() {
+                return self.scroll_columns(-lines * WHEEL_COLUMNS);
+            }
+            return self.scroll_lines(lines);
+        }
+        let action = if lines > 0 {
+            MouseAction::WheelLeft
+        } else {
+            MouseAction::WheelRight
+        };
+        for _ in 0..lines.unsigned_abs() {
+            self.report_mouse(action);
+        }
+        false
+    }
+
+    /// Scroll the scrollback by `lines` from the wheel or touchpad, or
+    /// report them as wheel clicks to an application tracking the mouse.
+    /// Returns `true` if the view moved.
+    fn scroll_lines(&mut self, lines: isize) -> bool {
+        if lines == 0 {
+            return false;
+        }
+        if self.mouse_reporting() {
+            let action = if lines > 0 {
+                MouseAction::WheelUp
+            } else {
+                MouseAction::WheelDown
+            };
+            for _ in 0..lines.unsigned_abs() {
+                self.report_mouse(action);
+            }
+            return false;
+        }
+        self.scroll(lines);
+        true
+    }
+
+    /// The scrollbar, if it is shown (`Config::scrollbar`).
+    fn scrollbar(&self) -> Option<Scrollbar> {
+        let shown = match self.scrollbar_mode {
+            ScrollbarMode::Never => false,
+            ScrollbarMode::Scrolled => {
+                self.terminal.grid().display_offset() > 0
+                    || matches!(self.drag, Some(Drag::Scrollbar(_)))
+                    || self.over_scrollbar()
+            }
+            ScrollbarMode::Always => true,
+        };
+        Scrollbar::new(self.terminal.grid())
+            .filter(|_| shown)
+            .map(|scrollbar| scrollbar.with_matches(&self.search_matches))
+    }
+
+    /// Whether the pointer is over the scrollbar's strip along the right
+    /// edge, or the minimap's left one.
+    fn over_scrollbar(&self) -> bool {
+        let width = scrollbar::width(self.renderer.cell_metrics().width);
+        let x = self.pointer.x as f32 - self.position[0];
+        let y = self.pointer.y as f32 - self.position[1];
+        let right = self.width as f32 - self.minimap_width();
+        (right - width..right).contains(&x) && (0.0..self.height as f32).contains(&y)
+    }
+
+    /// Width of the minimap's column in pixels; 0 without one.
+    fn minimap_width(&self) -> f32 {
+        if self.show_minimap {
+            minimap::width(self.renderer.cell_metrics().width)
+        } else {
+            0.0
+        }
+    }
+
+    /// The pixel row of the minimap under the pointer, if it is over it.
+    fn minimap_row(&self) -> Option<usize> {
+        let x = self.pointer.x as f32 - self.position[0];
+        let y = self.pointer.y as f32 - self.position[1];
+        let width = self.width as f32;
+        let over = (width - self.minimap_width()..width).contains(&x)
+            && (0.0..self.height as f32).contains(&y);
+        over.then_some(y as usize)
+    }
+
+    /// Make the minimap again if the scrollback, the view or the height
+    /// changed since it was last drawn.
+    fn refresh_minimap(&mut self) {
+        if !self.show_minimap {
+            return;
+        }
+        let grid = self.terminal.grid();
+        let key = (
+            self.terminal.generation(),
+            grid.display_offset(),
+            self.height,
+        );
+        if self.minimap.as_ref().is_none_or(|(made, _)| *made != key) {
+            let minimap =
+                Minimap::new(grid, self.height as usize).with_matches(&self.search_matches);
+            self.minimap = Some((key, minimap));
+        }
+    }
+
+    /// How far down the pointer is, as a fraction of the widget's height.
+    fn pointer_fraction(&self) -> f32 {
+        (self.pointer.y as f32 - self.position[1]) / self.height as f32
+    }
+
+    /// Scroll to where the thumb, held `grab` below its top, follows the
+    /// pointer. Returns `true` if the view moved.
+    fn drag_scrollbar(&mut self, grab: f32) -> bool {
+        let Some(scrollbar) = Scrollbar::new(self.terminal.grid()) else {
+            return false;
+        };
+        let offset = scrollbar.offset_at(self.pointer_fraction() - grab);
+        // The rows follow the pointer right away
+        self.smooth_scroll.stop();
+        if let Some(animation) = &mut self.scroll_animation {
+            animation.stop();
+        }
+        let grid = self.terminal.grid_mut();
+        let delta = offset as isize - grid.display_offset() as isize;
+        grid.scroll_display(delta);
+        delta != 0
+    }
+
+    /// Whether mouse input goes to the application rather than selecting
+    /// text: it must have enabled mouse tracking, the widget must not be
+    /// read-only, and the selection modifier (Shift) overrides it.
+    fn mouse_reporting(&self) -> bool {
+        self.tracks_mouse() && self.modifiers.shift_key() == self.swap_selection_modifier
+    }
+
+    /// Whether the application tracks the mouse and may be told about it:
+    /// neither the widget being read-only nor the app profile stops it.
+    fn tracks_mouse(&self) -> bool {
+        !self.read_only
+            && !self
+                .app_profile()
+                .is_some_and(|profile| profile.disable_mouse_reporting)
+            && self.terminal.mode().intersects(Mode::MOUSE_MODE)
+    }
+
+    fn report_mouse(&mut self, action: MouseAction) {
+        let mut modifiers = self.modifiers;
+        // Shift was only held to route the event here.
+        if self.swap_selection_modifier {
+            modifiers.remove(ModifiersState::SHIFT);
+        }
+        let (col, row) = self.pointer_cell;
+        if let Some(bytes) = input::mouse_report(action, col, row, modifiers, self.terminal.mode())
+        {
+            self.write(&bytes);
+        }
+    }
+
+    /// Extend the selection being dragged to the pointer. Returns whether
+    /// there is one.
+    fn drag_selection(&mut self) -> bool {
+        let Some(Drag::Select(unit, anchor)) = self.drag else {
+            return false;
+        };
+        let point = self.pointer_point();
+        let head = self.terminal.grid().unit_at(point, unit, self.word_chars);
+        let Some(selection) = &mut self.selection else {
+            return false;
+        };
+        selection.extend(anchor, head);
+        true
+    }
+
+    /// How many lines a pointer position is above the grid, or below it
+    /// (negative); 0 over it.
+    fn overshoot(&self, position: PhysicalPosition<f64>) -> f32 {
+        let metrics = self.renderer.cell_metrics();
+        let y = (position.y as f32 - self.position[1]) / metrics.height - self.rows_above() as f32;
+        let rows = self.terminal.rows() as f32;
+        if y < 0.0 {
+            -y
+        } else if y > rows {
+            rows - y
+        } else {
+            0.0
+        }
+    }
+
+    /// The cell (column, row) drawn under a pointer position, clamped to
+    /// the grid.
+    fn screen_cell_at(&self, position: PhysicalPosition<f64>) -> (usize, usize) {
+        let metrics = self.renderer.cell_metrics();
+        let x = (position.x as f32 - self.position[0]) / metrics.width;
+        let y = (position.y as f32 - self.position[1]) / metrics.height - self.rows_above() as f32;
+        let col = (x.max(0.0) as usize).min(self.view_cols() - 1);
+        let row = (y.max(0.0) as usize).min(self.terminal.rows() - 1);
+        (col, row)
+    }
+
+    /// The grid cell shown at a screen cell: the view of a wide grid may
+    /// be scrolled sideways, and reordered rows are hit-tested in visual
+    /// order.
+    fn logical_cell(&self, (col, row): (usize, usize)) -> (usize, usize) {
+        let col = col + self.terminal.grid().column_offset();
+        if self.renderer.bidi()
+            && let Some(bidi) = BidiRow::new(self.terminal.grid().visible_row(row))
+        {
+            return (bidi.logical(col), row);
+        }
+        (col, row)
+    }
+
+    /// A mouse button pressed over the overlays: activates the element
+    /// under the pointer, dismisses a toast or a menu clicked outside of.
+    /// Returns `None` if the overlays don't take the click, otherwise
+    /// whether to redraw.
+    fn ui_click(&mut self) -> Option<bool> {
+        let (col, row) = self.pointer_screen_cell;
+        let redraw = self.click_layers(row, col);
+        // A menu clicked outside of may have been the palette
+        if let Some((layer, _)) = &self.palette
+            && self.ui.layer(*layer).is_none()
+        {
+            self.palette = None;
+        }
+        if let Some((layer, _)) = &self.clipboard_picker
+            && self.ui.layer(*layer).is_none()
+        {
+            self.clipboard_picker = None;
+        }
+        redraw
+    }
+
+    fn click_layers(&mut self, row: usize, col: usize) -> Option<bool> {
+        match self.ui.hit(row, col) {
+            Some((layer, Some(id))) => {
+                self.ui.remove(layer);
+                match &mut self.palette {
+                    Some((palette_layer, palette)) if *palette_layer == layer => {
+                        palette.select(id);
+                        self.command = palette.selected();
+                    }
+                    _ if self
+                        .clipboard_picker
+                        .as_ref()
+                        .is_some_and(|(picker_layer, _)| *picker_layer == layer) =>
+                    {
+                        let (_, mut picker) = self.clipboard_picker.take().unwrap();
+                        picker.select(id);
+                        if let Some(text) = picker.selected() {
+                            self.paste(text);
+                        }
+                    }
+                    _ => self.activate(layer, id),
+                }
+                Some(true)
+            }
+            Some((layer, None)) => {
+                let toast = self
+                    .ui
+                    .layer(layer)
+                    .is_some_and(|layer| layer.kind == LayerKind::Toast);
+                if toast {
+                    self.ui.remove(layer);
+                }
+                Some(toast)
+            }
+            None => {
+                let modal = self.ui.modal()?;
+                if self.ui.layer(modal)?.kind == LayerKind::Menu {
+                    self.ui.remove(modal);
+                    return Some(true);
+                }
+                // Dialogs keep the input to themselves
+                Some(false)
+            }
+        }
+    }
+
+    /// A key pressed while a dialog or menu is open: Tab and the arrow
+    /// keys move the focus, Enter activates, Escape closes.
+    fn ui_key(&mut self, key: &Key) {
+        let Some(id) = self.ui.modal() else {
+            return;
+        };
+        let Some(layer) = self.ui.layer_mut(id) else {
+            return;
+        };
+        let back = self.modifiers.shift_key();
+        match key {
+            Key::Named(NamedKey::Escape) => {
+                self.ui.remove(id);
+                if self
+                    .paste_prompt
+                    .as_ref()
+                    .is_some_and(|(layer, _)| *layer == id)
+                {
+                    self.paste_prompt = None;
+                }
+            }
+            Key::Named(NamedKey::Enter) => {
+                if let Some(element) = layer.focused().and_then(|element| element.id) {
+                    self.ui.remove(id);
+                    self.activate(id, element);
+                }
+            }
+            Key::Named(NamedKey::Tab) if back => layer.focus_prev(),
+            Key::Named(NamedKey::ArrowUp | NamedKey::ArrowLeft) => layer.focus_prev(),
+            Key::Named(NamedKey::Tab | NamedKey::ArrowDown | NamedKey::ArrowRight) => {
+                layer.focus_next()
+            }
+            _ => {}
+        }
+    }
+
+    /// A button of a removed overlay pressed: the paste preview's are
+    /// handled here, the others wait for the host.
+    fn activate(&mut self, layer: LayerId, id: usize) {
+        match self.paste_prompt.take() {
+            Some((prompt, text)) if prompt == layer => {
+                if id == PASTE_BUTTON {
+                    self.send_paste(&text);
+                }
+            }
+            prompt => {
+                self.paste_prompt = prompt;
+                self.activated = Some((layer, id));
+            }
+        }
+    }
+
+    /// Find the link under the pointer again, if the link modifier is held.
+    /// Returns whether it changed.
+    fn refresh_hovered_link(&mut self) -> bool {
+        let held = match self.link_modifier {
+            LinkModifier::Control => self.modifiers.control_key(),
+            LinkModifier::Shift => self.modifiers.shift_key(),
+            LinkModifier::Alt => self.modifiers.alt_key(),
+            LinkModifier::Super => self.modifiers.super_key(),
+        };
+        let hovering = self.drag.is_none() && self.hints.is_none() && !self.mouse_reporting();
+        let hyperlink = hovering.then(|| self.hyperlink_at_pointer()).flatten();
+        let tooltip = self.show_tooltip(hyperlink.as_ref());
+        let link = if held && hovering {
+            hyperlink.or_else(|| {
+                let (col, row) = self.pointer_cell;
+                let hints = self.hint_matcher.find(self.terminal.grid());
+                hints.hint_at(row, col).cloned()
+            })
+        } else {
+            None
+        };
+        let changed = link != self.hovered_link;
+        self.hovered_link = link;
+        changed || tooltip
+    }
+
+    /// The OSC 8 hyperlink under the pointer, as a hint opening its
+    /// target: the run of cells around the pointer with the same link.
+    fn hyperlink_at_pointer(&self) -> Option<Hint> {
+        let grid = self.terminal.grid();
+        let cols = grid.cols();
+        let link_at = |index: usize| grid.visible_row(index / cols)[index % cols].link;
+        let (col, row) = self.pointer_cell;
+        let index = row * cols + col;
+        let link = link_at(index);
+        let target = self.terminal.hyperlink(link)?;
+        let mut start = index;
+        while start > 0 && link_at(start - 1) == link {
+            start -= 1;
+        }
+        let mut end = index + 1;
+        while end < grid.rows() * cols && link_at(end) == link {
+            end += 1;
+        }
+        Some(Hint {
+            row: start / cols,
+            col: start % cols,
+            width: end - start,
+            text: target.to_string(),
+            pattern: "hyperlink",
+            pick: HintPick::Open(target.to_string()),
+            label: String::new(),
+        })
+    }
+
+    /// Show where `hyperlink` goes under it, or take the tooltip away.
+    /// Returns whether that changed anything.
+    fn show_tooltip(&mut self, hyperlink: Option<&Hint>) -> bool {
+        if self.tooltip.as_ref().map(|(_, target)| target) == hyperlink.map(|link| &link.text) {
+            return false;
+        }
+        if let Some((layer, _)) = self.tooltip.take() {
+            self.ui.remove(layer);
+        }
+        if let Some(link) = hyperlink {
+            let (rows, cols) = (self.terminal.rows(), self.view_cols());
+            let col = link
+                .col
+                .saturating_sub(self.terminal.grid().column_offset());
+            let layer = Layer::tooltip(&link.text, link.row, col, rows, cols);
+            self.tooltip = Some((self.ui.push(layer), link.text.clone()));
+        }
+        true
+    }
+
+    /// Run the action of the hint pattern matching under the pointer.
+    fn pick_at_pointer(&mut self) -> bool {
+        let (col, row) = self.pointer_cell;
+        let hints = self.hint_matcher.find(self.terminal.grid());
+        let Some(hint) = hints.hint_at(row, col) else {
+            return false;
+        };
+        self.picked_hint = Some(hint.pick.clone());
+        true
+    }
+
+    /// A key pressed in copy mode (see [`start_copy_mode`]).
+    ///
+    /// [`start_copy_mode`]: Self::start_copy_mode
+    fn copy_mode_key(&mut self, key: &Key) {
+        let shift = self.modifiers.shift_key();
+        let motion = match key {
+            Key::Named(NamedKey::ArrowLeft) if shift => SelectionMotion::WordLeft,
+            Key::Named(NamedKey::ArrowRight) if shift => SelectionMotion::WordRight,
+            Key::Named(NamedKey::ArrowUp) if shift => SelectionMotion::LineUp,
+            Key::Named(NamedKey::ArrowDown) if shift => SelectionMotion::LineDown,
+            Key::Named(NamedKey::ArrowLeft) => SelectionMotion::Left,
+            Key::Named(NamedKey::ArrowRight) => SelectionMotion::Right,
+            Key::Named(NamedKey::ArrowUp) => SelectionMotion::Up,
+            Key::Named(NamedKey::ArrowDown) => SelectionMotion::Down,
+            Key::Named(NamedKey::PageUp) => SelectionMotion::PageUp,
+            Key::Named(NamedKey::PageDown) => SelectionMotion::PageDown,
+            Key::Named(NamedKey::Enter) => {
+                self.command = Some(Action::Copy);
+                self.copy_mode = false;
+                return;
+            }
+            Key::Named(NamedKey::Escape) => {
+                self.set_selection(None);
+                self.copy_mode = false;
+                return;
+            }
+            _ => return,
+        };
+        let Some(selection) = &mut self.selection else {
+            self.copy_mode = false;
+            return;
+        };
+        let head = self
+            .terminal
+            .grid()
+            .move_point(selection.head(), motion, self.word_chars);
+        selection.update(head);
+        self.reveal_selection();
+    }
+
+    /// Scroll the view so the selection's free end is on screen.
+    fn reveal_selection(&mut self) {
+        let Some(selection) = &self.selection else {
+            return;
+        };
+        let grid = self.terminal.grid();
+        let line = selection.head().line;
+        let top = grid.visible_line_index(0);
+        let bottom = top + grid.rows() - 1;
+        if line < top {
+            self.scroll((top - line) as isize);
+        } else if line > bottom {
+            self.scroll(-((line - bottom) as isize));
+        }
+    }
+
+    /// A key pressed in hints mode: a label character, Backspace, or
+    /// Escape to leave.
+    fn hint_key(&mut self, key: &Key) {
+        let Some(hints) = &mut self.hints else {
+            return;
+        };
+        match key {
+            Key::Named(NamedKey::Escape) => self.hints = None,
+            Key::Named(NamedKey::Backspace) => hints.backspace(),
+            Key::Character(text) => {
+                for c in text.chars() {
+                    if let Some(picked) = hints.input(c) {
+                        self.picked_hint = Some(picked);
+                        self.hints = None;
+                        return;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// A key pressed in the command palette: characters filter, the arrow
+    /// keys and Tab move the highlight, Enter runs, Escape closes.
+    fn palette_key(&mut self, key: &Key) {
+        let Some((layer, palette)) = &mut self.palette else {
+            return;
+        };
+        match key {
+            Key::Named(NamedKey::Escape) => {}
+            Key::Named(NamedKey::Enter) => self.command = palette.selected(),
+            Key::Named(NamedKey::Backspace) => palette.backspace(),
+            Key::Named(NamedKey::Tab) if self.modifiers.shift_key() => palette.select_prev(),
+            Key::Named(NamedKey::ArrowUp) => palette.select_prev(),
+            Key::Named(NamedKey::ArrowDown | NamedKey::Tab) => palette.select_next(),
+            Key::Named(NamedKey::Space) => palette.input(' '),
+            Key::Character(text) => text.chars().for_each(|c| palette.input(c)),
+            _ => return,
+        }
+        if matches!(key, Key::Named(NamedKey::Escape | NamedKey::Enter)) {
+            self.ui.remove(*layer);
+            self.palette = None;
+        } else {
+            self.refresh_palette();
+        }
+    }
+
+    /// A key pressed in the clipboard history picker: like the command
+    /// palette, but Enter pastes the highlighted entry.
+    fn clipboard_picker_key(&mut self, key: &Key) {
+        let Some((layer, picker)) = &mut self.clipboard_picker else {
+            return;
+        };
+        match key {
+            Key::Named(NamedKey::Escape | NamedKey::Enter) => {}
+            Key::Named(NamedKey::Backspace) => picker.backspace(),
+            Key::Named(NamedKey::Tab) if self.modifiers.shift_key() => picker.select_prev(),
+            Key::Named(NamedKey::ArrowUp) => picker.select_prev(),
+            Key::Named(NamedKey::ArrowDown | NamedKey::Tab) => picker.select_next(),
+            Key::Named(NamedKey::Space) => picker.input(' '),
+            Key::Character(text) => text.chars().for_each(|c| picker.input(c)),
+            _ => return,
+        }
+        if matches!(key, Key::Named(NamedKey::Escape | NamedKey::Enter)) {
+            self.ui.remove(*layer);
+            let (_, picker) = self.clipboard_picker.take().unwrap();
+            if matches!(key, Key::Named(NamedKey::Enter))
+                && let Some(text) = picker.selected()
+            {
+                self.paste(text);
+            }
+        } else {
+            self.refresh_clipboard_picker();
+        }
+    }
+
+    /// A key pressed in the rename prompt: Enter renames, Escape cancels.
+    fn rename_key(&mut self, key: &Key) {
+        let Some((layer, name)) = &mut self.rename else {
+            return;
+        };
+        match key {
+            Key::Named(NamedKey::Escape) => {}
+            Key::Named(NamedKey::Enter) => {
+                let name = name.trim();
+                let name = (!name.is_empty()).then(|| name.to_string());
+                self.terminal.rename(name);
+            }
+            Key::Named(NamedKey::Backspace) => drop(name.pop()),
+            Key::Named(NamedKey::Space) => name.push(' '),
+            Key::Character(text) => name.push_str(text),
+            _ => return,
+        }
+        if matches!(key, Key::Named(NamedKey::Escape | NamedKey::Enter)) {
+            self.ui.remove(*layer);
+            self.rename = None;
+        } else {
+            self.refresh_rename();
+        }
+    }
+
+    /// A key pressed in the search prompt: Enter and Shift+Enter go to the
+    /// next match, Escape closes it.
+    fn search_key(&mut self, key: &Key) {
+        let Some((layer, query)) = &mut self.search else {
+            return;
+        };
+        match key {
+            Key::Named(NamedKey::Escape) => {
+                self.ui.remove(*layer);
+                self.search = None;
+                self.search_matches.clear();
+                self.minimap = None;
+                return;
+            }
+            Key::Named(NamedKey::Enter) => {
+                self.show_match(!self.modifiers.shift_key());
+                return;
+            }
+            Key::Named(NamedKey::Backspace) => drop(query.pop()),
+            Key::Named(NamedKey::Space) => query.push(' '),
+            Key::Character(text) => query.push_str(text),
+            _ => return,
+        }
+        self.refresh_search();
+    }
+
+    /// Find the query again and redraw the prompt, after the query or the
+    /// scrollback changed.
+    fn refresh_search(&mut self) {
+        let (rows, cols) = (self.terminal.rows(), self.view_cols());
+        let Some((id, query)) = &self.search else {
+            return;
+        };
+        self.search_matches = self.terminal.grid().search(query);
+        if let Some(layer) = self.ui.layer_mut(*id) {
+            *layer = Self::search_layer(query, self.search_matches.len(), rows, cols);
+        }
+        // The marks are part of the minimap
+        self.minimap = None;
+    }
+
+    /// Center the view on the closest match above it (`older`) or below
+    /// it, going round to the other end after the last one.
+    fn show_match(&mut self, older: bool) {
+        let grid = self.terminal.grid();
+        let (top, bottom) = (
+            grid.visible_line_index(0),
+            grid.visible_line_index(grid.rows() - 1),
+        );
+        let lines = self.search_matches.iter().map(|found| found.line);
+        let line = if older {
+            let mut lines = lines.rev();
+            lines
+                .clone()
+                .find(|&line| line < top)
+                .or_else(|| lines.next())
+        } else {
+            let mut lines = lines;
+            lines
+                .clone()
+                .find(|&line| line > bottom)
+                .or_else(|| lines.next())
+        };
+        let Some(line) = line else {
+            return;
+        };
+        let history = grid.history_len();
+        let offset = history - line.saturating_sub(grid.rows() / 2).min(history);
+        self.scroll(offset as isize - grid.display_offset() as isize);
+    }
+
+    /// Redraw the rename prompt after its text or the screen changed.
+    fn refresh_rename(&mut self) {
+        let (rows, cols) = (self.terminal.rows(), self.view_cols());
+        if let Some((id, name)) = &self.rename
+            && let Some(layer) = self.ui.layer_mut(*id)
+        {
+            *layer = Self::rename_layer(name, rows, cols);
+        }
+    }
+
+    /// Redraw the palette's layer after its query or the screen changed.
+    fn refresh_palette(&mut self) {
+        let (rows, cols) = (self.terminal.rows(), self.view_cols());
+        if let Some((id, palette)) = &self.palette
+            && let Some(layer) = self.ui.layer_mut(*id)
+        {
+            *layer = palette.layer(rows, cols);
+        }
+    }
+
+    /// Redraw the paste preview after the screen changed.
+    fn refresh_paste_prompt(&mut self) {
+        let (rows, cols) = (self.terminal.rows(), self.view_cols());
+        if let Some((id, text)) = &self.paste_prompt
+            && let Some(layer) = self.ui.layer_mut(*id)
+        {
+            *layer = Self::paste_layer(text, rows, cols);
+        }
+    }
+
+    /// Redraw the picker's layer after its query or the screen changed.
+    fn refresh_clipboard_picker(&mut self) {
+        let (rows, cols) = (self.terminal.rows(), self.view_cols());
+        if let Some((id, picker)) = &self.clipboard_picker
+            && let Some(layer) = self.ui.layer_mut(*id)
+        {
+            *layer = picker.layer(rows, cols);
+        }
+    }
+
+    /// How to draw the cursor right now.
+    fn cursor_display(&self) -> CursorDisplay {
+        if !self.focused {
+            return CursorDisplay::Hollow;
+        }
+        if self.cursor_style().blinking {
+            let phase = self.blink_epoch.elapsed().as_millis() / CURSOR_BLINK_INTERVAL.as_millis();
+            if phase % 2 == 1 {
+                return CursorDisplay::Hidden;
+            }
+        }
+        CursorDisplay::Normal
+    }
+
+    /// Count a left click at `point`: 2 right after a click on the same
+    /// cell, 3 after a double click, then 1 again.
+    fn count_click(&mut self, point: Point) -> u8 {
+        let now = Instant::now();
+        let count = match self.last_click {
+            Some((at, last, count))
+                if last == point && now.duration_since(at) <= self.multi_click =>
+            {
+                count % 3 + 1
+            }
+            _ => 1,
+        };
+        self.last_click = Some((now, point, count));
+        count
+    }
+
+    /// The grid point under the pointer, for selections.
+    fn pointer_point(&self) -> Point {
+        let (col, row) = self.pointer_cell;
+        Point::new(self.terminal.grid().visible_line_index(row), col)
+    }
+
+    /// Draw the terminal into `target`, preserving what the host already drew.
+    pub fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+    ) {
+        self.ui.expire(Instant::now());
+        self.refresh_plugin_overlay();
+        self.refresh_minimap();
+        let scrollbar = self.scrollbar();
+        let status_cells =
+            (self.status_bar.as_ref()).map(|bar| (bar.position(), bar.cells(self.view_cols())));
+        let decorations = Decorations {
+            selection: self.selection.as_ref(),
+            cursor: self.cursor_display(),
+            cursor_shape: self.cursor_style().shape,
+            preedit: self.preedit.as_ref(),
+            hints: self.hints.as_ref(),
+            link: self.hovered_link.as_ref(),
+            ui: Some(&self.ui),
+            // Overlays stay where they are: no sliding under them
+            scroll: match &self.scroll_animation {
+                Some(animation) if self.ui.is_empty() => animation.lag(Instant::now()),
+                _ => 0.0,
+            },
+            scrollbar: scrollbar.as_ref(),
+            minimap: self.minimap.as_ref().map(|(_, minimap)| minimap),
+            status_bar: (status_cells.as_ref()).map(|(position, cells)| (*position, &cells[..])),
+            scrollbar_active: matches!(self.drag, Some(Drag::Scrollbar(_)))
+                || self.over_scrollbar(),
+        };
+        self.renderer
+            .prepare(device, queue, &self.terminal, &decorations);
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Terminal Widget Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+            multiview_mask: None,
+        });
+
+        let [x, y] = self.position;
+        render_pass.set_viewport(x, y, self.width as f32, self.height as f32, 0.0, 1.0);
+        self.renderer.draw(&mut render_pass);
+    }
+}
+
+/// The grid that fits `width` x `height` pixels, less a row for a status
+/// bar.
+fn fit_grid(
+    renderer: &TerminalRenderer,
+    bar: bool,
+    minimap: bool,
+    mut width: u32,
+    height: u32,
+) -> (usize, usize) {
+    if minimap {
+        let column = minimap::width(renderer.cell_metrics().width) as u32;
+        width = width.saturating_sub(column);
+    }
+    let (cols, rows) = renderer.grid_size(width, height);
+    (cols, if bar { (rows - 1).max(1) } else { rows })
+}
@@ -0,0 +1,163 @@
+//! Screen reader announcements.
+//!
+//! Screens are fed through a real [`Terminal`] so scrolling and line
+//! editing produce what a shell session would.
+
+use adamant::a11y::{A11yTracker, AccessibleScreen, Announcement};
+use adamant::term::Terminal;
+
+fn screen(term: &Terminal) -> AccessibleScreen {
+    let grid = term.grid();
+    AccessibleScreen {
+        lines: (0..grid.rows()).map(|row| grid.row(row).text()).collect(),
+        cursor: Some((grid.cursor.row, grid.cursor.col)),
+        selection: None,
+        selected: None,
+    }
+}
+
+/// Feed each chunk and collect what gets announced after it.
+fn announce(rows: usize, chunks: &[&str]) -> Vec<Vec<Announcement>> {
+    let mut term = Terminal::new(20, rows, 100);
+    let mut tracker = A11yTracker::default();
+    tracker.update(screen(&term));
+    chunks
+        .iter()
+        .map(|chunk| {
+            term.feed(chunk.as_bytes());
+            tracker.update(screen(&term))
+        })
+        .collect()
+}
+
+fn output(text: &str) -> Vec<Announcement> {
+    vec![Announcement::Output(text.into())]
+}
+
+#[test]
+fn first_screen_is_not_announced() {
+    let mut tracker = A11yTracker::default();
+    let screen = AccessibleScreen {
+        lines: vec!["welcome".into()],
+        ..AccessibleScreen::default()
+    };
+    assert!(tracker.update(screen.clone()).is_empty());
+    assert_eq!(tracker.screen(), Some(&screen));
+}
+
+#[test]
+fn typed_characters_are_announced_one_by_one() {
+    let announced = announce(3, &["$ ", "l", "s"]);
+    assert_eq!(announced, vec![output("$"), output("l"), output("s")]);
+}
+
+#[test]
+fn command_output_is_announced_once() {
+    let announced = announce(3, &["$ ls", "\r\nCargo.toml\r\nsrc\r\n$ "]);
+    assert_eq!(announced[1], output("Cargo.toml\nsrc\n$"));
+}
+
+#[test]
+fn scrolled_lines_are_not_repeated() {
+    let announced = announce(3, &["1\r\n2\r\n3", "\r\n4", "\r\n5"]);
+    assert_eq!(announced[1], output("4"));
+    assert_eq!(announced[2], output("5"));
+}
+
+#[test]
+fn unchanged_screen_is_silent() {
+    let announced = announce(3, &["hello", "\x1b[1m", "\x1b[0m"]);
+    assert!(announced[1].is_empty());
+    assert!(announced[2].is_empty());
+}
+
+#[test]
+fn new_selection_is_announced() {
+    let mut tracker = A11yTracker::default();
+    let mut screen = AccessibleScreen {
+        lines: vec!["some text".into()],
+        ..AccessibleScreen::default()
+    };
+    tracker.update(screen.clone());
+    screen.selection = Some("text".into());
+    assert_eq!(
+        tracker.update(screen.clone()),
+        vec![Announcement::Selection("text".into())]
+    );
+    assert!(tracker.update(screen).is_empty());
+}
+
+#[cfg(feature = "gui")]
+#[test]
+fn the_tree_holds_lines_cursor_and_announcement() {
+    use accesskit::{Live, NodeId, Role};
+    use adamant::a11y::{LIVE_NODE, TERMINAL_NODE, WINDOW_NODE, line_node, tree_update};
+    use adamant::term::WidthOptions;
+
+    let mut term = Terminal::new(20, 3, 100);
+    term.feed("$ ls\r\n日本 ok".as_bytes());
+    let update = tree_update(&screen(&term), "日本 ok", WidthOptions::default());
+    let node = |id: NodeId| {
+        &update
+            .nodes
+            .iter()
+            .find(|(node, _)| *node == id)
+            .expect("node in the tree")
+            .1
+    };
+
+    assert_eq!(
+        update.tree.as_ref().map(|tree| tree.root),
+        Some(WINDOW_NODE)
+    );
+    assert_eq!(update.focus, TERMINAL_NODE);
+    assert_eq!(node(WINDOW_NODE).children(), [TERMINAL_NODE, LIVE_NODE]);
+    let terminal = node(TERMINAL_NODE);
+    assert_eq!(terminal.role(), Role::Terminal);
+    assert_eq!(
+        terminal.children(),
+        [line_node(0), line_node(1), line_node(2)]
+    );
+
+    // Lines end in their break but the last; lengths are UTF-8 bytes
+    let line = node(line_node(1));
+    assert_eq!(line.role(), Role::TextRun);
+    assert_eq!(line.value(), Some("日本 ok\n"));
+    assert_eq!(line.character_lengths(), [3, 3, 1, 1, 1, 1]);
+    assert_eq!(node(line_node(2)).value(), Some(""));
+
+    // The cursor is after "ok", at column 7 past two wide characters
+    let cursor = terminal.text_selection().expect("cursor");
+    assert_eq!(cursor.anchor, cursor.focus);
+    assert_eq!(cursor.focus.node, line_node(1));
+    assert_eq!(cursor.focus.character_index, 5);
+
+    let live = node(LIVE_NODE);
+    assert_eq!(live.live(), Some(Live::Polite));
+    assert_eq!(live.label(), Some("日本 ok"));
+}
+
+#[cfg(feature = "gui")]
+#[test]
+fn the_tree_holds_the_selection_instead_of_the_cursor() {
+    use adamant::a11y::{TERMINAL_NODE, line_node, tree_update};
+    use adamant::term::WidthOptions;
+
+    let mut term = Terminal::new(20, 3, 100);
+    term.feed("$ ls\r\n日本 ok".as_bytes());
+    let mut screen = screen(&term);
+    // From "ls" to the end of "本", its second cell
+    screen.selected = Some(((0, 2), (1, 3)));
+    let update = tree_update(&screen, "", WidthOptions::default());
+    let terminal = &update
+        .nodes
+        .iter()
+        .find(|(node, _)| *node == TERMINAL_NODE)
+        .expect("terminal in the tree")
+        .1;
+    let selection = terminal.text_selection().expect("selection");
+    assert_eq!(selection.anchor.node, line_node(0));
+    assert_eq!(selection.anchor.character_index, 2);
+    assert_eq!(selection.focus.node, line_node(1));
+    assert_eq!(selection.focus.character_index, 2);
+}
@@ -0,0 +1,106 @@
+//! Bidirectional reordering.
+//!
+//! Known mixed-direction rows are checked against their expected visual
+//! order, and random rows must always map to a permutation whose two
+//! directions agree, so the cursor and selections can't land on a column
+//! that isn't drawn.
+
+use adamant::grid::{BidiRow, Flags, Grid};
+use proptest::prelude::*;
+
+/// A one-row grid holding `text`.
+fn row_of(text: &str, cols: usize) -> Grid {
+    let mut grid = Grid::new(cols, 1, 0);
+    for c in text.chars() {
+        let width = unicode_width::UnicodeWidthChar::width(c).unwrap_or(1);
+        grid.write(c, width, true);
+    }
+    grid
+}
+
+/// The row as drawn.
+fn visual(grid: &Grid) -> String {
+    let row = grid.row(0);
+    match BidiRow::new(row) {
+        Some(bidi) => (0..grid.cols())
+            .map(|col| {
+                let logical = bidi.logical(col);
+                bidi.glyph(logical, row[logical].c)
+            })
+            .collect(),
+        None => (0..grid.cols()).map(|col| row[col].c).collect(),
+    }
+}
+
+#[test]
+fn left_to_right_rows_are_not_reordered() {
+    assert_eq!(BidiRow::new(row_of("hello, world", 16).row(0)), None);
+}
+
+#[test]
+fn hebrew_run_inside_english() {
+    assert_eq!(visual(&row_of("see שלום now", 12)), "see םולש now");
+}
+
+#[test]
+fn numbers_keep_their_order_inside_rtl_text() {
+    assert_eq!(visual(&row_of("שנה 2024 טובה", 13)), "הבוט 2024 הנש");
+}
+
+#[test]
+fn arabic_letters_and_digits() {
+    assert_eq!(visual(&row_of("عدد ١٢٣", 7)), "١٢٣ ددع");
+}
+
+#[test]
+fn brackets_are_mirrored_in_rtl_runs() {
+    assert_eq!(visual(&row_of("א(ב)ג", 5)), "ג(ב)א");
+}
+
+#[test]
+fn trailing_blanks_stay_at_the_end() {
+    let grid = row_of("אב", 5);
+    let bidi = BidiRow::new(grid.row(0)).unwrap();
+    assert_eq!(visual(&grid), "בא   ");
+    assert_eq!(bidi.visual(0), 1);
+    assert_eq!(bidi.visual(4), 4);
+}
+
+fn cell_char() -> impl Strategy<Value = char> {
+    prop_oneof![
+        Just('a'),
+        Just('1'),
+        Just(' '),
+        Just('-'),
+        Just('('),
+        Just('א'),
+        Just('ع'),
+        Just('٣'),
+        Just('\u{05B0}'),
+        Just('漢'),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn mapping_is_a_round_trip(text in prop::collection::vec(cell_char(), 0..24)) {
+        let text: String = text.into_iter().collect();
+        let grid = row_of(&text, 24);
+        let row = grid.row(0);
+        if let Some(bidi) = BidiRow::new(row) {
+            let mut seen = vec![false; grid.cols()];
+            for col in 0..grid.cols() {
+                let logical = bidi.logical(col);
+                prop_assert!(!seen[logical]);
+                seen[logical] = true;
+                prop_assert_eq!(bidi.visual(logical), col);
+            }
+            // Both halves of a wide character stay together, left half first
+            for col in 0..grid.cols() {
+                if row[col].flags.contains(Flags::WIDE) {
+                    prop_assert_eq!(bidi.visual(col) + 1, bidi.visual(col + 1));
+                }
+            }
+        }
+    }
+}
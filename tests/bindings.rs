@@ -0,0 +1,389 @@
+//! Key bindings: leader-key sequences, what a key that continues no binding
+//! does, the timeout, the keys shown as hints and keys held down. Mouse
+//! bindings: chords,
+//! falling back to the plain binding, and the wheel reported sideways.
+
+#![cfg(feature = "gui")]
+
+use adamant::bindings::{Chord, KeyBindings, KeyOutcome, MouseBindings, MouseChord, MouseTrigger};
+use adamant::commands::{self, Action};
+use adamant::config::{Config, HookAction, KeyBinding, MouseBinding, MouseCommand};
+use adamant::input::{self, MouseAction};
+use adamant::term::Mode;
+use adamant::ui::Layer;
+use web_time::{Duration, Instant};
+use winit::event::MouseButton;
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+
+const TIMEOUT: Duration = Duration::from_secs(1);
+const NONE: ModifiersState = ModifiersState::empty();
+const CTRL: ModifiersState = ModifiersState::CONTROL;
+const SHIFT: ModifiersState = ModifiersState::SHIFT;
+
+fn command(name: &str) -> HookAction {
+    HookAction::Command(name.into())
+}
+
+fn key_bindings() -> Vec<KeyBinding> {
+    vec![
+        KeyBinding {
+            keys: "Ctrl+A c",
+            action: command("New window"),
+        },
+        KeyBinding {
+            keys: "Ctrl+A |",
+            action: command("Zoom in"),
+        },
+        KeyBinding {
+            keys: "Ctrl+A Ctrl+A",
+            action: HookAction::Input("\x01".into()),
+        },
+        KeyBinding {
+            keys: "Ctrl+A g g",
+            action: command("Scroll to top"),
+        },
+        KeyBinding {
+            keys: "Ctrl+Shift+F5",
+            action: command("Reset zoom"),
+        },
+    ]
+}
+
+fn character(text: &str) -> Key {
+    Key::Character(text.into())
+}
+
+#[test]
+fn a_leader_waits_for_the_key_after_it() {
+    let mut bindings = KeyBindings::new(&key_bindings(), TIMEOUT).unwrap();
+    let now = Instant::now();
+    assert_eq!(
+        bindings.key(CTRL, &character("a"), now),
+        KeyOutcome::Pending
+    );
+    assert!(bindings.is_pending());
+    assert_eq!(bindings.deadline(), Some(now + TIMEOUT));
+    assert_eq!(
+        bindings.key(NONE, &character("c"), now),
+        KeyOutcome::Run(command("New window"))
+    );
+    assert!(!bindings.is_pending());
+
+    // The leader twice types it
+    bindings.key(CTRL, &character("a"), now);
+    assert_eq!(
+        bindings.key(CTRL, &character("a"), now),
+        KeyOutcome::Run(HookAction::Input("\x01".into()))
+    );
+}
+
+#[test]
+fn sequences_can_be_longer_than_two_keys() {
+    let mut bindings = KeyBindings::new(&key_bindings(), TIMEOUT).unwrap();
+    let now = Instant::now();
+    bindings.key(CTRL, &character("a"), now);
+    let later = now + Duration::from_millis(800);
+    assert_eq!(
+        bindings.key(NONE, &character("g"), later),
+        KeyOutcome::Pending
+    );
+    // Each key gives the next one the whole timeout
+    assert_eq!(bindings.deadline(), Some(later + TIMEOUT));
+    assert_eq!(
+        bindings.key(NONE, &character("g"), later + Duration::from_millis(800)),
+        KeyOutcome::Run(command("Scroll to top"))
+    );
+}
+
+#[test]
+fn single_keys_run_at_once() {
+    let mut bindings = KeyBindings::new(&key_bindings(), TIMEOUT).unwrap();
+    let f5 = Key::Named(NamedKey::F5);
+    assert_eq!(
+        bindings.key(CTRL | SHIFT, &f5, Instant::now()),
+        KeyOutcome::Run(command("Reset zoom"))
+    );
+    assert_eq!(bindings.key(CTRL, &f5, Instant::now()), KeyOutcome::Unbound);
+    assert_eq!(
+        bindings.key(NONE, &character("c"), Instant::now()),
+        KeyOutcome::Unbound
+    );
+}
+
+#[test]
+fn a_key_continuing_no_binding_cancels_the_sequence() {
+    let mut bindings = KeyBindings::new(&key_bindings(), TIMEOUT).unwrap();
+    let now = Instant::now();
+    bindings.key(CTRL, &character("a"), now);
+    assert_eq!(
+        bindings.key(NONE, &character("x"), now),
+        KeyOutcome::Cancelled
+    );
+    assert!(!bindings.is_pending());
+    // The next key is on its own again
+    assert_eq!(
+        bindings.key(NONE, &character("c"), now),
+        KeyOutcome::Unbound
+    );
+}
+
+#[test]
+fn modifiers_pressed_on_the_way_keep_the_sequence() {
+    let mut bindings = KeyBindings::new(&key_bindings(), TIMEOUT).unwrap();
+    let now = Instant::now();
+    assert_eq!(
+        bindings.key(CTRL, &Key::Named(NamedKey::Control), now),
+        KeyOutcome::Unbound
+    );
+    bindings.key(CTRL, &character("a"), now);
+    assert_eq!(
+        bindings.key(SHIFT, &Key::Named(NamedKey::Shift), now),
+        KeyOutcome::Pending
+    );
+    // `|` is Shift+\ on US layouts
+    assert_eq!(
+        bindings.key(SHIFT, &character("|"), now),
+        KeyOutcome::Run(command("Zoom in"))
+    );
+}
+
+#[test]
+fn a_sequence_is_dropped_after_the_timeout() {
+    let mut bindings = KeyBindings::new(&key_bindings(), TIMEOUT).unwrap();
+    let now = Instant::now();
+    bindings.key(CTRL, &character("a"), now);
+    assert!(!bindings.expire(now + Duration::from_millis(999)));
+    assert!(bindings.expire(now + TIMEOUT));
+    assert!(!bindings.is_pending());
+
+    bindings.key(CTRL, &character("a"), now);
+    assert_eq!(
+        bindings.key(NONE, &character("c"), now + TIMEOUT * 2),
+        KeyOutcome::Unbound
+    );
+}
+
+#[test]
+fn a_held_key_repeats_what_its_press_ran() {
+    let mut bindings = KeyBindings::new(&key_bindings(), TIMEOUT).unwrap();
+    let now = Instant::now();
+    let f5 = Key::Named(NamedKey::F5);
+    bindings.key(CTRL | SHIFT, &f5, now);
+    assert_eq!(bindings.repeat(now), KeyOutcome::Run(command("Reset zoom")));
+    assert_eq!(bindings.repeat(now), KeyOutcome::Run(command("Reset zoom")));
+
+    // A held leader doesn't type its second half
+    bindings.key(CTRL, &character("a"), now);
+    assert_eq!(bindings.repeat(now), KeyOutcome::Pending);
+    assert_eq!(
+        bindings.key(NONE, &character("c"), now),
+        KeyOutcome::Run(command("New window"))
+    );
+
+    // Text keys repeat into the program
+    bindings.key(NONE, &character("x"), now);
+    assert_eq!(bindings.repeat(now), KeyOutcome::Unbound);
+}
+
+#[test]
+fn only_scrolling_actions_repeat() {
+    assert!(Action::ScrollPageUp.is_scroll());
+    assert!(Action::ScrollLineDown.is_scroll());
+    assert!(!Action::ZoomIn.is_scroll());
+    assert!(!Action::NewWindow.is_scroll());
+    assert!(Config::default().repeat_scroll_keys);
+    assert_eq!(
+        commands::command_named(&Config::default(), "Scroll up a page"),
+        Some(Action::ScrollPageUp)
+    );
+}
+
+#[test]
+fn next_keys_list_each_key_once_with_what_it_does() {
+    let mut bindings = KeyBindings::new(&key_bindings(), TIMEOUT).unwrap();
+    assert!(bindings.next_keys().is_empty());
+    bindings.key(CTRL, &character("a"), Instant::now());
+    assert_eq!(
+        bindings.next_keys(),
+        [
+            ("c", "New window".to_string()),
+            ("|", "Zoom in".to_string()),
+            ("Ctrl+A", "Type \"\\u{1}\"".to_string()),
+            ("g", "…".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn chords_parse_characters_and_hotkeys() {
+    assert_eq!(Chord::parse("|"), Ok(Chord::Char('|')));
+    assert!(matches!(Chord::parse("c"), Ok(Chord::Hotkey(_))));
+    assert!(matches!(
+        Chord::parse("Ctrl+Shift+Up"),
+        Ok(Chord::Hotkey(_))
+    ));
+    assert!(Chord::parse("Ctrl+Nope").is_err());
+
+    // AltGr arrives as Ctrl+Alt on Windows; Ctrl alone is another key
+    let bar = Chord::Char('|');
+    assert!(bar.matches(CTRL | ModifiersState::ALT, &character("|")));
+    assert!(!bar.matches(CTRL, &character("|")));
+}
+
+#[test]
+fn bad_bindings_are_configuration_errors() {
+    let binding = |keys| KeyBinding {
+        keys,
+        action: command("Quit"),
+    };
+    assert!(KeyBindings::new(&[binding("  ")], TIMEOUT).is_err());
+    assert!(KeyBindings::new(&[binding("Ctrl+A Hyper+X")], TIMEOUT).is_err());
+    // `Ctrl+A` alone would never wait for `c`
+    let error = KeyBindings::new(&[binding("Ctrl+A c"), binding("Ctrl+A")], TIMEOUT).unwrap_err();
+    assert!(error.to_string().contains("start of \"Ctrl+A c\""));
+    assert!(KeyBindings::new(&[binding("Ctrl+A c"), binding("Ctrl+A d")], TIMEOUT).is_ok());
+
+    let config = Config {
+        key_sequence_timeout_ms: 0,
+        ..Config::default()
+    };
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn bound_commands_show_their_keys_in_the_palette() {
+    let config = Config {
+        key_bindings: key_bindings(),
+        ..Config::default()
+    };
+    let commands = commands::commands(&config);
+    let shortcut = |name| {
+        (commands.iter())
+            .find(|command| command.name == name)
+            .and_then(|command| command.shortcut)
+    };
+    assert_eq!(shortcut("New window"), Some("Ctrl+A c"));
+    assert_eq!(shortcut("Scroll to top"), Some("Ctrl+A g g"));
+    // Built-in shortcuts stay
+    assert_eq!(shortcut("Copy selection"), Some("Ctrl+Shift+C"));
+}
+
+#[test]
+fn key_hints_expire_with_the_sequence() {
+    let expires = Instant::now() + TIMEOUT;
+    let keys = [
+        ("c", "New window".to_string()),
+        ("|", "Zoom in".to_string()),
+    ];
+    let layer = Layer::key_hints(&keys, expires, 24, 80);
+    assert_eq!(layer.expires, Some(expires));
+    assert!(!layer.is_modal());
+    let text: Vec<&str> = layer.elements.iter().map(|e| e.text.as_str()).collect();
+    assert!(text.contains(&"c  New window\n|  Zoom in"));
+}
+
+#[test]
+fn mouse_chords_parse_buttons_and_the_wheel() {
+    assert_eq!(
+        MouseChord::parse("Ctrl+RightClick"),
+        Ok(MouseChord {
+            modifiers: CTRL,
+            trigger: MouseTrigger::Button(MouseButton::Right),
+        })
+    );
+    assert_eq!(
+        MouseChord::parse("shift+wheel").map(|chord| chord.trigger),
+        Ok(MouseTrigger::Wheel)
+    );
+    assert_eq!(
+        MouseChord::parse("Back").map(|chord| chord.trigger),
+        Ok(MouseTrigger::Button(MouseButton::Back))
+    );
+    assert!(MouseChord::parse("Ctrl+").is_err());
+    assert!(MouseChord::parse("Ctrl+Thumb").is_err());
+    assert!(MouseChord::parse("Hyper+Left").is_err());
+}
+
+#[test]
+fn the_default_mouse_bindings_keep_the_usual_clicks() {
+    let bindings = MouseBindings::new(&Config::default().mouse.bindings).unwrap();
+    let button = |modifiers, button| bindings.command(modifiers, MouseTrigger::Button(button));
+    assert_eq!(
+        button(NONE, MouseButton::Right),
+        Some(&MouseCommand::OpenAtPointer)
+    );
+    assert_eq!(
+        button(NONE, MouseButton::Middle),
+        Some(&MouseCommand::PasteSelection)
+    );
+    assert_eq!(button(NONE, MouseButton::Left), None);
+    assert_eq!(button(CTRL, MouseButton::Left), None);
+    // Modifiers no binding names fall back to the plain one
+    assert_eq!(
+        bindings.command(CTRL, MouseTrigger::WheelDown),
+        Some(&MouseCommand::Scroll)
+    );
+    assert_eq!(
+        bindings.command(SHIFT, MouseTrigger::WheelDown),
+        Some(&MouseCommand::ScrollHorizontal)
+    );
+}
+
+#[test]
+fn mouse_bindings_with_modifiers_come_first() {
+    let zoom = MouseCommand::Run(command("Zoom in"));
+    let mut config = Config::default();
+    config.mouse.bindings.extend([
+        MouseBinding {
+            mouse: "Ctrl+Right",
+            command: MouseCommand::PasteSelection,
+        },
+        MouseBinding {
+            mouse: "Shift+Wheel",
+            command: MouseCommand::ScrollHorizontal,
+        },
+        MouseBinding {
+            mouse: "Ctrl+WheelUp",
+            command: zoom.clone(),
+        },
+    ]);
+    assert!(config.validate().is_ok());
+    let bindings = MouseBindings::new(&config.mouse.bindings).unwrap();
+    assert_eq!(
+        bindings.command(CTRL, MouseTrigger::Button(MouseButton::Right)),
+        Some(&MouseCommand::PasteSelection)
+    );
+    assert_eq!(
+        bindings.command(SHIFT, MouseTrigger::WheelUp),
+        Some(&MouseCommand::ScrollHorizontal)
+    );
+    assert_eq!(bindings.command(CTRL, MouseTrigger::WheelUp), Some(&zoom));
+    assert_eq!(
+        bindings.command(CTRL, MouseTrigger::WheelDown),
+        Some(&MouseCommand::Scroll)
+    );
+}
+
+#[test]
+fn a_plain_left_click_cant_be_bound() {
+    let binding = |mouse| MouseBinding {
+        mouse,
+        command: MouseCommand::PasteSelection,
+    };
+    assert!(MouseBindings::new(&[binding("Left")]).is_err());
+    assert!(MouseBindings::new(&[binding("Alt+Left")]).is_ok());
+    assert!(MouseBindings::new(&[binding("Alt+Nose")]).is_err());
+}
+
+#[test]
+fn sideways_wheel_is_reported_as_tilted() {
+    let mode = Mode::MOUSE_MODE | Mode::SGR_MOUSE;
+    assert_eq!(
+        input::mouse_report(MouseAction::WheelLeft, 4, 2, NONE, mode),
+        Some(b"\x1b[<66;5;3M".to_vec())
+    );
+    assert_eq!(
+        input::mouse_report(MouseAction::WheelRight, 4, 2, SHIFT, mode),
+        Some(b"\x1b[<71;5;3M".to_vec())
+    );
+}
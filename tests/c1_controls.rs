@@ -0,0 +1,62 @@
+//! C1 controls in their 8-bit (UTF-8 encoded) forms, next to their 7-bit
+//! ESC forms, and malformed input around them.
+
+use adamant::grid::Color;
+use adamant::term::Terminal;
+
+fn screen(bytes: &[u8]) -> Vec<String> {
+    let mut term = Terminal::new(10, 4, 0);
+    term.feed(bytes);
+    (0..4).map(|row| row_text(&term, row)).collect()
+}
+
+fn row_text(term: &Terminal, row: usize) -> String {
+    let cells = term.grid().visible_row(row).cells();
+    cells
+        .iter()
+        .map(|cell| cell.c)
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+#[test]
+fn eight_bit_controls_act_like_seven_bit_ones() {
+    // HTS at column 5, NEL, IND, RI, then a tab to the new stop
+    let seven = screen(b"\x1b[3g\x1b[1;6H\x1bH\x1b[Ha\x1bEb\x1bDc\x1bM\rd\te");
+    let eight = screen("\x1b[3g\x1b[1;6H\u{88}\x1b[Ha\u{85}b\u{84}c\u{8d}\rd\te".as_bytes());
+    assert_eq!(seven, eight);
+    assert_eq!(eight, ["a", "d    e", " c", ""]);
+}
+
+#[test]
+fn eight_bit_introducers_start_sequences() {
+    let mut term = Terminal::new(10, 2, 0);
+    // CSI for SGR, then an XTGETTCAP for `TN` framed by DCS and ST
+    term.feed("\u{9b}31mx\u{90}+q544e\u{9c}y".as_bytes());
+    assert_eq!(row_text(&term, 0), "xy");
+    assert_eq!(term.grid().visible_row(0)[1].fg, Color::Indexed(1));
+    let reply = String::from_utf8(term.take_responses()).unwrap();
+    assert!(reply.starts_with("\x1bP1+r544e="), "{:?}", reply);
+}
+
+#[test]
+fn single_shifts_use_g2_and_g3_once() {
+    // DEC line drawing into G2, then SS2 for one character
+    assert_eq!(screen("\x1b*0\u{8e}qq\x1b+0\x1bOqq".as_bytes())[0], "─q─q");
+}
+
+#[test]
+fn malformed_input_stays_contained() {
+    // A bare C1 byte is broken UTF-8, not a control
+    assert_eq!(screen(b"a\x85b")[0], "a\u{fffd}b");
+    // A lead byte without a C1 after it stays in the OSC payload
+    let mut term = Terminal::new(10, 2, 0);
+    term.feed("\x1b]2;\u{a9}\u{9c}".as_bytes());
+    assert_eq!(term.title(), Some("\u{a9}"));
+    // Strings cut off by a C1 control end, and output carries on
+    assert_eq!(
+        screen("\u{90}+q\u{85}x\u{9f}junk\u{9c}y".as_bytes())[1],
+        "xy"
+    );
+}
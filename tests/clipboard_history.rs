@@ -0,0 +1,66 @@
+//! Clipboard history: what is kept, and the picker over it.
+
+#![cfg(feature = "gui")]
+
+use adamant::clipboard_history::{ClipboardHistory, ClipboardPicker};
+use adamant::config::ClipboardHistoryConfig;
+
+fn history(config: ClipboardHistoryConfig, copies: &[&str]) -> ClipboardHistory {
+    let mut history = ClipboardHistory::new(&config);
+    for copy in copies {
+        history.push(copy.to_string());
+    }
+    history
+}
+
+#[test]
+fn history_is_bounded_and_skips_blank_copies() {
+    let config = ClipboardHistoryConfig {
+        max_entries: 2,
+        max_entry_bytes: 8,
+        ..ClipboardHistoryConfig::default()
+    };
+    let history = history(config, &["one", "two", "  \n", "far too long", "three"]);
+    assert_eq!(history.entries(), ["three", "two"]);
+}
+
+#[test]
+fn disabled_history_keeps_nothing() {
+    let config = ClipboardHistoryConfig {
+        enabled: false,
+        ..ClipboardHistoryConfig::default()
+    };
+    assert!(history(config, &["secret"]).is_empty());
+}
+
+#[test]
+fn picker_prefers_recent_copies_between_equal_matches() {
+    let history = history(
+        ClipboardHistoryConfig::default(),
+        &["make build", "make test", "ls"],
+    );
+    let mut picker = ClipboardPicker::new(history.entries().to_vec());
+    assert_eq!(picker.selected(), Some("ls"));
+    picker.input('m');
+    picker.input('a');
+    assert_eq!(picker.selected(), Some("make test"));
+    picker.select_next();
+    assert_eq!(picker.selected(), Some("make build"));
+    picker.input('z');
+    assert_eq!(picker.selected(), None);
+    picker.backspace();
+    assert_eq!(picker.query(), "ma");
+}
+
+#[test]
+fn layer_shows_each_copy_on_one_line() {
+    let picker = ClipboardPicker::new(vec!["for x in *\ndo echo $x\ndone\n".into()]);
+    let layer = picker.layer(10, 80);
+    assert_eq!(layer.elements[0].text.trim_end(), "paste>");
+    let entry = &layer.elements[1];
+    assert_eq!(
+        entry.text.trim_end(),
+        " for x in *\u{23CE}do echo $x\u{23CE}done"
+    );
+    assert_eq!(layer.focused().unwrap().id, Some(0));
+}
@@ -0,0 +1,87 @@
+//! Color vision filters: the matrices, their shaders and the palette
+//! entries switching them.
+
+#![cfg(feature = "gui")]
+
+use adamant::commands::{Action, commands};
+use adamant::config::{ColorBlindness, ColorFilter, Config};
+use adamant::renderer::{check_post_shader, color_filter_matrix, color_filter_shader};
+
+const BLINDNESS: [ColorBlindness; 3] = [
+    ColorBlindness::Protanopia,
+    ColorBlindness::Deuteranopia,
+    ColorBlindness::Tritanopia,
+];
+
+fn filters() -> impl Iterator<Item = ColorFilter> {
+    BLINDNESS.into_iter().flat_map(|blindness| {
+        [
+            ColorFilter::Simulate(blindness),
+            ColorFilter::Correct(blindness),
+        ]
+    })
+}
+
+/// `filter` applied to a linear color, clamped like the shader does.
+fn apply(filter: ColorFilter, color: [f32; 3]) -> [f32; 3] {
+    let m = color_filter_matrix(filter);
+    m.map(|row| {
+        (0..3)
+            .map(|k| row[k] * color[k])
+            .sum::<f32>()
+            .clamp(0.0, 1.0)
+    })
+}
+
+fn distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    (0..3).map(|i| (a[i] - b[i]).powi(2)).sum::<f32>().sqrt()
+}
+
+#[test]
+fn grays_stay_gray() {
+    for filter in filters() {
+        for level in [0.0, 0.2, 1.0] {
+            let gray = apply(filter, [level; 3]);
+            assert!(distance(gray, [level; 3]) < 1e-4, "{:?}", filter);
+        }
+    }
+}
+
+#[test]
+fn correction_separates_what_simulation_merges() {
+    let simulate = ColorFilter::Simulate(ColorBlindness::Deuteranopia);
+    let correct = ColorFilter::Correct(ColorBlindness::Deuteranopia);
+    // A red and a green that look alike without green cones
+    let (red, green) = ([0.5, 0.1, 0.1], [0.15, 0.25, 0.1]);
+    let before = distance(apply(simulate, red), apply(simulate, green));
+    assert!(before < 0.02);
+    let after = distance(
+        apply(simulate, apply(correct, red)),
+        apply(simulate, apply(correct, green)),
+    );
+    assert!(after > 0.2);
+}
+
+#[test]
+fn every_filter_compiles() {
+    for filter in filters() {
+        for linear_frame in [true, false] {
+            let source = color_filter_shader(filter, linear_frame);
+            if let Err(e) = check_post_shader(&source) {
+                panic!("{:?}: {}", filter, e);
+            }
+        }
+    }
+}
+
+#[test]
+fn the_palette_switches_filters() {
+    let actions: Vec<Action> = commands(&Config::default())
+        .into_iter()
+        .map(|command| command.action)
+        .collect();
+    for filter in filters() {
+        assert!(actions.contains(&Action::ColorFilter(Some(filter))));
+    }
+    assert!(actions.contains(&Action::ColorFilter(None)));
+}
@@ -0,0 +1,55 @@
+//! Surface format choice, and which targets take linear colors.
+
+#![cfg(feature = "gui")]
+
+use adamant::renderer::{is_linear, surface_format};
+use wgpu::TextureFormat;
+
+#[test]
+fn srgb_unless_wide_gamut_is_asked_for() {
+    let formats = [
+        TextureFormat::Rgba16Float,
+        TextureFormat::Bgra8Unorm,
+        TextureFormat::Bgra8UnormSrgb,
+    ];
+    assert_eq!(
+        surface_format(&formats, false),
+        Some(TextureFormat::Bgra8UnormSrgb)
+    );
+    assert_eq!(
+        surface_format(&formats, true),
+        Some(TextureFormat::Rgba16Float)
+    );
+}
+
+#[test]
+fn wide_gamut_falls_back_to_srgb() {
+    let formats = [
+        TextureFormat::Bgra8Unorm,
+        TextureFormat::Rgb10a2Unorm,
+        TextureFormat::Bgra8UnormSrgb,
+    ];
+    assert_eq!(
+        surface_format(&formats, true),
+        Some(TextureFormat::Bgra8UnormSrgb)
+    );
+}
+
+#[test]
+fn without_srgb_the_preferred_format() {
+    let formats = [TextureFormat::Bgra8Unorm, TextureFormat::Rgba8Unorm];
+    assert_eq!(
+        surface_format(&formats, true),
+        Some(TextureFormat::Bgra8Unorm)
+    );
+    assert_eq!(surface_format(&[], false), None);
+}
+
+#[test]
+fn linear_targets() {
+    assert!(is_linear(TextureFormat::Rgba8UnormSrgb));
+    assert!(is_linear(TextureFormat::Rgba16Float));
+    // Colors go out as sRGB values
+    assert!(!is_linear(TextureFormat::Bgra8Unorm));
+    assert!(!is_linear(TextureFormat::Rgb10a2Unorm));
+}
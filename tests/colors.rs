@@ -0,0 +1,124 @@
+//! Session colors: the OSC sequences changing them, the tint, and the IPC
+//! commands.
+
+use adamant::config::{Rgb, Tint};
+use adamant::term::{ColorSlot, Terminal, parse_color};
+
+#[test]
+fn colors_are_parsed_as_x11_spells_them() {
+    assert_eq!(parse_color("#ff8000"), Some(Rgb(0xff, 0x80, 0)));
+    assert_eq!(parse_color("#f80"), Some(Rgb(0xff, 0x88, 0)));
+    assert_eq!(parse_color("#ffff80000000"), Some(Rgb(0xff, 0x80, 0)));
+    assert_eq!(parse_color("rgb:ff/80/00"), Some(Rgb(0xff, 0x80, 0)));
+    assert_eq!(parse_color("rgb:f/8/0"), Some(Rgb(0xff, 0x88, 0)));
+    assert_eq!(parse_color("rgb:ffff/8080/0000"), Some(Rgb(0xff, 0x80, 0)));
+    for bad in [
+        "",
+        "?",
+        "red",
+        "#ff80",
+        "#gg0000",
+        "rgb:ff/80",
+        "rgb:+f/0/0",
+    ] {
+        assert_eq!(parse_color(bad), None, "{:?}", bad);
+    }
+}
+
+#[test]
+fn osc_sets_and_resets_palette_entries() {
+    let mut term = Terminal::new(80, 24, 100);
+    term.feed(b"\x1b]4;1;#ff0000;2;#00ff00\x07");
+    let red = Some(Rgb(0xff, 0, 0));
+    assert_eq!(term.colors().get(ColorSlot::Indexed(1)), red);
+    assert_eq!(
+        term.colors().get(ColorSlot::Indexed(2)),
+        Some(Rgb(0, 0xff, 0))
+    );
+
+    term.feed(b"\x1b]104;2\x07");
+    assert_eq!(term.colors().get(ColorSlot::Indexed(1)), red);
+    assert_eq!(term.colors().get(ColorSlot::Indexed(2)), None);
+    term.feed(b"\x1b]104\x07");
+    assert_eq!(term.colors().indexed().count(), 0);
+}
+
+#[test]
+fn osc_sets_the_default_colors_in_order() {
+    let mut term = Terminal::new(80, 24, 100);
+    // OSC 11 with two colors sets the background, then the cursor
+    term.feed(b"\x1b]10;#eeeeee\x1b\\\x1b]11;#200000;#ff0000\x1b\\");
+    let colors = term.colors();
+    assert_eq!(
+        colors.get(ColorSlot::Foreground),
+        Some(Rgb(0xee, 0xee, 0xee))
+    );
+    assert_eq!(colors.get(ColorSlot::Background), Some(Rgb(0x20, 0, 0)));
+    assert_eq!(colors.get(ColorSlot::Cursor), Some(Rgb(0xff, 0, 0)));
+
+    // The query changes nothing; OSC 111 resets the background
+    term.feed(b"\x1b]11;?\x07\x1b]111\x07");
+    assert_eq!(term.colors().get(ColorSlot::Background), None);
+    assert_eq!(term.colors().get(ColorSlot::Cursor), Some(Rgb(0xff, 0, 0)));
+}
+
+#[test]
+fn a_reset_keeps_the_tint() {
+    let mut term = Terminal::new(80, 24, 100);
+    let tint = Tint {
+        color: Rgb(0xff, 0, 0),
+        amount: 0.25,
+    };
+    term.colors_mut().tint = Some(tint);
+    term.feed(b"\x1b]11;#202020\x07\x1bc");
+    assert_eq!(term.colors().get(ColorSlot::Background), None);
+    assert_eq!(term.colors().tint, Some(tint));
+}
+
+#[test]
+fn tints_mix_into_the_background() {
+    let tint = Tint {
+        color: Rgb(0xff, 0, 0),
+        amount: 0.25,
+    };
+    assert_eq!(tint.over(Rgb(0x10, 0x10, 0x10)), Rgb(0x4c, 0x0c, 0x0c));
+    let full = Tint {
+        amount: 1.0,
+        ..tint
+    };
+    assert_eq!(full.over(Rgb(0x10, 0x10, 0x10)), Rgb(0xff, 0, 0));
+}
+
+#[cfg(unix)]
+#[test]
+fn ipc_commands_change_colors() {
+    use adamant::ipc::IpcCommand;
+
+    assert_eq!(
+        IpcCommand::parse("set-color background #300000"),
+        Ok(IpcCommand::SetColor(ColorSlot::Background, Rgb(0x30, 0, 0)))
+    );
+    assert_eq!(
+        IpcCommand::parse("set-color 9 rgb:ff/00/00"),
+        Ok(IpcCommand::SetColor(ColorSlot::Indexed(9), Rgb(0xff, 0, 0)))
+    );
+    assert_eq!(
+        IpcCommand::parse("tint #ff0000"),
+        Ok(IpcCommand::Tint(Some(Tint {
+            color: Rgb(0xff, 0, 0),
+            amount: Tint::DEFAULT_AMOUNT,
+        })))
+    );
+    assert_eq!(IpcCommand::parse("tint off"), Ok(IpcCommand::Tint(None)));
+    assert_eq!(
+        IpcCommand::parse("reset-colors"),
+        Ok(IpcCommand::ResetColors)
+    );
+    for bad in [
+        "set-color 256 #000000",
+        "set-color cursor red",
+        "tint #ff0000 2",
+    ] {
+        assert!(IpcCommand::parse(bad).is_err(), "{:?}", bad);
+    }
+}
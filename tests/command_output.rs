@@ -0,0 +1,68 @@
+//! The output of the last command, as shell integration (OSC 133) marks
+//! it, and the user commands it is piped to.
+
+use adamant::config::{CommandAction, Config, UserCommand};
+use adamant::term::Terminal;
+
+/// A prompt, `command` typed at it, `output` and the exit `status`.
+fn run(term: &mut Terminal, command: &str, output: &str, status: i32) {
+    term.feed(format!("\x1b]133;A\x07$ \x1b]133;B\x07{command}\r\n\x1b]133;C\x07").as_bytes());
+    term.feed(output.as_bytes());
+    term.feed(format!("\x1b]133;D;{status}\x07").as_bytes());
+}
+
+fn last_output(term: &Terminal) -> Option<String> {
+    let grid = term.grid();
+    grid.last_output()
+        .map(|selection| grid.selection_text(&selection))
+}
+
+#[test]
+fn the_last_finished_command_is_picked() {
+    let mut term = Terminal::new(20, 5, 100);
+    run(&mut term, "ls", "a\r\nb\r\n", 0);
+    run(&mut term, "make", "error: x\r\n\r\n  at y\r\n\r\n\r\n", 2);
+    // The next prompt, with a command still being typed
+    term.feed(b"\x1b]133;A\x07$ \x1b]133;B\x07vim");
+    assert_eq!(last_output(&term).as_deref(), Some("error: x\n\n  at y"));
+}
+
+#[test]
+fn long_commands_and_lines_wrap() {
+    let mut term = Terminal::new(10, 5, 100);
+    run(&mut term, "echo 0123456789", "0123456789abc\r\n", 0);
+    term.feed(b"\x1b]133;A\x07$ ");
+    assert_eq!(last_output(&term).as_deref(), Some("0123456789abc"));
+}
+
+#[test]
+fn without_prompt_marks_the_next_command_ends_it() {
+    let mut term = Terminal::new(20, 5, 100);
+    term.feed(b"$ \x1b]133;B\x07date\r\nMon\r\n\x1b]133;D;0\x07");
+    term.feed(b"$ \x1b]133;B\x07");
+    assert_eq!(last_output(&term).as_deref(), Some("Mon"));
+}
+
+#[test]
+fn nothing_without_finished_output() {
+    let mut term = Terminal::new(20, 5, 100);
+    term.feed(b"plain shell\r\n$ ");
+    assert_eq!(last_output(&term), None);
+    run(&mut term, "true", "", 0);
+    assert_eq!(last_output(&term), None);
+    // A command still running has no output yet
+    term.feed(b"\x1b]133;A\x07$ \x1b]133;B\x07sleep 9\r\n\x1b]133;C\x07zz");
+    assert_eq!(last_output(&term), None);
+}
+
+#[test]
+fn piping_needs_a_program() {
+    let mut config = Config::default();
+    config.commands.push(UserCommand {
+        name: "Paste bin",
+        action: CommandAction::PipeOutput(&[]),
+    });
+    assert!(config.validate().is_err());
+    config.commands[0].action = CommandAction::PipeOutput(&["wc", "-l"]);
+    assert!(config.validate().is_ok());
+}
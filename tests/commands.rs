@@ -0,0 +1,134 @@
+//! Command palette: the registry, fuzzy filtering and the palette overlay.
+
+#![cfg(feature = "gui")]
+
+use adamant::commands::{Action, CommandPalette, commands, fuzzy_score};
+use adamant::config::{CommandAction, Config, UserCommand};
+
+fn palette(query: &str) -> CommandPalette {
+    let config = Config {
+        commands: vec![UserCommand {
+            name: "Git status",
+            action: CommandAction::Input("git status\r"),
+        }],
+        ..Config::default()
+    };
+    let mut palette = CommandPalette::new(commands(&config));
+    query.chars().for_each(|c| palette.input(c));
+    palette
+}
+
+fn names(palette: &CommandPalette) -> Vec<&str> {
+    palette.matches().map(|command| command.name).collect()
+}
+
+#[test]
+fn characters_must_appear_in_order() {
+    assert!(fuzzy_score("cpy", "Copy selection").is_some());
+    assert!(fuzzy_score("COPY", "Copy selection").is_some());
+    assert!(fuzzy_score("ypc", "Copy selection").is_none());
+    assert!(fuzzy_score("copyx", "Copy selection").is_none());
+    assert_eq!(fuzzy_score("", "anything"), Some(0));
+}
+
+#[test]
+fn word_starts_and_runs_rank_higher() {
+    let word_starts = fuzzy_score("sb", "Scroll to bottom").unwrap();
+    let inside = fuzzy_score("sb", "Absorb").unwrap();
+    assert!(word_starts > inside);
+
+    let run = fuzzy_score("opy", "Copy").unwrap();
+    let scattered = fuzzy_score("opy", "xoxpxy").unwrap();
+    assert!(run > scattered);
+    // The best placement counts, not the first one
+    assert!(fuzzy_score("ab", "xa ab") > fuzzy_score("ab", "xa axb"));
+
+    assert!(fuzzy_score("gS", "gitStatus") > fuzzy_score("gs", "gist"));
+}
+
+#[test]
+fn user_commands_follow_the_builtin_actions() {
+    let palette = palette("");
+    let all = names(&palette);
+    assert_eq!(all[0], "Copy selection");
+    assert_eq!(all.last(), Some(&"Git status"));
+    assert_eq!(palette.selected(), Some(Action::Copy));
+    let with_shortcut = palette.matches().filter(|c| c.shortcut.is_some()).count();
+    assert_eq!(with_shortcut, 10);
+}
+
+#[test]
+fn typing_filters_and_ranks() {
+    let mut palette = palette("scro to");
+    assert_eq!(names(&palette), vec!["Scroll to top", "Scroll to bottom"]);
+    palette.input('b');
+    assert_eq!(names(&palette), vec!["Scroll to bottom"]);
+    assert_eq!(palette.selected(), Some(Action::ScrollToBottom));
+    palette.backspace();
+    assert_eq!(palette.query(), "scro to");
+    // Fuzzy: `gpu` is everywhere in the GPU entries, at word starts
+    let gpu = self::palette("gpu");
+    assert!(names(&gpu)[..3].iter().all(|name| name.contains("GPU")));
+
+    assert_eq!(palette_selected("gits"), Some(Action::User(0)));
+    assert_eq!(palette_selected("zzz"), None);
+}
+
+fn palette_selected(query: &str) -> Option<Action> {
+    palette(query).selected()
+}
+
+#[test]
+fn highlight_wraps_around() {
+    let mut palette = palette("scroll to");
+    palette.select_prev();
+    assert_eq!(palette.selected(), Some(Action::ScrollToBottom));
+    palette.select_next();
+    assert_eq!(palette.selected(), Some(Action::ScrollToTop));
+}
+
+#[test]
+fn layer_shows_the_query_and_highlights_the_selection() {
+    let mut palette = palette("scroll to");
+    palette.select_next();
+    let layer = palette.layer(24, 80);
+    assert_eq!(layer.elements[0].text, "> scroll to");
+    assert_eq!(layer.elements.len(), 3);
+    // 64 columns, centered
+    assert_eq!(layer.elements[1].rect.col, 8);
+    assert_eq!(layer.elements[1].rect.cols, 64);
+    let focused = layer.focused().unwrap();
+    assert!(focused.text.starts_with(" Scroll to bottom "));
+    palette.select(focused.id.unwrap());
+    assert_eq!(palette.selected(), Some(Action::ScrollToBottom));
+}
+
+#[test]
+fn long_lists_scroll_to_keep_the_selection_visible() {
+    let mut palette = palette("");
+    // Room for the query and three matches
+    for _ in 0..14 {
+        palette.select_next();
+    }
+    let layer = palette.layer(5, 80);
+    let shown: Vec<&str> = layer.elements[1..]
+        .iter()
+        .map(|element| element.text.trim_end())
+        .collect();
+    assert_eq!(shown.len(), 3);
+    assert!(layer.focused().unwrap().text.contains("integrated GPU"));
+    assert_eq!(
+        layer.elements.last().unwrap().id,
+        layer.focused().unwrap().id
+    );
+}
+
+#[test]
+fn commands_without_a_program_are_rejected() {
+    let mut config = Config::default();
+    config.commands.push(UserCommand {
+        name: "nothing",
+        action: CommandAction::Run(&[]),
+    });
+    assert!(config.validate().is_err());
+}
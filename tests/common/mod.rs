@@ -0,0 +1,313 @@
+//! Tiny TrueType fonts built in memory for the font tests: a few
+//! characters drawn with one outline, so a glyph shows which font drew it,
+//! and optionally variation axes and OpenType features.
+
+#![allow(dead_code)]
+
+use adamant::config::FontSource;
+
+/// The outline of every glyph of a font.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Shape {
+    Square,
+    Triangle,
+    Bar,
+}
+
+impl Shape {
+    /// The points of the outline's one contour, clockwise, in font units.
+    /// At the default 16 pixels per em they fall on whole pixels, so the
+    /// same shape is drawn the same way in every font.
+    fn points(self) -> &'static [(i16, i16)] {
+        match self {
+            Shape::Square => &[(128, 0), (128, 704), (512, 704), (512, 0)],
+            Shape::Triangle => &[(128, 0), (320, 704), (512, 0)],
+            Shape::Bar => &[(256, 0), (256, 704), (384, 704), (384, 0)],
+        }
+    }
+}
+
+/// A font to build.
+pub struct TestFont {
+    chars: Vec<char>,
+    shape: Shape,
+    /// Tag, minimum, default and maximum of each axis
+    axes: Vec<(&'static str, f32, f32, f32)>,
+    features: bool,
+}
+
+impl TestFont {
+    /// A font with a glyph for each of `chars`, drawn as `shape`.
+    pub fn new(chars: &str, shape: Shape) -> Self {
+        Self {
+            chars: chars.chars().collect(),
+            shape,
+            axes: Vec::new(),
+            features: false,
+        }
+    }
+
+    /// Make it a variable font with another axis.
+    pub fn axis(mut self, tag: &'static str, min: f32, default: f32, max: f32) -> Self {
+        self.axes.push((tag, min, default, max));
+        self
+    }
+
+    /// Add the OpenType features `liga`, an `fi` ligature, and `zero`, a
+    /// slashed zero. The font needs `f`, `i` and `0`.
+    pub fn features(mut self) -> Self {
+        self.features = true;
+        self
+    }
+
+    /// The glyph of `c`.
+    pub fn glyph(&self, c: char) -> u16 {
+        let index = self.chars.iter().position(|&other| other == c).unwrap();
+        index as u16 + 1
+    }
+
+    /// The `fi` ligature's glyph.
+    pub fn fi(&self) -> u16 {
+        self.chars.len() as u16 + 1
+    }
+
+    /// The slashed zero's glyph.
+    pub fn slashed_zero(&self) -> u16 {
+        self.chars.len() as u16 + 2
+    }
+
+    /// The font as a source for `FontConfig`.
+    pub fn source(&self) -> FontSource {
+        FontSource::Embedded(Box::leak(self.build().into_boxed_slice()))
+    }
+
+    /// The font file.
+    pub fn build(&self) -> Vec<u8> {
+        // .notdef, the characters, then the features' glyphs
+        let glyphs = self.chars.len() as u16 + 1 + if self.features { 2 } else { 0 };
+        let (glyf, loca) = self.outlines(glyphs);
+        let mut tables: Vec<(&[u8; 4], Vec<u8>)> = vec![
+            (b"cmap", self.cmap()),
+            (b"glyf", glyf),
+            (b"head", head()),
+            (b"hhea", hhea(glyphs)),
+            (b"hmtx", hmtx(glyphs)),
+            (b"loca", loca),
+            (b"maxp", maxp(glyphs)),
+            (b"post", post()),
+        ];
+        if !self.axes.is_empty() {
+            tables.push((b"fvar", self.fvar()));
+        }
+        if self.features {
+            tables.push((b"GSUB", self.gsub()));
+        }
+        tables.sort_by_key(|(tag, _)| **tag);
+        sfnt(&tables)
+    }
+
+    /// A format 12 character map.
+    fn cmap(&self) -> Vec<u8> {
+        let mut mapped: Vec<(char, u16)> =
+            (self.chars.iter()).map(|&c| (c, self.glyph(c))).collect();
+        mapped.sort();
+        let mut table = Vec::new();
+        // One Windows Unicode (full repertoire) subtable
+        push16(&mut table, &[0, 1, 3, 10]);
+        push32(&mut table, &[12]);
+        push16(&mut table, &[12, 0]);
+        push32(
+            &mut table,
+            &[16 + 12 * mapped.len() as u32, 0, mapped.len() as u32],
+        );
+        for (c, glyph) in mapped {
+            push32(&mut table, &[c as u32, c as u32, glyph as u32]);
+        }
+        table
+    }
+
+    /// The `glyf` and long `loca` tables: an empty .notdef, then every
+    /// glyph drawn as the shape.
+    fn outlines(&self, glyphs: u16) -> (Vec<u8>, Vec<u8>) {
+        let points = self.shape.points();
+        let mut outline = Vec::new();
+        let (xs, ys): (Vec<i16>, Vec<i16>) = points.iter().copied().unzip();
+        let bounds = [
+            *xs.iter().min().unwrap(),
+            *ys.iter().min().unwrap(),
+            *xs.iter().max().unwrap(),
+            *ys.iter().max().unwrap(),
+        ];
+        push16(&mut outline, &[1]);
+        push16(&mut outline, &bounds.map(|v| v as u16));
+        push16(&mut outline, &[points.len() as u16 - 1, 0]);
+        // Every point on the curve, coordinates as 16-bit deltas
+        outline.extend(std::iter::repeat_n(1, points.len()));
+        for coords in [&xs, &ys] {
+            let mut last = 0;
+            for &v in coords.iter() {
+                push16(&mut outline, &[(v - last) as u16]);
+                last = v;
+            }
+        }
+        outline.resize(outline.len().next_multiple_of(4), 0);
+
+        let glyf = outline.repeat(glyphs as usize - 1);
+        let mut loca = Vec::new();
+        push32(&mut loca, &[0]);
+        for glyph in 0..glyphs as u32 - 1 {
+            push32(&mut loca, &[glyph * outline.len() as u32]);
+        }
+        push32(&mut loca, &[glyf.len() as u32]);
+        (glyf, loca)
+    }
+
+    /// The variation axes, with no named instances.
+    fn fvar(&self) -> Vec<u8> {
+        let count = self.axes.len() as u16;
+        let mut table = Vec::new();
+        push16(&mut table, &[1, 0, 16, 2, count, 20, 0, 4 + 4 * count]);
+        for &(tag, min, default, max) in &self.axes {
+            table.extend_from_slice(tag.as_bytes());
+            let fixed = |v: f32| (v * 65536.0) as i32 as u32;
+            push32(&mut table, &[fixed(min), fixed(default), fixed(max)]);
+            push16(&mut table, &[0, 256]);
+        }
+        table
+    }
+
+    /// `liga` (lookup 0: f i to the ligature) and `zero` (lookup 1: 0 to
+    /// the slashed zero), for the default and Latin scripts.
+    fn gsub(&self) -> Vec<u8> {
+        let (f, i, zero) = (self.glyph('f'), self.glyph('i'), self.glyph('0'));
+        let mut table = Vec::new();
+        // Header: the script list at 10, the feature list at 38 and the
+        // lookup list at 64
+        push16(&mut table, &[1, 0, 10, 38, 64]);
+        // Script list: both scripts share the table at 14, whose default
+        // language system turns on features 0 and 1
+        push16(&mut table, &[2]);
+        table.extend_from_slice(b"DFLT");
+        push16(&mut table, &[14]);
+        table.extend_from_slice(b"latn");
+        push16(&mut table, &[14]);
+        push16(&mut table, &[4, 0]);
+        push16(&mut table, &[0, 0xffff, 2, 0, 1]);
+        // Feature list: liga at 14 uses lookup 0, zero at 20 lookup 1
+        push16(&mut table, &[2]);
+        table.extend_from_slice(b"liga");
+        push16(&mut table, &[14]);
+        table.extend_from_slice(b"zero");
+        push16(&mut table, &[20]);
+        push16(&mut table, &[0, 1, 0]);
+        push16(&mut table, &[0, 1, 1]);
+        // Lookup list: the lookups at 6 and 38
+        push16(&mut table, &[2, 6, 38]);
+        // A ligature substitution, its coverage at 8 and ligature set at 14
+        push16(&mut table, &[4, 0, 1, 8]);
+        push16(&mut table, &[1, 8, 1, 14]);
+        push16(&mut table, &[1, 1, f]);
+        push16(&mut table, &[1, 4]);
+        push16(&mut table, &[self.fi(), 2, i]);
+        // A single substitution, its coverage at 8
+        push16(&mut table, &[1, 0, 1, 8]);
+        push16(&mut table, &[2, 8, 1, self.slashed_zero()]);
+        push16(&mut table, &[1, 1, zero]);
+        table
+    }
+}
+
+fn head() -> Vec<u8> {
+    let mut table = Vec::new();
+    // Version, revision, checksum adjustment and magic number
+    push32(&mut table, &[0x10000, 0x10000, 0, 0x5f0f3cf5]);
+    // Flags and units per em
+    push16(&mut table, &[3, 1024]);
+    // Created and modified
+    push32(&mut table, &[0, 0, 0, 0]);
+    // Bounds, style, smallest size, direction, long offsets and format 0
+    push16(&mut table, &[0, -192i16 as u16, 640, 832, 0, 8, 2, 1, 0]);
+    table
+}
+
+fn hhea(glyphs: u16) -> Vec<u8> {
+    let mut table = Vec::new();
+    push32(&mut table, &[0x10000]);
+    // Ascender, descender, line gap, widest advance, bearings, extent and
+    // an upright caret
+    push16(
+        &mut table,
+        &[832, -192i16 as u16, 0, 640, 0, 0, 640, 1, 0, 0],
+    );
+    push16(&mut table, &[0, 0, 0, 0, 0, glyphs]);
+    table
+}
+
+/// Every glyph 640 units wide.
+fn hmtx(glyphs: u16) -> Vec<u8> {
+    let mut table = Vec::new();
+    for _ in 0..glyphs {
+        push16(&mut table, &[640, 0]);
+    }
+    table
+}
+
+fn maxp(glyphs: u16) -> Vec<u8> {
+    let mut table = Vec::new();
+    push32(&mut table, &[0x10000]);
+    push16(&mut table, &[glyphs, 4, 1, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0]);
+    table
+}
+
+fn post() -> Vec<u8> {
+    let mut table = Vec::new();
+    // Version 3 (no glyph names), upright
+    push32(&mut table, &[0x30000, 0]);
+    // Underline position and thickness, monospaced
+    push16(&mut table, &[-128i16 as u16, 64]);
+    push32(&mut table, &[1, 0, 0, 0, 0]);
+    table
+}
+
+/// The font file holding `tables`, sorted by tag.
+fn sfnt(tables: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+    let count = tables.len() as u16;
+    let power = 1 << count.ilog2();
+    let mut font = Vec::new();
+    push32(&mut font, &[0x10000]);
+    push16(
+        &mut font,
+        &[
+            count,
+            power * 16,
+            count.ilog2() as u16,
+            (count - power) * 16,
+        ],
+    );
+    let mut offset = 12 + 16 * tables.len();
+    for (tag, table) in tables {
+        font.extend_from_slice(*tag);
+        let checksum = (table.chunks(4))
+            .map(|word| {
+                let mut bytes = [0; 4];
+                bytes[..word.len()].copy_from_slice(word);
+                u32::from_be_bytes(bytes)
+            })
+            .fold(0u32, u32::wrapping_add);
+        push32(&mut font, &[checksum, offset as u32, table.len() as u32]);
+        offset += table.len().next_multiple_of(4);
+    }
+    for (_, table) in tables {
+        font.extend_from_slice(table);
+        font.resize(font.len().next_multiple_of(4), 0);
+    }
+    font
+}
+
+fn push16(table: &mut Vec<u8>, values: &[u16]) {
+    table.extend(values.iter().flat_map(|v| v.to_be_bytes()));
+}
+
+fn push32(table: &mut Vec<u8>, values: &[u32]) {
+    table.extend(values.iter().flat_map(|v| v.to_be_bytes()));
+}
@@ -0,0 +1,381 @@
+//! Conformance with xterm, as exercised by esctest and vttest: curated
+//! escape-sequence scripts fed to a terminal, with the screen they leave
+//! (text, attributes, cursor, scrollback and unhandled sequences) checked
+//! against snapshots in `tests/conformance/<name>.snap`.
+//!
+//! A scenario whose result still differs from xterm lists the difference
+//! in `gaps`, and its snapshot records what the terminal does today, so
+//! implementing the sequence shows up as a changed snapshot. Review the
+//! change, rewrite the snapshots with
+//!
+//! ```text
+//! UPDATE_SNAPSHOTS=1 cargo test --test conformance
+//! ```
+//!
+//! and drop the gap.
+
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use adamant::grid::{Cell, Color, Flags, Row};
+use adamant::term::{Terminal, escape_bytes};
+
+/// A script and where its expectations come from.
+struct Scenario {
+    /// Name of the snapshot file
+    name: &'static str,
+    /// The esctest case or vttest screen it follows
+    source: &'static str,
+    cols: usize,
+    rows: usize,
+    script: &'static [u8],
+    /// How the result differs from xterm's
+    gaps: &'static [&'static str],
+}
+
+const SCENARIOS: &[Scenario] = &[
+    Scenario {
+        name: "cup_clamps",
+        source: "esctest CUP: default and out-of-range parameters",
+        cols: 10,
+        rows: 4,
+        script: b"\x1b[99;99Ha\x1b[Hb\x1b[0;0Hc\x1b[3;4fd\x1b[;2He",
+        gaps: &[],
+    },
+    Scenario {
+        name: "cursor_motion",
+        source: "esctest CUU/CUD/CUF/CUB/CNL/CPL/CHA/VPA: relative moves stop at the edges",
+        cols: 10,
+        rows: 5,
+        script: b"\x1b[3;5H1\x1b[9A2\x1b[2B\x1b[9C3\x1b[99D4\x1b[E5\x1b[2F6\x1b[8G7\x1b[5d8",
+        gaps: &[],
+    },
+    Scenario {
+        name: "decaln",
+        source: "vttest 1: DECALN fills the screen with E and homes the cursor",
+        cols: 8,
+        rows: 4,
+        script: b"\x1b[2;3r\x1b[1;31m\x1b[3;3H\x1b#8x\x1b[4;1H\x1b[Dy",
+        gaps: &[],
+    },
+    Scenario {
+        name: "erase_in_display_and_line",
+        source: "esctest ED/EL: erase below, above and around the cursor",
+        cols: 8,
+        rows: 5,
+        script:
+            b"\x1b#8\x1b[2;4H\x1b[1K\x1b[2;6H\x1b[0K\x1b[3;1H\x1b[2K\x1b[4;5H\x1b[1J\x1b[5;7H\x1b[J",
+        gaps: &[],
+    },
+    Scenario {
+        name: "background_color_erase",
+        source: "vttest 11.4: erasing fills with the current background",
+        cols: 8,
+        rows: 3,
+        script: b"abcdefgh\x1b[44m\x1b[1;3H\x1b[K\x1b[2;1H\x1b[2X\x1b[3;5H\x1b[1K\x1b[m",
+        gaps: &[],
+    },
+    Scenario {
+        name: "insert_delete_characters",
+        source: "esctest ICH/DCH/ECH: shifting and blanking within the line",
+        cols: 8,
+        rows: 3,
+        script: b"abcdefgh\r\nabcdefgh\r\nabcdefgh\x1b[1;3H\x1b[2@\x1b[2;3H\x1b[2P\x1b[3;3H\x1b[2X",
+        gaps: &[],
+    },
+    Scenario {
+        name: "insert_delete_lines_in_margins",
+        source: "esctest IL/DL: lines move only inside the scrolling region",
+        cols: 4,
+        rows: 6,
+        script:
+            b"1\r\n2\r\n3\r\n4\r\n5\r\n6\x1b[2;5r\x1b[3;1H\x1b[L\x1b[5;1H\x1b[2M\x1b[1;1H\x1b[L",
+        gaps: &[],
+    },
+    Scenario {
+        name: "scroll_up_down",
+        source: "esctest SU/SD: scrolling the region with and without scrollback",
+        cols: 4,
+        rows: 4,
+        script: b"1\r\n2\r\n3\r\n4\x1b[2S\x1b[T\x1b[2;3r\x1b[S",
+        gaps: &[],
+    },
+    Scenario {
+        name: "linefeed_at_bottom",
+        source: "vttest 1: linefeeds scroll the screen into scrollback, RI scrolls back down",
+        cols: 4,
+        rows: 3,
+        script: b"1\r\n2\r\n3\r\n4\r\n5\x1b[H\x1bMt",
+        gaps: &[],
+    },
+    Scenario {
+        name: "autowrap",
+        source: "vttest 1: DECAWM, the pending wrap and its cancellation by CR",
+        cols: 5,
+        rows: 4,
+        script: b"abcdefg\r\n12345\rx\n\x1b[?7lwxyz!?\x1b[?7h\r\n\x1b[4;5HAB",
+        gaps: &[],
+    },
+    Scenario {
+        name: "tab_stops",
+        source: "vttest 2: HTS, TBC, CHT and CBT",
+        cols: 20,
+        rows: 3,
+        script:
+            b"\ta\tb\r\n\x1b[3g\x1b[4GH\x1bH\x1b[10G\x1bH\r\tc\td\r\n\x1b[20G\x1b[2Ze\x1b[G\x1b[2If",
+        gaps: &[],
+    },
+    Scenario {
+        name: "insert_mode_and_newline_mode",
+        source: "esctest IRM and LNM",
+        cols: 8,
+        rows: 3,
+        script: b"abcdef\x1b[3G\x1b[4hXY\x1b[4l\x1b[20h\nnew\x1b[20l\ndown",
+        gaps: &[],
+    },
+    Scenario {
+        name: "origin_mode",
+        source: "vttest 1: DECOM makes CUP relative to the scrolling region",
+        cols: 6,
+        rows: 6,
+        script: b"\x1b[3;5r\x1b[?6h\x1b[Ho\x1b[9;9Hz\x1b[?6l\x1b[Hh\x1b[r",
+        gaps: &[],
+    },
+    Scenario {
+        name: "save_restore_cursor",
+        source: "esctest DECSC/DECRC: position, rendition and origin mode",
+        cols: 8,
+        rows: 3,
+        script: b"\x1b[2;3H\x1b[1;32m\x1b7\x1b[m\x1b[3;6Hp\x1b8r\x1b[s\x1b[Hq\x1b[us",
+        gaps: &[],
+    },
+    Scenario {
+        name: "sgr_attributes",
+        source: "vttest 11.6: SGR renditions, 256 colors and direct colors",
+        cols: 12,
+        rows: 3,
+        script: b"\x1b[1mb\x1b[2md\x1b[3mi\x1b[4mu\x1b[5mk\x1b[7mv\x1b[8mh\x1b[9ms\x1b[m.\
+                  \x1b[22;1m\x1b[22mn\r\n\x1b[38;5;208mo\x1b[48;2;1;2;3mr\x1b[38:2::9:8:7mc\
+                  \x1b[39;49m\x1b[93;104mB\x1b[m\r\n\x1b[4:3mc\x1b[4:0mu\x1b[1;3;7m\x1b[23;27mi",
+        gaps: &[],
+    },
+    Scenario {
+        name: "wide_characters",
+        source: "esctest: double-width characters at the margin and overwritten halves",
+        cols: 5,
+        rows: 3,
+        script: "ab漢字\r\n漢字x\x1b[2;2Hy\r\n\x1b[3;3H漢\x1b[3;3Hz".as_bytes(),
+        gaps: &[],
+    },
+    Scenario {
+        name: "dec_line_drawing",
+        source: "vttest 3: the DEC special graphics set through G0, G1 and SS2",
+        cols: 6,
+        rows: 3,
+        script: b"\x1b(0lqqk\x1b(B\r\n\x1b)0\x0etwu\x0fx\r\n\x1b*0\x1bNa\x1bNjb",
+        gaps: &[],
+    },
+    Scenario {
+        name: "repeat_character",
+        source: "esctest REP: repeats the last printed graphic character",
+        cols: 8,
+        rows: 2,
+        script: b"ab\x1b[3b\r\n\x1b[31m-\x1b[12b",
+        gaps: &[],
+    },
+    Scenario {
+        name: "alternate_screen",
+        source: "esctest: mode 1049 saves the cursor and clears the alternate screen",
+        cols: 6,
+        rows: 3,
+        script: b"main\x1b[2;2H\x1b[?1049hALT\x1b[?1049lx",
+        gaps: &[],
+    },
+    Scenario {
+        name: "deccolm",
+        source: "vttest 1: DECCOLM switches to 132 columns and clears the screen",
+        cols: 8,
+        rows: 3,
+        script: b"text\x1b[?3hz",
+        gaps: &["DECCOLM (mode 3) is ignored: xterm with mode 40 resizes, clears and homes"],
+    },
+    Scenario {
+        name: "left_right_margins",
+        source: "esctest DECSLRM: text wraps at the left and right margins",
+        cols: 8,
+        rows: 3,
+        script: b"\x1b[?69h\x1b[3;5s\x1b[3Gabcdef",
+        gaps: &[
+            "DECLRMM (mode 69) is unhandled and CSI s always saves the cursor, \
+             so the text runs on to the screen edge instead of wrapping at column 5",
+        ],
+    },
+    Scenario {
+        name: "insert_delete_columns",
+        source: "esctest DECIC/DECDC: columns shift across the scrolling region",
+        cols: 6,
+        rows: 2,
+        script: b"abcdef\r\nabcdef\x1b[1;2H\x1b[2'}",
+        gaps: &["DECIC and DECDC are unhandled, so nothing moves"],
+    },
+];
+
+/// Where a scenario's snapshot lives.
+fn snapshot_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/conformance")
+        .join(format!("{}.snap", name))
+}
+
+/// The text of `row` between bars, wide characters once.
+fn row_line(row: &Row) -> String {
+    let text: String = row
+        .cells()
+        .iter()
+        .filter(|cell| !cell.flags.contains(Flags::WIDE_SPACER))
+        .map(|cell| cell.c)
+        .collect();
+    format!("|{}|", text)
+}
+
+fn color(color: Color) -> String {
+    match color {
+        Color::Default => "default".to_string(),
+        Color::Indexed(index) => index.to_string(),
+        Color::Rgb(r, g, b) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+    }
+}
+
+/// The rendition of `cell`, empty for the default one.
+fn rendition(cell: &Cell) -> String {
+    let flags = cell.flags - Flags::WIDE - Flags::WIDE_SPACER;
+    let mut parts: Vec<String> = flags
+        .iter_names()
+        .map(|(name, _)| name.to_lowercase())
+        .collect();
+    if cell.fg != Color::Default {
+        parts.push(format!("fg={}", color(cell.fg)));
+    }
+    if cell.bg != Color::Default {
+        parts.push(format!("bg={}", color(cell.bg)));
+    }
+    parts.join(" ")
+}
+
+/// Runs of cells with the same non-default rendition: `<row> <first
+/// col>-<last col> <rendition>`, counting from 1.
+fn attribute_lines(out: &mut String, label: &str, row: &Row) {
+    let cells = row.cells();
+    let mut start = 0;
+    while start < cells.len() {
+        let style = rendition(&cells[start]);
+        let len = cells[start..]
+            .iter()
+            .take_while(|cell| rendition(cell) == style)
+            .count();
+        if !style.is_empty() {
+            writeln!(out, "{} {}-{} {}", label, start + 1, start + len, style).unwrap();
+        }
+        start += len;
+    }
+}
+
+/// The snapshot of the terminal `scenario` leaves.
+fn snapshot(scenario: &Scenario) -> String {
+    let mut term = Terminal::new(scenario.cols, scenario.rows, 100);
+    term.feed(scenario.script);
+    let grid = term.grid();
+    let history = grid.history_len();
+
+    let mut out = String::new();
+    writeln!(out, "# {}", scenario.source).unwrap();
+    writeln!(out, "script {}", escape_bytes(scenario.script)).unwrap();
+    writeln!(
+        out,
+        "cursor {},{}{}",
+        grid.cursor.row + 1,
+        grid.cursor.col + 1,
+        if grid.cursor.pending_wrap {
+            " (pending wrap)"
+        } else {
+            ""
+        }
+    )
+    .unwrap();
+    if history > 0 {
+        out.push_str("scrollback\n");
+        for line in 0..history {
+            writeln!(out, "{}", row_line(grid.line(line))).unwrap();
+        }
+    }
+    out.push_str("screen\n");
+    for row in 0..grid.rows() {
+        writeln!(out, "{}", row_line(grid.row(row))).unwrap();
+    }
+    let mut attributes = String::new();
+    for row in 0..grid.rows() {
+        attribute_lines(&mut attributes, &(row + 1).to_string(), grid.row(row));
+    }
+    if !attributes.is_empty() {
+        out.push_str("attributes\n");
+        out.push_str(&attributes);
+    }
+    if !term.unhandled().is_empty() {
+        out.push_str("unhandled\n");
+        for unhandled in term.unhandled() {
+            writeln!(out, "{}", unhandled.line()).unwrap();
+        }
+    }
+    out
+}
+
+#[test]
+fn scenarios_match_their_snapshots() {
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+    let mut failures = Vec::new();
+    for scenario in SCENARIOS {
+        let actual = snapshot(scenario);
+        let path = snapshot_path(scenario.name);
+        if update {
+            std::fs::write(&path, &actual).unwrap();
+            continue;
+        }
+        match std::fs::read_to_string(&path) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => failures.push(format!(
+                "{}:\n--- expected\n{}+++ actual\n{}",
+                scenario.name, expected, actual
+            )),
+            Err(e) => failures.push(format!("{}: {}\n{}", scenario.name, e, actual)),
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{}\nIf the changes are right, run UPDATE_SNAPSHOTS=1 cargo test --test conformance",
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn scenarios_without_gaps_use_only_handled_sequences() {
+    for scenario in SCENARIOS.iter().filter(|scenario| scenario.gaps.is_empty()) {
+        let mut term = Terminal::new(scenario.cols, scenario.rows, 100);
+        term.feed(scenario.script);
+        let unhandled: Vec<String> = term.unhandled().iter().map(|u| u.line()).collect();
+        assert!(unhandled.is_empty(), "{}: {:?}", scenario.name, unhandled);
+    }
+}
+
+#[test]
+fn every_snapshot_has_a_scenario() {
+    let dir = snapshot_path("").parent().unwrap().to_path_buf();
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_stem().unwrap().to_str().unwrap().to_string();
+        assert!(
+            SCENARIOS.iter().any(|scenario| scenario.name == name),
+            "{} is left over",
+            path.display()
+        );
+    }
+}
@@ -0,0 +1,48 @@
+//! Selected text as copied: trailing blanks and wrapped rows.
+
+use adamant::grid::{CopyOptions, Point, Selection};
+use adamant::term::Terminal;
+
+/// A command wrapped over two rows, then a line with trailing blanks.
+fn copy(options: CopyOptions) -> String {
+    let mut term = Terminal::new(8, 4, 0);
+    term.feed(b"echo abcdefgh\r\nok   ");
+    let mut selection = Selection::new(Point::new(0, 0));
+    selection.update(Point::new(2, 7));
+    term.grid().selection_text_with(&selection, options)
+}
+
+#[test]
+fn wrapped_rows_join_and_blanks_go_by_default() {
+    assert_eq!(copy(CopyOptions::default()), "echo abcdefgh\nok");
+}
+
+#[test]
+fn both_can_be_turned_off() {
+    let keep_blanks = CopyOptions {
+        trim_trailing_whitespace: false,
+        ..CopyOptions::default()
+    };
+    assert_eq!(copy(keep_blanks), "echo abcdefgh   \nok      ");
+    let keep_rows = CopyOptions {
+        join_wrapped_lines: false,
+        ..CopyOptions::default()
+    };
+    assert_eq!(copy(keep_rows), "echo abc\ndefgh\nok");
+}
+
+#[test]
+fn blocks_copy_the_same_columns_of_each_row() {
+    let mut term = Terminal::new(12, 4, 0);
+    term.feed(b"a.rs   12 KB\r\nlib.rs  3 KB\r\nx       1 B");
+    // From the size column's right end up to its left
+    let mut selection = Selection::block(Point::new(2, 10));
+    selection.update(Point::new(0, 7));
+    assert_eq!(selection.columns(), (7, 10));
+    assert!(selection.contains(Point::new(1, 8)));
+    assert!(!selection.contains(Point::new(1, 11)));
+    let text = term
+        .grid()
+        .selection_text_with(&selection, CopyOptions::default());
+    assert_eq!(text, "12 K\n 3 K\n 1 B");
+}
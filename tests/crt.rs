@@ -0,0 +1,43 @@
+//! The built-in CRT effect: its shader compiles at every intensity, and
+//! intensities out of range are caught at startup.
+
+#![cfg(feature = "gui")]
+
+use adamant::config::{Config, CrtConfig};
+use adamant::renderer::{check_post_shader, crt_shader};
+
+#[test]
+fn the_shader_compiles_at_every_intensity() {
+    for intensity in [0.0, 0.25, 1.0] {
+        let crt = CrtConfig {
+            enabled: true,
+            scanlines: intensity,
+            bloom: intensity,
+            curvature: intensity,
+            persistence: intensity,
+        };
+        let source = crt_shader(&crt);
+        if let Err(e) = check_post_shader(&source) {
+            panic!("at {}: {}", intensity, e);
+        }
+    }
+}
+
+#[test]
+fn intensities_are_between_0_and_1() {
+    let mut config = Config::default();
+    config.crt.enabled = true;
+    assert!(config.validate().is_ok());
+    config.crt.bloom = 1.5;
+    assert!(config.validate().is_err());
+    config.crt.bloom = 1.0;
+    config.crt.persistence = -0.1;
+    assert!(config.validate().is_err());
+    config.crt.persistence = f32::NAN;
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn safe_mode_turns_it_off() {
+    assert!(!Config::safe_mode().crt.enabled);
+}
@@ -0,0 +1,79 @@
+//! DCS strings: the ones not implemented are swallowed whole, and tmux's
+//! passthrough is unwrapped and run.
+
+use adamant::grid::Color;
+use adamant::term::Terminal;
+
+fn row_text(term: &Terminal, row: usize) -> String {
+    let cells = term.grid().visible_row(row).cells();
+    cells
+        .iter()
+        .map(|cell| cell.c)
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+/// `ESC P tmux; … ESC \` around `inner`, its ESCs doubled.
+fn wrap(inner: &[u8]) -> Vec<u8> {
+    let mut wrapped = b"\x1bPtmux;".to_vec();
+    for &byte in inner {
+        if byte == 0x1b {
+            wrapped.push(byte);
+        }
+        wrapped.push(byte);
+    }
+    wrapped.extend_from_slice(b"\x1b\\");
+    wrapped
+}
+
+#[test]
+fn unimplemented_dcs_leaves_no_text() {
+    let mut term = Terminal::new(20, 3, 0);
+    // Sixel, DECUDK and tmux control mode, across feeds
+    term.feed(b"a\x1bPq#0;2;0;0;0#0!10~-\x1b\\");
+    term.feed(b"\x1bP1;1|17/6c73\x1b\\\x1bP1000p%begin 1 2 0\r\n");
+    term.feed(b"%end 1 2 0\r\n\x1b\\b");
+    assert_eq!(row_text(&term, 0), "ab");
+    assert_eq!(row_text(&term, 1), "");
+}
+
+#[test]
+fn tmux_passthrough_runs_the_inner_sequence() {
+    let mut term = Terminal::new(20, 2, 0);
+    term.feed(&wrap(b"\x1b]2;outer\x07"));
+    term.feed(&wrap(b"\x1b[31m"));
+    term.feed(b"x");
+    term.feed(&wrap(b"\x1bP+q544e\x1b\\"));
+    assert_eq!(term.title(), Some("outer"));
+    assert_eq!(row_text(&term, 0), "x");
+    assert_eq!(term.grid().visible_row(0)[0].fg, Color::Indexed(1));
+    let reply = String::from_utf8(term.take_responses()).unwrap();
+    assert!(reply.starts_with("\x1bP1+r544e="), "{:?}", reply);
+}
+
+#[test]
+fn nested_tmux_passthrough_unwraps_twice() {
+    let mut term = Terminal::new(20, 2, 0);
+    term.feed(&wrap(&wrap(b"\x1b]2;twice\x07")));
+    assert_eq!(term.title(), Some("twice"));
+    assert_eq!(row_text(&term, 0), "");
+}
+
+#[test]
+fn broken_passthrough_runs_nothing() {
+    let mut term = Terminal::new(20, 2, 0);
+    // Cancelled halfway
+    let mut cancelled = wrap(b"\x1b]2;cancelled\x07");
+    cancelled.insert(cancelled.len() - 2, 0x18);
+    term.feed(&cancelled);
+    // Too long to keep
+    let mut long = b"\x1b]2;long".to_vec();
+    long.resize(100_000, b'x');
+    long.push(0x07);
+    term.feed(&wrap(&long));
+    // A `t` final byte, but not tmux's
+    term.feed(b"\x1bPtext\x1b\x1b]2;other\x07\x1b\\");
+    assert_eq!(term.title(), None);
+    assert_eq!(row_text(&term, 0), "");
+}
@@ -0,0 +1,81 @@
+//! Desktop integration: decoding the window icon.
+
+#![cfg(feature = "gui")]
+
+use std::io::Cursor;
+
+use adamant::config::Config;
+use adamant::desktop::decode_png;
+
+fn encode(
+    color: png::ColorType,
+    depth: png::BitDepth,
+    pixels: &[u8],
+    palette: Option<&[u8]>,
+) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, 2, 1);
+    encoder.set_color(color);
+    encoder.set_depth(depth);
+    if let Some(palette) = palette {
+        encoder.set_palette(palette.to_vec());
+    }
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(pixels).unwrap();
+    writer.finish().unwrap();
+    bytes
+}
+
+/// The RGBA pixels of a 2x1 image.
+fn decoded(color: png::ColorType, pixels: &[u8], palette: Option<&[u8]>) -> Vec<u8> {
+    let png = encode(color, png::BitDepth::Eight, pixels, palette);
+    let image = decode_png(Cursor::new(png)).unwrap();
+    assert_eq!((image.width, image.height), (2, 1));
+    image.rgba
+}
+
+#[test]
+fn every_color_type_becomes_rgba() {
+    use png::{BitDepth, ColorType};
+
+    let rgba = [1, 2, 3, 4, 5, 6, 7, 8];
+    assert_eq!(decoded(ColorType::Rgba, &rgba, None), rgba);
+    assert_eq!(
+        decoded(ColorType::Rgb, &[1, 2, 3, 4, 5, 6], None),
+        [1, 2, 3, 255, 4, 5, 6, 255]
+    );
+    assert_eq!(
+        decoded(ColorType::GrayscaleAlpha, &[9, 128, 10, 0], None),
+        [9, 9, 9, 128, 10, 10, 10, 0]
+    );
+    let palette = [0, 0, 0, 200, 100, 50];
+    assert_eq!(
+        decoded(ColorType::Indexed, &[1, 0], Some(&palette)),
+        [200, 100, 50, 255, 0, 0, 0, 255]
+    );
+
+    // 16 bits per sample are cut to 8
+    let png = encode(
+        ColorType::Grayscale,
+        BitDepth::Sixteen,
+        &[0xab, 0xcd, 0x12, 0x34],
+        None,
+    );
+    let image = decode_png(Cursor::new(png)).unwrap();
+    assert_eq!(image.rgba, [0xab, 0xab, 0xab, 255, 0x12, 0x12, 0x12, 255]);
+}
+
+#[test]
+fn other_files_are_rejected() {
+    assert!(decode_png(Cursor::new(b"GIF89a".to_vec())).is_err());
+}
+
+#[test]
+fn app_id_must_be_a_name() {
+    let mut config = Config::default();
+    assert_eq!(config.desktop.app_id, "adamant");
+    config.desktop.app_id = "";
+    assert!(config.validate().is_err());
+    config.desktop.app_id = "org.example.Term";
+    assert!(config.validate().is_ok());
+}
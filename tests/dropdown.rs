@@ -0,0 +1,106 @@
+//! Dropdown window: toggling, sliding and the remembered height.
+
+#![cfg(feature = "gui")]
+
+use adamant::config::DropdownConfig;
+use adamant::dropdown::{Dropdown, MonitorArea, Toggle};
+use web_time::{Duration, Instant};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+
+const MONITOR: MonitorArea = (
+    PhysicalPosition::new(1920, 0),
+    PhysicalSize::new(2560, 1440),
+);
+
+fn dropdown() -> Dropdown {
+    Dropdown::new(&DropdownConfig::default())
+}
+
+#[test]
+fn an_unfocused_window_is_brought_to_the_front_first() {
+    let mut dropdown = dropdown();
+    let now = Instant::now();
+    assert_eq!(dropdown.toggle(false, now), Toggle::Focus);
+    assert!(!dropdown.is_sliding());
+    assert_eq!(dropdown.toggle(true, now), Toggle::Hide);
+}
+
+#[test]
+fn sliding_in_ends_on_the_frame() {
+    let mut dropdown = dropdown();
+    let start = Instant::now();
+    dropdown.toggle(true, start);
+    assert_eq!(
+        dropdown.slide_position(MONITOR, start + Duration::from_secs(1)),
+        None
+    );
+    assert!(!dropdown.is_shown());
+
+    assert_eq!(dropdown.toggle(false, start), Toggle::Show);
+    // Starts fully above the monitor's top edge
+    let first = dropdown.slide_position(MONITOR, start).unwrap();
+    assert_eq!(first, PhysicalPosition::new(1920, -576));
+    let later = dropdown
+        .slide_position(MONITOR, start + Duration::from_millis(100))
+        .unwrap();
+    assert!(later.y > first.y && later.y < 0);
+    assert_eq!(
+        dropdown.slide_position(MONITOR, start + Duration::from_secs(1)),
+        None
+    );
+    assert!(dropdown.is_shown() && !dropdown.is_sliding());
+}
+
+#[test]
+fn pressing_again_while_sliding_turns_around() {
+    let mut dropdown = dropdown();
+    let start = Instant::now();
+    assert_eq!(dropdown.toggle(true, start), Toggle::Hide);
+    assert_eq!(dropdown.toggle(false, start), Toggle::Show);
+    assert_eq!(dropdown.toggle(false, start), Toggle::Hide);
+}
+
+#[test]
+fn the_resized_height_is_kept() {
+    let mut dropdown = dropdown();
+    dropdown.resized(700);
+    assert_eq!(dropdown.frame(MONITOR).1, PhysicalSize::new(2560, 700));
+    // Not taller than the monitor
+    dropdown.resized(5000);
+    assert_eq!(dropdown.frame(MONITOR).1.height, 1440);
+
+    // Sizes set while sliding are the app's own
+    dropdown.toggle(true, Instant::now());
+    dropdown.resized(10);
+    assert_eq!(dropdown.frame(MONITOR).1.height, 1440);
+}
+
+#[test]
+fn without_animation_it_toggles_right_away() {
+    let mut dropdown = Dropdown::new(&DropdownConfig {
+        slide_ms: 0,
+        ..DropdownConfig::default()
+    });
+    let now = Instant::now();
+    dropdown.toggle(true, now);
+    assert_eq!(dropdown.slide_position(MONITOR, now), None);
+    assert!(!dropdown.is_shown());
+}
+
+#[test]
+fn height_must_be_a_fraction() {
+    let mut config = adamant::config::Config::default();
+    config.dropdown.height = 0.0;
+    assert!(config.validate().is_err());
+    config.dropdown.height = 1.0;
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn dropdown_starts_windowed() {
+    let mut config = adamant::config::Config::default();
+    config.dropdown.enabled = true;
+    assert!(config.validate().is_ok());
+    config.window_mode = adamant::config::WindowMode::Fullscreen;
+    assert!(config.validate().is_err());
+}
@@ -0,0 +1,135 @@
+//! The C API: creating and freeing a terminal, feeding it, reading cells,
+//! serializing the screen into buffers of every size, and query replies.
+
+#![cfg(feature = "ffi")]
+
+use std::ffi::CStr;
+use std::ptr;
+
+use adamant::ffi::*;
+
+/// A terminal fed `bytes`.
+fn terminal(cols: u32, rows: u32, bytes: &[u8]) -> *mut AdamantTerminal {
+    let term = adamant_terminal_new(cols, rows, 100);
+    assert!(!term.is_null());
+    unsafe { adamant_terminal_feed(term, bytes.as_ptr(), bytes.len()) };
+    term
+}
+
+/// The screen serialized into a buffer of `cap` bytes, and the length
+/// returned.
+fn serialize(term: *const AdamantTerminal, cap: usize) -> (String, usize) {
+    let mut buf = vec![0x7f as std::ffi::c_char; cap];
+    let len = unsafe { adamant_terminal_serialize(term, buf.as_mut_ptr(), cap) };
+    let text = unsafe { CStr::from_ptr(buf.as_ptr()) };
+    (text.to_str().unwrap().to_string(), len)
+}
+
+#[test]
+fn cells_hold_what_was_fed() {
+    let term = terminal(10, 3, b"a\x1b[1;31mb\x1b[0m\r\n\xe4\xb8\xad");
+    let mut cell = unsafe { std::mem::zeroed::<AdamantCell>() };
+    unsafe {
+        assert!(adamant_terminal_cell(term, 0, 0, &mut cell));
+        assert_eq!(cell.codepoint, 'a' as u32);
+        assert_eq!(cell.fg.kind, AdamantColorKind::Default);
+        assert_eq!(cell.flags, 0);
+
+        assert!(adamant_terminal_cell(term, 0, 1, &mut cell));
+        assert_eq!(cell.codepoint, 'b' as u32);
+        assert_eq!(
+            (cell.fg.kind, cell.fg.index),
+            (AdamantColorKind::Indexed, 1)
+        );
+        assert_eq!(cell.flags, ADAMANT_FLAG_BOLD);
+
+        assert!(adamant_terminal_cell(term, 1, 0, &mut cell));
+        assert_eq!(cell.codepoint, '中' as u32);
+        assert_eq!(cell.flags, ADAMANT_FLAG_WIDE);
+        assert!(adamant_terminal_cell(term, 1, 1, &mut cell));
+        assert_eq!(cell.flags, ADAMANT_FLAG_WIDE_SPACER);
+
+        // Outside the grid, `out` is left alone
+        assert!(!adamant_terminal_cell(term, 3, 0, &mut cell));
+        assert!(!adamant_terminal_cell(term, 0, 10, &mut cell));
+        assert_eq!(cell.flags, ADAMANT_FLAG_WIDE_SPACER);
+
+        let (mut cols, mut rows, mut row, mut col) = (0, 0, 0, 0);
+        adamant_terminal_size(term, &mut cols, &mut rows);
+        adamant_terminal_cursor(term, &mut row, &mut col);
+        assert_eq!((cols, rows, row, col), (10, 3, 1, 2));
+        adamant_terminal_free(term);
+    }
+}
+
+#[test]
+fn serializing_truncates_like_snprintf() {
+    let term = terminal(10, 3, b"hello\r\nworld");
+    let full = "hello\nworld\n";
+    // The size alone
+    assert_eq!(
+        unsafe { adamant_terminal_serialize(term, ptr::null_mut(), 0) },
+        full.len()
+    );
+    assert_eq!(serialize(term, 64), (full.to_string(), full.len()));
+    assert_eq!(
+        serialize(term, full.len() + 1),
+        (full.to_string(), full.len())
+    );
+    // One byte short drops the last character for the NUL
+    assert_eq!(
+        serialize(term, full.len()),
+        ("hello\nworld".to_string(), full.len())
+    );
+    assert_eq!(serialize(term, 4), ("hel".to_string(), full.len()));
+    assert_eq!(serialize(term, 1), (String::new(), full.len()));
+    unsafe { adamant_terminal_free(term) };
+}
+
+#[test]
+fn replies_are_read_in_pieces() {
+    let term = terminal(10, 3, b"\x1b[2;5H\x1b[6n\x1b[5n");
+    let mut buf = [0u8; 4];
+    let mut replies = Vec::new();
+    loop {
+        let len = unsafe { adamant_terminal_read_responses(term, buf.as_mut_ptr(), buf.len()) };
+        if len == 0 {
+            break;
+        }
+        replies.extend_from_slice(&buf[..len]);
+    }
+    assert_eq!(replies, b"\x1b[2;5R\x1b[0n");
+    unsafe { adamant_terminal_free(term) };
+}
+
+#[test]
+fn a_cursor_above_the_origin_mode_region_is_reported() {
+    let term = terminal(10, 10, b"\x1b[?6h\x1b7\x1b[5;8r\x1b8\x1b[6n");
+    let mut buf = [0u8; 16];
+    let len = unsafe { adamant_terminal_read_responses(term, buf.as_mut_ptr(), buf.len()) };
+    assert_eq!(&buf[..len], b"\x1b[1;1R");
+    unsafe { adamant_terminal_free(term) };
+}
+
+#[test]
+fn null_handles_and_buffers_are_ignored() {
+    let mut cell = unsafe { std::mem::zeroed::<AdamantCell>() };
+    unsafe {
+        adamant_terminal_feed(ptr::null_mut(), b"x".as_ptr(), 1);
+        assert!(!adamant_terminal_cell(ptr::null(), 0, 0, &mut cell));
+        assert_eq!(
+            adamant_terminal_serialize(ptr::null(), ptr::null_mut(), 0),
+            0
+        );
+        assert_eq!(
+            adamant_terminal_read_responses(ptr::null_mut(), ptr::null_mut(), 0),
+            0
+        );
+        adamant_terminal_free(ptr::null_mut());
+
+        let term = terminal(10, 3, b"");
+        adamant_terminal_feed(term, ptr::null(), 0);
+        assert!(!adamant_terminal_cell(term, 0, 0, ptr::null_mut()));
+        adamant_terminal_free(term);
+    }
+}
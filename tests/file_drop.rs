@@ -0,0 +1,48 @@
+//! Dropped files: shell quoting, file URLs and the cd line.
+
+#![cfg(all(feature = "gui", unix))]
+
+use std::path::{Path, PathBuf};
+
+use adamant::config::DropFormat;
+use adamant::file_drop::{cd_command, drop_text, file_url, shell_quote};
+
+#[test]
+fn plain_paths_stay_unquoted() {
+    assert_eq!(
+        shell_quote("/usr/share/doc/a-b_c+1.0,x"),
+        "/usr/share/doc/a-b_c+1.0,x"
+    );
+    assert_eq!(shell_quote("user@host:path"), "user@host:path");
+}
+
+#[test]
+fn special_characters_are_quoted() {
+    assert_eq!(shell_quote(""), "''");
+    assert_eq!(shell_quote("/tmp/a b"), "'/tmp/a b'");
+    assert_eq!(shell_quote("/tmp/$HOME;rm *"), "'/tmp/$HOME;rm *'");
+    assert_eq!(shell_quote("/tmp/it's"), r"'/tmp/it'\''s'");
+    assert_eq!(shell_quote("/tmp/日本"), "'/tmp/日本'");
+}
+
+#[test]
+fn urls_are_percent_encoded() {
+    assert_eq!(file_url(Path::new("/tmp/a~b.txt")), "file:///tmp/a~b.txt");
+    assert_eq!(
+        file_url(Path::new("/tmp/50% #1")),
+        "file:///tmp/50%25%20%231"
+    );
+    assert_eq!(file_url(Path::new("/tmp/é")), "file:///tmp/%C3%A9");
+}
+
+#[test]
+fn dropped_files_are_separated() {
+    assert_eq!(drop_text(&[], DropFormat::Path), "");
+    let paths = [PathBuf::from("/a"), PathBuf::from("/b c")];
+    assert_eq!(drop_text(&paths, DropFormat::Path), "/a '/b c' ");
+}
+
+#[test]
+fn cd_runs_the_command() {
+    assert_eq!(cd_command(Path::new("/srv/my site")), "cd '/srv/my site'\r");
+}
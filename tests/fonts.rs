@@ -0,0 +1,160 @@
+//! Fonts: the axis values variable fonts are drawn at, and the font each
+//! character is drawn with, overrides first.
+
+#![cfg(feature = "gui")]
+
+mod common;
+
+use adamant::config::{FontConfig, FontOverride, FontSource};
+use adamant::error::AdamantError;
+use adamant::renderer::{FontSet, GlyphKey};
+
+use common::{Shape, TestFont};
+
+/// A config drawing everything with `font`.
+fn config(font: &TestFont) -> FontConfig {
+    FontConfig {
+        regular: vec![font.source()],
+        bold: Vec::new(),
+        fallback: Vec::new(),
+        ..FontConfig::default()
+    }
+}
+
+/// An override of `ranges` and `scripts` with the first of `font` that
+/// loads.
+fn over(ranges: &[(char, char)], scripts: &[&'static str], font: Vec<FontSource>) -> FontOverride {
+    FontOverride {
+        ranges: ranges.to_vec(),
+        scripts: scripts.to_vec(),
+        font,
+    }
+}
+
+/// The outline `c` is drawn with, if it is one of a test font's.
+fn drawn_with(fonts: &mut FontSet, c: char) -> Option<Shape> {
+    let pixels = |fonts: &mut FontSet, c| {
+        let glyph = fonts.rasterize(GlyphKey { c, bold: false })?;
+        Some((glyph.left, glyph.top, glyph.width, glyph.height, glyph.data))
+    };
+    let drawn = pixels(fonts, c)?;
+    [Shape::Square, Shape::Triangle, Shape::Bar]
+        .into_iter()
+        .find(|&shape| {
+            let mut fonts = FontSet::load(&config(&TestFont::new("x", shape)), 1.0).unwrap();
+            pixels(&mut fonts, 'x').as_ref() == Some(&drawn)
+        })
+}
+
+#[test]
+fn variations_set_the_axes_of_each_face() {
+    let font = TestFont::new("aM", Shape::Square)
+        .axis("wght", 100.0, 400.0, 900.0)
+        .axis("wdth", 50.0, 100.0, 200.0);
+    let mut config = config(&font);
+    config.variations = vec![("wght", 650.0)];
+    config.bold_variations = vec![("wght", 900.0), ("wdth", 75.0)];
+    let fonts = FontSet::load(&config, 1.0).unwrap();
+    // F2Dot14: 1.0 is 16384; axes left out stay at their default, 0
+    assert_eq!(fonts.variations(false), [8192, 0]);
+    assert_eq!(fonts.variations(true), [16384, -8192]);
+
+    // Without bold values, bold text uses the regular ones
+    config.bold_variations = Vec::new();
+    let fonts = FontSet::load(&config, 1.0).unwrap();
+    assert_eq!(fonts.variations(true), [8192, 0]);
+
+    // Axes the font doesn't have are ignored, and values are clamped
+    config.variations = vec![("slnt", -8.0), ("wdth", 400.0)];
+    let fonts = FontSet::load(&config, 1.0).unwrap();
+    assert_eq!(fonts.variations(false), [0, 16384]);
+}
+
+#[test]
+fn static_fonts_have_no_axes() {
+    let mut config = config(&TestFont::new("aM", Shape::Square));
+    config.variations = vec![("wght", 650.0)];
+    config.bold_variations = vec![("wght", 900.0)];
+    let fonts = FontSet::load(&config, 1.0).unwrap();
+    assert!(fonts.variations(false).is_empty());
+    assert!(fonts.variations(true).is_empty());
+}
+
+#[test]
+fn overrides_draw_their_ranges() {
+    let mut config = config(&TestFont::new("aαγж", Shape::Square));
+    let greek = TestFont::new("αβ", Shape::Triangle);
+    config.overrides = vec![over(&[('α', 'ω')], &[], vec![greek.source()])];
+    let mut fonts = FontSet::load(&config, 1.0).unwrap();
+    assert_eq!(drawn_with(&mut fonts, 'a'), Some(Shape::Square));
+    assert_eq!(drawn_with(&mut fonts, 'α'), Some(Shape::Triangle));
+    assert_eq!(drawn_with(&mut fonts, 'β'), Some(Shape::Triangle));
+    // In the range but not in the font: the regular face
+    assert_eq!(drawn_with(&mut fonts, 'γ'), Some(Shape::Square));
+    assert_eq!(drawn_with(&mut fonts, 'ж'), Some(Shape::Square));
+    // Overridden characters aren't shaped with the face
+    assert!(fonts.covers('a', false));
+    assert!(!fonts.covers('α', false));
+    assert!(fonts.covers('γ', false));
+}
+
+#[test]
+fn overrides_draw_their_scripts() {
+    let mut config = config(&TestFont::new("aαж", Shape::Square));
+    let greek = TestFont::new("aαж", Shape::Triangle);
+    // Unknown tags are left out
+    config.overrides = vec![over(&[], &["zzzz", "grek"], vec![greek.source()])];
+    let mut fonts = FontSet::load(&config, 1.0).unwrap();
+    assert_eq!(drawn_with(&mut fonts, 'α'), Some(Shape::Triangle));
+    assert_eq!(drawn_with(&mut fonts, 'a'), Some(Shape::Square));
+    assert_eq!(drawn_with(&mut fonts, 'ж'), Some(Shape::Square));
+}
+
+#[test]
+fn an_override_that_does_not_load_falls_back() {
+    let missing = FontSource::Path("/nonexistent/adamant-test.ttf");
+    let mut config = config(&TestFont::new("aα", Shape::Square));
+    config.overrides = vec![over(&[('α', 'ω')], &[], vec![missing])];
+    let mut fonts = FontSet::load(&config, 1.0).unwrap();
+    assert_eq!(drawn_with(&mut fonts, 'α'), Some(Shape::Square));
+    assert!(fonts.covers('α', false));
+
+    // The next candidate that loads is used
+    let greek = TestFont::new("α", Shape::Triangle);
+    config.overrides = vec![over(&[('α', 'ω')], &[], vec![missing, greek.source()])];
+    let mut fonts = FontSet::load(&config, 1.0).unwrap();
+    assert_eq!(drawn_with(&mut fonts, 'α'), Some(Shape::Triangle));
+}
+
+#[test]
+fn a_missing_regular_font_is_an_error() {
+    let missing = FontSource::Path("/nonexistent/adamant-test.ttf");
+    let mut config = config(&TestFont::new("a", Shape::Square));
+    config.regular = vec![missing];
+    let error = FontSet::load(&config, 1.0).err();
+    assert!(matches!(error, Some(AdamantError::Font("font.regular"))));
+
+    // Candidates after it, and missing bold and fallback fonts, are fine
+    config.regular.push(TestFont::new("a", Shape::Bar).source());
+    config.bold = vec![missing];
+    config.fallback = vec![missing];
+    let mut fonts = FontSet::load(&config, 1.0).unwrap();
+    assert_eq!(drawn_with(&mut fonts, 'a'), Some(Shape::Bar));
+}
+
+#[test]
+fn the_first_overlapping_override_with_the_character_wins() {
+    let mut config = config(&TestFont::new("aαβγδ", Shape::Square));
+    let first = TestFont::new("αβ", Shape::Triangle);
+    let second = TestFont::new("βγδ", Shape::Bar);
+    config.overrides = vec![
+        over(&[('α', 'γ')], &[], vec![first.source()]),
+        over(&[('β', 'ω')], &[], vec![second.source()]),
+    ];
+    let mut fonts = FontSet::load(&config, 1.0).unwrap();
+    assert_eq!(drawn_with(&mut fonts, 'α'), Some(Shape::Triangle));
+    assert_eq!(drawn_with(&mut fonts, 'β'), Some(Shape::Triangle));
+    // Missing from the first
+    assert_eq!(drawn_with(&mut fonts, 'γ'), Some(Shape::Bar));
+    assert_eq!(drawn_with(&mut fonts, 'δ'), Some(Shape::Bar));
+}
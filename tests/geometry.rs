@@ -0,0 +1,85 @@
+//! Saved window geometry: the file format and finding the place again.
+
+#![cfg(feature = "gui")]
+
+use adamant::config::WindowMode;
+use adamant::dropdown::MonitorArea;
+use adamant::geometry::{WindowGeometry, state_path};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+
+fn monitor(name: &str, x: i32, width: u32) -> (Option<String>, MonitorArea) {
+    let area = (PhysicalPosition::new(x, 0), PhysicalSize::new(width, 1080));
+    (Some(name.to_string()), area)
+}
+
+fn geometry() -> WindowGeometry {
+    WindowGeometry {
+        size: PhysicalSize::new(1024, 640),
+        position: Some(PhysicalPosition::new(100, 50)),
+        monitor: Some("HDMI-1".to_string()),
+        mode: WindowMode::Fullscreen,
+    }
+}
+
+#[test]
+fn saved_text_reads_back() {
+    let geometry = geometry();
+    assert_eq!(WindowGeometry::parse(&geometry.to_string()), Some(geometry));
+
+    let bare = WindowGeometry {
+        position: None,
+        monitor: None,
+        mode: WindowMode::Windowed,
+        ..self::geometry()
+    };
+    assert_eq!(bare.to_string(), "size 1024 640\nmode windowed\n");
+    assert_eq!(WindowGeometry::parse(&bare.to_string()), Some(bare));
+}
+
+#[test]
+fn unusable_text_is_skipped() {
+    assert_eq!(WindowGeometry::parse(""), None);
+    assert_eq!(WindowGeometry::parse("size 0 480\n"), None);
+    assert_eq!(WindowGeometry::parse("size big\nmode windowed\n"), None);
+
+    let geometry = WindowGeometry::parse("opacity 0.9\nsize 800 600\nposition x y\nmode tiled\n");
+    let geometry = geometry.unwrap();
+    assert_eq!(geometry.size, PhysicalSize::new(800, 600));
+    assert_eq!(geometry.position, None);
+    assert_eq!(geometry.mode, WindowMode::Windowed);
+}
+
+#[test]
+fn position_follows_the_monitor() {
+    let geometry = geometry();
+    let moved = [monitor("eDP-1", 0, 1920), monitor("HDMI-1", -1280, 1280)];
+    assert_eq!(
+        geometry.position_on(&moved),
+        Some(PhysicalPosition::new(-1180, 50))
+    );
+    // Unplugged, or too small for the old place now
+    assert_eq!(geometry.position_on(&[monitor("eDP-1", 0, 1920)]), None);
+    assert_eq!(geometry.position_on(&[monitor("HDMI-1", 0, 80)]), None);
+}
+
+#[test]
+fn state_files_are_per_app_id() {
+    let (Some(a), Some(b)) = (state_path("adamant"), state_path("adamant-scratch")) else {
+        return;
+    };
+    assert_ne!(a, b);
+    assert_eq!(a.parent(), b.parent());
+    let odd = state_path("../etc/passwd").unwrap();
+    assert_eq!(odd.parent(), a.parent());
+}
+
+#[test]
+fn save_and_load() {
+    let path = std::env::temp_dir()
+        .join(format!("adamant-geometry-{}", std::process::id()))
+        .join("adamant.geometry");
+    geometry().save(&path).unwrap();
+    assert_eq!(WindowGeometry::load(&path), Some(geometry()));
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    assert_eq!(WindowGeometry::load(&path), None);
+}
@@ -0,0 +1,85 @@
+//! GPU selection: the adapter each preference asks for, and telling
+//! battery power from AC on Linux and macOS.
+
+#![cfg(feature = "gui")]
+
+use std::path::{Path, PathBuf};
+
+use adamant::config::GpuPreference;
+use adamant::renderer::{pmset_on_battery, power_preference, sysfs_on_battery};
+use wgpu::PowerPreference;
+
+/// An empty directory for a fake `/sys/class/power_supply`.
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("adamant-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Add a supply with `files` (name, contents) to `dir`.
+fn supply(dir: &Path, name: &str, files: &[(&str, &str)]) {
+    let supply = dir.join(name);
+    std::fs::create_dir_all(&supply).unwrap();
+    for (file, contents) in files {
+        std::fs::write(supply.join(file), format!("{}\n", contents)).unwrap();
+    }
+}
+
+#[test]
+fn auto_follows_the_power_source() {
+    use GpuPreference::*;
+    assert_eq!(power_preference(Auto, true), PowerPreference::LowPower);
+    assert_eq!(
+        power_preference(Auto, false),
+        PowerPreference::HighPerformance
+    );
+    for on_battery in [true, false] {
+        assert_eq!(
+            power_preference(Integrated, on_battery),
+            PowerPreference::LowPower
+        );
+        assert_eq!(
+            power_preference(Discrete, on_battery),
+            PowerPreference::HighPerformance
+        );
+    }
+}
+
+#[test]
+fn a_discharging_battery_is_battery_power() {
+    let dir = scratch_dir("power-battery");
+    supply(&dir, "AC", &[("type", "Mains"), ("online", "0")]);
+    supply(
+        &dir,
+        "BAT0",
+        &[("type", "Battery"), ("status", "Discharging")],
+    );
+    assert!(sysfs_on_battery(&dir));
+
+    // Plugged in: charging or full, or mains online whatever the battery says
+    supply(&dir, "BAT0", &[("status", "Charging")]);
+    assert!(!sysfs_on_battery(&dir));
+    supply(&dir, "BAT0", &[("status", "Discharging")]);
+    supply(&dir, "AC", &[("online", "1")]);
+    assert!(!sysfs_on_battery(&dir));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn no_battery_or_no_sysfs_is_ac_power() {
+    let dir = scratch_dir("power-desktop");
+    supply(&dir, "ACAD", &[("type", "Mains"), ("online", "1")]);
+    assert!(!sysfs_on_battery(&dir));
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(!sysfs_on_battery(&dir));
+}
+
+#[test]
+fn pmset_names_the_power_source() {
+    let battery = "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=4653155)\t81%; discharging; 5:12 remaining present: true\n";
+    let ac = "Now drawing from 'AC Power'\n -InternalBattery-0 (id=4653155)\t100%; charged; 0:00 remaining present: true\n";
+    assert!(pmset_on_battery(battery));
+    assert!(!pmset_on_battery(ac));
+    assert!(!pmset_on_battery(""));
+}
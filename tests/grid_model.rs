@@ -0,0 +1,442 @@
+//! Grid property tests.
+//!
+//! Random operation sequences are applied both to [`Grid`] and to a
+//! deliberately naive reference model (plain vectors, no ring buffer, no
+//! shortcuts). After every operation the screen, wrap flags, cursor and
+//! scrollback must match, so optimizations of the grid can't silently change
+//! scrolling, wrapping or erase semantics.
+//!
+//! A second property checks that wide characters never leave orphaned halves.
+
+use adamant::grid::{Flags, Grid};
+use proptest::prelude::*;
+
+const TAB_WIDTH: usize = 8;
+
+/// One primitive grid operation.
+#[derive(Clone, Debug)]
+enum Op {
+    Write(char, bool),
+    Goto(usize, usize),
+    MoveUp(usize),
+    MoveDown(usize),
+    MoveForward(usize),
+    MoveBack(usize),
+    CarriageReturn,
+    Linefeed,
+    ReverseIndex,
+    TabForward(usize),
+    TabBackward(usize),
+    SetTabStop,
+    ClearTabStops(bool),
+    ScrollUp(usize),
+    ScrollDown(usize),
+    InsertLines(usize),
+    DeleteLines(usize),
+    EraseDisplay(u16),
+    EraseLine(u16),
+    EraseChars(usize),
+    InsertBlank(usize),
+    DeleteChars(usize),
+    SetScrollRegion(usize, usize),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let n = 0usize..12;
+    prop_oneof![
+        // Printing dominates real output, so weight it up.
+        6 => (proptest::char::range('a', 'z'), prop::bool::weighted(0.9))
+            .prop_map(|(c, autowrap)| Op::Write(c, autowrap)),
+        1 => (0usize..12, 0usize..12).prop_map(|(r, c)| Op::Goto(r, c)),
+        1 => n.clone().prop_map(Op::MoveUp),
+        1 => n.clone().prop_map(Op::MoveDown),
+        1 => n.clone().prop_map(Op::MoveForward),
+        1 => n.clone().prop_map(Op::MoveBack),
+        2 => Just(Op::CarriageReturn),
+        3 => Just(Op::Linefeed),
+        1 => Just(Op::ReverseIndex),
+        1 => (1usize..3).prop_map(Op::TabForward),
+        1 => (1usize..3).prop_map(Op::TabBackward),
+        1 => Just(Op::SetTabStop),
+        1 => any::<bool>().prop_map(Op::ClearTabStops),
+        1 => n.clone().prop_map(Op::ScrollUp),
+        1 => n.clone().prop_map(Op::ScrollDown),
+        1 => n.clone().prop_map(Op::InsertLines),
+        1 => n.clone().prop_map(Op::DeleteLines),
+        1 => (0u16..5).prop_map(Op::EraseDisplay),
+        1 => (0u16..4).prop_map(Op::EraseLine),
+        1 => n.clone().prop_map(Op::EraseChars),
+        1 => n.clone().prop_map(Op::InsertBlank),
+        1 => n.prop_map(Op::DeleteChars),
+        1 => (0usize..10, 0usize..10).prop_map(|(t, b)| Op::SetScrollRegion(t, b)),
+    ]
+}
+
+fn apply(grid: &mut Grid, op: &Op) {
+    match *op {
+        Op::Write(c, autowrap) => grid.write(c, 1, autowrap),
+        Op::Goto(r, c) => grid.goto(r, c),
+        Op::MoveUp(n) => grid.move_up(n),
+        Op::MoveDown(n) => grid.move_down(n),
+        Op::MoveForward(n) => grid.move_forward(n),
+        Op::MoveBack(n) => grid.move_back(n),
+        Op::CarriageReturn => grid.carriage_return(),
+        Op::Linefeed => grid.linefeed(),
+        Op::ReverseIndex => grid.reverse_index(),
+        Op::TabForward(n) => grid.tab_forward(n),
+        Op::TabBackward(n) => grid.tab_backward(n),
+        Op::SetTabStop => grid.set_tab_stop(),
+        Op::ClearTabStops(all) => grid.clear_tab_stops(all),
+        Op::ScrollUp(n) => grid.scroll_up(n),
+        Op::ScrollDown(n) => grid.scroll_down(n),
+        Op::InsertLines(n) => grid.insert_lines(n),
+        Op::DeleteLines(n) => grid.delete_lines(n),
+        Op::EraseDisplay(mode) => grid.erase_display(mode),
+        Op::EraseLine(mode) => grid.erase_line(mode),
+        Op::EraseChars(n) => grid.erase_chars(n),
+        Op::InsertBlank(n) => grid.insert_blank(n),
+        Op::DeleteChars(n) => grid.delete_chars(n),
+        Op::SetScrollRegion(t, b) => grid.set_scroll_region(t, b),
+    }
+}
+
+/// A line of the reference model.
+#[derive(Clone, Debug, PartialEq)]
+struct Line {
+    chars: Vec<char>,
+    wrapped: bool,
+}
+
+impl Line {
+    fn blank(cols: usize) -> Self {
+        Self {
+            chars: vec![' '; cols],
+            wrapped: false,
+        }
+    }
+}
+
+/// The simplest grid that could possibly work.
+#[derive(Debug)]
+struct Model {
+    cols: usize,
+    rows: usize,
+    max_scrollback: usize,
+    history: Vec<Line>,
+    screen: Vec<Line>,
+    row: usize,
+    col: usize,
+    pending_wrap: bool,
+    top: usize,
+    bottom: usize,
+    tabs: Vec<bool>,
+}
+
+impl Model {
+    fn new(cols: usize, rows: usize, max_scrollback: usize) -> Self {
+        Self {
+            cols,
+            rows,
+            max_scrollback,
+            history: Vec::new(),
+            screen: vec![Line::blank(cols); rows],
+            row: 0,
+            col: 0,
+            pending_wrap: false,
+            top: 0,
+            bottom: rows,
+            tabs: (0..cols).map(|c| c > 0 && c % TAB_WIDTH == 0).collect(),
+        }
+    }
+
+    fn apply(&mut self, op: &Op) {
+        match *op {
+            Op::Write(c, autowrap) => self.write(c, autowrap),
+            Op::Goto(r, c) => {
+                self.row = r.min(self.rows - 1);
+                self.col = c.min(self.cols - 1);
+                self.pending_wrap = false;
+            }
+            Op::MoveUp(n) => {
+                let min = if self.row >= self.top { self.top } else { 0 };
+                self.row = self.row.saturating_sub(n).max(min);
+                self.pending_wrap = false;
+            }
+            Op::MoveDown(n) => {
+                let max = if self.row < self.bottom {
+                    self.bottom - 1
+                } else {
+                    self.rows - 1
+                };
+                self.row = (self.row + n).min(max);
+                self.pending_wrap = false;
+            }
+            Op::MoveForward(n) => {
+                self.col = (self.col + n).min(self.cols - 1);
+                self.pending_wrap = false;
+            }
+            Op::MoveBack(n) => {
+                self.col = self.col.saturating_sub(n);
+                self.pending_wrap = false;
+            }
+            Op::CarriageReturn => {
+                self.col = 0;
+                self.pending_wrap = false;
+            }
+            Op::Linefeed => self.linefeed(),
+            Op::ReverseIndex => {
+                if self.row == self.top {
+                    self.scroll_down(1);
+                } else if self.row > 0 {
+                    self.row -= 1;
+                }
+                self.pending_wrap = false;
+            }
+            Op::TabForward(n) => {
+                for _ in 0..n {
+                    self.col = (self.col + 1..self.cols)
+                        .find(|&c| self.tabs[c])
+                        .unwrap_or(self.cols - 1);
+                }
+                self.pending_wrap = false;
+            }
+            Op::TabBackward(n) => {
+                for _ in 0..n {
+                    self.col = (0..self.col).rev().find(|&c| self.tabs[c]).unwrap_or(0);
+                }
+                self.pending_wrap = false;
+            }
+            Op::SetTabStop => self.tabs[self.col] = true,
+            Op::ClearTabStops(true) => self.tabs.fill(false),
+            Op::ClearTabStops(false) => self.tabs[self.col] = false,
+            Op::ScrollUp(n) => self.scroll_up(n, true),
+            Op::ScrollDown(n) => self.scroll_down(n),
+            Op::InsertLines(n) => {
+                if (self.top..self.bottom).contains(&self.row) {
+                    let top = std::mem::replace(&mut self.top, self.row);
+                    self.scroll_down(n);
+                    self.top = top;
+                    self.col = 0;
+                    self.pending_wrap = false;
+                }
+            }
+            Op::DeleteLines(n) => {
+                if (self.top..self.bottom).contains(&self.row) {
+                    let top = std::mem::replace(&mut self.top, self.row);
+                    self.scroll_up(n, false);
+                    self.top = top;
+                    self.col = 0;
+                    self.pending_wrap = false;
+                }
+            }
+            Op::EraseDisplay(mode) => match mode {
+                0 => {
+                    self.erase_line(0);
+                    for line in &mut self.screen[self.row + 1..] {
+                        *line = Line::blank(self.cols);
+                    }
+                }
+                1 => {
+                    for line in &mut self.screen[..self.row] {
+                        *line = Line::blank(self.cols);
+                    }
+                    self.erase_line(1);
+                }
+                2 => self.screen.fill(Line::blank(self.cols)),
+                3 => self.history.clear(),
+                _ => {}
+            },
+            Op::EraseLine(mode) => self.erase_line(mode),
+            Op::EraseChars(n) => {
+                let end = (self.col + n.max(1)).min(self.cols);
+                self.screen[self.row].chars[self.col..end].fill(' ');
+            }
+            Op::InsertBlank(n) => {
+                let n = n.min(self.cols - self.col);
+                let chars = &mut self.screen[self.row].chars;
+                for _ in 0..n {
+                    chars.insert(self.col, ' ');
+                }
+                chars.truncate(self.cols);
+                self.pending_wrap = false;
+            }
+            Op::DeleteChars(n) => {
+                let n = n.clamp(1, self.cols - self.col);
+                let chars = &mut self.screen[self.row].chars;
+                chars.drain(self.col..self.col + n);
+                chars.resize(self.cols, ' ');
+                self.pending_wrap = false;
+            }
+            Op::SetScrollRegion(t, b) => {
+                if t + 1 < b && b <= self.rows {
+                    (self.top, self.bottom) = (t, b);
+                } else {
+                    (self.top, self.bottom) = (0, self.rows);
+                }
+            }
+        }
+    }
+
+    fn write(&mut self, c: char, autowrap: bool) {
+        if self.pending_wrap && autowrap {
+            self.screen[self.row].wrapped = true;
+            self.col = 0;
+            self.linefeed();
+        }
+        self.screen[self.row].chars[self.col] = c;
+        if self.col + 1 == self.cols {
+            self.pending_wrap = true;
+        } else {
+            self.col += 1;
+            self.pending_wrap = false;
+        }
+    }
+
+    fn linefeed(&mut self) {
+        if self.row + 1 == self.bottom {
+            self.scroll_up(1, true);
+        } else if self.row + 1 < self.rows {
+            self.row += 1;
+        }
+        self.pending_wrap = false;
+    }
+
+    fn scroll_up(&mut self, n: usize, keep_history: bool) {
+        let n = n.min(self.bottom - self.top);
+        let full_screen = self.top == 0 && self.bottom == self.rows;
+        for _ in 0..n {
+            let line = self.screen.remove(self.top);
+            self.screen.insert(self.bottom - 1, Line::blank(self.cols));
+            if keep_history && full_screen && self.max_scrollback > 0 {
+                self.history.push(line);
+            }
+        }
+        let excess = self.history.len().saturating_sub(self.max_scrollback);
+        self.history.drain(..excess);
+    }
+
+    fn scroll_down(&mut self, n: usize) {
+        let n = n.min(self.bottom - self.top);
+        for _ in 0..n {
+            self.screen.remove(self.bottom - 1);
+            self.screen.insert(self.top, Line::blank(self.cols));
+        }
+    }
+
+    fn erase_line(&mut self, mode: u16) {
+        let line = &mut self.screen[self.row];
+        match mode {
+            0 => {
+                line.chars[self.col..].fill(' ');
+                line.wrapped = false;
+            }
+            1 => line.chars[..=self.col].fill(' '),
+            2 => *line = Line::blank(self.cols),
+            _ => {}
+        }
+    }
+}
+
+/// Read a grid row back into the model's representation.
+fn line_of(grid: &Grid, index: usize) -> Line {
+    let row = grid.line(index);
+    Line {
+        chars: row.cells().iter().map(|cell| cell.c).collect(),
+        wrapped: row.wrapped,
+    }
+}
+
+fn assert_matches(grid: &Grid, model: &Model) -> Result<(), TestCaseError> {
+    let history = grid.history_len();
+    prop_assert_eq!(history, model.history.len(), "history length");
+    for (i, expected) in model.history.iter().enumerate() {
+        prop_assert_eq!(&line_of(grid, i), expected, "history line {}", i);
+    }
+    for (r, expected) in model.screen.iter().enumerate() {
+        prop_assert_eq!(&line_of(grid, history + r), expected, "screen row {}", r);
+    }
+    prop_assert_eq!(
+        (grid.cursor.row, grid.cursor.col, grid.cursor.pending_wrap),
+        (model.row, model.col, model.pending_wrap),
+        "cursor (row, col, pending_wrap)"
+    );
+    prop_assert_eq!(grid.scroll_region(), (model.top, model.bottom));
+    Ok(())
+}
+
+/// Every WIDE cell is followed by a WIDE_SPACER and every spacer follows a
+/// WIDE cell.
+fn assert_no_orphans(grid: &Grid) -> Result<(), TestCaseError> {
+    for i in 0..grid.total_lines() {
+        let cells = grid.line(i).cells();
+        for (col, cell) in cells.iter().enumerate() {
+            if cell.flags.contains(Flags::WIDE) {
+                prop_assert!(
+                    cells
+                        .get(col + 1)
+                        .is_some_and(|next| next.flags.contains(Flags::WIDE_SPACER)),
+                    "wide cell without spacer at line {} col {}",
+                    i,
+                    col
+                );
+            }
+            if cell.flags.contains(Flags::WIDE_SPACER) {
+                prop_assert!(
+                    col > 0 && cells[col - 1].flags.contains(Flags::WIDE),
+                    "orphaned spacer at line {} col {}",
+                    i,
+                    col
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    #[test]
+    fn grid_matches_reference_model(
+        cols in 1usize..10,
+        rows in 1usize..8,
+        max_scrollback in 0usize..6,
+        ops in prop::collection::vec(op(), 0..200),
+    ) {
+        let mut grid = Grid::new(cols, rows, max_scrollback);
+        let mut model = Model::new(cols, rows, max_scrollback);
+
+        for op in &ops {
+            apply(&mut grid, op);
+            model.apply(op);
+            assert_matches(&grid, &model)?;
+        }
+    }
+
+    #[test]
+    fn wide_chars_leave_no_orphaned_halves(
+        cols in 1usize..10,
+        rows in 1usize..6,
+        ops in prop::collection::vec(
+            prop_oneof![
+                3 => any::<bool>().prop_map(|wide| if wide {
+                    Op::Write('\u{4e00}', true)
+                } else {
+                    Op::Write('a', true)
+                }),
+                1 => op(),
+            ],
+            0..200,
+        ),
+    ) {
+        let mut grid = Grid::new(cols, rows, 4);
+
+        for op in &ops {
+            match *op {
+                Op::Write('\u{4e00}', autowrap) => grid.write('\u{4e00}', 2, autowrap),
+                _ => apply(&mut grid, op),
+            }
+            assert_no_orphans(&grid)?;
+        }
+    }
+}
@@ -0,0 +1,342 @@
+//! Hints mode: what gets matched on screen and how labels are picked;
+//! finding the link under the pointer.
+
+#![cfg(feature = "gui")]
+
+use adamant::config::{HintAction, HintPattern, HintsConfig};
+use adamant::hints::{HintMatcher, HintPick, Hints, editor_args, open_target};
+use adamant::term::Terminal;
+
+/// The hints for a screen showing `text`, with the default patterns.
+fn hints_for(text: &str) -> Hints {
+    hints_with(&HintsConfig::default(), text)
+}
+
+fn hints_with(config: &HintsConfig, text: &str) -> Hints {
+    let mut term = Terminal::new(60, 6, 0);
+    term.feed(text.as_bytes());
+    HintMatcher::new(config).unwrap().find(term.grid())
+}
+
+/// (pattern, text) of every hint.
+fn matches(hints: &Hints) -> Vec<(&'static str, &str)> {
+    hints
+        .hints()
+        .iter()
+        .map(|hint| (hint.pattern, hint.text.as_str()))
+        .collect()
+}
+
+#[test]
+fn urls_paths_and_hashes() {
+    let hints = hints_for(
+        "open https://example.com/a?b=1\r\nsee ./src/main.rs and ~/notes\r\ncommit 3f2a9c1e fixed it",
+    );
+    assert_eq!(
+        matches(&hints),
+        vec![
+            ("url", "https://example.com/a?b=1"),
+            ("path", "./src/main.rs"),
+            ("path", "~/notes"),
+            ("hash", "3f2a9c1e"),
+        ]
+    );
+}
+
+#[test]
+fn surrounding_punctuation_is_not_part_of_a_url() {
+    let hints = hints_for("(docs: https://en.wikipedia.org/wiki/Foo_(bar)), or https://a.io/x.");
+    assert_eq!(
+        matches(&hints),
+        vec![
+            ("url", "https://en.wikipedia.org/wiki/Foo_(bar)"),
+            ("url", "https://a.io/x"),
+        ]
+    );
+}
+
+#[test]
+fn matches_continue_on_wrapped_rows() {
+    let mut term = Terminal::new(20, 3, 0);
+    term.feed(b"log: https://example.com/a/b/c done");
+    let hints = HintMatcher::new(&HintsConfig::default())
+        .unwrap()
+        .find(term.grid());
+    let hint = &hints.hints()[0];
+    assert_eq!(hint.text, "https://example.com/a/b/c");
+    assert_eq!((hint.row, hint.col, hint.width), (0, 5, 25));
+}
+
+#[test]
+fn hovering_finds_the_link_under_the_pointer() {
+    let mut term = Terminal::new(20, 3, 0);
+    term.feed(b"log: https://example.com/a/b/c done");
+    let hints = HintMatcher::new(&HintsConfig::default())
+        .unwrap()
+        .find(term.grid());
+    let at = |row, col| hints.hint_at(row, col).map(|hint| hint.text.as_str());
+    assert_eq!(at(0, 5), Some("https://example.com/a/b/c"));
+    // The wrapped part of the link
+    assert_eq!(at(1, 9), Some("https://example.com/a/b/c"));
+    assert_eq!(at(0, 4), None);
+    assert_eq!(at(1, 10), None);
+}
+
+#[test]
+fn urls_reach_the_handler_as_they_are() {
+    let url = "https://example.com/?a=1&calc|b^c";
+    let hints = hints_for(&format!("see {}", url));
+    assert_eq!(hints.hints()[0].pick, HintPick::Open(url.into()));
+    let target = open_target(url);
+    assert_eq!(target, url);
+    #[cfg(not(windows))]
+    {
+        let command = adamant::hints::open_command(&target);
+        assert_eq!(command.get_args().collect::<Vec<_>>(), [target.as_os_str()]);
+    }
+}
+
+#[test]
+fn plain_numbers_are_not_hashes() {
+    assert!(hints_for("pid 12345678 exited").is_empty());
+}
+
+#[test]
+fn columns_account_for_wide_characters() {
+    let hints = hints_for("日本 https://example.jp");
+    let hint = &hints.hints()[0];
+    assert_eq!((hint.col, hint.width), (5, 18));
+}
+
+#[test]
+fn same_text_gets_the_same_label() {
+    let hints = hints_for("/tmp/a /tmp/b\r\n/tmp/a");
+    let labels: Vec<&str> = hints
+        .hints()
+        .iter()
+        .map(|hint| hint.label.as_str())
+        .collect();
+    assert_eq!(labels[0], labels[2]);
+    assert_ne!(labels[0], labels[1]);
+    // The latest output gets the first label
+    assert_eq!(labels[2], "a");
+}
+
+#[test]
+fn labels_grow_when_the_alphabet_runs_out() {
+    let config = HintsConfig {
+        alphabet: "ab",
+        ..HintsConfig::default()
+    };
+    let hints = hints_with(&config, "/a /b /c /d /e");
+    let labels: Vec<&str> = hints
+        .hints()
+        .iter()
+        .map(|hint| hint.label.as_str())
+        .collect();
+    assert_eq!(labels, vec!["baa", "abb", "aba", "aab", "aaa"]);
+}
+
+#[test]
+fn typing_a_label_picks_the_hint() {
+    let config = HintsConfig {
+        alphabet: "ab",
+        ..HintsConfig::default()
+    };
+    let mut hints = hints_with(&config, "https://x.org /etc/hosts /tmp");
+    let label = hints.hints()[1].label.clone();
+    let mut chars = label.chars();
+    let first = chars.next().unwrap();
+    assert_eq!(hints.input(first), None);
+    assert_eq!(hints.typed(), first.to_string());
+    assert!(hints.candidates().all(|hint| hint.label.starts_with(first)));
+    assert_eq!(
+        hints.input(chars.next().unwrap()),
+        Some(HintPick::Copy("/etc/hosts".to_string()))
+    );
+}
+
+#[test]
+fn upper_case_label_pastes() {
+    let mut hints = hints_for("https://x.org");
+    assert_eq!(
+        hints.input('A'),
+        Some(HintPick::Paste("https://x.org".to_string()))
+    );
+}
+
+#[test]
+fn keys_outside_the_labels_are_ignored() {
+    let mut hints = hints_for("/a /b");
+    assert_eq!(hints.input('z'), None);
+    assert_eq!(hints.input('1'), None);
+    assert_eq!(hints.typed(), "");
+    hints.backspace();
+    assert_eq!(hints.typed(), "");
+}
+
+#[test]
+fn custom_patterns_and_bad_regexes() {
+    let config = HintsConfig {
+        patterns: vec![HintPattern {
+            name: "ticket",
+            regex: r"\bPROJ-\d+\b",
+            action: HintAction::Paste,
+        }],
+        ..HintsConfig::default()
+    };
+    let hints = hints_with(&config, "fixes PROJ-123 and PROJ-4");
+    assert_eq!(
+        matches(&hints),
+        vec![("ticket", "PROJ-123"), ("ticket", "PROJ-4")]
+    );
+
+    let broken = HintsConfig {
+        patterns: vec![HintPattern {
+            name: "broken",
+            regex: "(",
+            action: HintAction::Copy,
+        }],
+        ..HintsConfig::default()
+    };
+    assert!(HintMatcher::new(&broken).is_err());
+}
+
+#[test]
+fn uuids_and_ip_addresses() {
+    let hints = hints_for("id 123e4567-e89b-12d3-a456-426614174000 from 10.0.0.12:8080");
+    assert_eq!(
+        matches(&hints),
+        vec![
+            ("uuid", "123e4567-e89b-12d3-a456-426614174000"),
+            ("ip", "10.0.0.12:8080"),
+        ]
+    );
+}
+
+#[test]
+fn file_positions_open_in_the_editor() {
+    let hints = hints_for(
+        "  --> src/main.rs:12:5\r\nlib.c:7: warning\r\nsee ./docs/guide.md and example.com",
+    );
+    assert_eq!(
+        matches(&hints),
+        vec![
+            ("file-line", "src/main.rs:12:5"),
+            ("file-line", "lib.c:7"),
+            ("path", "./docs/guide.md"),
+        ]
+    );
+    assert_eq!(
+        hints.hints()[0].pick,
+        HintPick::Edit {
+            path: "src/main.rs".into(),
+            line: Some(12),
+            column: Some(5),
+        }
+    );
+    assert_eq!(
+        hints.hints()[1].pick,
+        HintPick::Edit {
+            path: "lib.c".into(),
+            line: Some(7),
+            column: None,
+        }
+    );
+}
+
+#[test]
+fn editors_get_the_position_their_way() {
+    let args = |editor| editor_args(editor, "a.rs", Some(3), Some(9));
+    assert_eq!(args("nvim"), ["+call cursor(3,9)", "a.rs"]);
+    assert_eq!(args("nano"), ["+3,9", "a.rs"]);
+    assert_eq!(args("emacsclient"), ["+3:9", "a.rs"]);
+    assert_eq!(args("hx"), ["a.rs:3:9"]);
+    assert_eq!(args("ed"), ["+3", "a.rs"]);
+    assert_eq!(editor_args("vim", "a.rs", None, None), ["a.rs"]);
+}
+
+/// A config with only `pattern`.
+fn only(pattern: HintPattern) -> HintsConfig {
+    HintsConfig {
+        patterns: vec![pattern],
+        ..HintsConfig::default()
+    }
+}
+
+#[test]
+fn url_templates_use_capture_groups() {
+    let config = only(HintPattern {
+        name: "ticket",
+        regex: r"\b(?P<project>[A-Z]{2,})-(?P<id>\d+)\b",
+        action: HintAction::OpenUrl("https://tracker.example.com/${project}/issues/${id}"),
+    });
+    let hints = hints_with(&config, "fixes ADM-42");
+    assert_eq!(
+        hints.hints()[0].pick,
+        HintPick::Open("https://tracker.example.com/ADM/issues/42".into())
+    );
+}
+
+#[test]
+fn url_templates_encode_the_groups() {
+    let config = only(HintPattern {
+        name: "search",
+        regex: r"find: (?P<query>\S+)",
+        action: HintAction::OpenUrl("https://search.example.com/?q=${query}&cost=$$1"),
+    });
+    let hints = hints_with(&config, "find: a&calc|b^c=d/é");
+    assert_eq!(
+        hints.hints()[0].pick,
+        HintPick::Open(
+            "https://search.example.com/?q=a%26calc%7Cb%5Ec%3Dd%2F%C3%A9&cost=$1".into()
+        )
+    );
+}
+
+#[test]
+fn commands_get_the_capture_groups_as_arguments() {
+    let config = only(HintPattern {
+        name: "pid",
+        regex: r"pid (\d+)",
+        action: HintAction::Run(&["kill", "-TERM", "$1"]),
+    });
+    let hints = hints_with(&config, "worker pid 4242 hung");
+    assert_eq!(
+        hints.hints()[0].pick,
+        HintPick::Run(vec!["kill".into(), "-TERM".into(), "4242".into()])
+    );
+}
+
+#[test]
+fn text_group_narrows_the_hint() {
+    let config = only(HintPattern {
+        name: "token",
+        regex: r"token=(?P<text>\w+)",
+        action: HintAction::Copy,
+    });
+    let hints = hints_with(&config, "login token=abc123 ok");
+    let hint = &hints.hints()[0];
+    assert_eq!((hint.text.as_str(), hint.col), ("abc123", 12));
+    assert_eq!(hint.pick, HintPick::Copy("abc123".into()));
+}
+
+#[test]
+fn hint_under_a_cell() {
+    let hints = hints_for("see /etc/hosts and /tmp");
+    assert_eq!(hints.hint_at(0, 3), None);
+    assert_eq!(hints.hint_at(0, 4).unwrap().text, "/etc/hosts");
+    assert_eq!(hints.hint_at(0, 13).unwrap().text, "/etc/hosts");
+    assert_eq!(hints.hint_at(0, 14), None);
+    assert_eq!(hints.hint_at(0, 19).unwrap().text, "/tmp");
+}
+
+#[test]
+fn run_without_a_program_is_rejected() {
+    let config = only(HintPattern {
+        name: "nothing",
+        regex: "x",
+        action: HintAction::Run(&[]),
+    });
+    assert!(HintMatcher::new(&config).is_err());
+}
@@ -0,0 +1,82 @@
+//! Hooks: finding palette commands by name and reporting lines of output
+//! for `on_output`.
+
+#![cfg(feature = "gui")]
+
+use adamant::commands::{Action, command_named};
+use adamant::config::{
+    CommandAction, Config, HookAction, Hooks, UserCommand, WatchAction, WatchRule,
+};
+use adamant::term::Terminal;
+use adamant::watch::{WatchHit, WatchMatcher};
+
+#[test]
+fn hooks_run_palette_commands_by_name() {
+    let config = Config {
+        commands: vec![UserCommand {
+            name: "Deploy",
+            action: CommandAction::Input("make deploy\r"),
+        }],
+        ..Config::default()
+    };
+    assert_eq!(
+        command_named(&config, "Toggle read-only"),
+        Some(Action::ReadOnly)
+    );
+    assert_eq!(command_named(&config, "Deploy"), Some(Action::User(0)));
+    assert_eq!(command_named(&config, "toggle read-only"), None);
+}
+
+#[test]
+fn output_lines_are_reported_with_rule_hits() {
+    let rules = [WatchRule {
+        name: "warning",
+        regex: "^warning",
+        action: WatchAction::Notify,
+    }];
+    let mut matcher = WatchMatcher::new(&[]).unwrap();
+    assert!(matcher.is_empty());
+    matcher.report_lines(true);
+    assert!(!matcher.is_empty());
+
+    let mut matcher = WatchMatcher::new(&rules).unwrap();
+    matcher.report_lines(true);
+    let mut term = Terminal::new(10, 5, 100);
+    term.feed(b"warning: 0123\r\n\r\ndone\r\nnext");
+    assert_eq!(
+        matcher.scan(term.grid_mut()),
+        [
+            WatchHit::Line("warning: 0123".into()),
+            WatchHit::Notify {
+                rule: "warning",
+                line: "warning: 0123".into()
+            },
+            WatchHit::Line("done".into()),
+        ]
+    );
+}
+
+#[test]
+fn hooks_answer_with_actions() {
+    let hooks = Hooks {
+        on_focus_change: Some(|focused| {
+            if focused {
+                Vec::new()
+            } else {
+                vec![HookAction::Command("Watch for activity".into())]
+            }
+        }),
+        ..Hooks::default()
+    };
+    let on_focus_change = hooks.on_focus_change.unwrap();
+    assert!(on_focus_change(true).is_empty());
+    let config = Config {
+        hooks,
+        ..Config::default()
+    };
+    let [HookAction::Command(name)] = &on_focus_change(false)[..] else {
+        panic!("expected a command");
+    };
+    assert!(command_named(&config, name).is_some());
+    assert!(config.hooks.on_startup.is_none());
+}
@@ -0,0 +1,55 @@
+//! Hotkeys: parsing the configured combination, and matching key presses
+//! for plugin shortcuts.
+
+#![cfg(feature = "gui")]
+
+use adamant::hotkey::{Hotkey, HotkeyKey};
+use winit::keyboard::ModifiersState;
+
+#[test]
+fn modifiers_have_aliases_and_ignore_case() {
+    let hotkey = Hotkey::parse("control+SHIFT+Option+cmd+Space").unwrap();
+    assert_eq!(hotkey.modifiers, ModifiersState::all());
+    assert_eq!(hotkey.key, HotkeyKey::Space);
+    assert_eq!(
+        Hotkey::parse("Win + grave").unwrap().modifiers,
+        ModifiersState::SUPER
+    );
+}
+
+#[test]
+fn letters_digits_and_function_keys() {
+    assert_eq!(Hotkey::parse("Ctrl+T").unwrap().key, HotkeyKey::Char('t'));
+    assert_eq!(Hotkey::parse("Alt+1").unwrap().key, HotkeyKey::Char('1'));
+    assert_eq!(Hotkey::parse("f1").unwrap().key, HotkeyKey::F(1));
+    assert_eq!(Hotkey::parse("f").unwrap().key, HotkeyKey::Char('f'));
+    assert_eq!(Hotkey::parse("Ctrl+Shift+Up").unwrap().key, HotkeyKey::Up);
+    assert_eq!(Hotkey::parse("left").unwrap().key, HotkeyKey::Left);
+}
+
+#[test]
+fn unknown_keys_are_rejected() {
+    for text in ["", "Ctrl+", "F13", "F0", "Ctrl+é", "Ctrl+Enter", "Meta+x"] {
+        assert!(Hotkey::parse(text).is_err(), "{:?}", text);
+    }
+}
+
+#[test]
+fn hotkeys_match_key_presses() {
+    use winit::keyboard::{Key, NamedKey};
+
+    let hotkey = Hotkey::parse("Ctrl+Shift+h").unwrap();
+    let ctrl_shift = ModifiersState::CONTROL | ModifiersState::SHIFT;
+    assert!(hotkey.matches(ctrl_shift, &Key::Character("H".into())));
+    assert!(!hotkey.matches(ModifiersState::CONTROL, &Key::Character("h".into())));
+    assert!(!hotkey.matches(ctrl_shift, &Key::Character("j".into())));
+
+    let f12 = Hotkey::parse("F12").unwrap();
+    assert!(f12.matches(ModifiersState::empty(), &Key::Named(NamedKey::F12)));
+    assert!(!f12.matches(ModifiersState::empty(), &Key::Named(NamedKey::F1)));
+    let space = Hotkey::parse("Alt+space").unwrap();
+    assert!(space.matches(ModifiersState::ALT, &Key::Named(NamedKey::Space)));
+    let right = Hotkey::parse("Ctrl+Shift+Right").unwrap();
+    assert!(right.matches(ctrl_shift, &Key::Named(NamedKey::ArrowRight)));
+    assert!(!right.matches(ctrl_shift, &Key::Named(NamedKey::ArrowLeft)));
+}
@@ -0,0 +1,44 @@
+//! OSC 8 hyperlinks: which cells link where.
+
+use adamant::term::Terminal;
+
+/// The link target of the first `cols` cells of the first row.
+fn targets(term: &Terminal, cols: usize) -> Vec<Option<&str>> {
+    (0..cols)
+        .map(|col| term.hyperlink(term.grid().visible_row(0)[col].link))
+        .collect()
+}
+
+#[test]
+fn text_between_the_sequences_links_to_the_target() {
+    let mut term = Terminal::new(20, 2, 0);
+    term.feed(b"see \x1b]8;;https://example.com/\x1b\\docs\x1b]8;;\x1b\\ now");
+    let link = Some("https://example.com/");
+    assert_eq!(
+        targets(&term, 10),
+        [None, None, None, None, link, link, link, link, None, None]
+    );
+}
+
+#[test]
+fn links_survive_attribute_resets_and_cover_wide_characters() {
+    let mut term = Terminal::new(20, 2, 0);
+    term.feed("\x1b]8;;file:///tmp\x07\x1b[1ma\x1b[m日\x1b]8;;\x07".as_bytes());
+    let link = Some("file:///tmp");
+    assert_eq!(targets(&term, 4), [link, link, link, None]);
+}
+
+#[test]
+fn same_id_and_target_is_the_same_link() {
+    let mut term = Terminal::new(20, 2, 0);
+    term.feed(b"\x1b]8;id=1;https://a.io\x1b\\a\x1b]8;;\x1b\\ ");
+    term.feed(b"\x1b]8;id=2;https://a.io\x1b\\b\x1b]8;;\x1b\\ ");
+    term.feed(b"\x1b]8;id=1;https://a.io\x1b\\c\x1b]8;;\x1b\\");
+    let row = term.grid().visible_row(0);
+    let links: Vec<u16> = [0, 2, 4].iter().map(|&col| row[col].link).collect();
+    assert_eq!(links[0], links[2]);
+    assert_ne!(links[0], links[1]);
+    // Erasing takes the link away
+    term.feed(b"\x1b[2J");
+    assert_eq!(term.grid().visible_row(0)[0].link, 0);
+}
@@ -0,0 +1,112 @@
+//! The IPC socket: parsing `exec` and the queries, and commands and
+//! answers going through a listening socket.
+
+#![cfg(unix)]
+
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::sync::{Arc, Mutex};
+
+use adamant::ipc::{IpcCommand, IpcQuery, IpcServer};
+
+#[test]
+fn exec_takes_a_program_and_its_arguments() {
+    assert_eq!(
+        IpcCommand::parse("exec htop"),
+        Ok(IpcCommand::Exec {
+            program: "htop".into(),
+            args: Vec::new(),
+        })
+    );
+    // Quoted like a shell
+    assert_eq!(
+        IpcCommand::parse(r#"exec sh -c 'echo "a b"' "c\"d" e\ f"#),
+        Ok(IpcCommand::Exec {
+            program: "sh".into(),
+            args: vec![
+                "-c".into(),
+                "echo \"a b\"".into(),
+                "c\"d".into(),
+                "e f".into()
+            ],
+        })
+    );
+    for bad in ["exec", "exec   ", "exec 'htop", "exec htop\\"] {
+        assert!(IpcCommand::parse(bad).is_err(), "{:?}", bad);
+    }
+}
+
+#[test]
+fn queries_take_no_arguments() {
+    assert_eq!(
+        IpcCommand::parse("get-text"),
+        Ok(IpcCommand::Query(IpcQuery::Text))
+    );
+    assert_eq!(
+        IpcCommand::parse("  get-cursor  "),
+        Ok(IpcCommand::Query(IpcQuery::Cursor))
+    );
+    assert_eq!(
+        IpcCommand::parse("get-selection"),
+        Ok(IpcCommand::Query(IpcQuery::Selection))
+    );
+    assert_eq!(
+        IpcCommand::parse("get-unhandled"),
+        Ok(IpcCommand::Query(IpcQuery::Unhandled))
+    );
+    for bad in ["get-texts", "text", ""] {
+        assert!(IpcCommand::parse(bad).is_err(), "{:?}", bad);
+    }
+}
+
+#[test]
+fn the_socket_passes_commands_on_and_answers_queries() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let server = IpcServer::bind({
+        let received = Arc::clone(&received);
+        move |command, reply| {
+            if command == IpcCommand::Query(IpcQuery::Text) {
+                reply.send(vec!["$ ls".into(), "a  b".into()]);
+            }
+            // Dropping the reply leaves other queries unanswered
+            received.lock().unwrap().push(command);
+        }
+    })
+    .unwrap();
+    assert_eq!(
+        adamant::ipc::active_socket().as_deref(),
+        Some(server.path())
+    );
+
+    let stream = UnixStream::connect(server.path()).unwrap();
+    let mut writer = stream.try_clone().unwrap();
+    let mut lines = BufReader::new(stream).lines();
+    let mut ask = |line: &str, answer: usize| {
+        writeln!(writer, "{}", line).unwrap();
+        (0..answer)
+            .map(|_| lines.next().unwrap().unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(ask("exec vim 'a file'", 1), ["ok"]);
+    assert_eq!(ask("get-text", 3), ["ok 2", "$ ls", "a  b"]);
+    assert_eq!(ask("get-cursor", 1), ["error: no answer"]);
+    // Blank lines are skipped, bad ones never reach the handler
+    assert_eq!(ask("\nexec", 1), ["error: exec: missing program"]);
+    assert_eq!(
+        *received.lock().unwrap(),
+        [
+            IpcCommand::Exec {
+                program: "vim".into(),
+                args: vec!["a file".into()],
+            },
+            IpcCommand::Query(IpcQuery::Text),
+            IpcCommand::Query(IpcQuery::Cursor),
+        ]
+    );
+
+    let path = server.path().to_path_buf();
+    drop(server);
+    assert!(!path.exists());
+    assert_eq!(adamant::ipc::active_socket(), None);
+}
@@ -0,0 +1,282 @@
+//! Keyboard translation for international layouts.
+//!
+//! Each case replays the key events the platform delivers for one way of
+//! typing a character (dead keys, compose sequences, AltGr, Option) and
+//! checks that exactly the composed text reaches the PTY. The numeric
+//! keypad is checked in both keypad modes (DECKPAM / DECKPNM).
+
+#![cfg(feature = "gui")]
+
+use adamant::config::Keypad;
+use adamant::input::{KeyInput, key_input_to_bytes};
+use adamant::term::Mode;
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+
+/// One key press.
+struct Press {
+    key: Key,
+    text: Option<&'static str>,
+    unmodified: Option<Key>,
+    mods: ModifiersState,
+}
+
+/// A key that produces `text` without modifiers.
+fn char_key(text: &'static str) -> Press {
+    Press {
+        key: Key::Character(text.into()),
+        text: Some(text),
+        unmodified: Some(Key::Character(text.into())),
+        mods: ModifiersState::empty(),
+    }
+}
+
+/// A dead key: it only arms the next key and types nothing itself.
+fn dead_key(accent: char) -> Press {
+    Press {
+        key: Key::Dead(Some(accent)),
+        text: None,
+        unmodified: Some(Key::Dead(Some(accent))),
+        mods: ModifiersState::empty(),
+    }
+}
+
+/// The key that completes a dead key or compose sequence: `base` on the
+/// layout, `composed` as the resulting text.
+fn composing(base: &'static str, composed: &'static str) -> Press {
+    Press {
+        key: Key::Character(composed.into()),
+        text: Some(composed),
+        unmodified: Some(Key::Character(base.into())),
+        mods: ModifiersState::empty(),
+    }
+}
+
+/// `base` pressed with `mods`, producing `produced` on the layout.
+fn with_mods(base: &'static str, produced: &'static str, mods: ModifiersState) -> Press {
+    Press {
+        key: Key::Character(produced.into()),
+        text: Some(produced),
+        unmodified: Some(Key::Character(base.into())),
+        mods,
+    }
+}
+
+/// Everything the presses send, concatenated.
+fn typed(presses: &[Press]) -> Vec<u8> {
+    presses
+        .iter()
+        .filter_map(|press| {
+            let input = KeyInput {
+                key: &press.key,
+                text: press.text,
+                unmodified: press.unmodified.as_ref(),
+                numpad: false,
+            };
+            key_input_to_bytes(&input, press.mods, Mode::default(), Keypad::NumLock)
+        })
+        .flatten()
+        .collect()
+}
+
+const ALT_GR: ModifiersState = ModifiersState::CONTROL.union(ModifiersState::ALT);
+
+#[test]
+fn dead_key_sends_nothing_by_itself() {
+    assert_eq!(typed(&[dead_key('´')]), b"");
+}
+
+#[test]
+fn us_international_acute() {
+    assert_eq!(typed(&[dead_key('´'), composing("e", "é")]), "é".as_bytes());
+}
+
+#[test]
+fn spanish_tilde() {
+    assert_eq!(typed(&[dead_key('~'), composing("n", "ñ")]), "ñ".as_bytes());
+}
+
+#[test]
+fn german_umlaut_key_and_diaeresis() {
+    assert_eq!(typed(&[char_key("ü")]), "ü".as_bytes());
+    assert_eq!(typed(&[dead_key('¨'), composing("u", "ü")]), "ü".as_bytes());
+}
+
+#[test]
+fn dead_key_then_space_types_the_accent() {
+    assert_eq!(typed(&[dead_key('^'), composing(" ", "^")]), b"^");
+}
+
+#[test]
+fn dead_key_before_a_letter_it_cannot_combine_with() {
+    assert_eq!(
+        typed(&[dead_key('´'), composing("x", "´x")]),
+        "´x".as_bytes()
+    );
+}
+
+#[test]
+fn compose_sequence_euro() {
+    // Compose, =, e: only the last key carries text
+    let compose = Press {
+        key: Key::Named(NamedKey::Compose),
+        text: None,
+        unmodified: Some(Key::Named(NamedKey::Compose)),
+        mods: ModifiersState::empty(),
+    };
+    let equals = Press {
+        text: None,
+        ..char_key("=")
+    };
+    assert_eq!(
+        typed(&[compose, equals, composing("e", "€")]),
+        "€".as_bytes()
+    );
+}
+
+#[test]
+fn alt_gr_on_windows_is_not_ctrl_alt() {
+    // German layout: AltGr+E = €, AltGr+Q = @
+    assert_eq!(typed(&[with_mods("e", "€", ALT_GR)]), "€".as_bytes());
+    assert_eq!(typed(&[with_mods("q", "@", ALT_GR)]), b"@");
+}
+
+#[test]
+fn option_on_macos_types_characters() {
+    let mods = ModifiersState::ALT | ModifiersState::SHIFT;
+    assert_eq!(typed(&[with_mods("2", "€", mods)]), "€".as_bytes());
+}
+
+#[test]
+fn ctrl_and_alt_combinations_still_work() {
+    assert_eq!(
+        typed(&[with_mods("c", "c", ModifiersState::CONTROL)]),
+        b"\x03"
+    );
+    assert_eq!(typed(&[with_mods("b", "b", ModifiersState::ALT)]), b"\x1bb");
+    assert_eq!(typed(&[with_mods("a", "a", ALT_GR)]), b"\x1b\x01");
+}
+
+/// What a keypad key sends with no modifiers: its text (NumLock on) or
+/// its named key (NumLock off), in application keypad mode or not.
+fn keypad(key: Key, application: bool, keypad: Keypad) -> Option<Vec<u8>> {
+    let text = match &key {
+        Key::Character(text) => Some(text.as_str()),
+        Key::Named(NamedKey::Enter) => Some("\r"),
+        _ => None,
+    };
+    let input = KeyInput {
+        key: &key,
+        text,
+        unmodified: Some(&key),
+        numpad: true,
+    };
+    let mode = if application {
+        Mode::APP_KEYPAD
+    } else {
+        Mode::default()
+    };
+    key_input_to_bytes(&input, ModifiersState::empty(), mode, keypad)
+}
+
+#[test]
+fn numeric_keypad_mode_types_digits() {
+    let digit = || Key::Character("7".into());
+    assert_eq!(
+        keypad(digit(), false, Keypad::Application),
+        Some(b"7".to_vec())
+    );
+    assert_eq!(
+        keypad(Key::Named(NamedKey::Enter), false, Keypad::Application),
+        Some(b"\r".to_vec())
+    );
+    assert_eq!(
+        keypad(Key::Named(NamedKey::Home), false, Keypad::Application),
+        Some(b"\x1b[H".to_vec())
+    );
+}
+
+#[test]
+fn application_keypad_sends_ss3() {
+    let key = |text: &str| Key::Character(text.into());
+    assert_eq!(
+        keypad(Key::Named(NamedKey::Enter), true, Keypad::NumLock),
+        Some(b"\x1bOM".to_vec())
+    );
+    assert_eq!(
+        keypad(key("+"), true, Keypad::NumLock),
+        Some(b"\x1bOk".to_vec())
+    );
+    assert_eq!(
+        keypad(key("/"), true, Keypad::NumLock),
+        Some(b"\x1bOo".to_vec())
+    );
+    // NumLock on: the digits still type themselves
+    assert_eq!(keypad(key("7"), true, Keypad::NumLock), Some(b"7".to_vec()));
+    assert_eq!(keypad(key("."), true, Keypad::NumLock), Some(b".".to_vec()));
+    // NumLock off: they move the cursor
+    assert_eq!(
+        keypad(Key::Named(NamedKey::ArrowUp), true, Keypad::NumLock),
+        Some(b"\x1b[A".to_vec())
+    );
+}
+
+#[test]
+fn vt100_keypad_ignores_numlock() {
+    let key = |text: &str| Key::Character(text.into());
+    assert_eq!(
+        keypad(key("0"), true, Keypad::Application),
+        Some(b"\x1bOp".to_vec())
+    );
+    assert_eq!(
+        keypad(key("9"), true, Keypad::Application),
+        Some(b"\x1bOy".to_vec())
+    );
+    assert_eq!(
+        keypad(key("."), true, Keypad::Application),
+        Some(b"\x1bOn".to_vec())
+    );
+    assert_eq!(
+        keypad(Key::Named(NamedKey::Home), true, Keypad::Application),
+        Some(b"\x1bOw".to_vec())
+    );
+    assert_eq!(
+        keypad(Key::Named(NamedKey::Delete), true, Keypad::Application),
+        Some(b"\x1bOn".to_vec())
+    );
+}
+
+#[test]
+fn keys_off_the_keypad_and_ctrl_combinations_are_unchanged() {
+    let seven = Key::Character("7".into());
+    let input = KeyInput {
+        key: &seven,
+        text: Some("7"),
+        unmodified: Some(&seven),
+        numpad: false,
+    };
+    assert_eq!(
+        key_input_to_bytes(
+            &input,
+            ModifiersState::empty(),
+            Mode::APP_KEYPAD,
+            Keypad::Application
+        ),
+        Some(b"7".to_vec())
+    );
+    let plus = Key::Character("+".into());
+    let input = KeyInput {
+        key: &plus,
+        text: Some("+"),
+        unmodified: Some(&plus),
+        numpad: true,
+    };
+    assert_eq!(
+        key_input_to_bytes(
+            &input,
+            ModifiersState::ALT,
+            Mode::APP_KEYPAD,
+            Keypad::NumLock
+        ),
+        Some(b"\x1b+".to_vec())
+    );
+}
@@ -0,0 +1,105 @@
+//! Launch profiles: applying one to the configuration, and the entries
+//! opening windows with them.
+
+use adamant::config::{Colors, Config, LaunchProfile, Rgb};
+
+fn config() -> Config {
+    Config {
+        launch_profiles: vec![
+            LaunchProfile {
+                name: "work",
+                command: &["ssh", "work.example.com"],
+                directory: Some("~/work"),
+                env: &[("AWS_PROFILE", "work")],
+                colors: Some(Colors {
+                    background: Rgb(0, 0, 0x30),
+                    ..Colors::default()
+                }),
+                ..LaunchProfile::default()
+            },
+            LaunchProfile {
+                name: "notes",
+                ..LaunchProfile::default()
+            },
+        ],
+        ..Config::default()
+    }
+}
+
+#[test]
+fn a_profile_replaces_what_it_sets() {
+    let mut config = config();
+    config.shell.env = &[("EDITOR", "vi")];
+    config.use_launch_profile("work").unwrap();
+    assert_eq!(config.shell.program, Some("ssh"));
+    assert_eq!(config.shell.args, ["work.example.com"]);
+    assert!(!config.shell.login);
+    assert_eq!(config.shell.directory, Some("~/work"));
+    assert_eq!(
+        config.shell.env,
+        [("EDITOR", "vi"), ("AWS_PROFILE", "work")]
+    );
+    assert_eq!(config.colors.background, Rgb(0, 0, 0x30));
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn an_empty_profile_keeps_the_configuration() {
+    let mut config = config();
+    config.use_launch_profile("notes").unwrap();
+    let default = Config::default();
+    assert_eq!(config.shell.program, default.shell.program);
+    assert_eq!(config.shell.login, default.shell.login);
+    assert_eq!(config.shell.directory, None);
+    assert_eq!(config.colors.background, default.colors.background);
+    assert!(config.use_launch_profile("play").is_err());
+}
+
+#[test]
+fn profiles_need_distinct_usable_names() {
+    for name in ["", "--hold", "notes"] {
+        let mut config = config();
+        config.launch_profiles.push(LaunchProfile {
+            name,
+            ..LaunchProfile::default()
+        });
+        assert!(config.validate().is_err(), "{:?}", name);
+    }
+    let mut config = config();
+    config.launch_profiles[1].command = &[""];
+    assert!(config.validate().is_err());
+}
+
+#[cfg(feature = "gui")]
+#[test]
+fn the_palette_and_menu_open_windows_with_them() {
+    use adamant::commands::{Action, commands};
+    use adamant::menu::{MenuItem, menu_bar};
+
+    let config = config();
+    let palette: Vec<_> = commands(&config)
+        .into_iter()
+        .filter(|command| matches!(command.action, Action::LaunchProfile(_)))
+        .map(|command| (command.action, command.name))
+        .collect();
+    assert_eq!(
+        palette,
+        [
+            (Action::LaunchProfile(0), "New window: work"),
+            (Action::LaunchProfile(1), "New window: notes"),
+        ]
+    );
+
+    let shell = menu_bar(&config)
+        .into_iter()
+        .find(|menu| menu.title == "Shell")
+        .unwrap();
+    assert!(shell.items.iter().any(|item| matches!(
+        item,
+        MenuItem::Action {
+            action: Action::LaunchProfile(1),
+            title: "New window: notes",
+            ..
+        }
+    )));
+}
@@ -0,0 +1,42 @@
+//! Ligatures: which cells each covers, and the slice of its image each of
+//! them draws.
+
+#![cfg(feature = "gui")]
+
+use adamant::ligatures::{Ligature, LigatureMap};
+
+#[test]
+fn columns_map_to_their_ligature() {
+    // `a != b => c`
+    let map = LigatureMap::new(vec![
+        Ligature { col: 2, len: 2 },
+        Ligature { col: 7, len: 2 },
+    ]);
+    let ligature = |col| map.at(col).map(|(ligature, index)| (ligature.col, index));
+    assert_eq!(ligature(0), None);
+    assert_eq!(ligature(2), Some((2, 0)));
+    assert_eq!(ligature(3), Some((2, 1)));
+    assert_eq!(ligature(4), None);
+    assert_eq!(ligature(8), Some((7, 1)));
+    assert_eq!(ligature(9), None);
+    assert_eq!(LigatureMap::default().at(0), None);
+}
+
+#[test]
+fn each_cell_draws_the_part_above_it() {
+    let arrow = Ligature { col: 0, len: 3 };
+    // An image from 2 to 28 pixels over three 10 pixel cells
+    assert_eq!(arrow.slice(0, 10, 2, 26), 0..8);
+    assert_eq!(arrow.slice(1, 10, 2, 26), 8..18);
+    assert_eq!(arrow.slice(2, 10, 2, 26), 18..26);
+}
+
+#[test]
+fn the_ends_draw_what_sticks_out() {
+    let arrow = Ligature { col: 0, len: 2 };
+    // From 3 pixels left of the first cell to 2 right of the last
+    assert_eq!(arrow.slice(0, 10, -3, 25), 0..13);
+    assert_eq!(arrow.slice(1, 10, -3, 25), 13..25);
+    // A cell the image doesn't reach draws nothing
+    assert!(arrow.slice(1, 10, 0, 8).is_empty());
+}
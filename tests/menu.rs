@@ -0,0 +1,51 @@
+//! Menu bar: the menus and their key equivalents.
+
+#![cfg(feature = "gui")]
+
+use std::collections::HashSet;
+
+use adamant::commands::commands;
+use adamant::config::Config;
+use adamant::menu::{MenuItem, menu_bar};
+
+#[test]
+fn menu_actions_are_palette_actions() {
+    let palette: Vec<_> = commands(&Config::default())
+        .into_iter()
+        .map(|command| command.action)
+        .collect();
+    for menu in menu_bar(&Config::default()) {
+        for item in &menu.items {
+            if let MenuItem::Action { action, title, .. } = item {
+                assert!(palette.contains(action), "{} is not in the palette", title);
+            }
+        }
+    }
+}
+
+#[test]
+fn key_equivalents_are_unique_and_use_command() {
+    let mut seen = HashSet::new();
+    for menu in menu_bar(&Config::default()) {
+        for item in &menu.items {
+            let key = match item {
+                MenuItem::Action { key, .. } | MenuItem::System { key, .. } => key,
+                _ => continue,
+            };
+            if let Some(key) = key {
+                assert!(key.modifiers.super_key());
+                assert!(seen.insert((key.key, key.modifiers)), "{:?} twice", key);
+            }
+        }
+    }
+    assert!(seen.len() >= 10);
+}
+
+#[test]
+fn only_the_window_menu_lists_windows() {
+    let menus = menu_bar(&Config::default());
+    let windows: Vec<_> = menus.iter().filter(|menu| menu.windows).collect();
+    assert_eq!(windows.len(), 1);
+    assert_eq!(windows[0].title, "Window");
+    assert_eq!(menus[0].title, "Adamant");
+}
@@ -0,0 +1,86 @@
+//! The minimap: which lines fit it, how each is summarized, and where
+//! clicking it scrolls to.
+
+#![cfg(feature = "gui")]
+
+use adamant::grid::{Color, Grid};
+use adamant::minimap::{Minimap, MinimapLine};
+use adamant::term::Terminal;
+
+/// A 4-row screen with `history` lines of scrollback.
+fn grid_with_history(history: usize) -> Grid {
+    let mut grid = Grid::new(10, 4, 1000);
+    for _ in 0..history {
+        grid.scroll_up(1);
+    }
+    grid
+}
+
+#[test]
+fn short_scrollback_is_shown_whole() {
+    let mut grid = grid_with_history(6);
+    let minimap = Minimap::new(&grid, 100);
+    assert_eq!((minimap.first(), minimap.view()), (0, (6, 10)));
+    grid.scroll_display(6);
+    assert_eq!(Minimap::new(&grid, 100).view(), (0, 4));
+}
+
+#[test]
+fn long_scrollback_is_shown_around_the_view() {
+    let mut grid = grid_with_history(96);
+    // 100 lines on 20 pixel rows
+    assert_eq!(Minimap::new(&grid, 20).first(), 80);
+    grid.scroll_display(96);
+    assert_eq!(Minimap::new(&grid, 20).first(), 0);
+    grid.scroll_display(-48);
+    let minimap = Minimap::new(&grid, 20);
+    assert_eq!(minimap.first(), 40);
+    assert_eq!(minimap.view(), (8, 12));
+}
+
+#[test]
+fn lines_have_their_length_and_most_common_color() {
+    let mut term = Terminal::new(10, 4, 100);
+    term.feed(b"abcde\r\n\r\n\x1b[31mred\x1b[32mgreen\x1b[m\r\n\x1b[44m  \x1b[mx");
+    let minimap = Minimap::new(term.grid(), 100);
+    let lines: Vec<(usize, f32, Color)> = minimap
+        .lines()
+        .map(|(y, MinimapLine { length, color })| (y, length, color))
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            (0, 0.5, Color::Default),
+            (2, 0.8, Color::Indexed(2)),
+            // Cells with a background show it
+            (3, 0.3, Color::Indexed(4)),
+        ]
+    );
+}
+
+#[test]
+fn clicking_centers_the_line_in_the_view() {
+    let grid = grid_with_history(16);
+    let minimap = Minimap::new(&grid, 100);
+    // Line 10 with 2 lines above it: lines 8 to 11 in view
+    assert_eq!(minimap.offset_at(10), 8);
+    // No further than either end
+    assert_eq!(minimap.offset_at(0), 16);
+    assert_eq!(minimap.offset_at(99), 0);
+}
+
+#[test]
+fn search_matches_are_marked_where_shown() {
+    let mut term = Terminal::new(10, 4, 1000);
+    for line in 0..100 {
+        let text = if line % 30 == 0 { "needle" } else { "hay" };
+        term.feed(format!("{text}\r\n").as_bytes());
+    }
+    let matches = term.grid().search("needle");
+    let lines: Vec<usize> = matches.iter().map(|found| found.line).collect();
+    assert_eq!(lines, [0, 30, 60, 90]);
+    // 101 lines on 60 pixel rows: the last 60, from line 41
+    let minimap = Minimap::new(term.grid(), 60).with_matches(&matches);
+    assert_eq!(minimap.first(), 41);
+    assert_eq!(minimap.matches(), [60 - 41, 90 - 41]);
+}
@@ -0,0 +1,100 @@
+//! Activity and silence monitoring: when each is reported, and turning it
+//! on from the configuration and the IPC socket.
+
+use adamant::config::{Config, LaunchProfile, Monitor};
+
+#[test]
+fn silence_needs_a_positive_interval() {
+    let mut config = Config::default();
+    assert!(config.validate().is_ok());
+    config.silence_ms = 0;
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn launch_profiles_can_watch() {
+    let mut config = Config {
+        monitor: Some(Monitor::Activity),
+        launch_profiles: vec![LaunchProfile {
+            name: "build",
+            monitor: Some(Monitor::Silence),
+            ..LaunchProfile::default()
+        }],
+        ..Config::default()
+    };
+    config.use_launch_profile("build").unwrap();
+    assert_eq!(config.monitor, Some(Monitor::Silence));
+}
+
+#[cfg(unix)]
+#[test]
+fn ipc_starts_and_stops_watching() {
+    use adamant::ipc::IpcCommand;
+
+    assert_eq!(
+        IpcCommand::parse("monitor activity"),
+        Ok(IpcCommand::Monitor(Some(Monitor::Activity)))
+    );
+    assert_eq!(
+        IpcCommand::parse("monitor silence"),
+        Ok(IpcCommand::Monitor(Some(Monitor::Silence)))
+    );
+    assert_eq!(
+        IpcCommand::parse("monitor off"),
+        Ok(IpcCommand::Monitor(None))
+    );
+    for bad in ["monitor", "monitor bell"] {
+        assert!(IpcCommand::parse(bad).is_err(), "{:?}", bad);
+    }
+}
+
+#[cfg(feature = "gui")]
+#[test]
+fn activity_is_output_after_a_pause() {
+    use adamant::monitor::ActivityMonitor;
+    use web_time::{Duration, Instant};
+
+    let start = Instant::now();
+    let at = |seconds| start + Duration::from_secs(seconds);
+    let mut monitor = ActivityMonitor::new(Duration::from_secs(10));
+    monitor.watch(Some(Monitor::Activity));
+    assert_eq!(monitor.watching(), Some(Monitor::Activity));
+
+    // The first output is reported, a steady stream only once
+    assert_eq!(monitor.output(at(1)), Some(Monitor::Activity));
+    for second in 2..30 {
+        assert_eq!(monitor.output(at(second)), None);
+    }
+    assert_eq!(monitor.check(at(35)), None);
+    assert_eq!(monitor.output(at(39)), Some(Monitor::Activity));
+}
+
+#[cfg(feature = "gui")]
+#[test]
+fn silence_is_reported_once_per_pause() {
+    use adamant::monitor::ActivityMonitor;
+    use web_time::{Duration, Instant};
+
+    let start = Instant::now();
+    let at = |seconds| start + Duration::from_secs(seconds);
+    let mut monitor = ActivityMonitor::new(Duration::from_secs(10));
+    monitor.output(start);
+    // Output from before watching doesn't count
+    monitor.watch(Some(Monitor::Silence));
+    assert_eq!(monitor.check(at(20)), None);
+
+    assert_eq!(monitor.output(at(20)), None);
+    assert_eq!(monitor.check(at(29)), None);
+    assert_eq!(monitor.check(at(30)), Some(Monitor::Silence));
+    assert_eq!(monitor.check(at(50)), None);
+
+    // More output starts another pause
+    monitor.output(at(50));
+    assert_eq!(monitor.check(at(60)), Some(Monitor::Silence));
+
+    // Nothing once it stops watching
+    monitor.watch(None);
+    monitor.output(at(60));
+    assert_eq!(monitor.check(at(90)), None);
+    assert_eq!(monitor.output(at(120)), None);
+}
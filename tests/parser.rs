@@ -0,0 +1,110 @@
+//! The escape sequence parser on its own: what reaches a `Perform` for
+//! printable text, controls and sequences, however the bytes are split.
+
+use adamant::parser::{Params, Parser, Perform};
+
+/// Everything the parser dispatched, one entry per callback.
+#[derive(Default)]
+struct Record(Vec<String>);
+
+impl Perform for Record {
+    fn print(&mut self, c: char) {
+        self.0.push(c.to_string());
+    }
+
+    fn execute(&mut self, byte: u8) {
+        self.0.push(format!("C0 {:02x}", byte));
+    }
+
+    fn csi_dispatch(&mut self, params: &Params, intermediates: &[u8], ignore: bool, action: char) {
+        let params: Vec<String> = params
+            .iter()
+            .map(|param| {
+                let values: Vec<String> = param.iter().map(u16::to_string).collect();
+                values.join(":")
+            })
+            .collect();
+        self.0.push(format!(
+            "CSI {}{}{}{}",
+            params.join(";"),
+            String::from_utf8_lossy(intermediates),
+            action,
+            if ignore { " (ignored)" } else { "" }
+        ));
+    }
+
+    fn esc_dispatch(&mut self, intermediates: &[u8], _: bool, byte: u8) {
+        self.0.push(format!(
+            "ESC {}{}",
+            String::from_utf8_lossy(intermediates),
+            byte as char
+        ));
+    }
+
+    fn osc_dispatch(&mut self, params: &[&[u8]], bell_terminated: bool) {
+        let params: Vec<_> = params.iter().map(|p| String::from_utf8_lossy(p)).collect();
+        let end = if bell_terminated { "BEL" } else { "ST" };
+        self.0.push(format!("OSC {} {}", params.join(";"), end));
+    }
+}
+
+fn parse(chunks: &[&[u8]]) -> Vec<String> {
+    let mut parser = Parser::new();
+    let mut record = Record::default();
+    for chunk in chunks {
+        parser.advance(&mut record, chunk);
+    }
+    record.0
+}
+
+#[test]
+fn text_and_controls_are_split_apart() {
+    assert_eq!(
+        parse(&["aé\r\n\x07".as_bytes()]),
+        ["a", "é", "C0 0d", "C0 0a", "C0 07"]
+    );
+}
+
+#[test]
+fn csi_sequences_carry_their_parameters() {
+    assert_eq!(
+        parse(&[b"\x1b[1;31m\x1b[H\x1b[?1049h\x1b[2 q\x1b[38:2::255:0:0m"]),
+        [
+            "CSI 1;31m",
+            "CSI H",
+            "CSI 1049?h",
+            "CSI 2 q",
+            "CSI 38:2:0:255:0:0m"
+        ]
+    );
+}
+
+#[test]
+fn sequences_split_across_reads_are_joined() {
+    let bytes = "x\x1b[38;5;208mé\x1b]0;title\x07".as_bytes();
+    let whole = parse(&[bytes]);
+    // Split everywhere, even inside the UTF-8 of é
+    let split: Vec<&[u8]> = bytes.chunks(1).collect();
+    assert_eq!(parse(&split), whole);
+    assert_eq!(whole.last().unwrap(), "OSC 0;title BEL");
+}
+
+#[test]
+fn controls_inside_a_sequence_run_without_ending_it() {
+    // A line feed in the middle of CUP still moves, and CUP still completes
+    assert_eq!(parse(&[b"\x1b[2\n;3H"]), ["C0 0a", "CSI 2;3H"]);
+}
+
+#[test]
+fn escape_and_osc_terminators() {
+    assert_eq!(
+        parse(&[b"\x1b7\x1b(0\x1b]2;a;b\x1b\\"]),
+        ["ESC 7", "ESC (0", "OSC 2;a;b ST", "ESC \\"]
+    );
+}
+
+#[test]
+fn cancel_drops_the_sequence() {
+    // CAN aborts the CSI and runs as a control; what follows is text again
+    assert_eq!(parse(&[b"\x1b[31\x18x"]), ["C0 18", "x"]);
+}
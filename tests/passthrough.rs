@@ -0,0 +1,85 @@
+//! Shortcut pass-through: which keys the rules name and which foreground
+//! programs they go to.
+
+#![cfg(feature = "gui")]
+
+use std::cell::Cell;
+
+use adamant::backend::is_program;
+use adamant::config::{Config, KeyPassthrough};
+use adamant::passthrough::Passthrough;
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+
+const CTRL_SHIFT: ModifiersState = ModifiersState::CONTROL.union(ModifiersState::SHIFT);
+
+fn rules() -> Vec<KeyPassthrough> {
+    vec![
+        KeyPassthrough {
+            programs: &["tmux"],
+            keys: &["Ctrl+Shift+Left", "Ctrl+Shift+Right", "Ctrl+Shift+c"],
+        },
+        KeyPassthrough {
+            programs: &["vim", "nvim"],
+            keys: &["Ctrl+Shift+Left"],
+        },
+    ]
+}
+
+#[test]
+fn keys_pass_to_the_programs_named_with_them() {
+    let passthrough = Passthrough::new(&rules()).unwrap();
+    let left = Key::Named(NamedKey::ArrowLeft);
+    let copy = Key::Character("C".into());
+    assert!(passthrough.passes(CTRL_SHIFT, &left, || vec!["nvim".into()]));
+    assert!(passthrough.passes(CTRL_SHIFT, &copy, || vec!["tmux: client".into()]));
+    assert!(!passthrough.passes(CTRL_SHIFT, &copy, || vec!["vim".into()]));
+    // A pipeline passes when any of its programs is named
+    assert!(passthrough.passes(CTRL_SHIFT, &left, || vec!["git".into(), "vim".into()]));
+    assert!(!passthrough.passes(CTRL_SHIFT, &left, || vec!["bash".into()]));
+    assert!(!passthrough.passes(ModifiersState::CONTROL, &left, || vec!["tmux".into()]));
+}
+
+#[test]
+fn the_foreground_job_is_only_looked_up_for_named_keys() {
+    let passthrough = Passthrough::new(&rules()).unwrap();
+    let looked_up = Cell::new(false);
+    let job = || {
+        looked_up.set(true);
+        vec!["tmux".to_string()]
+    };
+    assert!(!passthrough.passes(CTRL_SHIFT, &Key::Character("v".into()), job));
+    assert!(!looked_up.get());
+    assert!(
+        !Passthrough::default().passes(CTRL_SHIFT, &Key::Named(NamedKey::ArrowLeft), || {
+            unreachable!()
+        })
+    );
+}
+
+#[test]
+fn renamed_processes_keep_their_program_name() {
+    assert!(is_program("tmux", "tmux"));
+    assert!(is_program("tmux: client", "tmux"));
+    assert!(!is_program("tmuxinator", "tmux"));
+    assert!(!is_program("vi", "vim"));
+}
+
+#[test]
+fn rules_need_keys_that_parse_and_programs() {
+    let bad_key = [KeyPassthrough {
+        programs: &["tmux"],
+        keys: &["Ctrl+Shift+Enter"],
+    }];
+    assert!(Passthrough::new(&bad_key).is_err());
+
+    let mut config = Config {
+        passthrough: rules(),
+        ..Config::default()
+    };
+    assert!(config.validate().is_ok());
+    config.passthrough = vec![KeyPassthrough {
+        programs: &[],
+        keys: &["Ctrl+Shift+Up"],
+    }];
+    assert!(config.validate().is_err());
+}
@@ -0,0 +1,85 @@
+//! Pastes previewed before they reach a shell prompt.
+
+#![cfg(feature = "gui")]
+
+use adamant::config::PasteConfig;
+use adamant::paste::{line_endings, needs_confirmation, preview, sanitize};
+
+#[test]
+fn line_breaks_and_large_pastes_ask_first() {
+    let config = PasteConfig {
+        confirm_over_bytes: 10,
+        ..PasteConfig::default()
+    };
+    assert!(!needs_confirmation(&config, "echo hi", false));
+    assert!(needs_confirmation(&config, "echo hi\n", false));
+    assert!(needs_confirmation(&config, "echo hi\recho", false));
+    assert!(needs_confirmation(&config, "echo 0123456789", false));
+    assert!(!needs_confirmation(&config, "echo 0123456789\n", true));
+}
+
+#[test]
+fn asking_can_be_turned_off() {
+    let config = PasteConfig {
+        confirm_multi_line: false,
+        confirm_over_bytes: 0,
+        ..PasteConfig::default()
+    };
+    let text = "echo hi\n".repeat(10_000);
+    assert!(!needs_confirmation(&config, &text, false));
+}
+
+#[test]
+fn preview_cuts_long_lines_and_counts_the_rest() {
+    let text = format!("{}\n", "x".repeat(30)) + &"y\tz\n".repeat(10);
+    let message = preview(&text, 12);
+    let lines: Vec<&str> = message.lines().collect();
+    assert_eq!(lines[0], "Paste 11 lines (71 bytes) at the prompt?");
+    assert_eq!(lines[2], "  xxxxxxxxx\u{2026}");
+    assert_eq!(lines[3], "  y z");
+    assert_eq!(lines.last(), Some(&"  \u{2026} 3 more"));
+    assert_eq!(lines.len(), 2 + 8 + 1);
+}
+
+#[test]
+fn controls_are_stripped_but_tabs_and_line_breaks_kept() {
+    let config = PasteConfig::default();
+    assert_eq!(
+        sanitize(&config, "a\tb\r\nc\x00\x1b]52;c;eA==\x07\x7f\u{85}d"),
+        "a\tb\r\nc]52;c;eA==d"
+    );
+    let config = PasteConfig {
+        strip_control: false,
+        ..PasteConfig::default()
+    };
+    assert_eq!(sanitize(&config, "a\x1b[Ab"), "a\x1b[Ab");
+}
+
+#[test]
+fn bracketed_paste_cannot_be_left_early() {
+    let config = PasteConfig {
+        strip_control: false,
+        ..PasteConfig::default()
+    };
+    assert_eq!(
+        sanitize(&config, "safe\x1b[20\x1b[201~1~\nrm -rf ~\n"),
+        "safe\nrm -rf ~\n"
+    );
+    let config = PasteConfig {
+        strip_control: false,
+        strip_brackets: false,
+        ..PasteConfig::default()
+    };
+    assert_eq!(sanitize(&config, "\x1b[201~"), "\x1b[201~");
+}
+
+#[test]
+fn line_breaks_become_carriage_returns() {
+    let config = PasteConfig::default();
+    assert_eq!(line_endings(&config, "a\r\nb\nc\r"), "a\rb\rc\r");
+    let config = PasteConfig {
+        normalize_line_endings: false,
+        ..PasteConfig::default()
+    };
+    assert_eq!(line_endings(&config, "a\r\nb\n"), "a\r\nb\n");
+}
@@ -0,0 +1,97 @@
+//! Touch gestures for the font size: pinching, double taps, snapping.
+
+#![cfg(feature = "gui")]
+
+use adamant::pinch::{Gesture, TouchPinch, snap_zoom};
+use web_time::{Duration, Instant};
+use winit::dpi::PhysicalPosition;
+use winit::event::TouchPhase::{Cancelled, Ended, Moved, Started};
+
+fn at(x: f64) -> PhysicalPosition<f64> {
+    PhysicalPosition::new(x, 300.0)
+}
+
+fn ms(start: Instant, ms: u64) -> Instant {
+    start + Duration::from_millis(ms)
+}
+
+/// Two fingers tap at `start`, lifting after 100 ms.
+fn two_finger_tap(pinch: &mut TouchPinch, start: Instant) -> Option<Gesture> {
+    pinch.touch(0, Started, at(100.0), start);
+    pinch.touch(1, Started, at(200.0), ms(start, 10));
+    pinch.touch(1, Moved, at(203.0), ms(start, 50));
+    let first = pinch.touch(0, Ended, at(100.0), ms(start, 100));
+    assert_eq!(pinch.touch(1, Ended, at(203.0), ms(start, 110)), None);
+    first
+}
+
+#[test]
+fn pinching_scales_step_by_step() {
+    let mut pinch = TouchPinch::default();
+    let now = Instant::now();
+    pinch.touch(0, Started, at(100.0), now);
+    pinch.touch(1, Started, at(200.0), now);
+    // Within the tap slop: nothing yet
+    assert_eq!(pinch.touch(1, Moved, at(210.0), now), None);
+    assert_eq!(
+        pinch.touch(1, Moved, at(300.0), now),
+        Some(Gesture::Scale(2.0))
+    );
+    assert_eq!(
+        pinch.touch(0, Moved, at(200.0), now),
+        Some(Gesture::Scale(0.5))
+    );
+    assert_eq!(pinch.touch(0, Ended, at(200.0), now), Some(Gesture::Ended));
+    // The last finger moving alone does nothing
+    assert_eq!(pinch.touch(1, Moved, at(500.0), now), None);
+    assert_eq!(pinch.touch(1, Ended, at(500.0), now), None);
+}
+
+#[test]
+fn one_or_three_fingers_do_not_pinch() {
+    let mut pinch = TouchPinch::default();
+    let now = Instant::now();
+    pinch.touch(0, Started, at(100.0), now);
+    assert_eq!(pinch.touch(0, Moved, at(400.0), now), None);
+    pinch.touch(1, Started, at(500.0), now);
+    pinch.touch(2, Started, at(600.0), now);
+    assert_eq!(pinch.touch(2, Moved, at(900.0), now), None);
+}
+
+#[test]
+fn double_two_finger_tap_resets() {
+    let mut pinch = TouchPinch::default();
+    let start = Instant::now();
+    assert_eq!(two_finger_tap(&mut pinch, start), None);
+    assert_eq!(
+        two_finger_tap(&mut pinch, ms(start, 300)),
+        Some(Gesture::Reset)
+    );
+    // Too far apart in time
+    assert_eq!(two_finger_tap(&mut pinch, ms(start, 1000)), None);
+    assert_eq!(two_finger_tap(&mut pinch, ms(start, 2000)), None);
+}
+
+#[test]
+fn slow_or_cancelled_taps_do_not_count() {
+    let mut pinch = TouchPinch::default();
+    let start = Instant::now();
+    two_finger_tap(&mut pinch, start);
+    pinch.touch(0, Started, at(100.0), ms(start, 300));
+    pinch.touch(1, Started, at(200.0), ms(start, 300));
+    assert_eq!(pinch.touch(0, Cancelled, at(100.0), ms(start, 350)), None);
+    pinch.touch(1, Ended, at(200.0), ms(start, 350));
+
+    pinch.touch(0, Started, at(100.0), ms(start, 400));
+    pinch.touch(1, Started, at(200.0), ms(start, 400));
+    assert_eq!(pinch.touch(0, Ended, at(100.0), ms(start, 900)), None);
+}
+
+#[test]
+fn zoom_snaps_to_whole_pixels() {
+    // 14 px at 1.5x would be 21.7 px
+    assert_eq!(snap_zoom(1.55, 14.0), 22.0 / 14.0);
+    assert_eq!(snap_zoom(1.0, 14.0), 1.0);
+    assert_eq!(snap_zoom(0.01, 14.0), 1.0 / 14.0);
+    assert_eq!(snap_zoom(1.3, 0.0), 1.3);
+}
@@ -0,0 +1,152 @@
+//! Plugins: their palette entries and shortcuts, output filters and
+//! overlays.
+
+#![cfg(feature = "gui")]
+
+use adamant::commands::Action;
+use adamant::config::{Config, HookAction};
+use adamant::plugin::{Plugin, PluginAction, Plugins};
+use adamant::ui::{Element, Layer, LayerKind, Rect, Style, Ui};
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+
+/// Counts its runs and upper-cases the output.
+#[derive(Default)]
+struct Shout {
+    runs: usize,
+}
+
+impl Plugin for Shout {
+    fn name(&self) -> &'static str {
+        "shout"
+    }
+
+    fn actions(&self) -> Vec<PluginAction> {
+        vec![
+            PluginAction {
+                name: "Count",
+                shortcut: Some("Ctrl+Shift+F5"),
+            },
+            PluginAction {
+                name: "Say it",
+                shortcut: None,
+            },
+        ]
+    }
+
+    fn run(&mut self, action: usize) -> Vec<HookAction> {
+        self.runs += 1;
+        match action {
+            0 => vec![HookAction::Notify(format!("{} runs", self.runs))],
+            _ => vec![HookAction::Input("HEY\r".into())],
+        }
+    }
+
+    fn filter_output(&mut self, bytes: &mut Vec<u8>) {
+        bytes.make_ascii_uppercase();
+    }
+}
+
+/// Draws a bar on the last row.
+struct Bar;
+
+impl Plugin for Bar {
+    fn name(&self) -> &'static str {
+        "bar"
+    }
+
+    fn actions(&self) -> Vec<PluginAction> {
+        vec![PluginAction {
+            name: "Hide bar",
+            shortcut: Some("Alt+b"),
+        }]
+    }
+
+    fn filter_output(&mut self, bytes: &mut Vec<u8>) {
+        bytes.retain(|&b| b != b'!');
+    }
+
+    fn overlay(&mut self, rows: usize, cols: usize) -> Vec<Element> {
+        vec![Element::label(
+            Rect::new(rows - 1, 0, 1, cols),
+            "bar",
+            Style::PANEL,
+        )]
+    }
+}
+
+fn plugins() -> Plugins {
+    Plugins::new(&[|| Box::new(Shout::default()), || Box::new(Bar)]).unwrap()
+}
+
+#[test]
+fn actions_join_the_palette_with_their_shortcuts() {
+    let plugins = plugins();
+    let commands = plugins.commands();
+    let names: Vec<_> = commands.iter().map(|command| command.name).collect();
+    assert_eq!(names, ["Count", "Say it", "Hide bar"]);
+    assert_eq!(commands[2].action, Action::Plugin(1, 0));
+
+    let ctrl_shift = ModifiersState::CONTROL | ModifiersState::SHIFT;
+    assert_eq!(
+        plugins.shortcut(ctrl_shift, &Key::Named(NamedKey::F5)),
+        Some(Action::Plugin(0, 0))
+    );
+    assert_eq!(
+        plugins.shortcut(ModifiersState::CONTROL, &Key::Named(NamedKey::F5)),
+        None
+    );
+    assert_eq!(
+        plugins.shortcut(ModifiersState::ALT, &Key::Character("B".into())),
+        Some(Action::Plugin(1, 0))
+    );
+}
+
+#[test]
+fn plugins_keep_their_state() {
+    let mut plugins = plugins();
+    plugins.run(0, 0);
+    assert_eq!(plugins.run(0, 0), [HookAction::Notify("2 runs".into())]);
+    assert_eq!(plugins.run(0, 1), [HookAction::Input("HEY\r".into())]);
+    assert!(plugins.run(1, 0).is_empty());
+    assert!(plugins.run(5, 0).is_empty());
+}
+
+#[test]
+fn output_goes_through_every_filter() {
+    let mut plugins = plugins();
+    let mut bytes = b"hello, world!\r\n".to_vec();
+    plugins.filter_output(&mut bytes);
+    assert_eq!(bytes, b"HELLO, WORLD\r\n");
+}
+
+#[test]
+fn overlays_let_clicks_through() {
+    let mut plugins = plugins();
+    let mut ui = Ui::default();
+    ui.push(Layer::new(LayerKind::Status, plugins.overlays(24, 80)));
+    assert!(ui.covers(23, 10));
+    assert_eq!(ui.hit(23, 10), None);
+    assert_eq!(ui.modal(), None);
+}
+
+#[test]
+fn bad_shortcuts_and_safe_mode() {
+    struct Broken;
+    impl Plugin for Broken {
+        fn name(&self) -> &'static str {
+            "broken"
+        }
+        fn actions(&self) -> Vec<PluginAction> {
+            vec![PluginAction {
+                name: "Nothing",
+                shortcut: Some("Hyper+x"),
+            }]
+        }
+    }
+    assert!(Plugins::new(&[|| Box::new(Broken)]).is_err());
+
+    let mut config = Config::default();
+    config.plugins.push(|| Box::new(Bar));
+    assert!(Plugins::new(&config.plugins).is_ok_and(|plugins| !plugins.is_empty()));
+    assert!(Config::safe_mode().plugins.is_empty());
+}
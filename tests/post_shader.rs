@@ -0,0 +1,64 @@
+//! Post-processing shaders: what compiles, and how mistakes are reported.
+
+#![cfg(feature = "gui")]
+
+use adamant::AdamantError;
+use adamant::renderer::check_post_shader;
+
+fn error(source: &str) -> String {
+    match check_post_shader(source) {
+        Err(AdamantError::Shader(message)) => message,
+        other => panic!("expected a shader error, got {:?}", other),
+    }
+}
+
+#[test]
+fn effects_can_use_every_input() {
+    // Scanlines, a glow around the cursor and a slow pulse
+    let shader = "
+@fragment
+fn fs_main(in: PostInput) -> @location(0) vec4<f32> {
+    var color = textureSample(frame, frame_sampler, in.uv);
+    let scanline = 0.85 + 0.15 * sin(in.position.y * 3.14159);
+    let center = post.cursor + post.cell / 2.0;
+    let glow = 0.2 * exp(-distance(in.position.xy, center) / post.cell.y);
+    let pulse = 0.95 + 0.05 * sin(post.time);
+    let edge = step(in.position.x, post.resolution.x);
+    color = vec4<f32>(color.rgb * scanline * pulse + glow * color.a * edge, color.a);
+    return color;
+}
+";
+    assert!(check_post_shader(shader).is_ok());
+}
+
+#[test]
+fn errors_name_the_line_of_the_shader() {
+    let shader = "@fragment
+fn fs_main(in: PostInput) -> @location(0) vec4<f32> {
+    let x = ;
+    return vec4<f32>(1.0);
+}";
+    assert!(error(shader).starts_with("line 3:"));
+}
+
+#[test]
+fn type_errors_are_caught_before_the_gpu() {
+    let shader = "@fragment
+fn fs_main(in: PostInput) -> @location(0) vec4<f32> {
+    return in.uv;
+}";
+    assert!(error(shader).starts_with("line 3:"));
+}
+
+#[test]
+fn the_entry_point_is_required() {
+    let shader = "@fragment
+fn main(in: PostInput) -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0);
+}";
+    assert!(error(shader).contains("fs_main"));
+
+    // A function by that name isn't enough
+    let shader = "fn fs_main() -> f32 { return 1.0; }";
+    assert!(error(shader).contains("fs_main"));
+}
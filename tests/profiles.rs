@@ -0,0 +1,193 @@
+//! Host profiles: the hosts the shell reports, and the profiles they pick.
+//! App profiles: the titles and foreground programs that pick them.
+
+use adamant::config::{AppProfile, Colors, Config, HostProfile, Rgb, Tint};
+use adamant::term::{Event, Terminal};
+
+fn profile(hosts: &'static [&'static str]) -> HostProfile {
+    HostProfile {
+        name: "test",
+        hosts,
+        title: None,
+        colors: None,
+        bell: None,
+    }
+}
+
+#[test]
+fn osc_7_reports_host_and_directory() {
+    let mut term = Terminal::new(80, 24, 100);
+    term.feed(b"\x1b]7;file://web1.example.com/home/me/My%20Files\x07");
+    assert_eq!(term.host(), Some("web1.example.com"));
+    assert_eq!(term.directory(), Some("/home/me/My Files"));
+    assert_eq!(
+        term.take_events(),
+        vec![Event::Host(Some("web1.example.com".into()))]
+    );
+
+    // Only changes are reported; a URL without a host keeps it
+    term.feed(b"\x1b]7;file://web1.example.com/tmp\x1b\\\x1b]7;file:///var/%zz\x07");
+    assert_eq!(term.directory(), Some("/var/%zz"));
+    assert_eq!(term.host(), Some("web1.example.com"));
+    assert!(term.take_events().is_empty());
+}
+
+#[test]
+fn iterm2_remote_host_is_reported() {
+    let mut term = Terminal::new(80, 24, 100);
+    term.feed(b"\x1b]1337;RemoteHost=deploy@db1\x07\x1b]1337;CurrentDir=/srv\x07");
+    assert_eq!(term.host(), Some("deploy@db1"));
+    assert_eq!(term.directory(), Some("/srv"));
+    assert_eq!(
+        term.take_events(),
+        vec![Event::Host(Some("deploy@db1".into()))]
+    );
+}
+
+#[test]
+fn a_new_program_forgets_the_host() {
+    let mut term = Terminal::new(80, 24, 100);
+    term.feed(b"\x1b]7;file://db1/\x07");
+    term.take_events();
+    term.detach_program();
+    assert_eq!(term.host(), None);
+    assert_eq!(term.take_events(), vec![Event::Host(None)]);
+}
+
+#[test]
+fn hosts_match_patterns() {
+    let profile = profile(&["*.prod.example.com", "db*", "Bastion"]);
+    for host in [
+        "web1.prod.example.com",
+        "root@web1.prod.example.com",
+        "db1",
+        "DB",
+        "bastion",
+    ] {
+        assert!(profile.matches(host), "{:?}", host);
+    }
+    for host in [
+        "prod.example.com",
+        "web1.prod.example.com.evil",
+        "mydb1",
+        "bastion2",
+    ] {
+        assert!(!profile.matches(host), "{:?}", host);
+    }
+}
+
+#[test]
+fn the_first_matching_profile_wins() {
+    let config = Config {
+        profiles: vec![
+            HostProfile {
+                name: "db",
+                ..profile(&["db*"])
+            },
+            HostProfile {
+                name: "any",
+                title: Some("{host} - {title}"),
+                ..profile(&["*"])
+            },
+        ],
+        ..Config::default()
+    };
+    assert_eq!(config.profile_for("db2").unwrap().name, "db");
+    let any = config.profile_for("web").unwrap();
+    assert_eq!(any.title("htop", "web"), "web - htop");
+    assert_eq!(profile(&["web"]).title("htop", "web"), "htop");
+}
+
+#[test]
+fn profiles_need_hosts() {
+    let mut config = Config {
+        profiles: vec![profile(&[])],
+        ..Config::default()
+    };
+    assert!(config.validate().is_err());
+    config.profiles = vec![profile(&[""])];
+    assert!(config.validate().is_err());
+}
+
+fn programs(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn app_profiles_match_titles_or_foreground_programs() {
+    let profile = AppProfile {
+        name: "editor",
+        programs: &["vim", "tmux"],
+        titles: &["* - VIM", "emacs@*"],
+        ..AppProfile::default()
+    };
+    assert!(profile.matches("zsh", &programs(&["vim"])));
+    // A pipeline, and programs that rename themselves
+    assert!(profile.matches("zsh", &programs(&["git", "vim"])));
+    assert!(profile.matches("zsh", &programs(&["tmux: client"])));
+    assert!(profile.matches("notes.txt (~) - vim", &[]));
+    assert!(profile.matches("emacs@laptop", &programs(&["zsh"])));
+    assert!(!profile.matches("vim", &programs(&["vimdiff", "zsh"])));
+    assert!(!profile.matches("notes.txt - VIM - more", &[]));
+}
+
+#[test]
+fn the_first_matching_app_profile_wins() {
+    let config = Config {
+        app_profiles: vec![
+            AppProfile {
+                name: "less",
+                programs: &["less"],
+                disable_mouse_reporting: true,
+                ..AppProfile::default()
+            },
+            AppProfile {
+                name: "any pager",
+                programs: &["less", "more"],
+                ..AppProfile::default()
+            },
+        ],
+        ..Config::default()
+    };
+    assert_eq!(
+        config
+            .app_profile_for("", &programs(&["less"]))
+            .unwrap()
+            .name,
+        "less"
+    );
+    assert_eq!(
+        config
+            .app_profile_for("", &programs(&["more"]))
+            .unwrap()
+            .name,
+        "any pager"
+    );
+    assert!(config.app_profile_for("less", &programs(&["sh"])).is_none());
+}
+
+#[test]
+fn app_profiles_need_programs_or_titles() {
+    let mut config = Config {
+        app_profiles: vec![AppProfile::default()],
+        ..Config::default()
+    };
+    assert!(config.validate().is_err());
+    config.app_profiles = vec![AppProfile {
+        programs: &["vim", ""],
+        ..AppProfile::default()
+    }];
+    assert!(config.validate().is_err());
+    config.app_profiles = vec![AppProfile {
+        titles: &["*"],
+        colors: Some(Colors {
+            tint: Some(Tint {
+                color: Rgb(0, 0, 0xff),
+                amount: 2.0,
+            }),
+            ..Colors::default()
+        }),
+        ..AppProfile::default()
+    }];
+    assert!(config.validate().is_err());
+}
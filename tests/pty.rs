@@ -0,0 +1,306 @@
+//! Pseudo-terminal: the environment, the foreground job, the exit code of
+//! the child (also while a background job keeps the PTY open), the
+//! signals closing it, utmp records and input queued for it.
+
+#![cfg(all(feature = "pty", target_os = "linux"))]
+
+use std::io::Read;
+use std::time::{Duration, Instant};
+
+use adamant::backend::{Backend, BackendEvent, PtyBackend};
+use adamant::config::{CloseSignal, ShellConfig};
+use adamant::pty::Pty;
+
+/// Poll `check` for a few seconds until it returns true.
+fn eventually(mut check: impl FnMut() -> bool) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        if check() {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    false
+}
+
+#[test]
+fn foreground_job_of_an_interactive_shell() {
+    let mut pty =
+        Pty::spawn_program("sh", &["-i".to_string()], &ShellConfig::default(), 80, 24).unwrap();
+    // Drain output so the shell never blocks on a full PTY
+    let mut reader = pty.reader().unwrap();
+    std::thread::spawn(move || std::io::copy(&mut reader, &mut std::io::sink()));
+
+    assert!(eventually(|| pty.foreground_processes().is_empty()));
+    // The shell itself is the foreground job at its prompt
+    assert!(eventually(|| pty.foreground_job() == ["sh".to_string()]));
+    pty.write(b"sleep 30 | cat\n").unwrap();
+    assert!(eventually(
+        || pty.foreground_processes() == ["sleep".to_string(), "cat".to_string()]
+    ));
+    assert_eq!(pty.foreground_job(), pty.foreground_processes());
+    pty.write(b"\x03").unwrap();
+    assert!(eventually(|| pty.foreground_processes().is_empty()));
+}
+
+#[test]
+fn exit_code_of_a_finished_program() {
+    let pty = Pty::spawn_program(
+        "sh",
+        &["-c".to_string(), "exit 3".to_string()],
+        &ShellConfig::default(),
+        80,
+        24,
+    )
+    .unwrap();
+    let mut reader = pty.reader().unwrap();
+    // The end of the output means the program is gone
+    std::io::copy(&mut reader, &mut std::io::sink()).ok();
+    assert!(eventually(|| pty.exit_code() == Some(3)));
+}
+
+#[test]
+fn exit_is_seen_while_a_background_job_keeps_the_pty_open() {
+    let pty = Pty::spawn_program(
+        "sh",
+        &["-c".to_string(), "sleep 5 & exit 4".to_string()],
+        &ShellConfig::default(),
+        80,
+        24,
+    )
+    .unwrap();
+    // No end of output: the job still has the terminal
+    assert!(eventually(|| !pty.is_alive()));
+    assert_eq!(pty.exit_code(), Some(4));
+}
+
+#[test]
+fn backend_reports_the_exit_code() {
+    let mut backend = PtyBackend::spawn_program(
+        "sh",
+        &["-c".to_string(), "echo bye; sleep 5 & exit 4".to_string()],
+        &ShellConfig::default(),
+        80,
+        24,
+        || {},
+    )
+    .unwrap();
+    let mut events = Vec::new();
+    assert!(eventually(|| {
+        events.extend(std::iter::from_fn(|| backend.poll()));
+        events.last() == Some(&BackendEvent::Exited(Some(4)))
+    }));
+    // Output comes before the exit
+    assert!(matches!(&events[0], BackendEvent::Output(bytes) if bytes.starts_with(b"bye")));
+}
+
+/// The first line the program prints, e.g. a pid.
+fn first_line(pty: &Pty) -> String {
+    let mut reader = pty.reader().unwrap();
+    let mut line = Vec::new();
+    let mut byte = [0];
+    while reader.read(&mut byte).unwrap() == 1 && byte[0] != b'\n' {
+        line.push(byte[0]);
+    }
+    String::from_utf8_lossy(&line).trim().to_string()
+}
+
+/// Whether the process exited (or is a zombie nobody reaped yet).
+fn is_gone(pid: &str) -> bool {
+    match std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+        Ok(stat) => stat
+            .rsplit_once(") ")
+            .is_some_and(|(_, rest)| rest.starts_with('Z')),
+        Err(_) => true,
+    }
+}
+
+#[test]
+fn closing_hangs_up_jobs_the_shell_left_behind() {
+    let pty = Pty::spawn_program(
+        "sh",
+        // With job control the job has its own process group, which the
+        // shell exiting doesn't hang up
+        &[
+            "-m".to_string(),
+            "-c".to_string(),
+            "sleep 30 & echo $!".to_string(),
+        ],
+        &ShellConfig::default(),
+        80,
+        24,
+    )
+    .unwrap();
+    let job = first_line(&pty);
+    assert!(eventually(|| !pty.is_alive()));
+    assert!(!is_gone(&job));
+    drop(pty);
+    assert!(eventually(|| is_gone(&job)));
+}
+
+#[test]
+fn terminate_kills_what_ignores_it_after_the_grace_period() {
+    let shell = ShellConfig {
+        close_signal: CloseSignal::Terminate { grace_ms: 100 },
+        ..ShellConfig::default()
+    };
+    let script = "trap '' TERM HUP; echo $$; while :; do sleep 1; done";
+    let pty = Pty::spawn_program(
+        "sh",
+        &["-c".to_string(), script.to_string()],
+        &shell,
+        80,
+        24,
+    )
+    .unwrap();
+    let pid = first_line(&pty);
+    let exit = pty.exit_watch();
+    drop(pty);
+    assert!(exit.wait_timeout(Duration::from_secs(5)).is_some());
+    assert!(is_gone(&pid));
+}
+
+#[test]
+fn joining_closers_waits_out_a_shell_that_traps_term() {
+    let shell = ShellConfig {
+        close_signal: CloseSignal::Terminate { grace_ms: 300 },
+        ..ShellConfig::default()
+    };
+    let script = "trap 'echo caught' TERM; echo $$; while :; do sleep 1; done";
+    let pty = Pty::spawn_program(
+        "sh",
+        &["-c".to_string(), script.to_string()],
+        &shell,
+        80,
+        24,
+    )
+    .unwrap();
+    let pid = first_line(&pty);
+    let started = Instant::now();
+    drop(pty);
+    // What the app does before the process exits
+    adamant::pty::join_closers();
+    assert!(started.elapsed() >= Duration::from_millis(300));
+    assert!(eventually(|| is_gone(&pid)));
+}
+
+#[test]
+fn closers_stop_once_the_shell_is_reaped() {
+    let shell = ShellConfig {
+        close_signal: CloseSignal::Terminate { grace_ms: 60_000 },
+        ..ShellConfig::default()
+    };
+    let pty = Pty::spawn_program(
+        "sh",
+        &["-c".to_string(), "echo $$; sleep 30".to_string()],
+        &shell,
+        80,
+        24,
+    )
+    .unwrap();
+    let pid = first_line(&pty);
+    let exit = pty.exit_watch();
+    let started = Instant::now();
+    drop(pty);
+    assert!(exit.wait_timeout(Duration::from_secs(5)).is_some());
+    adamant::pty::join_closers();
+    assert!(started.elapsed() < Duration::from_secs(10));
+    assert!(is_gone(&pid));
+}
+
+#[test]
+fn utmp_records_dont_stop_the_program() {
+    // Whether they are written or not, which needs permission
+    let shell = ShellConfig {
+        utmp: true,
+        ..ShellConfig::default()
+    };
+    let pty = Pty::spawn_program(
+        "sh",
+        &["-c".to_string(), "exit 5".to_string()],
+        &shell,
+        80,
+        24,
+    )
+    .unwrap();
+    assert!(eventually(|| pty.exit_code() == Some(5)));
+}
+
+#[test]
+fn configured_environment_reaches_the_program() {
+    let shell = ShellConfig {
+        term: "adamant-test",
+        env: &[("ADAMANT_TEST", "one"), ("COLORTERM", "24bit")],
+        ..ShellConfig::default()
+    };
+    let script = r#"printf '%s %s %s' "$TERM" "$COLORTERM" "$ADAMANT_TEST""#;
+    let pty = Pty::spawn_program(
+        "sh",
+        &["-c".to_string(), script.to_string()],
+        &shell,
+        80,
+        24,
+    )
+    .unwrap();
+    let mut output = Vec::new();
+    pty.reader().unwrap().read_to_end(&mut output).ok();
+    assert_eq!(String::from_utf8_lossy(&output), "adamant-test 24bit one");
+}
+
+#[test]
+fn configured_shell_with_arguments() {
+    let shell = ShellConfig {
+        program: Some("sh"),
+        args: &["-c", "printf '%s' \"$0\""],
+        login: false,
+        ..ShellConfig::default()
+    };
+    let pty = Pty::spawn(&shell, 80, 24).unwrap();
+    let mut output = Vec::new();
+    pty.reader().unwrap().read_to_end(&mut output).ok();
+    assert_eq!(String::from_utf8_lossy(&output), "sh");
+}
+
+#[test]
+fn writes_dont_wait_for_the_child_to_read() {
+    let mut pty = Pty::spawn_program(
+        "sleep",
+        &["30".to_string()],
+        &ShellConfig::default(),
+        80,
+        24,
+    )
+    .unwrap();
+    let mut reader = pty.reader().unwrap();
+    std::thread::spawn(move || std::io::copy(&mut reader, &mut std::io::sink()));
+
+    // Far more than the PTY's buffer
+    let input = vec![b'x'; 4 << 20];
+    let started = Instant::now();
+    pty.write(&input).unwrap();
+    pty.write(b"\n").unwrap();
+    assert!(started.elapsed() < Duration::from_secs(1));
+    assert!(pty.pending_input() > 0);
+}
+
+#[test]
+fn queued_input_reaches_a_reading_child() {
+    let mut pty = Pty::spawn_program(
+        "sh",
+        &[
+            "-c".to_string(),
+            "stty raw -echo; exec cat > /dev/null".to_string(),
+        ],
+        &ShellConfig::default(),
+        80,
+        24,
+    )
+    .unwrap();
+    let mut reader = pty.reader().unwrap();
+    std::thread::spawn(move || std::io::copy(&mut reader, &mut std::io::sink()));
+
+    for _ in 0..16 {
+        pty.write(&[b'y'; 64 << 10]).unwrap();
+    }
+    assert!(eventually(|| pty.pending_input() == 0));
+}
@@ -0,0 +1,75 @@
+//! Read-only windows: turning the mode on from a launch profile, the
+//! palette, the menu and the IPC socket.
+
+use adamant::config::{Config, LaunchProfile};
+
+#[test]
+fn launch_profiles_can_open_read_only() {
+    let mut config = Config {
+        launch_profiles: vec![
+            LaunchProfile {
+                name: "logs",
+                command: &["tail", "-f", "/var/log/syslog"],
+                read_only: true,
+                ..LaunchProfile::default()
+            },
+            LaunchProfile {
+                name: "shell",
+                ..LaunchProfile::default()
+            },
+        ],
+        ..Config::default()
+    };
+    assert!(!config.read_only);
+    config.use_launch_profile("shell").unwrap();
+    assert!(!config.read_only);
+    config.use_launch_profile("logs").unwrap();
+    assert!(config.read_only);
+}
+
+#[cfg(unix)]
+#[test]
+fn ipc_locks_and_unlocks() {
+    use adamant::ipc::IpcCommand;
+
+    assert_eq!(
+        IpcCommand::parse("read-only on"),
+        Ok(IpcCommand::ReadOnly(Some(true)))
+    );
+    assert_eq!(
+        IpcCommand::parse("read-only off"),
+        Ok(IpcCommand::ReadOnly(Some(false)))
+    );
+    assert_eq!(
+        IpcCommand::parse("read-only toggle"),
+        Ok(IpcCommand::ReadOnly(None))
+    );
+    for bad in ["read-only", "read-only yes"] {
+        assert!(IpcCommand::parse(bad).is_err(), "{:?}", bad);
+    }
+}
+
+#[cfg(feature = "gui")]
+#[test]
+fn the_palette_and_menu_toggle_it() {
+    use adamant::commands::{Action, commands};
+    use adamant::menu::{MenuItem, menu_bar};
+
+    let config = Config::default();
+    assert!(
+        commands(&config)
+            .iter()
+            .any(|command| command.action == Action::ReadOnly)
+    );
+    let shell = menu_bar(&config)
+        .into_iter()
+        .find(|menu| menu.title == "Shell")
+        .unwrap();
+    assert!(shell.items.iter().any(|item| matches!(
+        item,
+        MenuItem::Action {
+            action: Action::ReadOnly,
+            ..
+        }
+    )));
+}
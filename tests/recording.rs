@@ -0,0 +1,67 @@
+#![cfg(feature = "gui")]
+//! Recording the output as `script --timing` does: file names, the header
+//! `scriptreplay` skips, and timing lines that add up to the output.
+
+use std::path::Path;
+
+use adamant::recording::{Recorder, default_path};
+use web_time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+#[test]
+fn recordings_are_named_after_the_time() {
+    let at = |seconds| UNIX_EPOCH + Duration::from_secs(seconds);
+    assert_eq!(
+        default_path(Path::new("/home/me"), at(1_792_056_600)),
+        Path::new("/home/me/adamant-20261015-093000.typescript")
+    );
+    // The last second of a leap day
+    assert_eq!(
+        default_path(Path::new(""), at(951_868_799)),
+        Path::new("adamant-20000229-235959.typescript")
+    );
+    assert_eq!(
+        Recorder::timing_path(Path::new("out.typescript")),
+        Path::new("out.typescript.timing")
+    );
+}
+
+#[test]
+fn timing_adds_up_to_the_output() {
+    let dir = std::env::temp_dir();
+    let path =
+        default_path(&dir, SystemTime::now()).with_extension(format!("{}", std::process::id()));
+    let start = Instant::now();
+    let mut recorder = Recorder::create(&path, 100, 30, start).unwrap();
+    let chunks: [&[u8]; 3] = [b"\x1b[?1049h", b"", "caf\u{e9}\r\n".as_bytes()];
+    for (i, chunk) in chunks.iter().enumerate() {
+        recorder
+            .record(chunk, start + Duration::from_millis(100 * i as u64))
+            .unwrap();
+    }
+    assert_eq!(recorder.path(), path);
+    recorder.finish().unwrap();
+
+    let typescript = std::fs::read(&path).unwrap();
+    let timing = std::fs::read_to_string(Recorder::timing_path(&path)).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(Recorder::timing_path(&path)).unwrap();
+
+    let header = typescript.iter().position(|&b| b == b'\n').unwrap() + 1;
+    let header_line = String::from_utf8_lossy(&typescript[..header]);
+    assert!(
+        header_line.contains("COLUMNS=\"100\" LINES=\"30\""),
+        "{}",
+        header_line
+    );
+    // Empty chunks take no line
+    assert_eq!(timing, "0.000000 8\n0.200000 7\n");
+    let recorded: usize = timing
+        .lines()
+        .map(|line| line.split(' ').nth(1).unwrap().parse::<usize>().unwrap())
+        .sum();
+    assert_eq!(
+        &typescript[header..header + recorded],
+        b"\x1b[?1049hcaf\xc3\xa9\r\n"
+    );
+    assert!(typescript[header + recorded..].starts_with(b"\nScript done on "));
+}
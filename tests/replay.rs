@@ -0,0 +1,135 @@
+#![cfg(feature = "gui")]
+//! Reading recordings back and replaying them: into a terminal for
+//! checking the screen, and through the replay backend.
+
+use std::time::Duration;
+
+use adamant::backend::{Backend, BackendEvent, ReplayBackend};
+use adamant::recording::{Recorder, Typescript};
+use web_time::Instant;
+
+fn screen(typescript: &Typescript) -> Vec<String> {
+    let term = typescript.terminal(0);
+    (0..term.rows())
+        .map(|row| {
+            let cells = term.grid().visible_row(row).cells();
+            cells
+                .iter()
+                .map(|cell| cell.c)
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn recordings_read_back_as_written() {
+    let path =
+        std::env::temp_dir().join(format!("adamant-replay-{}.typescript", std::process::id()));
+    let start = Instant::now();
+    let mut recorder = Recorder::create(&path, 12, 3, start).unwrap();
+    recorder
+        .record(b"one\r\n", start + Duration::from_millis(40))
+        .unwrap();
+    recorder
+        .record(b"\x1b[2;1Htwo", start + Duration::from_millis(540))
+        .unwrap();
+    recorder.finish().unwrap();
+
+    let typescript = Typescript::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(Recorder::timing_path(&path)).unwrap();
+    assert_eq!(typescript.size, Some((12, 3)));
+    assert_eq!(
+        typescript.chunks,
+        [
+            (Duration::from_millis(40), b"one\r\n".to_vec()),
+            (Duration::from_millis(500), b"\x1b[2;1Htwo".to_vec()),
+        ]
+    );
+    assert_eq!(screen(&typescript), ["one", "two", ""]);
+}
+
+#[test]
+fn typescripts_without_timing_or_header_are_one_chunk() {
+    let script = b"Script started on 2026-10-15 09:30:00+00:00 [COLUMNS=\"20\" LINES=\"2\"]\n\
+                   hi\r\n\nScript done on 2026-10-15 09:31:00+00:00\n";
+    let typescript = Typescript::parse(script, None).unwrap();
+    assert_eq!(typescript.size, Some((20, 2)));
+    assert_eq!(typescript.output(), b"hi\r\n");
+
+    let raw = Typescript::parse(b"\x1b[31mraw", None).unwrap();
+    assert_eq!((raw.size, raw.output()), (None, b"\x1b[31mraw".to_vec()));
+    // A terminal of the default size
+    assert_eq!(raw.terminal(0).cols(), 80);
+}
+
+#[test]
+fn broken_timing_is_an_error() {
+    let error = Typescript::parse(b"abc", Some("0.1 2\nsoon 1\n")).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "timing line 2: expected `<seconds> <bytes>`"
+    );
+    assert!(Typescript::parse(b"abc", Some("-1 3\n")).is_err());
+}
+
+#[test]
+fn replayed_screens_match_the_program_s() {
+    // A full-screen program: alternate screen, a colored status line, and
+    // an update drawn over it, with a wide character
+    let typescript = Typescript {
+        size: Some((16, 4)),
+        chunks: vec![
+            (
+                Duration::ZERO,
+                b"$ top\r\n\x1b[?1049h\x1b[H\x1b[2J".to_vec(),
+            ),
+            (
+                Duration::from_millis(5),
+                b"\x1b[7mload 0.1\x1b[m\x1b[3;1Hcpu \xe2\x96\x88".to_vec(),
+            ),
+            (
+                Duration::from_millis(5),
+                b"\x1b[1;6H0.9\x1b[4;1H\xe6\xbc\xa2 done".to_vec(),
+            ),
+        ],
+    };
+    // The wide character's second cell reads as a blank
+    assert_eq!(
+        screen(&typescript),
+        ["load 0.9", "", "cpu \u{2588}", "\u{6f22}  done"]
+    );
+}
+
+#[test]
+fn the_backend_plays_every_chunk_then_exits() {
+    let typescript = Typescript {
+        size: None,
+        chunks: vec![
+            (Duration::from_secs(60), b"a".to_vec()),
+            (Duration::ZERO, b"b".to_vec()),
+        ],
+    };
+    // At speed 0 the minute-long pause is skipped
+    let mut backend = ReplayBackend::start(typescript, 0.0, || {}).unwrap();
+    backend.write(b"ignored");
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut events = Vec::new();
+    while !matches!(events.last(), Some(BackendEvent::Exited(_))) {
+        assert!(Instant::now() < deadline, "{:?}", events);
+        match backend.poll() {
+            Some(event) => events.push(event),
+            None => std::thread::sleep(Duration::from_millis(1)),
+        }
+    }
+    assert_eq!(
+        events,
+        [
+            BackendEvent::Output(b"a".to_vec()),
+            BackendEvent::Output(b"b".to_vec()),
+            BackendEvent::Exited(Some(0)),
+        ]
+    );
+}
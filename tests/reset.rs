@@ -0,0 +1,82 @@
+//! Soft (DECSTR) and full (RIS) terminal resets: what each puts back.
+
+use adamant::grid::{Color, Flags};
+use adamant::term::{CursorShape, Mode, Terminal};
+
+fn row(term: &Terminal, row: usize) -> String {
+    let cells = term.grid().visible_row(row).cells();
+    cells
+        .iter()
+        .map(|cell| cell.c)
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+/// A terminal left in a mess, as binary output might leave it: modes,
+/// a scroll region, DEC line drawing, attributes, a custom tab stop and
+/// a hyperlink.
+fn garbled() -> Terminal {
+    let mut term = Terminal::new(20, 4, 100);
+    term.feed(b"old\r\n");
+    term.feed(b"\x1b[?1h\x1b[?7l\x1b[?25l\x1b[4h\x1b[?1000h\x1b[2 q");
+    term.feed(b"\x1b[2;3r\x1b[3g\x1b[1;4H\x1bH\x1b[1;31m\x1b]8;;https://a.io\x07\x1b(0");
+    term
+}
+
+#[test]
+fn soft_reset_keeps_the_screen_and_tab_stops() {
+    let mut term = garbled();
+    term.feed(b"\x1b[!p\x1b[Hq\tx");
+    // Back to ASCII, onto the old output, to the custom tab stop
+    assert_eq!(row(&term, 0), "qldx");
+    let cell = &term.grid().visible_row(0)[0];
+    assert_eq!(
+        (cell.fg, cell.flags, cell.link),
+        (Color::Default, Flags::empty(), 0)
+    );
+    assert_eq!(term.grid().scroll_region(), (0, 4));
+    let mode = term.mode();
+    assert!(mode.contains(Mode::AUTOWRAP | Mode::SHOW_CURSOR));
+    assert!(!mode.intersects(Mode::APP_CURSOR | Mode::INSERT));
+    // Mouse tracking is not DECSTR's to reset
+    assert!(mode.contains(Mode::MOUSE_REPORT_CLICK));
+}
+
+#[test]
+fn full_reset_starts_over() {
+    let mut term = garbled();
+    term.feed(b"\x1bcq\tx");
+    assert_eq!(term.grid().history_len(), 0);
+    assert_eq!(row(&term, 0), "q       x");
+    assert_eq!(row(&term, 1), "");
+    let cell = &term.grid().visible_row(0)[0];
+    assert_eq!(
+        (cell.fg, cell.flags, cell.link),
+        (Color::Default, Flags::empty(), 0)
+    );
+    assert_eq!(term.hyperlink(1), None);
+    assert_eq!(term.grid().scroll_region(), (0, 4));
+    assert_eq!(term.mode(), Mode::default());
+    assert_eq!(term.cursor_style().shape, CursorShape::Block);
+}
+
+#[test]
+fn full_reset_recovers_from_random_bytes() {
+    let mut term = Terminal::new(20, 4, 0);
+    // Deterministic junk, escape sequences and all
+    let mut seed = 0x2545_f491_u32;
+    let junk: Vec<u8> = (0..4096)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as u8
+        })
+        .collect();
+    term.feed(&junk);
+    term.reset();
+    term.feed(b"ok");
+    assert_eq!(row(&term, 0), "ok");
+    assert_eq!(term.mode(), Mode::default());
+}
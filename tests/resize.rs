@@ -0,0 +1,201 @@
+//! Resize property tests.
+//!
+//! Random writes interleaved with random resizes. Resizing pads or
+//! truncates rows rather than rewrapping them, and moves lines between the
+//! screen and scrollback, so the properties are:
+//!
+//! - lines that fit every width they are shown at are never lost, in
+//!   scrollback or on screen, in order
+//! - nothing panics, whatever the terminal was doing (margins, the
+//!   alternate screen, a saved cursor past the new edge)
+//! - the cursor, the scrolling region and every row stay within the size,
+//!   and wide characters keep both halves
+
+use adamant::grid::{Flags, Grid};
+use adamant::term::Terminal;
+use proptest::prelude::*;
+
+/// Columns every generated line fits in.
+const MIN_COLS: usize = 8;
+
+/// A line of at most `MIN_COLS - 1` columns, wide characters included.
+fn short_line() -> impl Strategy<Value = String> {
+    prop::collection::vec(
+        prop_oneof![4 => proptest::char::range('a', 'z').prop_map(|c| (c, 1)), 1 => Just(('\u{6f22}', 2))],
+        0..MIN_COLS,
+    )
+    .prop_map(|chars| {
+        let mut width = 0;
+        chars
+            .into_iter()
+            .take_while(|&(_, w)| {
+                width += w;
+                width < MIN_COLS
+            })
+            .map(|(c, _)| c)
+            .collect()
+    })
+}
+
+/// A resize or some output.
+#[derive(Clone, Debug)]
+enum Step {
+    Resize(usize, usize),
+    /// Written on a new line
+    Line(String),
+    Output(Vec<u8>),
+}
+
+fn write_line(grid: &mut Grid, line: &str) {
+    grid.carriage_return();
+    grid.linefeed();
+    for c in line.chars() {
+        let width = if c == '\u{6f22}' { 2 } else { 1 };
+        grid.write(c, width, true);
+    }
+}
+
+/// Sequences that keep state the size bounds: saved cursors, the other
+/// screen, origin mode.
+const SEQUENCES: &[&[u8]] = &[
+    b"\x1b7",
+    b"\x1b8",
+    b"\x1b[s",
+    b"\x1b[u",
+    b"\x1bM",
+    b"\x1b#8",
+    b"\x1b[?1049h",
+    b"\x1b[?1049l",
+    b"\x1b[?6h",
+    b"\x1b[?6l",
+    b"\x1b[?7l",
+    b"\x1b[?7h",
+    b"\x1b[4h",
+    b"\x1b[4l",
+];
+
+/// Pieces of output a program might write, escape sequences included.
+fn chunk() -> impl Strategy<Value = Vec<u8>> {
+    let n = 0u16..30;
+    prop_oneof![
+        4 => "[a-z \u{6f22}]{1,30}".prop_map(String::into_bytes),
+        2 => Just(b"\r\n".to_vec()),
+        1 => (n.clone(), n.clone()).prop_map(|(r, c)| format!("\x1b[{};{}H", r, c).into_bytes()),
+        1 => (n.clone(), n.clone()).prop_map(|(t, b)| format!("\x1b[{};{}r", t, b).into_bytes()),
+        1 => (n.clone(), prop::sample::select(&b"@ABCDEFGJKLMPSTXZbd"[..]))
+            .prop_map(|(n, action)| format!("\x1b[{}{}", n, action as char).into_bytes()),
+        1 => prop::sample::select(SEQUENCES).prop_map(<[u8]>::to_vec),
+    ]
+}
+
+/// The size and the state bound by it are consistent.
+fn assert_in_bounds(grid: &Grid, cols: usize, rows: usize) -> Result<(), TestCaseError> {
+    prop_assert_eq!((grid.cols(), grid.rows()), (cols, rows));
+    prop_assert!(
+        grid.cursor.row < rows && grid.cursor.col < cols,
+        "cursor {:?}",
+        (grid.cursor.row, grid.cursor.col)
+    );
+    let (top, bottom) = grid.scroll_region();
+    prop_assert!(
+        top < bottom && bottom <= rows,
+        "scroll region {:?}",
+        (top, bottom)
+    );
+    prop_assert_eq!(grid.total_lines(), grid.history_len() + rows);
+    for i in 0..grid.total_lines() {
+        let cells = grid.line(i).cells();
+        prop_assert_eq!(cells.len(), cols, "line {}", i);
+        for (col, cell) in cells.iter().enumerate() {
+            if cell.flags.contains(Flags::WIDE) {
+                prop_assert!(
+                    cells
+                        .get(col + 1)
+                        .is_some_and(|next| next.flags.contains(Flags::WIDE_SPACER)),
+                    "wide cell without spacer at line {} col {}",
+                    i,
+                    col
+                );
+            }
+            if cell.flags.contains(Flags::WIDE_SPACER) {
+                prop_assert!(
+                    col > 0 && cells[col - 1].flags.contains(Flags::WIDE),
+                    "orphaned spacer at line {} col {}",
+                    i,
+                    col
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    #[test]
+    fn short_lines_survive_resizes(
+        rows in 1usize..8,
+        steps in prop::collection::vec(
+            prop_oneof![
+                3 => short_line().prop_map(Step::Line),
+                1 => (MIN_COLS..20, 1usize..10).prop_map(|(c, r)| Step::Resize(c, r)),
+            ],
+            0..60,
+        ),
+    ) {
+        // Scrollback for every line, so none are dropped for room
+        let mut grid = Grid::new(MIN_COLS, rows, 100);
+        let mut written = vec![String::new()];
+        for step in &steps {
+            match step {
+                Step::Resize(cols, rows) => grid.resize(*cols, *rows),
+                Step::Line(line) => {
+                    write_line(&mut grid, line);
+                    written.push(line.clone());
+                }
+                Step::Output(_) => unreachable!(),
+            }
+        }
+
+        // The output ends on the cursor's line; below it is blank
+        let last = grid.history_len() + grid.cursor.row;
+        let lines: Vec<String> = (0..=last).map(|i| grid.line(i).text()).collect();
+        prop_assert_eq!(lines, written);
+        for i in last + 1..grid.total_lines() {
+            prop_assert_eq!(grid.line(i).text(), "", "line {} below the cursor", i);
+        }
+    }
+
+    #[test]
+    fn resizes_keep_the_terminal_in_bounds(
+        cols in 1usize..20,
+        rows in 1usize..12,
+        steps in prop::collection::vec(
+            prop_oneof![
+                4 => chunk().prop_map(Step::Output),
+                1 => (1usize..20, 1usize..12).prop_map(|(c, r)| Step::Resize(c, r)),
+            ],
+            0..60,
+        ),
+    ) {
+        let mut term = Terminal::new(cols, rows, 10);
+        let (mut cols, mut rows) = (cols, rows);
+        for step in &steps {
+            match step {
+                Step::Output(bytes) => term.feed(bytes),
+                Step::Resize(new_cols, new_rows) => {
+                    (cols, rows) = (*new_cols, *new_rows);
+                    term.resize(cols, rows);
+                }
+                Step::Line(_) => unreachable!(),
+            }
+            assert_in_bounds(term.grid(), cols, rows)?;
+        }
+        // The other screen was resized too
+        term.feed(b"\x1b[?1049h\x1b[?1049l\x1b[?1049h");
+        assert_in_bounds(term.grid(), cols, rows)?;
+        term.feed(b"\x1b[?1049l\x1b8x\x1b[ux");
+        assert_in_bounds(term.grid(), cols, rows)?;
+    }
+}
@@ -0,0 +1,61 @@
+//! State programs save and put back: the cursor (DECSC/DECRC), the
+//! attributes (XTPUSHSGR/XTPOPSGR); and focus reporting being asked for.
+
+use adamant::grid::{Cell, Color, Flags};
+use adamant::term::{Mode, Terminal};
+
+fn cell(term: &Terminal, row: usize, col: usize) -> Cell {
+    term.grid().visible_row(row)[col].clone()
+}
+
+#[test]
+fn decsc_saves_the_position_and_attributes() {
+    let mut term = Terminal::new(20, 4, 0);
+    term.feed(b"\x1b[2;5H\x1b[1;31m\x1b7\x1b[m\x1b[4;1Hx\x1b8y");
+    let y = cell(&term, 1, 4);
+    assert_eq!(y.c, 'y');
+    assert_eq!((y.fg, y.flags), (Color::Indexed(1), Flags::BOLD));
+    assert_eq!(cell(&term, 3, 0).flags, Flags::empty());
+}
+
+#[test]
+fn pushed_attributes_come_back_on_pop() {
+    let mut term = Terminal::new(20, 2, 0);
+    term.feed(b"\x1b[3;32ma\x1b[#{\x1b[0;1;44mb\x1b[#}c");
+    let (a, b, c) = (cell(&term, 0, 0), cell(&term, 0, 1), cell(&term, 0, 2));
+    assert_eq!((b.bg, b.flags), (Color::Indexed(4), Flags::BOLD));
+    assert_eq!((c.fg, c.bg, c.flags), (a.fg, a.bg, a.flags));
+}
+
+#[test]
+fn selective_push_only_restores_what_it_saved() {
+    let mut term = Terminal::new(20, 2, 0);
+    // Save bold and the foreground only
+    term.feed(b"\x1b[1;31m\x1b[1;30#{\x1b[22;32;43;4m\x1b[#}x");
+    let x = cell(&term, 0, 0);
+    assert_eq!(x.fg, Color::Indexed(1));
+    assert_eq!(x.bg, Color::Indexed(3));
+    assert_eq!(x.flags, Flags::BOLD | Flags::UNDERLINE);
+    // Nothing left to pop
+    term.feed(b"\x1b[#}y");
+    assert_eq!(cell(&term, 0, 1).flags, Flags::BOLD | Flags::UNDERLINE);
+}
+
+#[test]
+fn pushes_nest_and_a_soft_reset_forgets_them() {
+    let mut term = Terminal::new(20, 2, 0);
+    term.feed(b"\x1b[31m\x1b[#{\x1b[32m\x1b[#{\x1b[33m\x1b[#}a\x1b[#}b");
+    assert_eq!(cell(&term, 0, 0).fg, Color::Indexed(2));
+    assert_eq!(cell(&term, 0, 1).fg, Color::Indexed(1));
+    term.feed(b"\x1b[35m\x1b[#{\x1b[!p\x1b[#}c");
+    assert_eq!(cell(&term, 0, 2).fg, Color::Default);
+}
+
+#[test]
+fn focus_reports_are_asked_for_with_mode_1004() {
+    let mut term = Terminal::new(20, 2, 0);
+    term.feed(b"\x1b[?1004h");
+    assert!(term.mode().contains(Mode::FOCUS_EVENTS));
+    term.feed(b"\x1b[?1004l");
+    assert!(!term.mode().contains(Mode::FOCUS_EVENTS));
+}
@@ -0,0 +1,174 @@
+//! Touchpad scrolling: fractions of a line and the momentum glide; the
+//! scroll animation and the output scrolling that feeds it; scrolling
+//! while a selection is dragged past the edge.
+
+#![cfg(feature = "gui")]
+
+use adamant::grid::Grid;
+use adamant::scroll::{AutoScroll, ScrollAnimation, SmoothScroll};
+use web_time::{Duration, Instant};
+use winit::event::TouchPhase;
+
+fn ms(start: Instant, ms: u64) -> Instant {
+    start + Duration::from_millis(ms)
+}
+
+/// A flick upwards: 1.5 lines every 10 ms, then the fingers lift.
+fn flick(scroll: &mut SmoothScroll, start: Instant) -> isize {
+    let mut lines = scroll.pixels(0.0, TouchPhase::Started, start);
+    for i in 1..=5 {
+        lines += scroll.pixels(1.5, TouchPhase::Moved, ms(start, i * 10));
+    }
+    lines + scroll.pixels(0.0, TouchPhase::Ended, ms(start, 55))
+}
+
+#[test]
+fn fractions_add_up_both_ways() {
+    let mut scroll = SmoothScroll::new(false);
+    let now = Instant::now();
+    assert_eq!(scroll.pixels(0.75, TouchPhase::Moved, now), 0);
+    assert_eq!(scroll.pixels(0.75, TouchPhase::Moved, now), 1);
+    assert_eq!(scroll.pixels(-0.25, TouchPhase::Moved, now), 0);
+    assert_eq!(scroll.pixels(-2.5, TouchPhase::Moved, now), -2);
+}
+
+#[test]
+fn wheel_steps_keep_their_fractions() {
+    let mut scroll = SmoothScroll::new(false);
+    assert_eq!(scroll.lines(3.0), 3);
+    assert_eq!(scroll.lines(-0.5), 0);
+    assert_eq!(scroll.lines(-0.5), -1);
+}
+
+#[test]
+fn no_glide_without_momentum() {
+    let mut scroll = SmoothScroll::new(false);
+    let start = Instant::now();
+    assert_eq!(flick(&mut scroll, start), 7);
+    assert!(!scroll.is_gliding());
+    assert_eq!(scroll.glide(ms(start, 100)), 0);
+}
+
+#[test]
+fn flick_glides_and_slows_down() {
+    let mut scroll = SmoothScroll::new(true);
+    let start = Instant::now();
+    flick(&mut scroll, start);
+    assert!(scroll.is_gliding());
+
+    let mut previous = isize::MAX;
+    let mut total = 0;
+    let mut at = 55;
+    while scroll.is_gliding() {
+        at += 16;
+        let lines = scroll.glide(ms(start, at));
+        assert!(lines >= 0 && lines <= previous.saturating_add(1));
+        previous = lines;
+        total += lines;
+        assert!(at < 5000, "the glide never stopped");
+    }
+    assert!(total > 10, "glided only {} lines", total);
+}
+
+#[test]
+fn resting_fingers_or_a_wheel_stop_the_glide() {
+    let mut scroll = SmoothScroll::new(true);
+    let start = Instant::now();
+    scroll.pixels(0.0, TouchPhase::Started, start);
+    scroll.pixels(1.5, TouchPhase::Moved, ms(start, 10));
+    scroll.pixels(1.5, TouchPhase::Moved, ms(start, 20));
+    scroll.pixels(0.0, TouchPhase::Ended, ms(start, 300));
+    assert!(!scroll.is_gliding());
+
+    flick(&mut scroll, ms(start, 1000));
+    assert!(scroll.is_gliding());
+    scroll.lines(0.0);
+    assert!(!scroll.is_gliding());
+}
+
+#[test]
+fn animation_lag_adds_up_and_eases_out() {
+    let mut animation = ScrollAnimation::new(Duration::from_millis(100));
+    let start = Instant::now();
+    assert!(!animation.is_running(start));
+    animation.jump(2.0, 24.0, start);
+    // Another jump halfway continues from where the rows are
+    let halfway = animation.lag(ms(start, 50));
+    assert!(halfway > 0.0 && halfway < 1.0);
+    animation.jump(-3.0, 24.0, ms(start, 50));
+    assert_eq!(animation.lag(ms(start, 50)), halfway - 3.0);
+
+    let mut previous = f32::NEG_INFINITY;
+    for t in (60..150).step_by(10) {
+        let lag = animation.lag(ms(start, t));
+        assert!(lag <= 0.0 && lag >= previous);
+        previous = lag;
+    }
+    assert!(!animation.is_running(ms(start, 150)));
+}
+
+#[test]
+fn long_jumps_only_animate_their_end() {
+    let mut animation = ScrollAnimation::new(Duration::from_millis(100));
+    let now = Instant::now();
+    animation.jump(-5000.0, 24.0, now);
+    assert_eq!(animation.lag(now), -24.0);
+    animation.stop();
+    assert_eq!(animation.lag(now), 0.0);
+
+    let mut instant = ScrollAnimation::new(Duration::ZERO);
+    instant.jump(3.0, 24.0, now);
+    assert!(!instant.is_running(now));
+    assert_eq!(instant.lag(now), 0.0);
+}
+
+#[test]
+fn output_scrolling_is_counted_while_following_it() {
+    let mut grid = Grid::new(10, 3, 100);
+    grid.scroll_up(2);
+    assert_eq!(grid.take_scrolled(), 2);
+    assert_eq!(grid.take_scrolled(), 0);
+
+    // Scrolled back, the view stays put
+    grid.scroll_display(1);
+    grid.scroll_up(1);
+    assert_eq!(grid.take_scrolled(), 0);
+
+    // Scroll regions don't move the history
+    grid.reset_display();
+    grid.set_scroll_region(0, 2);
+    grid.scroll_up(1);
+    assert_eq!(grid.take_scrolled(), 0);
+}
+
+#[test]
+fn dragging_further_out_scrolls_faster() {
+    let start = Instant::now();
+    let lines_in_a_second = |overshoot| {
+        let mut scroll = AutoScroll::default();
+        scroll.drag(overshoot, start);
+        (1..=10)
+            .map(|i| scroll.step(ms(start, i * 100)))
+            .sum::<isize>()
+    };
+    assert_eq!(lines_in_a_second(0.5), 15);
+    assert_eq!(lines_in_a_second(3.0), 40);
+    // Below the bottom, towards the latest output
+    assert_eq!(lines_in_a_second(-3.0), -40);
+    // Far out, it stops speeding up
+    assert_eq!(lines_in_a_second(100.0), 300);
+}
+
+#[test]
+fn auto_scroll_follows_the_drag_and_stops_inside() {
+    let start = Instant::now();
+    let mut scroll = AutoScroll::default();
+    scroll.drag(1.0, start);
+    assert_eq!(scroll.step(ms(start, 100)), 2);
+    // Moving further out only changes the speed from here on
+    scroll.drag(4.0, ms(start, 150));
+    assert_eq!(scroll.step(ms(start, 200)), 5);
+    scroll.drag(0.0, ms(start, 250));
+    assert!(!scroll.is_running());
+    assert_eq!(scroll.step(ms(start, 1000)), 0);
+}
@@ -0,0 +1,63 @@
+//! Scrollback options: whether ED 3 erases the scrollback, and whether
+//! lines scrolled off the alternate screen are kept when it is left.
+
+use adamant::term::{ScrollbackOptions, Terminal};
+
+/// A 3-row terminal with `options` and a line of scrollback.
+fn terminal(options: ScrollbackOptions) -> Terminal {
+    let mut term = Terminal::new(20, 3, 100);
+    term.set_scrollback_options(options);
+    term.feed(b"shell 1\r\nshell 2\r\nshell 3\r\nshell 4");
+    assert_eq!(history(&term), ["shell 1"]);
+    term
+}
+
+/// The scrollback, oldest line first.
+fn history(term: &Terminal) -> Vec<String> {
+    let grid = term.grid();
+    (0..grid.history_len())
+        .map(|line| grid.line(line).text())
+        .collect()
+}
+
+/// Run a full-screen program that prints five lines.
+fn run_pager(term: &mut Terminal) {
+    term.feed(b"\x1b[?1049h\x1b[H");
+    term.feed(b"page 1\r\npage 2\r\npage 3\r\npage 4\r\npage 5");
+    term.feed(b"\x1b[?1049l");
+}
+
+#[test]
+fn leaving_the_alternate_screen_drops_its_lines_by_default() {
+    let mut term = terminal(ScrollbackOptions::default());
+    run_pager(&mut term);
+    assert_eq!(history(&term), ["shell 1"]);
+    assert_eq!(term.grid().row(2).text(), "shell 4");
+}
+
+#[test]
+fn leaving_the_alternate_screen_keeps_its_lines_when_asked() {
+    let mut term = terminal(ScrollbackOptions {
+        save_alt_screen_lines: true,
+        ..ScrollbackOptions::default()
+    });
+    run_pager(&mut term);
+    // What scrolled off the pager follows the shell's history, and the
+    // shell's screen comes back as it was
+    assert_eq!(history(&term), ["shell 1", "page 1", "page 2"]);
+    assert_eq!(term.grid().row(2).text(), "shell 4");
+}
+
+#[test]
+fn ed3_erases_the_scrollback_unless_turned_off() {
+    let mut term = terminal(ScrollbackOptions::default());
+    term.feed(b"\x1b[H\x1b[2J\x1b[3J");
+    assert!(history(&term).is_empty());
+
+    let mut term = terminal(ScrollbackOptions {
+        erase_saved_lines: false,
+        ..ScrollbackOptions::default()
+    });
+    term.feed(b"\x1b[H\x1b[2J\x1b[3J");
+    assert_eq!(history(&term), ["shell 1"]);
+}
@@ -0,0 +1,150 @@
+//! The scrollbar: the thumb's place, dragging it, and the failed commands
+//! shell integration marks along it.
+
+#![cfg(feature = "gui")]
+
+use adamant::grid::{CommandStatus, Grid};
+use adamant::scrollbar::Scrollbar;
+use adamant::term::Terminal;
+
+/// A 4-row screen with `history` lines of scrollback.
+fn grid_with_history(history: usize) -> Grid {
+    let mut grid = Grid::new(10, 4, 1000);
+    for _ in 0..history {
+        grid.scroll_up(1);
+    }
+    grid
+}
+
+/// Run `command` at a prompt, exiting with `status`.
+fn run(term: &mut Terminal, command: &str, status: i32) {
+    term.feed(format!("$ \x1b]133;B\x07{command}\r\n\x1b]133;C\x07").as_bytes());
+    term.feed(format!("output\r\n\x1b]133;D;{status}\x07").as_bytes());
+}
+
+#[test]
+fn no_scrollbar_without_scrollback() {
+    assert_eq!(Scrollbar::new(&Grid::new(10, 4, 1000)), None);
+}
+
+#[test]
+fn thumb_follows_the_view() {
+    let mut grid = grid_with_history(12);
+    // 16 lines, 4 of them in view
+    let at = |grid: &Grid| Scrollbar::new(grid).unwrap().thumb();
+    assert_eq!(at(&grid), (0.75, 1.0));
+    grid.scroll_display(6);
+    assert_eq!(at(&grid), (0.375, 0.625));
+    grid.scroll_display(6);
+    assert_eq!(at(&grid), (0.0, 0.25));
+}
+
+#[test]
+fn long_scrollback_keeps_a_grabbable_thumb() {
+    let mut grid = grid_with_history(996);
+    grid.scroll_display(996);
+    let (start, end) = Scrollbar::new(&grid).unwrap().thumb();
+    assert_eq!(start, 0.0);
+    assert!(end - start >= 0.04);
+}
+
+#[test]
+fn dragging_maps_back_to_the_offset() {
+    let mut grid = grid_with_history(12);
+    for offset in 0..=12 {
+        grid.reset_display();
+        grid.scroll_display(offset as isize);
+        let scrollbar = Scrollbar::new(&grid).unwrap();
+        assert_eq!(scrollbar.offset_at(scrollbar.thumb().0), offset);
+    }
+    // Past either end of the bar
+    let scrollbar = Scrollbar::new(&grid).unwrap();
+    assert_eq!(scrollbar.offset_at(-0.5), 12);
+    assert_eq!(scrollbar.offset_at(2.0), 0);
+}
+
+#[test]
+fn shell_integration_marks_how_commands_ended() {
+    let mut term = Terminal::new(20, 4, 100);
+    run(&mut term, "true", 0);
+    run(&mut term, "false", 1);
+    let grid = term.grid();
+    let commands: Vec<(String, CommandStatus)> = (0..grid.total_lines())
+        .filter_map(|line| Some((grid.line(line).text(), grid.line(line).command?)))
+        .collect();
+    assert_eq!(
+        commands,
+        [
+            ("$ true".to_string(), CommandStatus::Succeeded),
+            ("$ false".to_string(), CommandStatus::Failed),
+        ]
+    );
+}
+
+#[test]
+fn a_prompt_left_without_running_keeps_no_mark() {
+    let mut term = Terminal::new(20, 4, 100);
+    run(&mut term, "false", 2);
+    // Another D without a new command changes nothing
+    term.feed(b"\x1b]133;D;0\x07");
+    let grid = term.grid();
+    let line = (0..grid.total_lines())
+        .find(|&line| grid.line(line).command.is_some())
+        .unwrap();
+    assert_eq!(grid.line(line).command, Some(CommandStatus::Failed));
+}
+
+#[test]
+fn failed_commands_are_marked_along_the_bar() {
+    let mut term = Terminal::new(20, 4, 100);
+    run(&mut term, "make", 2);
+    for _ in 0..4 {
+        run(&mut term, "ls", 0);
+    }
+    let scrollbar = Scrollbar::new(term.grid()).unwrap();
+    let total = term.grid().total_lines() as f32;
+    let failed: Vec<f32> = scrollbar.failed().collect();
+    assert_eq!(failed, [0.5 / total]);
+}
+
+#[test]
+fn every_command_is_marked_and_leads_to_its_line() {
+    let mut term = Terminal::new(20, 4, 100);
+    run(&mut term, "make", 2);
+    for _ in 0..4 {
+        run(&mut term, "ls", 0);
+    }
+    let grid = term.grid();
+    let scrollbar = Scrollbar::new(grid).unwrap();
+    let statuses: Vec<CommandStatus> = scrollbar.commands().map(|(_, status)| status).collect();
+    assert_eq!(statuses[0], CommandStatus::Failed);
+    assert_eq!(statuses.len(), 5);
+    assert!(statuses[1..].iter().all(|&s| s == CommandStatus::Succeeded));
+
+    // The mark closest to the click, if it is close enough
+    let (second, _) = scrollbar.commands().nth(1).unwrap();
+    let line = scrollbar.command_near(second + 0.01, 0.02).unwrap();
+    assert_eq!(grid.line(line).text(), "$ ls");
+    assert_eq!(scrollbar.command_near(second + 0.05, 0.02), None);
+
+    // Jumping there shows the command at the top
+    let mut grid = grid.clone();
+    grid.scroll_display(scrollbar.offset_showing(line) as isize);
+    assert_eq!(grid.visible_line_index(0), line);
+    assert_eq!(scrollbar.offset_showing(grid.total_lines() - 1), 0);
+}
+
+#[test]
+fn search_matches_are_marked_along_the_bar() {
+    let mut term = Terminal::new(20, 4, 100);
+    term.feed(b"warning: a\r\nok\r\nerror: b\r\nok\r\nok\r\nerror: c, error: d\r\nok");
+    let matches = term.grid().search("error");
+    assert_eq!(matches.len(), 3);
+    let scrollbar = Scrollbar::new(term.grid()).unwrap().with_matches(&matches);
+    let total = term.grid().total_lines() as f32;
+    // A mark per line, however many matches it has
+    let marks: Vec<f32> = scrollbar.matches().collect();
+    assert_eq!(marks, [2.5 / total, 5.5 / total]);
+    // No search, no marks
+    assert_eq!(Scrollbar::new(term.grid()).unwrap().matches().count(), 0);
+}
@@ -0,0 +1,51 @@
+//! Scrollback search: where a query appears, in lines and columns.
+
+use adamant::grid::SearchMatch;
+use adamant::term::Terminal;
+
+fn found(line: usize, start: usize, end: usize) -> SearchMatch {
+    SearchMatch { line, start, end }
+}
+
+#[test]
+fn matches_are_found_in_the_scrollback_and_on_screen() {
+    let mut term = Terminal::new(20, 3, 100);
+    term.feed(b"error: one\r\nok\r\nok\r\nerror: two, error");
+    assert_eq!(term.grid().history_len(), 1);
+    assert_eq!(
+        term.grid().search("error"),
+        [found(0, 0, 5), found(3, 0, 5), found(3, 12, 17)]
+    );
+    assert_eq!(term.grid().search(""), []);
+    assert_eq!(term.grid().search("warning"), []);
+}
+
+#[test]
+fn lowercase_queries_ignore_case() {
+    let mut term = Terminal::new(20, 3, 100);
+    term.feed(b"Error\r\nERROR\r\nerror");
+    let lines = |query: &str| -> Vec<usize> {
+        term.grid()
+            .search(query)
+            .iter()
+            .map(|found| found.line)
+            .collect()
+    };
+    assert_eq!(lines("error"), [0, 1, 2]);
+    assert_eq!(lines("Error"), [0]);
+}
+
+#[test]
+fn wide_characters_take_two_columns() {
+    let mut term = Terminal::new(20, 3, 100);
+    term.feed("日本語 テスト".as_bytes());
+    assert_eq!(term.grid().search("本語"), [found(0, 2, 6)]);
+    assert_eq!(term.grid().search("テ"), [found(0, 7, 9)]);
+}
+
+#[test]
+fn matches_do_not_overlap() {
+    let mut term = Terminal::new(20, 3, 100);
+    term.feed(b"aaaa");
+    assert_eq!(term.grid().search("aa"), [found(0, 0, 2), found(0, 2, 4)]);
+}
@@ -0,0 +1,107 @@
+//! Double and triple click selection: words, lines and dragging by them;
+//! and moving a selection with the keyboard in copy mode.
+
+use adamant::grid::{Point, Selection, SelectionMotion, SelectionUnit};
+use adamant::term::Terminal;
+
+const WORD_CHARS: &str = "-_./~:@%+=?&#";
+
+fn term(cols: usize, output: &str) -> Terminal {
+    let mut term = Terminal::new(cols, 4, 0);
+    term.feed(output.as_bytes());
+    term
+}
+
+fn text(term: &Terminal, (start, end): (Point, Point)) -> String {
+    let mut selection = Selection::new(start);
+    selection.update(end);
+    term.grid().selection_text(&selection)
+}
+
+fn word(term: &Terminal, line: usize, col: usize) -> String {
+    let point = Point::new(line, col);
+    text(
+        term,
+        term.grid().unit_at(point, SelectionUnit::Word, WORD_CHARS),
+    )
+}
+
+#[test]
+fn words_take_paths_and_urls_whole() {
+    let term = term(60, "open ~/src/main.rs (see https://x.org/a?b=1#c) >> ok");
+    assert_eq!(word(&term, 0, 8), "~/src/main.rs");
+    assert_eq!(word(&term, 0, 30), "https://x.org/a?b=1#c");
+    // Punctuation outside the word characters stops a word, and runs of
+    // the same character are a word of their own
+    assert_eq!(word(&term, 0, 19), "(");
+    assert_eq!(word(&term, 0, 48), ">>");
+    assert_eq!(word(&term, 0, 45), ")");
+}
+
+#[test]
+fn words_continue_over_wrapped_rows_and_wide_characters() {
+    let term = term(8, "ls /usr/local/bin 日本語 x");
+    assert_eq!(word(&term, 1, 2), "/usr/local/bin");
+    // The right half of a wide character
+    assert_eq!(word(&term, 2, 3), "日本語");
+}
+
+#[test]
+fn lines_take_the_rows_autowrap_continued() {
+    let term = term(6, "first line\r\nsecond");
+    let line = |row| {
+        text(
+            &term,
+            term.grid()
+                .unit_at(Point::new(row, 2), SelectionUnit::Line, ""),
+        )
+    };
+    assert_eq!(line(1), "first line");
+    assert_eq!(line(2), "second");
+}
+
+#[test]
+fn dragging_extends_by_whole_words_either_way() {
+    let term = term(30, "alpha beta gamma");
+    let grid = term.grid();
+    let unit = |col| grid.unit_at(Point::new(0, col), SelectionUnit::Word, WORD_CHARS);
+    let beta = unit(7);
+    let mut selection = Selection::new(beta.0);
+    selection.extend(beta, unit(13));
+    assert_eq!(grid.selection_text(&selection), "beta gamma");
+    selection.extend(beta, unit(1));
+    assert_eq!(grid.selection_text(&selection), "alpha beta");
+}
+
+#[test]
+fn copy_mode_moves_by_cells_words_and_lines() {
+    let term = term(12, "one two\r\nthree");
+    let grid = term.grid();
+    let go = |line, col, motion| grid.move_point(Point::new(line, col), motion, WORD_CHARS);
+    assert_eq!(go(0, 11, SelectionMotion::Right), Point::new(1, 0));
+    assert_eq!(go(1, 0, SelectionMotion::Left), Point::new(0, 11));
+    assert_eq!(go(0, 0, SelectionMotion::Left), Point::new(0, 0));
+    // Over the blanks to the end of the next word, or the start of the
+    // one before
+    assert_eq!(go(0, 0, SelectionMotion::WordRight), Point::new(0, 2));
+    assert_eq!(go(0, 2, SelectionMotion::WordRight), Point::new(0, 6));
+    assert_eq!(go(1, 0, SelectionMotion::WordLeft), Point::new(0, 4));
+    assert_eq!(go(0, 4, SelectionMotion::WordLeft), Point::new(0, 0));
+    assert_eq!(go(0, 5, SelectionMotion::LineDown), Point::new(1, 11));
+    assert_eq!(go(1, 5, SelectionMotion::LineUp), Point::new(0, 0));
+    assert_eq!(go(1, 3, SelectionMotion::PageDown), Point::new(3, 3));
+    assert_eq!(go(3, 3, SelectionMotion::PageUp), Point::new(0, 3));
+}
+
+#[test]
+fn moving_the_head_extends_the_selection() {
+    let term = term(20, "alpha beta gamma");
+    let grid = term.grid();
+    let mut selection = Selection::new(Point::new(0, 6));
+    for _ in 0..2 {
+        let head = grid.move_point(selection.head(), SelectionMotion::WordRight, WORD_CHARS);
+        selection.update(head);
+    }
+    assert_eq!(selection.head(), Point::new(0, 15));
+    assert_eq!(grid.selection_text(&selection), "beta gamma");
+}
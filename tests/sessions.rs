@@ -0,0 +1,158 @@
+//! Detachable sessions: the frame protocol, terminal snapshots, and a
+//! session outliving the window attached to it.
+
+use adamant::grid::{Color, Flags};
+use adamant::term::{Mode, Terminal};
+
+/// A fresh terminal fed `term`'s snapshot.
+fn restored(term: &Terminal) -> Terminal {
+    let mut copy = Terminal::new(term.cols(), term.rows(), 100);
+    copy.feed(&term.snapshot());
+    copy
+}
+
+#[test]
+fn snapshots_keep_scrollback_and_rendition() {
+    let mut term = Terminal::new(10, 3, 100);
+    term.feed(b"one\r\n\x1b[1;38;5;200mtwo\x1b[0m\r\nthree\r\n\xe5\xad\x97 \x1b[44m \x1b[0m\r\n0123456789abc");
+    let copy = restored(&term);
+    let lines = |term: &Terminal| {
+        (0..term.grid().total_lines())
+            .map(|line| term.grid().line(line).text())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(lines(&copy), lines(&term));
+    assert!(copy.grid().line(4).wrapped);
+
+    let cell = &copy.grid().line(1).cells()[0];
+    assert_eq!(cell.fg, Color::Indexed(200));
+    assert!(cell.flags.contains(Flags::BOLD));
+    let wide = &copy.grid().line(3).cells()[0..4];
+    assert!(wide[0].flags.contains(Flags::WIDE));
+    assert_eq!(wide[3].bg, Color::Indexed(4));
+    assert_eq!(
+        (copy.grid().cursor.row, copy.grid().cursor.col),
+        (term.grid().cursor.row, term.grid().cursor.col)
+    );
+}
+
+#[test]
+fn snapshots_keep_the_alternate_screen_and_modes() {
+    let mut term = Terminal::new(20, 5, 100);
+    term.feed(b"shell$ vim\r\n");
+    term.feed(b"\x1b[?1049h\x1b[?1h\x1b[?2004h\x1b[?1002h\x1b[?1006h\x1b[6 q");
+    term.feed(b"\x1b[2;4r\x1b[3;1H~ buffer\x1b[?25l\x1b]11;#102030\x07");
+    let copy = restored(&term);
+    assert!(copy.mode().contains(
+        Mode::ALT_SCREEN
+            | Mode::APP_CURSOR
+            | Mode::BRACKETED_PASTE
+            | Mode::MOUSE_DRAG
+            | Mode::SGR_MOUSE
+    ));
+    assert!(!copy.mode().contains(Mode::SHOW_CURSOR));
+    assert_eq!(copy.grid().row(2).text(), "~ buffer");
+    assert_eq!(copy.grid().scroll_region(), (1, 4));
+    assert_eq!(copy.cursor_style(), term.cursor_style());
+    assert_eq!((copy.grid().cursor.row, copy.grid().cursor.col), (2, 8));
+    assert_eq!(copy.colors(), term.colors());
+
+    // Leaving the alternate screen shows the shell again
+    let mut copy = copy;
+    copy.feed(b"\x1b[?1049l");
+    assert_eq!(copy.grid().row(0).text(), "shell$ vim");
+}
+
+#[cfg(unix)]
+#[test]
+fn frames_round_trip() {
+    use adamant::session::Frame;
+
+    let frames = [
+        Frame::Output(b"\x1b[1mhi".to_vec()),
+        Frame::Input(Vec::new()),
+        Frame::Resize { cols: 300, rows: 2 },
+        Frame::Exited(None),
+        Frame::Exited(Some(130)),
+    ];
+    let bytes: Vec<u8> = frames.iter().flat_map(Frame::encode).collect();
+    let mut reader = &bytes[..];
+    for frame in frames {
+        assert_eq!(Frame::read(&mut reader).unwrap(), Some(frame));
+    }
+    assert_eq!(Frame::read(&mut reader).unwrap(), None);
+
+    // A resize needs exactly two sizes
+    assert!(Frame::read(&mut &[2, 1, 0, 0, 0, 80][..]).is_err());
+    assert!(adamant::session::check_name("work-2.1").is_ok());
+    for bad in ["", "../x", ".hidden", "a b"] {
+        assert!(adamant::session::check_name(bad).is_err(), "{:?}", bad);
+    }
+}
+
+#[cfg(all(feature = "pty", target_os = "linux"))]
+#[test]
+fn sessions_outlive_their_window() {
+    use std::time::{Duration, Instant};
+
+    use adamant::backend::{Backend, BackendEvent, SessionBackend};
+    use adamant::config::Config;
+    use adamant::session;
+
+    /// Everything the backend delivers until `done` is true of it.
+    fn read_until(backend: &mut SessionBackend, done: impl Fn(&[u8], bool) -> bool) -> Vec<u8> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let (mut output, mut exited) = (Vec::new(), false);
+        while !done(&output, exited) {
+            assert!(
+                Instant::now() < deadline,
+                "{:?}",
+                String::from_utf8_lossy(&output)
+            );
+            match backend.poll() {
+                Some(BackendEvent::Output(bytes)) => output.extend(bytes),
+                Some(BackendEvent::Exited(code)) => {
+                    assert_eq!(code, Some(0));
+                    exited = true;
+                }
+                None => std::thread::sleep(Duration::from_millis(10)),
+            }
+        }
+        output
+    }
+    let contains = |output: &[u8], text: &str| String::from_utf8_lossy(output).contains(text);
+
+    let name = format!("test-{}", std::process::id());
+    let server = {
+        let name = name.clone();
+        std::thread::spawn(move || {
+            let command = ["cat".to_string()];
+            session::serve(&name, &Config::default(), Some(&command))
+        })
+    };
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !session::is_running(&name) {
+        assert!(Instant::now() < deadline);
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert!(session::list().contains(&name));
+
+    let mut window = SessionBackend::attach(&name, 40, 10, || {}).unwrap();
+    window.write(b"kept\n");
+    read_until(&mut window, |output, _| contains(output, "kept"));
+    drop(window);
+
+    // The next window starts with the screen as it was
+    let mut window = SessionBackend::attach(&name, 40, 10, || {}).unwrap();
+    let snapshot = read_until(&mut window, |output, _| !output.is_empty());
+    let mut term = Terminal::new(40, 10, 100);
+    term.feed(&snapshot);
+    assert_eq!(term.grid().row(0).text(), "kept");
+    assert_eq!(term.grid().row(1).text(), "kept");
+
+    // End of input ends `cat`, and the session with it
+    window.write(b"\x04");
+    read_until(&mut window, |_, exited| exited);
+    server.join().unwrap().unwrap();
+    assert!(!session::is_running(&name));
+}
@@ -0,0 +1,71 @@
+//! Shaping: the glyphs the font puts in place of characters, as
+//! ligatures and OpenType features are turned on or off.
+
+#![cfg(feature = "gui")]
+
+mod common;
+
+use adamant::config::FontConfig;
+use adamant::renderer::FontSet;
+
+use common::{Shape, TestFont};
+
+/// Each cluster shaped into glyphs other than the characters' own: its
+/// characters and glyph ids.
+fn shape(
+    font: &TestFont,
+    ligatures: bool,
+    features: &[(&'static str, bool)],
+) -> Vec<(usize, usize, Vec<u16>)> {
+    let config = FontConfig {
+        regular: vec![font.source()],
+        bold: Vec::new(),
+        fallback: Vec::new(),
+        ligatures,
+        features: features.to_vec(),
+        ..FontConfig::default()
+    };
+    let mut fonts = FontSet::load(&config, 1.0).unwrap();
+    assert!(fonts.shaping());
+    (fonts.shape("fix 0", false).into_iter())
+        .map(|cluster| {
+            let glyphs = cluster.glyphs.iter().map(|&(id, ..)| id).collect();
+            (cluster.chars.start, cluster.chars.end, glyphs)
+        })
+        .collect()
+}
+
+#[test]
+fn ligatures_join_characters() {
+    let font = TestFont::new("fix 0", Shape::Square).features();
+    assert_eq!(shape(&font, true, &[]), [(0, 2, vec![font.fi()])]);
+}
+
+#[test]
+fn a_feature_turned_off_wins_over_ligatures() {
+    let font = TestFont::new("fix 0", Shape::Square).features();
+    assert_eq!(shape(&font, true, &[("liga", false)]), []);
+}
+
+#[test]
+fn a_feature_turned_on_substitutes_glyphs() {
+    let font = TestFont::new("fix 0", Shape::Square).features();
+    // Ligatures stay off
+    let zero = shape(&font, false, &[("zero", true)]);
+    assert_eq!(zero, [(4, 5, vec![font.slashed_zero()])]);
+    let both = shape(&font, true, &[("zero", true)]);
+    assert_eq!(
+        both,
+        [(0, 2, vec![font.fi()]), (4, 5, vec![font.slashed_zero()])]
+    );
+}
+
+#[test]
+fn nothing_to_shape_without_ligatures_or_features() {
+    let font = TestFont::new("fix 0", Shape::Square).features();
+    let config = FontConfig {
+        regular: vec![font.source()],
+        ..FontConfig::default()
+    };
+    assert!(!FontSet::load(&config, 1.0).unwrap().shaping());
+}
@@ -0,0 +1,88 @@
+//! The status bar: its segments, when they change, and its cells.
+
+#![cfg(feature = "gui")]
+
+use adamant::config::{Config, StatusBarConfig, StatusBarPosition, StatusSegment};
+use adamant::grid::Color;
+use adamant::status_bar::StatusBar;
+use adamant::term::Terminal;
+use web_time::{Duration, Instant};
+
+fn bar(segments: Vec<StatusSegment>) -> StatusBar {
+    let config = StatusBarConfig {
+        position: Some(StatusBarPosition::Top),
+        segments,
+        ..StatusBarConfig::default()
+    };
+    StatusBar::new(&config).unwrap().unwrap()
+}
+
+#[test]
+fn bar_needs_a_position_and_valid_segments() {
+    assert!(
+        StatusBar::new(&StatusBarConfig::default())
+            .unwrap()
+            .is_none()
+    );
+
+    let mut config = Config::default();
+    config.status_bar.interval_ms = 0;
+    assert!(config.validate().is_err());
+    let mut config = Config::default();
+    config.status_bar.segments = vec![StatusSegment::Command(&[])];
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn reported_directory_and_host_show_right_away() {
+    let mut bar = bar(vec![
+        StatusSegment::Cwd,
+        StatusSegment::Hostname,
+        StatusSegment::Text("x"),
+    ]);
+    let mut term = Terminal::new(40, 5, 100);
+    let now = Instant::now();
+    bar.refresh(&term, now);
+
+    term.feed(b"\x1b]7;file://build1/srv/app\x07");
+    assert!(bar.refresh(&term, now));
+    assert_eq!(bar.text(), "/srv/app | build1 | x");
+    // Nothing new until the next tick
+    assert!(!bar.refresh(&term, now + Duration::from_millis(10)));
+}
+
+#[test]
+fn clock_shows_hours_and_minutes() {
+    let mut bar = bar(vec![StatusSegment::Clock {
+        utc_offset_minutes: -330,
+    }]);
+    bar.refresh(&Terminal::new(10, 2, 0), Instant::now());
+    let text = bar.text();
+    assert_eq!(text.len(), 5, "{:?}", text);
+    let (hours, minutes) = text.split_once(':').unwrap();
+    assert!(hours.parse::<u32>().unwrap() < 24);
+    assert!(minutes.parse::<u32>().unwrap() < 60);
+}
+
+#[cfg(unix)]
+#[test]
+fn program_output_arrives_in_the_background() {
+    let mut bar = bar(vec![StatusSegment::Command(&["echo", "first\nsecond"])]);
+    let term = Terminal::new(10, 2, 0);
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while bar.text().is_empty() && Instant::now() < deadline {
+        bar.refresh(&term, Instant::now());
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(bar.text(), "first");
+}
+
+#[test]
+fn cells_fill_the_row() {
+    let mut bar = bar(vec![StatusSegment::Text("ok")]);
+    bar.refresh(&Terminal::new(10, 2, 0), Instant::now());
+    let cells = bar.cells(6);
+    let text: String = cells.iter().map(|cell| cell.c).collect();
+    assert_eq!(text, " ok   ");
+    assert!(cells.iter().all(|cell| cell.bg == Color::Indexed(8)));
+}
@@ -0,0 +1,76 @@
+//! Selections copied as HTML and RTF.
+
+#![cfg(feature = "gui")]
+
+use adamant::config::Rgb;
+use adamant::grid::{Cell, Color, CopyOptions, Flags, Point, Selection};
+use adamant::styled_copy::StyledText;
+use adamant::term::Terminal;
+
+const WHITE: Rgb = Rgb(255, 255, 255);
+const BLACK: Rgb = Rgb(0, 0, 0);
+const GREEN: Rgb = Rgb(0, 205, 0);
+
+/// Default colors, and green for anything else.
+fn colors(cell: &Cell) -> (Rgb, Rgb) {
+    let pick = |color, default| {
+        if color == Color::Default {
+            default
+        } else {
+            GREEN
+        }
+    };
+    (pick(cell.fg, WHITE), pick(cell.bg, BLACK))
+}
+
+fn styled(output: &[u8], cols: usize, end: Point) -> StyledText {
+    let mut term = Terminal::new(cols, 4, 0);
+    term.feed(output);
+    let mut selection = Selection::new(Point::new(0, 0));
+    selection.update(end);
+    StyledText::from_selection(
+        term.grid(),
+        &selection,
+        CopyOptions::default(),
+        (WHITE, BLACK),
+        colors,
+    )
+}
+
+#[test]
+fn runs_follow_the_style_and_lines_follow_the_text() {
+    let text = styled(
+        b"ab\x1b[32mcdefgh\x1b[m  \r\n\x1b[4;9mx",
+        6,
+        Point::new(2, 5),
+    );
+    // The wrapped line is one line; trailing blanks are dropped
+    assert_eq!(text.lines.len(), 2);
+    let first: Vec<(&str, Rgb)> = text.lines[0]
+        .iter()
+        .map(|span| (span.text.as_str(), span.fg))
+        .collect();
+    assert_eq!(first, [("ab", WHITE), ("cdefgh", GREEN)]);
+    assert_eq!(text.lines[1][0].flags, Flags::UNDERLINE | Flags::STRIKEOUT);
+    assert!(
+        text.html()
+            .ends_with("\n<span style=\"text-decoration:underline line-through\">x</span></pre>")
+    );
+}
+
+#[test]
+fn rtf_escapes_braces_and_non_ascii() {
+    let text = styled(
+        "\x1b[42m{é}\x1b[m\\ \u{1F600}".as_bytes(),
+        20,
+        Point::new(0, 19),
+    );
+    let rtf = text.rtf();
+    assert!(rtf.starts_with("{\\rtf1\\ansi"));
+    assert!(rtf.contains(
+        "{\\colortbl;\\red255\\green255\\blue255;\\red0\\green0\\blue0;\\red0\\green205\\blue0;}"
+    ));
+    assert!(rtf.contains("{\\cf1\\cb3\\highlight3 \\{\\u233?\\}}"));
+    assert!(rtf.contains("{\\cf1\\cb2\\highlight2 \\\\ \\u-10179?\\u-8704?}"));
+    assert!(rtf.ends_with('}'));
+}
@@ -0,0 +1,154 @@
+//! The terminal core without a window: escape sequences fed to
+//! [`Terminal`] and the grid they leave, as an embedder would use it.
+
+use adamant::grid::{Color, Flags};
+use adamant::term::{Mode, Terminal};
+
+/// The screen's rows, trailing blanks trimmed.
+fn screen(term: &Terminal) -> Vec<String> {
+    let grid = term.grid();
+    (0..grid.rows()).map(|row| grid.row(row).text()).collect()
+}
+
+fn cursor(term: &Terminal) -> (usize, usize) {
+    let cursor = &term.grid().cursor;
+    (cursor.row, cursor.col)
+}
+
+#[test]
+fn text_wraps_at_the_right_margin() {
+    let mut term = Terminal::new(5, 3, 100);
+    term.feed(b"abcdefg");
+    assert_eq!(screen(&term), ["abcde", "fg", ""]);
+    assert!(term.grid().row(0).wrapped);
+    assert_eq!(cursor(&term), (1, 2));
+}
+
+#[test]
+fn cursor_addressing_and_relative_moves() {
+    let mut term = Terminal::new(10, 4, 100);
+    term.feed(b"\x1b[3;4Hx\x1b[Ay\x1b[2Dz\x1b[1;1Hw\x1b[10;20Hq");
+    assert_eq!(screen(&term), ["w", "   zy", "   x", "         q"]);
+    // CUP clamps to the screen
+    assert_eq!(cursor(&term), (3, 9));
+}
+
+#[test]
+fn sgr_sets_colors_and_attributes() {
+    let mut term = Terminal::new(10, 2, 100);
+    term.feed(b"\x1b[1;31ma\x1b[38;5;208;48;2;1;2;3mb\x1b[0;4mc\x1b[mx");
+    let row = term.grid().row(0);
+    assert_eq!(row[0].fg, Color::Indexed(1));
+    assert!(row[0].flags.contains(Flags::BOLD));
+    assert_eq!(row[1].fg, Color::Indexed(208));
+    assert_eq!(row[1].bg, Color::Rgb(1, 2, 3));
+    assert!(row[1].flags.contains(Flags::BOLD));
+    assert_eq!(row[2].fg, Color::Default);
+    assert_eq!(row[2].flags, Flags::UNDERLINE);
+    assert_eq!((row[3].fg, row[3].flags), (Color::Default, Flags::empty()));
+}
+
+#[test]
+fn erasing_parts_of_lines_and_the_screen() {
+    let mut term = Terminal::new(6, 3, 100);
+    term.feed(b"aaaaaa\r\nbbbbbb\r\ncccccc");
+    // EL 1 up to the cursor, EL 0 from it
+    term.feed(b"\x1b[2;3H\x1b[1K\x1b[3;4H\x1b[K");
+    assert_eq!(screen(&term), ["aaaaaa", "   bbb", "ccc"]);
+    // ED 1 from the top to the cursor
+    term.feed(b"\x1b[2;5H\x1b[1J");
+    assert_eq!(screen(&term), ["", "     b", "ccc"]);
+    term.feed(b"\x1b[2J");
+    assert_eq!(screen(&term), ["", "", ""]);
+}
+
+#[test]
+fn a_scroll_region_keeps_the_lines_outside_it() {
+    let mut term = Terminal::new(10, 5, 100);
+    term.feed(b"top\r\n1\r\n2\r\n3\r\nbottom");
+    // Region rows 2-4; a line feed at its bottom scrolls only the region
+    term.feed(b"\x1b[2;4r\x1b[4;1H\nnew");
+    assert_eq!(screen(&term), ["top", "2", "3", "new", "bottom"]);
+    // Lines scrolled out of a region don't go to the scrollback
+    assert_eq!(term.grid().history_len(), 0);
+    // Reverse index at its top scrolls it down
+    term.feed(b"\x1b[2;1H\x1bM");
+    assert_eq!(screen(&term), ["top", "", "2", "3", "bottom"]);
+}
+
+#[test]
+fn inserting_and_deleting_lines_and_characters() {
+    let mut term = Terminal::new(6, 4, 100);
+    term.feed(b"1\r\n2\r\n3\r\n4");
+    term.feed(b"\x1b[2;1H\x1b[L");
+    assert_eq!(screen(&term), ["1", "", "2", "3"]);
+    term.feed(b"\x1b[2M");
+    assert_eq!(screen(&term), ["1", "3", "", ""]);
+
+    term.feed(b"\x1b[1;1Habcdef\x1b[1;2H\x1b[2P");
+    assert_eq!(screen(&term)[0], "adef");
+    term.feed(b"\x1b[@X");
+    assert_eq!(screen(&term)[0], "aXdef");
+}
+
+#[test]
+fn tab_stops_can_be_set_and_cleared() {
+    let mut term = Terminal::new(20, 2, 100);
+    term.feed(b"\tx");
+    assert_eq!(cursor(&term), (0, 9));
+    // Clear all stops, set one at column 3
+    term.feed(b"\r\x1b[3g\x1b[3G\x1bH\r\ty\tz");
+    assert_eq!(screen(&term)[0], "  y     x          z");
+}
+
+#[test]
+fn modes_switch_on_and_off() {
+    let mut term = Terminal::new(10, 3, 100);
+    assert!(term.mode().contains(Mode::SHOW_CURSOR));
+    term.feed(b"\x1b[?25l\x1b[?1049h");
+    assert!(!term.mode().contains(Mode::SHOW_CURSOR));
+    assert!(term.mode().contains(Mode::ALT_SCREEN));
+    term.feed(b"\x1b[?1049l\x1b[?25h");
+    assert!(term.mode().contains(Mode::SHOW_CURSOR));
+    assert!(!term.mode().contains(Mode::ALT_SCREEN));
+}
+
+#[test]
+fn the_alternate_screen_leaves_the_primary_one_alone() {
+    let mut term = Terminal::new(10, 3, 100);
+    term.feed(b"shell$ ");
+    term.feed(b"\x1b[?1049h\x1b[2J\x1b[Hfull screen");
+    assert_eq!(screen(&term), ["full scree", "n", ""]);
+    term.feed(b"\x1b[?1049l");
+    assert_eq!(screen(&term), ["shell$", "", ""]);
+    assert_eq!(cursor(&term), (0, 7));
+}
+
+#[test]
+fn lines_scrolled_off_the_top_go_to_the_scrollback() {
+    let mut term = Terminal::new(10, 2, 3);
+    term.feed(b"1\r\n2\r\n3\r\n4\r\n5\r\n6");
+    let grid = term.grid();
+    // The scrollback holds the 3 lines it has room for
+    let history: Vec<String> = (0..grid.history_len())
+        .map(|line| grid.line(line).text())
+        .collect();
+    assert_eq!(history, ["2", "3", "4"]);
+    assert_eq!(screen(&term), ["5", "6"]);
+}
+
+#[test]
+fn device_status_reports_answer_through_responses() {
+    let mut term = Terminal::new(10, 3, 100);
+    term.feed(b"\x1b[2;5H\x1b[6n\x1b[5n");
+    assert_eq!(term.take_responses(), b"\x1b[2;5R\x1b[0n");
+    assert!(term.take_responses().is_empty());
+}
+
+#[test]
+fn origin_mode_reports_a_cursor_above_the_region_at_its_top() {
+    let mut term = Terminal::new(10, 10, 100);
+    // DECRC restores a cursor saved above the region set after it
+    term.feed(b"\x1b[?6h\x1b7\x1b[5;8r\x1b8\x1b[6n");
+    assert_eq!(term.take_responses(), b"\x1b[1;1R");
+}
@@ -0,0 +1,121 @@
+//! Terminfo: the shipped entry compiles, is found where ncurses looks, and
+//! answers XTGETTCAP queries.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+use adamant::term::Terminal;
+use adamant::terminfo::{self, Capability, NAME, capability};
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("adamant-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn has_tic() -> bool {
+    Command::new("tic").arg("-V").output().is_ok()
+}
+
+#[test]
+fn source_names_the_entry() {
+    let entry = terminfo::SOURCE
+        .lines()
+        .find(|line| !line.starts_with('#') && !line.trim().is_empty())
+        .unwrap();
+    assert!(entry.starts_with("adamant|"));
+    for capability in ["smcup=", "setrgbf=", "BE=", "kmous=", "Ss="] {
+        assert!(terminfo::SOURCE.contains(capability), "{}", capability);
+    }
+}
+
+#[test]
+fn installed_entry_is_found() {
+    if !has_tic() {
+        eprintln!("tic is not installed, skipping");
+        return;
+    }
+    let dir = scratch_dir("terminfo");
+    terminfo::install_to(&dir).unwrap();
+    let path = terminfo::find_in(std::slice::from_ref(&dir), NAME).unwrap();
+    assert!(path.starts_with(&dir));
+
+    // Compiled without complaints, with the extended capabilities kept
+    let output = Command::new("infocmp")
+        .args(["-x", NAME])
+        .env("TERMINFO", &dir)
+        .output();
+    if let Ok(output) = output {
+        let described = String::from_utf8_lossy(&output.stdout);
+        assert!(described.contains("BE=\\E[?2004h"), "{}", described);
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn other_terms_are_passed_through() {
+    assert_eq!(terminfo::term("xterm-256color"), "xterm-256color");
+    assert_eq!(terminfo::term("vt100"), "vt100");
+}
+
+#[test]
+fn capabilities_are_read_from_the_source() {
+    assert_eq!(capability("pairs"), Some(Capability::Number(0x10000)));
+    assert_eq!(capability("Tc"), Some(Capability::Flag));
+    assert_eq!(capability("kbs"), Some(Capability::String(vec![0x7f])));
+    assert_eq!(
+        capability("Smulx"),
+        Some(Capability::String(b"\x1b[4:%p1%dm".to_vec()))
+    );
+    assert_eq!(
+        capability("TN"),
+        Some(Capability::String(NAME.as_bytes().to_vec()))
+    );
+    // Names, not prefixes or values
+    assert_eq!(capability("adamant"), None);
+    assert_eq!(capability("setrgb"), None);
+}
+
+/// Send an XTGETTCAP request for `names` and return the replies.
+fn query(names: &[&str]) -> String {
+    let hex: Vec<String> = names
+        .iter()
+        .map(|name| name.bytes().map(|b| format!("{:02x}", b)).collect())
+        .collect();
+    let mut term = Terminal::new(80, 24, 0);
+    term.feed(format!("\x1bP+q{}\x1b\\", hex.join(";")).as_bytes());
+    String::from_utf8(term.take_responses()).unwrap()
+}
+
+#[test]
+fn xtgettcap_answers_each_name() {
+    // Names are echoed as sent. "Co" is unknown (termcap name), "colors"
+    // = "256"
+    assert_eq!(
+        query(&["colors", "Co"]),
+        "\x1bP1+r636f6c6f7273=323536\x1b\\\x1bP0+r436f\x1b\\"
+    );
+    // RGB = "8/8/8"; booleans have no value
+    assert_eq!(query(&["RGB"]), "\x1bP1+r524742=382F382F38\x1b\\");
+    assert_eq!(query(&["bce"]), "\x1bP1+r626365\x1b\\");
+    // smcup = "\E[?1049h"
+    assert_eq!(
+        query(&["smcup"]),
+        "\x1bP1+r736d637570=1B5B3F3130343968\x1b\\"
+    );
+}
+
+#[test]
+fn malformed_xtgettcap_requests() {
+    let mut term = Terminal::new(80, 24, 0);
+    // Odd number of hex digits, not hex at all
+    term.feed(b"\x1bP+q636\x1b\\\x1bP+qzz\x1b\\");
+    assert_eq!(
+        String::from_utf8(term.take_responses()).unwrap(),
+        "\x1bP0+r636\x1b\\\x1bP0+rzz\x1b\\"
+    );
+    // Other DCS sequences get no answer and don't disturb the screen
+    term.feed(b"\x1bP$qm\x1b\\ok");
+    assert!(term.take_responses().is_empty());
+    assert_eq!(term.grid().row(0).text(), "ok");
+}
@@ -0,0 +1,56 @@
+//! Titles: renaming the terminal over the program's title, and the window
+//! title template.
+
+use adamant::config::{Config, expand_title};
+use adamant::term::{Event, Terminal};
+
+#[test]
+fn renamed_terminal_keeps_its_name() {
+    let mut term = Terminal::new(80, 24, 100);
+    term.feed(b"\x1b]2;vim\x07");
+    term.rename(Some("build".into()));
+    assert_eq!(term.title(), Some("build"));
+    assert_eq!(
+        term.take_events(),
+        [Event::Title("vim".into()), Event::Title("build".into())]
+    );
+
+    // The program's titles are kept, but not shown
+    term.feed(b"\x1b]0;make\x07");
+    assert!(term.take_events().is_empty());
+    assert_eq!(term.title(), Some("build"));
+    assert_eq!(term.program_title(), Some("make"));
+
+    term.rename(None);
+    assert_eq!(term.title(), Some("make"));
+    assert_eq!(term.take_events(), [Event::Title("make".into())]);
+}
+
+#[test]
+fn renaming_without_a_program_title() {
+    let mut term = Terminal::new(80, 24, 100);
+    term.rename(None);
+    assert!(term.take_events().is_empty());
+    term.rename(Some("logs".into()));
+    term.rename(None);
+    assert_eq!(term.title(), None);
+    assert_eq!(
+        term.take_events(),
+        [Event::Title("logs".into()), Event::Title(String::new())]
+    );
+}
+
+#[test]
+fn title_template_placeholders() {
+    assert_eq!(Config::default().title, "{title}");
+    assert_eq!(expand_title("{title}", "vim", "", ""), "vim");
+    assert_eq!(
+        expand_title("{host}: {cwd} ({title})", "top", "db1", "/var/log"),
+        "db1: /var/log (top)"
+    );
+    // Placeholders in the values stay as they are
+    assert_eq!(
+        expand_title("{title}@{host}", "{host}", "h", ""),
+        "{host}@h"
+    );
+}
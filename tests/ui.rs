@@ -0,0 +1,156 @@
+//! Overlay layers: layout, focus, hit testing and what gets drawn.
+
+#![cfg(feature = "gui")]
+
+use adamant::grid::{Color, Flags};
+use adamant::ui::{Element, Layer, LayerKind, Rect, Style, TOAST_DURATION, Ui};
+use web_time::{Duration, Instant};
+
+/// The text of row `row` between `col` and `col + len` as drawn.
+fn drawn(ui: &Ui, row: usize, col: usize, len: usize) -> String {
+    let cells = ui.cells(24, 80);
+    (col..col + len)
+        .map(|col| cells.get(&(row, col)).map_or('.', |cell| cell.c))
+        .collect()
+}
+
+#[test]
+fn rect_centering_and_containment() {
+    let rect = Rect::centered(4, 10, 24, 80);
+    assert_eq!(rect, Rect::new(10, 35, 4, 10));
+    assert!(rect.contains(10, 35));
+    assert!(rect.contains(13, 44));
+    assert!(!rect.contains(14, 44));
+    assert!(!rect.contains(13, 45));
+    // Too big for the screen
+    assert_eq!(Rect::centered(30, 100, 24, 80), Rect::new(0, 0, 24, 80));
+}
+
+#[test]
+fn dialog_buttons_take_the_focus_in_turn() {
+    let mut layer = Layer::dialog("Quit?", &[("Yes", 1), ("No", 2), ("Later", 3)], 24, 80);
+    assert!(layer.is_modal());
+    assert_eq!(layer.focused().unwrap().id, Some(1));
+    layer.focus_next();
+    layer.focus_next();
+    assert_eq!(layer.focused().unwrap().id, Some(3));
+    layer.focus_next();
+    assert_eq!(layer.focused().unwrap().id, Some(1));
+    layer.focus_prev();
+    assert_eq!(layer.focused().unwrap().id, Some(3));
+    layer.set_focus(2);
+    assert_eq!(layer.focused().unwrap().text, "[ No ]");
+}
+
+#[test]
+fn dialog_is_drawn_centered_with_the_focused_button_inverted() {
+    let mut ui = Ui::default();
+    let id = ui.push(Layer::dialog("Close?", &[("OK", 1)], 24, 80));
+    let layer = ui.layer(id).unwrap();
+    let frame = layer.elements[0].rect;
+    assert_eq!((frame.rows, frame.cols), (5, 10));
+    assert_eq!((frame.row, frame.col), (9, 35));
+
+    assert_eq!(drawn(&ui, 10, 35, 10), "  Close?  ");
+    let button = layer.focused().unwrap().rect;
+    assert_eq!(drawn(&ui, button.row, button.col, 6), "[ OK ]");
+    let cells = ui.cells(24, 80);
+    assert!(
+        cells[&(button.row, button.col)]
+            .flags
+            .contains(Flags::INVERSE)
+    );
+    assert!(!cells[&(10, 37)].flags.contains(Flags::INVERSE));
+    assert_eq!(cells[&(10, 37)].bg, Style::PANEL.bg);
+}
+
+#[test]
+fn hit_testing_finds_the_topmost_element() {
+    let mut ui = Ui::default();
+    let below = ui.push(Layer::new(
+        LayerKind::Dialog,
+        vec![Element::button(Rect::new(0, 0, 5, 20), "", Style::PANEL, 7)],
+    ));
+    let above = ui.push(Layer::menu(&[("Copy", 1), ("Paste", 2)], 2, 5, 24, 80));
+    assert_eq!(ui.hit(3, 6), Some((above, Some(2))));
+    assert_eq!(ui.hit(4, 6), Some((below, Some(7))));
+    assert_eq!(ui.hit(10, 10), None);
+    assert_eq!(ui.modal(), Some(above));
+    ui.remove(above);
+    assert_eq!(ui.modal(), Some(below));
+}
+
+#[test]
+fn menus_stay_on_screen() {
+    let layer = Layer::menu(&[("Open", 1), ("Copy path", 2)], 23, 78, 24, 80);
+    let rects: Vec<Rect> = layer.elements.iter().map(|e| e.rect).collect();
+    assert_eq!(
+        rects,
+        vec![Rect::new(22, 69, 1, 11), Rect::new(23, 69, 1, 11)]
+    );
+}
+
+#[test]
+fn toasts_replace_each_other_and_expire() {
+    let now = Instant::now();
+    let mut ui = Ui::default();
+    ui.toast("first", now, 24, 80);
+    ui.toast("Copied", now, 24, 80);
+    assert_eq!(drawn(&ui, 22, 71, 8), " Copied ");
+    assert_eq!(ui.modal(), None);
+
+    assert!(!ui.expire(now + Duration::from_millis(100)));
+    assert!(ui.expire(now + TOAST_DURATION));
+    assert!(ui.is_empty());
+}
+
+#[test]
+fn text_is_cut_at_the_element_edges() {
+    let mut ui = Ui::default();
+    let style = Style {
+        fg: Color::Indexed(1),
+        ..Style::TOAST
+    };
+    ui.push(Layer::new(
+        LayerKind::Toast,
+        vec![Element::label(
+            Rect::new(0, 0, 2, 5),
+            "日本語です\nab",
+            style,
+        )],
+    ));
+    let cells = ui.cells(24, 80);
+    assert_eq!(drawn(&ui, 0, 0, 6), "日 本  .");
+    assert!(cells[&(0, 1)].flags.contains(Flags::WIDE_SPACER));
+    // The third wide character doesn't fit in the last column
+    assert_eq!(cells[&(0, 4)].c, ' ');
+    assert_eq!(drawn(&ui, 1, 0, 5), "ab   ");
+    assert_eq!(cells[&(1, 0)].fg, Color::Indexed(1));
+}
+
+#[test]
+fn prompt_shows_the_end_of_long_text() {
+    let mut ui = Ui::default();
+    let prompt = ui.push(Layer::prompt("Name:", "short", 24, 80));
+    assert!(ui.layer(prompt).unwrap().is_modal());
+    assert_eq!(drawn(&ui, 10, 20, 5), "Name:");
+    assert_eq!(drawn(&ui, 12, 20, 7), "short\u{258F} ");
+
+    let long = "0123456789".repeat(5);
+    *ui.layer_mut(prompt).unwrap() = Layer::prompt("Name:", &long, 24, 80);
+    // 39 columns of text, then the caret
+    assert_eq!(drawn(&ui, 12, 20, 3), "123");
+    assert_eq!(drawn(&ui, 12, 57, 3), "89\u{258F}");
+}
+
+#[test]
+fn tooltips_go_under_the_link_or_over_it_on_the_last_row() {
+    let mut ui = Ui::default();
+    let tooltip = ui.push(Layer::tooltip("https://example.com/", 3, 70, 24, 80));
+    // Moved left to fit
+    assert_eq!(drawn(&ui, 4, 58, 22), " https://example.com/ ");
+    assert_eq!(ui.modal(), None);
+    ui.remove(tooltip);
+    ui.push(Layer::tooltip("a.io", 23, 0, 24, 80));
+    assert_eq!(drawn(&ui, 22, 0, 6), " a.io ");
+}
@@ -0,0 +1,80 @@
+//! The record of escape sequences received but not implemented, and the
+//! ways to read it.
+
+use adamant::term::{Terminal, escape_bytes};
+
+fn sequences(term: &Terminal) -> Vec<(&str, u64, String)> {
+    term.unhandled()
+        .iter()
+        .map(|entry| {
+            (
+                entry.sequence.as_str(),
+                entry.count,
+                escape_bytes(&entry.sample),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn unhandled_sequences_are_counted_with_a_sample() {
+    let mut term = Terminal::new(20, 4, 0);
+    term.feed(b"\x1b[?2026h\x1b[1;2;73mx\x1b[?2026l\x1b[>4;1m\x1b#3");
+    term.feed(b"\x1b]5522;ping\x07\x1bP$qm\x1b\\\x1b[12h");
+    assert_eq!(
+        sequences(&term),
+        [
+            ("DEC mode 2026", 2, "\\e[?2026h".to_string()),
+            ("SGR 73", 1, "\\e[73m".to_string()),
+            ("CSI > m", 1, "\\e[>4;1m".to_string()),
+            ("ESC # 3", 1, "\\e#3".to_string()),
+            ("OSC 5522", 1, "\\e]5522;ping\\x07".to_string()),
+            ("DCS $ q", 1, "\\eP$q".to_string()),
+            ("ANSI mode 12", 1, "\\e[12h".to_string()),
+        ]
+    );
+    assert_eq!(term.unhandled()[0].line(), "2\tDEC mode 2026\t\\e[?2026h");
+}
+
+#[test]
+fn handled_sequences_and_resets_leave_it_alone() {
+    let mut term = Terminal::new(20, 4, 0);
+    term.feed(b"\x1b[?1049h\x1b[1;31mx\x1b[2J\x1b]0;title\x07\x1b7\x1b8\x1b[?1049l");
+    assert!(term.unhandled().is_empty());
+    term.feed(b"\x1b[?9999h\x1bc");
+    assert_eq!(term.unhandled().len(), 1);
+}
+
+#[test]
+fn samples_are_cut_short() {
+    let mut term = Terminal::new(20, 4, 0);
+    let mut osc = b"\x1b]5522;".to_vec();
+    osc.resize(1000, b'a');
+    osc.push(0x07);
+    term.feed(&osc);
+    assert_eq!(term.unhandled()[0].sample.len(), 64);
+}
+
+#[cfg(unix)]
+#[test]
+fn ipc_asks_for_them() {
+    use adamant::ipc::{IpcCommand, IpcQuery};
+
+    assert_eq!(
+        IpcCommand::parse("get-unhandled"),
+        Ok(IpcCommand::Query(IpcQuery::Unhandled))
+    );
+}
+
+#[cfg(feature = "gui")]
+#[test]
+fn the_palette_shows_them() {
+    use adamant::commands::{Action, commands};
+    use adamant::config::Config;
+
+    assert!(
+        commands(&Config::default())
+            .iter()
+            .any(|command| command.action == Action::ShowUnhandled)
+    );
+}
@@ -0,0 +1,85 @@
+//! Watch rules: which lines are matched and when, highlighting, and what
+//! is reported to the host.
+
+#![cfg(feature = "gui")]
+
+use adamant::config::{WatchAction, WatchRule};
+use adamant::term::Terminal;
+use adamant::watch::{WatchHit, WatchMatcher};
+
+fn rule(name: &'static str, regex: &'static str, action: WatchAction) -> WatchRule {
+    WatchRule {
+        name,
+        regex,
+        action,
+    }
+}
+
+#[test]
+fn bad_rules_are_configuration_errors() {
+    assert!(WatchMatcher::new(&[rule("bad", "(", WatchAction::Highlight)]).is_err());
+    assert!(WatchMatcher::new(&[rule("run", "x", WatchAction::Run(&[]))]).is_err());
+    assert!(WatchMatcher::new(&[rule("sound", "x", WatchAction::Sound(""))]).is_err());
+    assert!(WatchMatcher::new(&[]).unwrap().is_empty());
+}
+
+#[test]
+fn complete_lines_are_highlighted_once() {
+    let matcher = WatchMatcher::new(&[rule("error", r"^error", WatchAction::Highlight)]).unwrap();
+    let mut term = Terminal::new(10, 4, 100);
+    // The wrapped line is matched as a whole, the cursor's line not yet
+    term.feed(b"ok\r\nerror: 0123456789\r\nerror");
+    assert!(matcher.scan(term.grid_mut()).is_empty());
+    let highlighted = |term: &Terminal| {
+        (0..term.grid().total_lines())
+            .filter(|&line| term.grid().line(line).highlighted)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(highlighted(&term), [1, 2]);
+
+    // Scrolled into history, the highlight goes with the line
+    term.feed(b"\r\n\r\n\r\n\r\n");
+    matcher.scan(term.grid_mut());
+    assert_eq!(highlighted(&term), [1, 2, 3]);
+    assert_eq!(term.grid().history_len(), 4);
+
+    // Lines cleared and printed again are matched again
+    term.feed(b"\x1b[2J\x1b[Hfine\r\nerror\r\n");
+    matcher.scan(term.grid_mut());
+    assert_eq!(highlighted(&term), [1, 2, 3, 5]);
+}
+
+#[test]
+fn each_rule_reports_once_per_scan() {
+    let matcher = WatchMatcher::new(&[
+        rule("failed", r"FAILED (\w+)", WatchAction::Notify),
+        rule(
+            "sound",
+            r"FAILED",
+            WatchAction::Sound("/usr/share/sounds/bell.oga"),
+        ),
+        rule(
+            "run",
+            r"FAILED (?P<test>\w+)",
+            WatchAction::Run(&["echo", "${test}"]),
+        ),
+    ])
+    .unwrap();
+    let mut term = Terminal::new(40, 10, 100);
+    term.feed(b"FAILED one\r\npassed\r\nFAILED two\r\n");
+    assert_eq!(
+        matcher.scan(term.grid_mut()),
+        [
+            WatchHit::Notify {
+                rule: "failed",
+                line: "FAILED one".into()
+            },
+            WatchHit::Sound("/usr/share/sounds/bell.oga"),
+            WatchHit::Run(vec!["echo".into(), "one".into()]),
+        ]
+    );
+    assert!(matcher.scan(term.grid_mut()).is_empty());
+
+    term.feed(b"FAILED three\r\n");
+    assert_eq!(matcher.scan(term.grid_mut()).len(), 3);
+}
@@ -0,0 +1,74 @@
+//! Wide grids: lines longer than the view don't wrap, and the view
+//! scrolls sideways within the grid's columns.
+
+use adamant::term::Terminal;
+
+/// A 20-column terminal shown 8 columns at a time.
+fn wide() -> Terminal {
+    let mut term = Terminal::new(20, 3, 100);
+    term.set_view_cols(8);
+    term
+}
+
+#[test]
+fn lines_wider_than_the_view_dont_wrap() {
+    let mut term = wide();
+    term.feed(b"0123456789abcdef");
+    assert_eq!(term.grid().row(0).text(), "0123456789abcdef");
+    assert_eq!(term.grid().row(1).text(), "");
+    assert_eq!(term.grid().cursor.col, 16);
+}
+
+#[test]
+fn the_view_scrolls_within_the_grid() {
+    let mut term = wide();
+    let grid = term.grid_mut();
+    assert_eq!((grid.view_cols(), grid.column_offset()), (8, 0));
+    assert!(!grid.scroll_columns(-3));
+    assert!(grid.scroll_columns(5));
+    assert_eq!(grid.column_offset(), 5);
+    // No further than the last column
+    assert!(grid.scroll_columns(100));
+    assert_eq!(grid.column_offset(), 12);
+    assert!(!grid.scroll_columns(1));
+}
+
+#[test]
+fn showing_a_column_scrolls_just_enough() {
+    let mut term = wide();
+    let grid = term.grid_mut();
+    grid.show_column(5);
+    assert_eq!(grid.column_offset(), 0);
+    grid.show_column(10);
+    assert_eq!(grid.column_offset(), 3);
+    grid.show_column(1);
+    assert_eq!(grid.column_offset(), 1);
+}
+
+#[test]
+fn a_full_view_follows_resizes_and_a_narrow_one_stays() {
+    let mut term = Terminal::new(10, 3, 100);
+    term.resize(14, 3);
+    assert_eq!(term.grid().view_cols(), 14);
+
+    let mut term = wide();
+    term.grid_mut().scroll_columns(12);
+    term.resize(16, 3);
+    assert_eq!(term.grid().view_cols(), 8);
+    assert_eq!(term.grid().column_offset(), 8);
+    // Wider than the grid is the whole grid
+    term.set_view_cols(30);
+    assert_eq!(
+        (term.grid().view_cols(), term.grid().column_offset()),
+        (16, 0)
+    );
+}
+
+#[test]
+fn both_screens_and_a_reset_keep_the_view() {
+    let mut term = wide();
+    term.feed(b"\x1b[?1049h");
+    assert_eq!(term.grid().view_cols(), 8);
+    term.feed(b"\x1bc");
+    assert_eq!(term.grid().view_cols(), 8);
+}
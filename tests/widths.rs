@@ -0,0 +1,67 @@
+//! Width policy for ambiguous characters and text-style emoji.
+//!
+//! Whatever the policy, the cursor position report must describe where
+//! the text actually ended up on the grid.
+
+use adamant::term::{Terminal, WidthOptions};
+
+/// Print `text` and return the reported 1-based (row, column).
+fn report(options: WidthOptions, text: &str) -> (Terminal, String) {
+    let mut term = Terminal::new(10, 3, 0);
+    term.set_width_options(options);
+    term.feed(text.as_bytes());
+    term.feed(b"\x1b[6n");
+    let response = String::from_utf8(term.take_responses()).unwrap();
+    (term, response)
+}
+
+const AMBIGUOUS: WidthOptions = WidthOptions {
+    ambiguous_wide: true,
+    emoji_wide: false,
+};
+
+const EMOJI: WidthOptions = WidthOptions {
+    ambiguous_wide: false,
+    emoji_wide: true,
+};
+
+#[test]
+fn ambiguous_characters_are_narrow_by_default() {
+    let (_, response) = report(WidthOptions::default(), "αβ①");
+    assert_eq!(response, "\x1b[1;4R");
+}
+
+#[test]
+fn ambiguous_characters_can_be_wide() {
+    let (term, response) = report(AMBIGUOUS, "αβ①");
+    assert_eq!(response, "\x1b[1;7R");
+    assert_eq!(term.grid().row(0).text(), "αβ①");
+}
+
+#[test]
+fn line_drawing_follows_the_ambiguous_policy() {
+    let (_, response) = report(AMBIGUOUS, "\x1b(0qq\x1b(B");
+    assert_eq!(response, "\x1b[1;5R");
+}
+
+#[test]
+fn text_style_emoji_can_be_wide() {
+    let (_, narrow) = report(WidthOptions::default(), "☺✔");
+    let (_, wide) = report(EMOJI, "☺✔");
+    assert_eq!(narrow, "\x1b[1;3R");
+    assert_eq!(wide, "\x1b[1;5R");
+}
+
+#[test]
+fn emoji_policy_leaves_digits_and_emoji_presentation_alone() {
+    // Keycap bases stay narrow, 😀 is always wide
+    let (_, response) = report(EMOJI, "1#😀");
+    assert_eq!(response, "\x1b[1;5R");
+}
+
+#[test]
+fn wide_ambiguous_character_wraps_at_the_right_edge() {
+    let (term, response) = report(AMBIGUOUS, "123456789α");
+    assert_eq!(response, "\x1b[2;3R");
+    assert_eq!(term.grid().row(1).text(), "α");
+}